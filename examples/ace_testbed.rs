@@ -0,0 +1,352 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+//! A reference Authorization Server and Resource Server, wired together from this crate's
+//! high-level helpers, which run through a complete ACE-OAuth flow in-process.
+//!
+//! Both servers handle the payloads of CoAP requests and return the CoAP response code along
+//! with the response payload, so that they can be mounted in any CoAP stack. Here, the
+//! "network" consists of plain function calls, which makes the example a self-contained testbed:
+//!
+//! ```text
+//! cargo run --example ace_testbed
+//! ```
+//!
+//! The same flow is run as the `ace_testbed` integration test, which includes this file.
+//!
+//! Tokens are CWTs protected by a `COSE_Mac0` structure using HMAC 256/256 (as provided by
+//! the RustCrypto `hmac` and `sha2` crates), and both servers keep their state in memory.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use coset::cwt::{ClaimsSetBuilder, Timestamp};
+use coset::iana::{Algorithm, KeyType, SymmetricKeyParameter};
+use coset::{CoseKey, CoseKeyBuilder, Header, Label, RegisteredLabel};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use dcaf::common::cose;
+use dcaf::common::scope::{AifRestMethod, ToidMatching};
+use dcaf::constants::coap_code;
+use dcaf::endpoints::message::AceMessage;
+use dcaf::error::CoseCipherError;
+use dcaf::token::format::{CoseMac0Format, TokenFormat};
+use dcaf::token::keys::AudienceKeyRing;
+use dcaf::token::policy::{AudiencePolicy, AuthServerConfig, ClientPolicy, IssuancePolicy};
+use dcaf::token::token_store::{EvictionStrategy, StoredToken, TokenStore};
+use dcaf::token::validation::ValidationPolicy;
+use dcaf::token::{CipherCapabilities, KeyUsage};
+use dcaf::{
+    AccessTokenRequest, AccessTokenResponse, AifEncodedScope, AuthServerRequestCreationHint,
+    CoseCipherCommon, CoseMac0Cipher, ErrorResponse, Scope,
+};
+
+/// The audience (i.e., the Resource Server) of this testbed.
+const AUDIENCE: &str = "tempSensor4711";
+
+/// A CoAP response, consisting of its code and payload.
+type Response = (u8, Vec<u8>);
+
+/// Returns the current time in seconds since the Unix epoch.
+fn now() -> i64 {
+    let elapsed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("system time must be after the Unix epoch");
+    i64::try_from(elapsed.as_secs()).expect("system time must fit into an i64")
+}
+
+/// A cipher computing HMAC 256/256 tags with a symmetric key.
+struct HmacCipher {
+    key: Vec<u8>,
+    key_id: Vec<u8>,
+}
+
+impl HmacCipher {
+    /// Creates a cipher using the symmetric `key`, or returns `None` if it isn't one.
+    fn from_key(key: &CoseKey) -> Option<HmacCipher> {
+        if key.kty != RegisteredLabel::Assigned(KeyType::Symmetric) {
+            return None;
+        }
+        let secret = key.params.iter().find_map(|(label, value)| match label {
+            Label::Int(x) if *x == SymmetricKeyParameter::K as i64 => value.as_bytes().cloned(),
+            _ => None,
+        })?;
+        Some(HmacCipher {
+            key: secret,
+            key_id: key.key_id.clone(),
+        })
+    }
+
+    fn mac(&self) -> Hmac<Sha256> {
+        Hmac::new_from_slice(&self.key).expect("HMAC accepts keys of any length")
+    }
+}
+
+impl CoseCipherCommon for HmacCipher {
+    type Error = String;
+
+    fn header(
+        &self,
+        unprotected_header: &mut Header,
+        protected_header: &mut Header,
+    ) -> Result<(), CoseCipherError<Self::Error>> {
+        if protected_header.alg.is_some() {
+            return Err(CoseCipherError::existing_header("alg"));
+        }
+        if !unprotected_header.key_id.is_empty() {
+            return Err(CoseCipherError::existing_header("kid"));
+        }
+        protected_header.alg = Some(coset::Algorithm::Assigned(Algorithm::HMAC_256_256));
+        unprotected_header.key_id = self.key_id.clone();
+        Ok(())
+    }
+
+    fn capabilities(&self) -> Option<CipherCapabilities> {
+        Some(CipherCapabilities::new(
+            vec![cose::Algorithm::Hmac256_256],
+            vec![KeyType::Symmetric],
+        ))
+    }
+}
+
+impl CoseMac0Cipher for HmacCipher {
+    fn generate_tag(&mut self, target: &[u8]) -> Vec<u8> {
+        let mut mac = self.mac();
+        mac.update(target);
+        mac.finalize().into_bytes().to_vec()
+    }
+
+    fn verify_tag(
+        &mut self,
+        tag: &[u8],
+        maced_data: &[u8],
+    ) -> Result<(), CoseCipherError<Self::Error>> {
+        let mut mac = self.mac();
+        mac.update(maced_data);
+        mac.verify_slice(tag)
+            .map_err(|_| CoseCipherError::VerificationFailure)
+    }
+}
+
+/// Encodes the given `message` into a CoAP response.
+fn respond<T>(message: T) -> Response
+where
+    T: AceMessage,
+{
+    let code = message.coap_code();
+    let payload = message.encode().expect("messages must be serializable");
+    (code, payload)
+}
+
+/// An Authorization Server serving the token endpoint.
+struct AuthServer {
+    config: AuthServerConfig,
+}
+
+impl AuthServer {
+    fn new(config: AuthServerConfig) -> AuthServer {
+        config.validate().expect("configuration must be valid");
+        AuthServer { config }
+    }
+
+    /// Handles a `POST` request to the token endpoint by the (authenticated) client `client_id`.
+    fn token(&self, client_id: &str, payload: &[u8]) -> Response {
+        let policy = &self.config.policy;
+        let request = match AccessTokenRequest::decode(payload) {
+            Ok(request) => request,
+            Err(_) => return (coap_code::BAD_REQUEST, Vec::new()),
+        };
+        let grant = match policy.evaluate(client_id, &request) {
+            Ok(grant) => grant,
+            Err(e) => return respond(policy.error_response(client_id, &request, &e)),
+        };
+        let key = self
+            .config
+            .keys
+            .keys_for(&grant.audience, KeyUsage::Sign)
+            .next()
+            .expect("valid configurations contain a key for each audience");
+        let cipher = HmacCipher::from_key(key).expect("audience keys must be symmetric");
+        let token = CoseMac0Format::new(cipher)
+            .encode(grant.claims(now()).build())
+            .expect("token must be issuable");
+        let response: AccessTokenResponse = grant
+            .response(token)
+            .build()
+            .expect("response must be valid");
+        respond(response)
+    }
+}
+
+/// A Resource Server serving the `authz-info` endpoint and a protected temperature resource.
+struct ResourceServer {
+    format: CoseMac0Format<HmacCipher>,
+    validation: ValidationPolicy,
+    tokens: TokenStore,
+}
+
+impl ResourceServer {
+    fn new(key: &CoseKey) -> ResourceServer {
+        let cipher = HmacCipher::from_key(key).expect("key must be symmetric");
+        ResourceServer {
+            format: CoseMac0Format::new(cipher),
+            validation: ValidationPolicy::default(),
+            tokens: TokenStore::new(16, EvictionStrategy::NearestExpiry)
+                .with_toid_matching(ToidMatching::Exact),
+        }
+    }
+
+    /// Handles a `POST` request to the `authz-info` endpoint by the client `identity`
+    /// (as determined by the secure channel).
+    fn authz_info(&mut self, identity: &str, token: &[u8]) -> Response {
+        let claims = match self.format.decode(token) {
+            Ok(claims) => claims,
+            Err(_) => return (coap_code::UNAUTHORIZED, Vec::new()),
+        };
+        if self.validation.validate_at(&claims, now()).is_err() {
+            return (coap_code::UNAUTHORIZED, Vec::new());
+        }
+        // Tokens meant for other Resource Servers must not be accepted, even if they share a key
+        // (see section 5.10.1.1 of RFC 9200).
+        if claims.audience.as_deref() != Some(AUDIENCE) {
+            return (coap_code::FORBIDDEN, Vec::new());
+        }
+        let expires_at = claims.expiration_time.as_ref().and_then(|x| match x {
+            Timestamp::WholeSeconds(x) => Some(*x),
+            Timestamp::FractionalSeconds(_) => None,
+        });
+        let stored = StoredToken::new(identity, claims, expires_at).with_token(token.to_vec());
+        match self.tokens.insert(stored, &mut ()) {
+            Ok(()) => (coap_code::CREATED, Vec::new()),
+            Err(_) => (coap_code::SERVICE_UNAVAILABLE, Vec::new()),
+        }
+    }
+
+    /// Handles a request with the given `method` to the resource at `path` by the client
+    /// `identity`.
+    ///
+    /// Clients without a valid token are pointed to the Authorization Server, while clients
+    /// whose token doesn't cover the request get a 4.03 (Forbidden) response, as specified in
+    /// [section 5.8.2 of RFC 9200](https://www.rfc-editor.org/rfc/rfc9200#section-5.8.2).
+    fn resource(&mut self, identity: &str, path: &str, method: AifRestMethod) -> Response {
+        self.tokens.remove_expired(now());
+        if self.tokens.is_authorized(identity, path, method) {
+            (coap_code::CONTENT, b"21.5".to_vec())
+        } else if self.tokens.peek(identity).is_some() {
+            (coap_code::FORBIDDEN, Vec::new())
+        } else {
+            let hint = AuthServerRequestCreationHint::builder()
+                .auth_server("coaps://as.example.com/token")
+                .audience(AUDIENCE)
+                .build()
+                .expect("hint must be valid");
+            respond(hint)
+        }
+    }
+}
+
+pub fn main() {
+    let key = CoseKeyBuilder::new_symmetric_key(vec![0xDC; 32])
+        .key_id(vec![0x01])
+        .algorithm(Algorithm::HMAC_256_256)
+        .build();
+
+    let mut audience = AudiencePolicy::new(AUDIENCE, 3600);
+    audience.algorithms = vec![cose::Algorithm::Hmac256_256];
+    let mut client = ClientPolicy::new("client1");
+    client.scope = Some(Scope::from(AifEncodedScope::from(vec![(
+        "/temp",
+        AifRestMethod::Get.into(),
+    )])));
+    let mut keys = AudienceKeyRing::default();
+    keys.insert(AUDIENCE, key.clone())
+        .expect("key ID must be unique");
+    let config = AuthServerConfig {
+        policy: IssuancePolicy {
+            clients: vec![client],
+            audiences: vec![audience],
+            ..IssuancePolicy::default()
+        },
+        keys,
+    };
+    let capabilities = HmacCipher::from_key(&key)
+        .and_then(|x| x.capabilities())
+        .expect("cipher must declare its capabilities");
+    config
+        .check_capabilities(&capabilities)
+        .expect("cipher must support the configuration");
+    let auth_server = AuthServer::new(config);
+    let mut resource_server = ResourceServer::new(&key);
+
+    // Without a token, the client is pointed to the Authorization Server.
+    let (code, payload) = resource_server.resource("client1", "/temp", AifRestMethod::Get);
+    assert_eq!(code, coap_code::UNAUTHORIZED);
+    let hint = AuthServerRequestCreationHint::decode(&payload).expect("hint must be valid");
+    println!("RS: 4.01 Unauthorized, AS is {:?}", hint.auth_server);
+
+    // The client requests a token for the audience given in the hint.
+    let request = AccessTokenRequest::builder()
+        .audience(hint.audience.expect("hint must contain the audience"))
+        .scope(Scope::from(AifEncodedScope::from(vec![(
+            "/temp",
+            AifRestMethod::Get.into(),
+        )])))
+        .build()
+        .expect("request must be valid");
+    let (code, payload) = auth_server.token("client1", &request.encode().expect("encodable"));
+    assert_eq!(code, coap_code::CREATED);
+    let response = AccessTokenResponse::decode(&payload).expect("response must be valid");
+    println!(
+        "AS: 2.01 Created, token of {} bytes valid for {:?} seconds",
+        response.access_token.len(),
+        response.expires_in
+    );
+
+    // Unknown clients get an error response instead.
+    let (code, payload) = auth_server.token(
+        "mallory",
+        &AccessTokenRequest::default().encode().expect("encodable"),
+    );
+    let error = ErrorResponse::decode(&payload).expect("error response must be valid");
+    println!(
+        "AS: {code:#04x} for unknown client, error {:?}",
+        error.error
+    );
+
+    // The client posts the token to the Resource Server and accesses the resource.
+    let (code, _) = resource_server.authz_info("client1", &response.access_token);
+    assert_eq!(code, coap_code::CREATED);
+    println!("RS: 2.01 Created, token stored");
+    let (code, payload) = resource_server.resource("client1", "/temp", AifRestMethod::Get);
+    assert_eq!(code, coap_code::CONTENT);
+    println!(
+        "RS: 2.05 Content, temperature is {}",
+        String::from_utf8_lossy(&payload)
+    );
+
+    // The token doesn't allow changing the temperature.
+    let (code, _) = resource_server.resource("client1", "/temp", AifRestMethod::Put);
+    assert_eq!(code, coap_code::FORBIDDEN);
+    println!("RS: 4.03 Forbidden for PUT");
+
+    // A token for another Resource Server is rejected, even though it's protected by the same key.
+    let foreign = CoseMac0Format::new(HmacCipher::from_key(&key).expect("key must be symmetric"))
+        .encode(
+            ClaimsSetBuilder::new()
+                .audience("tempSensor0815".to_string())
+                .expiration_time(Timestamp::WholeSeconds(now() + 3600))
+                .build(),
+        )
+        .expect("token must be issuable");
+    let (code, _) = resource_server.authz_info("client2", &foreign);
+    assert_eq!(code, coap_code::FORBIDDEN);
+    println!("RS: 4.03 Forbidden for token of another audience");
+}
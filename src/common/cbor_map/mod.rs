@@ -56,16 +56,25 @@ use ciborium::ser::into_writer;
 use ciborium::value::{Integer, Value};
 use ciborium_io::{Read, Write};
 use erased_serde::Serialize as ErasedSerialize;
+use serde::de::{Error as DeError, Unexpected};
 
 #[cfg(not(feature = "std"))]
 use {alloc::boxed::Box, alloc::format, alloc::vec::Vec, core::any::type_name};
 
+use crate::common::cbor_values::ProofOfPossessionKey;
+use crate::common::confirmation::decode_confirmation;
 use crate::common::scope::Scope;
-use crate::error::{TryFromCborMapError, ValueIsNotIntegerError};
+use crate::error::{DuplicateMapKeyError, TryFromCborMapError, ValueIsNotIntegerError};
+
+#[cfg(test)]
+mod tests;
 
 /// Creates a CBOR map from integer keys to values, where the given values must have a `map`
 /// method available (e.g. [`Option`]).
 ///
+/// All keys must have the same type, so that keys of different
+/// [`CborKey`](crate::constants::cbor_abbreviations::CborKey) registries can't be mixed.
+///
 /// The macro has been adapted from
 /// [a macro in ciborium's tests](https://github.com/enarx/ciborium/blob/main/ciborium/tests/macro.rs#L13)
 ///
@@ -85,10 +94,12 @@ use crate::error::{TryFromCborMapError, ValueIsNotIntegerError};
 /// }
 /// ```
 macro_rules! cbor_map_vec {
-    ($($key:expr => $val:expr),* $(,)*) => {
+    ($($key:expr => $val:expr),* $(,)*) => {{
+         // Fails to compile if keys of different registries are mixed.
+         let _ = [$($key),*];
          vec![$(
              (
-                 $key as i128,
+                 i128::from($key),
                  $val.map(|x| {
                          // `Box::<dyn ErasedSerialize>` would not work, see
                          // here for an explanation: https://stackoverflow.com/a/63550684
@@ -97,7 +108,7 @@ macro_rules! cbor_map_vec {
                      })
              )
          ),*]
-     };
+     }};
      }
 
 #[rustfmt::skip]
@@ -165,6 +176,45 @@ pub trait ToCborMap: private::Sealed {
         into_writer(&CborMap(self), writer)
     }
 
+    /// Serializes this type as a CBOR map bytestring into the given `writer` like
+    /// [`serialize_into`](ToCborMap::serialize_into), but emits the map keys in the given `order`.
+    ///
+    /// Receivers are supposed to accept map entries in any order, but some deployed parsers
+    /// don't, so this can be used to match whatever order they expect.
+    ///
+    /// # Example
+    /// ```
+    /// # use ciborium_io::Write;
+    /// # use dcaf::AuthServerRequestCreationHint;
+    /// # use dcaf::common::cbor_map::{MapKeyOrder, ToCborMap};
+    /// # #[cfg(feature = "std")] {
+    /// let hint = AuthServerRequestCreationHint::builder()
+    ///     .client_nonce(vec![0xDC])
+    ///     .audience("a")
+    ///     .build()?;
+    /// let mut serialized: Vec<u8> = Vec::new();
+    /// hint.serialize_into_ordered(&mut serialized, MapKeyOrder::Ascending)?;
+    /// assert_eq!(serialized, vec![0xA2, 0x05, 0x61, 0x61, 0x18, 0x27, 0x41, 0xDC]);
+    /// # }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    ///
+    /// # Errors
+    /// - When serialization of this value failed, e.g. due to malformed input.
+    /// - When the output couldn't be put inside the given `writer`.
+    fn serialize_into_ordered<W>(
+        self,
+        writer: W,
+        order: MapKeyOrder,
+    ) -> Result<(), ciborium::ser::Error<W::Error>>
+    where
+        Self: Sized,
+        W: Write,
+        W::Error: Debug,
+    {
+        into_writer(&self.to_ciborium_value_ordered(order), writer)
+    }
+
     /// Deserializes from the given `reader` --- which is expected to be an instance of this type,
     /// represented as a CBOR map bytestring --- into an instance of this type.
     ///
@@ -195,6 +245,64 @@ pub trait ToCborMap: private::Sealed {
         from_reader(reader).map(|x: CborMap<Self>| x.0)
     }
 
+    /// Deserializes from the given `reader` like [`deserialize_from`](ToCborMap::deserialize_from),
+    /// but handles keys which appear more than once in the CBOR map as specified by `duplicates`.
+    ///
+    /// [`deserialize_from`](ToCborMap::deserialize_from) itself behaves like
+    /// [`DuplicateKeyHandling::Overwrite`], i.e., the last value of a duplicate key wins.
+    /// The only exception is [`ProofOfPossessionKey`](crate::common::cbor_values::ProofOfPossessionKey),
+    /// whose map has to contain exactly one entry, so duplicate keys are always rejected there.
+    ///
+    /// # Example
+    /// Here, the `client_id` is contained twice, which we reject:
+    /// ```
+    /// # use ciborium_io::Read;
+    /// # use dcaf::{AccessTokenRequest, ToCborMap};
+    /// # use dcaf::common::cbor_map::DuplicateKeyHandling;
+    /// let serialized = vec![0xA2, 0x18, 0x18, 0x61, 0x61, 0x18, 0x18, 0x61, 0x62];
+    /// let first = AccessTokenRequest::deserialize_from_with(
+    ///     serialized.as_slice(), DuplicateKeyHandling::FirstWins
+    /// )?;
+    /// assert_eq!(first.client_id, Some("a".to_string()));
+    /// let last = AccessTokenRequest::deserialize_from(serialized.as_slice())?;
+    /// assert_eq!(last.client_id, Some("b".to_string()));
+    /// assert!(AccessTokenRequest::deserialize_from_with(
+    ///     serialized.as_slice(), DuplicateKeyHandling::Reject
+    /// ).is_err());
+    /// # Ok::<(), ciborium::de::Error<<&[u8] as Read>::Error>>(())
+    /// ```
+    ///
+    /// # Errors
+    /// - When deserialization of the bytestring failed, e.g. when the given `reader` does not
+    ///   contain a valid CBOR map or deserializes to a different type than this one.
+    /// - When the CBOR map contains a duplicate key and `duplicates` is
+    ///   [`DuplicateKeyHandling::Reject`].
+    /// - When the input couldn't be read from the given `reader`.
+    fn deserialize_from_with<R>(
+        reader: R,
+        duplicates: DuplicateKeyHandling,
+    ) -> Result<Self, ciborium::de::Error<R::Error>>
+    where
+        Self: Sized,
+        R: Read,
+        R::Error: Debug,
+    {
+        match from_reader(reader)? {
+            Value::Map(map) => {
+                let map = Self::cbor_map_from_int(map)
+                    .map_err(ciborium::de::Error::<R::Error>::custom)?;
+                let map = duplicates
+                    .apply(map)
+                    .map_err(ciborium::de::Error::<R::Error>::custom)?;
+                Self::try_from_cbor_map(map).map_err(ciborium::de::Error::<R::Error>::custom)
+            }
+            _ => Err(ciborium::de::Error::<R::Error>::invalid_type(
+                Unexpected::Other("unknown type"),
+                &"a CBOR map",
+            )),
+        }
+    }
+
     /// Converts this type into a CBOR map from integer keys to serializable values
     /// (which may be empty).
     ///
@@ -238,20 +346,70 @@ pub trait ToCborMap: private::Sealed {
     ///     .build();
     /// ```
     fn to_ciborium_value(&self) -> Value {
+        self.to_ciborium_value_ordered(MapKeyOrder::Registration)
+    }
+
+    /// Converts this type to a CBOR serializable [`Value`] like
+    /// [`to_ciborium_value`](ToCborMap::to_ciborium_value), with the map keys in the given `order`.
+    ///
+    /// # Panics
+    /// Under the same circumstances as [`to_ciborium_value`](ToCborMap::to_ciborium_value).
+    fn to_ciborium_value_ordered(&self, order: MapKeyOrder) -> Value {
+        let mut map: Vec<(i128, Option<Box<dyn ErasedSerialize + '_>>)> = self
+            .to_cbor_map()
+            .into_iter()
+            .filter(|x| x.1.is_some())
+            .collect();
+        order.sort(&mut map);
         Value::Map(
-            self.to_cbor_map()
-                .into_iter()
-                .filter(|x| x.1.is_some())
+            map.into_iter()
                 .map(|x| {
                     (
                         Value::Integer(x.0.try_into().expect("CBOR key value too high")),
-                        Value::serialized(&x.1).expect("Invalid CBOR map value"),
+                        order
+                            .apply_nested(Value::serialized(&x.1).expect("Invalid CBOR map value")),
                     )
                 })
                 .collect(),
         )
     }
 
+    /// Tries to create an instance of this type from the given [`Value`], which is expected to be
+    /// a CBOR map as created by [`to_ciborium_value`](ToCborMap::to_ciborium_value).
+    ///
+    /// Together with [`to_ciborium_value`](ToCborMap::to_ciborium_value), this is useful when
+    /// the type is embedded inside a larger CBOR structure (e.g., inside a claim of an access
+    /// token or inside another protocol's message), since it avoids serializing to
+    /// a bytestring and back.
+    ///
+    /// # Errors
+    /// - When the given `value` is not a CBOR map with integer keys.
+    /// - When the CBOR map can't be converted to this type.
+    ///
+    /// # Example
+    /// ```
+    /// # use ciborium::value::Value;
+    /// # use dcaf::ToCborMap;
+    /// # use dcaf::common::cbor_values::ProofOfPossessionKey;
+    /// # use dcaf::error::TryFromCborMapError;
+    /// let key = ProofOfPossessionKey::KeyId(vec![0xDC, 0xAF]);
+    /// let value = key.to_ciborium_value();
+    /// assert_eq!(ProofOfPossessionKey::from_ciborium_value(value)?, key);
+    /// assert!(ProofOfPossessionKey::from_ciborium_value(Value::Bool(true)).is_err());
+    /// # Ok::<(), TryFromCborMapError>(())
+    /// ```
+    fn from_ciborium_value(value: Value) -> Result<Self, TryFromCborMapError>
+    where
+        Self: Sized,
+    {
+        match value {
+            Value::Map(map) => Self::try_from_cbor_map(decode_int_map::<Self>(map, "value")?),
+            _ => Err(TryFromCborMapError::from_message(
+                "value must be a CBOR map",
+            )),
+        }
+    }
+
     /// Converts the given vector representing
     /// "a CBOR map from serializable keys to serializable values" (`Vec<(Value, Value)>`)
     /// into a similar vector which represents
@@ -280,6 +438,304 @@ pub trait ToCborMap: private::Sealed {
     }
 }
 
+/// Determines how keys which appear more than once in a CBOR map are handled during
+/// deserialization using [`ToCborMap::deserialize_from_with`].
+///
+/// [RFC 8949, section 5.6](https://www.rfc-editor.org/rfc/rfc8949#section-5.6) leaves the
+/// handling of such maps to the application, and different implementations behave differently,
+/// so the desired behavior should be chosen (and documented) explicitly.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum DuplicateKeyHandling {
+    /// The value of the last occurrence of a key is used.
+    Overwrite,
+
+    /// The value of the first occurrence of a key is used.
+    FirstWins,
+
+    /// Maps containing duplicate keys are rejected with a
+    /// [`DuplicateMapKeyError`].
+    Reject,
+}
+
+impl DuplicateKeyHandling {
+    /// Removes duplicate keys from the given `map` according to this strategy,
+    /// keeping the order of the remaining entries.
+    ///
+    /// # Errors
+    /// - If `map` contains a duplicate key and this is [`DuplicateKeyHandling::Reject`].
+    fn apply(self, map: Vec<(i128, Value)>) -> Result<Vec<(i128, Value)>, DuplicateMapKeyError> {
+        let mut result: Vec<(i128, Value)> = Vec::with_capacity(map.len());
+        for (key, value) in map {
+            match (result.iter().position(|x| x.0 == key), self) {
+                (None, _) => result.push((key, value)),
+                (Some(_), DuplicateKeyHandling::Reject) => {
+                    return Err(DuplicateMapKeyError { key })
+                }
+                (Some(_), DuplicateKeyHandling::FirstWins) => {}
+                (Some(index), DuplicateKeyHandling::Overwrite) => {
+                    result.remove(index);
+                    result.push((key, value));
+                }
+            }
+        }
+        Ok(result)
+    }
+}
+
+impl Default for DuplicateKeyHandling {
+    fn default() -> Self {
+        DuplicateKeyHandling::Overwrite
+    }
+}
+
+/// Determines the order in which the keys of a CBOR map are emitted by
+/// [`ToCborMap::serialize_into_ordered`] and [`ToCborMap::to_ciborium_value_ordered`].
+///
+/// Map entries may appear in any order according to
+/// [RFC 8949](https://www.rfc-editor.org/rfc/rfc8949#section-3.1), but some (non-conforming)
+/// parsers rely on a specific one, so interoperability may require matching it.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum MapKeyOrder {
+    /// Keys are emitted in the order in which the fields of the message are defined,
+    /// as done by [`ToCborMap::serialize_into`].
+    Registration,
+
+    /// Keys are emitted in ascending numerical order, so negative keys come first.
+    ///
+    /// Only the keys of the message itself are sorted, while maps nested inside it (e.g., the
+    /// COSE key of a `cnf` parameter) keep their order.
+    Ascending,
+
+    /// Keys are emitted in the bytewise lexicographic order of their encodings, as required for
+    /// deterministic encoding by
+    /// [RFC 8949, section 4.2.1](https://www.rfc-editor.org/rfc/rfc8949#section-4.2.1).
+    ///
+    /// For integer keys, this means that all non-negative keys come first in ascending order,
+    /// followed by all negative keys in descending order (e.g., `0, 1, 24, -1, -2, -25`).
+    ///
+    /// This applies recursively to all maps nested inside the message (e.g., the COSE key of a
+    /// `cnf` parameter), but not to maps which are encoded inside byte strings (e.g., the
+    /// protected header of a COSE structure), since changing those would break their signatures.
+    Canonical,
+}
+
+impl MapKeyOrder {
+    /// Sorts the given `map` by its keys according to this order.
+    ///
+    /// Since the sort is stable, entries with the same key keep their relative order.
+    fn sort<T>(self, map: &mut [(i128, T)]) {
+        match self {
+            MapKeyOrder::Registration => {}
+            MapKeyOrder::Ascending => map.sort_by_key(|x| x.0),
+            // Negative integers use major type 1 and encode their absolute value minus one,
+            // so they come after all non-negative ones and sort by descending value.
+            MapKeyOrder::Canonical => map.sort_by_key(|x| (x.0 < 0, x.0.abs())),
+        }
+    }
+
+    /// Applies this order to the maps nested inside the given map `value`.
+    fn apply_nested(self, value: Value) -> Value {
+        match self {
+            MapKeyOrder::Registration | MapKeyOrder::Ascending => value,
+            MapKeyOrder::Canonical => canonicalize(value),
+        }
+    }
+}
+
+/// Sorts the keys of all maps in the given `value` (including nested ones) by the bytewise
+/// lexicographic order of their encodings, as done for [`MapKeyOrder::Canonical`].
+fn canonicalize(value: Value) -> Value {
+    match value {
+        Value::Map(entries) => {
+            let mut entries: Vec<(Vec<u8>, (Value, Value))> = entries
+                .into_iter()
+                .map(|(key, value)| {
+                    let key = canonicalize(key);
+                    let mut encoded = Vec::new();
+                    into_writer(&key, &mut encoded).expect("Invalid CBOR map key");
+                    (encoded, (key, canonicalize(value)))
+                })
+                .collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            Value::Map(entries.into_iter().map(|x| x.1).collect())
+        }
+        Value::Array(values) => Value::Array(values.into_iter().map(canonicalize).collect()),
+        Value::Tag(tag, value) => Value::Tag(tag, Box::new(canonicalize(*value))),
+        value => value,
+    }
+}
+
+impl Default for MapKeyOrder {
+    fn default() -> Self {
+        MapKeyOrder::Registration
+    }
+}
+
+/// A message of this crate whose fields are embedded into a single CBOR map together with
+/// additional parameters, as done by profiles which extend an endpoint's payload
+/// (e.g., an [`AccessTokenRequest`](crate::AccessTokenRequest) carrying experimental parameters).
+///
+/// This works similar to `#[serde(flatten)]`: [`merge`](ExtendedMessage::merge) combines the
+/// fields of the `message` and the `extra` parameters into one map, while
+/// [`split`](ExtendedMessage::split) separates such a map into its two parts again.
+/// Since the message itself rejects unknown keys, the keys of the extra parameters have to be
+/// given when splitting.
+///
+/// # Example
+/// ```
+/// # use ciborium::value::Value;
+/// # use dcaf::AccessTokenRequest;
+/// # use dcaf::common::cbor_map::ExtendedMessage;
+/// // Our (hypothetical) profile adds a "nonce counter" parameter with the key -70000.
+/// const NONCE_COUNTER: i64 = -70000;
+/// # #[cfg(feature = "std")] {
+/// let request = AccessTokenRequest::builder().client_id("test").build()?;
+/// let extended = ExtendedMessage::new(request).with_parameter(NONCE_COUNTER, Value::from(3));
+/// let mut serialized = Vec::new();
+/// extended.serialize_into(&mut serialized)?;
+///
+/// let received: ExtendedMessage<AccessTokenRequest> =
+///     ExtendedMessage::deserialize_from(serialized.as_slice(), &[NONCE_COUNTER])?;
+/// assert_eq!(received.message.client_id, Some("test".to_string()));
+/// assert_eq!(received.parameter(NONCE_COUNTER), Some(&Value::from(3)));
+/// # }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, PartialEq, Clone)]
+pub struct ExtendedMessage<T>
+where
+    T: ToCborMap,
+{
+    /// The message whose fields are embedded.
+    pub message: T,
+
+    /// The additional parameters, as a CBOR map from integer keys to values.
+    pub extra: Vec<(i64, Value)>,
+}
+
+impl<T> ExtendedMessage<T>
+where
+    T: ToCborMap,
+{
+    /// Creates a new extended message embedding the given `message` without any
+    /// additional parameters.
+    #[must_use]
+    pub fn new(message: T) -> ExtendedMessage<T> {
+        ExtendedMessage {
+            message,
+            extra: Vec::new(),
+        }
+    }
+
+    /// Adds the additional parameter with the given `key` and `value`.
+    #[must_use]
+    pub fn with_parameter(mut self, key: i64, value: Value) -> ExtendedMessage<T> {
+        self.extra.push((key, value));
+        self
+    }
+
+    /// Returns the value of the additional parameter with the given `key`, if present.
+    #[must_use]
+    pub fn parameter(&self, key: i64) -> Option<&Value> {
+        self.extra.iter().find(|x| x.0 == key).map(|x| &x.1)
+    }
+
+    /// Merges the fields of the message and the additional parameters into a single CBOR map.
+    ///
+    /// # Errors
+    /// - If an additional parameter uses a key which is already used by a field of the message
+    ///   or by another additional parameter.
+    pub fn merge(&self) -> Result<Value, DuplicateMapKeyError> {
+        let mut map = match self.message.to_ciborium_value() {
+            Value::Map(map) => map,
+            _ => unreachable!("ToCborMap types always convert to a CBOR map"),
+        };
+        for (key, value) in &self.extra {
+            let key_value = Value::Integer(Integer::from(*key));
+            if map.iter().any(|x| x.0 == key_value) {
+                return Err(DuplicateMapKeyError {
+                    key: i128::from(*key),
+                });
+            }
+            map.push((key_value, value.clone()));
+        }
+        Ok(Value::Map(map))
+    }
+
+    /// Splits the given CBOR map into the message and its additional parameters,
+    /// where entries whose keys are contained in `extra_keys` are taken as additional parameters
+    /// and all other entries are taken as fields of the message.
+    ///
+    /// # Errors
+    /// - If `value` is not a CBOR map with integer keys.
+    /// - If the remaining entries can't be converted to the message, e.g., because they contain
+    ///   keys which are neither known to the message nor contained in `extra_keys`.
+    pub fn split(
+        value: Value,
+        extra_keys: &[i64],
+    ) -> Result<ExtendedMessage<T>, TryFromCborMapError> {
+        let map = match value {
+            Value::Map(map) => decode_int_map::<T>(map, "value")?,
+            _ => {
+                return Err(TryFromCborMapError::from_message(
+                    "value must be a CBOR map",
+                ))
+            }
+        };
+        let mut fields = Vec::with_capacity(map.len());
+        let mut extra = Vec::new();
+        for (key, value) in map {
+            match i64::try_from(key) {
+                Ok(key) if extra_keys.contains(&key) => extra.push((key, value)),
+                _ => fields.push((key, value)),
+            }
+        }
+        Ok(ExtendedMessage {
+            message: T::try_from_cbor_map(fields)?,
+            extra,
+        })
+    }
+
+    /// Serializes the [merged](ExtendedMessage::merge) CBOR map as a bytestring into the
+    /// given `writer`.
+    ///
+    /// # Errors
+    /// - If an additional parameter uses a key which is already in use.
+    /// - When serialization of this value failed, e.g. due to malformed input.
+    /// - When the output couldn't be put inside the given `writer`.
+    pub fn serialize_into<W>(&self, writer: W) -> Result<(), ciborium::ser::Error<W::Error>>
+    where
+        W: Write,
+        W::Error: Debug,
+    {
+        let value = self
+            .merge()
+            .map_err(|e| ciborium::ser::Error::Value(format!("{e}")))?;
+        into_writer(&value, writer)
+    }
+
+    /// Deserializes the CBOR map bytestring contained in the given `reader` and
+    /// [splits](ExtendedMessage::split) it into the message and the additional parameters whose
+    /// keys are contained in `extra_keys`.
+    ///
+    /// # Errors
+    /// - When deserialization of the bytestring failed, e.g. when the given `reader` does not
+    ///   contain a valid CBOR map.
+    /// - If the map can't be split, as described for [`split`](ExtendedMessage::split).
+    /// - When the input couldn't be read from the given `reader`.
+    pub fn deserialize_from<R>(
+        reader: R,
+        extra_keys: &[i64],
+    ) -> Result<ExtendedMessage<T>, ciborium::de::Error<R::Error>>
+    where
+        R: Read,
+        R::Error: Debug,
+    {
+        let value: Value = from_reader(reader)?;
+        ExtendedMessage::split(value, extra_keys).map_err(ciborium::de::Error::<R::Error>::custom)
+    }
+}
+
 /// Decodes the given specific `scope` into the general [`Scope`] type.
 ///
 /// # Errors
@@ -289,6 +745,19 @@ pub(crate) fn decode_scope(scope: Value) -> Result<Scope, TryFromCborMapError> {
         .map_err(|e| TryFromCborMapError::from_message(format!("couldn't decode scope: {e}")))
 }
 
+/// Decodes the given confirmation structure `cnf` of the field called `name` into a
+/// [`ProofOfPossessionKey`].
+///
+/// # Errors
+/// - If `cnf` is not a valid confirmation structure.
+pub(crate) fn decode_cnf(
+    cnf: Value,
+    name: &str,
+) -> Result<ProofOfPossessionKey, TryFromCborMapError> {
+    decode_confirmation(cnf)
+        .map_err(|e| TryFromCborMapError::from_message(format!("couldn't decode {name}: {e}")))
+}
+
 /// Decodes the given `number` Integer into a more specific integer of type `T`.
 ///
 /// # Errors
@@ -346,7 +815,15 @@ where
 /// Contains definitions according to C-SEALED, which turns [`ToCborMap`] into a sealed trait.
 mod private {
     use crate::common::cbor_values::ProofOfPossessionKey;
+    #[cfg(feature = "oscore-profile")]
+    use crate::endpoints::authz_info::{
+        OscoreAuthzInfoRequest, OscoreAuthzInfoResponse, OscoreInputMaterial,
+    };
     use crate::endpoints::creation_hint::AuthServerRequestCreationHint;
+    #[cfg(feature = "introspection")]
+    use crate::endpoints::introspection::{IntrospectionRequest, IntrospectionResponse};
+    use crate::endpoints::metadata::AuthServerMetadata;
+    use crate::endpoints::statistics::AuthServerStatistics;
     use crate::endpoints::token_req::{AccessTokenRequest, AccessTokenResponse, ErrorResponse};
 
     /// Sealed trait according to C-SEALED.
@@ -361,6 +838,25 @@ mod private {
     impl Sealed for ErrorResponse {}
 
     impl Sealed for ProofOfPossessionKey {}
+
+    #[cfg(feature = "oscore-profile")]
+    impl Sealed for OscoreAuthzInfoRequest {}
+
+    #[cfg(feature = "oscore-profile")]
+    impl Sealed for OscoreAuthzInfoResponse {}
+
+    #[cfg(feature = "oscore-profile")]
+    impl Sealed for OscoreInputMaterial {}
+
+    impl Sealed for AuthServerMetadata {}
+
+    #[cfg(feature = "introspection")]
+    impl Sealed for IntrospectionRequest {}
+
+    #[cfg(feature = "introspection")]
+    impl Sealed for IntrospectionResponse {}
+
+    impl Sealed for AuthServerStatistics {}
 }
 
 /// Contains methods to convert `CborMap` structs (so actually, types implementing `ToCborMap`)
@@ -0,0 +1,195 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+#[cfg(not(feature = "std"))]
+use {alloc::string::String, alloc::string::ToString, alloc::vec};
+
+use coset::CoseKeyBuilder;
+
+use crate::common::cbor_values::ProofOfPossessionKey;
+use crate::{AccessTokenResponse, AuthServerRequestCreationHint};
+
+use super::*;
+
+/// A creation hint containing the audience "a", the client nonce 0xDC, and the audience "b".
+const DUPLICATE_AUDIENCE: [u8; 11] = [
+    0xA3, 0x05, 0x61, 0x61, 0x18, 0x27, 0x41, 0xDC, 0x05, 0x61, 0x62,
+];
+
+fn decode(handling: DuplicateKeyHandling) -> Result<AuthServerRequestCreationHint, String> {
+    AuthServerRequestCreationHint::deserialize_from_with(DUPLICATE_AUDIENCE.as_slice(), handling)
+        .map_err(|x| x.to_string())
+}
+
+#[test]
+fn test_duplicate_keys() -> Result<(), String> {
+    let overwritten = decode(DuplicateKeyHandling::Overwrite)?;
+    assert_eq!(overwritten.audience, Some("b".to_string()));
+    assert_eq!(overwritten.client_nonce, Some(vec![0xDC]));
+    let first = decode(DuplicateKeyHandling::FirstWins)?;
+    assert_eq!(first.audience, Some("a".to_string()));
+    assert_eq!(first.client_nonce, Some(vec![0xDC]));
+    assert!(decode(DuplicateKeyHandling::Reject).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_duplicate_key_handling_apply() {
+    let map = vec![
+        (1, Value::from(1)),
+        (2, Value::from(2)),
+        (1, Value::from(3)),
+    ];
+    assert_eq!(
+        DuplicateKeyHandling::Overwrite.apply(map.clone()),
+        Ok(vec![(2, Value::from(2)), (1, Value::from(3))])
+    );
+    assert_eq!(
+        DuplicateKeyHandling::FirstWins.apply(map.clone()),
+        Ok(vec![(1, Value::from(1)), (2, Value::from(2))])
+    );
+    assert_eq!(
+        DuplicateKeyHandling::Reject.apply(map),
+        Err(DuplicateMapKeyError { key: 1 })
+    );
+    assert_eq!(
+        DuplicateKeyHandling::default(),
+        DuplicateKeyHandling::Overwrite
+    );
+}
+
+#[test]
+fn test_map_key_order() {
+    let keys = |order: MapKeyOrder| {
+        let mut map = vec![(1, ()), (-1, ()), (24, ()), (0, ()), (-25, ()), (-2, ())];
+        order.sort(&mut map);
+        map.into_iter().map(|x| x.0).collect::<Vec<i128>>()
+    };
+    assert_eq!(keys(MapKeyOrder::Registration), vec![1, -1, 24, 0, -25, -2]);
+    assert_eq!(keys(MapKeyOrder::Ascending), vec![-25, -2, -1, 0, 1, 24]);
+    assert_eq!(keys(MapKeyOrder::Canonical), vec![0, 1, 24, -1, -2, -25]);
+}
+
+#[test]
+fn test_serialize_into_ordered() -> Result<(), String> {
+    let hint = AuthServerRequestCreationHint::builder()
+        .client_nonce(vec![0xDC])
+        .audience("a")
+        .build()
+        .map_err(|x| x.to_string())?;
+    let mut registration = Vec::new();
+    hint.clone()
+        .serialize_into_ordered(&mut registration, MapKeyOrder::Registration)
+        .map_err(|x| x.to_string())?;
+    let mut default = Vec::new();
+    hint.clone()
+        .serialize_into(&mut default)
+        .map_err(|x| x.to_string())?;
+    assert_eq!(registration, default);
+    let mut canonical = Vec::new();
+    hint.clone()
+        .serialize_into_ordered(&mut canonical, MapKeyOrder::Canonical)
+        .map_err(|x| x.to_string())?;
+    assert_eq!(
+        canonical,
+        vec![0xA2, 0x05, 0x61, 0x61, 0x18, 0x27, 0x41, 0xDC]
+    );
+    assert_eq!(
+        AuthServerRequestCreationHint::deserialize_from(canonical.as_slice())
+            .map_err(|x| x.to_string())?,
+        hint
+    );
+    Ok(())
+}
+
+#[test]
+fn test_serialize_into_ordered_nested() -> Result<(), String> {
+    // The parameters of this key are defined in non-canonical order (x before crv).
+    let key = CoseKeyBuilder::new_okp_key()
+        .param(-2, Value::Bytes(vec![0x01]))
+        .param(-1, Value::from(6))
+        .build();
+    let response = AccessTokenResponse::builder()
+        .access_token(vec![0xDC, 0xAF])
+        .cnf(ProofOfPossessionKey::PlainCoseKey(key))
+        .build()
+        .map_err(|x| x.to_string())?;
+    let serialize = |order: MapKeyOrder| {
+        let mut serialized = Vec::new();
+        response
+            .clone()
+            .serialize_into_ordered(&mut serialized, order)
+            .map(|()| serialized)
+            .map_err(|x| x.to_string())
+    };
+    assert_eq!(
+        serialize(MapKeyOrder::Ascending)?,
+        vec![
+            0xA2, 0x01, 0x42, 0xDC, 0xAF, 0x08, 0xA1, 0x01, 0xA3, 0x01, 0x01, 0x21, 0x41, 0x01,
+            0x20, 0x06
+        ]
+    );
+    assert_eq!(
+        serialize(MapKeyOrder::Canonical)?,
+        vec![
+            0xA2, 0x01, 0x42, 0xDC, 0xAF, 0x08, 0xA1, 0x01, 0xA3, 0x01, 0x01, 0x20, 0x06, 0x21,
+            0x41, 0x01
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_extended_message() -> Result<(), String> {
+    let hint = AuthServerRequestCreationHint::builder()
+        .audience("a")
+        .build()
+        .map_err(|x| x.to_string())?;
+    let extended = ExtendedMessage::new(hint).with_parameter(-65537, Value::from(true));
+    let mut serialized = Vec::new();
+    extended
+        .serialize_into(&mut serialized)
+        .map_err(|x| x.to_string())?;
+    assert_eq!(
+        serialized,
+        vec![0xA2, 0x05, 0x61, 0x61, 0x3A, 0x00, 0x01, 0x00, 0x00, 0xF5]
+    );
+    let received = ExtendedMessage::deserialize_from(serialized.as_slice(), &[-65537])
+        .map_err(|x| x.to_string())?;
+    assert_eq!(received, extended);
+    assert_eq!(received.parameter(-65537), Some(&Value::from(true)));
+    assert_eq!(received.parameter(5), None);
+
+    // Unless its key is given, the extra parameter is unknown to the hint.
+    assert!(
+        ExtendedMessage::<AuthServerRequestCreationHint>::deserialize_from(
+            serialized.as_slice(),
+            &[]
+        )
+        .is_err()
+    );
+    Ok(())
+}
+
+#[test]
+fn test_extended_message_collision() {
+    let hint = AuthServerRequestCreationHint::builder()
+        .audience("a")
+        .build()
+        .expect("hint must be valid");
+    let colliding = ExtendedMessage::new(hint.clone()).with_parameter(5, Value::from(1));
+    assert_eq!(colliding.merge(), Err(DuplicateMapKeyError { key: 5 }));
+    assert!(colliding.serialize_into(Vec::new()).is_err());
+    let duplicate = ExtendedMessage::new(hint)
+        .with_parameter(-1, Value::from(1))
+        .with_parameter(-1, Value::from(2));
+    assert_eq!(duplicate.merge(), Err(DuplicateMapKeyError { key: -1 }));
+}
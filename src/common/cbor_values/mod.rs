@@ -29,11 +29,19 @@
 use core::fmt::{Debug, Display, Formatter};
 use core::ops::Deref;
 
-use coset::{CoseEncrypt0, CoseKey};
+use ciborium::value::{Integer, Value};
+use coset::cwt::ClaimsSet;
+use coset::{iana, AsCborValue, CoseEncrypt0, CoseKey, KeyType, Label};
 use strum_macros::IntoStaticStr;
 
+use crate::common::confirmation::confirmation_method_value;
+use crate::constants::cbor_abbreviations::confirmation;
+#[cfg(feature = "oscore-profile")]
+use crate::endpoints::authz_info::OscoreInputMaterial;
+use crate::error::KeyThumbprintError;
+
 #[cfg(not(feature = "std"))]
-use {alloc::boxed::Box, alloc::format, alloc::vec, alloc::vec::Vec};
+use {alloc::boxed::Box, alloc::vec, alloc::vec::Vec};
 
 #[cfg(test)]
 mod tests;
@@ -53,7 +61,8 @@ where
 /// A proof-of-possession key as specified by
 /// [RFC 8747, section 3.1](https://datatracker.ietf.org/doc/html/rfc8747#section-3.1).
 ///
-/// Can either be a COSE key, an encrypted COSE key, or simply a key ID.
+/// Can either be a COSE key, an encrypted COSE key, a CWT Claims Set containing a COSE key,
+/// a key thumbprint, OSCORE input material, or simply a key ID.
 /// As described in [RFC 9201](https://www.rfc-editor.org/rfc/rfc9201),
 /// PoP keys are used for the `req_cnf` parameter in [`AccessTokenRequest`](crate::AccessTokenRequest),
 /// as well as for the `cnf` and `rs_cnf` parameters in [`AccessTokenResponse`](crate::AccessTokenResponse).
@@ -91,12 +100,60 @@ pub enum ProofOfPossessionKey {
     ///
     /// For details, see [section 3.4 of RFC 8747](https://datatracker.ietf.org/doc/html/rfc8747#section-3.4).
     KeyId(KeyId),
+
+    /// COSE Key Thumbprint of the actual proof-of-possession key, which can be computed using
+    /// [`cose_key_thumbprint`].
+    ///
+    /// This binds the token to a key without having to include the full key.
+    ///
+    /// For details, see [section 5.2 of RFC 9679](https://www.rfc-editor.org/rfc/rfc9679#section-5.2).
+    KeyThumbprint(ByteString),
+
+    /// A CWT Claims Set (CCS) whose `cnf` claim contains the actual proof-of-possession key
+    /// (`kccs`), as used by the EDHOC and OSCORE profile to bind the token to the credential
+    /// the client uses in EDHOC.
+    /// Use [`matches_edhoc_credential`](ProofOfPossessionKey::matches_edhoc_credential) to check
+    /// whether an EDHOC session was established using this credential.
+    ///
+    /// Note that IANA hasn't assigned a confirmation method value for `kccs` yet, so the value
+    /// of the COSE header parameter `kccs` (14) is used for now.
+    ///
+    /// For details, see [section 3.2.1 of draft-ietf-ace-edhoc-oscore-profile](https://datatracker.ietf.org/doc/html/draft-ietf-ace-edhoc-oscore-profile#section-3.2.1).
+    Ccs(ClaimsSet),
+
+    /// The input material from which the client and the Resource Server derive their OSCORE
+    /// security context (`osc`), as used by the OSCORE profile, whose
+    /// [`key_id`](ProofOfPossessionKey::key_id) is the [`id`](OscoreInputMaterial::id) of the
+    /// material.
+    ///
+    /// For details, see [section 3.2.1 of RFC 9203](https://www.rfc-editor.org/rfc/rfc9203#section-3.2.1).
+    #[cfg(feature = "oscore-profile")]
+    OscoreInputMaterial(OscoreInputMaterial),
+}
+
+/// Identifies the credential a peer has used in an EDHOC session, as given by its `ID_CRED`
+/// field (see [section 3.5.3 of RFC 9528](https://www.rfc-editor.org/rfc/rfc9528#section-3.5.3)).
+///
+/// Used by the Resource Server to check whether an access token is bound to the credential
+/// of the EDHOC session, using [`ProofOfPossessionKey::matches_edhoc_credential`].
+#[cfg(feature = "oscore-profile")]
+#[derive(Debug, PartialEq, Clone)]
+pub enum EdhocCredentialId {
+    /// The credential is identified by its key ID (`kid`).
+    KeyId(KeyId),
+
+    /// The credential is a CWT Claims Set transported by value (`kccs`).
+    Ccs(ClaimsSet),
 }
 
 impl ProofOfPossessionKey {
     /// Returns the key ID of this PoP key, cloning it if necessary.
     /// Note that the returned key ID may be empty if no key ID was present in the key.
     ///
+    /// For a [`KeyThumbprint`](ProofOfPossessionKey::KeyThumbprint), the thumbprint itself is
+    /// returned, as it may be used as a key ID according to
+    /// [section 5.6 of RFC 9679](https://www.rfc-editor.org/rfc/rfc9679#section-5.6).
+    ///
     /// # Example
     /// ```
     /// # use coset::CoseKeyBuilder;
@@ -109,7 +166,7 @@ impl ProofOfPossessionKey {
     pub fn key_id(&self) -> &KeyId {
         match self {
             ProofOfPossessionKey::PlainCoseKey(k) => &k.key_id,
-            ProofOfPossessionKey::KeyId(k) => k,
+            ProofOfPossessionKey::KeyId(k) | ProofOfPossessionKey::KeyThumbprint(k) => k,
             ProofOfPossessionKey::EncryptedCoseKey(k) => {
                 if k.protected.header.key_id.is_empty() {
                     &k.unprotected.key_id
@@ -117,10 +174,136 @@ impl ProofOfPossessionKey {
                     &k.protected.header.key_id
                 }
             }
+            ProofOfPossessionKey::Ccs(ccs) => ccs_key_id(ccs),
+            #[cfg(feature = "oscore-profile")]
+            ProofOfPossessionKey::OscoreInputMaterial(material) => &material.id,
+        }
+    }
+
+    /// Returns whether this PoP key refers to the same credential as the given EDHOC `credential`
+    /// identifier, i.e., whether an EDHOC session established with `credential` is bound to
+    /// an access token with this PoP key.
+    ///
+    /// Key IDs are compared with the key ID of this PoP key, while CWT Claims Sets must either
+    /// be equal to a [`Ccs`](ProofOfPossessionKey::Ccs) or contain the same COSE key as a
+    /// [`PlainCoseKey`](ProofOfPossessionKey::PlainCoseKey). Empty key IDs never match.
+    ///
+    /// # Example
+    /// ```
+    /// # use dcaf::common::cbor_values::{EdhocCredentialId, ProofOfPossessionKey};
+    /// let pop_key = ProofOfPossessionKey::KeyId(vec![0xDC, 0xAF]);
+    /// assert!(pop_key.matches_edhoc_credential(&EdhocCredentialId::KeyId(vec![0xDC, 0xAF])));
+    /// assert!(!pop_key.matches_edhoc_credential(&EdhocCredentialId::KeyId(vec![0xDC])));
+    /// ```
+    #[cfg(feature = "oscore-profile")]
+    #[must_use]
+    pub fn matches_edhoc_credential(&self, credential: &EdhocCredentialId) -> bool {
+        match (credential, self) {
+            (EdhocCredentialId::KeyId(kid), _) => !kid.is_empty() && self.key_id() == kid,
+            (EdhocCredentialId::Ccs(ccs), ProofOfPossessionKey::Ccs(own)) => ccs == own,
+            (EdhocCredentialId::Ccs(ccs), ProofOfPossessionKey::PlainCoseKey(key)) => {
+                ccs_cose_key(ccs).as_ref() == Some(key)
+            }
+            (EdhocCredentialId::Ccs(ccs), ProofOfPossessionKey::KeyId(kid)) => {
+                !kid.is_empty() && ccs_key_id(ccs) == kid
+            }
+            (EdhocCredentialId::Ccs(_), _) => false,
         }
     }
 }
 
+/// Returns the COSE key contained in the `cnf` claim of the given `ccs`, if it's valid.
+pub(crate) fn ccs_cose_key(ccs: &ClaimsSet) -> Option<CoseKey> {
+    confirmation_method_value(ccs, confirmation::COSE_KEY)
+        .and_then(|key| CoseKey::from_cbor_value(key.clone()).ok())
+}
+
+/// Returns the key ID of the COSE key contained in the `cnf` claim of the given `ccs`,
+/// or an empty key ID if there is none.
+fn ccs_key_id(ccs: &ClaimsSet) -> &KeyId {
+    const NO_KEY_ID: &KeyId = &Vec::new();
+    match confirmation_method_value(ccs, confirmation::COSE_KEY) {
+        Some(Value::Map(params)) => params
+            .iter()
+            .find_map(|(label, value)| match (label, value) {
+                (Value::Integer(i), Value::Bytes(kid)) if *i == Integer::from(2) => Some(kid),
+                _ => None,
+            })
+            .unwrap_or(NO_KEY_ID),
+        _ => NO_KEY_ID,
+    }
+}
+
+/// Returns the input for the COSE Key Thumbprint of the given `key`, i.e., the deterministic CBOR
+/// encoding of a map containing only the key's required parameters, as specified in
+/// [section 3 of RFC 9679](https://www.rfc-editor.org/rfc/rfc9679#section-3).
+///
+/// Only key types whose required parameters are known are supported: `OKP` (`kty`, `crv`, `x`),
+/// `EC2` (`kty`, `crv`, `x`, `y`), `RSA` (`kty`, `n`, `e`), and `Symmetric` (`kty`, `k`).
+/// To compute the thumbprint itself, use [`cose_key_thumbprint`].
+///
+/// # Errors
+/// - If the key type is not one of the supported ones listed above.
+/// - If one of the required parameters is missing from the key.
+///
+/// # Example
+/// ```
+/// # use coset::CoseKeyBuilder;
+/// # use dcaf::common::cbor_values::cose_key_thumbprint_input;
+/// # use dcaf::error::KeyThumbprintError;
+/// let key = CoseKeyBuilder::new_symmetric_key(vec![0xDC, 0xAF]).key_id(vec![0x42]).build();
+/// // The key ID is not part of the thumbprint input.
+/// assert_eq!(cose_key_thumbprint_input(&key)?, vec![0xA2, 0x01, 0x04, 0x20, 0x42, 0xDC, 0xAF]);
+/// # Ok::<(), KeyThumbprintError>(())
+/// ```
+pub fn cose_key_thumbprint_input(key: &CoseKey) -> Result<ByteString, KeyThumbprintError> {
+    let required: &[i64] = match key.kty {
+        // OKP: crv, x. RSA: n, e.
+        KeyType::Assigned(iana::KeyType::OKP | iana::KeyType::RSA) => &[-1, -2],
+        // crv, x, y
+        KeyType::Assigned(iana::KeyType::EC2) => &[-1, -2, -3],
+        // k
+        KeyType::Assigned(iana::KeyType::Symmetric) => &[-1],
+        _ => return Err(KeyThumbprintError::UnsupportedKeyType),
+    };
+    let kty = key
+        .kty
+        .clone()
+        .to_cbor_value()
+        .map_err(|_| KeyThumbprintError::UnsupportedKeyType)?;
+    // Labels 1, -1, -2, -3 are already in deterministic (bytewise lexicographic) encoding order.
+    let mut map = vec![(Value::from(1), kty)];
+    for label in required {
+        let value = key
+            .params
+            .iter()
+            .find(|(l, _)| l == &Label::Int(*label))
+            .map(|(_, v)| v.clone())
+            .ok_or(KeyThumbprintError::MissingParameter(*label))?;
+        map.push((Value::from(*label), value));
+    }
+    let mut result = Vec::new();
+    // Serializing a plain `Value` into a vector can't fail for the values we've put in here.
+    ciborium::ser::into_writer(&Value::Map(map), &mut result)
+        .map_err(|_| KeyThumbprintError::UnsupportedKeyType)?;
+    Ok(result)
+}
+
+/// Computes the COSE Key Thumbprint of the given `key` as specified in
+/// [RFC 9679](https://www.rfc-editor.org/rfc/rfc9679), using the given `hash` function
+/// (usually SHA-256) on the [thumbprint input](cose_key_thumbprint_input).
+///
+/// The result can be used for a [`ProofOfPossessionKey::KeyThumbprint`].
+///
+/// # Errors
+/// Same as for [`cose_key_thumbprint_input`].
+pub fn cose_key_thumbprint<F>(key: &CoseKey, hash: F) -> Result<ByteString, KeyThumbprintError>
+where
+    F: FnOnce(&[u8]) -> ByteString,
+{
+    cose_key_thumbprint_input(key).map(|input| hash(&input))
+}
+
 impl<T> Deref for CborMapValue<T>
 where
     T: From<i32> + Into<i32> + Copy,
@@ -144,13 +327,12 @@ where
 
 /// Contains various `From`, `TryFrom` and other conversion methods for types of the parent module.
 mod conversion {
-    use ciborium::value::Value;
-    use coset::{AsCborValue, CoseEncrypt0, CoseKey};
     use erased_serde::Serialize as ErasedSerialize;
     use serde::de::Error;
     use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
     use crate::common::cbor_map::ToCborMap;
+    use crate::common::confirmation::{confirmation_method, decode_confirmation_map};
     use crate::error::{TryFromCborMapError, WrongSourceTypeError};
 
     use super::*;
@@ -190,64 +372,15 @@ mod conversion {
 
     impl ToCborMap for ProofOfPossessionKey {
         fn to_cbor_map(&self) -> Vec<(i128, Option<Box<dyn ErasedSerialize + '_>>)> {
-            // The fact that we have to clone this is a little unfortunate.
-            match self {
-                Self::PlainCoseKey(key) => {
-                    let x: i128 = 1;
-                    vec![(
-                        x,
-                        Some(Box::new(key.clone().to_cbor_value().expect("Invalid key"))),
-                    )]
-                }
-                Self::EncryptedCoseKey(enc) => {
-                    let x: i128 = 2;
-                    vec![(
-                        x,
-                        Some(Box::new(
-                            (*enc).clone().to_cbor_value().expect("Invalid key"),
-                        )),
-                    )]
-                }
-                Self::KeyId(kid) => {
-                    let x: i128 = 3;
-                    vec![(x, Some(Box::new(Value::Bytes(kid.clone()))))]
-                }
-            }
+            let (method, value) = confirmation_method(self);
+            vec![(method, Some(Box::new(value)))]
         }
 
         fn try_from_cbor_map(map: Vec<(i128, Value)>) -> Result<Self, TryFromCborMapError>
         where
             Self: Sized + ToCborMap,
         {
-            if map.len() != 1 {
-                Err(TryFromCborMapError::from_message(
-                    "given CBOR map must contain exactly one element",
-                ))
-            } else if let Some(entry) = map.into_iter().next() {
-                match entry {
-                    (1, x) => CoseKey::from_cbor_value(x)
-                        .map(ProofOfPossessionKey::PlainCoseKey)
-                        .map_err(|x| {
-                            TryFromCborMapError::from_message(format!(
-                                "couldn't create CoseKey from CBOR value: {x}"
-                            ))
-                        }),
-                    (2, x) => CoseEncrypt0::from_cbor_value(x)
-                        .map(ProofOfPossessionKey::EncryptedCoseKey)
-                        .map_err(|x| {
-                            TryFromCborMapError::from_message(format!(
-                                "couldn't create CoseEncrypt0 from CBOR value: {x}"
-                            ))
-                        }),
-                    (3, Value::Bytes(x)) => Ok(ProofOfPossessionKey::KeyId(x)),
-                    (x, _) => Err(TryFromCborMapError::unknown_field(u8::try_from(x)?)),
-                }
-            } else {
-                unreachable!(
-                    "we have previously verified that map.len() == 1, \
-                so map.into_iter().next() must return a next element"
-                )
-            }
+            Ok(decode_confirmation_map(map)?)
         }
     }
 
@@ -269,6 +402,13 @@ mod conversion {
         }
     }
 
+    #[cfg(feature = "oscore-profile")]
+    impl From<OscoreInputMaterial> for ProofOfPossessionKey {
+        fn from(material: OscoreInputMaterial) -> Self {
+            ProofOfPossessionKey::OscoreInputMaterial(material)
+        }
+    }
+
     impl TryFrom<ProofOfPossessionKey> for CoseKey {
         type Error = WrongSourceTypeError<ProofOfPossessionKey>;
 
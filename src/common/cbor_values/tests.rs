@@ -11,19 +11,22 @@
 
 mod pop {
     #[cfg(not(feature = "std"))]
-    use alloc::{string::String, string::ToString, vec};
+    use alloc::{string::String, string::ToString, vec, vec::Vec};
     use core::marker::PhantomData;
 
     use ciborium::value::Value;
-    use coset::iana::Algorithm;
+    use coset::cwt::{ClaimsSet, ClaimsSetBuilder};
+    use coset::iana::{Algorithm, CwtClaimName};
     use coset::{
-        iana, CoseEncrypt0, CoseEncrypt0Builder, CoseKey, CoseKeyBuilder, HeaderBuilder,
-        ProtectedHeader,
+        iana, AsCborValue, CoseEncrypt0, CoseEncrypt0Builder, CoseKey, CoseKeyBuilder,
+        HeaderBuilder, KeyType, Label, ProtectedHeader,
     };
 
-    use crate::common::cbor_values::KeyId;
+    #[cfg(feature = "oscore-profile")]
+    use crate::common::cbor_values::EdhocCredentialId;
+    use crate::common::cbor_values::{cose_key_thumbprint, cose_key_thumbprint_input, KeyId};
     use crate::common::test_helper::expect_ser_de;
-    use crate::error::WrongSourceTypeError;
+    use crate::error::{KeyThumbprintError, WrongSourceTypeError};
     use crate::ProofOfPossessionKey::{EncryptedCoseKey, PlainCoseKey};
     use crate::{ByteString, ProofOfPossessionKey, ToCborMap};
 
@@ -40,7 +43,7 @@ mod pop {
             WrongSourceTypeError {
                 expected_type: "PlainCoseKey",
                 actual_type: "KeyId",
-                general_type: PhantomData::default(),
+                general_type: PhantomData,
             }
         );
         expect_ser_de(pop, None, "A10342DCAF")?;
@@ -75,7 +78,7 @@ mod pop {
             WrongSourceTypeError {
                 expected_type: "EncryptedCoseKey",
                 actual_type: "PlainCoseKey",
-                general_type: PhantomData::default(),
+                general_type: PhantomData,
             }
         );
         expect_ser_de(pop, None, "A101A501020242DCAF2001215820D7CC072DE2205BDC1537A543D53C60A6ACB62ECCD890C7FA27C9E354089BBE13225820F95E1D4B851A2CC80FFF87D8E23F22AFB725D535E515D020731E79A3B4E47120")?;
@@ -117,7 +120,7 @@ mod pop {
             WrongSourceTypeError {
                 expected_type: "KeyId",
                 actual_type: "EncryptedCoseKey",
-                general_type: PhantomData::default(),
+                general_type: PhantomData,
             }
         );
         expect_ser_de(pop, Some(transform_header), "A1028343A1010AA20442DCAF054D636898994FF0EC7BFCF6D3F95B58300573318A3573EB983E55A7C2F06CADD0796C9E584F1D0E3EA8C5B052592A8B2694BE9654F0431F38D5BBC8049FA7F13F")?;
@@ -132,6 +135,121 @@ mod pop {
         Ok(())
     }
 
+    #[test]
+    fn test_key_thumbprint() -> Result<(), String> {
+        let pop = ProofOfPossessionKey::KeyThumbprint(vec![0xDC, 0xAF]);
+        assert_eq!(pop.key_id(), &vec![0xDC, 0xAF]);
+        expect_ser_de(pop, None, "A10542DCAF")?;
+        Ok(())
+    }
+
+    /// Example taken from RFC 9679, section 6.
+    #[test]
+    fn test_cose_key_thumbprint() -> Result<(), String> {
+        let key = CoseKeyBuilder::new_ec2_pub_key(
+            iana::EllipticCurve::P_256,
+            hex::decode("65eda5a12577c2bae829437fe338701a10aaa375e1bb5b5de108de439c08551d")
+                .map_err(|x| x.to_string())?,
+            hex::decode("1e52ed75701163f7f9e40ddf9f341b3dc9ba860af7e0ca7ca7e9eecd0084d19c")
+                .map_err(|x| x.to_string())?,
+        )
+        .key_id(vec![0xDC, 0xAF])
+        .algorithm(Algorithm::ES256)
+        .build();
+        let expected = hex::decode(
+            "a40102200121582065eda5a12577c2bae829437fe338701a10aaa375e1bb5b5de108de439c08551d\
+            2258201e52ed75701163f7f9e40ddf9f341b3dc9ba860af7e0ca7ca7e9eecd0084d19c",
+        )
+        .map_err(|x| x.to_string())?;
+        assert_eq!(
+            cose_key_thumbprint_input(&key).map_err(|x| x.to_string())?,
+            expected
+        );
+        // We don't have a hash function here, so we just check that it's applied to the input.
+        assert_eq!(
+            cose_key_thumbprint(&key, |input| input.iter().rev().copied().collect())
+                .map_err(|x| x.to_string())?,
+            expected.into_iter().rev().collect::<Vec<_>>()
+        );
+
+        let mut incomplete = key.clone();
+        incomplete.params.retain(|(l, _)| l != &Label::Int(-3));
+        assert_eq!(
+            cose_key_thumbprint_input(&incomplete),
+            Err(KeyThumbprintError::MissingParameter(-3))
+        );
+        let mut unsupported = key;
+        unsupported.kty = KeyType::Assigned(iana::KeyType::WalnutDSA);
+        assert_eq!(
+            cose_key_thumbprint_input(&unsupported),
+            Err(KeyThumbprintError::UnsupportedKeyType)
+        );
+        Ok(())
+    }
+
+    fn edhoc_key() -> CoseKey {
+        CoseKeyBuilder::new_okp_key()
+            .param(-1, Value::from(4))
+            .param(-2, Value::Bytes(vec![0xA3; 4]))
+            .key_id(vec![0xDC, 0xAF])
+            .build()
+    }
+
+    fn edhoc_ccs(key: CoseKey) -> Result<ClaimsSet, String> {
+        let key = key.to_cbor_value().map_err(|x| x.to_string())?;
+        Ok(ClaimsSetBuilder::new()
+            .subject("client".to_string())
+            .claim(CwtClaimName::Cnf, Value::Map(vec![(Value::from(1), key)]))
+            .build())
+    }
+
+    #[test]
+    fn test_ccs() -> Result<(), String> {
+        let pop = ProofOfPossessionKey::Ccs(edhoc_ccs(edhoc_key())?);
+        assert_eq!(pop.key_id(), &vec![0xDC, 0xAF]);
+        expect_ser_de(
+            pop,
+            None,
+            "A10EA20266636C69656E7408A101A401010242DCAF20042144A3A3A3A3",
+        )?;
+        let without_cnf = ProofOfPossessionKey::Ccs(
+            ClaimsSetBuilder::new()
+                .subject("client".to_string())
+                .build(),
+        );
+        assert!(without_cnf.key_id().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "oscore-profile")]
+    fn test_matches_edhoc_credential() -> Result<(), String> {
+        let ccs = edhoc_ccs(edhoc_key())?;
+        let by_kid = EdhocCredentialId::KeyId(vec![0xDC, 0xAF]);
+        let by_value = EdhocCredentialId::Ccs(ccs.clone());
+        for pop in [
+            ProofOfPossessionKey::Ccs(ccs),
+            PlainCoseKey(edhoc_key()),
+            ProofOfPossessionKey::KeyId(vec![0xDC, 0xAF]),
+        ] {
+            assert!(pop.matches_edhoc_credential(&by_kid), "{pop:?}");
+            assert!(pop.matches_edhoc_credential(&by_value), "{pop:?}");
+        }
+        let mut other_key = edhoc_key();
+        other_key.key_id = vec![0x42];
+        let other = EdhocCredentialId::Ccs(edhoc_ccs(other_key.clone())?);
+        assert!(!PlainCoseKey(edhoc_key()).matches_edhoc_credential(&other));
+        assert!(!ProofOfPossessionKey::KeyId(vec![0xDC, 0xAF]).matches_edhoc_credential(&other));
+        assert!(!ProofOfPossessionKey::KeyThumbprint(vec![0x01]).matches_edhoc_credential(&other));
+        // Empty key IDs must never match.
+        other_key.key_id = Vec::new();
+        let without_kid = EdhocCredentialId::Ccs(edhoc_ccs(other_key)?);
+        assert!(!ProofOfPossessionKey::KeyId(Vec::new()).matches_edhoc_credential(&without_kid));
+        assert!(!ProofOfPossessionKey::KeyId(Vec::new())
+            .matches_edhoc_credential(&EdhocCredentialId::KeyId(Vec::new())));
+        Ok(())
+    }
+
     #[test]
     fn test_try_from_invalid_cbor_map() {
         // This example is alright
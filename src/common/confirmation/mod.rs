@@ -0,0 +1,212 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+//! Contains the parser and serializer for confirmation (`cnf`) structures, as specified in
+//! [RFC 8747, section 3.1](https://www.rfc-editor.org/rfc/rfc8747#section-3.1).
+//!
+//! Such structures appear in the `req_cnf` parameter of an
+//! [`AccessTokenRequest`](crate::AccessTokenRequest), in the `cnf` and `rs_cnf` parameters of an
+//! [`AccessTokenResponse`](crate::AccessTokenResponse), and in the `cnf` claim of access tokens
+//! (or of CWT Claims Sets used as credentials). All of them are handled by the functions in this
+//! module, which represent the structure as a [`ProofOfPossessionKey`] and report malformed
+//! structures as a [`ConfirmationError`].
+//!
+//! # Example
+//! ```
+//! # use coset::cwt::ClaimsSetBuilder;
+//! # use coset::iana::CwtClaimName;
+//! # use dcaf::common::cbor_values::ProofOfPossessionKey;
+//! # use dcaf::error::ConfirmationError;
+//! use dcaf::common::confirmation::{decode_confirmation, encode_confirmation, get_confirmation};
+//!
+//! let key = ProofOfPossessionKey::KeyId(vec![0xDC, 0xAF]);
+//! let value = encode_confirmation(&key);
+//! assert_eq!(decode_confirmation(value.clone())?, key);
+//!
+//! let claims = ClaimsSetBuilder::new().claim(CwtClaimName::Cnf, value).build();
+//! assert_eq!(get_confirmation(&claims)?, Some(key));
+//! # Ok::<(), ConfirmationError>(())
+//! ```
+
+use ciborium::value::Value;
+use coset::cwt::{ClaimName, ClaimsSet};
+use coset::iana::CwtClaimName;
+use coset::{AsCborValue, CoseEncrypt0, CoseKey};
+
+#[cfg(feature = "oscore-profile")]
+use crate::common::cbor_map::ToCborMap;
+use crate::common::cbor_values::ProofOfPossessionKey;
+use crate::constants::cbor_abbreviations::{confirmation, CborKey};
+#[cfg(feature = "oscore-profile")]
+use crate::endpoints::authz_info::OscoreInputMaterial;
+use crate::error::ConfirmationError;
+
+#[cfg(not(feature = "std"))]
+use {alloc::string::ToString, alloc::vec, alloc::vec::Vec};
+
+#[cfg(test)]
+mod tests;
+
+/// Serializes the given `key` to a confirmation structure, i.e., a CBOR map containing a single
+/// confirmation method.
+///
+/// # Panics
+/// If the key material can't be serialized to CBOR, which would imply a programming mistake on
+/// account of `dcaf-rs`, not its users.
+#[must_use]
+pub fn encode_confirmation(key: &ProofOfPossessionKey) -> Value {
+    let (method, value) = confirmation_method(key);
+    Value::Map(vec![(Value::from(method), value)])
+}
+
+/// Parses the given confirmation structure `value` into a [`ProofOfPossessionKey`].
+///
+/// # Errors
+/// If `value` isn't a CBOR map containing exactly one known confirmation method with valid
+/// content, as detailed by the variants of [`ConfirmationError`].
+pub fn decode_confirmation(value: Value) -> Result<ProofOfPossessionKey, ConfirmationError> {
+    match value {
+        Value::Map(map) => decode_confirmation_map(
+            map.into_iter()
+                .map(|(method, value)| match method {
+                    Value::Integer(method) => Ok((i128::from(method), value)),
+                    _ => Err(ConfirmationError::NonIntegerMethod),
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+        ),
+        _ => Err(ConfirmationError::NotAMap),
+    }
+}
+
+/// Parses the `cnf` claim of the given `claims` into a [`ProofOfPossessionKey`], returning
+/// `None` if there is no such claim.
+///
+/// # Errors
+/// If the `cnf` claim is not a valid confirmation structure, as for [`decode_confirmation`].
+pub fn get_confirmation(
+    claims: &ClaimsSet,
+) -> Result<Option<ProofOfPossessionKey>, ConfirmationError> {
+    cnf_claim(claims)
+        .cloned()
+        .map(decode_confirmation)
+        .transpose()
+}
+
+/// Returns the confirmation method and its value representing the given `key`.
+///
+/// # Panics
+/// If the key material can't be serialized to CBOR.
+pub(crate) fn confirmation_method(key: &ProofOfPossessionKey) -> (i128, Value) {
+    // The fact that we have to clone this is a little unfortunate.
+    match key {
+        ProofOfPossessionKey::PlainCoseKey(key) => (
+            confirmation::COSE_KEY.into(),
+            key.clone().to_cbor_value().expect("Invalid key"),
+        ),
+        ProofOfPossessionKey::EncryptedCoseKey(enc) => (
+            confirmation::ENCRYPTED_COSE_KEY.into(),
+            enc.clone().to_cbor_value().expect("Invalid key"),
+        ),
+        ProofOfPossessionKey::KeyId(kid) => (confirmation::KID.into(), Value::Bytes(kid.clone())),
+        ProofOfPossessionKey::KeyThumbprint(thumbprint) => (
+            confirmation::KEY_THUMBPRINT.into(),
+            Value::Bytes(thumbprint.clone()),
+        ),
+        ProofOfPossessionKey::Ccs(ccs) => (
+            confirmation::KCCS.into(),
+            ccs.clone().to_cbor_value().expect("Invalid CCS"),
+        ),
+        #[cfg(feature = "oscore-profile")]
+        ProofOfPossessionKey::OscoreInputMaterial(material) => (
+            confirmation::OSCORE_INPUT_MATERIAL.into(),
+            material.to_ciborium_value(),
+        ),
+    }
+}
+
+/// Parses the given confirmation structure, already decoded into a `map` with integer keys,
+/// into a [`ProofOfPossessionKey`].
+///
+/// # Errors
+/// If `map` doesn't contain exactly one known confirmation method with valid content.
+pub(crate) fn decode_confirmation_map(
+    map: Vec<(i128, Value)>,
+) -> Result<ProofOfPossessionKey, ConfirmationError> {
+    if map.len() != 1 {
+        return Err(ConfirmationError::MethodCount(map.len()));
+    }
+    let (method, value) = map
+        .into_iter()
+        .next()
+        .ok_or(ConfirmationError::MethodCount(0))?;
+    let invalid = |method: &'static str| {
+        move |e: coset::CoseError| ConfirmationError::InvalidMethod {
+            method,
+            message: e.to_string(),
+        }
+    };
+    match (CborKey::try_from(method), value) {
+        (Ok(confirmation::COSE_KEY), x) => CoseKey::from_cbor_value(x)
+            .map(ProofOfPossessionKey::PlainCoseKey)
+            .map_err(invalid("COSE_Key")),
+        (Ok(confirmation::ENCRYPTED_COSE_KEY), x) => CoseEncrypt0::from_cbor_value(x)
+            .map(ProofOfPossessionKey::EncryptedCoseKey)
+            .map_err(invalid("Encrypted_COSE_Key")),
+        (Ok(confirmation::KID), Value::Bytes(x)) => Ok(ProofOfPossessionKey::KeyId(x)),
+        (Ok(confirmation::KEY_THUMBPRINT), Value::Bytes(x)) => {
+            Ok(ProofOfPossessionKey::KeyThumbprint(x))
+        }
+        (Ok(confirmation::KID), _) => Err(ConfirmationError::InvalidMethod {
+            method: "kid",
+            message: "must be a byte string".to_string(),
+        }),
+        (Ok(confirmation::KEY_THUMBPRINT), _) => Err(ConfirmationError::InvalidMethod {
+            method: "ckt",
+            message: "must be a byte string".to_string(),
+        }),
+        (Ok(confirmation::KCCS), x) => ClaimsSet::from_cbor_value(x)
+            .map(ProofOfPossessionKey::Ccs)
+            .map_err(invalid("kccs")),
+        #[cfg(feature = "oscore-profile")]
+        (Ok(confirmation::OSCORE_INPUT_MATERIAL), x) => OscoreInputMaterial::from_ciborium_value(x)
+            .map(ProofOfPossessionKey::OscoreInputMaterial)
+            .map_err(|e| ConfirmationError::InvalidMethod {
+                method: "osc",
+                message: e.to_string(),
+            }),
+        _ => Err(ConfirmationError::UnknownMethod(method)),
+    }
+}
+
+/// Returns the value of the given confirmation `method` in the `cnf` claim of the given `claims`
+/// without parsing it, if there is one.
+pub(crate) fn confirmation_method_value(
+    claims: &ClaimsSet,
+    method: CborKey<confirmation::Registry>,
+) -> Option<&Value> {
+    match cnf_claim(claims) {
+        Some(Value::Map(methods)) => methods
+            .iter()
+            .find(|(x, _)| x == &Value::from(method.value()))
+            .map(|(_, value)| value),
+        _ => None,
+    }
+}
+
+/// Returns the raw `cnf` claim of the given `claims`, if there is one.
+fn cnf_claim(claims: &ClaimsSet) -> Option<&Value> {
+    let cnf = ClaimName::Assigned(CwtClaimName::Cnf);
+    claims
+        .rest
+        .iter()
+        .find(|(name, _)| name == &cnf)
+        .map(|(_, value)| value)
+}
@@ -0,0 +1,145 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+use coset::cwt::ClaimsSetBuilder;
+use coset::iana::{self, CwtClaimName};
+use coset::{CoseEncrypt0Builder, CoseKeyBuilder};
+
+#[cfg(not(feature = "std"))]
+use {alloc::string::String, alloc::vec};
+
+use crate::common::cbor_map::ToCborMap;
+
+use super::*;
+
+fn keys() -> Vec<ProofOfPossessionKey> {
+    let key = CoseKeyBuilder::new_ec2_pub_key(iana::EllipticCurve::P_256, vec![1], vec![2])
+        .key_id(vec![0xDC, 0xAF])
+        .build();
+    #[allow(unused_mut)]
+    let mut keys = vec![
+        ProofOfPossessionKey::PlainCoseKey(key.clone()),
+        ProofOfPossessionKey::EncryptedCoseKey(
+            CoseEncrypt0Builder::new().ciphertext(vec![1, 2, 3]).build(),
+        ),
+        ProofOfPossessionKey::KeyId(vec![0xDC, 0xAF]),
+        ProofOfPossessionKey::KeyThumbprint(vec![0xAF; 32]),
+        ProofOfPossessionKey::Ccs(
+            ClaimsSetBuilder::new()
+                .subject(String::from("client"))
+                .claim(
+                    CwtClaimName::Cnf,
+                    encode_confirmation(&ProofOfPossessionKey::PlainCoseKey(key)),
+                )
+                .build(),
+        ),
+    ];
+    #[cfg(feature = "oscore-profile")]
+    keys.push(ProofOfPossessionKey::OscoreInputMaterial(
+        OscoreInputMaterial::builder()
+            .id(vec![0x01])
+            .master_secret(vec![0xAF; 16])
+            .context_id(vec![0xA5])
+            .build()
+            .expect("invalid OSCORE input material"),
+    ));
+    keys
+}
+
+#[test]
+fn test_confirmation_roundtrip() -> Result<(), ConfirmationError> {
+    for key in keys() {
+        let value = encode_confirmation(&key);
+        // The serializer of the `ToCborMap` implementation must agree with this module.
+        assert_eq!(key.to_ciborium_value(), value);
+        // Decoded COSE structures keep their original encoding, so we compare the re-encoded ones.
+        let decoded = decode_confirmation(value.clone())?;
+        assert_eq!(encode_confirmation(&decoded), value);
+
+        let claims = ClaimsSetBuilder::new()
+            .claim(CwtClaimName::Cnf, value.clone())
+            .build();
+        assert_eq!(
+            get_confirmation(&claims)?.map(|x| encode_confirmation(&x)),
+            Some(value)
+        );
+    }
+    assert_eq!(get_confirmation(&ClaimsSetBuilder::new().build())?, None);
+    Ok(())
+}
+
+#[test]
+fn test_confirmation_errors() {
+    assert_eq!(
+        decode_confirmation(Value::Bytes(vec![1])),
+        Err(ConfirmationError::NotAMap)
+    );
+    assert_eq!(
+        decode_confirmation(Value::Map(vec![])),
+        Err(ConfirmationError::MethodCount(0))
+    );
+    assert_eq!(
+        decode_confirmation(Value::Map(vec![
+            (Value::from(3), Value::Bytes(vec![1])),
+            (Value::from(5), Value::Bytes(vec![2])),
+        ])),
+        Err(ConfirmationError::MethodCount(2))
+    );
+    assert_eq!(
+        decode_confirmation(Value::Map(vec![(
+            Value::Text("kid".into()),
+            Value::Bytes(vec![1])
+        )])),
+        Err(ConfirmationError::NonIntegerMethod)
+    );
+    assert_eq!(
+        decode_confirmation(Value::Map(vec![(Value::from(-1), Value::Bytes(vec![1]))])),
+        Err(ConfirmationError::UnknownMethod(-1))
+    );
+    assert_eq!(
+        decode_confirmation(Value::Map(vec![(Value::from(42), Value::Bytes(vec![1]))])),
+        Err(ConfirmationError::UnknownMethod(42))
+    );
+    for (method, name) in [
+        (1, "COSE_Key"),
+        (2, "Encrypted_COSE_Key"),
+        (3, "kid"),
+        #[cfg(feature = "oscore-profile")]
+        (4, "osc"),
+        (5, "ckt"),
+        (14, "kccs"),
+    ] {
+        match decode_confirmation(Value::Map(vec![(Value::from(method), Value::Bool(true))])) {
+            Err(ConfirmationError::InvalidMethod { method, .. }) => assert_eq!(method, name),
+            other => panic!("unexpected result {other:?}"),
+        }
+    }
+    // OSCORE input material must contain at least an ID and a master secret.
+    #[cfg(feature = "oscore-profile")]
+    assert!(matches!(
+        decode_confirmation(Value::Map(vec![(
+            Value::from(4),
+            Value::Map(vec![(Value::from(0), Value::Bytes(vec![1]))])
+        )])),
+        Err(ConfirmationError::InvalidMethod { method: "osc", .. })
+    ));
+    // Without the OSCORE profile, OSCORE input material isn't supported.
+    #[cfg(not(feature = "oscore-profile"))]
+    assert_eq!(
+        decode_confirmation(Value::Map(vec![(Value::from(4), Value::Bool(true))])),
+        Err(ConfirmationError::UnknownMethod(4))
+    );
+
+    let claims = ClaimsSetBuilder::new()
+        .claim(CwtClaimName::Cnf, Value::Null)
+        .build();
+    assert_eq!(get_confirmation(&claims), Err(ConfirmationError::NotAMap));
+}
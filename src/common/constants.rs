@@ -19,99 +19,394 @@
 //! - [RFC 9203](https://www.rfc-editor.org/rfc/rfc9203)
 
 /// Constants which abbreviate string values as integers in CBOR.
+///
+/// Keys of CBOR maps are represented as [`CborKey`](cbor_abbreviations::CborKey)s, which carry the registry they belong to.
 pub mod cbor_abbreviations {
+    use core::fmt::{Display, Formatter};
+    use core::marker::PhantomData;
+    use core::num::TryFromIntError;
+
+    /// A key of a CBOR map, belonging to the registry `R`.
+    ///
+    /// Each module in here which contains map keys defines its own `Registry` marker type,
+    /// and all keys of a map must belong to the same registry. Hence, mixing up, e.g.,
+    /// a CWT claim key with an OAuth parameter key is a type error rather than a silent
+    /// interoperability problem:
+    /// ```compile_fail
+    /// use dcaf::constants::cbor_abbreviations::{introspection, token, CborKey};
+    /// let key: CborKey<token::Registry> = introspection::ISSUER;
+    /// ```
+    ///
+    /// The integer value of a key can be retrieved using [`value`](CborKey::value):
+    /// ```
+    /// use dcaf::constants::cbor_abbreviations::token;
+    /// assert_eq!(token::CLIENT_ID.value(), 24);
+    /// ```
+    ///
+    /// Values are `i64`s, so that keys from the private use ranges of the registries (which are
+    /// negative) can be represented as well.
+    #[derive(Debug, PartialEq, Eq, Hash)]
+    pub struct CborKey<R> {
+        value: i64,
+        registry: PhantomData<R>,
+    }
+
+    impl<R> CborKey<R> {
+        /// Creates a new key of the registry `R` with the given integer `value`.
+        #[must_use]
+        pub const fn new(value: i64) -> Self {
+            CborKey {
+                value,
+                registry: PhantomData,
+            }
+        }
+
+        /// Returns the integer value of this key.
+        #[must_use]
+        pub const fn value(self) -> i64 {
+            self.value
+        }
+    }
+
+    // Implemented manually, as deriving would require the registry marker to be `Copy` as well.
+    impl<R> Clone for CborKey<R> {
+        fn clone(&self) -> Self {
+            *self
+        }
+    }
+
+    impl<R> Copy for CborKey<R> {}
+
+    impl<R> From<CborKey<R>> for i128 {
+        fn from(key: CborKey<R>) -> Self {
+            i128::from(key.value)
+        }
+    }
+
+    impl<R> TryFrom<i128> for CborKey<R> {
+        type Error = TryFromIntError;
+
+        fn try_from(value: i128) -> Result<Self, Self::Error> {
+            i64::try_from(value).map(CborKey::new)
+        }
+    }
+
+    impl<R> Display for CborKey<R> {
+        fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+            write!(f, "{}", self.value)
+        }
+    }
+
     /// Constants for CBOR map keys in AS Request Creation Hints,
     /// as specified in [RFC 9200](https://www.rfc-editor.org/rfc/rfc9200), Table 1.
     pub mod creation_hint {
+        use super::CborKey;
+
+        /// Marker type for the registry of AS Request Creation Hint parameters.
+        #[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+        pub enum Registry {}
+
         /// See section 5.3 of [RFC 9200](https://www.rfc-editor.org/rfc/rfc9200).
-        pub const AS: u8 = 1;
+        pub const AS: CborKey<Registry> = CborKey::new(1);
 
         /// See section 5.3 of [RFC 9200](https://www.rfc-editor.org/rfc/rfc9200).
-        pub const KID: u8 = 2;
+        pub const KID: CborKey<Registry> = CborKey::new(2);
 
         /// See section 5.3 of [RFC 9200](https://www.rfc-editor.org/rfc/rfc9200).
-        pub const AUDIENCE: u8 = 5;
+        pub const AUDIENCE: CborKey<Registry> = CborKey::new(5);
 
         /// See section 5.3 of [RFC 9200](https://www.rfc-editor.org/rfc/rfc9200).
-        pub const SCOPE: u8 = 9;
+        pub const SCOPE: CborKey<Registry> = CborKey::new(9);
 
         /// See section 5.3 of [RFC 9200](https://www.rfc-editor.org/rfc/rfc9200).
-        pub const CNONCE: u8 = 39;
+        pub const CNONCE: CborKey<Registry> = CborKey::new(39);
     }
 
     /// Constants for CBOR map keys in token requests and responses,
     /// as specified in [RFC 9200](https://www.rfc-editor.org/rfc/rfc9200), Table 5
     /// and [RFC 9201](https://www.rfc-editor.org/rfc/rfc9201.html), Table 1.
     pub mod token {
+        use super::CborKey;
+
+        /// Marker type for the registry of OAuth parameters used in token requests and responses.
+        #[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+        pub enum Registry {}
+
         /// See section 5.1 of [RFC 6749](https://www.rfc-editor.org/rfc/rfc6749).
-        pub const ACCESS_TOKEN: u8 = 1;
+        pub const ACCESS_TOKEN: CborKey<Registry> = CborKey::new(1);
 
         /// See section 5.1 of [RFC 6749](https://www.rfc-editor.org/rfc/rfc6749).
-        pub const EXPIRES_IN: u8 = 2;
+        pub const EXPIRES_IN: CborKey<Registry> = CborKey::new(2);
+
+        /// The issuer of an access token request, as required by
+        /// [DCAF](https://gitlab.informatik.uni-bremen.de/DCAF/dcaf/).
+        ///
+        /// Note that this isn't a registered OAuth parameter, but reuses the key of the
+        /// corresponding CWT claim ([`introspection::ISSUER`](super::introspection::ISSUER)).
+        pub const ISSUER: CborKey<Registry> = CborKey::new(super::introspection::ISSUER.value());
+
+        /// The time at which an access token has been issued, as required by
+        /// [DCAF](https://gitlab.informatik.uni-bremen.de/DCAF/dcaf/).
+        ///
+        /// Note that this isn't a registered OAuth parameter, but reuses the key of the
+        /// corresponding CWT claim ([`introspection::ISSUED_AT`](super::introspection::ISSUED_AT)).
+        pub const ISSUED_AT: CborKey<Registry> =
+            CborKey::new(super::introspection::ISSUED_AT.value());
 
         /// See section 3.1 of [RFC 9201](https://www.rfc-editor.org/rfc/rfc9201).
-        pub const REQ_CNF: u8 = 4;
+        pub const REQ_CNF: CborKey<Registry> = CborKey::new(4);
 
         /// See section 2.1 of [RFC 8693](https://www.rfc-editor.org/rfc/rfc8693).
-        pub const AUDIENCE: u8 = 5;
+        pub const AUDIENCE: CborKey<Registry> = CborKey::new(5);
 
         /// See section 3.2 of [RFC 9201](https://www.rfc-editor.org/rfc/rfc9201).
-        pub const CNF: u8 = 8;
+        pub const CNF: CborKey<Registry> = CborKey::new(8);
 
         /// See section 4.4.2 of [RFC 6749](https://www.rfc-editor.org/rfc/rfc6749)
         /// and section 5.1 of [RFC 6749](https://www.rfc-editor.org/rfc/rfc6749).
-        pub const SCOPE: u8 = 9;
+        pub const SCOPE: CborKey<Registry> = CborKey::new(9);
 
         /// See section 2.2 of [RFC 6749](https://www.rfc-editor.org/rfc/rfc6749).
-        pub const CLIENT_ID: u8 = 24;
+        pub const CLIENT_ID: CborKey<Registry> = CborKey::new(24);
 
         /// See section 2.3.1 of [RFC 6749](https://www.rfc-editor.org/rfc/rfc6749).
-        pub const CLIENT_SECRET: u8 = 25;
+        pub const CLIENT_SECRET: CborKey<Registry> = CborKey::new(25);
 
         /// See section 3.1.1 of [RFC 6749](https://www.rfc-editor.org/rfc/rfc6749).
-        pub const RESPONSE_TYPE: u8 = 26;
+        pub const RESPONSE_TYPE: CborKey<Registry> = CborKey::new(26);
 
         /// See section 3.1.2 of [RFC 6749](https://www.rfc-editor.org/rfc/rfc6749).
-        pub const REDIRECT_URI: u8 = 27;
+        pub const REDIRECT_URI: CborKey<Registry> = CborKey::new(27);
 
         /// See section 4.1.1 of [RFC 6749](https://www.rfc-editor.org/rfc/rfc6749).
-        pub const STATE: u8 = 28;
+        pub const STATE: CborKey<Registry> = CborKey::new(28);
 
         /// See section 4.1.3 of [RFC 6749](https://www.rfc-editor.org/rfc/rfc6749).
-        pub const CODE: u8 = 29;
+        pub const CODE: CborKey<Registry> = CborKey::new(29);
 
         /// See section 5.2 of [RFC 6749](https://www.rfc-editor.org/rfc/rfc6749).
-        pub const ERROR: u8 = 30;
+        pub const ERROR: CborKey<Registry> = CborKey::new(30);
 
         /// See section 5.2 of [RFC 6749](https://www.rfc-editor.org/rfc/rfc6749).
-        pub const ERROR_DESCRIPTION: u8 = 31;
+        pub const ERROR_DESCRIPTION: CborKey<Registry> = CborKey::new(31);
 
         /// See section 5.2 of [RFC 6749](https://www.rfc-editor.org/rfc/rfc6749).
-        pub const ERROR_URI: u8 = 32;
+        pub const ERROR_URI: CborKey<Registry> = CborKey::new(32);
 
         /// See section 4.4.2 of [RFC 6749](https://www.rfc-editor.org/rfc/rfc6749).
-        pub const GRANT_TYPE: u8 = 33;
+        pub const GRANT_TYPE: CborKey<Registry> = CborKey::new(33);
 
         /// See section 5.1 of [RFC 6749](https://www.rfc-editor.org/rfc/rfc6749).
-        pub const TOKEN_TYPE: u8 = 34;
+        pub const TOKEN_TYPE: CborKey<Registry> = CborKey::new(34);
 
         /// See section 4.3.2 of [RFC 6749](https://www.rfc-editor.org/rfc/rfc6749).
-        pub const USERNAME: u8 = 35;
+        pub const USERNAME: CborKey<Registry> = CborKey::new(35);
 
         /// See section 4.3.2 of [RFC 6749](https://www.rfc-editor.org/rfc/rfc6749).
-        pub const PASSWORD: u8 = 36;
+        pub const PASSWORD: CborKey<Registry> = CborKey::new(36);
 
         /// See section 5.1 of [RFC 6749](https://www.rfc-editor.org/rfc/rfc6749).
-        pub const REFRESH_TOKEN: u8 = 37;
+        pub const REFRESH_TOKEN: CborKey<Registry> = CborKey::new(37);
 
         /// See section 5.8.4.3 of [RFC 9200](https://www.rfc-editor.org/rfc/rfc9200).
-        pub const ACE_PROFILE: u8 = 38;
+        pub const ACE_PROFILE: CborKey<Registry> = CborKey::new(38);
 
         /// See section 5.8.4.4 of [RFC 9200](https://www.rfc-editor.org/rfc/rfc9200).
-        pub const CNONCE: u8 = 39;
+        pub const CNONCE: CborKey<Registry> = CborKey::new(39);
 
         /// See section 3.2 of [RFC 9201](https://www.rfc-editor.org/rfc/rfc9201).
-        pub const RS_CNF: u8 = 41;
+        pub const RS_CNF: CborKey<Registry> = CborKey::new(41);
+
+        /// See section 4.2 of [RFC 7521](https://www.rfc-editor.org/rfc/rfc7521).
+        ///
+        /// Note that this extension parameter has not been registered, so this value is taken
+        /// from the private use range and may change once an abbreviation exists.
+        pub const CLIENT_ASSERTION: CborKey<Registry> = CborKey::new(-65537);
+
+        /// The profiles the Authorization Server supports for the requested audience, included in
+        /// error responses with the `incompatible_ace_profiles` error code.
+        ///
+        /// Note that this extension parameter has not been registered, so this value is taken
+        /// from the private use range and may change once an abbreviation exists.
+        pub const ACE_PROFILES_SUPPORTED: CborKey<Registry> = CborKey::new(-65538);
+
+        /// A proof that the Authorization Server possesses a key shared with (or the signing key
+        /// trusted by) the client, computed over the confirmation nonce of the request and the
+        /// rest of the response.
+        ///
+        /// Note that this extension parameter has not been registered, so this value is taken
+        /// from the private use range and may change once an abbreviation exists.
+        pub const KEY_CONFIRMATION: CborKey<Registry> = CborKey::new(-65539);
+
+        /// A nonce chosen by the client, over which the Authorization Server computes the
+        /// [`KEY_CONFIRMATION`] of its response.
+        ///
+        /// Note that this extension parameter has not been registered, so this value is taken
+        /// from the private use range and may change once an abbreviation exists.
+        pub const CONFIRMATION_NONCE: CborKey<Registry> = CborKey::new(-65540);
+
+        /// See section 2.1 of [RFC 8693](https://www.rfc-editor.org/rfc/rfc8693).
+        ///
+        /// Note that this extension parameter has not been registered, so this value is taken
+        /// from the private use range and may change once an abbreviation exists.
+        pub const SUBJECT_TOKEN: CborKey<Registry> = CborKey::new(-65541);
+
+        /// See section 2.1 of [RFC 8693](https://www.rfc-editor.org/rfc/rfc8693).
+        ///
+        /// Note that this extension parameter has not been registered, so this value is taken
+        /// from the private use range and may change once an abbreviation exists.
+        pub const SUBJECT_TOKEN_TYPE: CborKey<Registry> = CborKey::new(-65542);
+
+        /// See section 2.1 of [RFC 8693](https://www.rfc-editor.org/rfc/rfc8693).
+        ///
+        /// Note that this extension parameter has not been registered, so this value is taken
+        /// from the private use range and may change once an abbreviation exists.
+        pub const ACTOR_TOKEN: CborKey<Registry> = CborKey::new(-65543);
+
+        /// See section 2.1 of [RFC 8693](https://www.rfc-editor.org/rfc/rfc8693).
+        ///
+        /// Note that this extension parameter has not been registered, so this value is taken
+        /// from the private use range and may change once an abbreviation exists.
+        pub const ACTOR_TOKEN_TYPE: CborKey<Registry> = CborKey::new(-65544);
+    }
+
+    /// Constants for CBOR map keys in messages exchanged with the `authz-info` endpoint
+    /// when using the OSCORE profile, as specified in
+    /// [RFC 9203](https://www.rfc-editor.org/rfc/rfc9203), Table 1.
+    pub mod authz_info {
+        use super::CborKey;
+
+        /// Marker type for the registry of `authz-info` parameters of the OSCORE profile.
+        #[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+        pub enum Registry {}
+
+        /// See section 5.1 of [RFC 6749](https://www.rfc-editor.org/rfc/rfc6749).
+        pub const ACCESS_TOKEN: CborKey<Registry> = CborKey::new(1);
+
+        /// See section 4.1.1 of [RFC 9203](https://www.rfc-editor.org/rfc/rfc9203).
+        pub const NONCE1: CborKey<Registry> = CborKey::new(40);
+
+        /// See section 4.1.2 of [RFC 9203](https://www.rfc-editor.org/rfc/rfc9203).
+        pub const NONCE2: CborKey<Registry> = CborKey::new(42);
+
+        /// See section 4.1.1 of [RFC 9203](https://www.rfc-editor.org/rfc/rfc9203).
+        pub const ACE_CLIENT_RECIPIENTID: CborKey<Registry> = CborKey::new(43);
+
+        /// See section 4.1.2 of [RFC 9203](https://www.rfc-editor.org/rfc/rfc9203).
+        pub const ACE_SERVER_RECIPIENTID: CborKey<Registry> = CborKey::new(44);
+    }
+
+    /// Constants for the confirmation methods in `cnf` structures, as specified in
+    /// [RFC 8747](https://www.rfc-editor.org/rfc/rfc8747), Table 2.
+    pub mod confirmation {
+        use super::CborKey;
+
+        /// Marker type for the registry of CWT confirmation methods.
+        #[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+        pub enum Registry {}
+
+        /// See section 3.2 of [RFC 8747](https://www.rfc-editor.org/rfc/rfc8747).
+        pub const COSE_KEY: CborKey<Registry> = CborKey::new(1);
+
+        /// See section 3.3 of [RFC 8747](https://www.rfc-editor.org/rfc/rfc8747).
+        pub const ENCRYPTED_COSE_KEY: CborKey<Registry> = CborKey::new(2);
+
+        /// See section 3.4 of [RFC 8747](https://www.rfc-editor.org/rfc/rfc8747).
+        pub const KID: CborKey<Registry> = CborKey::new(3);
+
+        /// See section 3.2.1 of [RFC 9203](https://www.rfc-editor.org/rfc/rfc9203).
+        pub const OSCORE_INPUT_MATERIAL: CborKey<Registry> = CborKey::new(4);
+
+        /// See section 5.2 of [RFC 9679](https://www.rfc-editor.org/rfc/rfc9679).
+        pub const KEY_THUMBPRINT: CborKey<Registry> = CborKey::new(5);
+
+        /// See section 3.2.1 of
+        /// [draft-ietf-ace-edhoc-oscore-profile](https://datatracker.ietf.org/doc/html/draft-ietf-ace-edhoc-oscore-profile).
+        /// Not assigned by IANA yet, so the value of the COSE header parameter `kccs` is used.
+        pub const KCCS: CborKey<Registry> = CborKey::new(14);
+    }
+
+    /// Constants for CBOR map keys in the `OSCORE_Input_Material` object, as specified in
+    /// [RFC 9203](https://www.rfc-editor.org/rfc/rfc9203), Table 4.
+    pub mod oscore_input_material {
+        use super::CborKey;
+
+        /// Marker type for the registry of `OSCORE_Input_Material` parameters.
+        #[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+        pub enum Registry {}
+
+        /// See section 3.2.1 of [RFC 9203](https://www.rfc-editor.org/rfc/rfc9203).
+        pub const ID: CborKey<Registry> = CborKey::new(0);
+
+        /// See section 3.2.1 of [RFC 9203](https://www.rfc-editor.org/rfc/rfc9203).
+        pub const VERSION: CborKey<Registry> = CborKey::new(1);
+
+        /// See section 3.2.1 of [RFC 9203](https://www.rfc-editor.org/rfc/rfc9203).
+        pub const MS: CborKey<Registry> = CborKey::new(2);
+
+        /// See section 3.2.1 of [RFC 9203](https://www.rfc-editor.org/rfc/rfc9203).
+        pub const HKDF: CborKey<Registry> = CborKey::new(3);
+
+        /// See section 3.2.1 of [RFC 9203](https://www.rfc-editor.org/rfc/rfc9203).
+        pub const ALG: CborKey<Registry> = CborKey::new(4);
+
+        /// See section 3.2.1 of [RFC 9203](https://www.rfc-editor.org/rfc/rfc9203).
+        pub const SALT: CborKey<Registry> = CborKey::new(5);
+
+        /// See section 3.2.1 of [RFC 9203](https://www.rfc-editor.org/rfc/rfc9203).
+        pub const CONTEXT_ID: CborKey<Registry> = CborKey::new(6);
+    }
+
+    /// Constants for CBOR map keys in Authorization Server metadata documents.
+    ///
+    /// Note that there is no registry of CBOR abbreviations for the metadata parameters of
+    /// [RFC 8414](https://www.rfc-editor.org/rfc/rfc8414) yet, so these values are specific to
+    /// this crate and may change once such a registry exists.
+    pub mod as_metadata {
+        use super::CborKey;
+
+        /// Marker type for the (crate-specific) keys of Authorization Server metadata.
+        #[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+        pub enum Registry {}
+
+        /// See section 2 of [RFC 8414](https://www.rfc-editor.org/rfc/rfc8414).
+        pub const ISSUER: CborKey<Registry> = CborKey::new(1);
+
+        /// See section 2 of [RFC 8414](https://www.rfc-editor.org/rfc/rfc8414).
+        pub const TOKEN_ENDPOINT: CborKey<Registry> = CborKey::new(2);
+
+        /// See section 2 of [RFC 8414](https://www.rfc-editor.org/rfc/rfc8414).
+        pub const INTROSPECTION_ENDPOINT: CborKey<Registry> = CborKey::new(3);
+
+        /// See section 8.2 of [RFC 9200](https://www.rfc-editor.org/rfc/rfc9200).
+        pub const ACE_PROFILES_SUPPORTED: CborKey<Registry> = CborKey::new(4);
+
+        /// The algorithms supported for protecting access tokens.
+        pub const ALGORITHMS_SUPPORTED: CborKey<Registry> = CborKey::new(5);
+    }
+
+    /// Constants for CBOR map keys in Authorization Server statistics.
+    ///
+    /// Note that these values are specific to this crate, as statistics are not part of any
+    /// specification.
+    pub mod as_statistics {
+        use super::CborKey;
+
+        /// Marker type for the (crate-specific) keys of Authorization Server statistics.
+        #[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+        pub enum Registry {}
+
+        /// The number of access tokens issued per audience.
+        pub const TOKENS_ISSUED: CborKey<Registry> = CborKey::new(1);
+
+        /// The number of error responses returned per error code.
+        pub const ERRORS: CborKey<Registry> = CborKey::new(2);
+
+        /// The number of seconds the Authorization Server has been running.
+        pub const UPTIME: CborKey<Registry> = CborKey::new(3);
+
+        /// The number of rejected requests per [reason code](crate::error::ReasonCode).
+        pub const REJECTIONS: CborKey<Registry> = CborKey::new(4);
     }
 
     /// Constants for CBOR map keys in token introspections,
@@ -120,15 +415,78 @@ pub mod cbor_abbreviations {
     ///
     /// Some of these constants are also used by libdcaf for additional fields which are required
     /// according to [DCAF](https://gitlab.informatik.uni-bremen.de/DCAF/dcaf/).
-    ///
-    /// **NOTE: This is currently incomplete!**
-    /// Only libdcaf-required parameters are in here for now.
     pub mod introspection {
+        use super::CborKey;
+
+        /// Marker type for the registry of token introspection parameters.
+        #[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+        pub enum Registry {}
+
         /// See [section 3.1.1 of RFC 8392](https://www.rfc-editor.org/rfc/rfc8392#section-3.1.1).
-        pub const ISSUER: u8 = 1;
+        pub const ISSUER: CborKey<Registry> = CborKey::new(1);
+
+        /// See [section 3.1.2 of RFC 8392](https://www.rfc-editor.org/rfc/rfc8392#section-3.1.2).
+        pub const SUBJECT: CborKey<Registry> = CborKey::new(2);
+
+        /// See [section 3.1.3 of RFC 8392](https://www.rfc-editor.org/rfc/rfc8392#section-3.1.3).
+        pub const AUDIENCE: CborKey<Registry> = CborKey::new(3);
+
+        /// See [section 3.1.4 of RFC 8392](https://www.rfc-editor.org/rfc/rfc8392#section-3.1.4).
+        pub const EXPIRATION_TIME: CborKey<Registry> = CborKey::new(4);
+
+        /// See [section 3.1.5 of RFC 8392](https://www.rfc-editor.org/rfc/rfc8392#section-3.1.5).
+        pub const NOT_BEFORE: CborKey<Registry> = CborKey::new(5);
 
         /// See [section 3.1.6 of RFC 8392](https://www.rfc-editor.org/rfc/rfc8392#section-3.1.6).
-        pub const ISSUED_AT: u8 = 6;
+        pub const ISSUED_AT: CborKey<Registry> = CborKey::new(6);
+
+        /// See [section 3.1.7 of RFC 8392](https://www.rfc-editor.org/rfc/rfc8392#section-3.1.7).
+        pub const CWT_ID: CborKey<Registry> = CborKey::new(7);
+
+        /// See [section 5.9.2 of RFC 9200](https://www.rfc-editor.org/rfc/rfc9200#section-5.9.2).
+        pub const CNF: CborKey<Registry> = CborKey::new(8);
+
+        /// See [section 2.2 of RFC 7662](https://www.rfc-editor.org/rfc/rfc7662#section-2.2).
+        pub const SCOPE: CborKey<Registry> = CborKey::new(9);
+
+        /// See [section 2.2 of RFC 7662](https://www.rfc-editor.org/rfc/rfc7662#section-2.2).
+        pub const ACTIVE: CborKey<Registry> = CborKey::new(10);
+
+        /// See [section 2.1 of RFC 7662](https://www.rfc-editor.org/rfc/rfc7662#section-2.1).
+        pub const TOKEN: CborKey<Registry> = CborKey::new(11);
+
+        /// See [section 2.2 of RFC 7662](https://www.rfc-editor.org/rfc/rfc7662#section-2.2).
+        pub const CLIENT_ID: CborKey<Registry> = CborKey::new(24);
+
+        /// See [section 5.9.3 of RFC 9200](https://www.rfc-editor.org/rfc/rfc9200#section-5.9.3).
+        pub const ERROR: CborKey<Registry> = CborKey::new(30);
+
+        /// See [section 5.9.3 of RFC 9200](https://www.rfc-editor.org/rfc/rfc9200#section-5.9.3).
+        pub const ERROR_DESCRIPTION: CborKey<Registry> = CborKey::new(31);
+
+        /// See [section 5.9.3 of RFC 9200](https://www.rfc-editor.org/rfc/rfc9200#section-5.9.3).
+        pub const ERROR_URI: CborKey<Registry> = CborKey::new(32);
+
+        /// See [section 2.1 of RFC 7662](https://www.rfc-editor.org/rfc/rfc7662#section-2.1).
+        pub const TOKEN_TYPE_HINT: CborKey<Registry> = CborKey::new(33);
+
+        /// See [section 2.2 of RFC 7662](https://www.rfc-editor.org/rfc/rfc7662#section-2.2).
+        pub const TOKEN_TYPE: CborKey<Registry> = CborKey::new(34);
+
+        /// See [section 2.2 of RFC 7662](https://www.rfc-editor.org/rfc/rfc7662#section-2.2).
+        pub const USERNAME: CborKey<Registry> = CborKey::new(35);
+
+        /// See [section 5.9.2 of RFC 9200](https://www.rfc-editor.org/rfc/rfc9200#section-5.9.2).
+        pub const ACE_PROFILE: CborKey<Registry> = CborKey::new(38);
+
+        /// See [section 5.9.2 of RFC 9200](https://www.rfc-editor.org/rfc/rfc9200#section-5.9.2).
+        pub const CNONCE: CborKey<Registry> = CborKey::new(39);
+
+        /// See [section 5.9.2 of RFC 9200](https://www.rfc-editor.org/rfc/rfc9200#section-5.9.2).
+        pub const EXI: CborKey<Registry> = CborKey::new(40);
+
+        /// See [section 5.9.2 of RFC 9200](https://www.rfc-editor.org/rfc/rfc9200#section-5.9.2).
+        pub const RS_CNF: CborKey<Registry> = CborKey::new(41);
     }
 
     /// Constants for CBOR abbreviations in grant types,
@@ -145,6 +503,12 @@ pub mod cbor_abbreviations {
 
         /// See section 6 of [RFC 6749](https://www.rfc-editor.org/rfc/rfc6749).
         pub const REFRESH_TOKEN: i32 = 3;
+
+        /// See section 2.1 of [RFC 8693](https://www.rfc-editor.org/rfc/rfc8693).
+        ///
+        /// Note that this grant type has not been registered, so this value is taken from the
+        /// private use range and may change once an abbreviation exists.
+        pub const TOKEN_EXCHANGE: i32 = -65537;
     }
 
     /// Constants for CBOR abbreviations in token types,
@@ -201,3 +565,62 @@ pub mod cbor_abbreviations {
         pub const INCOMPATIBLE_ACE_PROFILES: i32 = 8;
     }
 }
+
+/// Constants for the media type and CoAP Content-Format of ACE-OAuth messages,
+/// as specified in [section 8.19 of RFC 9200](https://www.rfc-editor.org/rfc/rfc9200#section-8.19)
+/// and [section 8.20 of RFC 9200](https://www.rfc-editor.org/rfc/rfc9200#section-8.20).
+pub mod content_format {
+    /// The media type of CBOR-encoded ACE-OAuth messages.
+    pub const ACE_CBOR_MEDIA_TYPE: &str = "application/ace+cbor";
+
+    /// The CoAP Content-Format ID of CBOR-encoded ACE-OAuth messages.
+    pub const ACE_CBOR: u16 = 19;
+}
+
+/// Constants for the token type identifiers used in token exchange requests,
+/// as specified in [section 3 of RFC 8693](https://www.rfc-editor.org/rfc/rfc8693#section-3).
+pub mod token_type_identifiers {
+    /// Indicates that the token is an access token issued by the Authorization Server.
+    pub const ACCESS_TOKEN: &str = "urn:ietf:params:oauth:token-type:access_token";
+
+    /// Indicates that the token is a refresh token issued by the Authorization Server.
+    pub const REFRESH_TOKEN: &str = "urn:ietf:params:oauth:token-type:refresh_token";
+}
+
+/// Constants for CoAP codes used by ACE-OAuth messages, encoded as a single byte
+/// (three bits of class, followed by five bits of detail),
+/// as specified in [section 3 of RFC 7252](https://www.rfc-editor.org/rfc/rfc7252#section-3).
+pub mod coap_code {
+    /// 0.02 POST, see [section 5.8.2 of RFC 7252](https://www.rfc-editor.org/rfc/rfc7252#section-5.8.2).
+    pub const POST: u8 = 0x02;
+
+    /// 2.01 Created, see [section 5.9.1.1 of RFC 7252](https://www.rfc-editor.org/rfc/rfc7252#section-5.9.1.1).
+    pub const CREATED: u8 = 0x41;
+
+    /// 2.05 Content, see [section 5.9.1.4 of RFC 7252](https://www.rfc-editor.org/rfc/rfc7252#section-5.9.1.4).
+    pub const CONTENT: u8 = 0x45;
+
+    /// 4.00 Bad Request, see [section 5.9.2.1 of RFC 7252](https://www.rfc-editor.org/rfc/rfc7252#section-5.9.2.1).
+    pub const BAD_REQUEST: u8 = 0x80;
+
+    /// 4.01 Unauthorized, see [section 5.9.2.2 of RFC 7252](https://www.rfc-editor.org/rfc/rfc7252#section-5.9.2.2).
+    pub const UNAUTHORIZED: u8 = 0x81;
+
+    /// 4.03 Forbidden, see [section 5.9.2.4 of RFC 7252](https://www.rfc-editor.org/rfc/rfc7252#section-5.9.2.4).
+    pub const FORBIDDEN: u8 = 0x83;
+
+    /// 4.29 Too Many Requests, see [section 4 of RFC 8516](https://www.rfc-editor.org/rfc/rfc8516#section-4).
+    pub const TOO_MANY_REQUESTS: u8 = 0x9D;
+
+    /// 5.00 Internal Server Error, see [section 5.9.3.1 of RFC 7252](https://www.rfc-editor.org/rfc/rfc7252#section-5.9.3.1).
+    pub const INTERNAL_SERVER_ERROR: u8 = 0xA0;
+
+    /// 5.02 Bad Gateway, see [section 5.9.3.3 of RFC 7252](https://www.rfc-editor.org/rfc/rfc7252#section-5.9.3.3).
+    pub const BAD_GATEWAY: u8 = 0xA2;
+
+    /// 5.03 Service Unavailable, see [section 5.9.3.4 of RFC 7252](https://www.rfc-editor.org/rfc/rfc7252#section-5.9.3.4).
+    pub const SERVICE_UNAVAILABLE: u8 = 0xA3;
+
+    /// 5.04 Gateway Timeout, see [section 5.9.3.5 of RFC 7252](https://www.rfc-editor.org/rfc/rfc7252#section-5.9.3.5).
+    pub const GATEWAY_TIMEOUT: u8 = 0xA4;
+}
@@ -0,0 +1,239 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+//! Contains enums for values of the [COSE registries](https://www.iana.org/assignments/cose/cose.xhtml)
+//! which are commonly needed when working with ACE-OAuth, namely [`Algorithm`]s and
+//! [`EllipticCurve`]s.
+//!
+//! Both have an `Other` variant for values not listed here, and can be converted to and from
+//! their integer abbreviations as well as the corresponding types of [`coset`], so that they
+//! can be used in place of raw integers. Values are compared by their integer abbreviation, so
+//! e.g. `Algorithm::Other(-7)` is equal to `Algorithm::Es256`.
+//!
+//! # Example
+//! ```
+//! # use coset::CoseKeyBuilder;
+//! # use coset::iana;
+//! use dcaf::common::cose::{Algorithm, EllipticCurve};
+//!
+//! assert_eq!(Algorithm::from(-7), Algorithm::Es256);
+//! assert_eq!(i64::from(Algorithm::Other(-65537)), -65537);
+//! assert_eq!(Algorithm::Other(-7), Algorithm::Es256);
+//! assert_eq!(coset::Algorithm::from(Algorithm::EdDsa), coset::Algorithm::Assigned(iana::Algorithm::EdDSA));
+//!
+//! let key = CoseKeyBuilder::new_ec2_pub_key(iana::EllipticCurve::P_256, vec![1], vec![2]).build();
+//! assert_eq!(EllipticCurve::of_key(&key), Some(EllipticCurve::P256));
+//! ```
+
+use core::hash::{Hash, Hasher};
+
+use ciborium::value::Value;
+use coset::iana::{Ec2KeyParameter, EnumI64, KeyType, OkpKeyParameter};
+use coset::{CoseKey, Label, RegisteredLabel, RegisteredLabelWithPrivate};
+use serde::{Deserialize, Serialize};
+
+use crate::error::WrongSourceTypeError;
+
+#[cfg(test)]
+mod tests;
+
+/// Defines an enum mirroring a COSE registry, along with conversions from and to `i64` and
+/// the corresponding `coset` types.
+macro_rules! cose_registry_enum {
+    (
+        $(#[$meta:meta])*
+        pub enum $name:ident: $iana:ident {
+            $($(#[$variant_meta:meta])* $variant:ident = $value:literal,)*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+        #[serde(from = "i64", into = "i64")]
+        #[non_exhaustive]
+        pub enum $name {
+            $($(#[$variant_meta])* $variant,)*
+
+            /// Another value not listed here, given by its integer abbreviation.
+            ///
+            /// Conversions never produce this variant for a value which has a named variant, and
+            /// if it's constructed for such a value anyway, it's equal to the named variant.
+            Other(i64),
+        }
+
+        /// Compares the integer abbreviations, so that an [`Other`]($name::Other) value is equal
+        /// to the named variant with the same abbreviation.
+        impl PartialEq for $name {
+            fn eq(&self, other: &Self) -> bool {
+                i64::from(*self) == i64::from(*other)
+            }
+        }
+
+        impl Eq for $name {}
+
+        impl Hash for $name {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                i64::from(*self).hash(state);
+            }
+        }
+
+        impl From<i64> for $name {
+            fn from(value: i64) -> Self {
+                match value {
+                    $($value => $name::$variant,)*
+                    x => $name::Other(x),
+                }
+            }
+        }
+
+        impl From<$name> for i64 {
+            fn from(value: $name) -> Self {
+                match value {
+                    $($name::$variant => $value,)*
+                    $name::Other(x) => x,
+                }
+            }
+        }
+
+        impl From<coset::iana::$iana> for $name {
+            fn from(value: coset::iana::$iana) -> Self {
+                $name::from(value.to_i64())
+            }
+        }
+
+        /// Values which aren't known to [`coset`] are converted to the
+        /// [`PrivateUse`](RegisteredLabelWithPrivate::PrivateUse) variant, which is
+        /// serialized as the same integer.
+        impl From<$name> for RegisteredLabelWithPrivate<coset::iana::$iana> {
+            fn from(value: $name) -> Self {
+                let value = i64::from(value);
+                coset::iana::$iana::from_i64(value)
+                    .map_or(RegisteredLabelWithPrivate::PrivateUse(value), |x| {
+                        RegisteredLabelWithPrivate::Assigned(x)
+                    })
+            }
+        }
+
+        impl TryFrom<RegisteredLabelWithPrivate<coset::iana::$iana>> for $name {
+            type Error = WrongSourceTypeError<RegisteredLabelWithPrivate<coset::iana::$iana>>;
+
+            fn try_from(
+                value: RegisteredLabelWithPrivate<coset::iana::$iana>,
+            ) -> Result<Self, Self::Error> {
+                match value {
+                    RegisteredLabelWithPrivate::Assigned(x) => Ok($name::from(x)),
+                    RegisteredLabelWithPrivate::PrivateUse(x) => Ok($name::from(x)),
+                    RegisteredLabelWithPrivate::Text(_) => {
+                        Err(WrongSourceTypeError::new("integer", "text"))
+                    }
+                }
+            }
+        }
+    };
+}
+
+cose_registry_enum! {
+    /// A COSE algorithm, as registered in the
+    /// [COSE Algorithms registry](https://www.iana.org/assignments/cose/cose.xhtml#algorithms).
+    ///
+    /// Serialized as its integer abbreviation.
+    pub enum Algorithm: Algorithm {
+        /// ECDSA w/ SHA-256.
+        Es256 = -7,
+        /// ECDSA w/ SHA-384.
+        Es384 = -35,
+        /// ECDSA w/ SHA-512.
+        Es512 = -36,
+        /// EdDSA.
+        EdDsa = -8,
+        /// Shared secret w/ HKDF and SHA-256, which is also the default HKDF algorithm of OSCORE.
+        DirectHkdfSha256 = -10,
+        /// Shared secret w/ HKDF and SHA-512.
+        DirectHkdfSha512 = -11,
+        /// AES-GCM mode w/ 128-bit key, 128-bit tag.
+        A128Gcm = 1,
+        /// AES-GCM mode w/ 192-bit key, 128-bit tag.
+        A192Gcm = 2,
+        /// AES-GCM mode w/ 256-bit key, 128-bit tag.
+        A256Gcm = 3,
+        /// HMAC w/ SHA-256 truncated to 64 bits.
+        Hmac256_64 = 4,
+        /// HMAC w/ SHA-256.
+        Hmac256_256 = 5,
+        /// HMAC w/ SHA-384.
+        Hmac384_384 = 6,
+        /// HMAC w/ SHA-512.
+        Hmac512_512 = 7,
+        /// AES-CCM mode 128-bit key, 64-bit tag, 13-byte nonce.
+        AesCcm16_64_128 = 10,
+        /// AES-CCM mode 256-bit key, 64-bit tag, 13-byte nonce.
+        AesCcm16_64_256 = 11,
+        /// AES-CCM mode 128-bit key, 64-bit tag, 7-byte nonce.
+        AesCcm64_64_128 = 12,
+        /// AES-CCM mode 256-bit key, 64-bit tag, 7-byte nonce.
+        AesCcm64_64_256 = 13,
+        /// ChaCha20/Poly1305 w/ 256-bit key, 128-bit tag.
+        ChaCha20Poly1305 = 24,
+        /// AES-CCM mode 128-bit key, 128-bit tag, 13-byte nonce.
+        AesCcm16_128_128 = 30,
+        /// AES-CCM mode 256-bit key, 128-bit tag, 13-byte nonce.
+        AesCcm16_128_256 = 31,
+        /// AES-CCM mode 128-bit key, 128-bit tag, 7-byte nonce.
+        AesCcm64_128_128 = 32,
+        /// AES-CCM mode 256-bit key, 128-bit tag, 7-byte nonce.
+        AesCcm64_128_256 = 33,
+    }
+}
+
+cose_registry_enum! {
+    /// An elliptic curve, as registered in the
+    /// [COSE Elliptic Curves registry](https://www.iana.org/assignments/cose/cose.xhtml#elliptic-curves).
+    ///
+    /// Serialized as its integer abbreviation.
+    pub enum EllipticCurve: EllipticCurve {
+        /// NIST P-256, also known as secp256r1 (for EC2 keys).
+        P256 = 1,
+        /// NIST P-384, also known as secp384r1 (for EC2 keys).
+        P384 = 2,
+        /// NIST P-521, also known as secp521r1 (for EC2 keys).
+        P521 = 3,
+        /// X25519 for use with ECDH only (for OKP keys).
+        X25519 = 4,
+        /// X448 for use with ECDH only (for OKP keys).
+        X448 = 5,
+        /// Ed25519 for use with EdDSA only (for OKP keys).
+        Ed25519 = 6,
+        /// Ed448 for use with EdDSA only (for OKP keys).
+        Ed448 = 7,
+        /// SECG secp256k1 (for EC2 keys).
+        Secp256k1 = 8,
+    }
+}
+
+impl EllipticCurve {
+    /// Returns the curve of the given EC2 or OKP `key`, or `None` if it's another type of key
+    /// or doesn't specify a valid curve.
+    #[must_use]
+    pub fn of_key(key: &CoseKey) -> Option<EllipticCurve> {
+        let label = match key.kty {
+            RegisteredLabel::Assigned(KeyType::EC2) => Ec2KeyParameter::Crv as i64,
+            RegisteredLabel::Assigned(KeyType::OKP) => OkpKeyParameter::Crv as i64,
+            _ => return None,
+        };
+        key.params
+            .iter()
+            .find_map(|(key_label, value)| match (key_label, value) {
+                (Label::Int(x), Value::Integer(curve)) if *x == label => {
+                    i64::try_from(*curve).ok().map(EllipticCurve::from)
+                }
+                _ => None,
+            })
+    }
+}
@@ -0,0 +1,66 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+use coset::{iana, CoseKeyBuilder};
+
+#[cfg(not(feature = "std"))]
+use alloc::vec;
+
+use super::*;
+
+#[test]
+fn test_algorithm_conversion() {
+    for (algorithm, value) in [
+        (Algorithm::Es256, -7),
+        (Algorithm::EdDsa, -8),
+        (Algorithm::AesCcm16_64_128, 10),
+        (Algorithm::Other(-65537), -65537),
+        (Algorithm::Other(42_000), 42_000),
+    ] {
+        assert_eq!(Algorithm::from(value), algorithm);
+        assert_eq!(i64::from(algorithm), value);
+    }
+    // Registered values are never decoded into `Other`, and compare equal to it nonetheless.
+    assert!(matches!(Algorithm::from(-7), Algorithm::Es256));
+    assert_eq!(Algorithm::Other(-7), Algorithm::Es256);
+    assert_ne!(Algorithm::Other(-7), Algorithm::EdDsa);
+    assert_eq!(EllipticCurve::Other(1), EllipticCurve::P256);
+    assert_eq!(
+        Algorithm::from(iana::Algorithm::ChaCha20Poly1305),
+        Algorithm::ChaCha20Poly1305
+    );
+
+    let cose_algorithm = coset::Algorithm::Assigned(iana::Algorithm::ES256);
+    assert_eq!(coset::Algorithm::from(Algorithm::Es256), cose_algorithm);
+    assert_eq!(Algorithm::try_from(cose_algorithm), Ok(Algorithm::Es256));
+    assert_eq!(
+        coset::Algorithm::from(Algorithm::Other(-65537)),
+        coset::Algorithm::PrivateUse(-65537)
+    );
+    assert!(Algorithm::try_from(coset::Algorithm::Text("custom".into())).is_err());
+}
+
+#[test]
+fn test_elliptic_curve_of_key() {
+    let ec2 = CoseKeyBuilder::new_ec2_pub_key(iana::EllipticCurve::P_384, vec![1], vec![2]).build();
+    assert_eq!(EllipticCurve::of_key(&ec2), Some(EllipticCurve::P384));
+    let okp = CoseKeyBuilder::new_okp_key()
+        .param(
+            iana::OkpKeyParameter::Crv as i64,
+            Value::from(iana::EllipticCurve::Ed25519 as i64),
+        )
+        .build();
+    assert_eq!(EllipticCurve::of_key(&okp), Some(EllipticCurve::Ed25519));
+    let symmetric = CoseKeyBuilder::new_symmetric_key(vec![1, 2, 3]).build();
+    assert_eq!(EllipticCurve::of_key(&symmetric), None);
+    let without_curve = CoseKeyBuilder::new_okp_key().build();
+    assert_eq!(EllipticCurve::of_key(&without_curve), None);
+}
@@ -0,0 +1,89 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+//! Contains the text encodings of binary data used throughout the crate, namely base64url
+//! (e.g., for JSON Web Keys) and hexadecimal digits.
+
+use crate::common::cbor_values::ByteString;
+
+#[cfg(not(feature = "std"))]
+use {alloc::string::String, alloc::vec::Vec};
+
+#[cfg(test)]
+mod tests;
+
+/// The alphabet of the base64url encoding, see
+/// [section 5 of RFC 4648](https://www.rfc-editor.org/rfc/rfc4648#section-5).
+const BASE64URL: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// The digits used by the hexadecimal encoding.
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+/// Encodes the given `data` using base64url without padding.
+pub(crate) fn encode_base64url(data: &[u8]) -> String {
+    let mut result = String::with_capacity((data.len() * 4 + 2) / 3);
+    for chunk in data.chunks(3) {
+        let bits = chunk
+            .iter()
+            .enumerate()
+            .fold(0_u32, |acc, (i, x)| acc | u32::from(*x) << (16 - 8 * i));
+        for i in 0..=chunk.len() {
+            result.push(char::from(
+                BASE64URL[(bits >> (18 - 6 * i) & 0x3F) as usize],
+            ));
+        }
+    }
+    result
+}
+
+/// Decodes the given base64url-encoded `text` (without padding), returning `None` if it's invalid.
+#[allow(clippy::cast_possible_truncation)]
+pub(crate) fn decode_base64url(text: &str) -> Option<ByteString> {
+    if text.len() % 4 == 1 {
+        return None;
+    }
+    let mut result = Vec::with_capacity(text.len() * 3 / 4);
+    for chunk in text.as_bytes().chunks(4) {
+        let mut bits = 0_u32;
+        for (i, character) in chunk.iter().enumerate() {
+            let value = BASE64URL.iter().position(|x| x == character)?;
+            bits |= (value as u32) << (18 - 6 * i);
+        }
+        for i in 0..chunk.len() - 1 {
+            result.push((bits >> (16 - 8 * i)) as u8);
+        }
+    }
+    Some(result)
+}
+
+/// Encodes the given `data` using lowercase hexadecimal digits.
+pub(crate) fn encode_hex(data: &[u8]) -> String {
+    let mut result = String::with_capacity(data.len() * 2);
+    for x in data {
+        result.push(char::from(HEX_DIGITS[usize::from(x >> 4)]));
+        result.push(char::from(HEX_DIGITS[usize::from(x & 0x0F)]));
+    }
+    result
+}
+
+/// Decodes the given hex-encoded `text`, in which both lowercase and uppercase digits are
+/// accepted, returning `None` if it's invalid.
+#[allow(clippy::cast_possible_truncation)]
+pub(crate) fn decode_hex(text: &str) -> Option<ByteString> {
+    if text.len() % 2 != 0 {
+        return None;
+    }
+    let digit = |x: u8| char::from(x).to_digit(16).map(|x| x as u8);
+    text.as_bytes()
+        .chunks(2)
+        .map(|pair| Some(digit(pair[0])? << 4 | digit(pair[1])?))
+        .collect()
+}
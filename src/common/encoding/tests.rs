@@ -0,0 +1,47 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+#[cfg(not(feature = "std"))]
+use alloc::vec;
+
+use super::*;
+
+#[test]
+fn test_base64url() {
+    for length in 0..16 {
+        let data: Vec<u8> = (0..length)
+            .map(|x: u8| x.wrapping_mul(37).wrapping_add(250))
+            .collect();
+        let encoded = encode_base64url(&data);
+        assert_eq!(
+            encoded,
+            base64::encode_config(&data, base64::URL_SAFE_NO_PAD)
+        );
+        assert_eq!(decode_base64url(&encoded), Some(data));
+    }
+    assert_eq!(decode_base64url("a+b/"), None);
+    assert_eq!(decode_base64url("abcde"), None);
+    assert_eq!(decode_base64url("ab=="), None);
+}
+
+#[test]
+fn test_hex() {
+    assert_eq!(encode_hex(&[0x0A, 0xFF]), "0aff");
+    assert_eq!(encode_hex(&[]), "");
+    assert_eq!(decode_hex("0AfF"), Some(vec![0x0A, 0xFF]));
+    assert_eq!(decode_hex(""), Some(vec![]));
+    assert_eq!(decode_hex("0af"), None);
+    assert_eq!(decode_hex("zz"), None);
+    // Signs and whitespace aren't hex digits, even though `u8::from_str_radix` accepts a sign.
+    assert_eq!(decode_hex("+f"), None);
+    assert_eq!(decode_hex("-f"), None);
+    assert_eq!(decode_hex(" f"), None);
+}
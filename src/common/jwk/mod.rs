@@ -0,0 +1,322 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+//! Contains conversions between COSE keys and JSON Web Keys (JWKs), as defined in
+//! [RFC 7517](https://www.rfc-editor.org/rfc/rfc7517), so that keys can be loaded from existing
+//! JWK Sets or exported to web tooling.
+//!
+//! [`Jwk`] and [`JwkSet`] implement [`Serialize`] and [`Deserialize`] and can hence be
+//! (de)serialized from and to JSON using, e.g., `serde_json`. They can be converted from and to
+//! [`CoseKey`]s and [`ProofOfPossessionKey`]s using [`TryFrom`].
+//!
+//! Elliptic curve keys (`EC`), octet key pairs (`OKP`, e.g., Ed25519 keys) and symmetric keys
+//! (`oct`) are supported, both with and without their private parts. The key ID is converted
+//! from and to the UTF-8 encoding of the JWK's `kid`, as JWKs only use textual key IDs.
+//! Key IDs which aren't valid UTF-8 are exported as their base64url encoding instead (which is
+//! then also what the key ID of a key imported from such a JWK consists of).
+//!
+//! # Example
+//! ```
+//! # use coset::CoseKey;
+//! # use dcaf::error::JwkError;
+//! use dcaf::common::cose::EllipticCurve;
+//! use dcaf::common::jwk::Jwk;
+//!
+//! // As given in RFC 8037, Appendix A.2.
+//! let jwk = Jwk {
+//!     kty: "OKP".to_string(),
+//!     crv: Some("Ed25519".to_string()),
+//!     x: Some("11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo".to_string()),
+//!     ..Jwk::default()
+//! };
+//! let key = CoseKey::try_from(&jwk)?;
+//! assert_eq!(EllipticCurve::of_key(&key), Some(EllipticCurve::Ed25519));
+//! assert_eq!(Jwk::try_from(&key)?, jwk);
+//! # Ok::<(), JwkError>(())
+//! ```
+
+use ciborium::value::Value;
+use coset::iana::{Ec2KeyParameter, KeyType, OkpKeyParameter, SymmetricKeyParameter};
+use coset::{CoseKey, Label, RegisteredLabel};
+use serde::{Deserialize, Serialize};
+
+use crate::common::cbor_values::{ccs_cose_key, ByteString, ProofOfPossessionKey};
+use crate::common::cose::{Algorithm, EllipticCurve};
+use crate::common::encoding::{decode_base64url, encode_base64url};
+use crate::error::JwkError;
+
+#[cfg(not(feature = "std"))]
+use {
+    alloc::format,
+    alloc::string::{String, ToString},
+    alloc::vec,
+    alloc::vec::Vec,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The JOSE names of the algorithms which can be converted, along with their COSE counterparts.
+const ALGORITHMS: [(&str, Algorithm); 7] = [
+    ("ES256", Algorithm::Es256),
+    ("ES384", Algorithm::Es384),
+    ("ES512", Algorithm::Es512),
+    ("EdDSA", Algorithm::EdDsa),
+    ("HS256", Algorithm::Hmac256_256),
+    ("HS384", Algorithm::Hmac384_384),
+    ("HS512", Algorithm::Hmac512_512),
+];
+
+/// The JOSE names of the curves which can be converted, along with their COSE counterparts.
+const CURVES: [(&str, EllipticCurve); 8] = [
+    ("P-256", EllipticCurve::P256),
+    ("P-384", EllipticCurve::P384),
+    ("P-521", EllipticCurve::P521),
+    ("secp256k1", EllipticCurve::Secp256k1),
+    ("X25519", EllipticCurve::X25519),
+    ("X448", EllipticCurve::X448),
+    ("Ed25519", EllipticCurve::Ed25519),
+    ("Ed448", EllipticCurve::Ed448),
+];
+
+/// A base64url-encoded member of a [`Jwk`], given by its name, its value, and the label of the
+/// corresponding COSE key parameter.
+type JwkMember<'a> = (&'static str, &'a Option<String>, i64);
+
+/// A JSON Web Key, as defined in [RFC 7517](https://www.rfc-editor.org/rfc/rfc7517).
+///
+/// Only the members needed for the supported key types are represented, other members
+/// (such as `use` or `key_ops`) are ignored during deserialization.
+/// Binary members are base64url-encoded without padding.
+///
+/// For an example, see the [module-level documentation](self).
+#[derive(Debug, PartialEq, Eq, Clone, Hash, Default, Serialize, Deserialize)]
+pub struct Jwk {
+    /// The key type, i.e., `EC`, `OKP`, or `oct`.
+    pub kty: String,
+
+    /// The key ID.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kid: Option<String>,
+
+    /// The algorithm the key is intended for, e.g., `ES256`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alg: Option<String>,
+
+    /// The curve of `EC` and `OKP` keys, e.g., `P-256`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crv: Option<String>,
+
+    /// The x coordinate of `EC` keys, or the public key of `OKP` keys.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub x: Option<String>,
+
+    /// The y coordinate of `EC` keys.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub y: Option<String>,
+
+    /// The private key of `EC` and `OKP` keys.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub d: Option<String>,
+
+    /// The key value of `oct` (symmetric) keys.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub k: Option<String>,
+}
+
+/// A JWK Set, as defined in [section 5 of RFC 7517](https://www.rfc-editor.org/rfc/rfc7517#section-5).
+#[derive(Debug, PartialEq, Eq, Clone, Hash, Default, Serialize, Deserialize)]
+pub struct JwkSet {
+    /// The keys contained in this set.
+    pub keys: Vec<Jwk>,
+}
+
+impl JwkSet {
+    /// Converts all keys of this set to [`CoseKey`]s.
+    ///
+    /// # Errors
+    /// If any of the keys can't be converted, see [`JwkError`].
+    pub fn cose_keys(&self) -> Result<Vec<CoseKey>, JwkError> {
+        self.keys.iter().map(CoseKey::try_from).collect()
+    }
+}
+
+impl TryFrom<&CoseKey> for Jwk {
+    type Error = JwkError;
+
+    fn try_from(key: &CoseKey) -> Result<Self, Self::Error> {
+        let (kty, labels): (&str, &[(&str, i64)]) = match key.kty {
+            RegisteredLabel::Assigned(KeyType::EC2) => (
+                "EC",
+                &[
+                    ("x", Ec2KeyParameter::X as i64),
+                    ("y", Ec2KeyParameter::Y as i64),
+                    ("d", Ec2KeyParameter::D as i64),
+                ],
+            ),
+            RegisteredLabel::Assigned(KeyType::OKP) => (
+                "OKP",
+                &[
+                    ("x", OkpKeyParameter::X as i64),
+                    ("d", OkpKeyParameter::D as i64),
+                ],
+            ),
+            RegisteredLabel::Assigned(KeyType::Symmetric) => {
+                ("oct", &[("k", SymmetricKeyParameter::K as i64)])
+            }
+            ref other => return Err(JwkError::UnsupportedKeyType(format!("{other:?}"))),
+        };
+        let mut jwk = Jwk {
+            kty: kty.to_string(),
+            ..Jwk::default()
+        };
+        if !key.key_id.is_empty() {
+            jwk.kid = Some(
+                String::from_utf8(key.key_id.clone())
+                    .unwrap_or_else(|_| encode_base64url(&key.key_id)),
+            );
+        }
+        if let Some(alg) = &key.alg {
+            let alg = Algorithm::try_from(alg.clone())
+                .map_err(|_| JwkError::UnsupportedAlgorithm(format!("{alg:?}")))?;
+            jwk.alg = Some(
+                ALGORITHMS
+                    .iter()
+                    .find(|(_, x)| *x == alg)
+                    .map(|(name, _)| (*name).to_string())
+                    .ok_or_else(|| JwkError::UnsupportedAlgorithm(format!("{alg:?}")))?,
+            );
+        }
+        if kty != "oct" {
+            let curve = EllipticCurve::of_key(key).ok_or(JwkError::MissingParameter("crv"))?;
+            jwk.crv = Some(
+                CURVES
+                    .iter()
+                    .find(|(_, x)| *x == curve)
+                    .map(|(name, _)| (*name).to_string())
+                    .ok_or_else(|| JwkError::UnsupportedCurve(format!("{curve:?}")))?,
+            );
+        }
+        for (name, label) in labels {
+            let value = key.params.iter().find_map(|(l, v)| match (l, v) {
+                (Label::Int(l), Value::Bytes(v)) if l == label => Some(encode_base64url(v)),
+                _ => None,
+            });
+            match *name {
+                "x" => jwk.x = value,
+                "y" => jwk.y = value,
+                "d" => jwk.d = value,
+                _ => jwk.k = value,
+            }
+        }
+        match kty {
+            "oct" if jwk.k.is_none() => Err(JwkError::MissingParameter("k")),
+            "EC" if jwk.y.is_none() => Err(JwkError::MissingParameter("y")),
+            "EC" | "OKP" if jwk.x.is_none() => Err(JwkError::MissingParameter("x")),
+            _ => Ok(jwk),
+        }
+    }
+}
+
+impl TryFrom<&Jwk> for CoseKey {
+    type Error = JwkError;
+
+    fn try_from(jwk: &Jwk) -> Result<Self, Self::Error> {
+        let (kty, members): (KeyType, Vec<JwkMember>) = match jwk.kty.as_str() {
+            "EC" => (
+                KeyType::EC2,
+                vec![
+                    ("x", &jwk.x, Ec2KeyParameter::X as i64),
+                    ("y", &jwk.y, Ec2KeyParameter::Y as i64),
+                ],
+            ),
+            "OKP" => (KeyType::OKP, vec![("x", &jwk.x, OkpKeyParameter::X as i64)]),
+            "oct" => (
+                KeyType::Symmetric,
+                vec![("k", &jwk.k, SymmetricKeyParameter::K as i64)],
+            ),
+            other => return Err(JwkError::UnsupportedKeyType(other.to_string())),
+        };
+        let mut key = CoseKey {
+            kty: RegisteredLabel::Assigned(kty),
+            key_id: jwk
+                .kid
+                .as_ref()
+                .map(|x| x.as_bytes().to_vec())
+                .unwrap_or_default(),
+            ..CoseKey::default()
+        };
+        if let Some(alg) = &jwk.alg {
+            let alg = ALGORITHMS
+                .iter()
+                .find(|(name, _)| name == alg)
+                .map(|(_, x)| *x)
+                .ok_or_else(|| JwkError::UnsupportedAlgorithm(alg.clone()))?;
+            key.alg = Some(alg.into());
+        }
+        if kty != KeyType::Symmetric {
+            let crv = jwk.crv.as_ref().ok_or(JwkError::MissingParameter("crv"))?;
+            let curve = CURVES
+                .iter()
+                .find(|(name, _)| name == crv)
+                .map(|(_, x)| *x)
+                .ok_or_else(|| JwkError::UnsupportedCurve(crv.clone()))?;
+            // The curve has the same label (-1) for both EC2 and OKP keys.
+            key.params.push((
+                Label::Int(Ec2KeyParameter::Crv as i64),
+                Value::from(i64::from(curve)),
+            ));
+        }
+        for (name, value, label) in members {
+            let value = value.as_ref().ok_or(JwkError::MissingParameter(name))?;
+            key.params
+                .push((Label::Int(label), Value::Bytes(decode_member(name, value)?)));
+        }
+        if let (Some(d), true) = (&jwk.d, kty != KeyType::Symmetric) {
+            // The private key has the same label (-4) for both EC2 and OKP keys.
+            key.params.push((
+                Label::Int(Ec2KeyParameter::D as i64),
+                Value::Bytes(decode_member("d", d)?),
+            ));
+        }
+        Ok(key)
+    }
+}
+
+impl TryFrom<&ProofOfPossessionKey> for Jwk {
+    type Error = JwkError;
+
+    /// Converts the key contained in the given proof-of-possession key, which must either be a
+    /// [`PlainCoseKey`](ProofOfPossessionKey::PlainCoseKey) or a
+    /// [`Ccs`](ProofOfPossessionKey::Ccs) containing a key.
+    fn try_from(key: &ProofOfPossessionKey) -> Result<Self, Self::Error> {
+        match key {
+            ProofOfPossessionKey::PlainCoseKey(key) => Jwk::try_from(key),
+            ProofOfPossessionKey::Ccs(ccs) => ccs_cose_key(ccs)
+                .ok_or(JwkError::NoKeyMaterial)
+                .and_then(|key| Jwk::try_from(&key)),
+            _ => Err(JwkError::NoKeyMaterial),
+        }
+    }
+}
+
+impl TryFrom<&Jwk> for ProofOfPossessionKey {
+    type Error = JwkError;
+
+    fn try_from(jwk: &Jwk) -> Result<Self, Self::Error> {
+        CoseKey::try_from(jwk).map(ProofOfPossessionKey::PlainCoseKey)
+    }
+}
+
+/// Decodes the base64url-encoded member with the given `name` and `value`.
+fn decode_member(name: &'static str, value: &str) -> Result<ByteString, JwkError> {
+    decode_base64url(value).ok_or(JwkError::InvalidBase64(name))
+}
@@ -0,0 +1,164 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+use coset::cwt::ClaimsSetBuilder;
+use coset::{iana, CoseKeyBuilder};
+
+use crate::common::confirmation::encode_confirmation;
+
+use super::*;
+
+#[test]
+fn test_ec_key_roundtrip() -> Result<(), JwkError> {
+    // As given in RFC 7517, Appendix A.1.
+    let json = r#"{"keys": [{
+        "kty": "EC",
+        "crv": "P-256",
+        "x": "MKBCTNIcKUSDii11ySs3526iDZ8AiTo7Tu6KPAqv7D4",
+        "y": "4Etl6SRW2YiLUrN5vfvVHuhp7x8PxltmWWlbbM4IFyM",
+        "use": "enc",
+        "kid": "1"
+    }]}"#;
+    let set: JwkSet = serde_json::from_str(json).expect("invalid JWK Set");
+    let keys = set.cose_keys()?;
+    let expected = CoseKeyBuilder::new_ec2_pub_key(
+        iana::EllipticCurve::P_256,
+        hex::decode("30a0424cd21c2944838a2d75c92b37e76ea20d9f00893a3b4eee8a3c0aafec3e").unwrap(),
+        hex::decode("e04b65e92456d9888b52b379bdfbd51ee869ef1f0fc65b6659695b6cce081723").unwrap(),
+    )
+    .key_id(b"1".to_vec())
+    .build();
+    assert_eq!(keys, vec![expected.clone()]);
+
+    let exported = Jwk::try_from(&expected)?;
+    assert_eq!(exported.kid, Some("1".to_string()));
+    assert_eq!(exported.y, set.keys[0].y);
+    // Unknown members aren't kept, and unset ones aren't serialized.
+    assert_eq!(
+        serde_json::to_string(&exported).expect("serialization failed"),
+        r#"{"kty":"EC","kid":"1","crv":"P-256","x":"MKBCTNIcKUSDii11ySs3526iDZ8AiTo7Tu6KPAqv7D4","y":"4Etl6SRW2YiLUrN5vfvVHuhp7x8PxltmWWlbbM4IFyM"}"#
+    );
+    Ok(())
+}
+
+#[test]
+fn test_private_and_symmetric_keys() -> Result<(), JwkError> {
+    let private = CoseKeyBuilder::new_okp_key()
+        .param(
+            iana::OkpKeyParameter::Crv as i64,
+            Value::from(iana::EllipticCurve::Ed25519 as i64),
+        )
+        .param(iana::OkpKeyParameter::X as i64, Value::Bytes(vec![1; 32]))
+        .param(iana::OkpKeyParameter::D as i64, Value::Bytes(vec![2; 32]))
+        .algorithm(iana::Algorithm::EdDSA)
+        .build();
+    let jwk = Jwk::try_from(&private)?;
+    assert_eq!(jwk.alg, Some("EdDSA".to_string()));
+    assert!(jwk.d.is_some());
+    assert_eq!(CoseKey::try_from(&jwk)?, private);
+
+    let symmetric = CoseKeyBuilder::new_symmetric_key(vec![0xDC, 0xAF])
+        .key_id(b"our-key".to_vec())
+        .algorithm(iana::Algorithm::HMAC_256_256)
+        .build();
+    let jwk = Jwk::try_from(&symmetric)?;
+    assert_eq!(
+        jwk,
+        Jwk {
+            kty: "oct".to_string(),
+            kid: Some("our-key".to_string()),
+            alg: Some("HS256".to_string()),
+            k: Some("3K8".to_string()),
+            ..Jwk::default()
+        }
+    );
+    assert_eq!(CoseKey::try_from(&jwk)?, symmetric);
+    Ok(())
+}
+
+#[test]
+fn test_pop_key_conversion() -> Result<(), JwkError> {
+    let key = CoseKeyBuilder::new_symmetric_key(vec![0xDC, 0xAF]).build();
+    let jwk = Jwk::try_from(&key)?;
+    assert_eq!(
+        ProofOfPossessionKey::try_from(&jwk)?,
+        ProofOfPossessionKey::PlainCoseKey(key.clone())
+    );
+    assert_eq!(
+        Jwk::try_from(&ProofOfPossessionKey::PlainCoseKey(key.clone()))?,
+        jwk
+    );
+    let ccs = ClaimsSetBuilder::new()
+        .claim(
+            iana::CwtClaimName::Cnf,
+            encode_confirmation(&ProofOfPossessionKey::PlainCoseKey(key)),
+        )
+        .build();
+    assert_eq!(Jwk::try_from(&ProofOfPossessionKey::Ccs(ccs))?, jwk);
+    assert_eq!(
+        Jwk::try_from(&ProofOfPossessionKey::KeyId(vec![0x42])),
+        Err(JwkError::NoKeyMaterial)
+    );
+    Ok(())
+}
+
+#[test]
+fn test_jwk_errors() {
+    let jwk = |kty: &str| Jwk {
+        kty: kty.to_string(),
+        ..Jwk::default()
+    };
+    assert_eq!(
+        CoseKey::try_from(&jwk("RSA")),
+        Err(JwkError::UnsupportedKeyType("RSA".to_string()))
+    );
+    assert_eq!(
+        CoseKey::try_from(&jwk("oct")),
+        Err(JwkError::MissingParameter("k"))
+    );
+    assert_eq!(
+        CoseKey::try_from(&jwk("EC")),
+        Err(JwkError::MissingParameter("crv"))
+    );
+    let invalid = Jwk {
+        crv: Some("P-256".to_string()),
+        x: Some("not base64!".to_string()),
+        y: Some("AA".to_string()),
+        ..jwk("EC")
+    };
+    assert_eq!(
+        CoseKey::try_from(&invalid),
+        Err(JwkError::InvalidBase64("x"))
+    );
+    let unknown_curve = Jwk {
+        crv: Some("brainpoolP256r1".to_string()),
+        ..invalid
+    };
+    assert_eq!(
+        CoseKey::try_from(&unknown_curve),
+        Err(JwkError::UnsupportedCurve("brainpoolP256r1".to_string()))
+    );
+    // Key IDs which aren't valid UTF-8 are exported as their base64url encoding.
+    let non_utf8 = CoseKeyBuilder::new_symmetric_key(vec![1])
+        .key_id(vec![0xFF, 0xDC])
+        .build();
+    assert_eq!(
+        Jwk::try_from(&non_utf8).map(|x| x.kid),
+        Ok(Some("_9w".to_string()))
+    );
+    let unknown_algorithm = CoseKeyBuilder::new_symmetric_key(vec![1])
+        .algorithm(iana::Algorithm::A128GCM)
+        .build();
+    assert!(matches!(
+        Jwk::try_from(&unknown_algorithm),
+        Err(JwkError::UnsupportedAlgorithm(_))
+    ));
+}
@@ -16,19 +16,34 @@
 //! - [`cbor_map`] contains the [`ToCborMap`](crate::common::cbor_map::ToCborMap) trait with which
 //!   data types from this crate can be (de)serialized.
 //! - [`cbor_values`] contains various helper values for CBOR structures.
+//! - [`confirmation`] contains the parser and serializer for confirmation (`cnf`) structures.
+//! - [`cose`] contains enums for values of the COSE registries, such as algorithms and curves.
+//! - [`jwk`] contains conversions between COSE keys and JSON Web Keys.
 //! - [`scope`] contains data types and methods for working with OAuth scopes.
+//! - [`security`] contains the (optionally strict) limits on what is accepted from peers.
+//! - [`text`] contains limits for the length and characters of textual fields.
 //!
 //! Read the respective module-level documentation for details and examples.
 //!
 //! [`constants`]: crate::common::constants
 //! [`cbor_map`]: crate::common::cbor_map
 //! [`cbor_values`]: crate::common::cbor_values
+//! [`confirmation`]: crate::common::confirmation
+//! [`cose`]: crate::common::cose
+//! [`jwk`]: crate::common::jwk
 //! [`scope`]: crate::common::scope
+//! [`security`]: crate::common::security
 
 pub mod cbor_map;
 pub mod cbor_values;
+pub mod confirmation;
 pub mod constants;
+pub mod cose;
+pub(crate) mod encoding;
+pub mod jwk;
 pub mod scope;
+pub mod security;
+pub mod text;
 
 #[cfg(test)]
 pub(crate) mod test_helper;
@@ -14,6 +14,8 @@
 //! The main use case of this module is creating [Scope] instances for either text-,
 //! binary-, or AIF-encoded scopes,
 //! whose elements can then be extracted using the `elements()` method.
+//! Scopes using other encodings can be transported as [`Custom`](Scope::Custom) scopes and
+//! handled by application-provided codecs registered in a [`CustomScopeRegistry`].
 //!
 //! # Example
 //! For example, you could first create a text-, binary-, or AIF-encoded scope:
@@ -98,9 +100,14 @@
 //! AIF is defined in [RFC 9237](https://www.rfc-editor.org/rfc/rfc9237).
 
 #[cfg(not(feature = "std"))]
-use {alloc::string::String, alloc::string::ToString, alloc::vec, alloc::vec::Vec};
+use {
+    alloc::borrow::Cow, alloc::boxed::Box, alloc::string::String, alloc::string::ToString,
+    alloc::vec, alloc::vec::Vec,
+};
 
-use core::fmt::{Display, Formatter};
+use core::fmt::{Debug, Display, Formatter};
+#[cfg(feature = "std")]
+use std::borrow::Cow;
 
 use enumflags2::{bitflags, BitFlags};
 use serde::{Deserialize, Serialize};
@@ -108,6 +115,9 @@ use strum_macros::IntoStaticStr;
 
 use crate::common::cbor_values::ByteString;
 
+#[cfg(feature = "rs")]
+pub(crate) use conversion::remove_dot_segments;
+
 #[cfg(test)]
 mod tests;
 
@@ -399,6 +409,54 @@ pub struct AifEncodedScopeElement {
 #[derive(Debug, PartialEq, Eq, Clone, Hash, Serialize)]
 pub struct AifEncodedScope(Vec<AifEncodedScopeElement>);
 
+/// Determines how the object identifiers ("Toids") of an [`AifEncodedScope`] are matched
+/// against the URI paths of requested resources.
+///
+/// Since [RFC 9237](https://www.rfc-editor.org/rfc/rfc9237#section-2.1) leaves the
+/// interpretation of the Toid to the Resource Server, each Resource Server can pick the strategy
+/// fitting its resource layout. Hierarchical strategies allow policy authors to grant access
+/// to a whole subtree of resources without having to enumerate them.
+///
+/// With every strategy, dot segments (`.` and `..`) are removed from both the Toid and the path
+/// as described in [section 5.2.4 of RFC 3986](https://www.rfc-editor.org/rfc/rfc3986#section-5.2.4)
+/// before they're compared, so that, e.g., `/s/../admin` is treated just like `/admin`.
+/// No other normalization (such as percent-decoding or case folding) is done.
+/// The hierarchical strategies ([`Wildcard`](ToidMatching::Wildcard) and
+/// [`Prefix`](ToidMatching::Prefix)) only apply to absolute Toids: a Toid which is empty or
+/// doesn't start with `/` after this normalization (e.g., `.`) covers no path at all with them,
+/// as it would otherwise cover (nearly) every path.
+///
+/// # Example
+/// ```
+/// # use dcaf::common::scope::ToidMatching;
+/// assert!(!ToidMatching::Exact.covers("/s/*", "/s/temp"));
+/// assert!(ToidMatching::Wildcard.covers("/s/*", "/s/temp"));
+/// assert!(!ToidMatching::Wildcard.covers("/s/*", "/s"));
+/// assert!(ToidMatching::Prefix.covers("/s", "/s/temp"));
+/// assert!(!ToidMatching::Prefix.covers("/s", "/sensors"));
+/// assert!(!ToidMatching::Prefix.covers("/s", "/s/../admin"));
+/// assert!(ToidMatching::Exact.covers("/admin", "/s/../admin"));
+/// assert!(!ToidMatching::Prefix.covers(".", "/admin"));
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum ToidMatching {
+    /// A Toid only covers the path which is exactly equal to it.
+    ///
+    /// This is the default, as it's the only interpretation which doesn't grant access to
+    /// additional resources.
+    Exact,
+
+    /// Like [`Exact`](ToidMatching::Exact), except that a Toid ending in `/*` covers every path
+    /// below the part before the `*`. For example, `/s/*` covers `/s/temp` and `/s/a/b`,
+    /// but neither `/s` nor `/s/`.
+    Wildcard,
+
+    /// A Toid covers the path equal to it as well as every path below it, where only whole
+    /// path segments are compared. For example, `/s` covers `/s`, `/s/temp` and `/s/a/b`,
+    /// but not `/sensors`.
+    Prefix,
+}
+
 /// A scope encoded using the [Authorization Information Format (AIF) for ACE](https://www.rfc-editor.org/rfc/rfc9237)
 /// as in [`AifEncodedScope`], but only consisting of a single [`AifEncodedScopeElement`]
 /// instead of an array of them.
@@ -538,6 +596,149 @@ pub enum Scope {
     /// let scope = LibdcafEncodedScope::new("/s/temp", AifRestMethod::Get.into());
     /// ```
     LibdcafEncoded(LibdcafEncodedScope),
+
+    /// Scope using an encoding unknown to this crate, e.g., a future or proprietary one.
+    ///
+    /// It's transported as a byte string wrapped in a CBOR tag (see
+    /// [section 3.4 of RFC 8949](https://www.rfc-editor.org/rfc/rfc8949#section-3.4)) whose number
+    /// is the `encoding_id`. The bytes are kept as they are, and can be validated and compared by
+    /// an application-provided [`CustomScopeCodec`] registered in a [`CustomScopeRegistry`].
+    ///
+    /// Note that this is a convention of this crate rather than part of ACE-OAuth:
+    /// [RFC 9200](https://www.rfc-editor.org/rfc/rfc9200#section-5.8.1) only defines text and
+    /// byte strings as scopes, so peers not using this crate won't understand such scopes.
+    /// Since the tag number identifies the encoding, it must not be one whose meaning is already
+    /// defined in the [IANA CBOR Tags registry](https://www.iana.org/assignments/cbor-tags).
+    /// Applications should hence use a tag from its "First Come First Served" range
+    /// (32768 and above) which they have registered for their encoding, or which is at least
+    /// unassigned and only used within a closed deployment.
+    ///
+    /// # Example
+    /// Creating a scope in the (hypothetical) encoding `65000`:
+    /// ```
+    /// # use dcaf::Scope;
+    /// let scope = Scope::Custom { encoding_id: 65000, bytes: vec![0xDC, 0xAF] };
+    /// ```
+    Custom {
+        /// The identifier of the encoding, used as the number of the CBOR tag
+        /// (see above for how to choose it).
+        encoding_id: u64,
+
+        /// The encoded scope.
+        bytes: ByteString,
+    },
+}
+
+/// Structured difference between a requested and a granted [`Scope`], as returned by
+/// [`Scope::diff`].
+///
+/// This allows clients (and Authorization Servers) to find out which parts of a request
+/// were granted when the Authorization Server narrowed the scope down, and to decide whether
+/// to proceed or re-request.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[non_exhaustive]
+pub enum ScopeDiff {
+    /// Difference of two text-encoded scopes, compared element-wise.
+    Text(ScopeElementDiff<String>),
+
+    /// Difference of two AIF-encoded (or libdcaf-encoded) scopes, compared per path.
+    ///
+    /// Each path is contained at most once per list, with the permissions
+    /// of all elements for this path combined.
+    Aif(ScopeElementDiff<AifEncodedScopeElement>),
+
+    /// The scopes have no comparable structure, i.e., they're binary-encoded or use different
+    /// encodings. In this case, we can only tell whether they're equal.
+    Opaque {
+        /// Whether the requested and granted scopes are equal.
+        equal: bool,
+    },
+}
+
+/// Element-wise difference between a requested and a granted scope, see [`ScopeDiff`].
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct ScopeElementDiff<T> {
+    /// Elements which have been requested and granted.
+    pub granted: Vec<T>,
+
+    /// Elements which have been requested, but not granted.
+    pub withheld: Vec<T>,
+
+    /// Elements which have been granted without having been requested.
+    pub unrequested: Vec<T>,
+}
+
+/// Codec for [`Custom`](Scope::Custom) scopes of a single encoding, provided by the application.
+///
+/// Only [`validate`](CustomScopeCodec::validate) has to be implemented. Without further
+/// implementations, a custom scope is only considered to be within another one if both are equal,
+/// just like binary-encoded scopes.
+///
+/// For an example, see the documentation of [`CustomScopeRegistry`].
+pub trait CustomScopeCodec {
+    /// Returns whether the given encoded `scope` is valid in this encoding.
+    fn validate(&self, scope: &[u8]) -> bool;
+
+    /// Returns whether the encoded `scope` grants no more than the `other` one.
+    fn is_within(&self, scope: &[u8], other: &[u8]) -> bool {
+        scope == other
+    }
+
+    /// Narrows the `requested` scope down to the parts contained in the `allowed` one,
+    /// returning `None` if nothing of it is allowed.
+    fn narrow(&self, requested: &[u8], allowed: &[u8]) -> Option<ByteString> {
+        self.is_within(requested, allowed)
+            .then(|| requested.to_vec())
+    }
+}
+
+/// Registry of the [`CustomScopeCodec`]s an application supports, by the encoding identifier
+/// of the [`Custom`](Scope::Custom) scopes they handle.
+///
+/// Scopes using another encoding are handled as usual, e.g., [`is_within`](Self::is_within)
+/// falls back to [`Scope::is_within`]. This way, Authorization Servers can grant custom scopes
+/// using [`IssuancePolicy::evaluate_with_codecs`](crate::token::policy::IssuancePolicy::evaluate_with_codecs)
+/// (requires the `as` feature).
+///
+/// # Example
+/// A codec for scopes consisting of a bitmask of permissions:
+/// ```
+/// # use dcaf::Scope;
+/// # use dcaf::common::cbor_values::ByteString;
+/// # use dcaf::common::scope::{CustomScopeCodec, CustomScopeRegistry};
+/// # use dcaf::error::CustomScopeError;
+/// struct Bitmask;
+///
+/// impl CustomScopeCodec for Bitmask {
+///     fn validate(&self, scope: &[u8]) -> bool {
+///         scope.len() == 1
+///     }
+///
+///     fn is_within(&self, scope: &[u8], other: &[u8]) -> bool {
+///         scope[0] & !other[0] == 0
+///     }
+///
+///     fn narrow(&self, requested: &[u8], allowed: &[u8]) -> Option<ByteString> {
+///         Some(vec![requested[0] & allowed[0]]).filter(|x| x[0] != 0)
+///     }
+/// }
+///
+/// let mut registry = CustomScopeRegistry::new();
+/// registry.register(65000, Bitmask)?;
+/// let requested = Scope::Custom { encoding_id: 65000, bytes: vec![0b0111] };
+/// let allowed = Scope::Custom { encoding_id: 65000, bytes: vec![0b1100] };
+/// assert!(registry.validate(&requested).is_ok());
+/// assert!(!registry.is_within(&requested, &allowed));
+/// assert_eq!(
+///     registry.narrow(&requested, &allowed),
+///     Some(Scope::Custom { encoding_id: 65000, bytes: vec![0b0100] })
+/// );
+/// # Ok::<(), CustomScopeError>(())
+/// ```
+#[derive(Default)]
+pub struct CustomScopeRegistry {
+    /// The registered codecs along with their encoding identifiers.
+    codecs: Vec<(u64, Box<dyn CustomScopeCodec>)>,
 }
 
 /// Contains conversion methods for ACE-OAuth data types.
@@ -551,8 +752,8 @@ mod conversion {
     use serde::{Deserializer, Serializer};
 
     use crate::error::{
-        InvalidAifEncodedScopeError, InvalidBinaryEncodedScopeError, InvalidTextEncodedScopeError,
-        ScopeFromValueError, WrongSourceTypeError,
+        CustomScopeError, InvalidAifEncodedScopeError, InvalidBinaryEncodedScopeError,
+        InvalidTextEncodedScopeError, ScopeFromValueError, WrongSourceTypeError,
     };
 
     use super::*;
@@ -777,6 +978,13 @@ mod conversion {
                 })
         }
 
+        /// Returns whether this element applies to the given `path`, with its
+        /// [`path`](AifEncodedScopeElement::path) being interpreted according to `matching`.
+        #[must_use]
+        pub fn covers(&self, path: &str, matching: ToidMatching) -> bool {
+            matching.covers(&self.path, path)
+        }
+
         /// Turns itself into a [`Value`].
         fn into_cbor_value(self) -> Value {
             Value::Array(vec![
@@ -826,6 +1034,521 @@ mod conversion {
         pub fn to_elements(self) -> Vec<AifEncodedScopeElement> {
             self.0
         }
+
+        /// Returns all permissions this scope grants for the given `path`, i.e., the union of
+        /// the permissions of all elements [covering](AifEncodedScopeElement::covers) the `path`
+        /// when matched according to `matching`.
+        ///
+        /// # Example
+        /// ```
+        /// # use dcaf::AifEncodedScope;
+        /// # use dcaf::common::scope::{AifRestMethod, ToidMatching};
+        /// let scope = AifEncodedScope::from(vec![
+        ///    ("/s/*", AifRestMethod::Get.into()),
+        ///    ("/s/temp", AifRestMethod::Put.into()),
+        /// ]);
+        /// let permissions = scope.permissions_for("/s/temp", ToidMatching::Wildcard);
+        /// assert_eq!(permissions, AifRestMethod::Get | AifRestMethod::Put);
+        /// assert!(scope.allows("/s/humidity", AifRestMethod::Get, ToidMatching::Wildcard));
+        /// assert!(!scope.allows("/s/humidity", AifRestMethod::Get, ToidMatching::Exact));
+        /// ```
+        #[must_use]
+        pub fn permissions_for(&self, path: &str, matching: ToidMatching) -> AifRestMethodSet {
+            self.0
+                .iter()
+                .filter(|element| element.covers(path, matching))
+                .fold(AifRestMethodSet::empty(), |acc, element| {
+                    acc | element.permissions
+                })
+        }
+
+        /// Returns whether this scope allows the given `method` to be used on the given `path`,
+        /// with Toids being matched according to `matching`.
+        #[must_use]
+        pub fn allows(&self, path: &str, method: AifRestMethod, matching: ToidMatching) -> bool {
+            self.permissions_for(path, matching).contains(method)
+        }
+    }
+
+    impl Scope {
+        /// Returns whether this scope grants no more than the given `other` scope, e.g., to check
+        /// that the scope granted by the Authorization Server doesn't exceed the requested one.
+        ///
+        /// - Text-encoded scopes are compared element-wise.
+        /// - AIF-encoded (and libdcaf-encoded) scopes are compared per path: each path of this
+        ///   scope must be contained in `other` with at least the same permissions.
+        ///   Toids are compared exactly.
+        /// - Binary-encoded scopes have no known structure, so they must be equal.
+        ///
+        /// Scopes with different encodings are never considered to be within each other
+        /// (except for AIF- and libdcaf-encoded scopes).
+        ///
+        /// This is the case exactly if the [`diff`](Scope::diff) between both scopes
+        /// [is complete](ScopeDiff::is_complete), i.e., if nothing of this scope is withheld
+        /// by `other`.
+        ///
+        /// # Example
+        /// ```
+        /// # use dcaf::{AifEncodedScope, Scope};
+        /// # use dcaf::common::scope::AifRestMethod;
+        /// # use dcaf::error::InvalidTextEncodedScopeError;
+        /// let requested = Scope::try_from(vec!["rTempC", "rHum"])?;
+        /// assert!(Scope::try_from(vec!["rHum"])?.is_within(&requested));
+        /// assert!(!Scope::try_from(vec!["rHum", "wLed"])?.is_within(&requested));
+        ///
+        /// let requested = Scope::from(AifEncodedScope::from(vec![
+        ///     ("/s/temp", AifRestMethod::Get | AifRestMethod::Put),
+        /// ]));
+        /// let granted = Scope::from(AifEncodedScope::from(vec![("/s/temp", AifRestMethod::Get.into())]));
+        /// assert!(granted.is_within(&requested));
+        /// assert!(!requested.is_within(&granted));
+        /// # Ok::<(), InvalidTextEncodedScopeError>(())
+        /// ```
+        #[must_use]
+        pub fn is_within(&self, other: &Scope) -> bool {
+            self.diff(other).is_complete()
+        }
+
+        /// Returns the structured difference between this requested scope and the given
+        /// `granted` one, e.g., to find out which parts of a request were granted by the
+        /// Authorization Server.
+        ///
+        /// Scopes are compared as described in [`is_within`](Scope::is_within), with the resulting
+        /// [`ScopeDiff`] listing the granted, withheld and unrequested parts.
+        /// If the encodings differ or the scopes are binary-encoded (or custom), an
+        /// [`Opaque`](ScopeDiff::Opaque) difference is returned.
+        ///
+        /// # Example
+        /// ```
+        /// # use dcaf::{AifEncodedScope, Scope};
+        /// # use dcaf::common::scope::{AifEncodedScopeElement, AifRestMethod, ScopeDiff};
+        /// let requested = Scope::from(AifEncodedScope::from(vec![
+        ///     ("/s/temp", AifRestMethod::Get | AifRestMethod::Put),
+        ///     ("/a/led", AifRestMethod::Put.into()),
+        /// ]));
+        /// let granted = Scope::from(AifEncodedScope::from(vec![("/s/temp", AifRestMethod::Get.into())]));
+        /// let diff = requested.diff(&granted);
+        /// assert!(!diff.is_complete());
+        /// assert!(!diff.exceeds_request());
+        /// if let ScopeDiff::Aif(diff) = diff {
+        ///     assert_eq!(diff.granted, vec![AifEncodedScopeElement::new("/s/temp", AifRestMethod::Get)]);
+        ///     assert_eq!(diff.withheld, vec![
+        ///         AifEncodedScopeElement::new("/s/temp", AifRestMethod::Put),
+        ///         AifEncodedScopeElement::new("/a/led", AifRestMethod::Put),
+        ///     ]);
+        /// }
+        /// ```
+        #[must_use]
+        pub fn diff(&self, granted: &Scope) -> ScopeDiff {
+            match (self, granted) {
+                (Scope::TextEncoded(requested), Scope::TextEncoded(granted)) => {
+                    let contains = |scope: &TextEncodedScope, element: &str| {
+                        scope.elements().any(|x| x == element)
+                    };
+                    let (granted_elements, withheld) = requested
+                        .elements()
+                        .map(ToString::to_string)
+                        .partition(|x| contains(granted, x));
+                    ScopeDiff::Text(ScopeElementDiff {
+                        granted: granted_elements,
+                        withheld,
+                        unrequested: granted
+                            .elements()
+                            .filter(|x| !contains(requested, x))
+                            .map(ToString::to_string)
+                            .collect(),
+                    })
+                }
+                (requested, granted) => match (aif_elements(requested), aif_elements(granted)) {
+                    (Some(requested), Some(granted)) => {
+                        let requested = combine_permissions(&requested);
+                        let granted = combine_permissions(&granted);
+                        let permissions_in = |elements: &[(String, AifRestMethodSet)], path| {
+                            elements
+                                .iter()
+                                .find(|(x, _)| x == path)
+                                .map_or(AifRestMethodSet::empty(), |(_, p)| *p)
+                        };
+                        let mut diff = ScopeElementDiff {
+                            granted: Vec::new(),
+                            withheld: Vec::new(),
+                            unrequested: Vec::new(),
+                        };
+                        for (path, permissions) in &requested {
+                            let received = permissions_in(&granted, path);
+                            for (list, methods) in [
+                                (&mut diff.granted, *permissions & received),
+                                (&mut diff.withheld, *permissions & !received),
+                            ] {
+                                if !methods.is_empty() {
+                                    list.push(AifEncodedScopeElement::new(path.clone(), methods));
+                                }
+                            }
+                        }
+                        for (path, permissions) in &granted {
+                            let methods = *permissions & !permissions_in(&requested, path);
+                            if !methods.is_empty() {
+                                diff.unrequested
+                                    .push(AifEncodedScopeElement::new(path.clone(), methods));
+                            }
+                        }
+                        ScopeDiff::Aif(diff)
+                    }
+                    _ => ScopeDiff::Opaque {
+                        equal: requested == granted,
+                    },
+                },
+            }
+        }
+
+        /// Narrows this scope down to the parts contained in the `allowed` scope, or returns
+        /// `None` if no part of it is allowed.
+        ///
+        /// Scopes without comparable structure (see [`ScopeDiff::Opaque`]) are only kept
+        /// if they're equal.
+        #[cfg(any(feature = "as", feature = "client"))]
+        pub(crate) fn narrow(&self, allowed: &Scope) -> Option<Scope> {
+            match self.diff(allowed) {
+                ScopeDiff::Text(diff) if !diff.granted.is_empty() => {
+                    TextEncodedScope::try_from(diff.granted.join(" ").as_str())
+                        .ok()
+                        .map(Scope::from)
+                }
+                ScopeDiff::Aif(diff) if !diff.granted.is_empty() => {
+                    Some(Scope::from(AifEncodedScope::new(diff.granted)))
+                }
+                ScopeDiff::Opaque { equal: true } => Some(self.clone()),
+                _ => None,
+            }
+        }
+
+        /// Returns the canonical form of this scope, which grants exactly the same permissions
+        /// but is encoded deterministically, so that equal scopes are also encoded equally
+        /// (e.g., before they're included in the claims of a token which is then signed).
+        ///
+        /// - The elements of text-encoded scopes are sorted, and duplicates are removed.
+        /// - The elements of AIF-encoded scopes are sorted by path, and elements with the same
+        ///   path are merged into one containing the permissions of all of them.
+        /// - Binary- and libdcaf-encoded scopes are returned unchanged, as the former have no
+        ///   known structure and the latter consist of a single element.
+        ///
+        /// # Example
+        /// ```
+        /// # use dcaf::{AifEncodedScope, Scope};
+        /// # use dcaf::common::scope::AifRestMethod;
+        /// # use dcaf::error::InvalidTextEncodedScopeError;
+        /// let scope = Scope::try_from(vec!["rTempC", "rHum", "rTempC"])?;
+        /// assert_eq!(scope.canonicalize(), Scope::try_from(vec!["rHum", "rTempC"])?);
+        ///
+        /// let scope = Scope::from(AifEncodedScope::from(vec![
+        ///     ("/s/temp", AifRestMethod::Put.into()),
+        ///     ("/a/led", AifRestMethod::Get.into()),
+        ///     ("/s/temp", AifRestMethod::Get.into()),
+        /// ]));
+        /// assert_eq!(scope.canonicalize(), Scope::from(AifEncodedScope::from(vec![
+        ///     ("/a/led", AifRestMethod::Get.into()),
+        ///     ("/s/temp", AifRestMethod::Get | AifRestMethod::Put),
+        /// ])));
+        /// # Ok::<(), InvalidTextEncodedScopeError>(())
+        /// ```
+        #[must_use]
+        pub fn canonicalize(self) -> Scope {
+            match self {
+                Scope::TextEncoded(scope) => {
+                    let mut elements: Vec<&str> = scope.elements().collect();
+                    elements.sort_unstable();
+                    elements.dedup();
+                    Scope::TextEncoded(TextEncodedScope(elements.join(" ")))
+                }
+                Scope::AifEncoded(scope) => {
+                    let mut combined =
+                        combine_permissions(&scope.elements().iter().collect::<Vec<_>>());
+                    combined.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+                    Scope::AifEncoded(AifEncodedScope::new(
+                        combined
+                            .into_iter()
+                            .map(|(path, permissions)| {
+                                AifEncodedScopeElement::new(path, permissions)
+                            })
+                            .collect(),
+                    ))
+                }
+                Scope::BinaryEncoded(_) | Scope::LibdcafEncoded(_) | Scope::Custom { .. } => self,
+            }
+        }
+    }
+
+    /// Encoding identifier and codec of two custom scopes, along with their bytes.
+    type CustomScopePair<'a> = (u64, &'a dyn CustomScopeCodec, &'a [u8], &'a [u8]);
+
+    impl CustomScopeRegistry {
+        /// Creates a new, empty [`CustomScopeRegistry`].
+        #[must_use]
+        pub fn new() -> CustomScopeRegistry {
+            CustomScopeRegistry::default()
+        }
+
+        /// Registers the given `codec` for [`Custom`](Scope::Custom) scopes using the
+        /// encoding identified by `encoding_id`.
+        ///
+        /// # Errors
+        /// If a codec has already been registered for this encoding
+        /// ([`CustomScopeError::DuplicateEncoding`]).
+        pub fn register<C>(&mut self, encoding_id: u64, codec: C) -> Result<(), CustomScopeError>
+        where
+            C: CustomScopeCodec + 'static,
+        {
+            if self.codec(encoding_id).is_some() {
+                return Err(CustomScopeError::DuplicateEncoding(encoding_id));
+            }
+            self.codecs.push((encoding_id, Box::new(codec)));
+            Ok(())
+        }
+
+        /// Returns the codec registered for the encoding identified by `encoding_id`, if any.
+        #[must_use]
+        pub fn codec(&self, encoding_id: u64) -> Option<&dyn CustomScopeCodec> {
+            self.codecs
+                .iter()
+                .find(|(id, _)| *id == encoding_id)
+                .map(|(_, codec)| codec.as_ref())
+        }
+
+        /// Validates the given `scope` using the codec registered for its encoding.
+        /// Scopes which aren't [`Custom`](Scope::Custom) are always valid, as they've already
+        /// been validated upon creation.
+        ///
+        /// # Errors
+        /// - If no codec has been registered for the encoding of the custom scope
+        ///   ([`CustomScopeError::UnknownEncoding`]).
+        /// - If the codec considers the scope invalid ([`CustomScopeError::InvalidScope`]).
+        pub fn validate(&self, scope: &Scope) -> Result<(), CustomScopeError> {
+            if let Scope::Custom { encoding_id, bytes } = scope {
+                let codec = self
+                    .codec(*encoding_id)
+                    .ok_or(CustomScopeError::UnknownEncoding(*encoding_id))?;
+                if !codec.validate(bytes) {
+                    return Err(CustomScopeError::InvalidScope(*encoding_id));
+                }
+            }
+            Ok(())
+        }
+
+        /// Returns whether the given `scope` grants no more than the `other` one, like
+        /// [`Scope::is_within`], but using the registered codec for custom scopes of the same
+        /// encoding.
+        ///
+        /// Custom scopes which aren't valid according to their codec are never within another
+        /// scope.
+        #[must_use]
+        pub fn is_within(&self, scope: &Scope, other: &Scope) -> bool {
+            match self.custom_pair(scope, other) {
+                Some((_, codec, this, other)) => {
+                    codec.validate(this) && codec.validate(other) && codec.is_within(this, other)
+                }
+                None => scope.is_within(other),
+            }
+        }
+
+        /// Narrows the `requested` custom scope down to the parts contained in the `allowed`
+        /// one, using the registered codec for their encoding, returning `None` if nothing
+        /// of it is allowed or it can't be narrowed down.
+        ///
+        /// Scopes which aren't custom scopes of the same, registered encoding can't be narrowed
+        /// down by this registry, so `None` is returned for them as well.
+        #[must_use]
+        pub fn narrow(&self, requested: &Scope, allowed: &Scope) -> Option<Scope> {
+            let (encoding_id, codec, this, other) = self.custom_pair(requested, allowed)?;
+            if !codec.validate(this) || !codec.validate(other) {
+                return None;
+            }
+            codec
+                .narrow(this, other)
+                .filter(|bytes| codec.validate(bytes))
+                .map(|bytes| Scope::Custom { encoding_id, bytes })
+        }
+
+        /// Returns the encoding identifier and the registered codec along with the bytes of
+        /// both scopes if they're custom scopes of the same encoding.
+        fn custom_pair<'a>(
+            &'a self,
+            scope: &'a Scope,
+            other: &'a Scope,
+        ) -> Option<CustomScopePair<'a>> {
+            match (scope, other) {
+                (
+                    Scope::Custom { encoding_id, bytes },
+                    Scope::Custom {
+                        encoding_id: other_id,
+                        bytes: other_bytes,
+                    },
+                ) if encoding_id == other_id => self.codec(*encoding_id).map(|codec| {
+                    (
+                        *encoding_id,
+                        codec,
+                        bytes.as_slice(),
+                        other_bytes.as_slice(),
+                    )
+                }),
+                _ => None,
+            }
+        }
+    }
+
+    impl Debug for CustomScopeRegistry {
+        fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+            f.debug_struct("CustomScopeRegistry")
+                .field(
+                    "encodings",
+                    &self.codecs.iter().map(|(id, _)| id).collect::<Vec<_>>(),
+                )
+                .finish()
+        }
+    }
+
+    impl<T> ScopeElementDiff<T> {
+        /// Returns whether everything that has been requested has also been granted.
+        #[must_use]
+        pub fn is_complete(&self) -> bool {
+            self.withheld.is_empty()
+        }
+    }
+
+    impl ScopeDiff {
+        /// Returns whether everything that has been requested has also been granted.
+        ///
+        /// For [`Opaque`](ScopeDiff::Opaque) differences, this is only the case if both scopes
+        /// are equal.
+        #[must_use]
+        pub fn is_complete(&self) -> bool {
+            match self {
+                ScopeDiff::Text(diff) => diff.is_complete(),
+                ScopeDiff::Aif(diff) => diff.is_complete(),
+                ScopeDiff::Opaque { equal } => *equal,
+            }
+        }
+
+        /// Returns whether anything has been granted which hasn't been requested.
+        ///
+        /// For [`Opaque`](ScopeDiff::Opaque) differences, this is the case unless both scopes
+        /// are equal.
+        #[must_use]
+        pub fn exceeds_request(&self) -> bool {
+            match self {
+                ScopeDiff::Text(diff) => !diff.unrequested.is_empty(),
+                ScopeDiff::Aif(diff) => !diff.unrequested.is_empty(),
+                ScopeDiff::Opaque { equal } => !*equal,
+            }
+        }
+    }
+
+    /// Returns the paths of the given AIF `elements` in order of first occurrence, each
+    /// together with the combined permissions of all elements for it.
+    fn combine_permissions(
+        elements: &[&AifEncodedScopeElement],
+    ) -> Vec<(String, AifRestMethodSet)> {
+        let mut combined: Vec<(String, AifRestMethodSet)> = Vec::new();
+        for element in elements {
+            if let Some((_, permissions)) = combined.iter_mut().find(|(x, _)| x == &element.path) {
+                *permissions |= element.permissions;
+            } else {
+                combined.push((element.path.clone(), element.permissions));
+            }
+        }
+        combined
+    }
+
+    /// Returns the AIF elements of the given `scope` if it's AIF- or libdcaf-encoded.
+    fn aif_elements(scope: &Scope) -> Option<Vec<&AifEncodedScopeElement>> {
+        match scope {
+            Scope::AifEncoded(scope) => Some(scope.elements().iter().collect()),
+            Scope::LibdcafEncoded(scope) => Some(scope.elements()),
+            Scope::TextEncoded(_) | Scope::BinaryEncoded(_) | Scope::Custom { .. } => None,
+        }
+    }
+
+    impl ToidMatching {
+        /// Returns whether the given `toid` covers the given `path` according to this strategy,
+        /// after removing dot segments from both of them (see [`ToidMatching`]).
+        #[must_use]
+        pub fn covers(self, toid: &str, path: &str) -> bool {
+            let toid = remove_dot_segments(toid);
+            let path = remove_dot_segments(path);
+            self.covers_normalized(&toid, &path)
+        }
+
+        /// Returns whether the given `toid` covers the given `path` according to this strategy,
+        /// where both of them must not contain dot segments.
+        pub(crate) fn covers_normalized(self, toid: &str, path: &str) -> bool {
+            if self != ToidMatching::Exact && !toid.starts_with('/') {
+                return false;
+            }
+            match self {
+                ToidMatching::Exact => toid == path,
+                ToidMatching::Wildcard => match toid.strip_suffix('*') {
+                    Some(parent) if parent.ends_with('/') => {
+                        path.len() > parent.len() && path.starts_with(parent)
+                    }
+                    _ => toid == path,
+                },
+                ToidMatching::Prefix => match path.strip_prefix(toid) {
+                    Some(rest) => rest.is_empty() || toid.ends_with('/') || rest.starts_with('/'),
+                    None => false,
+                },
+            }
+        }
+
+        /// Returns whether the given `toid` only [covers](ToidMatching::covers) the path equal
+        /// to it according to this strategy.
+        #[cfg(feature = "rs")]
+        pub(crate) fn is_exact(self, toid: &str) -> bool {
+            match self {
+                ToidMatching::Exact => true,
+                ToidMatching::Wildcard => toid.starts_with('/') && !toid.ends_with("/*"),
+                ToidMatching::Prefix => false,
+            }
+        }
+    }
+
+    /// Removes the dot segments (`.` and `..`) from the given `path`, as described in
+    /// [section 5.2.4 of RFC 3986](https://www.rfc-editor.org/rfc/rfc3986#section-5.2.4).
+    ///
+    /// Paths without dot segments are returned as they are, without allocating.
+    pub(crate) fn remove_dot_segments(path: &str) -> Cow<'_, str> {
+        if !path.split('/').any(|x| x == "." || x == "..") {
+            return Cow::Borrowed(path);
+        }
+        let mut input = path;
+        let mut output = String::with_capacity(path.len());
+        while !input.is_empty() {
+            if let Some(rest) = input
+                .strip_prefix("../")
+                .or_else(|| input.strip_prefix("./"))
+            {
+                input = rest;
+            } else if input.starts_with("/./") {
+                input = &input[2..];
+            } else if input == "/." {
+                input = "/";
+            } else if input.starts_with("/../") || input == "/.." {
+                input = if input == "/.." { "/" } else { &input[3..] };
+                output.truncate(output.rfind('/').unwrap_or(0));
+            } else if input == "." || input == ".." {
+                input = "";
+            } else {
+                let start = usize::from(input.starts_with('/'));
+                let end = input[start..].find('/').map_or(input.len(), |x| x + start);
+                output.push_str(&input[..end]);
+                input = &input[end..];
+            }
+        }
+        Cow::Owned(output)
+    }
+
+    impl Default for ToidMatching {
+        fn default() -> Self {
+            ToidMatching::Exact
+        }
     }
 
     impl Serialize for AifEncodedScopeElement {
@@ -1045,6 +1768,9 @@ mod conversion {
                         .collect(),
                 ),
                 Scope::LibdcafEncoded(lib) => lib.0.into_cbor_value(),
+                Scope::Custom { encoding_id, bytes } => {
+                    Value::Tag(encoding_id, Box::new(Value::Bytes(bytes)))
+                }
             }
         }
     }
@@ -1087,7 +1813,7 @@ mod conversion {
                 )?)),
                 Value::Text(t) => Ok(Scope::TextEncoded(TextEncodedScope::try_from(t.as_str())?)),
                 Value::Array(a) => {
-                    if a.first().filter(|x| x.is_text()).is_some() {
+                    if matches!(a.first(), Some(Value::Text(_))) {
                         // Special handling for libdcaf
                         Ok(Scope::LibdcafEncoded(LibdcafEncodedScope(
                             value_to_aif_element(Value::Array(a))?,
@@ -1100,6 +1826,10 @@ mod conversion {
                             .map_err(ScopeFromValueError::InvalidAifEncodedScope)
                     }
                 }
+                Value::Tag(encoding_id, content) => match *content {
+                    Value::Bytes(bytes) => Ok(Scope::Custom { encoding_id, bytes }),
+                    v => Err(ScopeFromValueError::invalid_type(&v)),
+                },
                 v => Err(ScopeFromValueError::invalid_type(&v)),
             }
         }
@@ -151,6 +151,19 @@ mod text {
         Ok(())
     }
 
+    #[test]
+    fn test_scope_canonicalize() -> Result<(), InvalidTextEncodedScopeError> {
+        let scope = Scope::try_from(vec!["b", "a", "c", "a", "b"])?;
+        let canonical = scope.canonicalize();
+        assert_eq!(canonical, Scope::try_from(vec!["a", "b", "c"])?);
+        assert_eq!(canonical.clone().canonicalize(), canonical);
+        assert_eq!(
+            Scope::try_from(vec!["c", "a", "b"])?.canonicalize(),
+            canonical
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_convert_invalid_value_to_scope() {
         assert!(decode_scope(Value::Bool(false)).is_err());
@@ -165,7 +178,7 @@ mod text {
         let error = TextEncodedScope::try_from(scope).expect_err("expected error");
         assert_eq!(error.actual_type, "AifEncoded");
         assert_eq!(error.expected_type, "TextEncoded");
-        assert_eq!(error.general_type, PhantomData::<Scope>::default());
+        assert_eq!(error.general_type, PhantomData::<Scope>);
     }
 }
 
@@ -183,7 +196,9 @@ mod aif {
         alloc::vec::Vec,
     };
 
-    use crate::common::scope::{AifEncodedScopeElement, AifRestMethod, AifRestMethodSet};
+    use crate::common::scope::{
+        AifEncodedScopeElement, AifRestMethod, AifRestMethodSet, ToidMatching,
+    };
     use crate::error::InvalidAifEncodedScopeError;
     use crate::{AifEncodedScope, LibdcafEncodedScope, Scope};
 
@@ -316,7 +331,204 @@ mod aif {
         let error = AifEncodedScope::try_from(scope).expect_err("expected error");
         assert_eq!(error.actual_type, "LibdcafEncoded");
         assert_eq!(error.expected_type, "AifEncoded");
-        assert_eq!(error.general_type, PhantomData::<Scope>::default());
+        assert_eq!(error.general_type, PhantomData::<Scope>);
+    }
+
+    #[test]
+    fn test_toid_matching() {
+        let cases = [
+            ("/s/temp", "/s/temp", [true, true, true]),
+            ("/s/*", "/s/temp", [false, true, false]),
+            ("/s/*", "/s/a/b", [false, true, false]),
+            ("/s/*", "/s/", [false, false, false]),
+            ("/s/*", "/s", [false, false, false]),
+            ("/s/*", "/s/*", [true, true, true]),
+            ("/s", "/s/temp", [false, false, true]),
+            ("/s/", "/s/temp", [false, false, true]),
+            ("/s", "/sensors", [false, false, false]),
+            ("/s/temp", "/s", [false, false, false]),
+            // Dot segments are removed before comparing.
+            ("/s/temp", "/s/./temp", [true, true, true]),
+            ("/s/temp", "/a/../s/temp", [true, true, true]),
+            ("/s/*", "/s/../admin", [false, false, false]),
+            ("/s", "/s/temp/../..", [false, false, false]),
+            ("/a/../s", "/s/temp", [false, false, true]),
+            // Empty or relative Toids don't cover anything hierarchically.
+            ("", "/s/temp", [false, false, false]),
+            ("", "", [true, false, false]),
+            (".", "/s/temp", [false, false, false]),
+            (".", "", [true, false, false]),
+            ("s", "s", [true, false, false]),
+            ("s/*", "s/temp", [false, false, false]),
+        ];
+        for (toid, path, expected) in cases {
+            let actual = [
+                ToidMatching::Exact,
+                ToidMatching::Wildcard,
+                ToidMatching::Prefix,
+            ]
+            .map(|matching| matching.covers(toid, path));
+            assert_eq!(actual, expected, "{toid} covering {path}");
+        }
+        assert_eq!(ToidMatching::default(), ToidMatching::Exact);
+    }
+
+    #[test]
+    fn test_remove_dot_segments() {
+        use crate::common::scope::conversion::remove_dot_segments;
+        #[cfg(not(feature = "std"))]
+        use alloc::borrow::Cow;
+        #[cfg(feature = "std")]
+        use std::borrow::Cow;
+
+        // Examples from section 5.2.4 of RFC 3986.
+        assert_eq!(remove_dot_segments("/a/b/c/./../../g"), "/a/g");
+        assert_eq!(remove_dot_segments("mid/content=5/../6"), "mid/6");
+        assert_eq!(remove_dot_segments("/s/temp/."), "/s/temp/");
+        assert_eq!(remove_dot_segments("/s/temp/.."), "/s/");
+        assert_eq!(remove_dot_segments("/../.."), "/");
+        assert_eq!(remove_dot_segments("/s/..temp/.x"), "/s/..temp/.x");
+        assert!(matches!(
+            remove_dot_segments("/s/temp"),
+            Cow::Borrowed("/s/temp")
+        ));
+    }
+
+    #[test]
+    fn test_scope_permissions_for() {
+        let scope = AifEncodedScope::from(vec![
+            ("/s", make_bitflags!(AifRestMethod::{Get})),
+            ("/s/temp", make_bitflags!(AifRestMethod::{Put})),
+            ("/a/*", make_bitflags!(AifRestMethod::{Post})),
+        ]);
+        assert_eq!(
+            scope.permissions_for("/s/temp", ToidMatching::Exact),
+            AifRestMethod::Put
+        );
+        assert_eq!(
+            scope.permissions_for("/s/temp", ToidMatching::Prefix),
+            AifRestMethod::Get | AifRestMethod::Put
+        );
+        assert!(scope.allows("/a/led", AifRestMethod::Post, ToidMatching::Wildcard));
+        assert!(!scope.allows("/a/led", AifRestMethod::Get, ToidMatching::Wildcard));
+        assert!(scope.permissions_for("/b", ToidMatching::Prefix).is_empty());
+    }
+
+    #[test]
+    fn test_scope_is_within() {
+        let requested = Scope::from(AifEncodedScope::from(vec![
+            ("/s/temp", make_bitflags!(AifRestMethod::{Get})),
+            ("/s/temp", make_bitflags!(AifRestMethod::{Put})),
+            ("/a/led", make_bitflags!(AifRestMethod::{Get | Post})),
+        ]));
+        let granted = Scope::from(AifEncodedScope::from(vec![(
+            "/s/temp",
+            make_bitflags!(AifRestMethod::{Get | Put}),
+        )]));
+        assert!(granted.is_within(&requested));
+        assert!(!requested.is_within(&granted));
+        let libdcaf = Scope::from(LibdcafEncodedScope::new(
+            "/a/led",
+            make_bitflags!(AifRestMethod::{Post}),
+        ));
+        assert!(libdcaf.is_within(&requested));
+        assert!(!libdcaf.is_within(&granted));
+        let text = Scope::try_from(vec!["/a/led"]).expect("valid text scope");
+        assert!(!text.is_within(&requested));
+        assert!(!requested.is_within(&text));
+        let text_requested = Scope::try_from(vec!["r_a", "r_b"]).expect("valid text scope");
+        assert!(Scope::try_from(vec!["r_b"])
+            .expect("valid text scope")
+            .is_within(&text_requested));
+        assert!(!text_requested.is_within(&Scope::try_from(vec!["r_b"]).expect("valid text scope")));
+        let binary = Scope::try_from(vec![0xDC, 0xAF].as_slice()).expect("valid binary scope");
+        assert!(binary.is_within(&binary));
+        assert!(
+            !binary.is_within(&Scope::try_from(vec![0xDC].as_slice()).expect("valid binary scope"))
+        );
+    }
+
+    #[test]
+    fn test_scope_diff() {
+        use crate::common::scope::{ScopeDiff, ScopeElementDiff};
+
+        let requested = Scope::from(AifEncodedScope::from(vec![
+            ("/s/temp", make_bitflags!(AifRestMethod::{Get})),
+            ("/a/led", make_bitflags!(AifRestMethod::{Get | Post})),
+            ("/s/temp", make_bitflags!(AifRestMethod::{Put})),
+        ]));
+        let granted = Scope::from(AifEncodedScope::from(vec![
+            (
+                "/s/temp",
+                make_bitflags!(AifRestMethod::{Get | Put | Delete}),
+            ),
+            ("/a/led", make_bitflags!(AifRestMethod::{Get})),
+            ("/x", make_bitflags!(AifRestMethod::{Get})),
+        ]));
+        let diff = requested.diff(&granted);
+        assert_eq!(
+            diff,
+            ScopeDiff::Aif(ScopeElementDiff {
+                granted: vec![
+                    AifEncodedScopeElement::new(
+                        "/s/temp",
+                        make_bitflags!(AifRestMethod::{Get | Put})
+                    ),
+                    AifEncodedScopeElement::new("/a/led", AifRestMethod::Get),
+                ],
+                withheld: vec![AifEncodedScopeElement::new("/a/led", AifRestMethod::Post)],
+                unrequested: vec![
+                    AifEncodedScopeElement::new("/s/temp", AifRestMethod::Delete),
+                    AifEncodedScopeElement::new("/x", AifRestMethod::Get),
+                ],
+            })
+        );
+        assert!(!diff.is_complete());
+        assert!(diff.exceeds_request());
+        assert!(requested.diff(&requested).is_complete());
+        assert!(!requested.diff(&requested).exceeds_request());
+
+        let text_requested = Scope::try_from(vec!["r_a", "r_b"]).expect("valid text scope");
+        let text_granted = Scope::try_from(vec!["r_b", "r_c"]).expect("valid text scope");
+        assert_eq!(
+            text_requested.diff(&text_granted),
+            ScopeDiff::Text(ScopeElementDiff {
+                granted: vec!["r_b".to_string()],
+                withheld: vec!["r_a".to_string()],
+                unrequested: vec!["r_c".to_string()],
+            })
+        );
+        assert_eq!(
+            text_requested.diff(&requested),
+            ScopeDiff::Opaque { equal: false }
+        );
+    }
+
+    #[test]
+    fn test_scope_canonicalize() {
+        let scope = Scope::from(AifEncodedScope::from(vec![
+            ("/s/temp", make_bitflags!(AifRestMethod::{Put})),
+            ("/none", AifRestMethodSet::empty()),
+            ("/a/led", make_bitflags!(AifRestMethod::{Get})),
+            ("/s/temp", make_bitflags!(AifRestMethod::{Get | Put})),
+        ]));
+        let canonical = scope.clone().canonicalize();
+        assert_eq!(
+            canonical,
+            Scope::from(AifEncodedScope::from(vec![
+                ("/a/led", make_bitflags!(AifRestMethod::{Get})),
+                ("/none", AifRestMethodSet::empty()),
+                ("/s/temp", make_bitflags!(AifRestMethod::{Get | Put})),
+            ]))
+        );
+        assert!(canonical.is_within(&scope) && scope.is_within(&canonical));
+        assert_eq!(canonical.clone().canonicalize(), canonical);
+
+        let libdcaf = Scope::from(LibdcafEncodedScope::new(
+            "/s/temp",
+            AifRestMethod::Get.into(),
+        ));
+        assert_eq!(libdcaf.clone().canonicalize(), libdcaf);
     }
 }
 
@@ -337,7 +549,7 @@ mod libdcaf {
     fn test_scope_elements_normal() {
         let (restricted, dynamic, all, none) = example_elements();
 
-        for element in vec![restricted, dynamic, all, none] {
+        for element in [restricted, dynamic, all, none] {
             let scope = LibdcafEncodedScope::from_element(element.clone());
             assert_eq!(scope.elements(), vec![&element]);
         }
@@ -389,7 +601,7 @@ mod libdcaf {
         let error = LibdcafEncodedScope::try_from(scope).expect_err("expected error");
         assert_eq!(error.actual_type, "BinaryEncoded");
         assert_eq!(error.expected_type, "LibdcafEncoded");
-        assert_eq!(error.general_type, PhantomData::<Scope>::default());
+        assert_eq!(error.general_type, PhantomData::<Scope>);
         Ok(())
     }
 }
@@ -510,7 +722,7 @@ mod binary {
         let error = BinaryEncodedScope::try_from(scope).expect_err("expected error");
         assert_eq!(error.actual_type, "TextEncoded");
         assert_eq!(error.expected_type, "BinaryEncoded");
-        assert_eq!(error.general_type, PhantomData::<Scope>::default());
+        assert_eq!(error.general_type, PhantomData::<Scope>);
 
         let invalid_values = vec![
             Value::Bool(true),
@@ -529,3 +741,124 @@ mod binary {
         Ok(())
     }
 }
+
+/// Tests for scopes using custom encodings.
+mod custom {
+    #[cfg(not(feature = "std"))]
+    use alloc::{boxed::Box, vec, vec::Vec};
+
+    use ciborium::value::Value;
+
+    use crate::common::cbor_values::ByteString;
+    use crate::common::scope::{CustomScopeCodec, CustomScopeRegistry};
+    use crate::error::{CustomScopeError, ScopeFromValueError};
+    use crate::Scope;
+
+    /// Codec for scopes consisting of a single byte of permission bits.
+    struct Bitmask;
+
+    impl CustomScopeCodec for Bitmask {
+        fn validate(&self, scope: &[u8]) -> bool {
+            scope.len() == 1
+        }
+
+        fn is_within(&self, scope: &[u8], other: &[u8]) -> bool {
+            scope[0] & !other[0] == 0
+        }
+
+        fn narrow(&self, requested: &[u8], allowed: &[u8]) -> Option<ByteString> {
+            Some(vec![requested[0] & allowed[0]]).filter(|x| x[0] != 0)
+        }
+    }
+
+    /// Codec which only implements validation.
+    struct NonEmpty;
+
+    impl CustomScopeCodec for NonEmpty {
+        fn validate(&self, scope: &[u8]) -> bool {
+            !scope.is_empty()
+        }
+    }
+
+    fn custom(encoding_id: u64, bytes: &[u8]) -> Scope {
+        Scope::Custom {
+            encoding_id,
+            bytes: bytes.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_scope_custom_value() -> Result<(), ScopeFromValueError> {
+        let scope = custom(65000, &[0xDC, 0xAF]);
+        let value = Value::from(scope.clone());
+        assert_eq!(
+            value,
+            Value::Tag(65000, Box::new(Value::Bytes(vec![0xDC, 0xAF])))
+        );
+        assert_eq!(Scope::try_from(value)?, scope);
+        assert!(Scope::try_from(Value::Tag(65000, Box::new(Value::Text("x".into())))).is_err());
+
+        let mut serialized = Vec::new();
+        ciborium::ser::into_writer(&scope, &mut serialized).expect("serialization failed");
+        let deserialized: Scope =
+            ciborium::de::from_reader(serialized.as_slice()).expect("deserialization failed");
+        assert_eq!(deserialized, scope);
+        Ok(())
+    }
+
+    #[test]
+    fn test_scope_custom_without_registry() {
+        assert!(custom(1, &[1]).is_within(&custom(1, &[1])));
+        assert!(!custom(1, &[1]).is_within(&custom(1, &[3])));
+        assert!(!custom(1, &[1]).is_within(&custom(2, &[1])));
+        assert!(!custom(1, &[1]).diff(&custom(1, &[3])).is_complete());
+        assert_eq!(custom(1, &[1]).canonicalize(), custom(1, &[1]));
+    }
+
+    #[test]
+    fn test_scope_custom_registry() -> Result<(), CustomScopeError> {
+        let mut registry = CustomScopeRegistry::new();
+        registry.register(1, Bitmask)?;
+        registry.register(2, NonEmpty)?;
+        assert_eq!(
+            registry.register(1, NonEmpty),
+            Err(CustomScopeError::DuplicateEncoding(1))
+        );
+        assert!(registry.codec(2).is_some());
+        assert!(registry.codec(3).is_none());
+
+        registry.validate(&custom(1, &[1]))?;
+        registry.validate(&Scope::try_from(vec![1, 2].as_slice()).expect("invalid scope"))?;
+        assert_eq!(
+            registry.validate(&custom(1, &[1, 2])),
+            Err(CustomScopeError::InvalidScope(1))
+        );
+        assert_eq!(
+            registry.validate(&custom(3, &[1])),
+            Err(CustomScopeError::UnknownEncoding(3))
+        );
+
+        assert!(registry.is_within(&custom(1, &[0b01]), &custom(1, &[0b11])));
+        assert!(!registry.is_within(&custom(1, &[0b11]), &custom(1, &[0b01])));
+        assert!(!registry.is_within(&custom(1, &[0b01, 0]), &custom(1, &[0b11])));
+        assert!(!registry.is_within(&custom(1, &[0b01]), &custom(2, &[0b11])));
+        // Without a custom `is_within`, only equal scopes are within each other.
+        assert!(registry.is_within(&custom(2, &[1]), &custom(2, &[1])));
+        assert!(!registry.is_within(&custom(2, &[1]), &custom(2, &[1, 2])));
+
+        assert_eq!(
+            registry.narrow(&custom(1, &[0b0111]), &custom(1, &[0b1100])),
+            Some(custom(1, &[0b0100]))
+        );
+        assert_eq!(
+            registry.narrow(&custom(1, &[0b0011]), &custom(1, &[0b1100])),
+            None
+        );
+        assert_eq!(
+            registry.narrow(&custom(2, &[1]), &custom(2, &[1])),
+            Some(custom(2, &[1]))
+        );
+        assert_eq!(registry.narrow(&custom(3, &[1]), &custom(3, &[1])), None);
+        Ok(())
+    }
+}
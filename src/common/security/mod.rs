@@ -0,0 +1,544 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+//! Contains the [`SecurityConfig`], which bundles the settings limiting what this crate accepts
+//! from (and how it encodes messages for) its peers.
+//!
+//! By default, this crate is lenient in order to interoperate with as many implementations as
+//! possible. Security-conscious integrators who control both ends can instead use
+//! [`SecurityConfig::strict`], which
+//! - rejects messages which are larger than [`SecurityConfig::STRICT_MAX_MESSAGE_SIZE`],
+//! - rejects messages which aren't
+//!   [deterministically encoded](https://www.rfc-editor.org/rfc/rfc8949#section-4.2.1)
+//!   and encodes outgoing messages deterministically,
+//! - rejects CBOR maps containing duplicate keys,
+//! - only allows the modern AEAD and signature algorithms in [`STRICT_ALGORITHMS`],
+//! - requires access tokens to contain both an `aud` and an `exp` claim, and
+//! - only accepts proof-of-possession keys of [sufficient strength](KeyStrengthPolicy::strict).
+//!
+//! Messages are checked by [`SecurityConfig::decode`], while access tokens have to be received
+//! using [`verify_access_token_with_config`](crate::token::verify_access_token_with_config),
+//! [`verify_access_token_tag_with_config`](crate::token::verify_access_token_tag_with_config), or
+//! [`decrypt_access_token_with_config`](crate::token::decrypt_access_token_with_config)
+//! (requiring the `rs` feature), which also check the claims of the token using
+//! [`SecurityConfig::validation_policy`].
+//!
+//! # Example
+//! ```
+//! # use dcaf::{AccessTokenRequest, ToCborMap};
+//! # use dcaf::error::SecurityConfigError;
+//! use dcaf::common::security::SecurityConfig;
+//!
+//! let config = SecurityConfig::strict();
+//! // Deterministically encoded, since the keys 5 (`audience`) and 24 (`client_id`) are sorted.
+//! let sorted = vec![0xA2, 0x05, 0x61, 0x61, 0x18, 0x18, 0x61, 0x62];
+//! let request: AccessTokenRequest = config.decode(&sorted)?;
+//! assert_eq!(request.client_id, Some("b".to_string()));
+//! assert_eq!(config.encode(request)?, sorted);
+//!
+//! let unsorted = vec![0xA2, 0x18, 0x18, 0x61, 0x62, 0x05, 0x61, 0x61];
+//! assert_eq!(
+//!     config.decode::<AccessTokenRequest>(&unsorted),
+//!     Err(SecurityConfigError::NonDeterministicEncoding)
+//! );
+//! // The default configuration accepts it anyway.
+//! assert!(SecurityConfig::default().decode::<AccessTokenRequest>(&unsorted).is_ok());
+//! # Ok::<(), SecurityConfigError>(())
+//! ```
+
+use ciborium::de::from_reader;
+use ciborium::ser::into_writer;
+use ciborium::value::Value;
+use coset::cwt::ClaimsSet;
+use coset::iana::{KeyType, SymmetricKeyParameter};
+use coset::{CoseKey, Label, ProtectedHeader, RegisteredLabel};
+use serde::{Deserialize, Serialize};
+
+use crate::common::cbor_map::{DuplicateKeyHandling, ToCborMap};
+use crate::common::cbor_values::ProofOfPossessionKey;
+use crate::common::confirmation::get_confirmation;
+use crate::common::cose::{Algorithm, EllipticCurve};
+#[cfg(feature = "rs")]
+use crate::error::ClaimsValidationError;
+use crate::error::{KeyStrengthError, SecurityConfigError};
+#[cfg(feature = "rs")]
+use crate::token::validation::ValidationPolicy;
+use crate::token::CipherCapabilities;
+
+#[cfg(not(feature = "std"))]
+use {alloc::boxed::Box, alloc::string::ToString, alloc::vec::Vec};
+
+#[cfg(test)]
+mod tests;
+
+/// The algorithms allowed by [`SecurityConfig::strict`]: AEADs with 128-bit tags, and
+/// ECDSA as well as EdDSA signatures.
+///
+/// Notably, this excludes MACs (which can't provide non-repudiation and are often truncated)
+/// as well as AES-CCM with 64-bit tags.
+pub const STRICT_ALGORITHMS: [Algorithm; 12] = [
+    Algorithm::A128Gcm,
+    Algorithm::A192Gcm,
+    Algorithm::A256Gcm,
+    Algorithm::ChaCha20Poly1305,
+    Algorithm::AesCcm16_128_128,
+    Algorithm::AesCcm16_128_256,
+    Algorithm::AesCcm64_128_128,
+    Algorithm::AesCcm64_128_256,
+    Algorithm::Es256,
+    Algorithm::Es384,
+    Algorithm::Es512,
+    Algorithm::EdDsa,
+];
+
+/// The elliptic curves allowed by [`KeyStrengthPolicy::strict`], which excludes `secp256k1`.
+pub const STRICT_CURVES: [EllipticCurve; 7] = [
+    EllipticCurve::P256,
+    EllipticCurve::P384,
+    EllipticCurve::P521,
+    EllipticCurve::X25519,
+    EllipticCurve::X448,
+    EllipticCurve::Ed25519,
+    EllipticCurve::Ed448,
+];
+
+/// Minimum requirements for the proof-of-possession keys access tokens are bound to, checked
+/// on the `req_cnf` parameter of access token requests by the Authorization Server (see
+/// `IssuancePolicy::key_strength`) and on the `cnf` claim of access tokens by the Resource Server.
+///
+/// Only keys whose material is contained in the confirmation structure can be checked, i.e.,
+/// plain COSE keys (directly or as part of a CWT Claims Set). Encrypted keys, key IDs and key
+/// thumbprints are always accepted, as their strength can't be determined from them.
+///
+/// The [`Default`] policy doesn't impose any requirements.
+///
+/// # Example
+/// ```
+/// # use coset::CoseKeyBuilder;
+/// # use coset::iana;
+/// # use dcaf::common::cbor_values::ProofOfPossessionKey;
+/// # use dcaf::error::KeyStrengthError;
+/// use dcaf::common::security::KeyStrengthPolicy;
+///
+/// let policy = KeyStrengthPolicy::strict();
+/// let short = CoseKeyBuilder::new_symmetric_key(vec![0xDC; 8]).build();
+/// assert_eq!(
+///     policy.check(&ProofOfPossessionKey::PlainCoseKey(short)),
+///     Err(KeyStrengthError::SymmetricKeyTooShort { length: 8, min_length: 16 })
+/// );
+/// let ec = CoseKeyBuilder::new_ec2_pub_key(iana::EllipticCurve::P_256, vec![1], vec![2]).build();
+/// assert!(policy.check(&ProofOfPossessionKey::PlainCoseKey(ec)).is_ok());
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Hash, Default, Serialize, Deserialize)]
+pub struct KeyStrengthPolicy {
+    /// The minimum length of symmetric keys in bytes.
+    #[serde(default)]
+    pub min_symmetric_key_length: usize,
+
+    /// The curves elliptic curve (EC2 and OKP) keys may use, or `None` if any curve is allowed.
+    #[serde(default)]
+    pub allowed_curves: Option<Vec<EllipticCurve>>,
+}
+
+impl KeyStrengthPolicy {
+    /// The minimum length of symmetric keys in bytes used by [`KeyStrengthPolicy::strict`].
+    pub const STRICT_MIN_SYMMETRIC_KEY_LENGTH: usize = 16;
+
+    /// Returns the strict policy, which requires symmetric keys to be at least
+    /// [`STRICT_MIN_SYMMETRIC_KEY_LENGTH`](KeyStrengthPolicy::STRICT_MIN_SYMMETRIC_KEY_LENGTH)
+    /// bytes long and elliptic curve keys to use one of the [`STRICT_CURVES`].
+    #[must_use]
+    pub fn strict() -> KeyStrengthPolicy {
+        KeyStrengthPolicy {
+            min_symmetric_key_length: KeyStrengthPolicy::STRICT_MIN_SYMMETRIC_KEY_LENGTH,
+            allowed_curves: Some(STRICT_CURVES.to_vec()),
+        }
+    }
+
+    /// Checks the given proof-of-possession `key` against this policy.
+    ///
+    /// # Errors
+    /// - If the key is a symmetric key (or OSCORE input material whose master secret is)
+    ///   shorter than [`min_symmetric_key_length`](KeyStrengthPolicy::min_symmetric_key_length).
+    /// - If the key is an elliptic curve key whose curve isn't contained in
+    ///   [`allowed_curves`](KeyStrengthPolicy::allowed_curves).
+    /// - If the key is a CWT Claims Set whose `cnf` claim can't be parsed.
+    pub fn check(&self, key: &ProofOfPossessionKey) -> Result<(), KeyStrengthError> {
+        match key {
+            ProofOfPossessionKey::PlainCoseKey(key) => self.check_cose_key(key),
+            ProofOfPossessionKey::Ccs(claims) => self.check_claims(claims),
+            #[cfg(feature = "oscore-profile")]
+            ProofOfPossessionKey::OscoreInputMaterial(material) => {
+                self.check_symmetric_key_length(material.master_secret.len())
+            }
+            ProofOfPossessionKey::EncryptedCoseKey(_)
+            | ProofOfPossessionKey::KeyId(_)
+            | ProofOfPossessionKey::KeyThumbprint(_) => Ok(()),
+        }
+    }
+
+    /// Checks the key contained in the `cnf` claim of the given `claims` (if any) against this
+    /// policy.
+    ///
+    /// # Errors
+    /// If the `cnf` claim can't be parsed or the contained key is
+    /// [too weak](KeyStrengthPolicy::check).
+    pub fn check_claims(&self, claims: &ClaimsSet) -> Result<(), KeyStrengthError> {
+        match get_confirmation(claims).map_err(KeyStrengthError::InvalidConfirmation)? {
+            Some(key) => self.check(&key),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks the key contained in the `cnf` claim of the given access token `claims` (if any)
+    /// against this policy, as done by the Resource Server.
+    ///
+    /// # Errors
+    /// If the key is [too weak](KeyStrengthPolicy::check) or the `cnf` claim can't be parsed
+    /// ([`ClaimsValidationError::WeakKey`]).
+    #[cfg(feature = "rs")]
+    pub fn validate(&self, claims: &ClaimsSet) -> Result<(), ClaimsValidationError> {
+        self.check_claims(claims)
+            .map_err(ClaimsValidationError::WeakKey)
+    }
+
+    /// Checks whether a symmetric key of the given `length` (in bytes) is long enough.
+    fn check_symmetric_key_length(&self, length: usize) -> Result<(), KeyStrengthError> {
+        if length < self.min_symmetric_key_length {
+            return Err(KeyStrengthError::SymmetricKeyTooShort {
+                length,
+                min_length: self.min_symmetric_key_length,
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks the given COSE `key` against this policy.
+    fn check_cose_key(&self, key: &CoseKey) -> Result<(), KeyStrengthError> {
+        match key.kty {
+            RegisteredLabel::Assigned(KeyType::Symmetric) => {
+                let k = Label::Int(SymmetricKeyParameter::K as i64);
+                let length = key
+                    .params
+                    .iter()
+                    .find(|(label, _)| label == &k)
+                    .and_then(|(_, value)| value.as_bytes())
+                    .map_or(0, Vec::len);
+                self.check_symmetric_key_length(length)
+            }
+            RegisteredLabel::Assigned(KeyType::EC2 | KeyType::OKP) => {
+                let curve = EllipticCurve::of_key(key);
+                match (&self.allowed_curves, curve) {
+                    (None, _) => Ok(()),
+                    (Some(allowed), Some(curve)) if allowed.contains(&curve) => Ok(()),
+                    _ => Err(KeyStrengthError::DisallowedCurve(curve)),
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Settings limiting what is accepted from peers, and how messages are encoded for them.
+///
+/// The [`Default`] configuration is lenient and matches the behavior of this crate's other
+/// (de)serialization methods, while [`SecurityConfig::strict`] is intended for security-conscious
+/// integrators. Individual settings can be adjusted by modifying the respective fields.
+///
+/// For an example, see the [module-level documentation](self).
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct SecurityConfig {
+    /// The maximum size of a received message in bytes, or `None` if there is no limit.
+    pub max_message_size: Option<usize>,
+
+    /// Whether received messages have to be encoded deterministically, and sent messages are
+    /// encoded deterministically, as specified in
+    /// [section 4.2.1 of RFC 8949](https://www.rfc-editor.org/rfc/rfc8949#section-4.2.1).
+    pub deterministic_encoding: bool,
+
+    /// How keys which appear more than once in a received CBOR map are handled.
+    pub duplicates: DuplicateKeyHandling,
+
+    /// The algorithms tokens and other COSE structures may be protected with,
+    /// or `None` if any algorithm is allowed.
+    pub allowed_algorithms: Option<Vec<Algorithm>>,
+
+    /// Whether access tokens without an `aud` claim, or with a wildcard audience, are rejected.
+    pub require_audience: bool,
+
+    /// Whether access tokens without an `exp` claim are rejected.
+    pub require_expiration: bool,
+
+    /// The requirements for the proof-of-possession keys of access tokens.
+    pub key_strength: KeyStrengthPolicy,
+}
+
+impl Default for SecurityConfig {
+    fn default() -> Self {
+        SecurityConfig {
+            max_message_size: None,
+            deterministic_encoding: false,
+            duplicates: DuplicateKeyHandling::Overwrite,
+            allowed_algorithms: None,
+            require_audience: true,
+            require_expiration: false,
+            key_strength: KeyStrengthPolicy::default(),
+        }
+    }
+}
+
+impl SecurityConfig {
+    /// The maximum message size used by [`SecurityConfig::strict`], in bytes.
+    pub const STRICT_MAX_MESSAGE_SIZE: usize = 4096;
+
+    /// Returns the strict configuration, as described in the
+    /// [module-level documentation](self).
+    #[must_use]
+    pub fn strict() -> SecurityConfig {
+        SecurityConfig {
+            max_message_size: Some(SecurityConfig::STRICT_MAX_MESSAGE_SIZE),
+            deterministic_encoding: true,
+            duplicates: DuplicateKeyHandling::Reject,
+            allowed_algorithms: Some(STRICT_ALGORITHMS.to_vec()),
+            require_audience: true,
+            require_expiration: true,
+            key_strength: KeyStrengthPolicy::strict(),
+        }
+    }
+
+    /// Decodes the given `message` into an instance of `T`, after checking it against
+    /// this configuration.
+    ///
+    /// # Errors
+    /// - If the message is larger than [`max_message_size`](SecurityConfig::max_message_size).
+    /// - If [`deterministic_encoding`](SecurityConfig::deterministic_encoding) is set
+    ///   and the message isn't encoded deterministically.
+    /// - If the message can't be decoded into `T`, which includes maps with duplicate keys
+    ///   if [`duplicates`](SecurityConfig::duplicates) is [`DuplicateKeyHandling::Reject`].
+    pub fn decode<T>(&self, message: &[u8]) -> Result<T, SecurityConfigError>
+    where
+        T: ToCborMap,
+    {
+        self.check_size(message)?;
+        if self.deterministic_encoding && !is_deterministically_encoded(message) {
+            return Err(SecurityConfigError::NonDeterministicEncoding);
+        }
+        T::deserialize_from_with(message, self.duplicates)
+            .map_err(|e| SecurityConfigError::InvalidMessage(e.to_string()))
+    }
+
+    /// Checks that the given received `message` isn't larger than
+    /// [`max_message_size`](SecurityConfig::max_message_size).
+    ///
+    /// # Errors
+    /// If the message is too large ([`SecurityConfigError::TooLarge`]).
+    pub fn check_size(&self, message: &[u8]) -> Result<(), SecurityConfigError> {
+        match self.max_message_size {
+            Some(max_size) if message.len() > max_size => Err(SecurityConfigError::TooLarge {
+                size: message.len(),
+                max_size,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks that the given received `message` is encoded as required by this configuration,
+    /// including any maps nested in it.
+    ///
+    /// Note that CBOR data items embedded in byte strings (such as the payload of a COSE
+    /// structure) aren't covered by this and have to be checked separately.
+    ///
+    /// # Errors
+    /// - If [`deterministic_encoding`](SecurityConfig::deterministic_encoding) is set
+    ///   and the message isn't encoded deterministically
+    ///   ([`SecurityConfigError::NonDeterministicEncoding`]).
+    /// - If [`duplicates`](SecurityConfig::duplicates) is [`DuplicateKeyHandling::Reject`]
+    ///   and a map in the message contains a key more than once
+    ///   ([`SecurityConfigError::InvalidMessage`]).
+    pub fn check_encoding(&self, message: &[u8]) -> Result<(), SecurityConfigError> {
+        if self.deterministic_encoding && !is_deterministically_encoded(message) {
+            return Err(SecurityConfigError::NonDeterministicEncoding);
+        }
+        if self.duplicates == DuplicateKeyHandling::Reject {
+            let value: Value = from_reader(message)
+                .map_err(|e| SecurityConfigError::InvalidMessage(e.to_string()))?;
+            if has_duplicate_keys(&value) {
+                return Err(SecurityConfigError::InvalidMessage(
+                    "map contains a duplicate key".to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Encodes the given `message`, deterministically if
+    /// [`deterministic_encoding`](SecurityConfig::deterministic_encoding) is set.
+    ///
+    /// # Errors
+    /// If the message couldn't be serialized.
+    pub fn encode<T>(&self, message: T) -> Result<Vec<u8>, SecurityConfigError>
+    where
+        T: ToCborMap,
+    {
+        let mut serialized = Vec::new();
+        message
+            .serialize_into(&mut serialized)
+            .map_err(|e| SecurityConfigError::InvalidMessage(e.to_string()))?;
+        if !self.deterministic_encoding {
+            return Ok(serialized);
+        }
+        let value: Value = from_reader(serialized.as_slice())
+            .map_err(|e| SecurityConfigError::InvalidMessage(e.to_string()))?;
+        encode_value(&sort_maps(value)).ok_or_else(|| {
+            SecurityConfigError::InvalidMessage("couldn't re-encode message".to_string())
+        })
+    }
+
+    /// Checks that the algorithm given in the `protected` header of a COSE structure
+    /// (e.g., as returned by [`get_token_headers`](crate::token::get_token_headers))
+    /// is allowed by this configuration.
+    ///
+    /// Note that the algorithm in the unprotected header is ignored, as it could have been
+    /// changed by an attacker.
+    ///
+    /// # Errors
+    /// If [`allowed_algorithms`](SecurityConfig::allowed_algorithms) is set and doesn't contain
+    /// the algorithm of the header (or the header doesn't specify an algorithm).
+    pub fn check_algorithm(&self, protected: &ProtectedHeader) -> Result<(), SecurityConfigError> {
+        let allowed = match &self.allowed_algorithms {
+            None => return Ok(()),
+            Some(allowed) => allowed,
+        };
+        let algorithm = protected
+            .header
+            .alg
+            .clone()
+            .and_then(|x| Algorithm::try_from(x).ok());
+        match algorithm {
+            Some(algorithm) if allowed.contains(&algorithm) => Ok(()),
+            _ => Err(SecurityConfigError::DisallowedAlgorithm(algorithm)),
+        }
+    }
+
+    /// Checks that at least one of the [`allowed_algorithms`](SecurityConfig::allowed_algorithms)
+    /// is supported according to the given `capabilities` of the cipher verifying or decrypting
+    /// tokens, which should be done on startup, so that configuration mismatches don't only
+    /// become apparent once the first token is received.
+    ///
+    /// # Example
+    /// ```
+    /// # use coset::iana::KeyType;
+    /// use dcaf::common::cose::Algorithm;
+    /// use dcaf::common::security::SecurityConfig;
+    /// use dcaf::error::SecurityConfigError;
+    /// use dcaf::token::CipherCapabilities;
+    ///
+    /// let legacy = CipherCapabilities::new(vec![Algorithm::Hmac256_64], vec![KeyType::Symmetric]);
+    /// assert_eq!(
+    ///     SecurityConfig::strict().check_capabilities(&legacy),
+    ///     Err(SecurityConfigError::NoSupportedAlgorithm)
+    /// );
+    /// // Any algorithm is allowed by default.
+    /// assert!(SecurityConfig::default().check_capabilities(&legacy).is_ok());
+    /// ```
+    ///
+    /// # Errors
+    /// If [`allowed_algorithms`](SecurityConfig::allowed_algorithms) is set and none of them is
+    /// supported ([`SecurityConfigError::NoSupportedAlgorithm`]).
+    pub fn check_capabilities(
+        &self,
+        capabilities: &CipherCapabilities,
+    ) -> Result<(), SecurityConfigError> {
+        match &self.allowed_algorithms {
+            Some(allowed) if !allowed.iter().any(|x| capabilities.supports_algorithm(*x)) => {
+                Err(SecurityConfigError::NoSupportedAlgorithm)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the [`ValidationPolicy`] with which the Resource Server should check the claims
+    /// of access tokens under this configuration.
+    #[cfg(feature = "rs")]
+    #[must_use]
+    pub fn validation_policy(&self) -> ValidationPolicy {
+        ValidationPolicy {
+            require_audience: self.require_audience,
+            require_expiration: self.require_expiration,
+            ..ValidationPolicy::default()
+        }
+    }
+}
+
+/// Returns whether the given `message` consists of exactly one CBOR data item which is encoded
+/// deterministically, as specified in
+/// [section 4.2.1 of RFC 8949](https://www.rfc-editor.org/rfc/rfc8949#section-4.2.1):
+/// Integers, lengths and floats use their shortest form, indefinite lengths aren't used,
+/// and the keys of maps are sorted bytewise lexicographically by their encoding.
+///
+/// # Example
+/// ```
+/// use dcaf::common::security::is_deterministically_encoded;
+///
+/// assert!(is_deterministically_encoded(&[0xA2, 0x01, 0x02, 0x20, 0x03]));
+/// // The key -1 (0x20) has to come after 1 (0x01).
+/// assert!(!is_deterministically_encoded(&[0xA2, 0x20, 0x03, 0x01, 0x02]));
+/// // The integer 1 is encoded using an unnecessary additional byte.
+/// assert!(!is_deterministically_encoded(&[0x18, 0x01]));
+/// ```
+#[must_use]
+pub fn is_deterministically_encoded(message: &[u8]) -> bool {
+    from_reader(message)
+        .ok()
+        .and_then(|value: Value| encode_value(&sort_maps(value)))
+        .map_or(false, |encoded| encoded == message)
+}
+
+/// Returns whether any map in the given `value` contains a key more than once.
+fn has_duplicate_keys(value: &Value) -> bool {
+    match value {
+        Value::Map(map) => map.iter().enumerate().any(|(index, (key, value))| {
+            map[..index].iter().any(|(other, _)| other == key)
+                || has_duplicate_keys(key)
+                || has_duplicate_keys(value)
+        }),
+        Value::Array(array) => array.iter().any(has_duplicate_keys),
+        Value::Tag(_, inner) => has_duplicate_keys(inner),
+        _ => false,
+    }
+}
+
+/// Serializes the given `value`, returning `None` if that fails.
+fn encode_value(value: &Value) -> Option<Vec<u8>> {
+    let mut encoded = Vec::new();
+    into_writer(value, &mut encoded).ok()?;
+    Some(encoded)
+}
+
+/// Recursively sorts the entries of all maps in the given `value` by the encoding of their keys.
+fn sort_maps(value: Value) -> Value {
+    match value {
+        Value::Map(map) => {
+            let mut entries: Vec<(Option<Vec<u8>>, Value, Value)> = map
+                .into_iter()
+                .map(|(key, value)| {
+                    let key = sort_maps(key);
+                    (encode_value(&key), key, sort_maps(value))
+                })
+                .collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            Value::Map(entries.into_iter().map(|(_, k, v)| (k, v)).collect())
+        }
+        Value::Array(array) => Value::Array(array.into_iter().map(sort_maps).collect()),
+        Value::Tag(tag, inner) => Value::Tag(tag, Box::new(sort_maps(*inner))),
+        other => other,
+    }
+}
@@ -0,0 +1,210 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+use coset::cwt::ClaimsSetBuilder;
+use coset::iana::{self, CwtClaimName};
+use coset::{CoseKeyBuilder, Header, ProtectedHeader};
+
+use crate::common::confirmation::encode_confirmation;
+#[cfg(feature = "oscore-profile")]
+use crate::endpoints::authz_info::OscoreInputMaterial;
+use crate::AccessTokenRequest;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec;
+
+use super::*;
+
+fn protected(algorithm: Option<Algorithm>) -> ProtectedHeader {
+    ProtectedHeader {
+        original_data: None,
+        header: Header {
+            alg: algorithm.map(Into::into),
+            ..Header::default()
+        },
+    }
+}
+
+#[test]
+fn test_deterministic_encoding() {
+    // Shortest-form integers and lengths, sorted keys.
+    assert!(is_deterministically_encoded(&[0x18, 0x18]));
+    assert!(is_deterministically_encoded(&[
+        0xA2, 0x05, 0x61, 0x61, 0x18, 0x18, 0x61, 0x62
+    ]));
+    assert!(is_deterministically_encoded(&[0x81, 0xA1, 0x01, 0x02]));
+    // Integer which would fit into the initial byte.
+    assert!(!is_deterministically_encoded(&[0x18, 0x17]));
+    // Indefinite-length array.
+    assert!(!is_deterministically_encoded(&[0x9F, 0x01, 0xFF]));
+    // Unsorted keys in a nested map.
+    assert!(!is_deterministically_encoded(&[
+        0x81, 0xA2, 0x02, 0x00, 0x01, 0x00
+    ]));
+    // Trailing data and truncated input.
+    assert!(!is_deterministically_encoded(&[0x01, 0x02]));
+    assert!(!is_deterministically_encoded(&[0xA1, 0x01]));
+}
+
+#[test]
+fn test_strict_decoding() {
+    let strict = SecurityConfig::strict();
+    let duplicate = vec![0xA2, 0x18, 0x18, 0x61, 0x61, 0x18, 0x18, 0x61, 0x62];
+    assert!(matches!(
+        strict.decode::<AccessTokenRequest>(&duplicate),
+        Err(SecurityConfigError::InvalidMessage(_))
+    ));
+    assert!(SecurityConfig::default()
+        .decode::<AccessTokenRequest>(&duplicate)
+        .is_ok());
+
+    let mut large = vec![0x18, 0x18, 0x79, 0x10, 0x00];
+    large.extend(vec![0x61; 4096]);
+    large.insert(0, 0xA1);
+    assert_eq!(
+        strict.decode::<AccessTokenRequest>(&large),
+        Err(SecurityConfigError::TooLarge {
+            size: 4102,
+            max_size: SecurityConfig::STRICT_MAX_MESSAGE_SIZE
+        })
+    );
+}
+
+#[test]
+fn test_strict_encoding() -> Result<(), SecurityConfigError> {
+    let request = AccessTokenRequest::builder()
+        .client_id("b")
+        .audience("a")
+        .client_nonce(vec![0xDC, 0xAF])
+        .build()
+        .expect("invalid request");
+    let encoded = SecurityConfig::strict().encode(request.clone())?;
+    assert!(is_deterministically_encoded(&encoded));
+    assert_eq!(
+        SecurityConfig::strict().decode::<AccessTokenRequest>(&encoded)?,
+        request
+    );
+    Ok(())
+}
+
+#[test]
+fn test_algorithm_allow_list() {
+    let strict = SecurityConfig::strict();
+    assert!(strict
+        .check_algorithm(&protected(Some(Algorithm::EdDsa)))
+        .is_ok());
+    assert!(strict
+        .check_algorithm(&protected(Some(Algorithm::A256Gcm)))
+        .is_ok());
+    for rejected in [Algorithm::Hmac256_64, Algorithm::AesCcm16_64_128] {
+        assert_eq!(
+            strict.check_algorithm(&protected(Some(rejected))),
+            Err(SecurityConfigError::DisallowedAlgorithm(Some(rejected)))
+        );
+    }
+    assert_eq!(
+        strict.check_algorithm(&protected(None)),
+        Err(SecurityConfigError::DisallowedAlgorithm(None))
+    );
+    assert!(SecurityConfig::default()
+        .check_algorithm(&protected(Some(Algorithm::Hmac256_64)))
+        .is_ok());
+}
+
+#[cfg(feature = "rs")]
+#[test]
+fn test_strict_validation_policy() {
+    let policy = SecurityConfig::strict().validation_policy();
+    assert!(policy.require_audience);
+    assert!(policy.require_expiration);
+    assert_eq!(
+        SecurityConfig::default().validation_policy(),
+        ValidationPolicy::default()
+    );
+}
+
+#[test]
+fn test_key_strength() {
+    let policy = KeyStrengthPolicy::strict();
+    let plain = |key| ProofOfPossessionKey::PlainCoseKey(key);
+    assert_eq!(
+        policy.check(&plain(
+            CoseKeyBuilder::new_symmetric_key(vec![0; 15]).build()
+        )),
+        Err(KeyStrengthError::SymmetricKeyTooShort {
+            length: 15,
+            min_length: 16
+        })
+    );
+    assert!(policy
+        .check(&plain(
+            CoseKeyBuilder::new_symmetric_key(vec![0; 16]).build()
+        ))
+        .is_ok());
+    let secp256k1 =
+        CoseKeyBuilder::new_ec2_pub_key(iana::EllipticCurve::Secp256k1, vec![1], vec![2]).build();
+    assert_eq!(
+        policy.check(&plain(secp256k1.clone())),
+        Err(KeyStrengthError::DisallowedCurve(Some(
+            EllipticCurve::Secp256k1
+        )))
+    );
+    assert!(KeyStrengthPolicy::default()
+        .check(&plain(secp256k1.clone()))
+        .is_ok());
+    let curveless = CoseKeyBuilder::new_okp_key().build();
+    assert_eq!(
+        policy.check(&plain(curveless)),
+        Err(KeyStrengthError::DisallowedCurve(None))
+    );
+    // Keys whose material isn't contained can't be checked.
+    assert!(policy
+        .check(&ProofOfPossessionKey::KeyId(vec![0xDC, 0xAF]))
+        .is_ok());
+    // The master secret of OSCORE input material is a symmetric key, too.
+    #[cfg(feature = "oscore-profile")]
+    {
+        let material = OscoreInputMaterial::builder()
+            .id(vec![0x01])
+            .master_secret(vec![0; 8])
+            .build()
+            .expect("invalid OSCORE input material");
+        assert_eq!(
+            policy.check(&ProofOfPossessionKey::OscoreInputMaterial(material)),
+            Err(KeyStrengthError::SymmetricKeyTooShort {
+                length: 8,
+                min_length: 16
+            })
+        );
+    }
+    // Keys contained in a CWT Claims Set are checked as well.
+    let ccs = ClaimsSetBuilder::new()
+        .claim(CwtClaimName::Cnf, encode_confirmation(&plain(secp256k1)))
+        .build();
+    assert_eq!(
+        policy.check(&ProofOfPossessionKey::Ccs(ccs.clone())),
+        Err(KeyStrengthError::DisallowedCurve(Some(
+            EllipticCurve::Secp256k1
+        )))
+    );
+    assert_eq!(
+        policy.check_claims(&ClaimsSetBuilder::new().build()),
+        Ok(())
+    );
+    #[cfg(feature = "rs")]
+    assert_eq!(
+        policy.validate(&ccs),
+        Err(ClaimsValidationError::WeakKey(
+            KeyStrengthError::DisallowedCurve(Some(EllipticCurve::Secp256k1))
+        ))
+    );
+    assert_eq!(SecurityConfig::strict().key_strength, policy);
+}
@@ -40,12 +40,15 @@ use {
 /// - The serialized [`value`] is equal to the bytestring given in [`expected_hex`].
 /// - The deserialized value is equal to [`value`], with [`transform_value`] applied to it.
 ///   If [`transform_value`] is `None`, it will be equal to the identity function.
+/// - Converting [`value`] into a [`Value`] and back results in the same value (again with
+///   [`transform_value`] applied to it).
 ///
 /// # Errors
 /// This will return an error message if any of the following is true:
 /// - Serialization of [`value`] failed.
 /// - Deserializing of the serialized [`value`] failed.
 /// - Deserializing of the serialized [`value`] does not result in a CBOR map.
+/// - Converting [`value`] back from a [`Value`] failed.
 /// - Given [`expected_hex`] is not valid hexadecimal.
 ///
 /// # Panics
@@ -69,6 +72,8 @@ where
         &result,
         &hex::decode(expected_hex).map_err(|x| x.to_string())?
     );
+    let from_value = T::from_ciborium_value(copy.to_ciborium_value()).map_err(|x| x.to_string())?;
+    assert_eq!(copy, transform_value.unwrap_or(identity)(from_value));
     let decoded = T::deserialize_from(result.as_slice()).map_err(|x| x.to_string());
     if let Ok(decoded_value) = decoded {
         let decoded_value = transform_value.unwrap_or(identity)(decoded_value);
@@ -101,7 +106,7 @@ impl CoseCipherCommon for FakeCrypto {
         {
             return Err(CoseCipherError::existing_header_label(&label.0));
         }
-        if protected_header.alg != None {
+        if protected_header.alg.is_some() {
             return Err(CoseCipherError::existing_header("alg"));
         }
         unprotected_header.rest.push((Label::Int(47), Value::Null));
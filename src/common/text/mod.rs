@@ -0,0 +1,235 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+//! Contains [`TextLimits`], which restrict the length and the allowed characters of the
+//! textual fields of messages, such as client identifiers, audiences, error descriptions,
+//! and URIs.
+//!
+//! Constrained parsers shouldn't have to deal with arbitrarily long or malformed text, so the
+//! [default limits](TextLimits::DEFAULT) are enforced whenever a message is built or decoded.
+//! Applications can apply stricter limits to any message implementing [`TextFields`] using
+//! [`TextLimits::check`], which reports violations as an [`InvalidTextError`].
+//!
+//! Apart from their length, the characters of each field are restricted according to its
+//! [`TextKind`]: Client identifiers and error descriptions may only contain the characters
+//! allowed by [RFC 6749, Appendix A](https://www.rfc-editor.org/rfc/rfc6749#appendix-A),
+//! URIs may only contain printable ASCII characters other than spaces, and other identifiers
+//! (like audiences) may contain any characters except control characters.
+//!
+//! # Example
+//! ```
+//! # use dcaf::AccessTokenRequest;
+//! # use dcaf::endpoints::token_req::AccessTokenRequestBuilderError;
+//! use dcaf::common::text::TextLimits;
+//! use dcaf::error::InvalidTextError;
+//!
+//! let request = AccessTokenRequest::builder()
+//!     .client_id("myclient")
+//!     .audience("tempSensor4711")
+//!     .build()?;
+//! let limits = TextLimits {
+//!     max_identifier_length: 8,
+//!     ..TextLimits::default()
+//! };
+//! assert_eq!(
+//!     limits.check(&request),
+//!     Err(InvalidTextError::TooLong { field: "audience", length: 14, max_length: 8 })
+//! );
+//!
+//! // Client identifiers must not contain control characters.
+//! assert!(AccessTokenRequest::builder().client_id("my\nclient").build().is_err());
+//! # Ok::<(), AccessTokenRequestBuilderError>(())
+//! ```
+
+use crate::endpoints::creation_hint::AuthServerRequestCreationHint;
+use crate::error::InvalidTextError;
+use crate::{AccessTokenRequest, ErrorResponse};
+
+#[cfg(not(feature = "std"))]
+use {alloc::vec, alloc::vec::Vec};
+
+#[cfg(test)]
+mod tests;
+
+/// The kind of a textual field, which determines its maximum length and allowed characters.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+pub enum TextKind {
+    /// A client identifier, which may only contain printable ASCII characters
+    /// (`VSCHAR` in [RFC 6749, Appendix A.1](https://www.rfc-editor.org/rfc/rfc6749#appendix-A.1)).
+    ClientId,
+
+    /// Any other identifier, such as an audience or an issuer, which may contain any characters
+    /// except control characters.
+    Identifier,
+
+    /// A human-readable description, which may only contain printable ASCII characters other
+    /// than `"` and `\`
+    /// (`NQSCHAR` in [RFC 6749, Appendix A.7](https://www.rfc-editor.org/rfc/rfc6749#appendix-A.7)).
+    Description,
+
+    /// A URI, which may only contain printable ASCII characters other than spaces.
+    Uri,
+}
+
+impl TextKind {
+    /// Returns whether the given `character` may be used in fields of this kind.
+    #[must_use]
+    pub fn allows(self, character: char) -> bool {
+        match self {
+            TextKind::ClientId => matches!(character, '\x20'..='\x7E'),
+            TextKind::Identifier => !character.is_control(),
+            TextKind::Description => {
+                matches!(character, '\x20'..='\x7E') && character != '"' && character != '\\'
+            }
+            TextKind::Uri => matches!(character, '\x21'..='\x7E'),
+        }
+    }
+}
+
+/// The maximum lengths (in bytes) of textual fields.
+///
+/// For an example, see the [module-level documentation](self).
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+pub struct TextLimits {
+    /// The maximum length of client identifiers and other identifiers.
+    pub max_identifier_length: usize,
+
+    /// The maximum length of human-readable descriptions.
+    pub max_description_length: usize,
+
+    /// The maximum length of URIs.
+    pub max_uri_length: usize,
+}
+
+impl Default for TextLimits {
+    fn default() -> Self {
+        TextLimits::DEFAULT
+    }
+}
+
+impl TextLimits {
+    /// The limits which are enforced whenever a message is built or decoded.
+    ///
+    /// Stricter limits can be applied using [`check`](TextLimits::check), while more lenient
+    /// ones are not possible.
+    pub const DEFAULT: TextLimits = TextLimits {
+        max_identifier_length: 255,
+        max_description_length: 1024,
+        max_uri_length: 2048,
+    };
+
+    /// Returns the maximum length of fields of the given `kind`.
+    #[must_use]
+    pub fn max_length(&self, kind: TextKind) -> usize {
+        match kind {
+            TextKind::ClientId | TextKind::Identifier => self.max_identifier_length,
+            TextKind::Description => self.max_description_length,
+            TextKind::Uri => self.max_uri_length,
+        }
+    }
+
+    /// Checks the `value` of the field named `field` of the given `kind` against these limits.
+    ///
+    /// # Errors
+    /// If the value is too long or contains a character which isn't allowed for its `kind`.
+    pub fn check_field(
+        &self,
+        field: &'static str,
+        value: &str,
+        kind: TextKind,
+    ) -> Result<(), InvalidTextError> {
+        let max_length = self.max_length(kind);
+        if value.len() > max_length {
+            return Err(InvalidTextError::TooLong {
+                field,
+                length: value.len(),
+                max_length,
+            });
+        }
+        match value.chars().find(|x| !kind.allows(*x)) {
+            Some(character) => Err(InvalidTextError::InvalidCharacter { field, character }),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks all textual fields of the given `message` against these limits.
+    ///
+    /// For an example, see the [module-level documentation](self).
+    ///
+    /// # Errors
+    /// If any field is too long or contains a character which isn't allowed for its kind.
+    pub fn check<T>(&self, message: &T) -> Result<(), InvalidTextError>
+    where
+        T: TextFields,
+    {
+        self.check_fields(&message.text_fields())
+    }
+
+    /// Checks the given `fields` (which are skipped if absent) against these limits.
+    pub(crate) fn check_fields(
+        &self,
+        fields: &[(&'static str, Option<&str>, TextKind)],
+    ) -> Result<(), InvalidTextError> {
+        fields
+            .iter()
+            .filter_map(|(field, value, kind)| value.map(|x| (field, x, kind)))
+            .try_for_each(|(field, value, kind)| self.check_field(field, value, *kind))
+    }
+}
+
+/// A message with textual fields which can be checked using [`TextLimits`].
+pub trait TextFields {
+    /// Returns the name, value (if present), and kind of each textual field of this message.
+    fn text_fields(&self) -> Vec<(&'static str, Option<&str>, TextKind)>;
+}
+
+impl TextFields for AccessTokenRequest {
+    fn text_fields(&self) -> Vec<(&'static str, Option<&str>, TextKind)> {
+        vec![
+            ("client_id", self.client_id.as_deref(), TextKind::ClientId),
+            ("audience", self.audience.as_deref(), TextKind::Identifier),
+            ("redirect_uri", self.redirect_uri.as_deref(), TextKind::Uri),
+            ("issuer", self.issuer.as_deref(), TextKind::Identifier),
+            (
+                "subject_token_type",
+                self.subject_token_type.as_deref(),
+                TextKind::Uri,
+            ),
+            (
+                "actor_token_type",
+                self.actor_token_type.as_deref(),
+                TextKind::Uri,
+            ),
+        ]
+    }
+}
+
+impl TextFields for ErrorResponse {
+    fn text_fields(&self) -> Vec<(&'static str, Option<&str>, TextKind)> {
+        vec![
+            (
+                "error_description",
+                self.description.as_deref(),
+                TextKind::Description,
+            ),
+            ("error_uri", self.uri.as_deref(), TextKind::Uri),
+        ]
+    }
+}
+
+impl TextFields for AuthServerRequestCreationHint {
+    fn text_fields(&self) -> Vec<(&'static str, Option<&str>, TextKind)> {
+        vec![
+            ("AS", self.auth_server.as_deref(), TextKind::Uri),
+            ("audience", self.audience.as_deref(), TextKind::Identifier),
+        ]
+    }
+}
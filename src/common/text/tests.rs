@@ -0,0 +1,93 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+#[cfg(not(feature = "std"))]
+use {alloc::string::String, alloc::string::ToString, alloc::vec};
+
+use crate::{ErrorCode, ToCborMap};
+
+use super::*;
+
+#[test]
+fn test_text_kinds() {
+    assert!(TextKind::ClientId.allows(' ') && TextKind::ClientId.allows('"'));
+    assert!(!TextKind::ClientId.allows('\n') && !TextKind::ClientId.allows('ä'));
+    assert!(TextKind::Identifier.allows('ä') && !TextKind::Identifier.allows('\u{7F}'));
+    assert!(TextKind::Description.allows(' ') && !TextKind::Description.allows('"'));
+    assert!(!TextKind::Description.allows('\\'));
+    assert!(TextKind::Uri.allows('~') && !TextKind::Uri.allows(' '));
+}
+
+#[test]
+fn test_text_limits() -> Result<(), String> {
+    let response = ErrorResponse::builder()
+        .error(ErrorCode::InvalidRequest)
+        .description("Missing audience.")
+        .uri("https://as.example.com/errors/1")
+        .build()
+        .map_err(|x| x.to_string())?;
+    assert_eq!(TextLimits::default().check(&response), Ok(()));
+    let limits = TextLimits {
+        max_uri_length: 16,
+        ..TextLimits::DEFAULT
+    };
+    assert_eq!(
+        limits.check(&response),
+        Err(InvalidTextError::TooLong {
+            field: "error_uri",
+            length: 31,
+            max_length: 16
+        })
+    );
+    assert_eq!(
+        TextLimits::DEFAULT.check_field("audience", "tempSensor\t4711", TextKind::Identifier),
+        Err(InvalidTextError::InvalidCharacter {
+            field: "audience",
+            character: '\t'
+        })
+    );
+    Ok(())
+}
+
+#[test]
+fn test_text_limits_enforced() {
+    let oversized = "x".repeat(TextLimits::DEFAULT.max_identifier_length + 1);
+    assert!(AccessTokenRequest::builder()
+        .audience(oversized.as_str())
+        .build()
+        .is_err());
+    assert!(AccessTokenRequest::builder()
+        .audience(&oversized[1..])
+        .build()
+        .is_ok());
+    assert!(AuthServerRequestCreationHint::builder()
+        .auth_server("coaps://as.example.com/token with space")
+        .build()
+        .is_err());
+    assert!(ErrorResponse::builder()
+        .error(ErrorCode::InvalidRequest)
+        .description("Say \"hello\".")
+        .build()
+        .is_err());
+    // Oversized fields are also rejected when decoding.
+    let encoded_audience = |length: usize| {
+        // A map containing only an audience (key 5) consisting of `length` characters.
+        let mut encoded = vec![0xA1, 0x05, 0x79];
+        encoded.extend_from_slice(&u16::try_from(length).expect("too long").to_be_bytes());
+        encoded.extend_from_slice("x".repeat(length).as_bytes());
+        encoded
+    };
+    let max_length = TextLimits::DEFAULT.max_identifier_length;
+    assert!(AccessTokenRequest::deserialize_from(encoded_audience(max_length).as_slice()).is_ok());
+    assert!(
+        AccessTokenRequest::deserialize_from(encoded_audience(max_length + 1).as_slice()).is_err()
+    );
+}
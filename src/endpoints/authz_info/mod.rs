@@ -0,0 +1,556 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+//! Contains the data models for the messages exchanged with the `authz-info` endpoint of the
+//! Resource Server when using the OSCORE profile, as described in
+//! [section 4.1 of RFC 9203](https://www.rfc-editor.org/rfc/rfc9203#section-4.1).
+//!
+//! The client posts its access token along with a nonce and its chosen Recipient ID as an
+//! [`OscoreAuthzInfoRequest`], and the Resource Server answers with its own nonce and Recipient
+//! ID as an [`OscoreAuthzInfoResponse`]. Both nonces and Recipient IDs are then used to derive
+//! the OSCORE security context.
+//!
+//! # Example
+//! ```
+//! # use std::error::Error;
+//! # use dcaf::ToCborMap;
+//! use dcaf::endpoints::authz_info::{OscoreAuthzInfoRequest, OscoreAuthzInfoResponse};
+//!
+//! # #[cfg(feature = "std")] {
+//! let request = OscoreAuthzInfoRequest::builder()
+//!     .access_token(vec![0xDC, 0xAF])
+//!     .nonce1(vec![0x01, 0x8a, 0x27, 0x8f, 0x7f, 0xaa, 0xb5, 0x5a])
+//!     .ace_client_recipientid(vec![0x1f])
+//!     .build()?;
+//! let mut serialized = Vec::new();
+//! request.clone().serialize_into(&mut serialized)?;
+//! assert_eq!(OscoreAuthzInfoRequest::deserialize_from(serialized.as_slice())?, request);
+//!
+//! let response = OscoreAuthzInfoResponse::builder()
+//!     .nonce2(vec![0x25, 0xa8, 0x99, 0x1c, 0xd7, 0x00, 0xac, 0x01])
+//!     .ace_server_recipientid(vec![0x00])
+//!     .build()?;
+//! // Recipient IDs longer than the maximum allowed length are rejected.
+//! assert!(OscoreAuthzInfoResponse::builder()
+//!     .nonce2(vec![0x25])
+//!     .ace_server_recipientid(vec![0x00; 8])
+//!     .build()
+//!     .is_err());
+//! # }
+//! # Ok::<(), Box<dyn Error>>(())
+//! ```
+//!
+//! # OSCORE Input Material
+//! The keying material from which the security context is derived is transported to both
+//! parties (in the `cnf` claim of the token and the `cnf` parameter of the access token response)
+//! as an [`OscoreInputMaterial`], using the `osc` confirmation method represented by
+//! [`ProofOfPossessionKey::OscoreInputMaterial`](crate::common::cbor_values::ProofOfPossessionKey::OscoreInputMaterial).
+//! Together with the exchanged nonces and Recipient IDs,
+//! [`OscoreInputMaterial::context_parameters`] then yields the parameters for deriving the OSCORE
+//! security context of either party.
+//!
+//! A Resource Server which accepts tokens from multiple Authorization Servers can't rely on the
+//! [`id`](OscoreInputMaterial::id) of the input material alone to tell clients apart, since
+//! each Authorization Server chooses these identifiers independently. The Authorization
+//! Servers should hence set a distinct [`context_id`](OscoreInputMaterial::context_id), which is
+//! used as the OSCORE ID Context, and the Resource Server should store the tokens it receives
+//! under the [`identity`](OscoreInputMaterial::identity) of their input material, which
+//! includes the ID Context.
+//!
+//! ## Example
+//! ```
+//! # use dcaf::endpoints::authz_info::{OscoreAuthzInfoRequest, OscoreAuthzInfoResponse};
+//! use dcaf::common::cose::Algorithm;
+//! use dcaf::endpoints::authz_info::{OscoreInputMaterial, OscoreRole};
+//!
+//! # #[cfg(feature = "std")] {
+//! let material = OscoreInputMaterial::builder()
+//!     .id(vec![0x01])
+//!     .master_secret(vec![0xDC; 16])
+//!     .context_id(vec![0xA5])
+//!     .build()?;
+//! # let request = OscoreAuthzInfoRequest::builder()
+//! #     .access_token(vec![0xDC, 0xAF])
+//! #     .nonce1(vec![0x01, 0x8a, 0x27, 0x8f, 0x7f, 0xaa, 0xb5, 0x5a])
+//! #     .ace_client_recipientid(vec![0x1f])
+//! #     .build()?;
+//! # let response = OscoreAuthzInfoResponse::builder()
+//! #     .nonce2(vec![0x25, 0xa8, 0x99, 0x1c, 0xd7, 0x00, 0xac, 0x01])
+//! #     .ace_server_recipientid(vec![0x00])
+//! #     .build()?;
+//! let parameters = material.context_parameters(&request, &response, OscoreRole::Client);
+//! assert_eq!(parameters.id_context, Some(vec![0xA5]));
+//! assert_eq!(parameters.sender_id, vec![0x00]);
+//! assert_eq!(parameters.alg, Algorithm::AesCcm16_64_128);
+//! assert_eq!(material.identity(), "a5:01");
+//! # }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use core::fmt::Write;
+
+use crate::common::cbor_values::ByteString;
+use crate::common::cose::Algorithm;
+
+#[cfg(not(feature = "std"))]
+use {alloc::format, alloc::string::String, alloc::vec::Vec};
+
+#[cfg(test)]
+mod tests;
+
+/// The maximum length of an OSCORE Recipient ID in bytes.
+///
+/// As described in [section 3.3 of RFC 8613](https://www.rfc-editor.org/rfc/rfc8613#section-3.3),
+/// Recipient IDs may be at most the AEAD nonce length minus 6 bytes long.
+/// This is the value for the default AEAD algorithm AES-CCM-16-64-128, whose nonce is 13 bytes long.
+pub const MAX_RECIPIENT_ID_LENGTH: usize = 7;
+
+/// Request sent by the client to the `authz-info` endpoint of the Resource Server when using the
+/// OSCORE profile, as described in
+/// [section 4.1.1 of RFC 9203](https://www.rfc-editor.org/rfc/rfc9203#section-4.1.1).
+///
+/// Use the [`OscoreAuthzInfoRequestBuilder`] (which you can access using the
+/// [`builder()`](OscoreAuthzInfoRequest::builder) method) to create an instance of this struct.
+/// Note that all fields are required, and that [`nonce1`](OscoreAuthzInfoRequest::nonce1) must
+/// not be empty, while [`ace_client_recipientid`](OscoreAuthzInfoRequest::ace_client_recipientid)
+/// must not be longer than [`MAX_RECIPIENT_ID_LENGTH`].
+///
+/// For an example, see the [module-level documentation](self).
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Builder)]
+#[builder(
+    no_std,
+    setter(into),
+    derive(Debug, PartialEq, Eq),
+    build_fn(validate = "Self::validate")
+)]
+pub struct OscoreAuthzInfoRequest {
+    /// The access token the client received from the Authorization Server.
+    pub access_token: ByteString,
+
+    /// A random nonce generated by the client, which is used to derive the OSCORE
+    /// security context.
+    ///
+    /// It is recommended to use 8 bytes.
+    pub nonce1: ByteString,
+
+    /// The OSCORE Recipient ID chosen by the client.
+    pub ace_client_recipientid: ByteString,
+}
+
+/// Response sent by the Resource Server to an [`OscoreAuthzInfoRequest`] when using the
+/// OSCORE profile, as described in
+/// [section 4.1.2 of RFC 9203](https://www.rfc-editor.org/rfc/rfc9203#section-4.1.2).
+///
+/// Use the [`OscoreAuthzInfoResponseBuilder`] (which you can access using the
+/// [`builder()`](OscoreAuthzInfoResponse::builder) method) to create an instance of this struct.
+/// Note that all fields are required, and that [`nonce2`](OscoreAuthzInfoResponse::nonce2) must
+/// not be empty, while [`ace_server_recipientid`](OscoreAuthzInfoResponse::ace_server_recipientid)
+/// must not be longer than [`MAX_RECIPIENT_ID_LENGTH`].
+///
+/// For an example, see the [module-level documentation](self).
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Builder)]
+#[builder(
+    no_std,
+    setter(into),
+    derive(Debug, PartialEq, Eq),
+    build_fn(validate = "Self::validate")
+)]
+pub struct OscoreAuthzInfoResponse {
+    /// A random nonce generated by the Resource Server, which is used to derive the OSCORE
+    /// security context.
+    ///
+    /// It is recommended to use 8 bytes.
+    pub nonce2: ByteString,
+
+    /// The OSCORE Recipient ID chosen by the Resource Server, which must differ from the
+    /// Recipient ID chosen by the client.
+    pub ace_server_recipientid: ByteString,
+}
+
+/// The OSCORE input material, which is used to derive the OSCORE security context between
+/// client and Resource Server, as described in
+/// [section 3.2.1 of RFC 9203](https://www.rfc-editor.org/rfc/rfc9203#section-3.2.1).
+///
+/// Use the [`OscoreInputMaterialBuilder`] (which you can access using the
+/// [`builder()`](OscoreInputMaterial::builder) method) to create an instance of this struct.
+/// Note that [`id`](OscoreInputMaterial::id) and
+/// [`master_secret`](OscoreInputMaterial::master_secret) are required and must not be empty.
+///
+/// For an example, see the [module-level documentation](self).
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Builder)]
+#[builder(
+    no_std,
+    setter(into, strip_option),
+    derive(Debug, PartialEq, Eq),
+    build_fn(validate = "Self::validate")
+)]
+pub struct OscoreInputMaterial {
+    /// Identifier of this input material, chosen by the Authorization Server.
+    ///
+    /// Note that this is only unique per Authorization Server and hence not sufficient to
+    /// identify a client at a Resource Server which accepts tokens from multiple ones---use
+    /// [`identity`](OscoreInputMaterial::identity) for that purpose instead.
+    pub id: ByteString,
+
+    /// The OSCORE version, which defaults to 1 if absent.
+    #[builder(default)]
+    pub version: Option<u32>,
+
+    /// The OSCORE Master Secret.
+    pub master_secret: ByteString,
+
+    /// The HKDF algorithm used to derive the security context, which defaults to
+    /// [`Algorithm::DirectHkdfSha256`] if absent.
+    #[builder(default)]
+    pub hkdf: Option<Algorithm>,
+
+    /// The AEAD algorithm used by the security context, which defaults to
+    /// [`Algorithm::AesCcm16_64_128`] if absent.
+    #[builder(default)]
+    pub alg: Option<Algorithm>,
+
+    /// The OSCORE Master Salt, which defaults to an empty byte string if absent.
+    #[builder(default)]
+    pub salt: Option<ByteString>,
+
+    /// The OSCORE ID Context, which keeps security contexts apart whose other identifiers
+    /// (e.g., Recipient IDs chosen for clients of different Authorization Servers) collide.
+    #[builder(default)]
+    pub context_id: Option<ByteString>,
+}
+
+/// The role of a party in an OSCORE security context established using the OSCORE profile.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum OscoreRole {
+    /// The client, which sent the [`OscoreAuthzInfoRequest`].
+    Client,
+
+    /// The Resource Server, which sent the [`OscoreAuthzInfoResponse`].
+    ResourceServer,
+}
+
+/// The parameters from which a party derives its OSCORE security context, as described in
+/// [section 4.3 of RFC 9203](https://www.rfc-editor.org/rfc/rfc9203#section-4.3).
+///
+/// Created using [`OscoreInputMaterial::context_parameters`]. The actual key derivation is out
+/// of scope for this crate and has to be done by the OSCORE implementation.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct OscoreContextParameters {
+    /// The OSCORE Master Secret.
+    pub master_secret: ByteString,
+
+    /// The OSCORE Master Salt, which combines the salt of the input material with both nonces.
+    pub master_salt: ByteString,
+
+    /// The OSCORE ID Context, if any.
+    pub id_context: Option<ByteString>,
+
+    /// The Sender ID of this party, which is the Recipient ID chosen by the other party.
+    pub sender_id: ByteString,
+
+    /// The Recipient ID of this party, which it has chosen itself.
+    pub recipient_id: ByteString,
+
+    /// The HKDF algorithm.
+    pub hkdf: Algorithm,
+
+    /// The AEAD algorithm.
+    pub alg: Algorithm,
+}
+
+impl OscoreInputMaterial {
+    /// Returns the parameters for deriving the OSCORE security context of the given `role`
+    /// after the given `request` and `response` have been exchanged at the `authz-info` endpoint.
+    ///
+    /// The Master Salt is the concatenation of the CBOR encodings of the
+    /// [`salt`](OscoreInputMaterial::salt) (or an empty byte string if absent),
+    /// [`nonce1`](OscoreAuthzInfoRequest::nonce1), and
+    /// [`nonce2`](OscoreAuthzInfoResponse::nonce2), while the ID Context is the
+    /// [`context_id`](OscoreInputMaterial::context_id).
+    ///
+    /// For an example, see the [module-level documentation](self).
+    ///
+    /// # Panics
+    /// If the Master Salt couldn't be encoded, which would imply a programming mistake on account
+    /// of `dcaf-rs`, not its users.
+    #[must_use]
+    pub fn context_parameters(
+        &self,
+        request: &OscoreAuthzInfoRequest,
+        response: &OscoreAuthzInfoResponse,
+        role: OscoreRole,
+    ) -> OscoreContextParameters {
+        let mut master_salt = Vec::new();
+        for part in [
+            self.salt.as_deref().unwrap_or_default(),
+            &request.nonce1,
+            &response.nonce2,
+        ] {
+            ciborium::ser::into_writer(
+                &ciborium::value::Value::Bytes(part.to_vec()),
+                &mut master_salt,
+            )
+            .expect("encoding byte strings into a vector can't fail");
+        }
+        let (sender_id, recipient_id) = match role {
+            OscoreRole::Client => (
+                &response.ace_server_recipientid,
+                &request.ace_client_recipientid,
+            ),
+            OscoreRole::ResourceServer => (
+                &request.ace_client_recipientid,
+                &response.ace_server_recipientid,
+            ),
+        };
+        OscoreContextParameters {
+            master_secret: self.master_secret.clone(),
+            master_salt,
+            id_context: self.context_id.clone(),
+            sender_id: sender_id.clone(),
+            recipient_id: recipient_id.clone(),
+            hkdf: self.hkdf.unwrap_or(Algorithm::DirectHkdfSha256),
+            alg: self.alg.unwrap_or(Algorithm::AesCcm16_64_128),
+        }
+    }
+
+    /// Returns the identity under which a Resource Server should store tokens bound to this
+    /// input material, e.g., when passing them to a token store keyed by client.
+    ///
+    /// The identity consists of the hex-encoded [`context_id`](OscoreInputMaterial::context_id)
+    /// (empty if absent) and [`id`](OscoreInputMaterial::id), separated by a colon, so that
+    /// input material from different Authorization Servers with colliding identifiers is kept
+    /// apart as long as they use distinct ID Contexts.
+    ///
+    /// For an example, see the [module-level documentation](self).
+    #[must_use]
+    pub fn identity(&self) -> String {
+        let mut identity = String::new();
+        for byte in self.context_id.iter().flatten() {
+            // Writing into a String can't fail.
+            let _ = write!(identity, "{byte:02x}");
+        }
+        identity.push(':');
+        for byte in &self.id {
+            let _ = write!(identity, "{byte:02x}");
+        }
+        identity
+    }
+}
+
+/// Checks that the given `nonce` is not empty and that the given `recipient_id` is not longer
+/// than [`MAX_RECIPIENT_ID_LENGTH`], returning an error message otherwise.
+fn validate_oscore_input(
+    nonce: Option<&ByteString>,
+    nonce_name: &str,
+    recipient_id: Option<&ByteString>,
+    recipient_id_name: &str,
+) -> Result<(), String> {
+    if matches!(nonce, Some(x) if x.is_empty()) {
+        return Err(format!("{nonce_name} must not be empty"));
+    }
+    if matches!(recipient_id, Some(x) if x.len() > MAX_RECIPIENT_ID_LENGTH) {
+        return Err(format!(
+            "{recipient_id_name} must not be longer than {MAX_RECIPIENT_ID_LENGTH} bytes"
+        ));
+    }
+    Ok(())
+}
+
+#[allow(clippy::unused_self, clippy::unnecessary_wraps)]
+mod builder {
+    use super::*;
+
+    impl OscoreAuthzInfoRequest {
+        /// Initializes and returns a new [`OscoreAuthzInfoRequestBuilder`].
+        #[must_use]
+        pub fn builder() -> OscoreAuthzInfoRequestBuilder {
+            OscoreAuthzInfoRequestBuilder::default()
+        }
+    }
+
+    impl OscoreAuthzInfoRequestBuilder {
+        pub(crate) fn validate(&self) -> Result<(), OscoreAuthzInfoRequestBuilderError> {
+            validate_oscore_input(
+                self.nonce1.as_ref(),
+                "nonce1",
+                self.ace_client_recipientid.as_ref(),
+                "ace_client_recipientid",
+            )
+            .map_err(OscoreAuthzInfoRequestBuilderError::from)
+        }
+    }
+
+    impl OscoreAuthzInfoResponse {
+        /// Initializes and returns a new [`OscoreAuthzInfoResponseBuilder`].
+        #[must_use]
+        pub fn builder() -> OscoreAuthzInfoResponseBuilder {
+            OscoreAuthzInfoResponseBuilder::default()
+        }
+    }
+
+    impl OscoreAuthzInfoResponseBuilder {
+        pub(crate) fn validate(&self) -> Result<(), OscoreAuthzInfoResponseBuilderError> {
+            validate_oscore_input(
+                self.nonce2.as_ref(),
+                "nonce2",
+                self.ace_server_recipientid.as_ref(),
+                "ace_server_recipientid",
+            )
+            .map_err(OscoreAuthzInfoResponseBuilderError::from)
+        }
+    }
+
+    impl OscoreInputMaterial {
+        /// Initializes and returns a new [`OscoreInputMaterialBuilder`].
+        #[must_use]
+        pub fn builder() -> OscoreInputMaterialBuilder {
+            OscoreInputMaterialBuilder::default()
+        }
+    }
+
+    impl OscoreInputMaterialBuilder {
+        pub(crate) fn validate(&self) -> Result<(), OscoreInputMaterialBuilderError> {
+            if matches!(&self.id, Some(x) if x.is_empty()) {
+                Err(OscoreInputMaterialBuilderError::from(String::from(
+                    "id must not be empty",
+                )))
+            } else if matches!(&self.master_secret, Some(x) if x.is_empty()) {
+                Err(OscoreInputMaterialBuilderError::from(String::from(
+                    "master_secret must not be empty",
+                )))
+            } else {
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Contains conversion methods for ACE-OAuth data types.
+///
+/// One part of this is converting enum types from and to their CBOR abbreviations in
+/// [`cbor_abbreviations`](crate::constants::cbor_abbreviations),
+/// another part is implementing the [`ToCborMap`](crate::ToCborMap) type for the
+/// models which are represented as CBOR maps.
+mod conversion {
+    #[cfg(not(feature = "std"))]
+    use alloc::boxed::Box;
+
+    use ciborium::value::Value;
+    use erased_serde::Serialize as ErasedSerialize;
+
+    use crate::common::cbor_map::{cbor_map_vec, decode_number, ToCborMap};
+    use crate::common::constants::cbor_abbreviations::{
+        authz_info, oscore_input_material, CborKey,
+    };
+    use crate::error::TryFromCborMapError;
+
+    use super::*;
+
+    impl ToCborMap for OscoreAuthzInfoRequest {
+        fn to_cbor_map(&self) -> Vec<(i128, Option<Box<dyn ErasedSerialize + '_>>)> {
+            cbor_map_vec! {
+                authz_info::ACCESS_TOKEN => Some(Value::Bytes(self.access_token.clone())),
+                authz_info::NONCE1 => Some(Value::Bytes(self.nonce1.clone())),
+                authz_info::ACE_CLIENT_RECIPIENTID => Some(Value::Bytes(self.ace_client_recipientid.clone()))
+            }
+        }
+
+        fn try_from_cbor_map(map: Vec<(i128, Value)>) -> Result<Self, TryFromCborMapError>
+        where
+            Self: Sized + ToCborMap,
+        {
+            let mut request = OscoreAuthzInfoRequest::builder();
+            for entry in map {
+                match (CborKey::try_from(entry.0)?, entry.1) {
+                    (authz_info::ACCESS_TOKEN, Value::Bytes(x)) => request.access_token(x),
+                    (authz_info::NONCE1, Value::Bytes(x)) => request.nonce1(x),
+                    (authz_info::ACE_CLIENT_RECIPIENTID, Value::Bytes(x)) => {
+                        request.ace_client_recipientid(x)
+                    }
+                    (key, _) => return Err(TryFromCborMapError::unknown_field(key)),
+                };
+            }
+            request
+                .build()
+                .map_err(|x| TryFromCborMapError::build_failed("OscoreAuthzInfoRequest", x))
+        }
+    }
+
+    impl ToCborMap for OscoreAuthzInfoResponse {
+        fn to_cbor_map(&self) -> Vec<(i128, Option<Box<dyn ErasedSerialize + '_>>)> {
+            cbor_map_vec! {
+                authz_info::NONCE2 => Some(Value::Bytes(self.nonce2.clone())),
+                authz_info::ACE_SERVER_RECIPIENTID => Some(Value::Bytes(self.ace_server_recipientid.clone()))
+            }
+        }
+
+        fn try_from_cbor_map(map: Vec<(i128, Value)>) -> Result<Self, TryFromCborMapError>
+        where
+            Self: Sized + ToCborMap,
+        {
+            let mut response = OscoreAuthzInfoResponse::builder();
+            for entry in map {
+                match (CborKey::try_from(entry.0)?, entry.1) {
+                    (authz_info::NONCE2, Value::Bytes(x)) => response.nonce2(x),
+                    (authz_info::ACE_SERVER_RECIPIENTID, Value::Bytes(x)) => {
+                        response.ace_server_recipientid(x)
+                    }
+                    (key, _) => return Err(TryFromCborMapError::unknown_field(key)),
+                };
+            }
+            response
+                .build()
+                .map_err(|x| TryFromCborMapError::build_failed("OscoreAuthzInfoResponse", x))
+        }
+    }
+
+    impl ToCborMap for OscoreInputMaterial {
+        fn to_cbor_map(&self) -> Vec<(i128, Option<Box<dyn ErasedSerialize + '_>>)> {
+            cbor_map_vec! {
+                oscore_input_material::ID => Some(Value::Bytes(self.id.clone())),
+                oscore_input_material::VERSION => self.version,
+                oscore_input_material::MS => Some(Value::Bytes(self.master_secret.clone())),
+                oscore_input_material::HKDF => self.hkdf,
+                oscore_input_material::ALG => self.alg,
+                oscore_input_material::SALT => self.salt.as_ref().map(|x| Value::Bytes(x.clone())),
+                oscore_input_material::CONTEXT_ID => self.context_id.as_ref().map(|x| Value::Bytes(x.clone()))
+            }
+        }
+
+        fn try_from_cbor_map(map: Vec<(i128, Value)>) -> Result<Self, TryFromCborMapError>
+        where
+            Self: Sized + ToCborMap,
+        {
+            let mut material = OscoreInputMaterial::builder();
+            for entry in map {
+                match (CborKey::try_from(entry.0)?, entry.1) {
+                    (oscore_input_material::ID, Value::Bytes(x)) => material.id(x),
+                    (oscore_input_material::VERSION, Value::Integer(x)) => {
+                        material.version(decode_number::<u32>(x, "version")?)
+                    }
+                    (oscore_input_material::MS, Value::Bytes(x)) => material.master_secret(x),
+                    (oscore_input_material::HKDF, Value::Integer(x)) => {
+                        material.hkdf(Algorithm::from(decode_number::<i64>(x, "hkdf")?))
+                    }
+                    (oscore_input_material::ALG, Value::Integer(x)) => {
+                        material.alg(Algorithm::from(decode_number::<i64>(x, "alg")?))
+                    }
+                    (oscore_input_material::SALT, Value::Bytes(x)) => material.salt(x),
+                    (oscore_input_material::CONTEXT_ID, Value::Bytes(x)) => material.context_id(x),
+                    (key, _) => return Err(TryFromCborMapError::unknown_field(key)),
+                };
+            }
+            material
+                .build()
+                .map_err(|x| TryFromCborMapError::build_failed("OscoreInputMaterial", x))
+        }
+    }
+}
@@ -0,0 +1,171 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+#[cfg(not(feature = "std"))]
+use {alloc::string::ToString, alloc::vec};
+
+use crate::common::cose::Algorithm;
+use crate::common::test_helper::expect_ser_de;
+use crate::ToCborMap;
+
+use super::*;
+
+/// Example data based on RFC 9203, Figure 3.
+#[test]
+fn test_authz_info_request() -> Result<(), String> {
+    let request = OscoreAuthzInfoRequest::builder()
+        .access_token(vec![0xDC, 0xAF])
+        .nonce1(hex::decode("018a278f7faab55a").map_err(|x| x.to_string())?)
+        .ace_client_recipientid(vec![0x16, 0x45])
+        .build()
+        .map_err(|x| x.to_string())?;
+    expect_ser_de(request, None, "A30142DCAF182848018A278F7FAAB55A182B421645")
+}
+
+/// Example data based on RFC 9203, Figure 4.
+#[test]
+fn test_authz_info_response() -> Result<(), String> {
+    let response = OscoreAuthzInfoResponse::builder()
+        .nonce2(hex::decode("25a8991cd700ac01").map_err(|x| x.to_string())?)
+        .ace_server_recipientid(vec![0x00])
+        .build()
+        .map_err(|x| x.to_string())?;
+    expect_ser_de(response, None, "A2182A4825A8991CD700AC01182C4100")
+}
+
+#[test]
+fn test_authz_info_invalid() {
+    assert!(OscoreAuthzInfoRequest::builder()
+        .access_token(vec![0xDC, 0xAF])
+        .nonce1(vec![])
+        .ace_client_recipientid(vec![0x16])
+        .build()
+        .is_err());
+    assert!(OscoreAuthzInfoResponse::builder()
+        .nonce2(vec![0x25])
+        .ace_server_recipientid(vec![0; MAX_RECIPIENT_ID_LENGTH + 1])
+        .build()
+        .is_err());
+    assert!(OscoreAuthzInfoResponse::builder()
+        .nonce2(vec![0x25])
+        .ace_server_recipientid(vec![0; MAX_RECIPIENT_ID_LENGTH])
+        .build()
+        .is_ok());
+    // Missing fields and invalid lengths are also rejected when decoding.
+    assert!(OscoreAuthzInfoRequest::deserialize_from(
+        hex::decode("A20142DCAF182848018A278F7FAAB55A")
+            .expect("invalid hex")
+            .as_slice()
+    )
+    .is_err());
+    assert!(OscoreAuthzInfoResponse::deserialize_from(
+        hex::decode("A2182A40182C4100")
+            .expect("invalid hex")
+            .as_slice()
+    )
+    .is_err());
+}
+
+#[test]
+fn test_oscore_input_material() -> Result<(), String> {
+    let material = OscoreInputMaterial::builder()
+        .id(vec![0x01])
+        .master_secret(hex::decode("f9af838368e353e78888e1426bd94e6f").map_err(|x| x.to_string())?)
+        .alg(Algorithm::AesCcm16_64_128)
+        .context_id(vec![0xA5])
+        .build()
+        .map_err(|x| x.to_string())?;
+    expect_ser_de(
+        material,
+        None,
+        "A400410102\
+         50F9AF838368E353E78888E1426BD94E6F040A0641A5",
+    )?;
+    assert!(OscoreInputMaterial::builder()
+        .id(vec![])
+        .master_secret(vec![0xDC])
+        .build()
+        .is_err());
+    assert!(OscoreInputMaterial::builder()
+        .id(vec![0x01])
+        .build()
+        .is_err());
+    Ok(())
+}
+
+/// Example nonces based on RFC 9203, Figures 3 and 4.
+#[test]
+fn test_oscore_context_parameters() -> Result<(), String> {
+    let request = OscoreAuthzInfoRequest::builder()
+        .access_token(vec![0xDC, 0xAF])
+        .nonce1(hex::decode("018a278f7faab55a").map_err(|x| x.to_string())?)
+        .ace_client_recipientid(vec![0x16, 0x45])
+        .build()
+        .map_err(|x| x.to_string())?;
+    let response = OscoreAuthzInfoResponse::builder()
+        .nonce2(hex::decode("25a8991cd700ac01").map_err(|x| x.to_string())?)
+        .ace_server_recipientid(vec![0x00])
+        .build()
+        .map_err(|x| x.to_string())?;
+    let material = OscoreInputMaterial::builder()
+        .id(vec![0x01])
+        .master_secret(vec![0xDC; 16])
+        .salt(hex::decode("f9af838368e353e78888e1426bd94e6f").map_err(|x| x.to_string())?)
+        .hkdf(Algorithm::DirectHkdfSha512)
+        .build()
+        .map_err(|x| x.to_string())?;
+    let client = material.context_parameters(&request, &response, OscoreRole::Client);
+    assert_eq!(
+        client.master_salt,
+        hex::decode("50f9af838368e353e78888e1426bd94e6f48018a278f7faab55a4825a8991cd700ac01")
+            .map_err(|x| x.to_string())?
+    );
+    assert_eq!(client.sender_id, vec![0x00]);
+    assert_eq!(client.recipient_id, vec![0x16, 0x45]);
+    assert_eq!(client.id_context, None);
+    assert_eq!(client.hkdf, Algorithm::DirectHkdfSha512);
+    assert_eq!(client.alg, Algorithm::AesCcm16_64_128);
+
+    let server = material.context_parameters(&request, &response, OscoreRole::ResourceServer);
+    assert_eq!(server.sender_id, client.recipient_id);
+    assert_eq!(server.recipient_id, client.sender_id);
+    assert_eq!(server.master_salt, client.master_salt);
+
+    // Without a salt, an empty byte string is used in its place.
+    let unsalted = OscoreInputMaterial {
+        salt: None,
+        ..material
+    };
+    assert!(unsalted
+        .context_parameters(&request, &response, OscoreRole::Client)
+        .master_salt
+        .starts_with(&[0x40, 0x48]));
+    Ok(())
+}
+
+#[test]
+fn test_oscore_input_material_identity() -> Result<(), String> {
+    let material = |context_id: Option<Vec<u8>>| {
+        let mut builder = OscoreInputMaterial::builder();
+        builder.id(vec![0x01]).master_secret(vec![0xDC; 16]);
+        if let Some(context_id) = context_id {
+            builder.context_id(context_id);
+        }
+        builder.build().map_err(|x| x.to_string())
+    };
+    // Colliding identifiers of different Authorization Servers are kept apart by the ID Context.
+    let first = material(Some(vec![0xA5]))?;
+    let second = material(Some(vec![0xA6, 0x01]))?;
+    assert_eq!(first.identity(), "a5:01");
+    assert_eq!(second.identity(), "a601:01");
+    assert_eq!(material(None)?.identity(), ":01");
+    Ok(())
+}
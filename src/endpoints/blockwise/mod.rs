@@ -0,0 +1,353 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+//! Contains helpers for transferring large access tokens to the `authz-info` endpoint
+//! using CoAP block-wise transfers, as described in
+//! [RFC 7959](https://www.rfc-editor.org/rfc/rfc7959).
+//!
+//! Access tokens (especially ones containing asymmetric keys or certificates) can easily exceed
+//! the maximum message size of constrained links, in which case they have to be POSTed to the
+//! `authz-info` endpoint in multiple blocks using the Block1 option.
+//! This module doesn't implement CoAP itself, but provides the transport-independent parts:
+//! - The [`Block`] option value, which can be [encoded](Block::encode) into and
+//!   [decoded](Block::decode) from the value of a CoAP Block1 option.
+//! - The [`BlockSender`], which splits a token into blocks on the client side and adapts
+//!   to a smaller block size requested by the Resource Server.
+//! - The [`BlockAssembler`], which reassembles a token on the Resource Server side,
+//!   enforcing a maximum token size and optionally requesting a smaller block size.
+//!
+//! # Example
+//! ```
+//! # use dcaf::error::BlockwiseError;
+//! use dcaf::endpoints::blockwise::{AssemblyProgress, BlockAssembler, BlockSender};
+//!
+//! let token = vec![0xDC; 100];
+//! // The client would like to use blocks of 64 bytes...
+//! let mut sender = BlockSender::new(&token, 64)?;
+//! // ...but the Resource Server only accepts blocks of up to 32 bytes.
+//! let mut assembler = BlockAssembler::new(1024, 32)?;
+//! assert_eq!(assembler.check_size(sender.size()), Ok(()));
+//!
+//! let received = loop {
+//!     let (block, data) = sender.next_block().expect("token has not been sent completely");
+//!     // `block.encode()` and `data` would now be sent in a CoAP POST request...
+//!     match assembler.push(block, data)? {
+//!         // ...to which the RS responds with "2.31 Continue" and the `acknowledgement`.
+//!         AssemblyProgress::Continue(acknowledgement) => sender.acknowledge(acknowledgement),
+//!         AssemblyProgress::Complete(token) => break token,
+//!     }
+//! };
+//! assert_eq!(received, token);
+//! # Ok::<(), BlockwiseError>(())
+//! ```
+
+use crate::common::cbor_values::ByteString;
+use crate::error::BlockwiseError;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+#[cfg(test)]
+mod tests;
+
+/// The smallest block size allowed by [RFC 7959](https://www.rfc-editor.org/rfc/rfc7959#section-2.2).
+pub const MIN_BLOCK_SIZE: usize = 16;
+
+/// The largest block size allowed by [RFC 7959](https://www.rfc-editor.org/rfc/rfc7959#section-2.2).
+pub const MAX_BLOCK_SIZE: usize = 1024;
+
+/// The largest block number which can be represented in a block option.
+const MAX_BLOCK_NUMBER: u32 = (1 << 20) - 1;
+
+/// The value of a CoAP Block1 (or Block2) option, as described in
+/// [section 2.2 of RFC 7959](https://www.rfc-editor.org/rfc/rfc7959#section-2.2).
+///
+/// # Example
+/// ```
+/// # use dcaf::endpoints::blockwise::Block;
+/// let block = Block::new(2, true, 64)?;
+/// assert_eq!(block.encode(), vec![0x2A]);
+/// assert_eq!(Block::decode(&[0x2A])?, block);
+/// assert_eq!(block.offset(), 128);
+/// # Ok::<(), dcaf::error::BlockwiseError>(())
+/// ```
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+pub struct Block {
+    /// The number of this block, i.e., its offset divided by its size.
+    pub num: u32,
+
+    /// Whether more blocks follow this one.
+    pub more: bool,
+
+    /// The size exponent of this block, where the block size is `2^(szx + 4)`.
+    ///
+    /// Must be at most 6, as the value 7 is reserved.
+    pub szx: u8,
+}
+
+impl Block {
+    /// Creates a new block with the given number, "more" flag, and `size` in bytes.
+    ///
+    /// # Errors
+    /// - If `size` is not a power of two between [`MIN_BLOCK_SIZE`] and [`MAX_BLOCK_SIZE`].
+    /// - If `num` is too large to be represented in a block option.
+    pub fn new(num: u32, more: bool, size: usize) -> Result<Block, BlockwiseError> {
+        if num > MAX_BLOCK_NUMBER {
+            return Err(BlockwiseError::InvalidBlockNumber(num));
+        }
+        Ok(Block {
+            num,
+            more,
+            szx: size_exponent(size)?,
+        })
+    }
+
+    /// Returns the size of this block in bytes.
+    #[must_use]
+    pub fn size(&self) -> usize {
+        1 << (self.szx + 4)
+    }
+
+    /// Returns the offset of this block's data within the whole body in bytes.
+    #[must_use]
+    pub fn offset(&self) -> usize {
+        self.num as usize * self.size()
+    }
+
+    /// Encodes this block as the value of a CoAP block option, i.e., as an unsigned integer
+    /// in as few bytes as possible.
+    #[must_use]
+    pub fn encode(&self) -> Vec<u8> {
+        let value = self.num << 4 | u32::from(self.more) << 3 | u32::from(self.szx);
+        let bytes = value.to_be_bytes();
+        let start = bytes.iter().position(|x| *x != 0).unwrap_or(bytes.len());
+        bytes[start..].to_vec()
+    }
+
+    /// Decodes a block from the given value of a CoAP block option.
+    ///
+    /// # Errors
+    /// - If the option value is longer than three bytes.
+    /// - If the option value uses the reserved size exponent 7.
+    pub fn decode(value: &[u8]) -> Result<Block, BlockwiseError> {
+        if value.len() > 3 {
+            return Err(BlockwiseError::InvalidOption);
+        }
+        let value = value.iter().fold(0_u32, |acc, x| acc << 8 | u32::from(*x));
+        let szx = (value & 0x07) as u8;
+        if szx == 7 {
+            return Err(BlockwiseError::InvalidOption);
+        }
+        Ok(Block {
+            num: value >> 4,
+            more: value & 0x08 != 0,
+            szx,
+        })
+    }
+}
+
+/// Returns the size exponent for the given block `size`.
+fn size_exponent(size: usize) -> Result<u8, BlockwiseError> {
+    (MIN_BLOCK_SIZE..=MAX_BLOCK_SIZE)
+        .contains(&size)
+        .then(|| size.trailing_zeros() - 4)
+        .filter(|_| size.is_power_of_two())
+        .and_then(|x| u8::try_from(x).ok())
+        .ok_or(BlockwiseError::InvalidBlockSize(size))
+}
+
+/// Splits an access token into blocks for a block-wise transfer to the `authz-info` endpoint,
+/// used on the client side.
+///
+/// Call [`next_block`](BlockSender::next_block) to get the next block to send, and pass the
+/// Block1 option of each "2.31 Continue" response to [`acknowledge`](BlockSender::acknowledge),
+/// so that the block size can be reduced if the Resource Server asks for it
+/// (as described in [section 2.5 of RFC 7959](https://www.rfc-editor.org/rfc/rfc7959#section-2.5)).
+/// The total size of the token (e.g., for the Size1 option) is returned by
+/// [`size`](BlockSender::size).
+///
+/// For an example, see the [module-level documentation](self).
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct BlockSender<'a> {
+    token: &'a [u8],
+    szx: u8,
+    offset: usize,
+    finished: bool,
+}
+
+impl<'a> BlockSender<'a> {
+    /// Creates a new sender for the given `token`, initially using the given block `size`.
+    ///
+    /// # Errors
+    /// - If `size` is not a power of two between [`MIN_BLOCK_SIZE`] and [`MAX_BLOCK_SIZE`].
+    pub fn new(token: &'a [u8], size: usize) -> Result<BlockSender<'a>, BlockwiseError> {
+        Ok(BlockSender {
+            token,
+            szx: size_exponent(size)?,
+            offset: 0,
+            finished: false,
+        })
+    }
+
+    /// Returns the total size of the token in bytes, to be sent in the Size1 option so that the
+    /// Resource Server can reject tokens which are too large before they are transferred.
+    #[must_use]
+    pub fn size(&self) -> usize {
+        self.token.len()
+    }
+
+    /// Returns the next block to send along with its data, or `None` if the whole token has
+    /// already been sent (or it's too large to be represented in blocks of the current size).
+    pub fn next_block(&mut self) -> Option<(Block, &'a [u8])> {
+        if self.finished {
+            return None;
+        }
+        let size = 1 << (self.szx + 4);
+        let end = usize::min(self.offset + size, self.token.len());
+        let block = Block {
+            num: u32::try_from(self.offset / size)
+                .ok()
+                .filter(|x| *x <= MAX_BLOCK_NUMBER)?,
+            more: end < self.token.len(),
+            szx: self.szx,
+        };
+        let data = &self.token[self.offset..end];
+        self.offset = end;
+        self.finished = !block.more;
+        Some((block, data))
+    }
+
+    /// Processes the Block1 option of a "2.31 Continue" response, adopting the block size
+    /// of the `acknowledgement` for the following blocks if it's smaller than the current one.
+    pub fn acknowledge(&mut self, acknowledgement: Block) {
+        self.szx = self.szx.min(acknowledgement.szx);
+    }
+}
+
+/// Result of passing a block to a [`BlockAssembler`].
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub enum AssemblyProgress {
+    /// More blocks are expected. The contained block should be sent as the Block1 option of
+    /// a "2.31 Continue" response to acknowledge the received block.
+    Continue(Block),
+
+    /// The token has been reassembled completely and is contained in this field.
+    Complete(ByteString),
+}
+
+/// Reassembles an access token sent to the `authz-info` endpoint in multiple blocks,
+/// used on the Resource Server side.
+///
+/// Tokens larger than the configured maximum size are rejected as early as possible, either
+/// from the Size1 option (using [`check_size`](BlockAssembler::check_size)) or once the received
+/// blocks exceed it. Blocks must arrive in order; if a block is lost or reordered, the transfer
+/// has to be restarted (using [`reset`](BlockAssembler::reset)).
+///
+/// For an example, see the [module-level documentation](self).
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct BlockAssembler {
+    max_token_size: usize,
+    preferred_szx: u8,
+    buffer: ByteString,
+}
+
+impl BlockAssembler {
+    /// Creates a new assembler accepting tokens of up to `max_token_size` bytes, which asks
+    /// clients to use blocks of at most `preferred_block_size` bytes.
+    ///
+    /// # Errors
+    /// - If `preferred_block_size` is not a power of two between [`MIN_BLOCK_SIZE`] and
+    ///   [`MAX_BLOCK_SIZE`].
+    pub fn new(
+        max_token_size: usize,
+        preferred_block_size: usize,
+    ) -> Result<BlockAssembler, BlockwiseError> {
+        Ok(BlockAssembler {
+            max_token_size,
+            preferred_szx: size_exponent(preferred_block_size)?,
+            buffer: Vec::new(),
+        })
+    }
+
+    /// Checks the total token size announced by the client in the Size1 option.
+    ///
+    /// # Errors
+    /// - If `size` exceeds the maximum token size of this assembler, in which case the
+    ///   Resource Server should respond with "4.13 Request Entity Too Large" and a Size1 option
+    ///   containing [`max_token_size`](BlockAssembler::max_token_size).
+    pub fn check_size(&self, size: usize) -> Result<(), BlockwiseError> {
+        if size > self.max_token_size {
+            Err(BlockwiseError::TooLarge {
+                max: self.max_token_size,
+            })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Returns the maximum token size accepted by this assembler in bytes.
+    #[must_use]
+    pub fn max_token_size(&self) -> usize {
+        self.max_token_size
+    }
+
+    /// Adds the given `block` with its `data` to the token being reassembled.
+    ///
+    /// A block with number 0 always starts a new transfer, discarding any previously received
+    /// blocks.
+    ///
+    /// # Errors
+    /// - If the block doesn't continue the previously received data, in which case the
+    ///   Resource Server should respond with "4.08 Request Entity Incomplete".
+    /// - If the size of `data` doesn't match the block size, even though more blocks follow.
+    /// - If the token would exceed the maximum token size of this assembler.
+    ///
+    /// In all of these cases, the previously received data is discarded.
+    pub fn push(&mut self, block: Block, data: &[u8]) -> Result<AssemblyProgress, BlockwiseError> {
+        if block.num == 0 {
+            self.reset();
+        }
+        let result = self.append(block, data);
+        if result.is_err() {
+            self.reset();
+        }
+        result
+    }
+
+    fn append(&mut self, block: Block, data: &[u8]) -> Result<AssemblyProgress, BlockwiseError> {
+        if block.offset() != self.buffer.len() {
+            return Err(BlockwiseError::Incomplete {
+                expected_offset: self.buffer.len(),
+                offset: block.offset(),
+            });
+        }
+        if data.len() > block.size() || (block.more && data.len() != block.size()) {
+            return Err(BlockwiseError::InvalidBlockSize(data.len()));
+        }
+        self.check_size(self.buffer.len() + data.len())?;
+        self.buffer.extend_from_slice(data);
+        if block.more {
+            Ok(AssemblyProgress::Continue(Block {
+                szx: block.szx.min(self.preferred_szx),
+                ..block
+            }))
+        } else {
+            Ok(AssemblyProgress::Complete(core::mem::take(
+                &mut self.buffer,
+            )))
+        }
+    }
+
+    /// Discards all data received so far.
+    pub fn reset(&mut self) {
+        self.buffer.clear();
+    }
+}
@@ -0,0 +1,121 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+#[cfg(not(feature = "std"))]
+use alloc::vec;
+
+use super::*;
+
+#[test]
+fn test_block_option() -> Result<(), BlockwiseError> {
+    assert_eq!(Block::new(0, false, 16)?.encode(), Vec::<u8>::new());
+    assert_eq!(Block::decode(&[])?, Block::new(0, false, 16)?);
+    let large = Block::new(MAX_BLOCK_NUMBER, true, MAX_BLOCK_SIZE)?;
+    assert_eq!(large.encode(), vec![0xFF, 0xFF, 0xFE]);
+    assert_eq!(Block::decode(&large.encode())?, large);
+    assert_eq!(Block::decode(&[0x16])?, Block::new(1, false, 1024)?);
+    assert_eq!(Block::decode(&[0x0F]), Err(BlockwiseError::InvalidOption));
+    assert_eq!(
+        Block::decode(&[0x01, 0x00, 0x00, 0x00]),
+        Err(BlockwiseError::InvalidOption)
+    );
+    assert_eq!(
+        Block::new(0, false, 48),
+        Err(BlockwiseError::InvalidBlockSize(48))
+    );
+    assert_eq!(
+        Block::new(MAX_BLOCK_NUMBER + 1, false, 16),
+        Err(BlockwiseError::InvalidBlockNumber(MAX_BLOCK_NUMBER + 1))
+    );
+    Ok(())
+}
+
+#[test]
+fn test_sender() -> Result<(), BlockwiseError> {
+    let token: Vec<u8> = (0..=99).collect();
+    let mut sender = BlockSender::new(&token, 64)?;
+    assert_eq!(sender.size(), 100);
+    assert_eq!(
+        sender.next_block(),
+        Some((Block::new(0, true, 64)?, &token[..64]))
+    );
+    // The Resource Server asks for smaller blocks, so block numbers are adapted accordingly.
+    sender.acknowledge(Block::new(0, true, 32)?);
+    assert_eq!(
+        sender.next_block(),
+        Some((Block::new(2, true, 32)?, &token[64..96]))
+    );
+    // Larger block sizes are ignored.
+    sender.acknowledge(Block::new(2, true, 1024)?);
+    assert_eq!(
+        sender.next_block(),
+        Some((Block::new(3, false, 32)?, &token[96..]))
+    );
+    assert_eq!(sender.next_block(), None);
+
+    let mut empty = BlockSender::new(&[], 16)?;
+    assert_eq!(
+        empty.next_block(),
+        Some((Block::new(0, false, 16)?, &[][..]))
+    );
+    assert_eq!(empty.next_block(), None);
+    Ok(())
+}
+
+#[test]
+fn test_assembler() -> Result<(), BlockwiseError> {
+    let token: Vec<u8> = (0..=99).collect();
+    let mut assembler = BlockAssembler::new(100, 32)?;
+    assert_eq!(assembler.max_token_size(), 100);
+    assert_eq!(assembler.check_size(100), Ok(()));
+    assert_eq!(
+        assembler.check_size(101),
+        Err(BlockwiseError::TooLarge { max: 100 })
+    );
+    assert_eq!(
+        assembler.push(Block::new(0, true, 64)?, &token[..64])?,
+        AssemblyProgress::Continue(Block::new(0, true, 32)?)
+    );
+    // Skipping a block aborts the transfer.
+    assert_eq!(
+        assembler.push(Block::new(3, false, 32)?, &token[96..]),
+        Err(BlockwiseError::Incomplete {
+            expected_offset: 64,
+            offset: 96
+        })
+    );
+    assert!(matches!(
+        assembler.push(Block::new(2, true, 32)?, &token[64..96]),
+        Err(BlockwiseError::Incomplete { .. })
+    ));
+
+    // Restarting works, but intermediate blocks must contain a whole block of data.
+    assembler.push(Block::new(0, true, 64)?, &token[..64])?;
+    assert_eq!(
+        assembler.push(Block::new(2, true, 32)?, &token[64..80]),
+        Err(BlockwiseError::InvalidBlockSize(16))
+    );
+    assembler.push(Block::new(0, true, 64)?, &token[..64])?;
+    assembler.push(Block::new(2, true, 32)?, &token[64..96])?;
+    assert_eq!(
+        assembler.push(Block::new(3, false, 32)?, &token[96..])?,
+        AssemblyProgress::Complete(token.clone())
+    );
+
+    // Tokens exceeding the maximum size are rejected as soon as possible.
+    let mut small = BlockAssembler::new(40, 1024)?;
+    small.push(Block::new(0, true, 32)?, &token[..32])?;
+    assert_eq!(
+        small.push(Block::new(1, true, 32)?, &token[32..64]),
+        Err(BlockwiseError::TooLarge { max: 40 })
+    );
+    Ok(())
+}
@@ -0,0 +1,222 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+//! Contains helpers for handling the freshness of cacheable responses of the Authorization
+//! Server, such as [metadata documents](crate::endpoints::metadata::AuthServerMetadata) or key
+//! sets, and the [`ResponseCache`] in which clients can keep them.
+//!
+//! In CoAP, freshness is conveyed by the Max-Age option, which defaults to
+//! [`DEFAULT_MAX_AGE`] if absent (use [`max_age_from_coap`]). In HTTP, it's conveyed by the
+//! `Cache-Control` header (use [`max_age_from_cache_control`] and [`render_cache_control`]).
+//! Note that responses of the token endpoint must not be cached, so Authorization Servers
+//! using CoAP should explicitly send them with a Max-Age of 0.
+//!
+//! # Example
+//! ```
+//! use dcaf::endpoints::caching::{max_age_from_cache_control, ResponseCache};
+//!
+//! let mut cache = ResponseCache::new(4);
+//! let uri = "https://as.example.com/.well-known/oauth-authorization-server";
+//! let max_age = max_age_from_cache_control("public, max-age=300").unwrap_or(0);
+//! cache.insert(uri, "metadata", 1000, max_age);
+//! assert_eq!(cache.get(uri, 1299), Some(&"metadata"));
+//! // Once the response is stale, it has to be retrieved again.
+//! assert_eq!(cache.get(uri, 1300), None);
+//! ```
+
+#[cfg(not(feature = "std"))]
+use {
+    alloc::format,
+    alloc::string::{String, ToString},
+    alloc::vec::Vec,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The Max-Age (in seconds) of a CoAP response without a Max-Age option, as specified in
+/// [section 5.10.5 of RFC 7252](https://www.rfc-editor.org/rfc/rfc7252#section-5.10.5).
+pub const DEFAULT_MAX_AGE: u32 = 60;
+
+/// Returns the number of seconds for which a CoAP response with the given Max-Age `option`
+/// (if present) is fresh.
+#[must_use]
+pub fn max_age_from_coap(option: Option<u32>) -> u32 {
+    option.unwrap_or(DEFAULT_MAX_AGE)
+}
+
+/// Returns the number of seconds for which an HTTP response with the given `Cache-Control`
+/// `header` is fresh, or `None` if the header doesn't specify it.
+///
+/// Responses with a `no-store` or `no-cache` directive are never fresh (i.e., this returns
+/// `Some(0)`), regardless of any `max-age` directive. Directive names are case-insensitive, and
+/// unknown directives are ignored.
+///
+/// # Example
+/// ```
+/// use dcaf::endpoints::caching::max_age_from_cache_control;
+///
+/// assert_eq!(max_age_from_cache_control("max-age=3600"), Some(3600));
+/// assert_eq!(max_age_from_cache_control("No-Store"), Some(0));
+/// assert_eq!(max_age_from_cache_control("public"), None);
+/// ```
+#[must_use]
+pub fn max_age_from_cache_control(header: &str) -> Option<u32> {
+    let mut max_age = None;
+    for directive in header.split(',').map(str::trim) {
+        let (name, value) = directive
+            .split_once('=')
+            .map_or((directive, None), |(name, value)| {
+                (name.trim(), Some(value))
+            });
+        if name.eq_ignore_ascii_case("no-store") || name.eq_ignore_ascii_case("no-cache") {
+            return Some(0);
+        } else if name.eq_ignore_ascii_case("max-age") {
+            max_age = value.and_then(|x| x.trim().trim_matches('"').parse().ok());
+        }
+    }
+    max_age
+}
+
+/// Returns the value of the `Cache-Control` header with which an HTTP response that is fresh for
+/// `max_age` seconds should be sent, where a `max_age` of 0 results in `no-store`.
+///
+/// # Example
+/// ```
+/// use dcaf::endpoints::caching::render_cache_control;
+///
+/// assert_eq!(render_cache_control(3600), "max-age=3600");
+/// assert_eq!(render_cache_control(0), "no-store");
+/// ```
+#[must_use]
+pub fn render_cache_control(max_age: u32) -> String {
+    if max_age == 0 {
+        "no-store".to_string()
+    } else {
+        format!("max-age={max_age}")
+    }
+}
+
+/// A cached response, along with the URI it has been retrieved from.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+struct CacheEntry<T> {
+    /// The URI the response has been retrieved from.
+    uri: String,
+
+    /// The (decoded) response.
+    value: T,
+
+    /// The point in time (in seconds) from which on the response is stale.
+    expires_at: i64,
+}
+
+/// A capacity-bounded cache of responses (e.g., decoded metadata documents or key sets), keyed
+/// by the URI they have been retrieved from.
+///
+/// Responses are only returned by [`get`](ResponseCache::get) while they're fresh. If the cache
+/// is full, the response which becomes stale first is evicted to make room for a new one.
+///
+/// For an example, see the [module-level documentation](self).
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct ResponseCache<T> {
+    /// The cached responses.
+    entries: Vec<CacheEntry<T>>,
+
+    /// The maximum number of cached responses.
+    capacity: usize,
+}
+
+impl<T> ResponseCache<T> {
+    /// Creates a new, empty [`ResponseCache`] holding at most `capacity` responses.
+    #[must_use]
+    pub fn new(capacity: usize) -> ResponseCache<T> {
+        ResponseCache {
+            entries: Vec::new(),
+            capacity,
+        }
+    }
+
+    /// Returns the maximum number of responses this cache holds.
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of responses in this cache, including stale ones which haven't been
+    /// [purged](ResponseCache::purge_expired) yet.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if this cache contains no responses.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Caches the given `value` retrieved from the given `uri` at `now` (in seconds), which is
+    /// fresh for `max_age` seconds, replacing any response previously cached for this URI.
+    ///
+    /// Responses with a `max_age` of 0 aren't cached (but still remove the previous response).
+    pub fn insert<U>(&mut self, uri: U, value: T, now: i64, max_age: u32)
+    where
+        U: Into<String>,
+    {
+        let uri = uri.into();
+        self.remove(&uri);
+        if max_age == 0 || self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() >= self.capacity {
+            self.purge_expired(now);
+        }
+        if self.entries.len() >= self.capacity {
+            if let Some(index) = self
+                .entries
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, x)| x.expires_at)
+                .map(|(index, _)| index)
+            {
+                self.entries.remove(index);
+            }
+        }
+        self.entries.push(CacheEntry {
+            uri,
+            value,
+            expires_at: now.saturating_add(i64::from(max_age)),
+        });
+    }
+
+    /// Returns the response cached for the given `uri` if it's still fresh at `now`
+    /// (in seconds).
+    #[must_use]
+    pub fn get(&self, uri: &str, now: i64) -> Option<&T> {
+        self.entries
+            .iter()
+            .find(|x| x.uri == uri && now < x.expires_at)
+            .map(|x| &x.value)
+    }
+
+    /// Removes the response cached for the given `uri` (even if it's stale), returning it.
+    pub fn remove(&mut self, uri: &str) -> Option<T> {
+        let index = self.entries.iter().position(|x| x.uri == uri)?;
+        Some(self.entries.remove(index).value)
+    }
+
+    /// Removes all responses which are stale at `now` (in seconds), returning their number.
+    pub fn purge_expired(&mut self, now: i64) -> usize {
+        let before = self.entries.len();
+        self.entries.retain(|x| now < x.expires_at);
+        before - self.entries.len()
+    }
+}
@@ -0,0 +1,81 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+use super::*;
+
+#[test]
+fn test_max_age() {
+    assert_eq!(max_age_from_coap(None), DEFAULT_MAX_AGE);
+    assert_eq!(max_age_from_coap(Some(0)), 0);
+    assert_eq!(max_age_from_coap(Some(3600)), 3600);
+
+    assert_eq!(max_age_from_cache_control("max-age=0"), Some(0));
+    assert_eq!(
+        max_age_from_cache_control("private, MAX-AGE = \"120\" , must-revalidate"),
+        Some(120)
+    );
+    assert_eq!(max_age_from_cache_control("max-age=60, no-cache"), Some(0));
+    assert_eq!(max_age_from_cache_control("max-age=invalid"), None);
+    assert_eq!(max_age_from_cache_control(""), None);
+    assert_eq!(
+        max_age_from_cache_control(&render_cache_control(42)),
+        Some(42)
+    );
+    assert_eq!(
+        max_age_from_cache_control(&render_cache_control(0)),
+        Some(0)
+    );
+}
+
+#[test]
+fn test_cache_freshness() {
+    let mut cache = ResponseCache::new(2);
+    cache.insert("coap://as/a", 1, 100, 10);
+    assert_eq!(cache.get("coap://as/a", 100), Some(&1));
+    assert_eq!(cache.get("coap://as/a", 109), Some(&1));
+    assert_eq!(cache.get("coap://as/a", 110), None);
+    assert_eq!(cache.get("coap://as/b", 100), None);
+
+    // Refreshing a response replaces it.
+    cache.insert("coap://as/a", 2, 110, 10);
+    assert_eq!(cache.get("coap://as/a", 115), Some(&2));
+    assert_eq!(cache.len(), 1);
+
+    // Uncacheable responses remove the previous one.
+    cache.insert("coap://as/a", 3, 115, 0);
+    assert_eq!(cache.get("coap://as/a", 115), None);
+    assert!(cache.is_empty());
+}
+
+#[test]
+fn test_cache_eviction() {
+    let mut cache = ResponseCache::new(2);
+    cache.insert("a", 'a', 0, 100);
+    cache.insert("b", 'b', 0, 50);
+    // The response becoming stale first is evicted.
+    cache.insert("c", 'c', 10, 100);
+    assert_eq!(cache.get("b", 10), None);
+    assert_eq!(cache.get("a", 10), Some(&'a'));
+    assert_eq!(cache.get("c", 10), Some(&'c'));
+
+    // Stale responses are purged before evicting fresh ones.
+    cache.insert("d", 'd', 100, 100);
+    assert_eq!(cache.len(), 2);
+    assert_eq!(cache.get("c", 100), Some(&'c'));
+    assert_eq!(cache.purge_expired(110), 1);
+    assert_eq!(cache.remove("d"), Some('d'));
+    assert!(cache.is_empty());
+
+    let mut disabled = ResponseCache::new(0);
+    disabled.insert("a", 'a', 0, 100);
+    assert!(disabled.is_empty());
+    assert_eq!(disabled.capacity(), 0);
+}
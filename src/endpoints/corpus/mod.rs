@@ -0,0 +1,246 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+//! Contains the [`CorpusRecorder`], which exports every successfully decoded message as a
+//! fuzzing corpus, so that fuzzers can be seeded with the shapes of real traffic.
+//!
+//! Messages are decoded as usual using [`CorpusRecorder::decode`], but are additionally passed
+//! to a [`CorpusSink`] under the name of their fuzz target (the snake case name of the message
+//! type, e.g., `access_token_request`) and a name derived from their content, so that
+//! identical messages are only stored once. When the `std` feature is enabled,
+//! [`DirectoryCorpus`] writes them into the directory layout used by
+//! [`cargo-fuzz`](https://github.com/rust-fuzz/cargo-fuzz), i.e., one file per message in
+//! `<root>/<target>/`.
+//!
+//! As real traffic contains tokens, keys, and identifiers, messages can be [redacted](Redaction)
+//! before being exported. Redaction keeps the structure of each message (including the lengths
+//! of all strings) intact, but overwrites the content of its strings.
+//!
+//! # Example
+//! ```
+//! # use std::convert::Infallible;
+//! use dcaf::endpoints::corpus::{CorpusRecorder, CorpusSink, Redaction};
+//! use dcaf::endpoints::message::AceMessage;
+//! use dcaf::AccessTokenResponse;
+//!
+//! /// Keeps the corpus in memory.
+//! #[derive(Default)]
+//! struct Corpus(Vec<(String, Vec<u8>)>);
+//!
+//! impl CorpusSink for Corpus {
+//!     type Error = Infallible;
+//!
+//!     fn add(&mut self, target: &str, _name: &str, data: &[u8]) -> Result<(), Self::Error> {
+//!         self.0.push((target.to_string(), data.to_vec()));
+//!         Ok(())
+//!     }
+//! }
+//!
+//! # #[cfg(feature = "std")] {
+//! let encoded = AccessTokenResponse::builder().access_token(vec![0xDC, 0xAF]).build()?.encode()?;
+//! let mut recorder = CorpusRecorder::new(Corpus::default(), Redaction::ByteStrings);
+//! let response: AccessTokenResponse = recorder.decode(&encoded)?;
+//! assert_eq!(response.access_token, vec![0xDC, 0xAF]);
+//! // The exported message has the same shape, but doesn't contain the actual token.
+//! let corpus = recorder.into_sink();
+//! assert_eq!(corpus.0[0].0, "access_token_response");
+//! assert_eq!(corpus.0[0].1, AccessTokenResponse::builder().access_token(vec![0, 0]).build()?.encode()?);
+//! # }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use core::fmt::Write;
+
+use ciborium::value::Value;
+use ciborium_io::Read;
+
+use crate::endpoints::message::{message_name, AceMessage};
+use crate::error::CorpusExportError;
+
+#[cfg(not(feature = "std"))]
+use {alloc::string::String, alloc::vec::Vec};
+
+#[cfg(test)]
+mod tests;
+
+/// Determines how messages are redacted before being exported by a [`CorpusRecorder`].
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+pub enum Redaction {
+    /// Exports the messages exactly as they have been received.
+    None,
+
+    /// Overwrites the content of all byte strings (such as tokens, keys, and nonces) with zeros.
+    ByteStrings,
+
+    /// Overwrites the content of all byte strings with zeros and the content of all text strings
+    /// (such as client identifiers, audiences, and text-encoded scopes) with `x` characters.
+    Strings,
+}
+
+/// Receives the messages exported by a [`CorpusRecorder`].
+pub trait CorpusSink {
+    /// The error type used when a message couldn't be stored.
+    type Error;
+
+    /// Stores the given `data` as an input named `name` in the corpus of the fuzz target
+    /// named `target`.
+    ///
+    /// # Errors
+    /// If the message couldn't be stored.
+    fn add(&mut self, target: &str, name: &str, data: &[u8]) -> Result<(), Self::Error>;
+}
+
+/// Writes messages into a directory using the layout of
+/// [`cargo-fuzz`](https://github.com/rust-fuzz/cargo-fuzz), with one subdirectory per
+/// fuzz target (e.g., `fuzz/corpus`).
+///
+/// Requires the `std` feature.
+#[cfg(feature = "std")]
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct DirectoryCorpus {
+    /// The directory containing the corpus of each fuzz target.
+    pub root: std::path::PathBuf,
+}
+
+#[cfg(feature = "std")]
+impl DirectoryCorpus {
+    /// Creates a new [`DirectoryCorpus`] writing into the given `root` directory.
+    #[must_use]
+    pub fn new<T>(root: T) -> DirectoryCorpus
+    where
+        T: Into<std::path::PathBuf>,
+    {
+        DirectoryCorpus { root: root.into() }
+    }
+}
+
+#[cfg(feature = "std")]
+impl CorpusSink for DirectoryCorpus {
+    type Error = std::io::Error;
+
+    fn add(&mut self, target: &str, name: &str, data: &[u8]) -> Result<(), Self::Error> {
+        let directory = self.root.join(target);
+        std::fs::create_dir_all(&directory)?;
+        std::fs::write(directory.join(name), data)
+    }
+}
+
+/// Decodes messages and exports each successfully decoded one to a [`CorpusSink`],
+/// redacting it according to a [`Redaction`].
+///
+/// For an example, see the [module-level documentation](self).
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct CorpusRecorder<S>
+where
+    S: CorpusSink,
+{
+    sink: S,
+    redaction: Redaction,
+    error: Option<CorpusExportError<S::Error>>,
+}
+
+impl<S> CorpusRecorder<S>
+where
+    S: CorpusSink,
+{
+    /// Creates a new [`CorpusRecorder`] exporting messages to the given `sink`
+    /// after redacting them according to `redaction`.
+    #[must_use]
+    pub fn new(sink: S, redaction: Redaction) -> CorpusRecorder<S> {
+        CorpusRecorder {
+            sink,
+            redaction,
+            error: None,
+        }
+    }
+
+    /// Returns the sink of this recorder.
+    #[must_use]
+    pub fn into_sink(self) -> S {
+        self.sink
+    }
+
+    /// Returns (and forgets) the most recent error, if exporting a message failed
+    /// since the last call of this method.
+    ///
+    /// Such errors don't affect the result of [`decode`](CorpusRecorder::decode), so that
+    /// a full disk doesn't interrupt the processing of messages.
+    pub fn take_error(&mut self) -> Option<CorpusExportError<S::Error>> {
+        self.error.take()
+    }
+
+    /// Decodes a message of type `T` from the given `encoded` bytes (just like
+    /// [`AceMessage::decode`]) and, if successful, exports it to the sink of this recorder.
+    ///
+    /// # Errors
+    /// When `encoded` does not contain a valid CBOR map representing this message type.
+    pub fn decode<T>(
+        &mut self,
+        encoded: &[u8],
+    ) -> Result<T, ciborium::de::Error<<&[u8] as Read>::Error>>
+    where
+        T: AceMessage,
+    {
+        let message = T::decode(encoded)?;
+        let exported = redact(encoded, self.redaction).and_then(|data| {
+            self.sink
+                .add(&message_name::<T>(), &input_name(&data), &data)
+                .map_err(CorpusExportError::Sink)
+        });
+        if let Err(e) = exported {
+            self.error = Some(e);
+        }
+        Ok(message)
+    }
+}
+
+/// Returns the name of the corpus input containing `data`, which is the hex-encoded
+/// 64-bit FNV-1a hash of it.
+fn input_name(data: &[u8]) -> String {
+    let hash = data.iter().fold(0xCBF2_9CE4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01B3)
+    });
+    let mut name = String::with_capacity(16);
+    // Writing into a String can't fail.
+    let _ = write!(name, "{hash:016x}");
+    name
+}
+
+/// Returns the given `encoded` message, redacted according to `redaction`.
+///
+/// # Errors
+/// If a redaction is requested, but the message can't be decoded as a CBOR value or re-encoded
+/// after redacting it.
+fn redact<T>(encoded: &[u8], redaction: Redaction) -> Result<Vec<u8>, CorpusExportError<T>> {
+    if redaction == Redaction::None {
+        return Ok(encoded.to_vec());
+    }
+    let mut value =
+        ciborium::de::from_reader::<Value, _>(encoded).map_err(|_| CorpusExportError::Redaction)?;
+    redact_value(&mut value, redaction);
+    let mut redacted = Vec::new();
+    ciborium::ser::into_writer(&value, &mut redacted).map_err(|_| CorpusExportError::Redaction)?;
+    Ok(redacted)
+}
+
+/// Overwrites the strings contained in the given `value` according to `redaction`.
+fn redact_value(value: &mut Value, redaction: Redaction) {
+    match value {
+        Value::Bytes(x) => x.iter_mut().for_each(|b| *b = 0),
+        Value::Text(x) if redaction == Redaction::Strings => {
+            *x = "x".repeat(x.len());
+        }
+        Value::Array(x) => x.iter_mut().for_each(|v| redact_value(v, redaction)),
+        Value::Map(x) => x.iter_mut().for_each(|(_, v)| redact_value(v, redaction)),
+        Value::Tag(_, x) => redact_value(x, redaction),
+        _ => {}
+    }
+}
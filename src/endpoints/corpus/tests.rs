@@ -0,0 +1,147 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+#[cfg(not(feature = "std"))]
+use {alloc::string::ToString, alloc::vec};
+
+use crate::{AccessTokenRequest, AccessTokenResponse, ErrorCode, ErrorResponse};
+
+use super::*;
+
+/// Keeps the corpus in memory, failing once it contains `limit` inputs.
+#[derive(Debug, Default)]
+struct MemoryCorpus {
+    inputs: Vec<(String, String, Vec<u8>)>,
+    limit: Option<usize>,
+}
+
+impl CorpusSink for MemoryCorpus {
+    type Error = String;
+
+    fn add(&mut self, target: &str, name: &str, data: &[u8]) -> Result<(), Self::Error> {
+        if Some(self.inputs.len()) == self.limit {
+            return Err("corpus is full".to_string());
+        }
+        self.inputs
+            .push((target.to_string(), name.to_string(), data.to_vec()));
+        Ok(())
+    }
+}
+
+fn request() -> AccessTokenRequest {
+    AccessTokenRequest::builder()
+        .client_id("myclient")
+        .client_nonce(vec![0xDC, 0xAF])
+        .build()
+        .expect("invalid request")
+}
+
+#[test]
+fn test_corpus_redaction() -> Result<(), String> {
+    let encoded = request().encode().map_err(|x| x.to_string())?;
+    let redacted = |redaction| -> Result<Vec<u8>, String> {
+        let mut recorder = CorpusRecorder::new(MemoryCorpus::default(), redaction);
+        let decoded: AccessTokenRequest = recorder.decode(&encoded).map_err(|x| x.to_string())?;
+        assert_eq!(decoded, request());
+        let mut inputs = recorder.into_sink().inputs;
+        assert_eq!(inputs.len(), 1);
+        let (target, name, data) = inputs.remove(0);
+        assert_eq!(target, "access_token_request");
+        assert_eq!(name, input_name(&data));
+        assert_eq!(name.len(), 16);
+        Ok(data)
+    };
+    assert_eq!(redacted(Redaction::None)?, encoded);
+    let expected = AccessTokenRequest::builder()
+        .client_id("myclient")
+        .client_nonce(vec![0, 0])
+        .build()
+        .map_err(|x| x.to_string())?;
+    assert_eq!(
+        redacted(Redaction::ByteStrings)?,
+        expected.encode().map_err(|x| x.to_string())?
+    );
+    let expected = AccessTokenRequest::builder()
+        .client_id("xxxxxxxx")
+        .client_nonce(vec![0, 0])
+        .build()
+        .map_err(|x| x.to_string())?;
+    assert_eq!(
+        redacted(Redaction::Strings)?,
+        expected.encode().map_err(|x| x.to_string())?
+    );
+    Ok(())
+}
+
+#[test]
+fn test_corpus_only_decoded_messages() -> Result<(), String> {
+    let mut recorder = CorpusRecorder::new(
+        MemoryCorpus {
+            inputs: Vec::new(),
+            limit: Some(1),
+        },
+        Redaction::None,
+    );
+    let error = ErrorResponse::builder()
+        .error(ErrorCode::InvalidClient)
+        .build()
+        .map_err(|x| x.to_string())?;
+    let encoded = error.clone().encode().map_err(|x| x.to_string())?;
+    assert!(recorder.decode::<AccessTokenResponse>(&encoded).is_err());
+    assert_eq!(
+        recorder.decode::<ErrorResponse>(&encoded).ok(),
+        Some(error.clone())
+    );
+    assert_eq!(recorder.take_error(), None);
+    // Errors of the sink don't prevent decoding.
+    assert_eq!(recorder.decode::<ErrorResponse>(&encoded).ok(), Some(error));
+    assert_eq!(
+        recorder.take_error(),
+        Some(CorpusExportError::Sink("corpus is full".to_string()))
+    );
+    assert_eq!(recorder.take_error(), None);
+    let inputs = recorder.into_sink().inputs;
+    assert_eq!(inputs.len(), 1);
+    assert_eq!(inputs[0].0, "error_response");
+    Ok(())
+}
+
+#[test]
+fn test_corpus_redaction_failure() {
+    // Messages which can't be decoded as a single CBOR value can't be redacted.
+    assert_eq!(
+        redact::<()>(&[0xBF], Redaction::ByteStrings),
+        Err(CorpusExportError::Redaction)
+    );
+    assert_eq!(redact::<()>(&[0xBF], Redaction::None), Ok(vec![0xBF]));
+}
+
+#[test]
+fn test_corpus_input_name() {
+    // FNV-1a test vectors.
+    assert_eq!(input_name(&[]), "cbf29ce484222325");
+    assert_eq!(input_name(b"a"), "af63dc4c8601ec8c");
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_directory_corpus() -> Result<(), String> {
+    let root = std::env::temp_dir().join(format!("dcaf-corpus-{}", std::process::id()));
+    let mut recorder = CorpusRecorder::new(DirectoryCorpus::new(&root), Redaction::None);
+    let encoded = request().encode().map_err(|x| x.to_string())?;
+    let _: AccessTokenRequest = recorder.decode(&encoded).map_err(|x| x.to_string())?;
+    assert!(recorder.take_error().is_none());
+    let path = root.join("access_token_request").join(input_name(&encoded));
+    let written = std::fs::read(path).map_err(|x| x.to_string());
+    std::fs::remove_dir_all(&root).map_err(|x| x.to_string())?;
+    assert_eq!(written?, encoded);
+    Ok(())
+}
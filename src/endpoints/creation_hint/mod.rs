@@ -14,12 +14,18 @@
 //! as described in [RFC 9200, section 5.3](https://www.rfc-editor.org/rfc/rfc9200#name-as-request-creation-hints).
 //!
 //! See the documentation of [`AuthServerRequestCreationHint`] for details and an example.
+//!
+//! Clients which have been provisioned with the information usually contained in a creation hint
+//! (e.g., during commissioning) can skip the Unauthorized Resource Request by using
+//! [`AuthServerDiscovery::Provisioned`], which also pins the provisioned Authorization Server.
 
 use crate::common::cbor_values::ByteString;
-use crate::Scope;
+use crate::common::scope::{AifEncodedScopeElement, AifRestMethod};
+use crate::error::PinnedHintMismatchError;
+use crate::{AifEncodedScope, Scope};
 
 #[cfg(not(feature = "std"))]
-use {alloc::string::String, alloc::vec::Vec};
+use {alloc::string::String, alloc::string::ToString, alloc::vec, alloc::vec::Vec};
 
 #[cfg(test)]
 mod tests;
@@ -100,8 +106,137 @@ pub struct AuthServerRequestCreationHint {
     pub client_nonce: Option<Vec<u8>>,
 }
 
+/// Determines how a client finds out which Authorization Server to request an access token from
+/// (and which audience and scope to request) for a given Resource Server.
+///
+/// # Example
+/// ```
+/// # use dcaf::AuthServerRequestCreationHint;
+/// # use dcaf::endpoints::creation_hint::AuthServerDiscovery;
+/// # use dcaf::error::PinnedHintMismatchError;
+/// let provisioned = AuthServerRequestCreationHint {
+///     auth_server: Some("coaps://as.example.com/token".to_string()),
+///     audience: Some("tempSensor4711".to_string()),
+///     ..AuthServerRequestCreationHint::default()
+/// };
+/// let discovery = AuthServerDiscovery::Provisioned(provisioned.clone());
+/// // No need to send an Unauthorized Resource Request first.
+/// assert_eq!(discovery.initial_hint(), Some(&provisioned));
+///
+/// // If the RS sends a hint anyway (e.g., with a client nonce), it's merged with the pinned one...
+/// let received = AuthServerRequestCreationHint {
+///     client_nonce: Some(vec![0xDC, 0xAF]),
+///     ..provisioned.clone()
+/// };
+/// assert_eq!(discovery.resolve(received.clone())?, received);
+///
+/// // ...as long as it doesn't point to a different AS.
+/// let rogue = AuthServerRequestCreationHint {
+///     auth_server: Some("coaps://rogue.example.com/token".to_string()),
+///     ..AuthServerRequestCreationHint::default()
+/// };
+/// assert!(discovery.resolve(rogue).is_err());
+/// # Ok::<(), PinnedHintMismatchError>(())
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub enum AuthServerDiscovery {
+    /// The client first sends an Unauthorized Resource Request to the Resource Server and uses
+    /// the creation hint it receives in response.
+    ///
+    /// This is the default.
+    Dynamic,
+
+    /// The client has been provisioned with the contained hint and uses it to request an access
+    /// token directly, skipping the Unauthorized Resource Request.
+    ///
+    /// The [`auth_server`](AuthServerRequestCreationHint::auth_server) and
+    /// [`audience`](AuthServerRequestCreationHint::audience) of the provisioned hint are pinned,
+    /// i.e., hints received from the Resource Server must not contradict them.
+    Provisioned(AuthServerRequestCreationHint),
+}
+
+impl Default for AuthServerDiscovery {
+    fn default() -> Self {
+        AuthServerDiscovery::Dynamic
+    }
+}
+
+impl AuthServerDiscovery {
+    /// Returns the hint with which an access token can be requested without contacting
+    /// the Resource Server first, which is only the case in
+    /// [`Provisioned`](AuthServerDiscovery::Provisioned) mode.
+    #[must_use]
+    pub fn initial_hint(&self) -> Option<&AuthServerRequestCreationHint> {
+        match self {
+            AuthServerDiscovery::Dynamic => None,
+            AuthServerDiscovery::Provisioned(hint) => Some(hint),
+        }
+    }
+
+    /// Determines the hint to use for requesting an access token after the given hint
+    /// has been `received` from the Resource Server.
+    ///
+    /// In [`Dynamic`](AuthServerDiscovery::Dynamic) mode, this is simply the `received` hint.
+    /// In [`Provisioned`](AuthServerDiscovery::Provisioned) mode, fields of the provisioned hint
+    /// take precedence, while fields only contained in the `received` hint are taken over from it.
+    /// The only exception is the client nonce, for which the `received` one is always used if
+    /// present, as the Resource Server expects its nonce to be echoed back in the token.
+    ///
+    /// # Errors
+    /// In [`Provisioned`](AuthServerDiscovery::Provisioned) mode, if the `received` hint contains
+    /// a different Authorization Server or audience than the provisioned one.
+    pub fn resolve(
+        &self,
+        received: AuthServerRequestCreationHint,
+    ) -> Result<AuthServerRequestCreationHint, PinnedHintMismatchError> {
+        let pinned = match self {
+            AuthServerDiscovery::Dynamic => return Ok(received),
+            AuthServerDiscovery::Provisioned(pinned) => pinned,
+        };
+        check_pinned(
+            "auth_server",
+            pinned.auth_server.as_deref(),
+            received.auth_server.as_deref(),
+        )?;
+        check_pinned(
+            "audience",
+            pinned.audience.as_deref(),
+            received.audience.as_deref(),
+        )?;
+        Ok(AuthServerRequestCreationHint {
+            auth_server: pinned.auth_server.clone().or(received.auth_server),
+            kid: pinned.kid.clone().or(received.kid),
+            audience: pinned.audience.clone().or(received.audience),
+            scope: pinned.scope.clone().or(received.scope),
+            // The nonce must be fresh, so a received one replaces any provisioned one.
+            client_nonce: received
+                .client_nonce
+                .or_else(|| pinned.client_nonce.clone()),
+        })
+    }
+}
+
+/// Returns an error if both the `pinned` and the `received` value of the given `field`
+/// are present, but differ.
+fn check_pinned(
+    field: &'static str,
+    pinned: Option<&str>,
+    received: Option<&str>,
+) -> Result<(), PinnedHintMismatchError> {
+    match (pinned, received) {
+        (Some(pinned), Some(received)) if pinned != received => Err(PinnedHintMismatchError {
+            field,
+            pinned: pinned.to_string(),
+            received: received.to_string(),
+        }),
+        _ => Ok(()),
+    }
+}
+
 #[allow(clippy::unused_self, clippy::unnecessary_wraps)]
 mod builder {
+    use crate::common::text::{TextKind, TextLimits};
+
     use super::*;
 
     impl AuthServerRequestCreationHint {
@@ -115,8 +250,86 @@ mod builder {
     impl AuthServerRequestCreationHintBuilder {
         /// Validates this builder's fields for correctness.
         pub(crate) fn validate(&self) -> Result<(), AuthServerRequestCreationHintBuilderError> {
-            // TODO: Check whether there are invariants to validate
-            Ok(())
+            TextLimits::DEFAULT
+                .check_fields(&[
+                    (
+                        "AS",
+                        self.auth_server.as_ref().and_then(Option::as_deref),
+                        TextKind::Uri,
+                    ),
+                    (
+                        "audience",
+                        self.audience.as_ref().and_then(Option::as_deref),
+                        TextKind::Identifier,
+                    ),
+                ])
+                .map_err(|e| {
+                    AuthServerRequestCreationHintBuilderError::ValidationError(e.to_string())
+                })
+        }
+
+        /// Sets the [`client_nonce`](AuthServerRequestCreationHint::client_nonce) to a new nonce
+        /// of the given `length`, whose bytes are filled in by the given `generate` function
+        /// (which should use a cryptographically secure random number generator).
+        ///
+        /// As described in [section 5.3.1 of RFC 9200](https://www.rfc-editor.org/rfc/rfc9200#section-5.3.1),
+        /// the RS should keep track of the nonce to later check that it's contained in the
+        /// `cnonce` claim of the token, and it's recommended to use at least 8 bytes.
+        ///
+        /// # Example
+        /// ```
+        /// # use dcaf::AuthServerRequestCreationHint;
+        /// # use dcaf::endpoints::creation_hint::AuthServerRequestCreationHintBuilderError;
+        /// let hint = AuthServerRequestCreationHint::builder()
+        ///     // A real RS would use a proper random number generator here.
+        ///     .generated_client_nonce(8, |nonce| nonce.fill(0xDC))
+        ///     .build()?;
+        /// assert_eq!(hint.client_nonce, Some(vec![0xDC; 8]));
+        /// # Ok::<(), AuthServerRequestCreationHintBuilderError>(())
+        /// ```
+        pub fn generated_client_nonce<F>(&mut self, length: usize, generate: F) -> &mut Self
+        where
+            F: FnOnce(&mut [u8]),
+        {
+            let mut nonce = vec![0; length];
+            generate(&mut nonce);
+            self.client_nonce = Some(Some(nonce));
+            self
+        }
+
+        /// Sets the [`kid`](AuthServerRequestCreationHint::kid) to the given key ID if it's
+        /// present, i.e., if a security association with the client already exists.
+        /// Otherwise, the `kid` is left out.
+        pub fn optional_kid(&mut self, kid: Option<ByteString>) -> &mut Self {
+            self.kid = Some(kid);
+            self
+        }
+
+        /// Sets the [`scope`](AuthServerRequestCreationHint::scope) to an AIF-encoded scope
+        /// granting exactly the given `method` on the given `path`, which is intended to be
+        /// taken from the request which has been denied.
+        ///
+        /// # Example
+        /// ```
+        /// # use dcaf::{AifEncodedScope, AuthServerRequestCreationHint, Scope};
+        /// # use dcaf::common::scope::AifRestMethod;
+        /// # use dcaf::endpoints::creation_hint::AuthServerRequestCreationHintBuilderError;
+        /// let hint = AuthServerRequestCreationHint::builder()
+        ///     .scope_for_request("/s/temp", AifRestMethod::Get)
+        ///     .build()?;
+        /// assert_eq!(
+        ///     hint.scope,
+        ///     Some(Scope::from(AifEncodedScope::from(vec![("/s/temp", AifRestMethod::Get.into())])))
+        /// );
+        /// # Ok::<(), AuthServerRequestCreationHintBuilderError>(())
+        /// ```
+        pub fn scope_for_request<T>(&mut self, path: T, method: AifRestMethod) -> &mut Self
+        where
+            T: Into<String>,
+        {
+            let scope = AifEncodedScope::new(vec![AifEncodedScopeElement::new(path, method)]);
+            self.scope = Some(Some(Scope::from(scope)));
+            self
         }
     }
 }
@@ -135,7 +348,7 @@ mod conversion {
     use erased_serde::Serialize as ErasedSerialize;
 
     use crate::common::cbor_map::{cbor_map_vec, decode_scope, ToCborMap};
-    use crate::common::constants::cbor_abbreviations::creation_hint;
+    use crate::common::constants::cbor_abbreviations::{creation_hint, CborKey};
     use crate::error::TryFromCborMapError;
 
     use super::*;
@@ -157,7 +370,7 @@ mod conversion {
         {
             let mut hint = AuthServerRequestCreationHint::builder();
             for entry in map {
-                match (u8::try_from(entry.0)?, entry.1) {
+                match (CborKey::try_from(entry.0)?, entry.1) {
                     (creation_hint::AS, Value::Text(x)) => hint.auth_server(x),
                     (creation_hint::KID, Value::Bytes(x)) => hint.kid(x),
                     (creation_hint::AUDIENCE, Value::Text(x)) => hint.audience(x),
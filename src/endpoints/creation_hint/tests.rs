@@ -73,3 +73,106 @@ fn test_creation_hint_libdcaf_scope() -> Result<(), String> {
         .map_err(|x| x.to_string())?;
     expect_ser_de(hint, None, "A401781C636F6170733A2F2F61732E6578616D706C652E636F6D2F746F6B656E0576636F6170733A2F2F72732E6578616D706C652E636F6D0982672F782F6E6F6E6500182745E0A156BB3F")
 }
+
+#[test]
+fn test_creation_hint_builder_helpers() -> Result<(), String> {
+    let hint = AuthServerRequestCreationHint::builder()
+        .auth_server("coaps://as.example.com/token")
+        .generated_client_nonce(5, |nonce| {
+            nonce.copy_from_slice(&[0xe0, 0xa1, 0x56, 0xbb, 0x3f]);
+        })
+        .optional_kid(None)
+        .scope_for_request("/s/temp", AifRestMethod::Get)
+        .build()
+        .map_err(|x| x.to_string())?;
+    assert_eq!(hint.client_nonce, Some(vec![0xe0, 0xa1, 0x56, 0xbb, 0x3f]));
+    assert_eq!(hint.kid, None);
+    assert_eq!(
+        hint.scope,
+        Some(Scope::from(AifEncodedScope::from(vec![(
+            "/s/temp",
+            make_bitflags!(AifRestMethod::{Get})
+        )])))
+    );
+    expect_ser_de(
+        hint,
+        None,
+        "a301781c636f6170733a2f2f61732e6578616d706c652e636f6d2f746f6b656e098182672f732f74656d7001182745e0a156bb3f",
+    )?;
+
+    let hint = AuthServerRequestCreationHint::builder()
+        .optional_kid(Some(vec![0x01, 0x02]))
+        .build()
+        .map_err(|x| x.to_string())?;
+    assert_eq!(hint.kid, Some(vec![0x01, 0x02]));
+    Ok(())
+}
+
+#[test]
+fn test_discovery_resolve() -> Result<(), PinnedHintMismatchError> {
+    let received = AuthServerRequestCreationHint {
+        auth_server: Some("coaps://as.example.com/token".to_string()),
+        audience: Some("tempSensor4711".to_string()),
+        client_nonce: Some(vec![0xDC, 0xAF]),
+        ..AuthServerRequestCreationHint::default()
+    };
+    assert_eq!(AuthServerDiscovery::default().initial_hint(), None);
+    assert_eq!(
+        AuthServerDiscovery::Dynamic.resolve(received.clone())?,
+        received
+    );
+
+    let pinned = AuthServerRequestCreationHint {
+        auth_server: Some("coaps://as.example.com/token".to_string()),
+        scope: Some(Scope::try_from(vec!["rTempC"]).expect("valid scope")),
+        ..AuthServerRequestCreationHint::default()
+    };
+    let discovery = AuthServerDiscovery::Provisioned(pinned.clone());
+    assert_eq!(discovery.initial_hint(), Some(&pinned));
+    assert_eq!(
+        discovery.resolve(received.clone())?,
+        AuthServerRequestCreationHint {
+            scope: pinned.scope.clone(),
+            ..received.clone()
+        }
+    );
+    assert_eq!(
+        AuthServerDiscovery::Provisioned(AuthServerRequestCreationHint {
+            audience: Some("tempSensor0815".to_string()),
+            ..pinned
+        })
+        .resolve(received),
+        Err(PinnedHintMismatchError {
+            field: "audience",
+            pinned: "tempSensor0815".to_string(),
+            received: "tempSensor4711".to_string()
+        })
+    );
+    Ok(())
+}
+
+#[test]
+fn test_discovery_resolve_client_nonce() -> Result<(), PinnedHintMismatchError> {
+    let pinned = AuthServerRequestCreationHint {
+        auth_server: Some("coaps://as.example.com/token".to_string()),
+        client_nonce: Some(vec![0x01]),
+        ..AuthServerRequestCreationHint::default()
+    };
+    let discovery = AuthServerDiscovery::Provisioned(pinned.clone());
+    let received = AuthServerRequestCreationHint {
+        client_nonce: Some(vec![0xDC, 0xAF]),
+        ..AuthServerRequestCreationHint::default()
+    };
+    assert_eq!(
+        discovery.resolve(received)?.client_nonce,
+        Some(vec![0xDC, 0xAF])
+    );
+    // Without a received nonce, the provisioned one is kept.
+    assert_eq!(
+        discovery
+            .resolve(AuthServerRequestCreationHint::default())?
+            .client_nonce,
+        pinned.client_nonce
+    );
+    Ok(())
+}
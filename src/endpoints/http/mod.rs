@@ -0,0 +1,149 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+//! Contains helpers for transporting CBOR access tokens over HTTP, e.g., for gateways which
+//! bridge CoAP Resource Servers behind HTTP reverse proxies.
+//!
+//! HTTP headers can only contain text, so the binary token has to be rendered using a
+//! [`TokenEncoding`] before it's put into an `Authorization` header, which consists of an
+//! authentication scheme (e.g., [`BEARER_SCHEME`] or [`ACE_SCHEME`]) followed by the encoded
+//! token. [`Base64Url`] (the default) and [`Hex`] encodings are provided, while applications can
+//! implement [`TokenEncoding`] themselves to support others.
+//!
+//! # Example
+//! ```
+//! # use dcaf::error::HttpTokenError;
+//! use dcaf::endpoints::http::{
+//!     parse_authorization, render_authorization, Base64Url, Hex, ACE_SCHEME, BEARER_SCHEME,
+//! };
+//!
+//! let token = vec![0xDC, 0xAF, 0x01];
+//! let header = render_authorization(&token, BEARER_SCHEME, &Base64Url);
+//! assert_eq!(header, "Bearer 3K8B");
+//! assert_eq!(parse_authorization(&header, BEARER_SCHEME, &Base64Url)?, token);
+//!
+//! let header = render_authorization(&token, ACE_SCHEME, &Hex);
+//! assert_eq!(header, "ACE dcaf01");
+//! // Authentication schemes are compared case-insensitively.
+//! assert_eq!(parse_authorization("ace DCAF01", ACE_SCHEME, &Hex)?, token);
+//! assert_eq!(
+//!     parse_authorization(&header, BEARER_SCHEME, &Hex),
+//!     Err(HttpTokenError::UnexpectedScheme("ACE".to_string()))
+//! );
+//! # Ok::<(), HttpTokenError>(())
+//! ```
+
+use crate::common::cbor_values::ByteString;
+use crate::common::encoding::{decode_base64url, decode_hex, encode_base64url, encode_hex};
+use crate::error::HttpTokenError;
+
+#[cfg(not(feature = "std"))]
+use {
+    alloc::format,
+    alloc::string::{String, ToString},
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The `Bearer` authentication scheme of
+/// [RFC 6750](https://www.rfc-editor.org/rfc/rfc6750#section-2.1).
+pub const BEARER_SCHEME: &str = "Bearer";
+
+/// The `ACE` authentication scheme, used by deployments which want to distinguish ACE-OAuth
+/// proof-of-possession tokens from bearer tokens.
+pub const ACE_SCHEME: &str = "ACE";
+
+/// A text encoding for binary access tokens, as used by [`render_authorization`] and
+/// [`parse_authorization`].
+pub trait TokenEncoding {
+    /// Encodes the given `token` as text.
+    fn encode(&self, token: &[u8]) -> String;
+
+    /// Decodes the given `text` into a token, returning `None` if it's not validly encoded.
+    fn decode(&self, text: &str) -> Option<ByteString>;
+}
+
+/// Encodes tokens using base64url without padding, as specified in
+/// [section 5 of RFC 4648](https://www.rfc-editor.org/rfc/rfc4648#section-5).
+///
+/// When decoding, trailing padding characters are accepted.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash, Default)]
+pub struct Base64Url;
+
+impl TokenEncoding for Base64Url {
+    fn encode(&self, token: &[u8]) -> String {
+        encode_base64url(token)
+    }
+
+    fn decode(&self, text: &str) -> Option<ByteString> {
+        decode_base64url(text.trim_end_matches('='))
+    }
+}
+
+/// Encodes tokens using lowercase hexadecimal digits.
+///
+/// When decoding, both lowercase and uppercase digits are accepted.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash, Default)]
+pub struct Hex;
+
+impl TokenEncoding for Hex {
+    fn encode(&self, token: &[u8]) -> String {
+        encode_hex(token)
+    }
+
+    fn decode(&self, text: &str) -> Option<ByteString> {
+        decode_hex(text)
+    }
+}
+
+/// Renders the given `token` as the value of an HTTP `Authorization` header, using the given
+/// authentication `scheme` and `encoding`.
+///
+/// For an example, see the [module-level documentation](self).
+#[must_use]
+pub fn render_authorization<E>(token: &[u8], scheme: &str, encoding: &E) -> String
+where
+    E: TokenEncoding,
+{
+    format!("{scheme} {}", encoding.encode(token))
+}
+
+/// Parses the token from the given value of an HTTP `Authorization` `header`, which has to use
+/// the given authentication `scheme` (compared case-insensitively) and `encoding`.
+///
+/// For an example, see the [module-level documentation](self).
+///
+/// # Errors
+/// - If the header doesn't use the given `scheme`.
+/// - If the header contains no token after the scheme.
+/// - If the token isn't validly encoded according to `encoding`.
+pub fn parse_authorization<E>(
+    header: &str,
+    scheme: &str,
+    encoding: &E,
+) -> Result<ByteString, HttpTokenError>
+where
+    E: TokenEncoding,
+{
+    let header = header.trim();
+    let (actual_scheme, credentials) = header.split_once(' ').unwrap_or((header, ""));
+    if !actual_scheme.eq_ignore_ascii_case(scheme) {
+        return Err(HttpTokenError::UnexpectedScheme(actual_scheme.to_string()));
+    }
+    let credentials = credentials.trim_start();
+    if credentials.is_empty() {
+        return Err(HttpTokenError::MissingToken);
+    }
+    encoding
+        .decode(credentials)
+        .ok_or(HttpTokenError::InvalidEncoding)
+}
@@ -0,0 +1,73 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+#[cfg(not(feature = "std"))]
+use {alloc::string::ToString, alloc::vec::Vec};
+
+use super::*;
+
+#[test]
+fn test_roundtrip() {
+    for length in 1..10_u8 {
+        let token: Vec<u8> = (0..length)
+            .map(|x| x.wrapping_mul(97).wrapping_add(13))
+            .collect();
+        for scheme in [BEARER_SCHEME, ACE_SCHEME] {
+            let header = render_authorization(&token, scheme, &Base64Url);
+            assert_eq!(
+                parse_authorization(&header, scheme, &Base64Url),
+                Ok(token.clone())
+            );
+            let header = render_authorization(&token, scheme, &Hex);
+            assert_eq!(
+                parse_authorization(&header, scheme, &Hex),
+                Ok(token.clone())
+            );
+        }
+    }
+}
+
+#[test]
+fn test_encodings() {
+    assert_eq!(Base64Url.encode(&[0xFB, 0xFF]), "-_8");
+    assert_eq!(Base64Url.decode("-_8="), Some(vec![0xFB, 0xFF]));
+    assert_eq!(Base64Url.decode("+/8"), None);
+    assert_eq!(Hex.encode(&[0x0A, 0xFF]), "0aff");
+    assert_eq!(Hex.decode("0AfF"), Some(vec![0x0A, 0xFF]));
+    assert_eq!(Hex.decode("0af"), None);
+    assert_eq!(Hex.decode("zz"), None);
+    assert_eq!(Hex.decode("+f"), None);
+}
+
+#[test]
+fn test_parse_errors() {
+    assert_eq!(
+        parse_authorization("Basic dXNlcjpwYXNz", BEARER_SCHEME, &Base64Url),
+        Err(HttpTokenError::UnexpectedScheme("Basic".to_string()))
+    );
+    assert_eq!(
+        parse_authorization("Bearer", BEARER_SCHEME, &Base64Url),
+        Err(HttpTokenError::MissingToken)
+    );
+    // Empty tokens can't be distinguished from missing ones.
+    assert_eq!(
+        parse_authorization("Bearer   ", BEARER_SCHEME, &Base64Url),
+        Err(HttpTokenError::MissingToken)
+    );
+    assert_eq!(
+        parse_authorization("Bearer 3K8B!", BEARER_SCHEME, &Base64Url),
+        Err(HttpTokenError::InvalidEncoding)
+    );
+    assert_eq!(
+        parse_authorization("  bearer  3K8B ", BEARER_SCHEME, &Base64Url),
+        Ok(vec![0xDC, 0xAF, 0x01])
+    );
+}
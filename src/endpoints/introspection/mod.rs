@@ -0,0 +1,851 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+//! Contains the data models for messages exchanged with the introspection endpoint, as well as
+//! functions for protecting them, as described in
+//! [section 5.9 of RFC 9200](https://www.rfc-editor.org/rfc/rfc9200#section-5.9).
+//!
+//! The Resource Server sends an [`IntrospectionRequest`] containing a token to the Authorization
+//! Server, which responds with an [`IntrospectionResponse`] describing the token's state (or with
+//! an [`ErrorResponse`](crate::ErrorResponse) if the request was malformed or unauthorized).
+//!
+//! Usually, introspection requests are protected by the secure channel between the Resource Server
+//! and the Authorization Server. If this channel traverses proxies, the Resource Server can
+//! additionally wrap the request into a `COSE_Sign1` ([`sign_introspection_request`]) or a
+//! `COSE_Mac0` ([`tag_introspection_request`]) structure using its own credentials, which the
+//! Authorization Server then checks using [`verify_introspection_request`] or
+//! [`verify_introspection_request_tag`], respectively. The Authorization Server can use
+//! [`get_token_headers`](crate::get_token_headers) to find out which key (identified by the `kid`
+//! in the headers) it needs to use for this.
+//!
+//! It's recommended to pass the URI of the introspection endpoint as the `aad`,
+//! so that protected requests can't be redirected to another endpoint.
+//!
+//! The functions in this module work for any message implementing [`ToCborMap`].
+//!
+//! # Key Transport
+//! Resource Servers which only receive opaque token references can't read the proof-of-possession
+//! key from the token itself, so the Authorization Server includes it in the `cnf` parameter of
+//! the introspection response instead, as described in
+//! [section 5.9.2 of RFC 9200](https://www.rfc-editor.org/rfc/rfc9200#section-5.9.2).
+//! Symmetric keys should be wrapped for the Resource Server using [`encrypt_confirmation_key`],
+//! and the Resource Server retrieves the key using [`decrypt_confirmation_key`].
+//!
+//! # Claims Filters
+//! The claims the Authorization Server returns in introspection responses end up at the Resource
+//! Server, and often in its logs. To keep internal claims (e.g., tenant tags) out of them,
+//! Authorization Server operators can register [`ClaimsFilter`]s in an [`IntrospectionFilters`]
+//! registry, which strip or transform the claims before they're returned. [`StripClaims`] covers
+//! the common case of simply removing some claims:
+//! ```
+//! # use ciborium::value::Value;
+//! # use coset::cwt::{ClaimName, ClaimsSetBuilder};
+//! use dcaf::endpoints::introspection::{IntrospectionFilters, StripClaims};
+//!
+//! let mut filters = IntrospectionFilters::new();
+//! filters.register(StripClaims::new(vec![ClaimName::Text("tenant".to_string())]));
+//! let claims = ClaimsSetBuilder::new()
+//!     .audience("valve242".to_string())
+//!     .text_claim("tenant".to_string(), Value::from("acme"))
+//!     .build();
+//! assert_eq!(
+//!     filters.apply(claims),
+//!     ClaimsSetBuilder::new().audience("valve242".to_string()).build()
+//! );
+//! ```
+//!
+//! # Example
+//! Assuming a cipher implementing [`CoseSign1Cipher`](crate::CoseSign1Cipher) exists in `cipher`,
+//! and a message we want to protect in `request`:
+//! ```
+//! # use coset::Header;
+//! # use dcaf::{AuthServerRequestCreationHint, CoseCipherCommon, CoseSign1Cipher};
+//! # use dcaf::error::{CoseCipherError, ProtectedMessageError};
+//! use dcaf::endpoints::introspection::{sign_introspection_request, verify_introspection_request};
+//! # struct FakeSigner {}
+//! # impl CoseCipherCommon for FakeSigner {
+//! #     type Error = String;
+//! #     fn header(&self, unprotected_header: &mut Header, protected_header: &mut Header) -> Result<(), CoseCipherError<Self::Error>> {
+//! #         Ok(())
+//! #     }
+//! # }
+//! # impl CoseSign1Cipher for FakeSigner {
+//! #     fn generate_signature(&mut self, target: &[u8]) -> Vec<u8> {
+//! #         target.to_vec()
+//! #     }
+//! #     fn verify_signature(&mut self, signature: &[u8], signed_data: &[u8]) -> Result<(), CoseCipherError<Self::Error>> {
+//! #         if signature == signed_data { Ok(()) } else { Err(CoseCipherError::VerificationFailure) }
+//! #     }
+//! # }
+//! # let mut cipher = FakeSigner {};
+//! # let request = AuthServerRequestCreationHint::default();
+//! let aad = b"coaps://as.example.com/introspect";
+//! let protected = sign_introspection_request(&request, &mut cipher, Some(aad), None, None)?;
+//! // ...which is then sent to the AS, which verifies it:
+//! let received: AuthServerRequestCreationHint =
+//!     verify_introspection_request(&protected, &mut cipher, Some(aad))?;
+//! assert_eq!(received, request);
+//! # Ok::<(), ProtectedMessageError<String>>(())
+//! ```
+
+use core::fmt::{Debug, Formatter};
+
+use ciborium::value::Value;
+use coset::cwt::{ClaimName, ClaimsSet, Timestamp};
+use coset::iana::CwtClaimName;
+use coset::{
+    CborSerializable, CoseEncrypt0Builder, CoseError, CoseKey, CoseMac0, CoseMac0Builder,
+    CoseSign1, CoseSign1Builder, Header,
+};
+
+use crate::common::cbor_map::ToCborMap;
+use crate::common::cbor_values::{ccs_cose_key, ByteString, ProofOfPossessionKey};
+use crate::common::scope::Scope;
+use crate::endpoints::token_req::{AceProfile, TokenType};
+use crate::error::{AccessTokenError, ProtectedMessageError, TryFromCborMapError};
+#[cfg(feature = "as")]
+use crate::token::storage::TokenReferenceStore;
+use crate::token::{
+    prepare_headers, CoseDecryptCipher, CoseEncryptCipher, CoseMac0Cipher, CoseSignCipher,
+    CoseVerifyCipher,
+};
+
+#[cfg(not(feature = "std"))]
+use {
+    alloc::boxed::Box, alloc::format, alloc::string::String, alloc::string::ToString,
+    alloc::vec::Vec,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Signs the given `request` with the given headers and `aad` using `cipher` for cryptography,
+/// returning the serialized [`CoseSign1`] structure containing it.
+///
+/// For an example, see the [module-level documentation](self).
+///
+/// # Errors
+/// - When the `cipher` fails to set its headers.
+/// - When there's a [`CoseError`] while serializing the `request` or the [`CoseSign1`] structure.
+pub fn sign_introspection_request<M, T>(
+    request: &M,
+    cipher: &mut T,
+    aad: Option<&[u8]>,
+    unprotected_header: Option<Header>,
+    protected_header: Option<Header>,
+) -> Result<ByteString, ProtectedMessageError<T::Error>>
+where
+    M: ToCborMap,
+    T: CoseSignCipher,
+{
+    let (unprotected, protected) = prepare_headers(unprotected_header, protected_header, cipher)?;
+    CoseSign1Builder::new()
+        .unprotected(unprotected)
+        .protected(protected)
+        .payload(encode_payload(request)?)
+        .create_signature(aad.unwrap_or(&[0; 0]), |x| cipher.generate_signature(x))
+        .build()
+        .to_vec()
+        .map_err(|e| AccessTokenError::from_cose_error(e).into())
+}
+
+/// Verifies the given signed `request` and `aad` using `cipher` for cryptography,
+/// returning the contained message if the signature is valid.
+///
+/// For an example, see the [module-level documentation](self).
+///
+/// # Errors
+/// - When there's a [`CoseError`] while deserializing the given `request`
+///   to a [`CoseSign1`] structure.
+/// - When the `cipher` can't verify the signature.
+/// - When the payload isn't a valid message of type `M`.
+pub fn verify_introspection_request<M, T>(
+    request: &[u8],
+    cipher: &mut T,
+    aad: Option<&[u8]>,
+) -> Result<M, ProtectedMessageError<T::Error>>
+where
+    M: ToCborMap,
+    T: CoseVerifyCipher,
+{
+    let sign = CoseSign1::from_slice(request).map_err(AccessTokenError::from_cose_error)?;
+    sign.verify_signature(aad.unwrap_or(&[0; 0]), |signature, signed_data| {
+        cipher.verify_signature(signature, signed_data)
+    })
+    .map_err(AccessTokenError::from_cose_cipher_error)?;
+    decode_payload(sign.payload)
+}
+
+/// Protects the given `request` with a MAC tag over it and the given headers and `aad`,
+/// using `cipher` for cryptography, returning the serialized [`CoseMac0`] structure containing it.
+///
+/// # Errors
+/// - When the `cipher` fails to set its headers.
+/// - When there's a [`CoseError`] while serializing the `request` or the [`CoseMac0`] structure.
+pub fn tag_introspection_request<M, T>(
+    request: &M,
+    cipher: &mut T,
+    aad: Option<&[u8]>,
+    unprotected_header: Option<Header>,
+    protected_header: Option<Header>,
+) -> Result<ByteString, ProtectedMessageError<T::Error>>
+where
+    M: ToCborMap,
+    T: CoseMac0Cipher,
+{
+    let (unprotected, protected) = prepare_headers(unprotected_header, protected_header, cipher)?;
+    CoseMac0Builder::new()
+        .unprotected(unprotected)
+        .protected(protected)
+        .payload(encode_payload(request)?)
+        .create_tag(aad.unwrap_or(&[0; 0]), |x| cipher.generate_tag(x))
+        .build()
+        .to_vec()
+        .map_err(|e| AccessTokenError::from_cose_error(e).into())
+}
+
+/// Verifies the MAC tag of the given `request` and `aad` using `cipher` for cryptography,
+/// returning the contained message if the tag is valid.
+///
+/// # Errors
+/// - When there's a [`CoseError`] while deserializing the given `request`
+///   to a [`CoseMac0`] structure.
+/// - When the `cipher` can't verify the tag.
+/// - When the payload isn't a valid message of type `M`.
+pub fn verify_introspection_request_tag<M, T>(
+    request: &[u8],
+    cipher: &mut T,
+    aad: Option<&[u8]>,
+) -> Result<M, ProtectedMessageError<T::Error>>
+where
+    M: ToCborMap,
+    T: CoseMac0Cipher,
+{
+    let mac = CoseMac0::from_slice(request).map_err(AccessTokenError::from_cose_error)?;
+    mac.verify_tag(aad.unwrap_or(&[0; 0]), |tag, maced_data| {
+        cipher.verify_tag(tag, maced_data)
+    })
+    .map_err(AccessTokenError::from_cose_cipher_error)?;
+    decode_payload(mac.payload)
+}
+
+/// Encrypts the given proof-of-possession `key` for the Resource Server with the given headers
+/// and `aad` using `cipher` for cryptography, returning an
+/// [`EncryptedCoseKey`](ProofOfPossessionKey::EncryptedCoseKey) intended for the `cnf` parameter
+/// of an introspection response.
+///
+/// For details, see [section 3.3 of RFC 8747](https://datatracker.ietf.org/doc/html/rfc8747#section-3.3).
+///
+/// # Errors
+/// - When the `cipher` fails to set its headers.
+/// - When there's a [`CoseError`] while serializing the `key`.
+pub fn encrypt_confirmation_key<T>(
+    key: &CoseKey,
+    cipher: &mut T,
+    aad: Option<&[u8]>,
+    unprotected_header: Option<Header>,
+    protected_header: Option<Header>,
+) -> Result<ProofOfPossessionKey, AccessTokenError<T::Error>>
+where
+    T: CoseEncryptCipher,
+{
+    let (unprotected, protected) = prepare_headers(unprotected_header, protected_header, cipher)?;
+    let plaintext = key
+        .clone()
+        .to_vec()
+        .map_err(AccessTokenError::from_cose_error)?;
+    Ok(ProofOfPossessionKey::EncryptedCoseKey(
+        CoseEncrypt0Builder::new()
+            .unprotected(unprotected)
+            .protected(protected)
+            .create_ciphertext(&plaintext, aad.unwrap_or(&[0; 0]), |payload, aad| {
+                cipher.encrypt(payload, aad)
+            })
+            .build(),
+    ))
+}
+
+/// Retrieves the proof-of-possession key from the given `cnf` parameter of an introspection
+/// response, decrypting it with the given `aad` using `cipher` if the Authorization Server
+/// has encrypted it for the Resource Server (see [`encrypt_confirmation_key`]).
+///
+/// Keys contained in a CWT Claims Set are returned as well, while `cipher` is only used for
+/// encrypted keys.
+///
+/// # Errors
+/// - When the `cnf` only references the key by its key ID or thumbprint, as it then doesn't
+///   contain any key material. In this case, the Resource Server needs to look up the key itself.
+/// - When there's a decryption error coming from the `cipher`.
+/// - When the decrypted data or the CWT Claims Set doesn't contain a valid [`CoseKey`].
+pub fn decrypt_confirmation_key<T>(
+    cnf: &ProofOfPossessionKey,
+    cipher: &mut T,
+    aad: Option<&[u8]>,
+) -> Result<CoseKey, AccessTokenError<T::Error>>
+where
+    T: CoseDecryptCipher,
+{
+    match cnf {
+        ProofOfPossessionKey::PlainCoseKey(key) => Ok(key.clone()),
+        ProofOfPossessionKey::EncryptedCoseKey(encrypted) => {
+            let plaintext = encrypted
+                .decrypt(aad.unwrap_or(&[0; 0]), |ciphertext, aad| {
+                    cipher.decrypt(ciphertext, aad)
+                })
+                .map_err(AccessTokenError::from_cose_cipher_error)?;
+            CoseKey::from_slice(&plaintext).map_err(AccessTokenError::from_cose_error)
+        }
+        ProofOfPossessionKey::Ccs(ccs) => ccs_cose_key(ccs).ok_or_else(|| {
+            AccessTokenError::from_cose_error(CoseError::UnexpectedItem("CCS", "COSE_Key"))
+        }),
+        ProofOfPossessionKey::KeyId(_) | ProofOfPossessionKey::KeyThumbprint(_) => {
+            Err(AccessTokenError::from_cose_error(
+                CoseError::UnexpectedItem("key reference", "COSE_Key"),
+            ))
+        }
+        #[cfg(feature = "oscore-profile")]
+        ProofOfPossessionKey::OscoreInputMaterial(_) => Err(AccessTokenError::from_cose_error(
+            CoseError::UnexpectedItem("OSCORE input material", "COSE_Key"),
+        )),
+    }
+}
+
+/// Strips or transforms the claims of an access token before the Authorization Server returns them
+/// in an introspection response.
+///
+/// Filters are registered in an [`IntrospectionFilters`] registry. Any closure taking and
+/// returning a [`ClaimsSet`] can be used as a filter.
+///
+/// For an example, see the [module-level documentation](self).
+pub trait ClaimsFilter {
+    /// Returns the given `claims` as they should be returned to the Resource Server.
+    fn filter(&self, claims: ClaimsSet) -> ClaimsSet;
+}
+
+impl<F> ClaimsFilter for F
+where
+    F: Fn(ClaimsSet) -> ClaimsSet,
+{
+    fn filter(&self, claims: ClaimsSet) -> ClaimsSet {
+        self(claims)
+    }
+}
+
+/// A [`ClaimsFilter`] which removes the claims with the given names.
+///
+/// For an example, see the [module-level documentation](self).
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct StripClaims {
+    /// The names of the claims to remove.
+    names: Vec<ClaimName>,
+}
+
+impl StripClaims {
+    /// Creates a new filter removing the claims with the given `names`.
+    ///
+    /// Both registered claims (e.g., `sub`) and private ones (e.g., internal tenant tags)
+    /// can be removed.
+    #[must_use]
+    pub fn new(names: Vec<ClaimName>) -> StripClaims {
+        StripClaims { names }
+    }
+}
+
+impl ClaimsFilter for StripClaims {
+    fn filter(&self, mut claims: ClaimsSet) -> ClaimsSet {
+        for name in &self.names {
+            match name {
+                ClaimName::Assigned(CwtClaimName::Iss) => claims.issuer = None,
+                ClaimName::Assigned(CwtClaimName::Sub) => claims.subject = None,
+                ClaimName::Assigned(CwtClaimName::Aud) => claims.audience = None,
+                ClaimName::Assigned(CwtClaimName::Exp) => claims.expiration_time = None,
+                ClaimName::Assigned(CwtClaimName::Nbf) => claims.not_before = None,
+                ClaimName::Assigned(CwtClaimName::Iat) => claims.issued_at = None,
+                ClaimName::Assigned(CwtClaimName::Cti) => claims.cwt_id = None,
+                _ => claims.rest.retain(|(x, _)| x != name),
+            }
+        }
+        claims
+    }
+}
+
+/// The [`ClaimsFilter`]s the Authorization Server applies to the claims of introspection
+/// responses, in the order in which they have been registered.
+///
+/// For an example, see the [module-level documentation](self).
+#[derive(Default)]
+pub struct IntrospectionFilters {
+    /// The registered filters.
+    filters: Vec<Box<dyn ClaimsFilter>>,
+}
+
+impl Debug for IntrospectionFilters {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("IntrospectionFilters")
+            .field("filters", &self.filters.len())
+            .finish()
+    }
+}
+
+impl IntrospectionFilters {
+    /// Creates a new registry without any filters, which returns all claims unchanged.
+    #[must_use]
+    pub fn new() -> IntrospectionFilters {
+        IntrospectionFilters::default()
+    }
+
+    /// Registers the given `filter`, which is applied after all previously registered ones.
+    pub fn register<T>(&mut self, filter: T)
+    where
+        T: ClaimsFilter + 'static,
+    {
+        self.filters.push(Box::new(filter));
+    }
+
+    /// Returns the number of registered filters.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.filters.len()
+    }
+
+    /// Returns whether no filters have been registered.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+
+    /// Applies all registered filters to the given `claims`, returning the claims which should
+    /// be included in the introspection response.
+    #[must_use]
+    pub fn apply(&self, claims: ClaimsSet) -> ClaimsSet {
+        self.filters
+            .iter()
+            .fold(claims, |claims, filter| filter.filter(claims))
+    }
+
+    /// Looks up the claims of the given token `reference` in the `store` and applies all
+    /// registered filters to them, returning `None` if the reference is unknown.
+    ///
+    /// # Errors
+    /// If the `store` fails to look up the reference.
+    #[cfg(feature = "as")]
+    pub fn resolve<S>(&self, store: &S, reference: &[u8]) -> Result<Option<ClaimsSet>, S::Error>
+    where
+        S: TokenReferenceStore,
+    {
+        Ok(store
+            .resolve_token_reference(reference)?
+            .map(|claims| self.apply(claims)))
+    }
+}
+
+/// Serializes the given `message` to the payload of a COSE structure.
+fn encode_payload<M, E>(message: &M) -> Result<Vec<u8>, ProtectedMessageError<E>>
+where
+    M: ToCborMap,
+    E: core::fmt::Display,
+{
+    let mut payload = Vec::new();
+    ciborium::ser::into_writer(&message.to_ciborium_value(), &mut payload)
+        .map_err(|_| AccessTokenError::from_cose_error(CoseError::EncodeFailed))?;
+    Ok(payload)
+}
+
+/// Deserializes the given (verified) `payload` of a COSE structure to a message.
+fn decode_payload<M, E>(payload: Option<Vec<u8>>) -> Result<M, ProtectedMessageError<E>>
+where
+    M: ToCborMap,
+    E: core::fmt::Display,
+{
+    let payload = payload.ok_or_else(|| {
+        TryFromCborMapError::from_message("protected message contains no payload")
+    })?;
+    let value: Value = ciborium::de::from_reader(payload.as_slice())
+        .map_err(|e| TryFromCborMapError::from_message(e.to_string()))?;
+    Ok(M::from_ciborium_value(value)?)
+}
+
+/// Request sent by the Resource Server to the introspection endpoint of the Authorization Server,
+/// as described in [section 5.9.1 of RFC 9200](https://www.rfc-editor.org/rfc/rfc9200#section-5.9.1).
+///
+/// Use the [`IntrospectionRequestBuilder`] (which you can access using the
+/// [`builder()`](IntrospectionRequest::builder) method) to create an instance of this struct.
+/// Note that the [`token`](IntrospectionRequest::token) is required.
+///
+/// # Example
+/// ```
+/// # use dcaf::endpoints::introspection::IntrospectionRequest;
+/// # use dcaf::endpoints::message::AceMessage;
+/// # #[cfg(feature = "std")] {
+/// let request = IntrospectionRequest::builder()
+///     .token(vec![0xDC, 0xAF])
+///     .token_type_hint("access_token")
+///     .build()?;
+/// let encoded = request.clone().encode()?;
+/// assert_eq!(IntrospectionRequest::decode(&encoded)?, request);
+/// # }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Hash, Builder)]
+#[builder(no_std, setter(into, strip_option), derive(Debug, PartialEq, Eq))]
+pub struct IntrospectionRequest {
+    /// The (possibly opaque) token whose state shall be introspected.
+    pub token: ByteString,
+
+    /// A hint about the type of the token, e.g., `access_token`, as described in
+    /// [section 2.1 of RFC 7662](https://www.rfc-editor.org/rfc/rfc7662#section-2.1).
+    #[builder(default)]
+    pub token_type_hint: Option<String>,
+}
+
+/// Response of the introspection endpoint of the Authorization Server, describing the state
+/// of the introspected token, as described in
+/// [section 5.9.2 of RFC 9200](https://www.rfc-editor.org/rfc/rfc9200#section-5.9.2).
+///
+/// Use the [`IntrospectionResponseBuilder`] (which you can access using the
+/// [`builder()`](IntrospectionResponse::builder) method) to create an instance of this struct.
+/// Note that [`active`](IntrospectionResponse::active) is required. Responses for inactive
+/// tokens shouldn't contain any other fields, so [`inactive()`](IntrospectionResponse::inactive)
+/// can be used to create them instead.
+///
+/// Errors are returned as an [`ErrorResponse`](crate::ErrorResponse).
+///
+/// # Example
+/// ```
+/// # use coset::cwt::Timestamp;
+/// # use dcaf::{AceProfile, ProofOfPossessionKey};
+/// # use dcaf::endpoints::introspection::IntrospectionResponse;
+/// # use dcaf::endpoints::message::AceMessage;
+/// # #[cfg(feature = "std")] {
+/// let response = IntrospectionResponse::builder()
+///     .active(true)
+///     .audience("tempSensor4711")
+///     .expiration_time(Timestamp::WholeSeconds(1_700_000_000))
+///     .ace_profile(AceProfile::CoapDtls)
+///     .cnf(ProofOfPossessionKey::KeyId(vec![0x42]))
+///     .build()?;
+/// let encoded = response.clone().encode()?;
+/// assert_eq!(IntrospectionResponse::decode(&encoded)?, response);
+/// assert!(!IntrospectionResponse::inactive().active);
+/// # }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, PartialEq, Default, Clone, Builder)]
+#[builder(no_std, setter(into, strip_option), derive(Debug, PartialEq))]
+pub struct IntrospectionResponse {
+    /// Whether the token is currently active.
+    ///
+    /// Must be included.
+    pub active: bool,
+
+    /// The scope of the token.
+    ///
+    /// See the documentation of [`Scope`] for details.
+    #[builder(default)]
+    pub scope: Option<Scope>,
+
+    /// The identifier of the client the token has been issued to.
+    #[builder(default)]
+    pub client_id: Option<String>,
+
+    /// A human-readable identifier of the resource owner who authorized the token.
+    #[builder(default)]
+    pub username: Option<String>,
+
+    /// The type of the token.
+    ///
+    /// See the documentation of [`TokenType`] for details.
+    #[builder(default)]
+    pub token_type: Option<TokenType>,
+
+    /// The time at which the token expires.
+    #[builder(default)]
+    pub expiration_time: Option<Timestamp>,
+
+    /// The time before which the token must not be accepted.
+    #[builder(default)]
+    pub not_before: Option<Timestamp>,
+
+    /// The time at which the token has been issued.
+    #[builder(default)]
+    pub issued_at: Option<Timestamp>,
+
+    /// The subject of the token, usually a machine-readable identifier of the resource owner.
+    #[builder(default)]
+    pub subject: Option<String>,
+
+    /// The audience the token is intended for.
+    #[builder(default)]
+    pub audience: Option<String>,
+
+    /// The issuer of the token.
+    #[builder(default)]
+    pub issuer: Option<String>,
+
+    /// The unique identifier of the token.
+    #[builder(default)]
+    pub cwt_id: Option<ByteString>,
+
+    /// The profile the client must use towards the Resource Server.
+    ///
+    /// See the documentation of [`AceProfile`] for details.
+    #[builder(default)]
+    pub ace_profile: Option<AceProfile>,
+
+    /// The client nonce the Resource Server included in its creation hint, if the token has
+    /// been requested with one.
+    #[builder(default)]
+    pub client_nonce: Option<ByteString>,
+
+    /// The proof-of-possession key bound to the token (see the
+    /// [section on key transport](self#key-transport)).
+    ///
+    /// See the documentation of [`ProofOfPossessionKey`] for details.
+    #[builder(default)]
+    pub cnf: Option<ProofOfPossessionKey>,
+
+    /// The key the Resource Server uses to authenticate towards the client.
+    ///
+    /// See the documentation of [`ProofOfPossessionKey`] for details.
+    #[builder(default)]
+    pub rs_cnf: Option<ProofOfPossessionKey>,
+
+    /// The number of seconds the token is valid after the Resource Server first received it,
+    /// as described in [section 5.10.3 of RFC 9200](https://www.rfc-editor.org/rfc/rfc9200#section-5.10.3).
+    #[builder(default)]
+    pub exi: Option<u32>,
+}
+
+mod builder {
+    use super::*;
+
+    impl IntrospectionRequest {
+        /// Initializes and returns a new [`IntrospectionRequestBuilder`].
+        #[must_use]
+        pub fn builder() -> IntrospectionRequestBuilder {
+            IntrospectionRequestBuilder::default()
+        }
+    }
+
+    impl IntrospectionResponse {
+        /// Initializes and returns a new [`IntrospectionResponseBuilder`].
+        #[must_use]
+        pub fn builder() -> IntrospectionResponseBuilder {
+            IntrospectionResponseBuilder::default()
+        }
+
+        /// Returns a response for an inactive token, which contains no further information.
+        #[must_use]
+        pub fn inactive() -> IntrospectionResponse {
+            IntrospectionResponse::default()
+        }
+    }
+}
+
+impl IntrospectionResponse {
+    /// Returns a response for an active token with the given `claims`, which are passed through
+    /// the given `filters` first (see the [section on claims filters](self#claims-filters)).
+    ///
+    /// The claims which have a corresponding response parameter (`iss`, `sub`, `aud`, `exp`,
+    /// `nbf`, `iat`, `cti`, `cnf`, `scope`, `ace_profile`, `cnonce`, and `exi`) are copied into
+    /// the response, all others are omitted.
+    ///
+    /// # Example
+    /// ```
+    /// # use coset::cwt::{ClaimName, ClaimsSetBuilder};
+    /// # use coset::iana::CwtClaimName;
+    /// # use dcaf::error::TryFromCborMapError;
+    /// use dcaf::endpoints::introspection::{IntrospectionFilters, IntrospectionResponse, StripClaims};
+    ///
+    /// let mut filters = IntrospectionFilters::new();
+    /// filters.register(StripClaims::new(vec![ClaimName::Assigned(CwtClaimName::Sub)]));
+    /// let claims = ClaimsSetBuilder::new()
+    ///     .subject("client1".to_string())
+    ///     .audience("valve242".to_string())
+    ///     .build();
+    /// let response = IntrospectionResponse::from_claims(claims, &filters)?;
+    /// assert!(response.active);
+    /// assert_eq!(response.audience.as_deref(), Some("valve242"));
+    /// assert_eq!(response.subject, None);
+    /// # Ok::<(), TryFromCborMapError>(())
+    /// ```
+    ///
+    /// # Errors
+    /// If one of the copied claims has a value which isn't valid for the corresponding
+    /// response parameter (e.g., a malformed `scope`).
+    pub fn from_claims(
+        claims: ClaimsSet,
+        filters: &IntrospectionFilters,
+    ) -> Result<IntrospectionResponse, TryFromCborMapError> {
+        conversion::from_claims(filters.apply(claims))
+    }
+}
+
+mod conversion {
+    use coset::AsCborValue;
+    use erased_serde::Serialize as ErasedSerialize;
+
+    use crate::common::cbor_map::{cbor_map_vec, decode_cnf, decode_number, decode_scope};
+    use crate::common::cbor_values::CborMapValue;
+    use crate::constants::cbor_abbreviations::{introspection, CborKey};
+
+    use super::*;
+
+    /// Encodes the given `timestamp` as a CBOR value.
+    fn encode_timestamp(timestamp: &Timestamp) -> Value {
+        timestamp
+            .clone()
+            .to_cbor_value()
+            .expect("timestamps can always be encoded")
+    }
+
+    /// Decodes the given `value` of the field called `name` as a [`Timestamp`].
+    fn decode_timestamp(value: Value, name: &str) -> Result<Timestamp, TryFromCborMapError> {
+        Timestamp::from_cbor_value(value)
+            .map_err(|x| TryFromCborMapError::from_message(format!("couldn't decode {name}: {x}")))
+    }
+
+    /// The response parameters which are abbreviated like the CWT claims they correspond to.
+    const CLAIM_PARAMETERS: [CborKey<introspection::Registry>; 12] = [
+        introspection::ISSUER,
+        introspection::SUBJECT,
+        introspection::AUDIENCE,
+        introspection::EXPIRATION_TIME,
+        introspection::NOT_BEFORE,
+        introspection::ISSUED_AT,
+        introspection::CWT_ID,
+        introspection::CNF,
+        introspection::SCOPE,
+        introspection::ACE_PROFILE,
+        introspection::CNONCE,
+        introspection::EXI,
+    ];
+
+    /// Creates the response for an active token with the given (already filtered) `claims`.
+    pub(super) fn from_claims(
+        claims: ClaimsSet,
+    ) -> Result<IntrospectionResponse, TryFromCborMapError> {
+        let claims = match claims.to_cbor_value() {
+            Ok(Value::Map(claims)) => claims,
+            _ => {
+                return Err(TryFromCborMapError::from_message(
+                    "claims must be a valid CBOR map",
+                ))
+            }
+        };
+        let mut map = vec![(i128::from(introspection::ACTIVE.value()), Value::Bool(true))];
+        map.extend(claims.into_iter().filter_map(|(name, value)| {
+            match name {
+                Value::Integer(name) => CLAIM_PARAMETERS
+                    .iter()
+                    .find(|x| i128::from(x.value()) == i128::from(name))
+                    .map(|x| (i128::from(x.value()), value)),
+                _ => None,
+            }
+        }));
+        IntrospectionResponse::try_from_cbor_map(map)
+    }
+
+    impl ToCborMap for IntrospectionRequest {
+        fn to_cbor_map(&self) -> Vec<(i128, Option<Box<dyn ErasedSerialize + '_>>)> {
+            cbor_map_vec! {
+                introspection::TOKEN => Some(Value::Bytes(self.token.clone())),
+                introspection::TOKEN_TYPE_HINT => self.token_type_hint.as_ref()
+            }
+        }
+
+        fn try_from_cbor_map(map: Vec<(i128, Value)>) -> Result<Self, TryFromCborMapError>
+        where
+            Self: Sized + ToCborMap,
+        {
+            let mut request = IntrospectionRequest::builder();
+            for entry in map {
+                match (CborKey::try_from(entry.0)?, entry.1) {
+                    (introspection::TOKEN, Value::Bytes(x)) => request.token(x),
+                    (introspection::TOKEN_TYPE_HINT, Value::Text(x)) => request.token_type_hint(x),
+                    (key, _) => return Err(TryFromCborMapError::unknown_field(key)),
+                };
+            }
+            request
+                .build()
+                .map_err(|x| TryFromCborMapError::build_failed("IntrospectionRequest", x))
+        }
+    }
+
+    impl ToCborMap for IntrospectionResponse {
+        fn to_cbor_map(&self) -> Vec<(i128, Option<Box<dyn ErasedSerialize + '_>>)> {
+            let token_type: Option<CborMapValue<TokenType>> = self.token_type.map(CborMapValue);
+            let ace_profile: Option<CborMapValue<AceProfile>> = self.ace_profile.map(CborMapValue);
+            cbor_map_vec! {
+                introspection::ACTIVE => Some(self.active),
+                introspection::SCOPE => self.scope.as_ref(),
+                introspection::CLIENT_ID => self.client_id.as_ref(),
+                introspection::USERNAME => self.username.as_ref(),
+                introspection::TOKEN_TYPE => token_type,
+                introspection::EXPIRATION_TIME => self.expiration_time.as_ref().map(encode_timestamp),
+                introspection::NOT_BEFORE => self.not_before.as_ref().map(encode_timestamp),
+                introspection::ISSUED_AT => self.issued_at.as_ref().map(encode_timestamp),
+                introspection::SUBJECT => self.subject.as_ref(),
+                introspection::AUDIENCE => self.audience.as_ref(),
+                introspection::ISSUER => self.issuer.as_ref(),
+                introspection::CWT_ID => self.cwt_id.as_ref().map(|v| Value::Bytes(v.clone())),
+                introspection::ACE_PROFILE => ace_profile,
+                introspection::CNONCE => self.client_nonce.as_ref().map(|v| Value::Bytes(v.clone())),
+                introspection::CNF => self.cnf.as_ref().map(ToCborMap::to_ciborium_value),
+                introspection::RS_CNF => self.rs_cnf.as_ref().map(ToCborMap::to_ciborium_value),
+                introspection::EXI => self.exi
+            }
+        }
+
+        fn try_from_cbor_map(map: Vec<(i128, Value)>) -> Result<Self, TryFromCborMapError>
+        where
+            Self: Sized + ToCborMap,
+        {
+            let mut response = IntrospectionResponse::builder();
+            for entry in map {
+                match (CborKey::try_from(entry.0)?, entry.1) {
+                    (introspection::ACTIVE, Value::Bool(x)) => response.active(x),
+                    (introspection::SCOPE, v) => response.scope(decode_scope(v)?),
+                    (introspection::CLIENT_ID, Value::Text(x)) => response.client_id(x),
+                    (introspection::USERNAME, Value::Text(x)) => response.username(x),
+                    (introspection::TOKEN_TYPE, Value::Integer(x)) => {
+                        response.token_type(TokenType::from(decode_number::<i32>(x, "token_type")?))
+                    }
+                    (introspection::EXPIRATION_TIME, v) => {
+                        response.expiration_time(decode_timestamp(v, "exp")?)
+                    }
+                    (introspection::NOT_BEFORE, v) => {
+                        response.not_before(decode_timestamp(v, "nbf")?)
+                    }
+                    (introspection::ISSUED_AT, v) => {
+                        response.issued_at(decode_timestamp(v, "iat")?)
+                    }
+                    (introspection::SUBJECT, Value::Text(x)) => response.subject(x),
+                    (introspection::AUDIENCE, Value::Text(x)) => response.audience(x),
+                    (introspection::ISSUER, Value::Text(x)) => response.issuer(x),
+                    (introspection::CWT_ID, Value::Bytes(x)) => response.cwt_id(x),
+                    (introspection::ACE_PROFILE, Value::Integer(x)) => response
+                        .ace_profile(AceProfile::from(decode_number::<i32>(x, "ace_profile")?)),
+                    (introspection::CNONCE, Value::Bytes(x)) => response.client_nonce(x),
+                    (introspection::CNF, v) => response.cnf(decode_cnf(v, "cnf")?),
+                    (introspection::RS_CNF, v) => response.rs_cnf(decode_cnf(v, "rs_cnf")?),
+                    (introspection::EXI, Value::Integer(x)) => {
+                        response.exi(decode_number::<u32>(x, "exi")?)
+                    }
+                    (key, _) => return Err(TryFromCborMapError::unknown_field(key)),
+                };
+            }
+            response
+                .build()
+                .map_err(|x| TryFromCborMapError::build_failed("IntrospectionResponse", x))
+        }
+    }
+}
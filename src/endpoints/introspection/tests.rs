@@ -0,0 +1,320 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+use coset::cwt::ClaimsSetBuilder;
+use coset::iana::CwtClaimName;
+use coset::{AsCborValue, CoseKeyBuilder};
+
+#[cfg(not(feature = "std"))]
+use {
+    alloc::string::{String, ToString},
+    alloc::vec,
+};
+
+use crate::common::confirmation::encode_confirmation;
+use crate::common::scope::TextEncodedScope;
+use crate::common::test_helper::{expect_ser_de, FakeCrypto};
+use crate::error::CoseCipherError;
+use crate::{
+    get_token_headers, AccessTokenResponse, AuthServerRequestCreationHint, ProofOfPossessionKey,
+};
+
+use super::*;
+
+const AAD: &[u8] = b"coaps://as.example.com/introspect";
+
+fn example_request() -> AuthServerRequestCreationHint {
+    AuthServerRequestCreationHint {
+        audience: Some(String::from("coaps://rs.example.com")),
+        client_nonce: Some(vec![0xDC, 0xAF]),
+        ..AuthServerRequestCreationHint::default()
+    }
+}
+
+#[test]
+fn test_sign_verify_request() -> Result<(), ProtectedMessageError<String>> {
+    let protected = sign_introspection_request(
+        &example_request(),
+        &mut FakeCrypto {},
+        Some(AAD),
+        None,
+        None,
+    )?;
+    assert!(get_token_headers(&protected).is_some());
+    let request: AuthServerRequestCreationHint =
+        verify_introspection_request(&protected, &mut FakeCrypto {}, Some(AAD))?;
+    assert_eq!(request, example_request());
+
+    // Requests redirected to another endpoint must be rejected.
+    assert!(matches!(
+        verify_introspection_request::<AuthServerRequestCreationHint, _>(
+            &protected,
+            &mut FakeCrypto {},
+            Some(b"coaps://other.example.com/introspect")
+        ),
+        Err(ProtectedMessageError::Protection(
+            AccessTokenError::CoseCipherError(CoseCipherError::VerificationFailure)
+        ))
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_tag_verify_request() -> Result<(), ProtectedMessageError<String>> {
+    let protected = tag_introspection_request(
+        &example_request(),
+        &mut FakeCrypto {},
+        Some(AAD),
+        None,
+        None,
+    )?;
+    let request: AuthServerRequestCreationHint =
+        verify_introspection_request_tag(&protected, &mut FakeCrypto {}, Some(AAD))?;
+    assert_eq!(request, example_request());
+
+    // A MAC-tagged request is not a signed one.
+    assert!(
+        verify_introspection_request::<AuthServerRequestCreationHint, _>(
+            &protected,
+            &mut FakeCrypto {},
+            Some(AAD)
+        )
+        .is_err()
+    );
+    Ok(())
+}
+
+#[test]
+fn test_verify_request_wrong_type() -> Result<(), ProtectedMessageError<String>> {
+    let protected = sign_introspection_request(
+        &example_request(),
+        &mut FakeCrypto {},
+        Some(AAD),
+        None,
+        None,
+    )?;
+    assert!(matches!(
+        verify_introspection_request::<AccessTokenResponse, _>(
+            &protected,
+            &mut FakeCrypto {},
+            Some(AAD)
+        ),
+        Err(ProtectedMessageError::InvalidPayload(_))
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_confirmation_key_transport() -> Result<(), AccessTokenError<String>> {
+    let key = CoseKeyBuilder::new_symmetric_key(vec![0xDC; 16])
+        .key_id(vec![0xDC, 0xAF])
+        .build();
+    let cnf = encrypt_confirmation_key(&key, &mut FakeCrypto {}, Some(AAD), None, None)?;
+    assert!(matches!(cnf, ProofOfPossessionKey::EncryptedCoseKey(_)));
+    assert_eq!(
+        decrypt_confirmation_key(&cnf, &mut FakeCrypto {}, Some(AAD))?,
+        key
+    );
+    assert!(matches!(
+        decrypt_confirmation_key(&cnf, &mut FakeCrypto {}, None),
+        Err(AccessTokenError::CoseCipherError(_))
+    ));
+
+    let plain = ProofOfPossessionKey::PlainCoseKey(key.clone());
+    assert_eq!(
+        decrypt_confirmation_key(&plain, &mut FakeCrypto {}, None)?,
+        key
+    );
+    let ccs = ClaimsSetBuilder::new()
+        .claim(
+            CwtClaimName::Cnf,
+            Value::Map(vec![(
+                Value::from(1),
+                key.clone()
+                    .to_cbor_value()
+                    .map_err(AccessTokenError::from_cose_error)?,
+            )]),
+        )
+        .build();
+    assert_eq!(
+        decrypt_confirmation_key(&ProofOfPossessionKey::Ccs(ccs), &mut FakeCrypto {}, None)?,
+        key
+    );
+    // Key references contain no key material.
+    assert!(matches!(
+        decrypt_confirmation_key(
+            &ProofOfPossessionKey::KeyId(vec![0xDC, 0xAF]),
+            &mut FakeCrypto {},
+            None
+        ),
+        Err(AccessTokenError::CoseError(_))
+    ));
+    Ok(())
+}
+
+fn internal_claims() -> ClaimsSet {
+    ClaimsSetBuilder::new()
+        .issuer("as.example.com".to_string())
+        .subject("client1".to_string())
+        .audience("valve242".to_string())
+        .claim(CwtClaimName::Scope, Value::from("r_temp"))
+        .text_claim("tenant".to_string(), Value::from("acme"))
+        .private_claim(-65537, Value::from(42))
+        .build()
+}
+
+#[test]
+fn test_strip_claims() {
+    let filter = StripClaims::new(vec![
+        ClaimName::Assigned(CwtClaimName::Sub),
+        ClaimName::Text("tenant".to_string()),
+        ClaimName::PrivateUse(-65537),
+    ]);
+    let expected = ClaimsSetBuilder::new()
+        .issuer("as.example.com".to_string())
+        .audience("valve242".to_string())
+        .claim(CwtClaimName::Scope, Value::from("r_temp"))
+        .build();
+    assert_eq!(filter.filter(internal_claims()), expected);
+    // Claims which aren't present are simply ignored.
+    assert_eq!(filter.filter(expected.clone()), expected);
+}
+
+#[test]
+fn test_introspection_filters() {
+    let mut filters = IntrospectionFilters::new();
+    assert!(filters.is_empty());
+    assert_eq!(filters.apply(internal_claims()), internal_claims());
+
+    filters.register(StripClaims::new(vec![ClaimName::Text(
+        "tenant".to_string(),
+    )]));
+    // Filters are applied in order, so this one sees the claims without the tenant tag.
+    filters.register(|mut claims: ClaimsSet| {
+        assert!(claims
+            .rest
+            .iter()
+            .all(|(x, _)| x != &ClaimName::Text("tenant".to_string())));
+        claims.issuer = Some("as".to_string());
+        claims
+    });
+    assert_eq!(filters.len(), 2);
+    let filtered = filters.apply(internal_claims());
+    assert_eq!(filtered.issuer, Some("as".to_string()));
+    assert_eq!(filtered.subject, Some("client1".to_string()));
+    assert_eq!(filtered.rest.len(), 2);
+}
+
+#[test]
+fn test_introspection_response_from_claims() -> Result<(), TryFromCborMapError> {
+    let mut filters = IntrospectionFilters::new();
+    filters.register(StripClaims::new(vec![ClaimName::Assigned(
+        CwtClaimName::Sub,
+    )]));
+    let mut claims = internal_claims();
+    claims.expiration_time = Some(Timestamp::WholeSeconds(1_700_000_000));
+    claims.rest.extend(vec![
+        (
+            ClaimName::Assigned(CwtClaimName::Cnf),
+            encode_confirmation(&ProofOfPossessionKey::KeyId(vec![0x42])),
+        ),
+        (
+            ClaimName::Assigned(CwtClaimName::AceProfile),
+            Value::from(1),
+        ),
+        (ClaimName::Assigned(CwtClaimName::Exi), Value::from(3600)),
+    ]);
+    let expected = IntrospectionResponse::builder()
+        .active(true)
+        .scope(TextEncodedScope::try_from("r_temp").expect("invalid scope"))
+        .expiration_time(Timestamp::WholeSeconds(1_700_000_000))
+        .audience("valve242")
+        .issuer("as.example.com")
+        .ace_profile(AceProfile::CoapDtls)
+        .cnf(ProofOfPossessionKey::KeyId(vec![0x42]))
+        .exi(3600_u32)
+        .build()
+        .expect("invalid response");
+    // The subject has been filtered out, and claims without a response parameter are omitted.
+    assert_eq!(
+        IntrospectionResponse::from_claims(claims, &filters)?,
+        expected
+    );
+
+    let invalid = ClaimsSetBuilder::new()
+        .claim(CwtClaimName::Scope, Value::Bool(true))
+        .build();
+    assert!(IntrospectionResponse::from_claims(invalid, &filters).is_err());
+    Ok(())
+}
+
+#[cfg(feature = "as")]
+#[test]
+fn test_resolve_filtered_reference() -> Result<(), core::convert::Infallible> {
+    use crate::token::storage::InMemoryStorage;
+
+    let mut store = InMemoryStorage::default();
+    store.store_token_reference(vec![0xDC, 0xAF], internal_claims())?;
+    let mut filters = IntrospectionFilters::new();
+    filters.register(StripClaims::new(vec![ClaimName::Text(
+        "tenant".to_string(),
+    )]));
+    let resolved = filters
+        .resolve(&store, &[0xDC, 0xAF])?
+        .expect("reference not found");
+    assert!(resolved
+        .rest
+        .iter()
+        .all(|(x, _)| x != &ClaimName::Text("tenant".to_string())));
+    assert_eq!(filters.resolve(&store, &[0x42])?, None);
+    Ok(())
+}
+
+#[test]
+fn test_introspection_request() -> Result<(), String> {
+    let request = IntrospectionRequest::builder()
+        .token(vec![0xDC, 0xAF])
+        .token_type_hint("access_token")
+        .build()
+        .map_err(|x| x.to_string())?;
+    expect_ser_de(request, None, "A20B42DCAF18216C6163636573735F746F6B656E")
+}
+
+#[test]
+fn test_introspection_request_missing_token() {
+    assert!(IntrospectionRequest::builder().build().is_err());
+}
+
+#[test]
+fn test_introspection_response_inactive() -> Result<(), String> {
+    expect_ser_de(IntrospectionResponse::inactive(), None, "A10AF4")
+}
+
+#[test]
+fn test_introspection_response() -> Result<(), String> {
+    let response = IntrospectionResponse::builder()
+        .active(true)
+        .scope(TextEncodedScope::try_from("r_temp").map_err(|x| x.to_string())?)
+        .client_id("client1")
+        .token_type(TokenType::ProofOfPossession)
+        .expiration_time(Timestamp::WholeSeconds(1_700_000_000))
+        .issued_at(Timestamp::WholeSeconds(1_699_996_400))
+        .audience("tempSensor4711")
+        .issuer("coaps://as.example.com")
+        .cwt_id(vec![0x0C])
+        .ace_profile(AceProfile::CoapDtls)
+        .client_nonce(vec![0xE0, 0xA1])
+        .cnf(ProofOfPossessionKey::KeyId(vec![0x42]))
+        .exi(3600_u32)
+        .build()
+        .map_err(|x| x.to_string())?;
+    expect_ser_de(response, None, "AD0AF50966725F74656D70181867636C69656E7431182202041A6553F100061A6553E2F0036E74656D7053656E736F72343731310176636F6170733A2F2F61732E6578616D706C652E636F6D07410C182601182742E0A108A10341421828190E10")
+}
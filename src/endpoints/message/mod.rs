@@ -0,0 +1,279 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+//! Contains the [`AceMessage`] trait, which is implemented by all messages exchanged
+//! with the endpoints of ACE-OAuth.
+//!
+//! This trait can be used to write transport adapters (e.g., for CoAP or HTTP) which work
+//! for any message type, instead of having to write one adapter per message type.
+//!
+//! # Example
+//! ```
+//! # use std::error::Error;
+//! # use ciborium_io::Write;
+//! use dcaf::constants::{coap_code, content_format};
+//! use dcaf::endpoints::message::AceMessage;
+//! use dcaf::{AccessTokenResponse, ErrorCode, ErrorResponse};
+//!
+//! fn send<T: AceMessage>(message: T) -> Result<(u8, u16, Vec<u8>), ciborium::ser::Error<<Vec<u8> as Write>::Error>> {
+//!     Ok((message.coap_code(), T::CONTENT_FORMAT, message.encode()?))
+//! }
+//!
+//! # #[cfg(feature = "std")] {
+//! let response = AccessTokenResponse::builder().access_token(vec![0xDC, 0xAF]).build()?;
+//! let (code, format, _) = send(response)?;
+//! assert_eq!(code, coap_code::CREATED);
+//! assert_eq!(format, content_format::ACE_CBOR);
+//!
+//! let error = ErrorResponse::builder().error(ErrorCode::InvalidClient).build()?;
+//! let (code, _, _) = send(error)?;
+//! assert_eq!(code, coap_code::UNAUTHORIZED);
+//! # }
+//! # Ok::<(), Box<dyn Error>>(())
+//! ```
+//!
+//! # Wire Format Stability
+//! The encoding of all messages is kept byte-stable across releases. If the encoding of any
+//! message has to change intentionally, the [`WIRE_FORMAT_VERSION`] is incremented, so that
+//! integrators can detect such incompatibilities programmatically using
+//! [`wire_format_version`] (e.g., before exchanging messages with peers or restoring
+//! persisted messages).
+
+use core::any::type_name;
+
+use ciborium_io::{Read, Write};
+
+use crate::common::cbor_map::ToCborMap;
+use crate::constants::{coap_code, content_format};
+#[cfg(feature = "oscore-profile")]
+use crate::endpoints::authz_info::{OscoreAuthzInfoRequest, OscoreAuthzInfoResponse};
+#[cfg(feature = "introspection")]
+use crate::endpoints::introspection::{IntrospectionRequest, IntrospectionResponse};
+use crate::endpoints::metadata::AuthServerMetadata;
+use crate::endpoints::statistics::AuthServerStatistics;
+use crate::{
+    AccessTokenRequest, AccessTokenResponse, AuthServerRequestCreationHint, ErrorResponse,
+};
+
+#[cfg(not(feature = "std"))]
+use {alloc::string::String, alloc::vec::Vec};
+
+#[cfg(test)]
+mod tests;
+
+/// The version of the wire format of all messages implementing [`AceMessage`].
+///
+/// This is incremented whenever the encoding of a message changes in any way, even if the new
+/// encoding is still accepted by older releases (e.g., when map keys are emitted in another order).
+pub const WIRE_FORMAT_VERSION: u16 = 1;
+
+/// Returns the version of the wire format of all messages implementing [`AceMessage`], see
+/// [`WIRE_FORMAT_VERSION`].
+///
+/// # Example
+/// ```
+/// use dcaf::endpoints::message::wire_format_version;
+///
+/// // Messages persisted by an application built against another wire format need migrating.
+/// let persisted_with = 1;
+/// assert_eq!(wire_format_version(), persisted_with);
+/// ```
+#[must_use]
+pub const fn wire_format_version() -> u16 {
+    WIRE_FORMAT_VERSION
+}
+
+/// A message which is exchanged with one of the endpoints of ACE-OAuth, such as an
+/// [`AccessTokenRequest`] or an [`AuthServerRequestCreationHint`].
+///
+/// Apart from being (de)serializable as CBOR maps (see [`ToCborMap`]), each message exposes
+/// the content format and the CoAP/HTTP code it's expected to be sent with, so that transport
+/// adapters can be written generically.
+///
+/// For an example, see the [module-level documentation](self).
+pub trait AceMessage: ToCborMap {
+    /// The CoAP Content-Format ID with which this message is sent.
+    const CONTENT_FORMAT: u16 = content_format::ACE_CBOR;
+
+    /// The media type with which this message is sent.
+    const MEDIA_TYPE: &'static str = content_format::ACE_CBOR_MEDIA_TYPE;
+
+    /// Returns the CoAP code with which this message is expected to be sent, encoded as
+    /// described in [section 3 of RFC 7252](https://www.rfc-editor.org/rfc/rfc7252#section-3)
+    /// (see [`coap_code`] for relevant values).
+    ///
+    /// For requests, this is the request method, for responses, this is the response code.
+    fn coap_code(&self) -> u8;
+
+    /// Returns the HTTP status code with which this message is expected to be sent,
+    /// or `None` if this message is a request (which are always sent using `POST`).
+    fn http_status(&self) -> Option<u16>;
+
+    /// Encodes this message as a CBOR map.
+    ///
+    /// # Errors
+    /// When serialization of this message failed, e.g. due to malformed input.
+    fn encode(self) -> Result<Vec<u8>, ciborium::ser::Error<<Vec<u8> as Write>::Error>>
+    where
+        Self: Sized,
+    {
+        let mut encoded = Vec::new();
+        self.serialize_into(&mut encoded)?;
+        Ok(encoded)
+    }
+
+    /// Decodes a message of this type from the given CBOR map bytestring.
+    ///
+    /// # Errors
+    /// When `encoded` does not contain a valid CBOR map representing this message type.
+    fn decode(encoded: &[u8]) -> Result<Self, ciborium::de::Error<<&[u8] as Read>::Error>>
+    where
+        Self: Sized,
+    {
+        Self::deserialize_from(encoded)
+    }
+}
+
+impl AceMessage for AuthServerRequestCreationHint {
+    /// Creation hints are sent in response to an unauthorized resource request,
+    /// hence this returns 4.01 (Unauthorized).
+    fn coap_code(&self) -> u8 {
+        coap_code::UNAUTHORIZED
+    }
+
+    fn http_status(&self) -> Option<u16> {
+        Some(401)
+    }
+}
+
+impl AceMessage for AccessTokenRequest {
+    fn coap_code(&self) -> u8 {
+        coap_code::POST
+    }
+
+    fn http_status(&self) -> Option<u16> {
+        None
+    }
+}
+
+impl AceMessage for AccessTokenResponse {
+    /// As specified in [section 5.8.2 of RFC 9200](https://www.rfc-editor.org/rfc/rfc9200#section-5.8.2),
+    /// this returns 2.01 (Created).
+    fn coap_code(&self) -> u8 {
+        coap_code::CREATED
+    }
+
+    fn http_status(&self) -> Option<u16> {
+        Some(200)
+    }
+}
+
+impl AceMessage for ErrorResponse {
+    /// As specified in [section 5.8.3 of RFC 9200](https://www.rfc-editor.org/rfc/rfc9200#section-5.8.3),
+    /// this returns 4.01 (Unauthorized) for [`InvalidClient`](crate::ErrorCode::InvalidClient) errors
+    /// and 4.00 (Bad Request) for all other errors (see [`ErrorCode::coap_code`](crate::ErrorCode::coap_code)).
+    fn coap_code(&self) -> u8 {
+        self.error.coap_code()
+    }
+
+    fn http_status(&self) -> Option<u16> {
+        Some(self.error.http_status())
+    }
+}
+
+#[cfg(feature = "oscore-profile")]
+impl AceMessage for OscoreAuthzInfoRequest {
+    fn coap_code(&self) -> u8 {
+        coap_code::POST
+    }
+
+    fn http_status(&self) -> Option<u16> {
+        None
+    }
+}
+
+#[cfg(feature = "oscore-profile")]
+impl AceMessage for OscoreAuthzInfoResponse {
+    /// As specified in [section 4.1.2 of RFC 9203](https://www.rfc-editor.org/rfc/rfc9203#section-4.1.2),
+    /// this returns 2.01 (Created).
+    fn coap_code(&self) -> u8 {
+        coap_code::CREATED
+    }
+
+    fn http_status(&self) -> Option<u16> {
+        Some(201)
+    }
+}
+
+#[cfg(feature = "introspection")]
+impl AceMessage for IntrospectionRequest {
+    fn coap_code(&self) -> u8 {
+        coap_code::POST
+    }
+
+    fn http_status(&self) -> Option<u16> {
+        None
+    }
+}
+
+#[cfg(feature = "introspection")]
+impl AceMessage for IntrospectionResponse {
+    /// As specified in [section 5.9.2 of RFC 9200](https://www.rfc-editor.org/rfc/rfc9200#section-5.9.2),
+    /// this returns 2.05 (Content).
+    fn coap_code(&self) -> u8 {
+        coap_code::CONTENT
+    }
+
+    fn http_status(&self) -> Option<u16> {
+        Some(200)
+    }
+}
+
+impl AceMessage for AuthServerMetadata {
+    /// Metadata is retrieved using a GET request, hence this returns 2.05 (Content).
+    fn coap_code(&self) -> u8 {
+        coap_code::CONTENT
+    }
+
+    fn http_status(&self) -> Option<u16> {
+        Some(200)
+    }
+}
+
+impl AceMessage for AuthServerStatistics {
+    /// Statistics are retrieved using a GET request, hence this returns 2.05 (Content).
+    fn coap_code(&self) -> u8 {
+        coap_code::CONTENT
+    }
+
+    fn http_status(&self) -> Option<u16> {
+        Some(200)
+    }
+}
+
+/// Returns the name of the message type `T` in snake case (e.g., `access_token_request`),
+/// which is used to label messages in corpora and traces.
+pub(crate) fn message_name<T>() -> String {
+    let full_name = type_name::<T>();
+    let name = full_name.rsplit("::").next().unwrap_or(full_name);
+    let mut snake_case = String::with_capacity(name.len() + 4);
+    for (index, character) in name.chars().enumerate() {
+        if character.is_ascii_uppercase() {
+            if index > 0 {
+                snake_case.push('_');
+            }
+            snake_case.push(character.to_ascii_lowercase());
+        } else {
+            snake_case.push(character);
+        }
+    }
+    snake_case
+}
@@ -0,0 +1,211 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+#[cfg(not(feature = "std"))]
+use {
+    alloc::string::{String, ToString},
+    alloc::vec,
+};
+
+use core::fmt::Debug;
+
+#[cfg(feature = "introspection")]
+use coset::cwt::Timestamp;
+
+use crate::common::test_helper::expect_ser_de;
+use crate::error::ReasonCode;
+use crate::{AceProfile, ErrorCode, ProofOfPossessionKey, Scope, TextEncodedScope, TokenType};
+
+use super::*;
+
+fn roundtrip<T>(message: &T) -> Result<(), String>
+where
+    T: AceMessage + Clone + PartialEq + Debug,
+{
+    let encoded = message.clone().encode().map_err(|x| x.to_string())?;
+    assert_eq!(&T::decode(&encoded).map_err(|x| x.to_string())?, message);
+    assert_eq!(T::CONTENT_FORMAT, content_format::ACE_CBOR);
+    Ok(())
+}
+
+#[test]
+fn test_codes() -> Result<(), String> {
+    let hint = AuthServerRequestCreationHint::default();
+    assert_eq!(hint.coap_code(), coap_code::UNAUTHORIZED);
+    assert_eq!(hint.http_status(), Some(401));
+    roundtrip(&hint)?;
+
+    let request = AccessTokenRequest::builder()
+        .client_id("myclient")
+        .build()
+        .map_err(|x| x.to_string())?;
+    assert_eq!(request.coap_code(), coap_code::POST);
+    assert_eq!(request.http_status(), None);
+    roundtrip(&request)?;
+
+    let response = AccessTokenResponse::builder()
+        .access_token(vec![0xDC, 0xAF])
+        .build()
+        .map_err(|x| x.to_string())?;
+    assert_eq!(response.coap_code(), coap_code::CREATED);
+    assert_eq!(response.http_status(), Some(200));
+    roundtrip(&response)
+}
+
+#[test]
+fn test_error_codes() -> Result<(), String> {
+    let invalid_client = ErrorResponse::builder()
+        .error(ErrorCode::InvalidClient)
+        .build()
+        .map_err(|x| x.to_string())?;
+    assert_eq!(invalid_client.coap_code(), coap_code::UNAUTHORIZED);
+    assert_eq!(invalid_client.http_status(), Some(401));
+    roundtrip(&invalid_client)?;
+
+    let invalid_scope = ErrorResponse::builder()
+        .error(ErrorCode::InvalidScope)
+        .build()
+        .map_err(|x| x.to_string())?;
+    assert_eq!(invalid_scope.coap_code(), coap_code::BAD_REQUEST);
+    assert_eq!(invalid_scope.http_status(), Some(400));
+    roundtrip(&invalid_scope)?;
+
+    for code in [ErrorCode::InvalidGrant, ErrorCode::Other(-42)] {
+        assert_eq!(code.coap_code(), coap_code::BAD_REQUEST);
+        assert_eq!(code.http_status(), 400);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_message_name() {
+    assert_eq!(message_name::<AccessTokenRequest>(), "access_token_request");
+    #[cfg(feature = "oscore-profile")]
+    assert_eq!(
+        message_name::<OscoreAuthzInfoRequest>(),
+        "oscore_authz_info_request"
+    );
+}
+
+/// Asserts that the encoding of each message type is byte-stable.
+///
+/// If any of these fail due to an intentional change of the wire format, the expected bytes
+/// must be updated **and** [`WIRE_FORMAT_VERSION`] must be incremented.
+#[test]
+fn test_wire_format_golden() -> Result<(), String> {
+    assert_eq!(wire_format_version(), 1);
+
+    let hint = AuthServerRequestCreationHint::builder()
+        .auth_server("coaps://as.example.com/token")
+        .audience("coaps://rs.example.com")
+        .client_nonce(vec![0xE0, 0xA1, 0x56, 0xBB])
+        .build()
+        .map_err(|x| x.to_string())?;
+    expect_ser_de(hint, None, "a301781c636f6170733a2f2f61732e6578616d706c652e636f6d2f746f6b656e0576636f6170733a2f2f72732e6578616d706c652e636f6d182744e0a156bb")?;
+
+    let request = AccessTokenRequest::builder()
+        .client_id("myclient")
+        .audience("tempSensor4711")
+        .scope(Scope::from(
+            TextEncodedScope::try_from("read").map_err(|x| x.to_string())?,
+        ))
+        .req_cnf(ProofOfPossessionKey::KeyId(vec![0xDC, 0xAF]))
+        .build()
+        .map_err(|x| x.to_string())?;
+    expect_ser_de(
+        request,
+        None,
+        "a404a10342dcaf056e74656d7053656e736f72343731310964726561641818686d79636c69656e74",
+    )?;
+
+    let response = AccessTokenResponse::builder()
+        .access_token(vec![0xDC, 0xAF])
+        .expires_in(3600_u32)
+        .ace_profile(AceProfile::CoapDtls)
+        .token_type(TokenType::ProofOfPossession)
+        .build()
+        .map_err(|x| x.to_string())?;
+    expect_ser_de(response, None, "a40142dcaf02190e10182202182601")?;
+
+    let error = ErrorResponse::builder()
+        .error(ErrorCode::InvalidScope)
+        .description("scope not allowed")
+        .build()
+        .map_err(|x| x.to_string())?;
+    expect_ser_de(
+        error,
+        None,
+        "a2181e06181f7173636f7065206e6f7420616c6c6f776564",
+    )?;
+
+    #[cfg(feature = "oscore-profile")]
+    {
+        let authz_info_request = OscoreAuthzInfoRequest::builder()
+            .access_token(vec![0xDC, 0xAF])
+            .nonce1(vec![0x01])
+            .ace_client_recipientid(vec![0x02])
+            .build()
+            .map_err(|x| x.to_string())?;
+        expect_ser_de(authz_info_request, None, "a30142dcaf18284101182b4102")?;
+
+        let authz_info_response = OscoreAuthzInfoResponse::builder()
+            .nonce2(vec![0x03])
+            .ace_server_recipientid(vec![0x04])
+            .build()
+            .map_err(|x| x.to_string())?;
+        expect_ser_de(authz_info_response, None, "a2182a4103182c4104")?;
+    }
+
+    #[cfg(feature = "introspection")]
+    {
+        let introspection_request = IntrospectionRequest::builder()
+            .token(vec![0xDC, 0xAF])
+            .token_type_hint("access_token")
+            .build()
+            .map_err(|x| x.to_string())?;
+        expect_ser_de(
+            introspection_request,
+            None,
+            "a20b42dcaf18216c6163636573735f746f6b656e",
+        )?;
+
+        let introspection_response = IntrospectionResponse::builder()
+            .active(true)
+            .expiration_time(Timestamp::WholeSeconds(1_700_000_000))
+            .audience("tempSensor4711")
+            .ace_profile(AceProfile::CoapDtls)
+            .cnf(ProofOfPossessionKey::KeyId(vec![0x42]))
+            .build()
+            .map_err(|x| x.to_string())?;
+        expect_ser_de(
+            introspection_response,
+            None,
+            "a50af5041a6553f100036e74656d7053656e736f723437313118260108a1034142",
+        )?;
+        expect_ser_de(IntrospectionResponse::inactive(), None, "a10af4")?;
+    }
+
+    let metadata = AuthServerMetadata::builder()
+        .issuer("coaps://as.example.com")
+        .token_endpoint("coaps://as.example.com/token")
+        .ace_profiles_supported(vec![AceProfile::CoapOscore])
+        .build()
+        .map_err(|x| x.to_string())?;
+    expect_ser_de(metadata, None, "a30176636f6170733a2f2f61732e6578616d706c652e636f6d02781c636f6170733a2f2f61732e6578616d706c652e636f6d2f746f6b656e048102")?;
+
+    let statistics = AuthServerStatistics {
+        tokens_issued: vec![("rs1".to_string(), 3)],
+        errors: vec![(ErrorCode::InvalidClient, 1)],
+        rejections: vec![(ReasonCode::UnknownClient, 1)],
+        uptime: Some(60),
+    };
+    expect_ser_de(statistics, None, "a401a1637273310302a1020103183c04a118c801")
+}
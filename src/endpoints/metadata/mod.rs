@@ -0,0 +1,254 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+//! Contains the data model for [Authorization Server Metadata](AuthServerMetadata),
+//! a CBOR variant of the metadata documents described in
+//! [RFC 8414](https://www.rfc-editor.org/rfc/rfc8414), which lets clients discover the
+//! endpoints and capabilities of an Authorization Server instead of hardcoding them.
+//!
+//! The Authorization Server serves the metadata at [`WELL_KNOWN_PATH`], and clients use
+//! [`AuthServerMetadata::load`] to decode it and check that it belongs to the expected issuer.
+//!
+//! # Example
+//! ```
+//! # use std::error::Error;
+//! # use dcaf::AceProfile;
+//! use dcaf::common::cose::Algorithm;
+//! use dcaf::endpoints::message::AceMessage;
+//! use dcaf::endpoints::metadata::AuthServerMetadata;
+//!
+//! # #[cfg(feature = "std")] {
+//! let metadata = AuthServerMetadata::builder()
+//!     .issuer("coaps://as.example.com")
+//!     .token_endpoint("coaps://as.example.com/token")
+//!     .ace_profiles_supported(vec![AceProfile::CoapDtls])
+//!     .algorithms_supported(vec![Algorithm::Es256])
+//!     .build()?;
+//! let encoded = metadata.clone().encode()?;
+//! // On the client:
+//! assert_eq!(AuthServerMetadata::load(&encoded, "coaps://as.example.com")?, metadata);
+//! assert!(AuthServerMetadata::load(&encoded, "coaps://evil.example.com").is_err());
+//! # }
+//! # Ok::<(), Box<dyn Error>>(())
+//! ```
+
+use crate::common::cose::Algorithm;
+use crate::error::InvalidMetadataError;
+use crate::AceProfile;
+
+#[cfg(not(feature = "std"))]
+use {
+    alloc::string::{String, ToString},
+    alloc::vec::Vec,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The path below which Authorization Servers serve their metadata, as specified in
+/// [section 3 of RFC 8414](https://www.rfc-editor.org/rfc/rfc8414#section-3).
+pub const WELL_KNOWN_PATH: &str = "/.well-known/oauth-authorization-server";
+
+/// Metadata describing the endpoints and capabilities of an Authorization Server.
+///
+/// Use the [`AuthServerMetadataBuilder`] (which you can access using the
+/// [`builder()`](AuthServerMetadata::builder) method) to create an instance of this struct.
+/// Note that the [`issuer`](AuthServerMetadata::issuer) is required and must not contain a query
+/// or fragment component.
+///
+/// For an example, see the [module-level documentation](self).
+#[derive(Debug, PartialEq, Eq, Clone, Builder)]
+#[builder(
+    no_std,
+    setter(into, strip_option),
+    derive(Debug, PartialEq, Eq),
+    build_fn(validate = "Self::validate")
+)]
+pub struct AuthServerMetadata {
+    /// The URI identifying the Authorization Server, which must be equal to the `iss` claim of
+    /// the tokens it issues.
+    pub issuer: String,
+
+    /// URI of the token endpoint of the Authorization Server.
+    #[builder(default)]
+    pub token_endpoint: Option<String>,
+
+    /// URI of the introspection endpoint of the Authorization Server.
+    #[builder(default)]
+    pub introspection_endpoint: Option<String>,
+
+    /// The ACE profiles supported by the Authorization Server.
+    #[builder(default)]
+    pub ace_profiles_supported: Vec<AceProfile>,
+
+    /// The algorithms the Authorization Server supports for protecting access tokens.
+    #[builder(default)]
+    pub algorithms_supported: Vec<Algorithm>,
+}
+
+impl AuthServerMetadata {
+    /// Decodes the given `encoded` metadata, as received by a client from [`WELL_KNOWN_PATH`],
+    /// and checks that it has been issued by the `expected_issuer`, as required by
+    /// [section 3.3 of RFC 8414](https://www.rfc-editor.org/rfc/rfc8414#section-3.3).
+    ///
+    /// # Errors
+    /// - If `encoded` is not a valid metadata document.
+    /// - If the issuer of the metadata is not the `expected_issuer`.
+    pub fn load(encoded: &[u8], expected_issuer: &str) -> Result<Self, InvalidMetadataError> {
+        let metadata = <Self as crate::ToCborMap>::deserialize_from(encoded)
+            .map_err(|x| InvalidMetadataError::Malformed(x.to_string()))?;
+        if metadata.issuer == expected_issuer {
+            Ok(metadata)
+        } else {
+            Err(InvalidMetadataError::IssuerMismatch {
+                expected: expected_issuer.to_string(),
+                actual: metadata.issuer,
+            })
+        }
+    }
+}
+
+#[allow(clippy::unused_self, clippy::unnecessary_wraps)]
+mod builder {
+    use super::*;
+
+    impl AuthServerMetadata {
+        /// Initializes and returns a new [`AuthServerMetadataBuilder`].
+        #[must_use]
+        pub fn builder() -> AuthServerMetadataBuilder {
+            AuthServerMetadataBuilder::default()
+        }
+    }
+
+    impl AuthServerMetadataBuilder {
+        pub(crate) fn validate(&self) -> Result<(), AuthServerMetadataBuilderError> {
+            match &self.issuer {
+                Some(issuer) if issuer.is_empty() => Err(AuthServerMetadataBuilderError::from(
+                    "issuer must not be empty".to_string(),
+                )),
+                Some(issuer) if issuer.contains(['?', '#']) => {
+                    Err(AuthServerMetadataBuilderError::from(
+                        "issuer must not contain a query or fragment".to_string(),
+                    ))
+                }
+                _ => Ok(()),
+            }
+        }
+    }
+}
+
+/// Contains conversion methods for ACE-OAuth data types.
+///
+/// One part of this is converting enum types from and to their CBOR abbreviations in
+/// [`cbor_abbreviations`](crate::constants::cbor_abbreviations),
+/// another part is implementing the [`ToCborMap`](crate::ToCborMap) type for the
+/// models which are represented as CBOR maps.
+mod conversion {
+    #[cfg(not(feature = "std"))]
+    use {alloc::boxed::Box, alloc::format};
+
+    use ciborium::value::Value;
+    use erased_serde::Serialize as ErasedSerialize;
+
+    use crate::common::cbor_map::{cbor_map_vec, decode_number, ToCborMap};
+    use crate::common::constants::cbor_abbreviations::{as_metadata, CborKey};
+    use crate::error::TryFromCborMapError;
+
+    use super::*;
+
+    /// Encodes the given `values` as a CBOR array, or returns `None` if there are none.
+    fn encode_array<T, F>(values: &[T], encode: F) -> Option<Value>
+    where
+        F: Fn(&T) -> Value,
+    {
+        if values.is_empty() {
+            None
+        } else {
+            Some(Value::Array(values.iter().map(encode).collect()))
+        }
+    }
+
+    /// Decodes the given `value` as a CBOR array, decoding each element using `decode`.
+    fn decode_array<T, F>(
+        value: Value,
+        name: &str,
+        decode: F,
+    ) -> Result<Vec<T>, TryFromCborMapError>
+    where
+        F: Fn(Value) -> Result<T, TryFromCborMapError>,
+    {
+        if let Value::Array(values) = value {
+            values.into_iter().map(decode).collect()
+        } else {
+            Err(TryFromCborMapError::from_message(format!(
+                "{name} must be an array"
+            )))
+        }
+    }
+
+    impl ToCborMap for AuthServerMetadata {
+        fn to_cbor_map(&self) -> Vec<(i128, Option<Box<dyn ErasedSerialize + '_>>)> {
+            let profiles = encode_array(&self.ace_profiles_supported, |profile| {
+                Value::from(i32::from(*profile))
+            });
+            let algorithms = encode_array(&self.algorithms_supported, |alg| {
+                Value::from(i64::from(*alg))
+            });
+            cbor_map_vec! {
+                as_metadata::ISSUER => Some(&self.issuer),
+                as_metadata::TOKEN_ENDPOINT => self.token_endpoint.as_ref(),
+                as_metadata::INTROSPECTION_ENDPOINT => self.introspection_endpoint.as_ref(),
+                as_metadata::ACE_PROFILES_SUPPORTED => profiles,
+                as_metadata::ALGORITHMS_SUPPORTED => algorithms
+            }
+        }
+
+        fn try_from_cbor_map(map: Vec<(i128, Value)>) -> Result<Self, TryFromCborMapError>
+        where
+            Self: Sized + ToCborMap,
+        {
+            let mut metadata = AuthServerMetadata::builder();
+            for entry in map {
+                match (CborKey::try_from(entry.0)?, entry.1) {
+                    (as_metadata::ISSUER, Value::Text(x)) => metadata.issuer(x),
+                    (as_metadata::TOKEN_ENDPOINT, Value::Text(x)) => metadata.token_endpoint(x),
+                    (as_metadata::INTROSPECTION_ENDPOINT, Value::Text(x)) => {
+                        metadata.introspection_endpoint(x)
+                    }
+                    (as_metadata::ACE_PROFILES_SUPPORTED, x) => metadata.ace_profiles_supported(
+                        decode_array(x, "ace_profiles_supported", |profile| match profile {
+                            Value::Integer(i) => {
+                                decode_number::<i32>(i, "ace_profile").map(AceProfile::from)
+                            }
+                            _ => Err(TryFromCborMapError::from_message(
+                                "ace_profile must be an integer",
+                            )),
+                        })?,
+                    ),
+                    (as_metadata::ALGORITHMS_SUPPORTED, x) => metadata.algorithms_supported(
+                        decode_array(x, "algorithms_supported", |alg| match alg {
+                            Value::Integer(i) => {
+                                decode_number::<i64>(i, "algorithm").map(Algorithm::from)
+                            }
+                            _ => Err(TryFromCborMapError::from_message(
+                                "algorithm must be an integer",
+                            )),
+                        })?,
+                    ),
+                    (key, _) => return Err(TryFromCborMapError::unknown_field(key)),
+                };
+            }
+            metadata
+                .build()
+                .map_err(|x| TryFromCborMapError::build_failed("AuthServerMetadata", x))
+        }
+    }
+}
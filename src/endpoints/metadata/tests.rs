@@ -0,0 +1,79 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+#[cfg(not(feature = "std"))]
+use {alloc::string::ToString, alloc::vec};
+
+use crate::common::test_helper::expect_ser_de;
+use crate::endpoints::message::AceMessage;
+
+use super::*;
+
+fn example_metadata() -> Result<AuthServerMetadata, String> {
+    AuthServerMetadata::builder()
+        .issuer("coaps://as.example.com")
+        .token_endpoint("coaps://as.example.com/token")
+        .introspection_endpoint("coaps://as.example.com/introspect")
+        .ace_profiles_supported(vec![AceProfile::CoapDtls, AceProfile::CoapOscore])
+        .algorithms_supported(vec![Algorithm::Es256, Algorithm::EdDsa])
+        .build()
+        .map_err(|x| x.to_string())
+}
+
+#[test]
+fn test_metadata() -> Result<(), String> {
+    expect_ser_de(example_metadata()?, None, "A50176636F6170733A2F2F61732E6578616D706C652E636F6D02781C636F6170733A2F2F61732E6578616D706C652E636F6D2F746F6B656E037821636F6170733A2F2F61732E6578616D706C652E636F6D2F696E74726F73706563740482010205822627")
+}
+
+#[test]
+fn test_metadata_minimal() -> Result<(), String> {
+    let metadata = AuthServerMetadata::builder()
+        .issuer("coaps://as.example.com")
+        .build()
+        .map_err(|x| x.to_string())?;
+    expect_ser_de(
+        metadata,
+        None,
+        "A10176636F6170733A2F2F61732E6578616D706C652E636F6D",
+    )
+}
+
+#[test]
+fn test_metadata_invalid_issuer() {
+    assert!(AuthServerMetadata::builder().build().is_err());
+    assert!(AuthServerMetadata::builder().issuer("").build().is_err());
+    assert!(AuthServerMetadata::builder()
+        .issuer("coaps://as.example.com?tenant=1")
+        .build()
+        .is_err());
+}
+
+#[test]
+fn test_metadata_load() -> Result<(), String> {
+    let metadata = example_metadata()?;
+    let encoded = metadata.clone().encode().map_err(|x| x.to_string())?;
+    assert_eq!(
+        AuthServerMetadata::load(&encoded, "coaps://as.example.com"),
+        Ok(metadata)
+    );
+    assert_eq!(
+        AuthServerMetadata::load(&encoded, "coaps://other.example.com"),
+        Err(InvalidMetadataError::IssuerMismatch {
+            expected: "coaps://other.example.com".to_string(),
+            actual: "coaps://as.example.com".to_string()
+        })
+    );
+    assert!(matches!(
+        AuthServerMetadata::load(&[0xA0], "coaps://as.example.com"),
+        Err(InvalidMetadataError::Malformed(_))
+    ));
+    Ok(())
+}
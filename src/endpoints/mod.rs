@@ -14,13 +14,44 @@
 //! These endpoints are described in section 5 of
 //! [RFC 9200](https://www.rfc-editor.org/rfc/rfc9200).
 //!
-//! Support for the introspection endpoint is planned.
-//!
 //! # Layout
+//! Note that [`caching`] and [`recovery`] require the `client` feature, [`introspection`] requires
+//! the `introspection` feature, and [`authz_info`] requires the `oscore-profile` feature:
+//! - [`authz_info`]: Contains the data models for the messages exchanged with the `authz-info`
+//!   endpoint when using the OSCORE profile.
+//! - [`blockwise`]: Contains helpers for transferring large access tokens to the `authz-info`
+//!   endpoint using CoAP block-wise transfers.
+//! - [`caching`]: Contains helpers for handling the freshness of cacheable responses of the
+//!   Authorization Server, such as metadata documents.
+//! - [`corpus`]: Contains the [`CorpusRecorder`](corpus::CorpusRecorder), which exports decoded
+//!   messages as a fuzzing corpus.
 //! - [`creation_hint`]: Contains the data model for Authorization Server Request Creation Hints.
+//! - [`http`]: Contains helpers for transporting access tokens in HTTP `Authorization` headers.
+//! - [`introspection`]: Contains the data models for introspection requests and responses, as well
+//!   as functions for protecting introspection requests with the Resource Server's credentials.
+//! - [`message`]: Contains the [`AceMessage`](message::AceMessage) trait implemented by all messages,
+//!   which can be used to write generic transport adapters.
+//! - [`metadata`]: Contains the data model for Authorization Server metadata documents.
+//! - [`recovery`]: Contains the [`RecoveryAction`](recovery::RecoveryAction) enum, which classifies
+//!   failed requests so that clients can react to them uniformly.
+//! - [`statistics`]: Contains the data model for operational statistics of an Authorization Server.
 //! - [`token_req`]: Contains the data models for structures related to access token requests and responses.
+//! - [`trace`]: Contains helpers for capturing the messages of a flow for interoperability debugging.
 
+#[cfg(feature = "oscore-profile")]
+pub mod authz_info;
+pub mod blockwise;
+#[cfg(feature = "client")]
+pub mod caching;
+pub mod corpus;
 pub mod creation_hint;
+pub mod http;
+#[cfg(feature = "introspection")]
+pub mod introspection;
+pub mod message;
+pub mod metadata;
+#[cfg(feature = "client")]
+pub mod recovery;
+pub mod statistics;
 pub mod token_req;
-
-// TODO: Introspection data structures
+pub mod trace;
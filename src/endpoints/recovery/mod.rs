@@ -0,0 +1,384 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+//! Contains the [`RecoveryAction`] enum, which classifies failures encountered by a client
+//! so that applications can implement uniform retry logic, as well as the [`RetryPolicy`]
+//! (configurable per message type using [`RetryPolicies`]) which determines how often and
+//! after which delay requests should be retried.
+//!
+//! # Example
+//! ```
+//! # use dcaf::{ErrorCode, ErrorResponse};
+//! # use dcaf::endpoints::token_req::ErrorResponseBuilderError;
+//! use dcaf::constants::coap_code;
+//! use dcaf::endpoints::recovery::RecoveryAction;
+//!
+//! // The Resource Server is temporarily overloaded.
+//! assert_eq!(RecoveryAction::for_coap_code(coap_code::SERVICE_UNAVAILABLE), Some(RecoveryAction::Retry));
+//! // The Resource Server no longer accepts our (e.g., expired) token.
+//! assert_eq!(RecoveryAction::for_coap_code(coap_code::UNAUTHORIZED), Some(RecoveryAction::RefreshToken));
+//! // The Authorization Server doesn't support any of our profiles.
+//! let error = ErrorResponse::builder().error(ErrorCode::IncompatibleAceProfiles).build()?;
+//! assert_eq!(RecoveryAction::from(&error), RecoveryAction::Fatal);
+//! # Ok::<(), ErrorResponseBuilderError>(())
+//! ```
+
+use core::any::TypeId;
+use core::time::Duration;
+
+use crate::common::cbor_values::ProofOfPossessionKey;
+use crate::constants::coap_code;
+use crate::endpoints::message::AceMessage;
+use crate::{AccessTokenRequest, AuthServerRequestCreationHint, ErrorCode, ErrorResponse};
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+#[cfg(test)]
+mod tests;
+
+/// Describes how a client should react to a failed request.
+///
+/// Failures are classified into four groups:
+/// - Transient failures, such as timeouts or an overloaded server, after which the same request
+///   can be sent again ([`Retry`](RecoveryAction::Retry)).
+///   Transports should treat timeouts as [`Retry`](RecoveryAction::Retry) as well.
+/// - Failures related to the access token, such as an expired token, which can be resolved by
+///   obtaining a new access token ([`RefreshToken`](RecoveryAction::RefreshToken)).
+/// - Failures caused by the access token not covering the request, which can only be resolved
+///   by requesting a new access token with a different scope
+///   ([`RequestScope`](RecoveryAction::RequestScope)).
+/// - Failures which won't go away by themselves, such as incompatible profiles, which require
+///   a change in configuration ([`Fatal`](RecoveryAction::Fatal)).
+///
+/// For an example, see the [module-level documentation](self).
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[non_exhaustive]
+pub enum RecoveryAction {
+    /// The same request may be sent again later, preferably after a backoff period.
+    Retry,
+
+    /// A new access token should be obtained from the Authorization Server (for an invalid grant,
+    /// this requires a new grant), after which the original request may be sent again.
+    RefreshToken,
+
+    /// The access token is valid, but its scope doesn't cover the request. Obtaining a new access
+    /// token with the same scope won't help, so a new access token with a scope covering the
+    /// request has to be requested first (if the client is allowed to do so at all).
+    RequestScope,
+
+    /// The request should not be sent again, as it will fail again in the same way.
+    Fatal,
+}
+
+impl RecoveryAction {
+    /// Classifies the given CoAP response `code` (see [`coap_code`]), or returns `None` if it
+    /// doesn't indicate a failure.
+    ///
+    /// This is intended for responses from the Resource Server, for which 4.01 (Unauthorized)
+    /// indicates that the access token is missing or invalid
+    /// ([`RefreshToken`](RecoveryAction::RefreshToken)), while 4.03 (Forbidden) indicates that
+    /// its scope doesn't cover the request ([`RequestScope`](RecoveryAction::RequestScope)),
+    /// as described in
+    /// [section 5.10.1.1 of RFC 9200](https://www.rfc-editor.org/rfc/rfc9200#section-5.10.1.1).
+    /// For error responses from the Authorization Server, use the contained [`ErrorCode`] instead.
+    #[must_use]
+    pub fn for_coap_code(code: u8) -> Option<RecoveryAction> {
+        match code {
+            coap_code::UNAUTHORIZED => Some(RecoveryAction::RefreshToken),
+            coap_code::FORBIDDEN => Some(RecoveryAction::RequestScope),
+            coap_code::TOO_MANY_REQUESTS
+            | coap_code::INTERNAL_SERVER_ERROR
+            | coap_code::BAD_GATEWAY
+            | coap_code::SERVICE_UNAVAILABLE
+            | coap_code::GATEWAY_TIMEOUT => Some(RecoveryAction::Retry),
+            // Classes 4 and 5 indicate client and server errors, respectively.
+            x if x >> 5 >= 4 => Some(RecoveryAction::Fatal),
+            _ => None,
+        }
+    }
+
+    /// Classifies the given HTTP `status`, or returns `None` if it doesn't indicate a failure.
+    ///
+    /// See [`for_coap_code`](RecoveryAction::for_coap_code) for details.
+    #[must_use]
+    pub fn for_http_status(status: u16) -> Option<RecoveryAction> {
+        match status {
+            401 => Some(RecoveryAction::RefreshToken),
+            403 => Some(RecoveryAction::RequestScope),
+            429 | 500 | 502 | 503 | 504 => Some(RecoveryAction::Retry),
+            x if x >= 400 => Some(RecoveryAction::Fatal),
+            _ => None,
+        }
+    }
+
+    /// Classifies the given `error` returned by the Authorization Server.
+    ///
+    /// Apart from [`InvalidGrant`](ErrorCode::InvalidGrant), for which a new grant may be used,
+    /// all errors are considered [`Fatal`](RecoveryAction::Fatal), since the Authorization Server
+    /// would reject the same request again.
+    #[must_use]
+    pub fn for_error_code(error: &ErrorCode) -> RecoveryAction {
+        match error {
+            ErrorCode::InvalidGrant => RecoveryAction::RefreshToken,
+            _ => RecoveryAction::Fatal,
+        }
+    }
+}
+
+impl From<&ErrorResponse> for RecoveryAction {
+    fn from(response: &ErrorResponse) -> Self {
+        RecoveryAction::for_error_code(&response.error)
+    }
+}
+
+/// Determines how often and after which delay a failed request is retried, using exponential
+/// backoff with optional jitter.
+///
+/// The [`Default`] policy never retries, which is the safe choice for requests which must not be
+/// processed twice (such as token requests, which might otherwise result in multiple tokens).
+///
+/// # Example
+/// ```
+/// # use core::time::Duration;
+/// use dcaf::endpoints::recovery::{RecoveryAction, RetryPolicy};
+///
+/// let policy = RetryPolicy {
+///     jitter: false,
+///     ..RetryPolicy::exponential(3, Duration::from_secs(2))
+/// };
+/// // After the first failed attempt, we wait two seconds, then four seconds.
+/// assert_eq!(policy.delay(RecoveryAction::Retry, 1, 0), Some(Duration::from_secs(2)));
+/// assert_eq!(policy.delay(RecoveryAction::Retry, 2, 0), Some(Duration::from_secs(4)));
+/// // The third attempt was the last one.
+/// assert_eq!(policy.delay(RecoveryAction::Retry, 3, 0), None);
+/// // Only transient failures are retried.
+/// assert_eq!(policy.delay(RecoveryAction::Fatal, 1, 0), None);
+/// assert_eq!(RetryPolicy::default().delay(RecoveryAction::Retry, 1, 0), None);
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct RetryPolicy {
+    /// The maximum number of attempts, including the first one.
+    /// Values of 0 and 1 both mean that no retries are made.
+    pub max_attempts: u32,
+
+    /// The delay before the first retry, which doubles for each following retry.
+    pub initial_backoff: Duration,
+
+    /// The upper limit for the delay between two attempts.
+    pub max_backoff: Duration,
+
+    /// Whether to randomize delays (to prevent multiple clients from retrying in lockstep).
+    ///
+    /// If enabled, each delay is chosen from between half of and the full backoff period.
+    pub jitter: bool,
+}
+
+impl RetryPolicy {
+    /// Creates a new policy making at most `max_attempts` attempts, waiting `initial_backoff`
+    /// before the first retry and doubling the delay for each further retry
+    /// (up to a maximum of 32 times the `initial_backoff`), with jitter enabled.
+    #[must_use]
+    pub fn exponential(max_attempts: u32, initial_backoff: Duration) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            initial_backoff,
+            max_backoff: initial_backoff.saturating_mul(32),
+            jitter: true,
+        }
+    }
+
+    /// Returns a policy which never retries.
+    #[must_use]
+    pub fn none() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 1,
+            initial_backoff: Duration::ZERO,
+            max_backoff: Duration::ZERO,
+            jitter: false,
+        }
+    }
+
+    /// Returns the delay after which a request should be retried, or `None` if it should not be
+    /// retried, given that `attempts` attempts have already failed, the last one of which
+    /// resulted in the given recovery `action`.
+    ///
+    /// Only [`RecoveryAction::Retry`] leads to a retry, as other failures need to be resolved
+    /// differently. `random` is used for jitter (if enabled) and should be chosen uniformly at
+    /// random by the caller for each call.
+    #[must_use]
+    pub fn delay(&self, action: RecoveryAction, attempts: u32, random: u32) -> Option<Duration> {
+        if action != RecoveryAction::Retry || attempts == 0 || attempts >= self.max_attempts {
+            return None;
+        }
+        let backoff = 2u32
+            .checked_pow(attempts - 1)
+            .and_then(|factor| self.initial_backoff.checked_mul(factor))
+            .map_or(self.max_backoff, |x| x.min(self.max_backoff));
+        if self.jitter {
+            // We use integers instead of floats, since the latter may be slow on constrained devices.
+            let half = backoff / 2;
+            let range = backoff.saturating_sub(half).as_nanos();
+            let random_part = range * u128::from(random) / u128::from(u32::MAX);
+            Some(half + Duration::from_nanos(u64::try_from(random_part).unwrap_or(u64::MAX)))
+        } else {
+            Some(backoff)
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy::none()
+    }
+}
+
+/// A default [`RetryPolicy`] along with overrides for specific message types.
+///
+/// The [`Default`] value retries up to four times using exponential backoff starting at two
+/// seconds (the default `ACK_TIMEOUT` of CoAP), except for [`AccessTokenRequest`]s,
+/// which are never retried.
+///
+/// # Example
+/// ```
+/// # use core::time::Duration;
+/// use dcaf::AccessTokenRequest;
+/// use dcaf::endpoints::recovery::{RetryPolicies, RetryPolicy};
+///
+/// let mut policies = RetryPolicies::default();
+/// assert_eq!(policies.policy_for::<AccessTokenRequest>(), &RetryPolicy::none());
+/// policies.set_override::<AccessTokenRequest>(RetryPolicy::exponential(2, Duration::from_secs(1)));
+/// assert_eq!(policies.policy_for::<AccessTokenRequest>().max_attempts, 2);
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RetryPolicies {
+    /// The policy used for all message types without an override.
+    pub default: RetryPolicy,
+
+    /// The policies for specific message types.
+    overrides: Vec<(TypeId, RetryPolicy)>,
+}
+
+impl RetryPolicies {
+    /// Creates new policies using the given `default` policy for all message types.
+    #[must_use]
+    pub fn new(default: RetryPolicy) -> RetryPolicies {
+        RetryPolicies {
+            default,
+            overrides: Vec::new(),
+        }
+    }
+
+    /// Sets the `policy` to use for messages of type `T`, replacing any previous override.
+    pub fn set_override<T>(&mut self, policy: RetryPolicy)
+    where
+        T: AceMessage + 'static,
+    {
+        let id = TypeId::of::<T>();
+        self.overrides.retain(|(x, _)| *x != id);
+        self.overrides.push((id, policy));
+    }
+
+    /// Returns the policy to use for messages of type `T`.
+    #[must_use]
+    pub fn policy_for<T>(&self) -> &RetryPolicy
+    where
+        T: AceMessage + 'static,
+    {
+        let id = TypeId::of::<T>();
+        self.overrides
+            .iter()
+            .find(|(x, _)| *x == id)
+            .map_or(&self.default, |(_, policy)| policy)
+    }
+}
+
+impl Default for RetryPolicies {
+    fn default() -> Self {
+        let mut policies = RetryPolicies::new(RetryPolicy::exponential(4, Duration::from_secs(2)));
+        policies.set_override::<AccessTokenRequest>(RetryPolicy::none());
+        policies
+    }
+}
+
+impl AccessTokenRequest {
+    /// Returns a modified copy of this request which may be accepted by the Authorization Server
+    /// after it rejected this request with the given `error`, or `None` if there is no such
+    /// modification.
+    ///
+    /// While [`RecoveryAction::for_error_code`] considers these errors [`Fatal`](RecoveryAction::Fatal)
+    /// (since the same request would be rejected again), clients can renegotiate as follows:
+    /// - For [`InvalidScope`](ErrorCode::InvalidScope), the scope is narrowed down to the one
+    ///   contained in the `hint` of the Resource Server, or set to it if this request contains no
+    ///   scope.
+    /// - For [`UnsupportedPopKey`](ErrorCode::UnsupportedPopKey), the proof-of-possession key is
+    ///   replaced by the first of the client's `alternative_keys` which uses another key type
+    ///   (or, if the key types are unknown, which is a different key).
+    ///
+    /// `None` is returned for all other errors, and whenever the modified request would be
+    /// identical to this one. To prevent loops, clients should still limit the number of
+    /// renegotiations per request.
+    ///
+    /// # Example
+    /// ```
+    /// # use dcaf::{AccessTokenRequest, AuthServerRequestCreationHint, ErrorCode, ErrorResponse, Scope};
+    /// # #[cfg(feature = "std")] {
+    /// let request = AccessTokenRequest::builder()
+    ///     .audience("sensor")
+    ///     .scope(Scope::try_from(vec!["read", "write"])?)
+    ///     .build()?;
+    /// let error = ErrorResponse::builder().error(ErrorCode::InvalidScope).build()?;
+    /// let hint = AuthServerRequestCreationHint::builder()
+    ///     .scope(Scope::try_from(vec!["read"])?)
+    ///     .build()?;
+    /// let retry = request.renegotiate(&error, Some(&hint), &[]).expect("scope can be narrowed");
+    /// assert_eq!(retry.scope, Some(Scope::try_from(vec!["read"])?));
+    /// // Narrowing the scope once more wouldn't change anything.
+    /// assert_eq!(retry.renegotiate(&error, Some(&hint), &[]), None);
+    /// # }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    #[must_use]
+    pub fn renegotiate(
+        &self,
+        error: &ErrorResponse,
+        hint: Option<&AuthServerRequestCreationHint>,
+        alternative_keys: &[ProofOfPossessionKey],
+    ) -> Option<AccessTokenRequest> {
+        let mut request = self.clone();
+        match error.error {
+            ErrorCode::InvalidScope => {
+                let allowed = hint.and_then(|x| x.scope.as_ref())?;
+                request.scope = match &self.scope {
+                    Some(scope) => Some(scope.narrow(allowed)?),
+                    None => Some(allowed.clone()),
+                };
+            }
+            ErrorCode::UnsupportedPopKey => {
+                let rejected = self.req_cnf.as_ref();
+                let alternative = alternative_keys.iter().find(|x| match (rejected, x) {
+                    (
+                        Some(ProofOfPossessionKey::PlainCoseKey(rejected)),
+                        ProofOfPossessionKey::PlainCoseKey(key),
+                    ) => key.kty != rejected.kty,
+                    (rejected, key) => rejected != Some(key),
+                })?;
+                request.req_cnf = Some(alternative.clone());
+            }
+            _ => return None,
+        }
+        if &request == self {
+            None
+        } else {
+            Some(request)
+        }
+    }
+}
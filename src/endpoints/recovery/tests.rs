@@ -0,0 +1,253 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+#[cfg(not(feature = "std"))]
+use {alloc::string::String, alloc::string::ToString, alloc::vec, alloc::vec::Vec};
+
+use coset::iana::EllipticCurve;
+use coset::CoseKeyBuilder;
+
+use crate::common::scope::TextEncodedScope;
+use crate::Scope;
+
+use super::*;
+
+#[test]
+fn test_coap_codes() {
+    assert_eq!(RecoveryAction::for_coap_code(coap_code::CREATED), None);
+    assert_eq!(
+        RecoveryAction::for_coap_code(coap_code::UNAUTHORIZED),
+        Some(RecoveryAction::RefreshToken)
+    );
+    assert_eq!(
+        RecoveryAction::for_coap_code(coap_code::FORBIDDEN),
+        Some(RecoveryAction::RequestScope)
+    );
+    assert_eq!(
+        RecoveryAction::for_coap_code(coap_code::GATEWAY_TIMEOUT),
+        Some(RecoveryAction::Retry)
+    );
+    assert_eq!(
+        RecoveryAction::for_coap_code(coap_code::BAD_REQUEST),
+        Some(RecoveryAction::Fatal)
+    );
+    // 5.01 Not Implemented
+    assert_eq!(
+        RecoveryAction::for_coap_code(0xA1),
+        Some(RecoveryAction::Fatal)
+    );
+}
+
+#[test]
+fn test_http_status() {
+    assert_eq!(RecoveryAction::for_http_status(201), None);
+    assert_eq!(
+        RecoveryAction::for_http_status(401),
+        Some(RecoveryAction::RefreshToken)
+    );
+    assert_eq!(
+        RecoveryAction::for_http_status(403),
+        Some(RecoveryAction::RequestScope)
+    );
+    assert_eq!(
+        RecoveryAction::for_http_status(503),
+        Some(RecoveryAction::Retry)
+    );
+    assert_eq!(
+        RecoveryAction::for_http_status(404),
+        Some(RecoveryAction::Fatal)
+    );
+}
+
+#[test]
+fn test_error_codes() {
+    assert_eq!(
+        RecoveryAction::for_error_code(&ErrorCode::InvalidGrant),
+        RecoveryAction::RefreshToken
+    );
+    for code in [
+        ErrorCode::InvalidRequest,
+        ErrorCode::InvalidClient,
+        ErrorCode::IncompatibleAceProfiles,
+        ErrorCode::Other(42),
+    ] {
+        assert_eq!(RecoveryAction::for_error_code(&code), RecoveryAction::Fatal);
+    }
+}
+
+#[test]
+fn test_retry_policy_backoff() {
+    let policy = RetryPolicy {
+        max_attempts: 10,
+        initial_backoff: Duration::from_secs(1),
+        max_backoff: Duration::from_secs(5),
+        jitter: false,
+    };
+    let delays: Vec<Option<Duration>> = (0..=10)
+        .map(|attempt| policy.delay(RecoveryAction::Retry, attempt, 0))
+        .collect();
+    assert_eq!(
+        delays,
+        [
+            None,
+            Some(1),
+            Some(2),
+            Some(4),
+            Some(5),
+            Some(5),
+            Some(5),
+            Some(5),
+            Some(5),
+            Some(5),
+            None
+        ]
+        .map(|x| x.map(Duration::from_secs))
+    );
+    assert_eq!(policy.delay(RecoveryAction::RefreshToken, 1, 0), None);
+    // Backoff must not overflow for high attempt counts.
+    let many = RetryPolicy {
+        max_attempts: u32::MAX,
+        ..policy
+    };
+    assert_eq!(
+        many.delay(RecoveryAction::Retry, 100, 0),
+        Some(Duration::from_secs(5))
+    );
+}
+
+#[test]
+fn test_retry_policy_jitter() {
+    let policy = RetryPolicy::exponential(3, Duration::from_secs(2));
+    assert_eq!(policy.max_backoff, Duration::from_secs(64));
+    assert_eq!(
+        policy.delay(RecoveryAction::Retry, 2, 0),
+        Some(Duration::from_secs(2))
+    );
+    assert_eq!(
+        policy.delay(RecoveryAction::Retry, 2, u32::MAX),
+        Some(Duration::from_secs(4))
+    );
+    let middle = policy
+        .delay(RecoveryAction::Retry, 2, u32::MAX / 2)
+        .expect("no delay");
+    assert!(middle > Duration::from_secs(2) && middle < Duration::from_secs(4));
+}
+
+#[test]
+fn test_retry_policies() {
+    let mut policies = RetryPolicies::default();
+    assert_eq!(
+        policies.policy_for::<AccessTokenRequest>(),
+        &RetryPolicy::none()
+    );
+    assert_eq!(policies.policy_for::<ErrorResponse>().max_attempts, 4);
+    policies.set_override::<AccessTokenRequest>(RetryPolicy::exponential(2, Duration::ZERO));
+    assert_eq!(policies.policy_for::<AccessTokenRequest>().max_attempts, 2);
+    let plain = RetryPolicies::new(RetryPolicy::none());
+    assert_eq!(
+        plain.policy_for::<AccessTokenRequest>(),
+        &RetryPolicy::none()
+    );
+}
+
+#[test]
+fn test_renegotiate_scope() -> Result<(), String> {
+    let error = ErrorResponse::builder()
+        .error(ErrorCode::InvalidScope)
+        .build()
+        .map_err(|x| x.to_string())?;
+    let scope = |x: &str| {
+        TextEncodedScope::try_from(x)
+            .map(Scope::from)
+            .map_err(|x| x.to_string())
+    };
+    let hint = AuthServerRequestCreationHint::builder()
+        .scope(scope("read config")?)
+        .build()
+        .map_err(|x| x.to_string())?;
+    let request = AccessTokenRequest::builder()
+        .scope(scope("write read")?)
+        .build()
+        .map_err(|x| x.to_string())?;
+    let retry = request
+        .renegotiate(&error, Some(&hint), &[])
+        .ok_or("scope must be narrowed")?;
+    assert_eq!(retry.scope, Some(scope("read")?));
+
+    // Without a scope, the hint's one is requested.
+    let unscoped = AccessTokenRequest::default()
+        .renegotiate(&error, Some(&hint), &[])
+        .ok_or("scope must be set")?;
+    assert_eq!(unscoped.scope, hint.scope);
+
+    // Nothing can be done without a hint or if nothing is left of the scope.
+    assert_eq!(request.renegotiate(&error, None, &[]), None);
+    let disjoint = AuthServerRequestCreationHint::builder()
+        .scope(scope("delete")?)
+        .build()
+        .map_err(|x| x.to_string())?;
+    assert_eq!(request.renegotiate(&error, Some(&disjoint), &[]), None);
+    Ok(())
+}
+
+#[test]
+fn test_renegotiate_pop_key() -> Result<(), String> {
+    let error = ErrorResponse::builder()
+        .error(ErrorCode::UnsupportedPopKey)
+        .build()
+        .map_err(|x| x.to_string())?;
+    let symmetric = ProofOfPossessionKey::PlainCoseKey(
+        CoseKeyBuilder::new_symmetric_key(vec![0xDC; 16]).build(),
+    );
+    let other_symmetric = ProofOfPossessionKey::PlainCoseKey(
+        CoseKeyBuilder::new_symmetric_key(vec![0xAF; 16]).build(),
+    );
+    let ec2 = ProofOfPossessionKey::PlainCoseKey(
+        CoseKeyBuilder::new_ec2_pub_key(EllipticCurve::P_256, vec![0x01; 32], vec![0x02; 32])
+            .build(),
+    );
+    let request = AccessTokenRequest::builder()
+        .req_cnf(symmetric.clone())
+        .build()
+        .map_err(|x| x.to_string())?;
+    let retry = request
+        .renegotiate(
+            &error,
+            None,
+            &[symmetric, other_symmetric.clone(), ec2.clone()],
+        )
+        .ok_or("key must be replaced")?;
+    assert_eq!(retry.req_cnf, Some(ec2));
+    assert_eq!(request.renegotiate(&error, None, &[other_symmetric]), None);
+
+    // Key IDs have no known key type, so any other key is used.
+    let key_id = ProofOfPossessionKey::KeyId(vec![0x01]);
+    let by_id = AccessTokenRequest::builder()
+        .req_cnf(key_id.clone())
+        .build()
+        .map_err(|x| x.to_string())?;
+    let retry = by_id
+        .renegotiate(
+            &error,
+            None,
+            &[key_id, ProofOfPossessionKey::KeyId(vec![0x02])],
+        )
+        .ok_or("key must be replaced")?;
+    assert_eq!(retry.req_cnf, Some(ProofOfPossessionKey::KeyId(vec![0x02])));
+
+    // Other errors aren't renegotiated.
+    let other = ErrorResponse::builder()
+        .error(ErrorCode::InvalidClient)
+        .build()
+        .map_err(|x| x.to_string())?;
+    assert_eq!(request.renegotiate(&other, None, &[]), None);
+    Ok(())
+}
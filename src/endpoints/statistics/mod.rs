@@ -0,0 +1,296 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+//! Contains the data model for [Authorization Server Statistics](AuthServerStatistics),
+//! a small CBOR document exposing operational statistics of an Authorization Server
+//! (such as the number of tokens issued per audience, the number of errors returned,
+//! and the [reasons](ReasonCode) for which requests have been rejected)
+//! to constrained management tools.
+//!
+//! These statistics are meant to be retrieved by management tools using the same CoAP stack
+//! as the other ACE-OAuth endpoints, and are not intended to replace the metrics the host of
+//! the Authorization Server collects.
+//!
+//! The Authorization Server reports the outcome of each request to a [`StatisticsHook`]
+//! (which [`AuthServerStatistics`] implements) and serves the collected statistics as an
+//! [`AceMessage`](crate::endpoints::message::AceMessage).
+//!
+//! # Example
+//! ```
+//! # use std::error::Error;
+//! use dcaf::ErrorCode;
+//! use dcaf::error::ReasonCode;
+//! use dcaf::endpoints::message::AceMessage;
+//! use dcaf::endpoints::statistics::{AuthServerStatistics, StatisticsHook};
+//!
+//! let mut statistics = AuthServerStatistics::default();
+//! statistics.token_issued("valve242");
+//! statistics.token_issued("valve242");
+//! statistics.token_issued("sensor1");
+//! statistics.error_returned(ErrorCode::InvalidScope);
+//! statistics.request_rejected(ReasonCode::ScopeNotAllowed);
+//! assert_eq!(statistics.rejections_for(ReasonCode::ScopeNotAllowed), 1);
+//! assert_eq!(statistics.tokens_issued_for("valve242"), 2);
+//! assert_eq!(statistics.total_tokens_issued(), 3);
+//!
+//! // On the management tool:
+//! let encoded = statistics.clone().encode()?;
+//! assert_eq!(AuthServerStatistics::decode(&encoded)?, statistics);
+//! # Ok::<(), Box<dyn Error>>(())
+//! ```
+
+use crate::error::ReasonCode;
+use crate::ErrorCode;
+
+#[cfg(not(feature = "std"))]
+use {alloc::string::String, alloc::vec::Vec};
+
+#[cfg(test)]
+mod tests;
+
+/// Operational statistics of an Authorization Server.
+///
+/// Counters are kept in the order in which their audience, error code, or reason code was
+/// first recorded.
+///
+/// For an example, see the [module-level documentation](self).
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct AuthServerStatistics {
+    /// The number of access tokens issued for each audience.
+    pub tokens_issued: Vec<(String, u64)>,
+
+    /// The number of error responses returned for each error code.
+    pub errors: Vec<(ErrorCode, u64)>,
+
+    /// The number of rejections for each reason code.
+    pub rejections: Vec<(ReasonCode, u64)>,
+
+    /// The number of seconds the Authorization Server has been running, if known.
+    pub uptime: Option<u64>,
+}
+
+impl AuthServerStatistics {
+    /// Returns the number of access tokens issued for the given `audience`.
+    #[must_use]
+    pub fn tokens_issued_for(&self, audience: &str) -> u64 {
+        self.tokens_issued
+            .iter()
+            .find(|(x, _)| x == audience)
+            .map_or(0, |(_, count)| *count)
+    }
+
+    /// Returns the number of access tokens issued for all audiences.
+    #[must_use]
+    pub fn total_tokens_issued(&self) -> u64 {
+        self.tokens_issued
+            .iter()
+            .fold(0, |sum, (_, count)| sum.saturating_add(*count))
+    }
+
+    /// Returns the number of error responses returned with the given `code`.
+    #[must_use]
+    pub fn errors_for(&self, code: ErrorCode) -> u64 {
+        self.errors
+            .iter()
+            .find(|(x, _)| *x == code)
+            .map_or(0, |(_, count)| *count)
+    }
+
+    /// Returns the number of error responses returned for all error codes.
+    #[must_use]
+    pub fn total_errors(&self) -> u64 {
+        self.errors
+            .iter()
+            .fold(0, |sum, (_, count)| sum.saturating_add(*count))
+    }
+
+    /// Returns the number of rejections with the given `reason`.
+    #[must_use]
+    pub fn rejections_for(&self, reason: ReasonCode) -> u64 {
+        self.rejections
+            .iter()
+            .find(|(x, _)| *x == reason)
+            .map_or(0, |(_, count)| *count)
+    }
+}
+
+/// A hook which is notified by the Authorization Server about the outcome of requests to its
+/// token endpoint, e.g., to collect [`AuthServerStatistics`].
+///
+/// For an example, see the [module-level documentation](self).
+pub trait StatisticsHook {
+    /// Called when an access token has been issued for the given `audience`.
+    fn token_issued(&mut self, audience: &str);
+
+    /// Called when an error response with the given `code` has been returned.
+    fn error_returned(&mut self, code: ErrorCode);
+
+    /// Called when a request has been rejected for the given `reason`, e.g., as obtained from
+    /// [`Rejection::reason_code`](crate::error::Rejection::reason_code).
+    ///
+    /// Several rejections may be reported for the same request. Does nothing by default.
+    fn request_rejected(&mut self, reason: ReasonCode) {
+        let _ = reason;
+    }
+}
+
+impl StatisticsHook for AuthServerStatistics {
+    fn token_issued(&mut self, audience: &str) {
+        if let Some((_, count)) = self.tokens_issued.iter_mut().find(|(x, _)| x == audience) {
+            *count = count.saturating_add(1);
+        } else {
+            self.tokens_issued.push((audience.into(), 1));
+        }
+    }
+
+    fn error_returned(&mut self, code: ErrorCode) {
+        if let Some((_, count)) = self.errors.iter_mut().find(|(x, _)| *x == code) {
+            *count = count.saturating_add(1);
+        } else {
+            self.errors.push((code, 1));
+        }
+    }
+
+    fn request_rejected(&mut self, reason: ReasonCode) {
+        if let Some((_, count)) = self.rejections.iter_mut().find(|(x, _)| *x == reason) {
+            *count = count.saturating_add(1);
+        } else {
+            self.rejections.push((reason, 1));
+        }
+    }
+}
+
+/// Contains conversion methods for ACE-OAuth data types.
+///
+/// One part of this is converting enum types from and to their CBOR abbreviations in
+/// [`cbor_abbreviations`](crate::constants::cbor_abbreviations),
+/// another part is implementing the [`ToCborMap`](crate::ToCborMap) type for the
+/// models which are represented as CBOR maps.
+mod conversion {
+    #[cfg(not(feature = "std"))]
+    use {alloc::boxed::Box, alloc::format};
+
+    use ciborium::value::Value;
+    use erased_serde::Serialize as ErasedSerialize;
+
+    use crate::common::cbor_map::{cbor_map_vec, decode_number, ToCborMap};
+    use crate::common::constants::cbor_abbreviations::{as_statistics, CborKey};
+    use crate::error::TryFromCborMapError;
+
+    use super::*;
+
+    /// Encodes the given `counters` as a CBOR map, encoding each key using `encode`,
+    /// or returns `None` if there are none.
+    fn encode_counters<T, F>(counters: &[(T, u64)], encode: F) -> Option<Value>
+    where
+        F: Fn(&T) -> Value,
+    {
+        if counters.is_empty() {
+            None
+        } else {
+            Some(Value::Map(
+                counters
+                    .iter()
+                    .map(|(key, count)| (encode(key), Value::from(*count)))
+                    .collect(),
+            ))
+        }
+    }
+
+    /// Decodes the given `value` as a CBOR map of counters, decoding each key using `decode`.
+    fn decode_counters<T, F>(
+        value: Value,
+        name: &str,
+        decode: F,
+    ) -> Result<Vec<(T, u64)>, TryFromCborMapError>
+    where
+        F: Fn(Value) -> Result<T, TryFromCborMapError>,
+    {
+        if let Value::Map(counters) = value {
+            counters
+                .into_iter()
+                .map(|(key, count)| match count {
+                    Value::Integer(count) => Ok((decode(key)?, decode_number(count, name)?)),
+                    _ => Err(TryFromCborMapError::from_message(format!(
+                        "counts of {name} must be integers"
+                    ))),
+                })
+                .collect()
+        } else {
+            Err(TryFromCborMapError::from_message(format!(
+                "{name} must be a map"
+            )))
+        }
+    }
+
+    impl ToCborMap for AuthServerStatistics {
+        fn to_cbor_map(&self) -> Vec<(i128, Option<Box<dyn ErasedSerialize + '_>>)> {
+            let tokens_issued = encode_counters(&self.tokens_issued, |audience| {
+                Value::Text(audience.clone())
+            });
+            let errors = encode_counters(&self.errors, |code| Value::from(i32::from(*code)));
+            let rejections =
+                encode_counters(&self.rejections, |reason| Value::from(u16::from(*reason)));
+            cbor_map_vec! {
+                as_statistics::TOKENS_ISSUED => tokens_issued,
+                as_statistics::ERRORS => errors,
+                as_statistics::UPTIME => self.uptime,
+                as_statistics::REJECTIONS => rejections
+            }
+        }
+
+        fn try_from_cbor_map(map: Vec<(i128, Value)>) -> Result<Self, TryFromCborMapError>
+        where
+            Self: Sized + ToCborMap,
+        {
+            let mut statistics = AuthServerStatistics::default();
+            for entry in map {
+                match (CborKey::try_from(entry.0)?, entry.1) {
+                    (as_statistics::TOKENS_ISSUED, x) => {
+                        statistics.tokens_issued =
+                            decode_counters(x, "tokens_issued", |audience| match audience {
+                                Value::Text(audience) => Ok(audience),
+                                _ => Err(TryFromCborMapError::from_message(
+                                    "audience must be a text string",
+                                )),
+                            })?;
+                    }
+                    (as_statistics::ERRORS, x) => {
+                        statistics.errors = decode_counters(x, "errors", |code| match code {
+                            Value::Integer(i) => {
+                                decode_number::<i32>(i, "error").map(ErrorCode::from)
+                            }
+                            _ => Err(TryFromCborMapError::from_message(
+                                "error must be an integer",
+                            )),
+                        })?;
+                    }
+                    (as_statistics::REJECTIONS, x) => {
+                        statistics.rejections =
+                            decode_counters(x, "rejections", |reason| match reason {
+                                Value::Integer(i) => {
+                                    decode_number::<u16>(i, "reason").map(ReasonCode::from)
+                                }
+                                _ => Err(TryFromCborMapError::from_message(
+                                    "reason must be an integer",
+                                )),
+                            })?;
+                    }
+                    (as_statistics::UPTIME, Value::Integer(x)) => {
+                        statistics.uptime = Some(decode_number(x, "uptime")?);
+                    }
+                    (key, _) => return Err(TryFromCborMapError::unknown_field(key)),
+                }
+            }
+            Ok(statistics)
+        }
+    }
+}
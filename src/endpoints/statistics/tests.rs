@@ -0,0 +1,76 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+#[cfg(not(feature = "std"))]
+use {alloc::string::ToString, alloc::vec};
+
+use crate::common::test_helper::expect_ser_de;
+use crate::constants::coap_code;
+use crate::endpoints::message::AceMessage;
+
+use super::*;
+
+fn example_statistics() -> AuthServerStatistics {
+    let mut statistics = AuthServerStatistics {
+        uptime: Some(3600),
+        ..AuthServerStatistics::default()
+    };
+    statistics.token_issued("valve242");
+    statistics.token_issued("sensor1");
+    statistics.token_issued("valve242");
+    statistics.error_returned(ErrorCode::InvalidScope);
+    statistics.error_returned(ErrorCode::Other(-42));
+    statistics
+}
+
+#[test]
+fn test_statistics() -> Result<(), String> {
+    expect_ser_de(
+        example_statistics(),
+        None,
+        "A301A26876616C7665323432026773656E736F72310102A2060138290103190E10",
+    )
+}
+
+#[test]
+fn test_empty_statistics() -> Result<(), String> {
+    expect_ser_de(AuthServerStatistics::default(), None, "A0")
+}
+
+#[test]
+fn test_counters() {
+    let statistics = example_statistics();
+    assert_eq!(
+        statistics.tokens_issued,
+        vec![("valve242".to_string(), 2), ("sensor1".to_string(), 1)]
+    );
+    assert_eq!(statistics.tokens_issued_for("valve242"), 2);
+    assert_eq!(statistics.tokens_issued_for("unknown"), 0);
+    assert_eq!(statistics.total_tokens_issued(), 3);
+    assert_eq!(statistics.errors_for(ErrorCode::InvalidScope), 1);
+    assert_eq!(statistics.errors_for(ErrorCode::InvalidClient), 0);
+    assert_eq!(statistics.total_errors(), 2);
+    assert_eq!(statistics.coap_code(), coap_code::CONTENT);
+    assert_eq!(statistics.http_status(), Some(200));
+}
+
+#[test]
+fn test_rejections() -> Result<(), String> {
+    let mut statistics = AuthServerStatistics::default();
+    statistics.request_rejected(ReasonCode::VerificationFailed);
+    statistics.request_rejected(ReasonCode::Other(999));
+    statistics.request_rejected(ReasonCode::VerificationFailed);
+    assert_eq!(statistics.rejections_for(ReasonCode::VerificationFailed), 2);
+    assert_eq!(statistics.rejections_for(ReasonCode::Throttled), 0);
+    assert_eq!(ReasonCode::from(300), ReasonCode::VerificationFailed);
+    assert_eq!(ReasonCode::from(999).category(), None);
+    expect_ser_de(statistics, None, "A104A219012C021903E701")
+}
@@ -0,0 +1,157 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+//! Contains the [`RequestBudget`] trait, which lets Authorization Servers reject floods of
+//! access token requests before spending any effort on decoding them or issuing tokens.
+//!
+//! Operators implement [`RequestBudget`] with their rate limiting strategy of choice (e.g., a token
+//! bucket) and call [`admit`](RequestBudget::admit) for every incoming request, *before* decoding
+//! its payload. As the request hasn't been decoded at this point, clients are identified by
+//! whatever the transport knows about them, e.g., the DTLS PSK identity or the peer's address.
+//!
+//! Rejected requests result in a [`RequestThrottledError`], which should be answered with
+//! [4.29 (Too Many Requests)](crate::constants::coap_code::TOO_MANY_REQUESTS)
+//! or HTTP 429, respectively.
+//!
+//! # Example
+//! ```
+//! # use dcaf::AccessTokenRequest;
+//! # use dcaf::constants::coap_code;
+//! # use dcaf::endpoints::message::AceMessage;
+//! use dcaf::endpoints::token_req::admission::{Admission, RequestBudget};
+//!
+//! /// Admits at most `remaining` requests in total.
+//! struct FixedBudget {
+//!     remaining: u32,
+//! }
+//!
+//! impl RequestBudget for FixedBudget {
+//!     fn admit_global(&mut self) -> Admission {
+//!         if let Some(remaining) = self.remaining.checked_sub(1) {
+//!             self.remaining = remaining;
+//!             Admission::Accept
+//!         } else {
+//!             Admission::Reject { retry_after: Some(60) }
+//!         }
+//!     }
+//!
+//!     fn refund_global(&mut self) {
+//!         self.remaining += 1;
+//!     }
+//!
+//!     fn admit_client(&mut self, _client: &[u8]) -> Admission {
+//!         Admission::Accept
+//!     }
+//! }
+//!
+//! let mut budget = FixedBudget { remaining: 1 };
+//! assert!(budget.admit(Some(b"client")).is_ok());
+//! let error = budget.admit(Some(b"client")).expect_err("budget must be exhausted");
+//! assert_eq!(error.coap_code(), coap_code::TOO_MANY_REQUESTS);
+//! assert_eq!(error.retry_after, Some(60));
+//! ```
+
+use crate::constants::coap_code;
+use crate::error::RequestThrottledError;
+
+#[cfg(test)]
+mod tests;
+
+/// Decision of a [`RequestBudget`] on whether to admit a request.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+pub enum Admission {
+    /// The request is within budget and may be processed.
+    Accept,
+
+    /// The request exceeds the budget and must be rejected.
+    Reject {
+        /// Number of seconds after which the client may try again, if known.
+        ///
+        /// In CoAP, this can be conveyed using the Max-Age option of the 4.29 response.
+        retry_after: Option<u32>,
+    },
+}
+
+/// Identifies which budget of a [`RequestBudget`] has been exhausted.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+pub enum BudgetScope {
+    /// The budget shared by all clients.
+    Global,
+
+    /// The budget of the individual client which sent the request.
+    Client,
+}
+
+/// Callbacks which decide whether the Authorization Server should process an incoming request,
+/// called before the request is decoded.
+///
+/// For details and an example, see the [module-level documentation](self).
+pub trait RequestBudget {
+    /// Decides whether the global budget (shared by all clients) admits another request,
+    /// consuming part of the budget if it does.
+    fn admit_global(&mut self) -> Admission;
+
+    /// Gives back the part of the global budget consumed by the last accepted call to
+    /// [`admit_global`](RequestBudget::admit_global).
+    ///
+    /// This is called by [`admit`](RequestBudget::admit) if the client's budget rejects a request
+    /// which the global budget already admitted, so that requests rejected by their client's
+    /// budget don't use up the budget of all other clients.
+    fn refund_global(&mut self);
+
+    /// Decides whether the budget of the given `client` admits another request,
+    /// consuming part of the budget if it does.
+    ///
+    /// `client` identifies the client on the transport layer, e.g., by its DTLS PSK identity.
+    fn admit_client(&mut self, client: &[u8]) -> Admission;
+
+    /// Checks the global budget and (if a `client` identity is known) the client's budget,
+    /// in this order.
+    ///
+    /// # Errors
+    /// If either of the two budgets rejects the request. Note that the client's budget isn't
+    /// consulted if the global budget already rejected the request, and that the global budget
+    /// is [refunded](RequestBudget::refund_global) if the client's budget rejects it.
+    fn admit(&mut self, client: Option<&[u8]>) -> Result<(), RequestThrottledError> {
+        if let Admission::Reject { retry_after } = self.admit_global() {
+            return Err(RequestThrottledError {
+                scope: BudgetScope::Global,
+                retry_after,
+            });
+        }
+        if let Some(client) = client {
+            if let Admission::Reject { retry_after } = self.admit_client(client) {
+                self.refund_global();
+                return Err(RequestThrottledError {
+                    scope: BudgetScope::Client,
+                    retry_after,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl RequestThrottledError {
+    /// Returns the CoAP code with which the rejected request should be answered,
+    /// i.e., [4.29 (Too Many Requests)](coap_code::TOO_MANY_REQUESTS).
+    #[must_use]
+    pub fn coap_code(&self) -> u8 {
+        coap_code::TOO_MANY_REQUESTS
+    }
+
+    /// Returns the HTTP status with which the rejected request should be answered,
+    /// i.e., 429 (Too Many Requests).
+    #[must_use]
+    pub fn http_status(&self) -> u16 {
+        429
+    }
+}
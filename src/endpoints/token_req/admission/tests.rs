@@ -0,0 +1,123 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+#[cfg(feature = "client")]
+use crate::endpoints::recovery::RecoveryAction;
+
+use super::*;
+
+/// Admits requests as long as neither the global nor the per-client count is exhausted,
+/// recording which clients have been consulted.
+struct CountingBudget {
+    global: u32,
+    per_client: u32,
+    consulted: Vec<Vec<u8>>,
+}
+
+impl RequestBudget for CountingBudget {
+    fn admit_global(&mut self) -> Admission {
+        match self.global.checked_sub(1) {
+            Some(remaining) => {
+                self.global = remaining;
+                Admission::Accept
+            }
+            None => Admission::Reject { retry_after: None },
+        }
+    }
+
+    fn refund_global(&mut self) {
+        self.global += 1;
+    }
+
+    fn admit_client(&mut self, client: &[u8]) -> Admission {
+        self.consulted.push(client.to_vec());
+        let used = self.consulted.iter().filter(|c| c == &client).count();
+        if used <= self.per_client as usize {
+            Admission::Accept
+        } else {
+            Admission::Reject {
+                retry_after: Some(10),
+            }
+        }
+    }
+}
+
+#[test]
+fn test_admit_per_client() {
+    let mut budget = CountingBudget {
+        global: 10,
+        per_client: 1,
+        consulted: Vec::new(),
+    };
+    assert_eq!(budget.admit(Some(b"a")), Ok(()));
+    assert_eq!(budget.admit(Some(b"b")), Ok(()));
+    assert_eq!(
+        budget.admit(Some(b"a")),
+        Err(RequestThrottledError {
+            scope: BudgetScope::Client,
+            retry_after: Some(10)
+        })
+    );
+    // Without a known client identity, only the global budget applies.
+    assert_eq!(budget.admit(None), Ok(()));
+}
+
+#[test]
+fn test_admit_client_rejection_refunds_global() {
+    let mut budget = CountingBudget {
+        global: 2,
+        per_client: 1,
+        consulted: Vec::new(),
+    };
+    assert_eq!(budget.admit(Some(b"a")), Ok(()));
+    for _ in 0..3 {
+        assert_eq!(
+            budget.admit(Some(b"a")).map_err(|e| e.scope),
+            Err(BudgetScope::Client)
+        );
+    }
+    // Requests rejected by their client's budget must not use up the global budget.
+    assert_eq!(budget.global, 1);
+    assert_eq!(budget.admit(Some(b"b")), Ok(()));
+    assert_eq!(budget.global, 0);
+}
+
+#[test]
+fn test_admit_global() {
+    let mut budget = CountingBudget {
+        global: 1,
+        per_client: 5,
+        consulted: Vec::new(),
+    };
+    assert_eq!(budget.admit(Some(b"a")), Ok(()));
+    let error = budget
+        .admit(Some(b"a"))
+        .expect_err("global budget must be exhausted");
+    assert_eq!(
+        error,
+        RequestThrottledError {
+            scope: BudgetScope::Global,
+            retry_after: None
+        }
+    );
+    // The client's budget must not be consumed by globally rejected requests.
+    assert_eq!(budget.consulted.len(), 1);
+    assert_eq!(error.coap_code(), coap_code::TOO_MANY_REQUESTS);
+    assert_eq!(error.http_status(), 429);
+    #[cfg(feature = "client")]
+    assert_eq!(
+        RecoveryAction::for_coap_code(error.coap_code()),
+        Some(RecoveryAction::Retry)
+    );
+}
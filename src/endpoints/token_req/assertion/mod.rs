@@ -0,0 +1,300 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+//! Contains functions for authenticating clients at the token endpoint using a signed CWT,
+//! placed in the [`client_assertion`](super::AccessTokenRequest::client_assertion) parameter.
+//!
+//! This works analogously to the JWT client assertions of
+//! [RFC 7523](https://www.rfc-editor.org/rfc/rfc7523#section-3), but uses a CWT protected by a
+//! `COSE_Sign1` structure instead: Both the issuer and the subject of the assertion are the
+//! identifier of the client, its audience is the Authorization Server, and it must contain a
+//! unique identifier (`cti`), the time it has been issued at, and an expiration time.
+//!
+//! Clients create such an assertion using [`create_client_assertion`], while Authorization
+//! Servers verify it against the key in the client's registration using
+//! `verify_client_assertion` (which requires the `as` feature). As required by
+//! [section 3 of RFC 7523](https://www.rfc-editor.org/rfc/rfc7523#section-3), the Authorization
+//! Server limits how long assertions may be valid and only accepts each of them once, so that a
+//! captured assertion can't be replayed.
+//!
+//! # Example
+//! A client which has registered the key of `signer` at the AS creates an assertion valid for
+//! a minute and uses it in its request:
+//! ```
+//! # use coset::Header;
+//! # use dcaf::{AccessTokenRequest, CoseCipherCommon, CoseSign1Cipher};
+//! # use dcaf::error::CoseCipherError;
+//! # use dcaf::endpoints::token_req::AccessTokenRequestBuilderError;
+//! use dcaf::endpoints::token_req::assertion::create_client_assertion;
+//! # struct FakeSigner {}
+//! # impl CoseCipherCommon for FakeSigner {
+//! #     type Error = String;
+//! #     fn header(&self, unprotected_header: &mut Header, protected_header: &mut Header) -> Result<(), CoseCipherError<Self::Error>> {
+//! #         Ok(())
+//! #     }
+//! # }
+//! # impl CoseSign1Cipher for FakeSigner {
+//! #     fn generate_signature(&mut self, target: &[u8]) -> Vec<u8> {
+//! #         target.to_vec()
+//! #     }
+//! #     fn verify_signature(&mut self, signature: &[u8], signed_data: &[u8]) -> Result<(), CoseCipherError<Self::Error>> {
+//! #         if signature == signed_data { Ok(()) } else { Err(CoseCipherError::VerificationFailure) }
+//! #     }
+//! # }
+//! # let mut signer = FakeSigner {};
+//! let assertion = create_client_assertion(
+//!     "myclient", "coaps://as.example.com", vec![0x01], 1_700_000_000, 1_700_000_060, &mut signer
+//! ).expect("signing failed");
+//! let request = AccessTokenRequest::builder()
+//!     .client_id("myclient")
+//!     .client_assertion(assertion)
+//!     .build()?;
+//! # Ok::<(), AccessTokenRequestBuilderError>(())
+//! ```
+
+#[cfg(feature = "as")]
+use coset::cwt::ClaimsSet;
+use coset::cwt::{ClaimsSetBuilder, Timestamp};
+#[cfg(feature = "as")]
+use coset::{CborSerializable, CoseError, CoseSign1};
+
+use crate::common::cbor_values::ByteString;
+#[cfg(feature = "as")]
+use crate::common::cbor_values::ProofOfPossessionKey;
+#[cfg(feature = "as")]
+use crate::endpoints::token_req::AccessTokenRequest;
+use crate::error::AccessTokenError;
+#[cfg(feature = "as")]
+use crate::error::ClientAssertionError;
+use crate::sign_access_token;
+#[cfg(feature = "as")]
+use crate::token::storage::{AssertionReplayCache, ClientRecord, ClientRegistry};
+use crate::token::CoseSignCipher;
+#[cfg(feature = "as")]
+use crate::token::CoseVerifyCipher;
+
+#[cfg(not(feature = "std"))]
+use alloc::string::ToString;
+
+#[cfg(test)]
+mod tests;
+
+/// Creates a client assertion for the client identified by `client_id`, intended for the
+/// Authorization Server identified by `audience`, and signs it using the given `cipher`.
+///
+/// The `token_id` must be unique for each assertion of the client (e.g., randomly generated),
+/// as the Authorization Server only accepts each assertion once.
+/// The assertion is valid from `issued_at` until `expires_at` (both in seconds since the
+/// Unix epoch), which should be as short as possible, and should be placed in the
+/// [`client_assertion`](super::AccessTokenRequest::client_assertion) parameter of a request.
+///
+/// For an example, see the [module-level documentation](self).
+///
+/// # Errors
+/// If the assertion couldn't be serialized or signed.
+pub fn create_client_assertion<T>(
+    client_id: &str,
+    audience: &str,
+    token_id: ByteString,
+    issued_at: i64,
+    expires_at: i64,
+    cipher: &mut T,
+) -> Result<ByteString, AccessTokenError<T::Error>>
+where
+    T: CoseSignCipher,
+{
+    let claims = ClaimsSetBuilder::new()
+        .issuer(client_id.to_string())
+        .subject(client_id.to_string())
+        .audience(audience.to_string())
+        .cwt_id(token_id)
+        .issued_at(Timestamp::WholeSeconds(issued_at))
+        .expiration_time(Timestamp::WholeSeconds(expires_at))
+        .build();
+    sign_access_token(claims, cipher, None, None, None)
+}
+
+/// Returns the given `timestamp` in seconds since the Unix epoch.
+#[cfg(feature = "as")]
+#[allow(clippy::cast_precision_loss)]
+fn seconds(timestamp: &Timestamp) -> f64 {
+    match timestamp {
+        Timestamp::WholeSeconds(x) => *x as f64,
+        Timestamp::FractionalSeconds(x) => *x,
+    }
+}
+
+/// Verifies the [`client_assertion`](AccessTokenRequest::client_assertion) of the given
+/// `request`, returning the record of the authenticated client from the `storage`.
+///
+/// The assertion must be intended for the given `audience` (i.e., this Authorization Server)
+/// and its signature must be valid for the key registered for the client. The cipher used to
+/// verify the signature is obtained by calling `cipher_for` with that key.
+/// If the `request` also contains a [`client_id`](AccessTokenRequest::client_id), it must
+/// identify the same client as the assertion.
+///
+/// The assertion must contain `cti`, `iat`, and `exp` claims. At `now` (in seconds since the
+/// Unix epoch), it must neither have expired nor lie in the future (according to its `iat` and
+/// its `nbf` claim, if present), and it may be valid for at most `max_lifetime` seconds.
+/// Its `cti` is recorded in the [`AssertionReplayCache`] of the `storage` once everything else
+/// has been verified, so that it can't be used again.
+///
+/// # Example
+/// ```
+/// # use coset::Header;
+/// # use dcaf::{AccessTokenRequest, CoseCipherCommon, CoseSign1Cipher};
+/// # use dcaf::common::cbor_values::ProofOfPossessionKey;
+/// # use dcaf::error::{ClientAssertionError, CoseCipherError};
+/// # use dcaf::token::storage::{ClientRecord, ClientRegistry, InMemoryStorage};
+/// use dcaf::endpoints::token_req::assertion::{create_client_assertion, verify_client_assertion};
+/// # struct FakeSigner {}
+/// # impl CoseCipherCommon for FakeSigner {
+/// #     type Error = String;
+/// #     fn header(&self, unprotected_header: &mut Header, protected_header: &mut Header) -> Result<(), CoseCipherError<Self::Error>> {
+/// #         Ok(())
+/// #     }
+/// # }
+/// # impl CoseSign1Cipher for FakeSigner {
+/// #     fn generate_signature(&mut self, target: &[u8]) -> Vec<u8> {
+/// #         target.to_vec()
+/// #     }
+/// #     fn verify_signature(&mut self, signature: &[u8], signed_data: &[u8]) -> Result<(), CoseCipherError<Self::Error>> {
+/// #         if signature == signed_data { Ok(()) } else { Err(CoseCipherError::VerificationFailure) }
+/// #     }
+/// # }
+/// # #[cfg(feature = "std")] {
+/// let mut storage = InMemoryStorage::default();
+/// let mut client = ClientRecord::new("myclient");
+/// client.key = Some(ProofOfPossessionKey::KeyId(vec![0xDC, 0xAF]));
+/// storage.register_client(client.clone())?;
+///
+/// # let assertion = create_client_assertion(
+/// #     "myclient", "coaps://as.example.com", vec![0x01], 1_700_000_000, 1_700_000_060, &mut FakeSigner {}
+/// # ).expect("signing failed");
+/// let request = AccessTokenRequest::builder().client_assertion(assertion).build()?;
+/// let mut verify = |storage: &mut InMemoryStorage| verify_client_assertion(
+///     &request, storage, "coaps://as.example.com", 1_700_000_030, 300, |_key| FakeSigner {}
+/// );
+/// assert_eq!(verify(&mut storage).ok(), Some(client));
+/// // The assertion can't be replayed.
+/// assert!(matches!(verify(&mut storage), Err(ClientAssertionError::Replayed)));
+/// # }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+///
+/// # Errors
+/// If the assertion is missing or can't be verified, as detailed by the variants of
+/// [`ClientAssertionError`]. Any such error should be answered with an
+/// [`InvalidClient`](crate::endpoints::token_req::ErrorCode::InvalidClient) error response.
+#[cfg(feature = "as")]
+pub fn verify_client_assertion<S, E, T, F>(
+    request: &AccessTokenRequest,
+    storage: &mut S,
+    audience: &str,
+    now: i64,
+    max_lifetime: u32,
+    cipher_for: F,
+) -> Result<ClientRecord, ClientAssertionError<T::Error, E>>
+where
+    S: ClientRegistry<Error = E> + AssertionReplayCache<Error = E>,
+    T: CoseVerifyCipher,
+    F: FnOnce(&ProofOfPossessionKey) -> T,
+{
+    let assertion = request
+        .client_assertion
+        .as_ref()
+        .ok_or(ClientAssertionError::MissingAssertion)?;
+    let sign = CoseSign1::from_slice(assertion).map_err(|e| {
+        ClientAssertionError::InvalidAssertion(AccessTokenError::from_cose_error(e))
+    })?;
+    // The claims are needed to find out which key the signature has to be verified with,
+    // but are only trusted once that has been done.
+    let claims = sign
+        .payload
+        .as_deref()
+        .ok_or(CoseError::UnexpectedItem("nil", "bstr"))
+        .and_then(ClaimsSet::from_slice)
+        .map_err(|e| {
+            ClientAssertionError::InvalidAssertion(AccessTokenError::from_cose_error(e))
+        })?;
+    let client_id = claims
+        .issuer
+        .as_deref()
+        .ok_or(ClientAssertionError::MissingClaim("iss"))?;
+    if claims.subject.as_deref() != Some(client_id)
+        || request
+            .client_id
+            .as_deref()
+            .map_or(false, |x| x != client_id)
+    {
+        return Err(ClientAssertionError::ClientMismatch);
+    }
+    let client = storage
+        .client(client_id)
+        .map_err(ClientAssertionError::Storage)?
+        .ok_or_else(|| ClientAssertionError::UnknownClient(client_id.to_string()))?;
+    let key = client
+        .key
+        .as_ref()
+        .ok_or(ClientAssertionError::MissingKey)?;
+    let mut cipher = cipher_for(key);
+    sign.verify_signature(&[0; 0], |signature, data| {
+        cipher.verify_signature(signature, data)
+    })
+    .map_err(|e| {
+        ClientAssertionError::InvalidAssertion(AccessTokenError::from_cose_cipher_error(e))
+    })?;
+    match claims.audience {
+        Some(ref x) if x == audience => {}
+        Some(x) => return Err(ClientAssertionError::WrongAudience(x)),
+        None => return Err(ClientAssertionError::MissingClaim("aud")),
+    }
+    let token_id = claims
+        .cwt_id
+        .as_ref()
+        .ok_or(ClientAssertionError::MissingClaim("cti"))?;
+    let issued_at = claims
+        .issued_at
+        .as_ref()
+        .map(seconds)
+        .ok_or(ClientAssertionError::MissingClaim("iat"))?;
+    let expires_at = claims
+        .expiration_time
+        .as_ref()
+        .map(seconds)
+        .ok_or(ClientAssertionError::MissingClaim("exp"))?;
+    let now_seconds = seconds(&Timestamp::WholeSeconds(now));
+    if expires_at <= now_seconds {
+        return Err(ClientAssertionError::Expired);
+    }
+    if issued_at > now_seconds
+        || claims
+            .not_before
+            .as_ref()
+            .map_or(false, |x| seconds(x) > now_seconds)
+    {
+        return Err(ClientAssertionError::NotYetValid);
+    }
+    if expires_at - issued_at > f64::from(max_lifetime) {
+        return Err(ClientAssertionError::LifetimeTooLong);
+    }
+    // Rounded up, so the identifier is remembered for as long as the assertion is valid.
+    #[allow(clippy::cast_possible_truncation)]
+    let expiration = expires_at as i64 + 1;
+    if storage
+        .record_assertion(client_id, token_id, expiration, now)
+        .map_err(ClientAssertionError::Storage)?
+    {
+        Ok(client)
+    } else {
+        Err(ClientAssertionError::Replayed)
+    }
+}
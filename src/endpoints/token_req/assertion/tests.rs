@@ -0,0 +1,256 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+use coset::cwt::ClaimsSet;
+use coset::{CborSerializable, CoseSign1};
+
+#[cfg(not(feature = "std"))]
+use {alloc::string::String, alloc::vec};
+
+use crate::common::test_helper::FakeCrypto;
+
+use super::*;
+
+const AS_URI: &str = "coaps://as.example.com";
+
+#[test]
+fn test_create_client_assertion() -> Result<(), AccessTokenError<String>> {
+    let assertion = create_client_assertion(
+        "myclient",
+        AS_URI,
+        vec![0x01],
+        1000,
+        2000,
+        &mut FakeCrypto {},
+    )?;
+    let sign = CoseSign1::from_slice(&assertion).map_err(AccessTokenError::from_cose_error)?;
+    let claims = ClaimsSet::from_slice(&sign.payload.expect("payload must be present"))
+        .map_err(AccessTokenError::from_cose_error)?;
+    assert_eq!(claims.issuer.as_deref(), Some("myclient"));
+    assert_eq!(claims.subject.as_deref(), Some("myclient"));
+    assert_eq!(claims.audience.as_deref(), Some(AS_URI));
+    assert_eq!(claims.cwt_id, Some(vec![0x01]));
+    assert_eq!(claims.issued_at, Some(Timestamp::WholeSeconds(1000)));
+    assert_eq!(claims.expiration_time, Some(Timestamp::WholeSeconds(2000)));
+    Ok(())
+}
+
+#[cfg(feature = "as")]
+mod verification {
+    use core::convert::Infallible;
+
+    use coset::cwt::ClaimsSetBuilder;
+
+    #[cfg(not(feature = "std"))]
+    use {alloc::string::ToString, alloc::vec};
+
+    use crate::common::cbor_values::ProofOfPossessionKey;
+    use crate::token::storage::InMemoryStorage;
+
+    use super::*;
+
+    const MAX_LIFETIME: u32 = 1000;
+
+    fn registry() -> Result<InMemoryStorage, Infallible> {
+        let mut registry = InMemoryStorage::default();
+        let mut client = ClientRecord::new("myclient");
+        client.key = Some(ProofOfPossessionKey::KeyId(vec![0xDC, 0xAF]));
+        registry.register_client(client)?;
+        registry.register_client(ClientRecord::new("keyless"))?;
+        Ok(registry)
+    }
+
+    fn verify(
+        client_id: Option<&str>,
+        assertion: ByteString,
+    ) -> Result<ClientRecord, ClientAssertionError<String, Infallible>> {
+        let mut request = AccessTokenRequest::builder();
+        if let Some(client_id) = client_id {
+            request.client_id(client_id);
+        }
+        let request = request
+            .client_assertion(assertion)
+            .build()
+            .expect("request must be valid");
+        verify_client_assertion(
+            &request,
+            &mut registry().unwrap(),
+            AS_URI,
+            1500,
+            MAX_LIFETIME,
+            |_| FakeCrypto {},
+        )
+    }
+
+    fn assertion(client_id: &str, audience: &str, expires_at: i64) -> ByteString {
+        create_client_assertion(
+            client_id,
+            audience,
+            vec![0x01],
+            1000,
+            expires_at,
+            &mut FakeCrypto {},
+        )
+        .expect("signing must succeed")
+    }
+
+    fn sign(claims: ClaimsSet) -> ByteString {
+        sign_access_token(claims, &mut FakeCrypto {}, None, None, None)
+            .expect("signing must succeed")
+    }
+
+    fn claims() -> ClaimsSetBuilder {
+        ClaimsSetBuilder::new()
+            .issuer("myclient".to_string())
+            .subject("myclient".to_string())
+            .audience(AS_URI.to_string())
+            .cwt_id(vec![0x01])
+            .issued_at(Timestamp::WholeSeconds(1000))
+            .expiration_time(Timestamp::WholeSeconds(2000))
+    }
+
+    #[test]
+    fn test_verify_client_assertion() {
+        let client = verify(Some("myclient"), assertion("myclient", AS_URI, 2000))
+            .expect("assertion must be valid");
+        assert_eq!(client.client_id, "myclient");
+        assert!(verify(None, assertion("myclient", AS_URI, 2000)).is_ok());
+
+        assert!(matches!(
+            verify(Some("otherclient"), assertion("myclient", AS_URI, 2000)),
+            Err(ClientAssertionError::ClientMismatch)
+        ));
+        assert!(matches!(
+            verify(None, assertion("myclient", "coaps://other.example.com", 2000)),
+            Err(ClientAssertionError::WrongAudience(x)) if x == "coaps://other.example.com"
+        ));
+        assert!(matches!(
+            verify(None, assertion("myclient", AS_URI, 1500)),
+            Err(ClientAssertionError::Expired)
+        ));
+        assert!(matches!(
+            verify(None, assertion("unknown", AS_URI, 2000)),
+            Err(ClientAssertionError::UnknownClient(x)) if x == "unknown"
+        ));
+        assert!(matches!(
+            verify(None, assertion("keyless", AS_URI, 2000)),
+            Err(ClientAssertionError::MissingKey)
+        ));
+
+        let mut tampered = assertion("myclient", AS_URI, 2000);
+        if let Some(last) = tampered.last_mut() {
+            *last ^= 0xFF;
+        }
+        assert!(matches!(
+            verify(None, tampered),
+            Err(ClientAssertionError::InvalidAssertion(_))
+        ));
+    }
+
+    #[test]
+    fn test_verify_client_assertion_claims() {
+        assert!(verify(None, sign(claims().build())).is_ok());
+        let mismatched_subject = claims().subject("otherclient".to_string()).build();
+        assert!(matches!(
+            verify(None, sign(mismatched_subject)),
+            Err(ClientAssertionError::ClientMismatch)
+        ));
+        let mut without_expiration = claims().build();
+        without_expiration.expiration_time = None;
+        assert!(matches!(
+            verify(None, sign(without_expiration)),
+            Err(ClientAssertionError::MissingClaim("exp"))
+        ));
+        let mut without_token_id = claims().build();
+        without_token_id.cwt_id = None;
+        assert!(matches!(
+            verify(None, sign(without_token_id)),
+            Err(ClientAssertionError::MissingClaim("cti"))
+        ));
+        let mut without_issued_at = claims().build();
+        without_issued_at.issued_at = None;
+        assert!(matches!(
+            verify(None, sign(without_issued_at)),
+            Err(ClientAssertionError::MissingClaim("iat"))
+        ));
+
+        let request = AccessTokenRequest::builder()
+            .client_id("myclient")
+            .build()
+            .expect("request must be valid");
+        assert!(matches!(
+            verify_client_assertion(
+                &request,
+                &mut registry().unwrap(),
+                AS_URI,
+                1500,
+                MAX_LIFETIME,
+                |_| FakeCrypto {}
+            ),
+            Err(ClientAssertionError::MissingAssertion)
+        ));
+    }
+
+    #[test]
+    fn test_verify_client_assertion_validity() {
+        let issued_in_future = claims().issued_at(Timestamp::WholeSeconds(1600)).build();
+        assert!(matches!(
+            verify(None, sign(issued_in_future)),
+            Err(ClientAssertionError::NotYetValid)
+        ));
+        let not_yet_valid = claims()
+            .not_before(Timestamp::FractionalSeconds(1500.5))
+            .build();
+        assert!(matches!(
+            verify(None, sign(not_yet_valid)),
+            Err(ClientAssertionError::NotYetValid)
+        ));
+        let valid_now = claims().not_before(Timestamp::WholeSeconds(1500)).build();
+        assert!(verify(None, sign(valid_now)).is_ok());
+        let too_long = claims()
+            .expiration_time(Timestamp::WholeSeconds(2001))
+            .build();
+        assert!(matches!(
+            verify(None, sign(too_long)),
+            Err(ClientAssertionError::LifetimeTooLong)
+        ));
+    }
+
+    #[test]
+    fn test_verify_client_assertion_replay() -> Result<(), Infallible> {
+        let verify_at = |storage: &mut InMemoryStorage, assertion: &ByteString, now: i64| {
+            let request = AccessTokenRequest::builder()
+                .client_assertion(assertion.clone())
+                .build()
+                .expect("request must be valid");
+            verify_client_assertion(&request, storage, AS_URI, now, MAX_LIFETIME, |_| {
+                FakeCrypto {}
+            })
+        };
+        let mut storage = registry()?;
+        let mut other = ClientRecord::new("otherclient");
+        other.key = Some(ProofOfPossessionKey::KeyId(vec![0x01]));
+        storage.register_client(other)?;
+
+        let first = assertion("myclient", AS_URI, 2000);
+        assert!(verify_at(&mut storage, &first, 1500).is_ok());
+        assert!(matches!(
+            verify_at(&mut storage, &first, 1600),
+            Err(ClientAssertionError::Replayed)
+        ));
+        // A different identifier is no replay, and neither is the same one of another client.
+        let second = sign(claims().cwt_id(vec![0x02]).build());
+        assert!(verify_at(&mut storage, &second, 1600).is_ok());
+        let other_assertion = assertion("otherclient", AS_URI, 2000);
+        assert!(verify_at(&mut storage, &other_assertion, 1600).is_ok());
+        Ok(())
+    }
+}
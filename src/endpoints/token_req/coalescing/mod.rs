@@ -0,0 +1,173 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+//! Contains the [`RequestCoalescer`], which lets clients coalesce concurrent, equivalent access
+//! token requests into a single request to the Authorization Server.
+//!
+//! Requests are equivalent if they only differ in their nonces (i.e., the
+//! [`client_nonce`](AccessTokenRequest::client_nonce) and the
+//! [`confirmation_nonce`](AccessTokenRequest::confirmation_nonce)). In particular, requests of
+//! different clients or for different proof-of-possession keys are never coalesced, as the
+//! resulting token would be bound to the client and key of the request which has been sent.
+//! Waiting tasks must hence check the shared response against the request which has been sent.
+//!
+//! When multiple tasks of an application need a token for the same audience and scope at the
+//! same time, sending one request per task would put needless load on the Authorization Server
+//! and (if the Resource Server issues client nonces) use up one nonce per request.
+//! Instead, each task [enqueues](RequestCoalescer::enqueue) its request along with a *waiter*
+//! (e.g., a channel sender or a waker, depending on the application's executor), and only the
+//! first task is told to actually [send](Coalesced::Send) the request. Once the response
+//! (or error) has been received, [`complete`](RequestCoalescer::complete) returns all waiters,
+//! which the application then notifies with (a clone of) the shared result.
+//!
+//! Like the other helpers of this crate, this does no I/O itself. To share it between threads,
+//! wrap it in a mutex.
+//!
+//! # Example
+//! ```
+//! # use std::error::Error;
+//! use dcaf::AccessTokenRequest;
+//! use dcaf::endpoints::token_req::coalescing::{Coalesced, RequestCoalescer};
+//!
+//! # #[cfg(feature = "std")] {
+//! let request = AccessTokenRequest::builder()
+//!     .client_id("myclient")
+//!     .audience("valve242")
+//!     .build()?;
+//! // The waiters are identified by a task number here.
+//! let mut coalescer = RequestCoalescer::new();
+//! assert_eq!(coalescer.enqueue(&request, 1), Coalesced::Send);
+//! assert_eq!(coalescer.enqueue(&request, 2), Coalesced::Wait);
+//!
+//! // Task 1 sends the request and receives the response, which is then shared with task 2.
+//! assert_eq!(coalescer.complete(&request), vec![1, 2]);
+//! assert!(coalescer.is_empty());
+//! # }
+//! # Ok::<(), Box<dyn Error>>(())
+//! ```
+
+use crate::AccessTokenRequest;
+
+#[cfg(not(feature = "std"))]
+use {alloc::vec, alloc::vec::Vec};
+
+#[cfg(test)]
+mod tests;
+
+/// What a task should do after [enqueuing](RequestCoalescer::enqueue) its access token request.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+pub enum Coalesced {
+    /// No equivalent request is in flight, so this task has to send the request to the
+    /// Authorization Server and call [`complete`](RequestCoalescer::complete) once it's done.
+    Send,
+
+    /// An equivalent request is already in flight, so this task has to wait until its waiter
+    /// is returned by [`complete`](RequestCoalescer::complete).
+    Wait,
+}
+
+/// An access token request which has been sent and hasn't been completed yet.
+#[derive(Debug, PartialEq, Clone)]
+struct InFlight<W> {
+    /// The request, without its nonces.
+    request: AccessTokenRequest,
+
+    /// The waiters of the tasks which need the result, in the order in which they were enqueued.
+    waiters: Vec<W>,
+}
+
+/// Returns the given `request` without its nonces, which is what equivalent requests have in
+/// common.
+fn without_nonces(request: &AccessTokenRequest) -> AccessTokenRequest {
+    AccessTokenRequest {
+        client_nonce: None,
+        confirmation_nonce: None,
+        ..request.clone()
+    }
+}
+
+impl<W> InFlight<W> {
+    /// Returns `true` if the given `request` is equivalent to this one, i.e., if it only differs
+    /// in its nonces (so that, e.g., its client, proof-of-possession key, profile, audience, and
+    /// scope are the same).
+    fn matches(&self, request: &AccessTokenRequest) -> bool {
+        self.request == without_nonces(request)
+    }
+}
+
+/// Coalesces concurrent, equivalent access token requests, keeping track of the waiters of each
+/// request which is in flight.
+///
+/// `W` is the type of the waiters, which are only stored and returned, but never used otherwise.
+///
+/// For an example, see the [module-level documentation](self).
+#[derive(Debug, PartialEq, Clone)]
+pub struct RequestCoalescer<W> {
+    /// The requests which are currently in flight.
+    in_flight: Vec<InFlight<W>>,
+}
+
+impl<W> Default for RequestCoalescer<W> {
+    fn default() -> Self {
+        RequestCoalescer {
+            in_flight: Vec::new(),
+        }
+    }
+}
+
+impl<W> RequestCoalescer<W> {
+    /// Creates a new [`RequestCoalescer`] without any requests in flight.
+    #[must_use]
+    pub fn new() -> RequestCoalescer<W> {
+        RequestCoalescer::default()
+    }
+
+    /// Returns the number of distinct requests which are currently in flight.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.in_flight.len()
+    }
+
+    /// Returns `true` if no requests are currently in flight.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.in_flight.is_empty()
+    }
+
+    /// Enqueues the given `request` of a task along with its `waiter`, returning whether the task
+    /// has to [send](Coalesced::Send) the request or [wait](Coalesced::Wait) for an equivalent
+    /// request (i.e., one which only differs in its nonces) which is already in flight.
+    pub fn enqueue(&mut self, request: &AccessTokenRequest, waiter: W) -> Coalesced {
+        if let Some(in_flight) = self.in_flight.iter_mut().find(|x| x.matches(request)) {
+            in_flight.waiters.push(waiter);
+            Coalesced::Wait
+        } else {
+            self.in_flight.push(InFlight {
+                request: without_nonces(request),
+                waiters: vec![waiter],
+            });
+            Coalesced::Send
+        }
+    }
+
+    /// Marks the in-flight request equivalent to the given `request` as completed (regardless of
+    /// whether it succeeded), returning the waiters of all tasks which enqueued it, including
+    /// the one which sent it.
+    ///
+    /// Returns an empty vector if no equivalent request is in flight.
+    pub fn complete(&mut self, request: &AccessTokenRequest) -> Vec<W> {
+        self.in_flight
+            .iter()
+            .position(|x| x.matches(request))
+            .map(|index| self.in_flight.remove(index).waiters)
+            .unwrap_or_default()
+    }
+}
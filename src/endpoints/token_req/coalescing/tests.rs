@@ -0,0 +1,112 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+#[cfg(not(feature = "std"))]
+use {alloc::string::ToString, alloc::vec};
+
+use crate::common::cbor_values::ProofOfPossessionKey;
+use crate::common::scope::TextEncodedScope;
+use crate::Scope;
+
+use super::*;
+
+fn request(audience: Option<&str>, scope: Option<&str>) -> AccessTokenRequest {
+    AccessTokenRequest {
+        audience: audience.map(ToString::to_string),
+        scope: scope
+            .map(|x| Scope::from(TextEncodedScope::try_from(x).expect("example scopes are valid"))),
+        client_id: Some("myclient".to_string()),
+        ..AccessTokenRequest::default()
+    }
+}
+
+#[test]
+fn test_coalescing() {
+    let mut coalescer = RequestCoalescer::new();
+    let read = request(Some("rs1"), Some("r_temp"));
+    let write = request(Some("rs1"), Some("w_temp"));
+    let other = request(Some("rs2"), Some("r_temp"));
+    assert_eq!(coalescer.enqueue(&read, 1), Coalesced::Send);
+    assert_eq!(coalescer.enqueue(&write, 2), Coalesced::Send);
+    assert_eq!(coalescer.enqueue(&read, 3), Coalesced::Wait);
+    assert_eq!(coalescer.enqueue(&other, 4), Coalesced::Send);
+    assert_eq!(coalescer.enqueue(&read, 5), Coalesced::Wait);
+    assert_eq!(coalescer.len(), 3);
+
+    assert_eq!(coalescer.complete(&read), vec![1, 3, 5]);
+    assert_eq!(coalescer.complete(&read), Vec::<i32>::new());
+    // A new request for the same audience and scope is sent again.
+    assert_eq!(coalescer.enqueue(&read, 6), Coalesced::Send);
+    assert_eq!(coalescer.complete(&other), vec![4]);
+    assert_eq!(coalescer.complete(&write), vec![2]);
+    assert_eq!(coalescer.complete(&read), vec![6]);
+    assert!(coalescer.is_empty());
+}
+
+#[test]
+fn test_coalescing_ignores_nonces() {
+    let mut coalescer = RequestCoalescer::new();
+    let read = request(Some("rs1"), Some("r_temp"));
+    let fresh = AccessTokenRequest {
+        client_nonce: Some(vec![0xDC]),
+        confirmation_nonce: Some(vec![0xAF]),
+        ..read.clone()
+    };
+    assert_eq!(coalescer.enqueue(&read, 1), Coalesced::Send);
+    assert_eq!(coalescer.enqueue(&fresh, 2), Coalesced::Wait);
+    assert_eq!(coalescer.complete(&fresh), vec![1, 2]);
+}
+
+#[test]
+fn test_coalescing_different_clients_and_keys() {
+    let mut coalescer = RequestCoalescer::new();
+    let read = request(Some("rs1"), Some("r_temp"));
+    let variants = [
+        AccessTokenRequest {
+            client_id: Some("otherclient".to_string()),
+            ..read.clone()
+        },
+        AccessTokenRequest {
+            req_cnf: Some(ProofOfPossessionKey::KeyId(vec![0x01])),
+            ..read.clone()
+        },
+        AccessTokenRequest {
+            req_cnf: Some(ProofOfPossessionKey::KeyId(vec![0x02])),
+            ..read.clone()
+        },
+        AccessTokenRequest {
+            ace_profile: Some(()),
+            ..read.clone()
+        },
+    ];
+    assert_eq!(coalescer.enqueue(&read, 0), Coalesced::Send);
+    for (index, variant) in variants.iter().enumerate() {
+        assert_eq!(coalescer.enqueue(variant, index + 1), Coalesced::Send);
+    }
+    assert_eq!(coalescer.len(), 5);
+    assert_eq!(coalescer.complete(&read), vec![0]);
+    for (index, variant) in variants.iter().enumerate() {
+        assert_eq!(coalescer.complete(variant), vec![index + 1]);
+    }
+}
+
+#[test]
+fn test_coalescing_without_parameters() {
+    let mut coalescer = RequestCoalescer::new();
+    let empty = request(None, None);
+    assert_eq!(coalescer.enqueue(&empty, "a"), Coalesced::Send);
+    assert_eq!(
+        coalescer.enqueue(&request(Some("rs1"), None), "b"),
+        Coalesced::Send
+    );
+    assert_eq!(coalescer.enqueue(&empty, "c"), Coalesced::Wait);
+    assert_eq!(coalescer.complete(&empty), vec!["a", "c"]);
+}
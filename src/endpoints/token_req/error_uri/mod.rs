@@ -0,0 +1,114 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+//! Contains the [`ErrorUriRegistry`], which attaches standardized
+//! [`uri`](ErrorResponse::uri) values to [`ErrorResponse`]s and parses them back.
+//!
+//! Each [`ErrorCode`] is mapped to a URI consisting of a configurable base URL followed by
+//! a path segment for the code, e.g., `https://as.example.com/errors/invalid_client`.
+//! Unregistered codes ([`ErrorCode::Other`]) use their integer value as the path segment.
+//! This way, operators can link each error to a page in their troubleshooting documentation.
+//!
+//! # Example
+//! ```
+//! # use std::error::Error;
+//! # use dcaf::{ErrorCode, ErrorResponse};
+//! use dcaf::endpoints::token_req::error_uri::ErrorUriRegistry;
+//!
+//! # #[cfg(feature = "std")] {
+//! let registry = ErrorUriRegistry::new("https://as.example.com/errors");
+//! let response = registry.attach(ErrorResponse::builder().error(ErrorCode::InvalidScope).build()?);
+//! assert_eq!(response.uri.as_deref(), Some("https://as.example.com/errors/invalid_scope"));
+//! assert_eq!(registry.parse(response.uri.as_deref().unwrap()), Some(ErrorCode::InvalidScope));
+//! # }
+//! # Ok::<(), Box<dyn Error>>(())
+//! ```
+
+use crate::endpoints::token_req::{ErrorCode, ErrorResponse};
+
+#[cfg(not(feature = "std"))]
+use {alloc::format, alloc::string::String};
+
+#[cfg(test)]
+mod tests;
+
+/// Maps [`ErrorCode`]s to error URIs below a common base URL, and back.
+///
+/// For details and an example, see the [module-level documentation](self).
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct ErrorUriRegistry {
+    /// The base URL below which the error URIs are located, without a trailing slash.
+    base: String,
+}
+
+impl ErrorUriRegistry {
+    /// Creates a new [`ErrorUriRegistry`] whose error URIs are located below the given `base` URL.
+    ///
+    /// A trailing slash in `base` is ignored.
+    #[must_use]
+    pub fn new<T>(base: T) -> ErrorUriRegistry
+    where
+        T: Into<String>,
+    {
+        let mut base = base.into();
+        if base.ends_with('/') {
+            base.pop();
+        }
+        ErrorUriRegistry { base }
+    }
+
+    /// Returns the error URI for the given `code`.
+    #[must_use]
+    pub fn uri_for(&self, code: ErrorCode) -> String {
+        let segment = match code {
+            ErrorCode::InvalidRequest => "invalid_request",
+            ErrorCode::InvalidClient => "invalid_client",
+            ErrorCode::InvalidGrant => "invalid_grant",
+            ErrorCode::UnauthorizedClient => "unauthorized_client",
+            ErrorCode::UnsupportedGrantType => "unsupported_grant_type",
+            ErrorCode::InvalidScope => "invalid_scope",
+            ErrorCode::UnsupportedPopKey => "unsupported_pop_key",
+            ErrorCode::IncompatibleAceProfiles => "incompatible_ace_profiles",
+            ErrorCode::Other(x) => return format!("{}/{x}", self.base),
+        };
+        format!("{}/{segment}", self.base)
+    }
+
+    /// Sets the [`uri`](ErrorResponse::uri) of the given `response` to the error URI of its
+    /// error code, unless it already has one.
+    #[must_use]
+    pub fn attach(&self, mut response: ErrorResponse) -> ErrorResponse {
+        if response.uri.is_none() {
+            response.uri = Some(self.uri_for(response.error));
+        }
+        response
+    }
+
+    /// Parses the given error `uri` back into the [`ErrorCode`] it refers to.
+    ///
+    /// Returns `None` if the `uri` is not located below the base URL of this registry or
+    /// doesn't refer to any error code.
+    #[must_use]
+    pub fn parse(&self, uri: &str) -> Option<ErrorCode> {
+        let segment = uri.strip_prefix(self.base.as_str())?.strip_prefix('/')?;
+        match segment {
+            "invalid_request" => Some(ErrorCode::InvalidRequest),
+            "invalid_client" => Some(ErrorCode::InvalidClient),
+            "invalid_grant" => Some(ErrorCode::InvalidGrant),
+            "unauthorized_client" => Some(ErrorCode::UnauthorizedClient),
+            "unsupported_grant_type" => Some(ErrorCode::UnsupportedGrantType),
+            "invalid_scope" => Some(ErrorCode::InvalidScope),
+            "unsupported_pop_key" => Some(ErrorCode::UnsupportedPopKey),
+            "incompatible_ace_profiles" => Some(ErrorCode::IncompatibleAceProfiles),
+            x => x.parse::<i32>().ok().map(ErrorCode::Other),
+        }
+    }
+}
@@ -0,0 +1,76 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+#[cfg(not(feature = "std"))]
+use alloc::string::ToString;
+
+use super::*;
+
+#[test]
+fn test_uri_roundtrip() {
+    let registry = ErrorUriRegistry::new("coaps://as.example.com/errors/");
+    for code in [
+        ErrorCode::InvalidRequest,
+        ErrorCode::InvalidClient,
+        ErrorCode::InvalidGrant,
+        ErrorCode::UnauthorizedClient,
+        ErrorCode::UnsupportedGrantType,
+        ErrorCode::InvalidScope,
+        ErrorCode::UnsupportedPopKey,
+        ErrorCode::IncompatibleAceProfiles,
+        ErrorCode::Other(-99999),
+    ] {
+        let uri = registry.uri_for(code);
+        assert!(uri.starts_with("coaps://as.example.com/errors/"));
+        assert_eq!(registry.parse(&uri), Some(code));
+    }
+    assert_eq!(
+        registry.uri_for(ErrorCode::Other(42)),
+        "coaps://as.example.com/errors/42"
+    );
+}
+
+#[test]
+fn test_parse_invalid() {
+    let registry = ErrorUriRegistry::new("coaps://as.example.com/errors");
+    assert_eq!(
+        registry.parse("coaps://other.example.com/errors/invalid_scope"),
+        None
+    );
+    assert_eq!(
+        registry.parse("coaps://as.example.com/errorsinvalid_scope"),
+        None
+    );
+    assert_eq!(
+        registry.parse("coaps://as.example.com/errors/unknown"),
+        None
+    );
+}
+
+#[test]
+fn test_attach() -> Result<(), String> {
+    let registry = ErrorUriRegistry::new("coaps://as.example.com/errors");
+    let response = ErrorResponse::builder()
+        .error(ErrorCode::InvalidClient)
+        .build()
+        .map_err(|x| x.to_string())?;
+    assert_eq!(
+        registry.attach(response).uri,
+        Some("coaps://as.example.com/errors/invalid_client".to_string())
+    );
+    let custom = ErrorResponse::builder()
+        .error(ErrorCode::InvalidClient)
+        .uri("https://example.com/custom")
+        .build()
+        .map_err(|x| x.to_string())?;
+    assert_eq!(registry.attach(custom.clone()), custom);
+    Ok(())
+}
@@ -0,0 +1,252 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+//! Contains the [`FixtureGenerator`], which creates randomized but valid token endpoint
+//! messages, e.g., for differential testing against other ACE-OAuth implementations or for
+//! populating demo Authorization Servers.
+//!
+//! The generator is seeded, so the same seed always yields the same sequence of messages,
+//! which makes it possible to reproduce (and store snapshots of) any generated fixture.
+//! Unlike arbitrary data, the generated messages are consistent with each other:
+//! A response generated for a request passes that request's
+//! [`validate_response`](AccessTokenRequest::validate_response) check when the client supports
+//! all [`FIXTURE_PROFILES`].
+//!
+//! Note that the generator is **not** cryptographically secure and must hence not be used to
+//! create actual tokens, keys, or nonces. For this reason, this module is only available
+//! with the `test-fixtures` feature, which should only be enabled for tests (e.g., as part of
+//! the `dev-dependencies`).
+//!
+//! # Example
+//! ```
+//! use dcaf::endpoints::token_req::fixtures::{FixtureGenerator, FIXTURE_PROFILES};
+//!
+//! let mut generator = FixtureGenerator::new(42);
+//! let request = generator.access_token_request();
+//! let response = generator.access_token_response(&request);
+//! # #[cfg(feature = "client")]
+//! assert!(request.validate_response(&response, &FIXTURE_PROFILES).is_ok());
+//!
+//! // The same seed yields the same fixtures.
+//! assert_eq!(FixtureGenerator::new(42).access_token_request(), request);
+//! ```
+
+use enumflags2::BitFlags;
+
+use crate::common::cbor_values::{ByteString, ProofOfPossessionKey};
+use crate::common::scope::{AifEncodedScopeElement, AifRestMethod, TextEncodedScope};
+use crate::endpoints::token_req::{
+    AccessTokenRequest, AccessTokenResponse, AceProfile, ErrorCode, ErrorResponse, TokenType,
+};
+use crate::{AifEncodedScope, Scope};
+
+#[cfg(not(feature = "std"))]
+use {alloc::format, alloc::string::String, alloc::vec::Vec};
+
+#[cfg(test)]
+mod tests;
+
+/// The profiles used in generated fixtures.
+pub const FIXTURE_PROFILES: [AceProfile; 2] = [AceProfile::CoapDtls, AceProfile::CoapOscore];
+
+/// Names of the resources used in generated scopes.
+const RESOURCES: [&str; 6] = ["temp", "hum", "led", "lock", "fan", "door"];
+
+/// Error codes used in generated error responses.
+const ERROR_CODES: [ErrorCode; 8] = [
+    ErrorCode::InvalidRequest,
+    ErrorCode::InvalidClient,
+    ErrorCode::InvalidGrant,
+    ErrorCode::UnauthorizedClient,
+    ErrorCode::UnsupportedGrantType,
+    ErrorCode::InvalidScope,
+    ErrorCode::UnsupportedPopKey,
+    ErrorCode::IncompatibleAceProfiles,
+];
+
+/// Generates randomized but valid token endpoint messages from a seed.
+///
+/// For an example, see the [module-level documentation](self).
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct FixtureGenerator {
+    /// The current state of the pseudorandom number generator.
+    state: u64,
+}
+
+impl FixtureGenerator {
+    /// Creates a new [`FixtureGenerator`] using the given `seed`.
+    #[must_use]
+    pub fn new(seed: u64) -> FixtureGenerator {
+        FixtureGenerator { state: seed }
+    }
+
+    /// Returns the next pseudorandom number, using the SplitMix64 algorithm.
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a pseudorandom number below the given (non-zero) `bound`.
+    #[allow(clippy::cast_possible_truncation)]
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+
+    /// Returns `true` with a probability of one half.
+    fn chance(&mut self) -> bool {
+        self.next_u64() & 1 == 1
+    }
+
+    /// Returns `len` pseudorandom bytes.
+    #[allow(clippy::cast_possible_truncation)]
+    fn bytes(&mut self, len: usize) -> ByteString {
+        (0..len).map(|_| self.next_u64() as u8).collect()
+    }
+
+    /// Returns a non-empty, pseudorandom subset of the given `items`, keeping their order.
+    fn subset<T: Clone>(&mut self, items: &[T]) -> Vec<T> {
+        let mut subset: Vec<T> = items.iter().filter(|_| self.chance()).cloned().collect();
+        if subset.is_empty() {
+            subset.extend(items.get(self.below(items.len().max(1))).cloned());
+        }
+        subset
+    }
+
+    /// Returns a pseudorandom text-encoded or AIF-encoded scope.
+    fn scope(&mut self) -> Scope {
+        let resources = self.subset(&RESOURCES);
+        if self.chance() {
+            let elements: Vec<String> = resources
+                .iter()
+                .map(|x| format!("{}{x}", if self.chance() { "r" } else { "w" }))
+                .collect();
+            Scope::from(
+                TextEncodedScope::try_from(elements.join(" ").as_str())
+                    .expect("generated text scopes are valid"),
+            )
+        } else {
+            let elements = resources
+                .iter()
+                .map(|x| {
+                    let permissions =
+                        BitFlags::<AifRestMethod>::from_bits_truncate(self.next_u64())
+                            | AifRestMethod::Get;
+                    AifEncodedScopeElement::new(format!("/s/{x}"), permissions)
+                })
+                .collect();
+            Scope::from(AifEncodedScope::new(elements))
+        }
+    }
+
+    /// Returns a pseudorandom scope within the given `requested` scope.
+    fn granted_scope(&mut self, requested: &Scope) -> Scope {
+        match requested {
+            Scope::TextEncoded(scope) => {
+                let elements: Vec<&str> = scope.elements().collect();
+                Scope::from(
+                    TextEncodedScope::try_from(self.subset(&elements).join(" ").as_str())
+                        .expect("subsets of text scopes are valid"),
+                )
+            }
+            Scope::AifEncoded(scope) => {
+                Scope::from(AifEncodedScope::new(self.subset(scope.elements())))
+            }
+            other => other.clone(),
+        }
+    }
+
+    /// Returns a pseudorandom access token request, which may or may not contain each of the
+    /// optional parameters.
+    ///
+    /// # Panics
+    /// If a generated message is invalid, which would imply a programming mistake on account
+    /// of `dcaf-rs`, not its users.
+    pub fn access_token_request(&mut self) -> AccessTokenRequest {
+        let mut request = AccessTokenRequest::builder();
+        request.client_id(format!("client{}", self.below(100)));
+        if self.chance() {
+            request.audience(format!("rs{}", self.below(100)));
+        }
+        if self.chance() {
+            request.scope(self.scope());
+        }
+        if self.chance() {
+            request.client_nonce(self.bytes(8));
+        }
+        if self.chance() {
+            request.req_cnf(ProofOfPossessionKey::KeyId(self.bytes(4)));
+        }
+        if self.chance() {
+            request.ace_profile();
+        }
+        request.build().expect("generated requests are valid")
+    }
+
+    /// Returns a pseudorandom access token response to the given `request`, which passes its
+    /// [`validate_response`](AccessTokenRequest::validate_response) check for
+    /// [`FIXTURE_PROFILES`].
+    ///
+    /// # Panics
+    /// If a generated message is invalid, which would imply a programming mistake on account
+    /// of `dcaf-rs`, not its users.
+    pub fn access_token_response(&mut self, request: &AccessTokenRequest) -> AccessTokenResponse {
+        let mut response = AccessTokenResponse::builder();
+        let token_length = 32 + self.below(32);
+        response.access_token(self.bytes(token_length));
+        if self.chance() {
+            response.expires_in(u32::try_from(60 + self.below(86_400)).unwrap_or(3600));
+        }
+        if let Some(scope) = &request.scope {
+            if self.chance() {
+                response.scope(self.granted_scope(scope));
+            }
+        }
+        if request.ace_profile.is_some() || self.chance() {
+            response.ace_profile(FIXTURE_PROFILES[self.below(FIXTURE_PROFILES.len())]);
+        }
+        if request.req_cnf.is_none() {
+            // Without a requested key, the AS has to provide one.
+            response.cnf(ProofOfPossessionKey::KeyId(self.bytes(4)));
+        }
+        if self.chance() {
+            response.rs_cnf(ProofOfPossessionKey::KeyId(self.bytes(4)));
+        }
+        if self.chance() {
+            response.token_type(TokenType::ProofOfPossession);
+        }
+        response.build().expect("generated responses are valid")
+    }
+
+    /// Returns a pseudorandom error response.
+    ///
+    /// # Panics
+    /// If a generated message is invalid, which would imply a programming mistake on account
+    /// of `dcaf-rs`, not its users.
+    pub fn error_response(&mut self) -> ErrorResponse {
+        let mut response = ErrorResponse::builder();
+        response.error(ERROR_CODES[self.below(ERROR_CODES.len())]);
+        if self.chance() {
+            response.description(format!("Fixture error {}", self.below(1000)));
+        }
+        if self.chance() {
+            response.uri(format!(
+                "https://as.example.com/errors/{}",
+                self.below(1000)
+            ));
+        }
+        response
+            .build()
+            .expect("generated error responses are valid")
+    }
+}
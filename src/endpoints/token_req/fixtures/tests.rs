@@ -0,0 +1,71 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+use core::fmt::Debug;
+
+use crate::ToCborMap;
+
+#[cfg(not(feature = "std"))]
+use {alloc::string::String, alloc::string::ToString, alloc::vec::Vec};
+
+use super::*;
+
+#[test]
+fn test_fixtures_deterministic() {
+    let mut first = FixtureGenerator::new(7);
+    let mut second = FixtureGenerator::new(7);
+    for _ in 0..10 {
+        let request = first.access_token_request();
+        assert_eq!(request, second.access_token_request());
+        assert_eq!(
+            first.access_token_response(&request),
+            second.access_token_response(&request)
+        );
+        assert_eq!(first.error_response(), second.error_response());
+    }
+    assert_ne!(
+        FixtureGenerator::new(7).access_token_request(),
+        FixtureGenerator::new(8).access_token_request()
+    );
+}
+
+/// Asserts that the given `value` survives a round trip through its CBOR representation.
+fn assert_roundtrip<T>(value: &T) -> Result<(), String>
+where
+    T: ToCborMap + Clone + Debug + PartialEq,
+{
+    let mut serialized = Vec::new();
+    value
+        .clone()
+        .serialize_into(&mut serialized)
+        .map_err(|x| x.to_string())?;
+    let decoded = T::deserialize_from(serialized.as_slice()).map_err(|x| x.to_string())?;
+    assert_eq!(&decoded, value);
+    Ok(())
+}
+
+#[test]
+fn test_fixtures_valid() -> Result<(), String> {
+    let mut generator = FixtureGenerator::new(0xDCAF);
+    for _ in 0..100 {
+        let request = generator.access_token_request();
+        let response = generator.access_token_response(&request);
+        #[cfg(feature = "client")]
+        assert_eq!(
+            request.validate_response(&response, &FIXTURE_PROFILES),
+            Ok(())
+        );
+        assert_roundtrip(&request)?;
+        assert_roundtrip(&response)?;
+        assert_roundtrip(&generator.error_response())?;
+    }
+    Ok(())
+}
@@ -0,0 +1,603 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+//! Contains grant-type-specific variants of the [`AccessTokenRequest`], which only expose
+//! the fields valid for their respective grant type.
+//!
+//! Each of these types can be converted into an [`AccessTokenRequest`] (e.g., for serialization)
+//! using [`From`], and can be extracted from an [`AccessTokenRequest`] using [`TryFrom`],
+//! which fails with an [`InvalidGrantRequestError`] if the request uses a different grant type
+//! or contains fields which aren't valid for the grant type.
+//!
+//! # Example
+//! ```
+//! # use std::error::Error;
+//! # use dcaf::{AccessTokenRequest, GrantType};
+//! use dcaf::endpoints::token_req::grant::ClientCredentialsRequest;
+//!
+//! # #[cfg(feature = "std")] {
+//! let request = ClientCredentialsRequest::builder()
+//!     .client_id("myclient")
+//!     .audience("tempSensor4711")
+//!     .build()?;
+//! let generic = AccessTokenRequest::from(request.clone());
+//! assert_eq!(generic.grant_type, Some(GrantType::ClientCredentials));
+//! assert_eq!(ClientCredentialsRequest::try_from(generic)?, request);
+//! # }
+//! # Ok::<(), Box<dyn Error>>(())
+//! ```
+//!
+//! # Token Exchange
+//! A [`TokenExchangeRequest`] exchanges an access token the client already holds for a new one,
+//! usually for a different audience, as described in
+//! [RFC 8693](https://www.rfc-editor.org/rfc/rfc8693). This allows, e.g., a gateway to act on
+//! behalf of the sensors behind it. The Authorization Server can decide on such requests using
+//! [`IssuancePolicy::evaluate_exchange`](crate::token::policy::IssuancePolicy::evaluate_exchange)
+//! (requires the `as` feature), which makes sure the new token doesn't grant more than the
+//! exchanged one.
+
+use crate::common::cbor_values::{ByteString, ProofOfPossessionKey};
+use crate::endpoints::token_req::{AccessTokenRequest, GrantType};
+use crate::error::InvalidGrantRequestError;
+use crate::Scope;
+
+#[cfg(not(feature = "std"))]
+use alloc::string::{String, ToString};
+
+#[cfg(test)]
+mod tests;
+
+/// Request for an access token using the [`ClientCredentials`](GrantType::ClientCredentials)
+/// grant type, as described in
+/// [section 4.4.2 of RFC 6749](https://www.rfc-editor.org/rfc/rfc6749#section-4.4.2).
+///
+/// This is the grant type used by default in ACE-OAuth.
+/// Use the [`ClientCredentialsRequestBuilder`] (which you can access using the
+/// [`ClientCredentialsRequest::builder()`] method) to create an instance of this struct.
+///
+/// For an example, see the [module-level documentation](self).
+#[derive(Debug, Default, PartialEq, Clone, Builder)]
+#[builder(
+    no_std,
+    setter(into, strip_option),
+    derive(Debug, PartialEq),
+    build_fn(validate = "Self::validate")
+)]
+pub struct ClientCredentialsRequest {
+    /// The client identifier, see [`AccessTokenRequest::client_id`].
+    #[builder(default)]
+    pub client_id: Option<String>,
+
+    /// A signed assertion authenticating the client,
+    /// see [`AccessTokenRequest::client_assertion`].
+    #[builder(default)]
+    pub client_assertion: Option<ByteString>,
+
+    /// The logical name of the target service, see [`AccessTokenRequest::audience`].
+    #[builder(default)]
+    pub audience: Option<String>,
+
+    /// Scope of the access request, see [`AccessTokenRequest::scope`].
+    #[builder(default)]
+    pub scope: Option<Scope>,
+
+    /// Client nonce to ensure the token is still fresh, see [`AccessTokenRequest::client_nonce`].
+    #[builder(default)]
+    pub client_nonce: Option<ByteString>,
+
+    /// Nonce chosen by the client for the key confirmation of the response,
+    /// see [`AccessTokenRequest::confirmation_nonce`].
+    #[builder(default)]
+    pub confirmation_nonce: Option<ByteString>,
+
+    /// Included in the request if the AS shall include the `ace_profile` parameter in its
+    /// response, see [`AccessTokenRequest::ace_profile`].
+    #[builder(setter(custom, strip_option), default = "None")]
+    pub ace_profile: Option<()>,
+
+    /// The key the client would like to bind to the access token,
+    /// see [`AccessTokenRequest::req_cnf`].
+    #[builder(default)]
+    pub req_cnf: Option<ProofOfPossessionKey>,
+}
+
+/// Request for an access token using the [`AuthorizationCode`](GrantType::AuthorizationCode)
+/// grant type, as described in
+/// [section 4.1.3 of RFC 6749](https://www.rfc-editor.org/rfc/rfc6749#section-4.1.3).
+///
+/// Use the [`AuthorizationCodeRequestBuilder`] (which you can access using the
+/// [`AuthorizationCodeRequest::builder()`] method) to create an instance of this struct.
+/// Note that the [`code`](AuthorizationCodeRequest::code) is required.
+#[derive(Debug, PartialEq, Clone, Builder)]
+#[builder(
+    no_std,
+    setter(into, strip_option),
+    derive(Debug, PartialEq),
+    build_fn(validate = "Self::validate")
+)]
+pub struct AuthorizationCodeRequest {
+    /// The authorization code received from the Authorization Server,
+    /// see [`AccessTokenRequest::code`].
+    ///
+    /// Must be included.
+    pub code: ByteString,
+
+    /// The redirection URI, which must be included if it was included in the authorization
+    /// request, see [`AccessTokenRequest::redirect_uri`].
+    #[builder(default)]
+    pub redirect_uri: Option<String>,
+
+    /// The client identifier, see [`AccessTokenRequest::client_id`].
+    #[builder(default)]
+    pub client_id: Option<String>,
+
+    /// A signed assertion authenticating the client,
+    /// see [`AccessTokenRequest::client_assertion`].
+    #[builder(default)]
+    pub client_assertion: Option<ByteString>,
+
+    /// The logical name of the target service, see [`AccessTokenRequest::audience`].
+    #[builder(default)]
+    pub audience: Option<String>,
+
+    /// Client nonce to ensure the token is still fresh, see [`AccessTokenRequest::client_nonce`].
+    #[builder(default)]
+    pub client_nonce: Option<ByteString>,
+
+    /// Nonce chosen by the client for the key confirmation of the response,
+    /// see [`AccessTokenRequest::confirmation_nonce`].
+    #[builder(default)]
+    pub confirmation_nonce: Option<ByteString>,
+
+    /// Included in the request if the AS shall include the `ace_profile` parameter in its
+    /// response, see [`AccessTokenRequest::ace_profile`].
+    #[builder(setter(custom, strip_option), default = "None")]
+    pub ace_profile: Option<()>,
+
+    /// The key the client would like to bind to the access token,
+    /// see [`AccessTokenRequest::req_cnf`].
+    #[builder(default)]
+    pub req_cnf: Option<ProofOfPossessionKey>,
+}
+
+/// Request for exchanging an access token for a new one using the
+/// [`TokenExchange`](GrantType::TokenExchange) grant type, as described in
+/// [section 2.1 of RFC 8693](https://www.rfc-editor.org/rfc/rfc8693#section-2.1).
+///
+/// Use the [`TokenExchangeRequestBuilder`] (which you can access using the
+/// [`TokenExchangeRequest::builder()`] method) to create an instance of this struct.
+/// Note that the [`subject_token`](TokenExchangeRequest::subject_token) and its
+/// [type](TokenExchangeRequest::subject_token_type) are required, and that the
+/// [`actor_token_type`](TokenExchangeRequest::actor_token_type) must be set if and only if
+/// the [`actor_token`](TokenExchangeRequest::actor_token) is.
+///
+/// # Example
+/// ```
+/// # use dcaf::{AccessTokenRequest, GrantType};
+/// # use dcaf::constants::token_type_identifiers::ACCESS_TOKEN;
+/// use dcaf::endpoints::token_req::grant::TokenExchangeRequest;
+///
+/// # #[cfg(feature = "std")] {
+/// let request = TokenExchangeRequest::builder()
+///     .subject_token(vec![0xDC, 0xAF])
+///     .subject_token_type(ACCESS_TOKEN)
+///     .client_id("gateway")
+///     .audience("backend")
+///     .build()?;
+/// let generic = AccessTokenRequest::from(request.clone());
+/// assert_eq!(generic.grant_type, Some(GrantType::TokenExchange));
+/// assert_eq!(TokenExchangeRequest::try_from(generic)?, request);
+///
+/// // The type of the actor token must be given as well.
+/// assert!(TokenExchangeRequest::builder()
+///     .subject_token(vec![0xDC, 0xAF])
+///     .subject_token_type(ACCESS_TOKEN)
+///     .actor_token(vec![0x01])
+///     .build()
+///     .is_err());
+/// # }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, PartialEq, Clone, Builder)]
+#[builder(
+    no_std,
+    setter(into, strip_option),
+    derive(Debug, PartialEq),
+    build_fn(validate = "Self::validate")
+)]
+pub struct TokenExchangeRequest {
+    /// The token to exchange, see [`AccessTokenRequest::subject_token`].
+    ///
+    /// Must be included.
+    pub subject_token: ByteString,
+
+    /// The type of the token to exchange, see [`AccessTokenRequest::subject_token_type`].
+    ///
+    /// Must be included.
+    pub subject_token_type: String,
+
+    /// A token representing the acting party, see [`AccessTokenRequest::actor_token`].
+    #[builder(default)]
+    pub actor_token: Option<ByteString>,
+
+    /// The type of the [`actor_token`](TokenExchangeRequest::actor_token),
+    /// see [`AccessTokenRequest::actor_token_type`].
+    #[builder(default)]
+    pub actor_token_type: Option<String>,
+
+    /// The client identifier, see [`AccessTokenRequest::client_id`].
+    #[builder(default)]
+    pub client_id: Option<String>,
+
+    /// A signed assertion authenticating the client,
+    /// see [`AccessTokenRequest::client_assertion`].
+    #[builder(default)]
+    pub client_assertion: Option<ByteString>,
+
+    /// The logical name of the target service of the new token,
+    /// see [`AccessTokenRequest::audience`].
+    #[builder(default)]
+    pub audience: Option<String>,
+
+    /// Scope of the new token, see [`AccessTokenRequest::scope`].
+    #[builder(default)]
+    pub scope: Option<Scope>,
+
+    /// Client nonce to ensure the token is still fresh, see [`AccessTokenRequest::client_nonce`].
+    #[builder(default)]
+    pub client_nonce: Option<ByteString>,
+
+    /// Nonce chosen by the client for the key confirmation of the response,
+    /// see [`AccessTokenRequest::confirmation_nonce`].
+    #[builder(default)]
+    pub confirmation_nonce: Option<ByteString>,
+
+    /// Included in the request if the AS shall include the `ace_profile` parameter in its
+    /// response, see [`AccessTokenRequest::ace_profile`].
+    #[builder(setter(custom, strip_option), default = "None")]
+    pub ace_profile: Option<()>,
+
+    /// The key the client would like to bind to the new access token,
+    /// see [`AccessTokenRequest::req_cnf`].
+    #[builder(default)]
+    pub req_cnf: Option<ProofOfPossessionKey>,
+}
+
+#[allow(clippy::unused_self, clippy::unnecessary_wraps)]
+mod builder {
+    use crate::common::text::{TextKind, TextLimits};
+
+    use super::*;
+
+    /// Checks the textual fields shared by all grant types against the
+    /// [default limits](TextLimits::DEFAULT), like the builder of [`AccessTokenRequest`] does.
+    fn check_common_text(
+        client_id: Option<&str>,
+        audience: Option<&str>,
+        redirect_uri: Option<&str>,
+    ) -> Result<(), String> {
+        TextLimits::DEFAULT
+            .check_fields(&[
+                ("client_id", client_id, TextKind::ClientId),
+                ("audience", audience, TextKind::Identifier),
+                ("redirect_uri", redirect_uri, TextKind::Uri),
+            ])
+            .map_err(|e| e.to_string())
+    }
+
+    impl ClientCredentialsRequest {
+        /// Initializes and returns a new [`ClientCredentialsRequestBuilder`].
+        #[must_use]
+        pub fn builder() -> ClientCredentialsRequestBuilder {
+            ClientCredentialsRequestBuilder::default()
+        }
+    }
+
+    impl ClientCredentialsRequestBuilder {
+        pub(crate) fn validate(&self) -> Result<(), ClientCredentialsRequestBuilderError> {
+            check_common_text(
+                self.client_id.as_ref().and_then(Option::as_deref),
+                self.audience.as_ref().and_then(Option::as_deref),
+                None,
+            )
+            .map_err(ClientCredentialsRequestBuilderError::ValidationError)
+        }
+
+        /// Sets the [`ace_profile`](ClientCredentialsRequest::ace_profile) field to an empty value,
+        /// which indicates a request for the Authorization Server to respond with the
+        /// `ace_profile` field in the response.
+        pub fn ace_profile(&mut self) -> &mut Self {
+            self.ace_profile = Some(Some(()));
+            self
+        }
+    }
+
+    impl AuthorizationCodeRequest {
+        /// Initializes and returns a new [`AuthorizationCodeRequestBuilder`].
+        #[must_use]
+        pub fn builder() -> AuthorizationCodeRequestBuilder {
+            AuthorizationCodeRequestBuilder::default()
+        }
+    }
+
+    impl AuthorizationCodeRequestBuilder {
+        pub(crate) fn validate(&self) -> Result<(), AuthorizationCodeRequestBuilderError> {
+            if matches!(&self.code, Some(x) if x.is_empty()) {
+                return Err(AuthorizationCodeRequestBuilderError::ValidationError(
+                    "code must not be empty".to_string(),
+                ));
+            }
+            check_common_text(
+                self.client_id.as_ref().and_then(Option::as_deref),
+                self.audience.as_ref().and_then(Option::as_deref),
+                self.redirect_uri.as_ref().and_then(Option::as_deref),
+            )
+            .map_err(AuthorizationCodeRequestBuilderError::ValidationError)
+        }
+
+        /// Sets the [`ace_profile`](AuthorizationCodeRequest::ace_profile) field to an empty value,
+        /// which indicates a request for the Authorization Server to respond with the
+        /// `ace_profile` field in the response.
+        pub fn ace_profile(&mut self) -> &mut Self {
+            self.ace_profile = Some(Some(()));
+            self
+        }
+    }
+
+    impl TokenExchangeRequest {
+        /// Initializes and returns a new [`TokenExchangeRequestBuilder`].
+        #[must_use]
+        pub fn builder() -> TokenExchangeRequestBuilder {
+            TokenExchangeRequestBuilder::default()
+        }
+    }
+
+    impl TokenExchangeRequestBuilder {
+        pub(crate) fn validate(&self) -> Result<(), TokenExchangeRequestBuilderError> {
+            if matches!(&self.subject_token, Some(x) if x.is_empty()) {
+                return Err(TokenExchangeRequestBuilderError::ValidationError(
+                    "subject_token must not be empty".to_string(),
+                ));
+            }
+            let actor_token = self.actor_token.as_ref().map_or(false, Option::is_some);
+            let actor_token_type = self
+                .actor_token_type
+                .as_ref()
+                .map_or(false, Option::is_some);
+            if actor_token != actor_token_type {
+                return Err(TokenExchangeRequestBuilderError::ValidationError(
+                    "actor_token and actor_token_type must be given together".to_string(),
+                ));
+            }
+            check_common_text(
+                self.client_id.as_ref().and_then(Option::as_deref),
+                self.audience.as_ref().and_then(Option::as_deref),
+                None,
+            )
+            .and_then(|()| {
+                TextLimits::DEFAULT
+                    .check_fields(&[
+                        (
+                            "subject_token_type",
+                            self.subject_token_type.as_deref(),
+                            TextKind::Uri,
+                        ),
+                        (
+                            "actor_token_type",
+                            self.actor_token_type.as_ref().and_then(Option::as_deref),
+                            TextKind::Uri,
+                        ),
+                    ])
+                    .map_err(|e| e.to_string())
+            })
+            .map_err(TokenExchangeRequestBuilderError::ValidationError)
+        }
+
+        /// Sets the [`ace_profile`](TokenExchangeRequest::ace_profile) field to an empty value,
+        /// which indicates a request for the Authorization Server to respond with the
+        /// `ace_profile` field in the response.
+        pub fn ace_profile(&mut self) -> &mut Self {
+            self.ace_profile = Some(Some(()));
+            self
+        }
+    }
+}
+
+mod conversion {
+    use super::*;
+
+    /// Returns an error if the `grant_type` of the given `request` isn't `expected`.
+    /// A missing grant type is treated as [`GrantType::ClientCredentials`], which is the default.
+    fn check_grant_type(
+        request: &AccessTokenRequest,
+        expected: GrantType,
+    ) -> Result<(), InvalidGrantRequestError> {
+        let actual = request.grant_type.unwrap_or(GrantType::ClientCredentials);
+        if actual == expected {
+            Ok(())
+        } else {
+            Err(InvalidGrantRequestError::WrongGrantType { expected, actual })
+        }
+    }
+
+    /// Returns an error if the given `field` (named `name`) is set.
+    fn check_absent<T>(
+        field: Option<&T>,
+        name: &'static str,
+    ) -> Result<(), InvalidGrantRequestError> {
+        if field.is_some() {
+            Err(InvalidGrantRequestError::UnexpectedField(name))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Returns an error if any of the token exchange fields of the given `request` is set.
+    fn check_exchange_absent(request: &AccessTokenRequest) -> Result<(), InvalidGrantRequestError> {
+        check_absent(request.subject_token.as_ref(), "subject_token")?;
+        check_absent(request.subject_token_type.as_ref(), "subject_token_type")?;
+        check_absent(request.actor_token.as_ref(), "actor_token")?;
+        check_absent(request.actor_token_type.as_ref(), "actor_token_type")
+    }
+
+    impl From<ClientCredentialsRequest> for AccessTokenRequest {
+        fn from(request: ClientCredentialsRequest) -> Self {
+            AccessTokenRequest {
+                client_id: request.client_id,
+                client_assertion: request.client_assertion,
+                grant_type: Some(GrantType::ClientCredentials),
+                audience: request.audience,
+                redirect_uri: None,
+                code: None,
+                client_nonce: request.client_nonce,
+                confirmation_nonce: request.confirmation_nonce,
+                scope: request.scope,
+                ace_profile: request.ace_profile,
+                req_cnf: request.req_cnf,
+                issuer: None,
+                subject_token: None,
+                subject_token_type: None,
+                actor_token: None,
+                actor_token_type: None,
+            }
+        }
+    }
+
+    impl TryFrom<AccessTokenRequest> for ClientCredentialsRequest {
+        type Error = InvalidGrantRequestError;
+
+        fn try_from(request: AccessTokenRequest) -> Result<Self, Self::Error> {
+            check_grant_type(&request, GrantType::ClientCredentials)?;
+            check_absent(request.redirect_uri.as_ref(), "redirect_uri")?;
+            check_absent(request.code.as_ref(), "code")?;
+            check_absent(request.issuer.as_ref(), "issuer")?;
+            check_exchange_absent(&request)?;
+            Ok(ClientCredentialsRequest {
+                client_id: request.client_id,
+                client_assertion: request.client_assertion,
+                audience: request.audience,
+                scope: request.scope,
+                client_nonce: request.client_nonce,
+                confirmation_nonce: request.confirmation_nonce,
+                ace_profile: request.ace_profile,
+                req_cnf: request.req_cnf,
+            })
+        }
+    }
+
+    impl From<AuthorizationCodeRequest> for AccessTokenRequest {
+        fn from(request: AuthorizationCodeRequest) -> Self {
+            AccessTokenRequest {
+                client_id: request.client_id,
+                client_assertion: request.client_assertion,
+                grant_type: Some(GrantType::AuthorizationCode),
+                audience: request.audience,
+                redirect_uri: request.redirect_uri,
+                code: Some(request.code),
+                client_nonce: request.client_nonce,
+                confirmation_nonce: request.confirmation_nonce,
+                scope: None,
+                ace_profile: request.ace_profile,
+                req_cnf: request.req_cnf,
+                issuer: None,
+                subject_token: None,
+                subject_token_type: None,
+                actor_token: None,
+                actor_token_type: None,
+            }
+        }
+    }
+
+    impl TryFrom<AccessTokenRequest> for AuthorizationCodeRequest {
+        type Error = InvalidGrantRequestError;
+
+        fn try_from(request: AccessTokenRequest) -> Result<Self, Self::Error> {
+            check_grant_type(&request, GrantType::AuthorizationCode)?;
+            check_absent(request.scope.as_ref(), "scope")?;
+            check_absent(request.issuer.as_ref(), "issuer")?;
+            check_exchange_absent(&request)?;
+            Ok(AuthorizationCodeRequest {
+                code: request
+                    .code
+                    .ok_or(InvalidGrantRequestError::MissingField("code"))?,
+                redirect_uri: request.redirect_uri,
+                client_id: request.client_id,
+                client_assertion: request.client_assertion,
+                audience: request.audience,
+                client_nonce: request.client_nonce,
+                confirmation_nonce: request.confirmation_nonce,
+                ace_profile: request.ace_profile,
+                req_cnf: request.req_cnf,
+            })
+        }
+    }
+
+    impl From<TokenExchangeRequest> for AccessTokenRequest {
+        fn from(request: TokenExchangeRequest) -> Self {
+            AccessTokenRequest {
+                client_id: request.client_id,
+                client_assertion: request.client_assertion,
+                grant_type: Some(GrantType::TokenExchange),
+                audience: request.audience,
+                redirect_uri: None,
+                code: None,
+                client_nonce: request.client_nonce,
+                confirmation_nonce: request.confirmation_nonce,
+                scope: request.scope,
+                ace_profile: request.ace_profile,
+                req_cnf: request.req_cnf,
+                issuer: None,
+                subject_token: Some(request.subject_token),
+                subject_token_type: Some(request.subject_token_type),
+                actor_token: request.actor_token,
+                actor_token_type: request.actor_token_type,
+            }
+        }
+    }
+
+    impl TryFrom<AccessTokenRequest> for TokenExchangeRequest {
+        type Error = InvalidGrantRequestError;
+
+        fn try_from(request: AccessTokenRequest) -> Result<Self, Self::Error> {
+            check_grant_type(&request, GrantType::TokenExchange)?;
+            check_absent(request.redirect_uri.as_ref(), "redirect_uri")?;
+            check_absent(request.code.as_ref(), "code")?;
+            check_absent(request.issuer.as_ref(), "issuer")?;
+            match (&request.actor_token, &request.actor_token_type) {
+                (Some(_), None) => {
+                    return Err(InvalidGrantRequestError::MissingField("actor_token_type"))
+                }
+                (None, Some(_)) => {
+                    return Err(InvalidGrantRequestError::MissingField("actor_token"))
+                }
+                _ => {}
+            }
+            Ok(TokenExchangeRequest {
+                subject_token: request
+                    .subject_token
+                    .ok_or(InvalidGrantRequestError::MissingField("subject_token"))?,
+                subject_token_type: request
+                    .subject_token_type
+                    .ok_or(InvalidGrantRequestError::MissingField("subject_token_type"))?,
+                actor_token: request.actor_token,
+                actor_token_type: request.actor_token_type,
+                client_id: request.client_id,
+                client_assertion: request.client_assertion,
+                audience: request.audience,
+                scope: request.scope,
+                client_nonce: request.client_nonce,
+                confirmation_nonce: request.confirmation_nonce,
+                ace_profile: request.ace_profile,
+                req_cnf: request.req_cnf,
+            })
+        }
+    }
+}
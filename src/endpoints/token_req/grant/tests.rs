@@ -0,0 +1,177 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+#[cfg(not(feature = "std"))]
+use {alloc::string::ToString, alloc::vec};
+
+use crate::common::test_helper::expect_ser_de;
+use crate::TextEncodedScope;
+
+use super::*;
+
+#[test]
+fn test_client_credentials_roundtrip() -> Result<(), String> {
+    let request = ClientCredentialsRequest::builder()
+        .client_id("myclient")
+        .audience("tempSensor4711")
+        .scope(TextEncodedScope::try_from("read").map_err(|x| x.to_string())?)
+        .ace_profile()
+        .build()
+        .map_err(|x| x.to_string())?;
+    let generic = AccessTokenRequest::from(request.clone());
+    assert_eq!(generic.grant_type, Some(GrantType::ClientCredentials));
+    assert_eq!(ClientCredentialsRequest::try_from(generic), Ok(request));
+    Ok(())
+}
+
+#[test]
+fn test_client_credentials_default_grant() -> Result<(), String> {
+    // Requests without a grant type use client credentials by default.
+    let generic = AccessTokenRequest::builder()
+        .client_id("myclient")
+        .build()
+        .map_err(|x| x.to_string())?;
+    assert!(ClientCredentialsRequest::try_from(generic).is_ok());
+    Ok(())
+}
+
+#[test]
+fn test_client_credentials_invalid() -> Result<(), String> {
+    let wrong_grant = AccessTokenRequest::builder()
+        .grant_type(GrantType::Password)
+        .build()
+        .map_err(|x| x.to_string())?;
+    assert_eq!(
+        ClientCredentialsRequest::try_from(wrong_grant),
+        Err(InvalidGrantRequestError::WrongGrantType {
+            expected: GrantType::ClientCredentials,
+            actual: GrantType::Password
+        })
+    );
+    let with_code = AccessTokenRequest::builder()
+        .code(vec![0xDC, 0xAF])
+        .build()
+        .map_err(|x| x.to_string())?;
+    assert_eq!(
+        ClientCredentialsRequest::try_from(with_code),
+        Err(InvalidGrantRequestError::UnexpectedField("code"))
+    );
+    // Text fields are checked just like for generic requests.
+    assert!(ClientCredentialsRequest::builder()
+        .client_id("my\nclient")
+        .build()
+        .is_err());
+    Ok(())
+}
+
+#[test]
+fn test_authorization_code_roundtrip() -> Result<(), String> {
+    let request = AuthorizationCodeRequest::builder()
+        .code(vec![0xDC, 0xAF])
+        .redirect_uri("coap://client.example.com/cb")
+        .client_id("myclient")
+        .build()
+        .map_err(|x| x.to_string())?;
+    let generic = AccessTokenRequest::from(request.clone());
+    assert_eq!(generic.grant_type, Some(GrantType::AuthorizationCode));
+    assert_eq!(
+        AuthorizationCodeRequest::try_from(generic.clone()),
+        Ok(request)
+    );
+    expect_ser_de(
+        generic,
+        None,
+        "A41818686D79636C69656E74181B781C636F61703A2F2F636C69656E742E6578616D706C652E636F6D2F6362181D42DCAF182101",
+    )
+}
+
+#[test]
+fn test_authorization_code_invalid() -> Result<(), String> {
+    assert!(AuthorizationCodeRequest::builder().build().is_err());
+    assert!(AuthorizationCodeRequest::builder()
+        .code(vec![])
+        .build()
+        .is_err());
+    assert!(AuthorizationCodeRequest::builder()
+        .code(vec![0xDC])
+        .redirect_uri("not a uri")
+        .build()
+        .is_err());
+    let without_code = AccessTokenRequest::builder()
+        .grant_type(GrantType::AuthorizationCode)
+        .build()
+        .map_err(|x| x.to_string())?;
+    assert_eq!(
+        AuthorizationCodeRequest::try_from(without_code),
+        Err(InvalidGrantRequestError::MissingField("code"))
+    );
+    Ok(())
+}
+
+#[test]
+fn test_token_exchange_roundtrip() -> Result<(), String> {
+    let request = TokenExchangeRequest::builder()
+        .subject_token(vec![0xDC, 0xAF])
+        .subject_token_type(crate::constants::token_type_identifiers::ACCESS_TOKEN)
+        .audience("backend")
+        .build()
+        .map_err(|x| x.to_string())?;
+    let generic = AccessTokenRequest::from(request.clone());
+    assert_eq!(generic.grant_type, Some(GrantType::TokenExchange));
+    assert_eq!(TokenExchangeRequest::try_from(generic.clone()), Ok(request));
+    expect_ser_de(
+        generic,
+        None,
+        "A405676261636B656E6418213A000100003A0001000442DCAF3A00010005782D75726E3A696574663A706172616D733A6F617574683A746F6B656E2D747970653A6163636573735F746F6B656E",
+    )
+}
+
+#[test]
+fn test_token_exchange_invalid() -> Result<(), String> {
+    assert!(TokenExchangeRequest::builder()
+        .subject_token(vec![0xDC])
+        .build()
+        .is_err());
+    assert!(TokenExchangeRequest::builder()
+        .subject_token(vec![0xDC])
+        .subject_token_type("urn:example")
+        .actor_token_type("urn:example")
+        .build()
+        .is_err());
+    assert!(TokenExchangeRequest::builder()
+        .subject_token(vec![])
+        .subject_token_type("urn:example")
+        .build()
+        .is_err());
+    let without_type = AccessTokenRequest::builder()
+        .grant_type(GrantType::TokenExchange)
+        .subject_token(vec![0xDC])
+        .build()
+        .map_err(|x| x.to_string())?;
+    assert_eq!(
+        TokenExchangeRequest::try_from(without_type.clone()),
+        Err(InvalidGrantRequestError::MissingField("subject_token_type"))
+    );
+    let mut with_actor = without_type.clone();
+    with_actor.subject_token_type = Some("urn:example".to_string());
+    with_actor.actor_token = Some(vec![0x01]);
+    assert_eq!(
+        TokenExchangeRequest::try_from(with_actor),
+        Err(InvalidGrantRequestError::MissingField("actor_token_type"))
+    );
+    let mut client_credentials = without_type;
+    client_credentials.grant_type = None;
+    assert_eq!(
+        ClientCredentialsRequest::try_from(client_credentials),
+        Err(InvalidGrantRequestError::UnexpectedField("subject_token"))
+    );
+    Ok(())
+}
@@ -0,0 +1,254 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+//! Contains functions with which the Authorization Server proves its possession of a key the
+//! client trusts, placed in the [`key_confirmation`](AccessTokenResponse::key_confirmation)
+//! parameter of the access token response.
+//!
+//! When the transport between client and Authorization Server doesn't authenticate the
+//! Authorization Server well enough, this lets the client authenticate it at the application
+//! layer instead. The proof is either a `COSE_Mac0` structure (using a key shared between client
+//! and Authorization Server) or a `COSE_Sign1` structure (using the signing key of the
+//! Authorization Server), whose payload is the
+//! [`confirmation_nonce`](AccessTokenRequest::confirmation_nonce) chosen by the client and whose
+//! external AAD is the encoded access token response (leaving out the `key_confirmation` itself).
+//! It can hence neither be replayed for another request nor be combined with a modified response,
+//! e.g., one in which an attacker replaced the `cnf`, `rs_cnf`, `ace_profile`, or `expires_in`.
+//!
+//! Note that the [`client_nonce`](AccessTokenRequest::client_nonce) can't be used for this, as it
+//! is chosen by the Resource Server rather than the client. Also note that this is an extension
+//! specific to this crate, so both sides need to support it, and that both sides encode the
+//! response the way this crate does.
+//!
+//! # Example
+//! ```
+//! # use coset::Header;
+//! # use dcaf::{AccessTokenRequest, AccessTokenResponse, CoseCipherCommon, CoseMac0Cipher};
+//! # use dcaf::error::{CoseCipherError, KeyConfirmationError};
+//! use dcaf::endpoints::token_req::key_confirmation::{create_mac_confirmation, verify_mac_confirmation};
+//! # struct FakeMac {}
+//! # impl CoseCipherCommon for FakeMac {
+//! #     type Error = String;
+//! #     fn header(&self, unprotected_header: &mut Header, protected_header: &mut Header) -> Result<(), CoseCipherError<Self::Error>> {
+//! #         Ok(())
+//! #     }
+//! # }
+//! # impl CoseMac0Cipher for FakeMac {
+//! #     fn generate_tag(&mut self, target: &[u8]) -> Vec<u8> {
+//! #         target.to_vec()
+//! #     }
+//! #     fn verify_tag(&mut self, tag: &[u8], maced_data: &[u8]) -> Result<(), CoseCipherError<Self::Error>> {
+//! #         if tag == maced_data { Ok(()) } else { Err(CoseCipherError::VerificationFailure) }
+//! #     }
+//! # }
+//! # let mut shared_key = FakeMac {};
+//! # #[cfg(feature = "std")] {
+//! let request = AccessTokenRequest::builder()
+//!     .client_id("myclient")
+//!     .confirmation_nonce(vec![0xDC, 0xAF])
+//!     .build()
+//!     .expect("request is valid");
+//!
+//! // On the Authorization Server:
+//! let mut response = AccessTokenResponse::builder()
+//!     .access_token(vec![0x42; 32])
+//!     .expires_in(3600_u32)
+//!     .build()
+//!     .expect("response is valid");
+//! response.key_confirmation = Some(create_mac_confirmation(&request, &response, &mut shared_key)?);
+//!
+//! // On the client:
+//! verify_mac_confirmation(&request, &response, &mut shared_key)?;
+//! # }
+//! # Ok::<(), KeyConfirmationError<String>>(())
+//! ```
+
+use coset::{CborSerializable, CoseError, CoseMac0, CoseMac0Builder, CoseSign1, CoseSign1Builder};
+
+use crate::common::cbor_map::ToCborMap;
+use crate::common::cbor_values::ByteString;
+use crate::endpoints::token_req::{AccessTokenRequest, AccessTokenResponse};
+use crate::error::{AccessTokenError, KeyConfirmationError};
+use crate::token::{prepare_headers, CoseMac0Cipher, CoseSign1Cipher};
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+#[cfg(test)]
+mod tests;
+
+/// Returns the confirmation nonce of the given `request`.
+fn confirmation_nonce<T>(
+    request: &AccessTokenRequest,
+) -> Result<&ByteString, KeyConfirmationError<T>>
+where
+    T: core::fmt::Display,
+{
+    request
+        .confirmation_nonce
+        .as_ref()
+        .ok_or(KeyConfirmationError::MissingConfirmationNonce)
+}
+
+/// Returns the encoding of the given `response` without its key confirmation, which is used as
+/// the external AAD of the key confirmation.
+fn confirmed_response<T>(response: &AccessTokenResponse) -> Result<Vec<u8>, KeyConfirmationError<T>>
+where
+    T: core::fmt::Display,
+{
+    let mut unconfirmed = response.clone();
+    unconfirmed.key_confirmation = None;
+    let mut encoded = Vec::new();
+    unconfirmed
+        .serialize_into(&mut encoded)
+        .map_err(|_| AccessTokenError::from_cose_error(CoseError::EncodeFailed))?;
+    Ok(encoded)
+}
+
+/// Creates a key confirmation for the given `request` and the `response` to it,
+/// MAC tagged with a key shared with the client using `cipher`.
+///
+/// A key confirmation already contained in the `response` is ignored.
+///
+/// For an example, see the [module-level documentation](self).
+///
+/// # Errors
+/// - If the `request` contains no confirmation nonce
+///   ([`KeyConfirmationError::MissingConfirmationNonce`]).
+/// - If the `cipher` fails to set its headers or the structure can't be serialized
+///   ([`KeyConfirmationError::InvalidConfirmation`]).
+pub fn create_mac_confirmation<T>(
+    request: &AccessTokenRequest,
+    response: &AccessTokenResponse,
+    cipher: &mut T,
+) -> Result<ByteString, KeyConfirmationError<T::Error>>
+where
+    T: CoseMac0Cipher,
+{
+    let nonce = confirmation_nonce(request)?;
+    let aad = confirmed_response(response)?;
+    let (unprotected, protected) = prepare_headers(None, None, cipher)?;
+    CoseMac0Builder::new()
+        .unprotected(unprotected)
+        .protected(protected)
+        .payload(nonce.clone())
+        .create_tag(&aad, |x| cipher.generate_tag(x))
+        .build()
+        .to_vec()
+        .map_err(|e| AccessTokenError::from_cose_error(e).into())
+}
+
+/// Creates a key confirmation for the given `request` and the `response` to it,
+/// signed with the signing key of the Authorization Server using `cipher`.
+///
+/// A key confirmation already contained in the `response` is ignored.
+///
+/// # Errors
+/// - If the `request` contains no confirmation nonce
+///   ([`KeyConfirmationError::MissingConfirmationNonce`]).
+/// - If the `cipher` fails to set its headers or the structure can't be serialized
+///   ([`KeyConfirmationError::InvalidConfirmation`]).
+pub fn create_signature_confirmation<T>(
+    request: &AccessTokenRequest,
+    response: &AccessTokenResponse,
+    cipher: &mut T,
+) -> Result<ByteString, KeyConfirmationError<T::Error>>
+where
+    T: CoseSign1Cipher,
+{
+    let nonce = confirmation_nonce(request)?;
+    let aad = confirmed_response(response)?;
+    let (unprotected, protected) = prepare_headers(None, None, cipher)?;
+    CoseSign1Builder::new()
+        .unprotected(unprotected)
+        .protected(protected)
+        .payload(nonce.clone())
+        .create_signature(&aad, |x| cipher.generate_signature(x))
+        .build()
+        .to_vec()
+        .map_err(|e| AccessTokenError::from_cose_error(e).into())
+}
+
+/// Verifies the MAC tagged key confirmation of the given `response` to the given `request`,
+/// using `cipher` with the key shared with the Authorization Server.
+///
+/// For an example, see the [module-level documentation](self).
+///
+/// # Errors
+/// - If the `request` contains no confirmation nonce
+///   ([`KeyConfirmationError::MissingConfirmationNonce`]).
+/// - If the `response` contains no key confirmation
+///   ([`KeyConfirmationError::MissingConfirmation`]).
+/// - If the key confirmation has been computed over another confirmation nonce
+///   ([`KeyConfirmationError::NonceMismatch`]).
+/// - If the key confirmation isn't a valid [`CoseMac0`] structure or its tag is invalid,
+///   e.g., because the response has been modified ([`KeyConfirmationError::InvalidConfirmation`]).
+pub fn verify_mac_confirmation<T>(
+    request: &AccessTokenRequest,
+    response: &AccessTokenResponse,
+    cipher: &mut T,
+) -> Result<(), KeyConfirmationError<T::Error>>
+where
+    T: CoseMac0Cipher,
+{
+    let nonce = confirmation_nonce(request)?;
+    let confirmation = response
+        .key_confirmation
+        .as_ref()
+        .ok_or(KeyConfirmationError::MissingConfirmation)?;
+    let aad = confirmed_response(response)?;
+    let mac = CoseMac0::from_slice(confirmation).map_err(AccessTokenError::from_cose_error)?;
+    mac.verify_tag(&aad, |tag, maced_data| cipher.verify_tag(tag, maced_data))
+        .map_err(AccessTokenError::from_cose_cipher_error)?;
+    if mac.payload.as_ref() == Some(nonce) {
+        Ok(())
+    } else {
+        Err(KeyConfirmationError::NonceMismatch)
+    }
+}
+
+/// Verifies the signed key confirmation of the given `response` to the given `request`,
+/// using `cipher` with the public key of the Authorization Server.
+///
+/// # Errors
+/// - If the `request` contains no confirmation nonce
+///   ([`KeyConfirmationError::MissingConfirmationNonce`]).
+/// - If the `response` contains no key confirmation
+///   ([`KeyConfirmationError::MissingConfirmation`]).
+/// - If the key confirmation has been computed over another confirmation nonce
+///   ([`KeyConfirmationError::NonceMismatch`]).
+/// - If the key confirmation isn't a valid [`CoseSign1`] structure or its signature is invalid,
+///   e.g., because the response has been modified ([`KeyConfirmationError::InvalidConfirmation`]).
+pub fn verify_signature_confirmation<T>(
+    request: &AccessTokenRequest,
+    response: &AccessTokenResponse,
+    cipher: &mut T,
+) -> Result<(), KeyConfirmationError<T::Error>>
+where
+    T: CoseSign1Cipher,
+{
+    let nonce = confirmation_nonce(request)?;
+    let confirmation = response
+        .key_confirmation
+        .as_ref()
+        .ok_or(KeyConfirmationError::MissingConfirmation)?;
+    let aad = confirmed_response(response)?;
+    let sign = CoseSign1::from_slice(confirmation).map_err(AccessTokenError::from_cose_error)?;
+    sign.verify_signature(&aad, |signature, signed_data| {
+        cipher.verify_signature(signature, signed_data)
+    })
+    .map_err(AccessTokenError::from_cose_cipher_error)?;
+    if sign.payload.as_ref() == Some(nonce) {
+        Ok(())
+    } else {
+        Err(KeyConfirmationError::NonceMismatch)
+    }
+}
@@ -0,0 +1,146 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+#[cfg(not(feature = "std"))]
+use {alloc::string::String, alloc::vec, alloc::vec::Vec};
+
+use crate::common::cbor_values::ProofOfPossessionKey;
+use crate::common::test_helper::FakeCrypto;
+use crate::endpoints::token_req::AceProfile;
+
+use super::*;
+
+fn example_request(confirmation_nonce: Option<Vec<u8>>) -> AccessTokenRequest {
+    let mut request = AccessTokenRequest::builder();
+    request.client_id("myclient").client_nonce(vec![0x01]);
+    if let Some(nonce) = confirmation_nonce {
+        request.confirmation_nonce(nonce);
+    }
+    request.build().expect("request must be valid")
+}
+
+fn example_response() -> AccessTokenResponse {
+    AccessTokenResponse::builder()
+        .access_token(vec![0x42; 16])
+        .expires_in(3600_u32)
+        .ace_profile(AceProfile::CoapDtls)
+        .cnf(ProofOfPossessionKey::KeyId(vec![0xDC]))
+        .build()
+        .expect("response must be valid")
+}
+
+fn confirmed(key_confirmation: ByteString) -> AccessTokenResponse {
+    let mut response = example_response();
+    response.key_confirmation = Some(key_confirmation);
+    response
+}
+
+#[test]
+fn test_mac_confirmation() -> Result<(), KeyConfirmationError<String>> {
+    let request = example_request(Some(vec![0xDC, 0xAF]));
+    let confirmation = create_mac_confirmation(&request, &example_response(), &mut FakeCrypto {})?;
+    verify_mac_confirmation(&request, &confirmed(confirmation), &mut FakeCrypto {})
+}
+
+#[test]
+fn test_signature_confirmation() -> Result<(), KeyConfirmationError<String>> {
+    let request = example_request(Some(vec![0xDC, 0xAF]));
+    let confirmation =
+        create_signature_confirmation(&request, &example_response(), &mut FakeCrypto {})?;
+    verify_signature_confirmation(&request, &confirmed(confirmation), &mut FakeCrypto {})
+}
+
+#[test]
+fn test_confirmation_ignores_existing() -> Result<(), KeyConfirmationError<String>> {
+    let request = example_request(Some(vec![0xDC, 0xAF]));
+    let confirmation = create_mac_confirmation(&request, &example_response(), &mut FakeCrypto {})?;
+    assert_eq!(
+        create_mac_confirmation(&request, &confirmed(vec![0x00]), &mut FakeCrypto {})?,
+        confirmation
+    );
+    Ok(())
+}
+
+#[test]
+fn test_confirmation_missing_confirmation_nonce() {
+    // The client nonce is chosen by the Resource Server, so it mustn't be used instead.
+    let request = example_request(None);
+    assert!(matches!(
+        create_mac_confirmation(&request, &example_response(), &mut FakeCrypto {}),
+        Err(KeyConfirmationError::MissingConfirmationNonce)
+    ));
+    assert!(matches!(
+        verify_signature_confirmation(&request, &confirmed(vec![0x00]), &mut FakeCrypto {}),
+        Err(KeyConfirmationError::MissingConfirmationNonce)
+    ));
+}
+
+#[test]
+fn test_confirmation_missing() {
+    let request = example_request(Some(vec![0xDC, 0xAF]));
+    assert!(matches!(
+        verify_mac_confirmation(&request, &example_response(), &mut FakeCrypto {}),
+        Err(KeyConfirmationError::MissingConfirmation)
+    ));
+}
+
+#[test]
+fn test_confirmation_other_nonce() -> Result<(), KeyConfirmationError<String>> {
+    let confirmation = create_mac_confirmation(
+        &example_request(Some(vec![0xDC, 0xAF])),
+        &example_response(),
+        &mut FakeCrypto {},
+    )?;
+    assert!(matches!(
+        verify_mac_confirmation(
+            &example_request(Some(vec![0xCA, 0xFE])),
+            &confirmed(confirmation),
+            &mut FakeCrypto {}
+        ),
+        Err(KeyConfirmationError::NonceMismatch)
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_confirmation_modified_response() -> Result<(), KeyConfirmationError<String>> {
+    let request = example_request(Some(vec![0xDC, 0xAF]));
+    let confirmation =
+        create_signature_confirmation(&request, &example_response(), &mut FakeCrypto {})?;
+    let modifications: [fn(&mut AccessTokenResponse); 5] = [
+        |x| x.access_token = vec![0x43; 16],
+        |x| x.cnf = Some(ProofOfPossessionKey::KeyId(vec![0xBA, 0xD0])),
+        |x| x.rs_cnf = Some(ProofOfPossessionKey::KeyId(vec![0xBA, 0xD0])),
+        |x| x.ace_profile = Some(AceProfile::CoapOscore),
+        |x| x.expires_in = Some(86400),
+    ];
+    for modify in modifications {
+        let mut response = confirmed(confirmation.clone());
+        modify(&mut response);
+        assert!(matches!(
+            verify_signature_confirmation(&request, &response, &mut FakeCrypto {}),
+            Err(KeyConfirmationError::InvalidConfirmation(_))
+        ));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_confirmation_wrong_structure() -> Result<(), KeyConfirmationError<String>> {
+    let request = example_request(Some(vec![0xDC, 0xAF]));
+    let confirmation =
+        create_signature_confirmation(&request, &example_response(), &mut FakeCrypto {})?;
+    assert!(matches!(
+        verify_mac_confirmation(&request, &confirmed(confirmation), &mut FakeCrypto {}),
+        Err(KeyConfirmationError::InvalidConfirmation(_))
+    ));
+    Ok(())
+}
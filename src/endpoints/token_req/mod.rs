@@ -15,15 +15,44 @@
 //! The most important members of this module are [`AccessTokenRequest`], [`AccessTokenResponse`],
 //! and [`ErrorResponse`]. Look at their documentation for usage examples.
 //! Other members are mainly used as part of the aforementioned structures.
-
+//!
+//! If you only want to allow the parameters valid for a specific grant type, use the request
+//! types in the [`grant`] module instead, which can be converted to and from [`AccessTokenRequest`].
+//! Standardized error URIs for [`ErrorResponse`]s can be created using the [`error_uri`] module,
+//! and randomized but valid messages for testing can be generated using the `fixtures` module
+//! (which requires the `test-fixtures` feature).
+//! Authorization Servers can reject floods of requests before decoding them using the
+//! `admission` module (which requires the `as` feature).
+//! Clients can authenticate themselves using signed CWTs created and verified by the
+//! [`assertion`] module, and coalesce concurrent, equivalent requests using the [`coalescing`]
+//! module. Conversely, the Authorization Server can prove its
+//! possession of a key trusted by the client using the [`key_confirmation`] module.
+
+use coset::cwt::ClaimsSet;
 use coset::AsCborValue;
 
 use crate::common::cbor_values::{ByteString, ProofOfPossessionKey};
+use crate::common::scope::ScopeDiff;
+use crate::constants::coap_code;
+#[cfg(feature = "client")]
+use crate::error::InvalidTokenResponseError;
+use crate::error::NonceMismatchError;
+use crate::token::get_client_nonce;
 use crate::Scope;
 
 #[cfg(not(feature = "std"))]
 use {alloc::boxed::Box, alloc::string::String, alloc::vec::Vec};
 
+#[cfg(feature = "as")]
+pub mod admission;
+pub mod assertion;
+pub mod coalescing;
+pub mod error_uri;
+#[cfg(any(test, feature = "test-fixtures"))]
+pub mod fixtures;
+pub mod grant;
+pub mod key_confirmation;
+
 #[cfg(test)]
 mod tests;
 
@@ -92,6 +121,15 @@ pub enum GrantType {
     /// for details.
     RefreshToken,
 
+    /// Used for exchanging one security token (e.g., an access token for one audience) for
+    /// another one, e.g., so that a gateway can act on behalf of the client the original token
+    /// was issued to. The token to exchange is given in the
+    /// [`subject_token`](AccessTokenRequest::subject_token) of the request.
+    ///
+    /// See [RFC 8693](https://www.rfc-editor.org/rfc/rfc8693) and the
+    /// [`TokenExchangeRequest`](grant::TokenExchangeRequest) for details.
+    TokenExchange,
+
     /// Another authorization grant not listed here.
     ///
     /// See [section 8.5 of RFC 9200](https://www.rfc-editor.org/rfc/rfc9200#section-8.5)
@@ -151,6 +189,14 @@ pub struct AccessTokenRequest {
     #[builder(default)]
     pub client_id: Option<String>,
 
+    /// A CWT signed by the client which authenticates it to the Authorization Server,
+    /// analogous to the `client_assertion` parameter of
+    /// [RFC 7523](https://www.rfc-editor.org/rfc/rfc7523#section-2.2).
+    ///
+    /// See the [`assertion`] module for details on how it can be created and verified.
+    #[builder(default)]
+    pub client_assertion: Option<ByteString>,
+
     /// Grant type used for this request.
     ///
     /// Defaults to [`GrantType::ClientCredentials`].
@@ -167,10 +213,25 @@ pub struct AccessTokenRequest {
     #[builder(default)]
     pub redirect_uri: Option<String>,
 
+    /// The authorization code received from the Authorization Server, as described in
+    /// [section 4.1.3 of RFC 6749](https://www.rfc-editor.org/rfc/rfc6749#section-4.1.3).
+    ///
+    /// Only used with the [`AuthorizationCode`](GrantType::AuthorizationCode) grant type.
+    #[builder(default)]
+    pub code: Option<ByteString>,
+
     /// Client nonce to ensure the token is still fresh.
     #[builder(default)]
     pub client_nonce: Option<ByteString>,
 
+    /// A nonce chosen by the client, over which the Authorization Server computes the
+    /// [`key_confirmation`](AccessTokenResponse::key_confirmation) of its response.
+    /// Note that this is an extension specific to this crate.
+    ///
+    /// See the [`key_confirmation`] module for details.
+    #[builder(default)]
+    pub confirmation_nonce: Option<ByteString>,
+
     /// Scope of the access request as described by section 3.3 of
     /// [RFC 6749](https://www.rfc-editor.org/rfc/rfc6749).
     ///
@@ -180,6 +241,9 @@ pub struct AccessTokenRequest {
 
     /// Included in the request if the AS shall include the `ace_profile` parameter in its
     /// response.
+    ///
+    /// If this is set, the AS must include the selected profile in its response, which the
+    /// client can check using [`validate_response`](AccessTokenRequest::validate_response).
     #[builder(setter(custom, strip_option), default = "None")]
     pub ace_profile: Option<()>,
 
@@ -199,6 +263,30 @@ pub struct AccessTokenRequest {
     /// and [Table 6 of RFC 9200](https://www.rfc-editor.org/rfc/rfc9200#table-6).
     #[builder(default)]
     pub issuer: Option<String>,
+
+    /// The security token which shall be exchanged for a new one, as described in
+    /// [section 2.1 of RFC 8693](https://www.rfc-editor.org/rfc/rfc8693#section-2.1).
+    ///
+    /// Only used with the [`TokenExchange`](GrantType::TokenExchange) grant type.
+    #[builder(default)]
+    pub subject_token: Option<ByteString>,
+
+    /// The type of the [`subject_token`](AccessTokenRequest::subject_token), given as one of the
+    /// [`token_type_identifiers`](crate::constants::token_type_identifiers).
+    #[builder(default)]
+    pub subject_token_type: Option<String>,
+
+    /// A security token representing the party which will act on behalf of the subject
+    /// of the [`subject_token`](AccessTokenRequest::subject_token).
+    ///
+    /// Only used with the [`TokenExchange`](GrantType::TokenExchange) grant type.
+    #[builder(default)]
+    pub actor_token: Option<ByteString>,
+
+    /// The type of the [`actor_token`](AccessTokenRequest::actor_token), which must be present
+    /// if and only if the `actor_token` is.
+    #[builder(default)]
+    pub actor_token_type: Option<String>,
 }
 
 /// The type of the token issued as described in section 7.1 of
@@ -431,6 +519,15 @@ pub struct AccessTokenResponse {
     /// and [table 6 of RFC 9200](https://www.rfc-editor.org/rfc/rfc9200#table-6).
     #[builder(default)]
     pub issued_at: Option<coset::cwt::Timestamp>,
+
+    /// A proof that the Authorization Server possesses a key the client trusts, computed over
+    /// the confirmation nonce of the request and the rest of this response, which lets the client authenticate the Authorization
+    /// Server at the application layer.
+    /// Note that this is an extension specific to this crate.
+    ///
+    /// See the [`key_confirmation`] module for details.
+    #[builder(default)]
+    pub key_confirmation: Option<ByteString>,
 }
 
 /// Error code specifying what went wrong for a token request, as specified in
@@ -541,6 +638,7 @@ pub enum ErrorCode {
     derive(Debug, PartialEq),
     build_fn(validate = "Self::validate")
 )]
+#[non_exhaustive]
 pub struct ErrorResponse {
     /// Error code for this error.
     ///
@@ -558,6 +656,47 @@ pub struct ErrorResponse {
     /// provide the client developer with additional information about the error.
     #[builder(default)]
     pub uri: Option<String>,
+
+    /// The profiles the Authorization Server supports for the requested audience, in order of
+    /// preference, which lets the client retry its request with one of them
+    /// (see [`compatible_profile`](ErrorResponse::compatible_profile)).
+    ///
+    /// This is an extension parameter which may only be included if the [`error`](ErrorResponse::error)
+    /// is [`IncompatibleAceProfiles`](ErrorCode::IncompatibleAceProfiles), and is omitted if empty.
+    #[builder(default)]
+    pub ace_profiles_supported: Vec<AceProfile>,
+}
+
+impl ErrorResponse {
+    /// Returns the first of the given `client_profiles` (in the client's order of preference)
+    /// which the Authorization Server supports according to the
+    /// [`ace_profiles_supported`](ErrorResponse::ace_profiles_supported) parameter of this error
+    /// response, or `None` if there is no such profile.
+    ///
+    /// Clients can use this to retry a request rejected with
+    /// [`IncompatibleAceProfiles`](ErrorCode::IncompatibleAceProfiles) using a profile which
+    /// will be accepted.
+    ///
+    /// # Example
+    /// ```
+    /// # use dcaf::{AceProfile, ErrorCode, ErrorResponse};
+    /// # use dcaf::endpoints::token_req::ErrorResponseBuilderError;
+    /// let error = ErrorResponse::builder()
+    ///     .error(ErrorCode::IncompatibleAceProfiles)
+    ///     .ace_profiles_supported(vec![AceProfile::CoapOscore])
+    ///     .build()?;
+    /// let ours = [AceProfile::CoapDtls, AceProfile::CoapOscore];
+    /// assert_eq!(error.compatible_profile(&ours), Some(AceProfile::CoapOscore));
+    /// assert_eq!(error.compatible_profile(&[AceProfile::CoapDtls]), None);
+    /// # Ok::<(), ErrorResponseBuilderError>(())
+    /// ```
+    #[must_use]
+    pub fn compatible_profile(&self, client_profiles: &[AceProfile]) -> Option<AceProfile> {
+        client_profiles
+            .iter()
+            .find(|x| self.ace_profiles_supported.contains(x))
+            .copied()
+    }
 }
 
 impl AccessTokenRequest {
@@ -566,16 +705,368 @@ impl AccessTokenRequest {
     pub fn builder() -> AccessTokenRequestBuilder {
         AccessTokenRequestBuilder::default()
     }
+
+    /// Determines the identity of the client which sent this request, intended for use by the
+    /// Authorization Server.
+    ///
+    /// As described in [section 5.8.1 of RFC 9200](https://www.rfc-editor.org/rfc/rfc9200#section-5.8.1),
+    /// the `client_id` may be omitted if the client is already authenticated through the secure
+    /// transport (e.g., by its DTLS or OSCORE identity), which can be passed in as
+    /// `transport_identity`. If both are present, they must match.
+    ///
+    /// # Errors
+    /// - [`ErrorCode::InvalidClient`] if the `client_id` doesn't match the `transport_identity`.
+    /// - [`ErrorCode::InvalidRequest`] if neither a `client_id` nor a `transport_identity`
+    ///   is present.
+    ///
+    /// # Example
+    /// ```
+    /// # use dcaf::{AccessTokenRequest, ErrorCode};
+    /// # use dcaf::endpoints::token_req::AccessTokenRequestBuilderError;
+    /// let request = AccessTokenRequest::builder().audience("tempSensor4711").build()?;
+    /// assert_eq!(request.client_identity(Some("myclient")), Ok("myclient"));
+    /// assert_eq!(request.client_identity(None), Err(ErrorCode::InvalidRequest));
+    /// # Ok::<(), AccessTokenRequestBuilderError>(())
+    /// ```
+    pub fn client_identity<'a>(
+        &'a self,
+        transport_identity: Option<&'a str>,
+    ) -> Result<&'a str, ErrorCode> {
+        match (self.client_id.as_deref(), transport_identity) {
+            (Some(client_id), Some(identity)) if client_id != identity => {
+                Err(ErrorCode::InvalidClient)
+            }
+            (Some(identity), _) | (None, Some(identity)) => Ok(identity),
+            (None, None) => Err(ErrorCode::InvalidRequest),
+        }
+    }
+
+    /// Validates the [`redirect_uri`](AccessTokenRequest::redirect_uri) of this request against
+    /// the `registered` redirection URIs of the client, intended for use by the Authorization
+    /// Server.
+    ///
+    /// As required by [section 3.1.2 of RFC 6749](https://www.rfc-editor.org/rfc/rfc6749#section-3.1.2),
+    /// URIs are compared using simple string comparison, i.e., no normalization takes place.
+    /// If `allow_loopback_port` is set, the port of loopback IP redirection URIs
+    /// (i.e., those with the host `127.0.0.1` or `[::1]`) may differ from the registered one,
+    /// as described in [section 7.3 of RFC 8252](https://www.rfc-editor.org/rfc/rfc8252#section-7.3).
+    /// Such URIs must not contain userinfo, and their port must consist of one to five digits.
+    ///
+    /// If this request contains no `redirect_uri`, there is nothing to validate and `Ok` is
+    /// returned.
+    ///
+    /// # Errors
+    /// [`ErrorCode::InvalidRequest`] if the `redirect_uri` matches none of the `registered` ones.
+    ///
+    /// # Example
+    /// ```
+    /// # use dcaf::{AccessTokenRequest, ErrorCode};
+    /// # use dcaf::endpoints::token_req::AccessTokenRequestBuilderError;
+    /// let registered = vec!["http://127.0.0.1/callback".to_string()];
+    /// let request = AccessTokenRequest::builder()
+    ///     .redirect_uri("http://127.0.0.1:49152/callback")
+    ///     .build()?;
+    /// assert_eq!(request.validate_redirect_uri(&registered, true), Ok(()));
+    /// assert_eq!(
+    ///     request.validate_redirect_uri(&registered, false),
+    ///     Err(ErrorCode::InvalidRequest)
+    /// );
+    /// # Ok::<(), AccessTokenRequestBuilderError>(())
+    /// ```
+    pub fn validate_redirect_uri(
+        &self,
+        registered: &[String],
+        allow_loopback_port: bool,
+    ) -> Result<(), ErrorCode> {
+        let requested = match &self.redirect_uri {
+            Some(uri) => uri.as_str(),
+            None => return Ok(()),
+        };
+        let matches = |uri: &String| {
+            uri == requested
+                || (allow_loopback_port
+                    && split_loopback_port(uri).is_some()
+                    && split_loopback_port(uri) == split_loopback_port(requested))
+        };
+        if registered.iter().any(matches) {
+            Ok(())
+        } else {
+            Err(ErrorCode::InvalidRequest)
+        }
+    }
+
+    /// Verifies that the `cnonce` claim of the access token with the given `claims` echoes the
+    /// [`client_nonce`](AccessTokenRequest::client_nonce) of this request, intended for use by
+    /// the client once it has obtained the claims of the issued token (e.g., through
+    /// introspection or because the token is only signed).
+    ///
+    /// If this request contains no `client_nonce`, there is nothing to verify and `Ok` is returned.
+    ///
+    /// # Errors
+    /// If this request contains a `client_nonce`, but the `claims` contain no `cnonce` claim
+    /// or one with a different value.
+    ///
+    /// # Example
+    /// ```
+    /// # use ciborium::value::Value;
+    /// # use coset::cwt::ClaimsSetBuilder;
+    /// # use coset::iana::CwtClaimName;
+    /// # use dcaf::AccessTokenRequest;
+    /// # use dcaf::endpoints::token_req::AccessTokenRequestBuilderError;
+    /// let request = AccessTokenRequest::builder().client_nonce(vec![0xDC, 0xAF]).build()?;
+    /// let claims = ClaimsSetBuilder::new()
+    ///     .claim(CwtClaimName::CNonce, Value::Bytes(vec![0xDC, 0xAF]))
+    ///     .build();
+    /// assert!(request.verify_client_nonce(&claims).is_ok());
+    /// assert!(request.verify_client_nonce(&ClaimsSetBuilder::new().build()).is_err());
+    /// # Ok::<(), AccessTokenRequestBuilderError>(())
+    /// ```
+    pub fn verify_client_nonce(&self, claims: &ClaimsSet) -> Result<(), NonceMismatchError> {
+        let expected = match &self.client_nonce {
+            Some(nonce) => nonce,
+            None => return Ok(()),
+        };
+        let actual = get_client_nonce(claims);
+        if actual == Some(expected) {
+            Ok(())
+        } else {
+            Err(NonceMismatchError {
+                expected: expected.clone(),
+                actual: actual.cloned(),
+            })
+        }
+    }
+
+    /// Returns the difference between the scope requested in this request and the one granted
+    /// in the given `response`, or `None` if this request contains no scope.
+    ///
+    /// If the `response` contains no scope, the requested scope has been granted as-is,
+    /// as specified in [section 5.8.2 of RFC 9200](https://www.rfc-editor.org/rfc/rfc9200#section-5.8.2).
+    /// This can be used by the client to decide whether to proceed with a narrowed scope
+    /// or re-request, as well as by the Authorization Server to inspect a response before
+    /// sending it. See [`Scope::diff`] for details.
+    ///
+    /// # Example
+    /// ```
+    /// # use dcaf::{AccessTokenRequest, AccessTokenResponse, Scope};
+    /// # use dcaf::common::scope::{ScopeDiff, ScopeElementDiff};
+    /// # #[cfg(feature = "std")] {
+    /// let request = AccessTokenRequest::builder()
+    ///     .scope(Scope::try_from(vec!["rTempC", "rHum"])?)
+    ///     .build()?;
+    /// let response = AccessTokenResponse::builder()
+    ///     .access_token(vec![0xDC, 0xAF])
+    ///     .scope(Scope::try_from(vec!["rTempC"])?)
+    ///     .build()?;
+    /// assert_eq!(
+    ///     request.scope_diff(&response),
+    ///     Some(ScopeDiff::Text(ScopeElementDiff {
+    ///         granted: vec!["rTempC".to_string()],
+    ///         withheld: vec!["rHum".to_string()],
+    ///         unrequested: vec![],
+    ///     }))
+    /// );
+    /// # }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    #[must_use]
+    pub fn scope_diff(&self, response: &AccessTokenResponse) -> Option<ScopeDiff> {
+        let requested = self.scope.as_ref()?;
+        Some(requested.diff(response.scope.as_ref().unwrap_or(requested)))
+    }
+
+    /// Validates the given `response` of the Authorization Server against this request,
+    /// intended for use by the client before it trusts the issued token.
+    ///
+    /// The following checks are performed:
+    /// - The granted [`scope`](AccessTokenResponse::scope) must not exceed the requested one
+    ///   (see [`Scope::is_within`]).
+    /// - If this request has the [`ace_profile`](AccessTokenRequest::ace_profile) flag set,
+    ///   the response must contain an [`ace_profile`](AccessTokenResponse::ace_profile).
+    ///   If it contains one, it must be one of the `supported_profiles` of the client.
+    /// - Proof-of-possession tokens (the default [`token_type`](AccessTokenResponse::token_type))
+    ///   require a [`cnf`](AccessTokenResponse::cnf) in the response if this request
+    ///   contains no [`req_cnf`](AccessTokenRequest::req_cnf), since the client otherwise has no
+    ///   key to prove possession with.
+    /// - If this request contains a `req_cnf`, a bearer token must not be issued.
+    ///
+    /// # Errors
+    /// If any of the above checks fails, with the variant of [`InvalidTokenResponseError`]
+    /// describing which one.
+    ///
+    /// # Example
+    /// ```
+    /// # use dcaf::{AccessTokenRequest, AccessTokenResponse, AceProfile, Scope};
+    /// # use dcaf::common::cbor_values::ProofOfPossessionKey;
+    /// # use dcaf::error::InvalidTokenResponseError;
+    /// # #[cfg(feature = "std")] {
+    /// let request = AccessTokenRequest::builder()
+    ///     .audience("tempSensor4711")
+    ///     .scope(Scope::try_from(vec!["rTempC", "rHum"])?)
+    ///     .ace_profile()
+    ///     .build()?;
+    /// let response = AccessTokenResponse::builder()
+    ///     .access_token(vec![0xDC, 0xAF])
+    ///     .scope(Scope::try_from(vec!["rTempC"])?)
+    ///     .ace_profile(AceProfile::CoapDtls)
+    ///     .cnf(ProofOfPossessionKey::KeyId(vec![0x42]))
+    ///     .build()?;
+    /// assert!(request.validate_response(&response, &[AceProfile::CoapDtls]).is_ok());
+    /// assert_eq!(
+    ///     request.validate_response(&response, &[AceProfile::CoapOscore]),
+    ///     Err(InvalidTokenResponseError::UnsupportedProfile(AceProfile::CoapDtls))
+    /// );
+    /// # }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    #[cfg(feature = "client")]
+    pub fn validate_response(
+        &self,
+        response: &AccessTokenResponse,
+        supported_profiles: &[AceProfile],
+    ) -> Result<(), InvalidTokenResponseError> {
+        if let (Some(requested), Some(granted)) = (&self.scope, &response.scope) {
+            if !granted.is_within(requested) {
+                return Err(InvalidTokenResponseError::ScopeExceedsRequest);
+            }
+        }
+        match response.ace_profile {
+            None if self.ace_profile.is_some() => {
+                return Err(InvalidTokenResponseError::MissingProfile);
+            }
+            Some(profile) if !supported_profiles.contains(&profile) => {
+                return Err(InvalidTokenResponseError::UnsupportedProfile(profile));
+            }
+            _ => {}
+        }
+        match response.token_type {
+            Some(TokenType::Bearer) if self.req_cnf.is_some() => Err(
+                InvalidTokenResponseError::UnexpectedTokenType(TokenType::Bearer),
+            ),
+            None | Some(TokenType::ProofOfPossession)
+                if self.req_cnf.is_none() && response.cnf.is_none() =>
+            {
+                Err(InvalidTokenResponseError::MissingConfirmation)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Splits the given `uri` into its scheme, host, and the part after its authority
+/// (e.g., `("http", "127.0.0.1", "/callback")`) if its host is a loopback IP address,
+/// leaving out the port.
+///
+/// Returns `None` for all other URIs, as well as for URIs whose authority contains userinfo
+/// or a port which doesn't consist of one to five ASCII digits.
+fn split_loopback_port(uri: &str) -> Option<(&str, &str, &str)> {
+    let (scheme, rest) = uri.split_once("://")?;
+    let authority_end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+    let (authority, path) = rest.split_at(authority_end);
+    if authority.contains('@') {
+        return None;
+    }
+    let host_end = if authority.starts_with('[') {
+        authority.find(']')? + 1
+    } else {
+        authority.find(':').unwrap_or(authority.len())
+    };
+    let (host, port) = authority.split_at(host_end);
+    if !matches!(host, "127.0.0.1" | "[::1]") {
+        return None;
+    }
+    match port.strip_prefix(':') {
+        None if port.is_empty() => {}
+        Some(digits)
+            if (1..=5).contains(&digits.len()) && digits.bytes().all(|x| x.is_ascii_digit()) => {}
+        _ => return None,
+    }
+    Some((scheme, host, path))
+}
+
+impl ErrorCode {
+    /// Returns the CoAP code with which an [`ErrorResponse`] containing this error code should
+    /// be sent, as specified in [section 5.8.3 of RFC 9200](https://www.rfc-editor.org/rfc/rfc9200#section-5.8.3):
+    /// [4.01 (Unauthorized)](coap_code::UNAUTHORIZED) for
+    /// [`InvalidClient`](ErrorCode::InvalidClient), and [4.00 (Bad Request)](coap_code::BAD_REQUEST)
+    /// for all other error codes (including unknown ones).
+    ///
+    /// # Example
+    /// ```
+    /// # use dcaf::ErrorCode;
+    /// # use dcaf::constants::coap_code;
+    /// assert_eq!(ErrorCode::InvalidClient.coap_code(), coap_code::UNAUTHORIZED);
+    /// assert_eq!(ErrorCode::InvalidScope.coap_code(), coap_code::BAD_REQUEST);
+    /// assert_eq!(ErrorCode::InvalidScope.http_status(), 400);
+    /// ```
+    #[must_use]
+    pub fn coap_code(self) -> u8 {
+        if self == ErrorCode::InvalidClient {
+            coap_code::UNAUTHORIZED
+        } else {
+            coap_code::BAD_REQUEST
+        }
+    }
+
+    /// Returns the HTTP status with which an [`ErrorResponse`] containing this error code should
+    /// be sent, as specified in [section 5.2 of RFC 6749](https://www.rfc-editor.org/rfc/rfc6749#section-5.2):
+    /// 401 (Unauthorized) for [`InvalidClient`](ErrorCode::InvalidClient), and 400 (Bad Request)
+    /// for all other error codes (including unknown ones).
+    #[must_use]
+    pub fn http_status(self) -> u16 {
+        if self == ErrorCode::InvalidClient {
+            401
+        } else {
+            400
+        }
+    }
 }
 
 #[allow(clippy::unused_self, clippy::unnecessary_wraps)]
 mod builder {
+    #[cfg(not(feature = "std"))]
+    use alloc::string::ToString;
+
+    use crate::common::text::{TextKind, TextLimits};
+
     use super::*;
 
     impl AccessTokenRequestBuilder {
         pub(crate) fn validate(&self) -> Result<(), AccessTokenRequestBuilderError> {
-            // TODO: Check whether there are invariants to validate
-            Ok(())
+            TextLimits::DEFAULT
+                .check_fields(&[
+                    (
+                        "client_id",
+                        self.client_id.as_ref().and_then(Option::as_deref),
+                        TextKind::ClientId,
+                    ),
+                    (
+                        "audience",
+                        self.audience.as_ref().and_then(Option::as_deref),
+                        TextKind::Identifier,
+                    ),
+                    (
+                        "redirect_uri",
+                        self.redirect_uri.as_ref().and_then(Option::as_deref),
+                        TextKind::Uri,
+                    ),
+                    (
+                        "issuer",
+                        self.issuer.as_ref().and_then(Option::as_deref),
+                        TextKind::Identifier,
+                    ),
+                    (
+                        "subject_token_type",
+                        self.subject_token_type.as_ref().and_then(Option::as_deref),
+                        TextKind::Uri,
+                    ),
+                    (
+                        "actor_token_type",
+                        self.actor_token_type.as_ref().and_then(Option::as_deref),
+                        TextKind::Uri,
+                    ),
+                ])
+                .map_err(|e| AccessTokenRequestBuilderError::ValidationError(e.to_string()))
         }
 
         /// Sets the [`ace_profile`](AccessTokenRequest::ace_profile) field to an empty value,
@@ -612,8 +1103,31 @@ mod builder {
 
     impl ErrorResponseBuilder {
         pub(crate) fn validate(&self) -> Result<(), ErrorResponseBuilderError> {
-            // TODO: Check whether there are invariants to validate
-            Ok(())
+            if self
+                .ace_profiles_supported
+                .as_ref()
+                .map_or(false, |x| !x.is_empty())
+                && self.error != Some(ErrorCode::IncompatibleAceProfiles)
+            {
+                return Err(ErrorResponseBuilderError::ValidationError(
+                    "ace_profiles_supported may only be included for incompatible_ace_profiles errors"
+                        .to_string(),
+                ));
+            }
+            TextLimits::DEFAULT
+                .check_fields(&[
+                    (
+                        "error_description",
+                        self.description.as_ref().and_then(Option::as_deref),
+                        TextKind::Description,
+                    ),
+                    (
+                        "error_uri",
+                        self.uri.as_ref().and_then(Option::as_deref),
+                        TextKind::Uri,
+                    ),
+                ])
+                .map_err(|e| ErrorResponseBuilderError::ValidationError(e.to_string()))
         }
     }
 }
@@ -622,13 +1136,14 @@ mod conversion {
     use ciborium::value::Value;
     use coset::cwt::Timestamp;
     use erased_serde::Serialize as ErasedSerialize;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
     use crate::common::cbor_map::{
-        cbor_map_vec, decode_int_map, decode_number, decode_scope, ToCborMap,
+        cbor_map_vec, decode_cnf, decode_number, decode_scope, ToCborMap,
     };
-    use crate::common::cbor_values::{CborMapValue, ProofOfPossessionKey};
+    use crate::common::cbor_values::CborMapValue;
     use crate::constants::cbor_abbreviations::{
-        ace_profile, error, grant_types, introspection, token, token_types,
+        ace_profile, error, grant_types, token, token_types, CborKey,
     };
 
     #[cfg(not(feature = "std"))]
@@ -646,6 +1161,7 @@ mod conversion {
                 grant_types::AUTHORIZATION_CODE => GrantType::AuthorizationCode,
                 grant_types::CLIENT_CREDENTIALS => GrantType::ClientCredentials,
                 grant_types::REFRESH_TOKEN => GrantType::RefreshToken,
+                grant_types::TOKEN_EXCHANGE => GrantType::TokenExchange,
                 x => GrantType::Other(x),
             }
         }
@@ -658,6 +1174,7 @@ mod conversion {
                 GrantType::AuthorizationCode => grant_types::AUTHORIZATION_CODE,
                 GrantType::ClientCredentials => grant_types::CLIENT_CREDENTIALS,
                 GrantType::RefreshToken => grant_types::REFRESH_TOKEN,
+                GrantType::TokenExchange => grant_types::TOKEN_EXCHANGE,
                 GrantType::Other(x) => x.to_owned(),
             }
         }
@@ -703,6 +1220,24 @@ mod conversion {
         }
     }
 
+    impl Serialize for AceProfile {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            i32::from(*self).serialize(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for AceProfile {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            i32::deserialize(deserializer).map(AceProfile::from)
+        }
+    }
+
     impl From<i32> for ErrorCode {
         fn from(value: i32) -> Self {
             match value {
@@ -739,15 +1274,22 @@ mod conversion {
         fn to_cbor_map(&self) -> Vec<(i128, Option<Box<dyn ErasedSerialize + '_>>)> {
             let grant_type: Option<CborMapValue<GrantType>> = self.grant_type.map(CborMapValue);
             cbor_map_vec! {
-                introspection::ISSUER => self.issuer.as_ref(),
+                token::ISSUER => self.issuer.as_ref(),
                 token::REQ_CNF => self.req_cnf.as_ref().map(ToCborMap::to_ciborium_value),
                 token::AUDIENCE => self.audience.as_ref(),
                 token::SCOPE => self.scope.as_ref(),
                 token::CLIENT_ID => self.client_id.as_ref(),
                 token::REDIRECT_URI => self.redirect_uri.as_ref(),
+                token::CODE => self.code.as_ref().map(|v| Value::Bytes(v.clone())),
                 token::GRANT_TYPE => grant_type,
                 token::ACE_PROFILE => self.ace_profile.as_ref(),
-                token::CNONCE => self.client_nonce.as_ref().map(|v| Value::Bytes(v.clone()))
+                token::CNONCE => self.client_nonce.as_ref().map(|v| Value::Bytes(v.clone())),
+                token::CONFIRMATION_NONCE => self.confirmation_nonce.as_ref().map(|v| Value::Bytes(v.clone())),
+                token::CLIENT_ASSERTION => self.client_assertion.as_ref().map(|v| Value::Bytes(v.clone())),
+                token::SUBJECT_TOKEN => self.subject_token.as_ref().map(|v| Value::Bytes(v.clone())),
+                token::SUBJECT_TOKEN_TYPE => self.subject_token_type.as_ref(),
+                token::ACTOR_TOKEN => self.actor_token.as_ref().map(|v| Value::Bytes(v.clone())),
+                token::ACTOR_TOKEN_TYPE => self.actor_token_type.as_ref()
             }
         }
 
@@ -757,24 +1299,25 @@ mod conversion {
         {
             let mut request = AccessTokenRequest::builder();
             for entry in map {
-                match (u8::try_from(entry.0)?, entry.1) {
-                    (token::REQ_CNF, Value::Map(x)) => {
-                        request.req_cnf(ProofOfPossessionKey::try_from_cbor_map(decode_int_map::<
-                            Self,
-                        >(
-                            x, "req_cnf"
-                        )?)?)
-                    }
+                match (CborKey::try_from(entry.0)?, entry.1) {
+                    (token::REQ_CNF, v) => request.req_cnf(decode_cnf(v, "req_cnf")?),
                     (token::AUDIENCE, Value::Text(x)) => request.audience(x),
                     (token::SCOPE, v) => request.scope(decode_scope(v)?),
                     (token::CLIENT_ID, Value::Text(x)) => request.client_id(x),
                     (token::REDIRECT_URI, Value::Text(x)) => request.redirect_uri(x),
+                    (token::CODE, Value::Bytes(x)) => request.code(x),
                     (token::GRANT_TYPE, Value::Integer(x)) => {
                         request.grant_type(GrantType::from(decode_number::<i32>(x, "grant_type")?))
                     }
                     (token::ACE_PROFILE, Value::Null) => request.ace_profile(),
                     (token::CNONCE, Value::Bytes(x)) => request.client_nonce(x),
-                    (introspection::ISSUER, Value::Text(x)) => request.issuer(x),
+                    (token::CONFIRMATION_NONCE, Value::Bytes(x)) => request.confirmation_nonce(x),
+                    (token::CLIENT_ASSERTION, Value::Bytes(x)) => request.client_assertion(x),
+                    (token::ISSUER, Value::Text(x)) => request.issuer(x),
+                    (token::SUBJECT_TOKEN, Value::Bytes(x)) => request.subject_token(x),
+                    (token::SUBJECT_TOKEN_TYPE, Value::Text(x)) => request.subject_token_type(x),
+                    (token::ACTOR_TOKEN, Value::Bytes(x)) => request.actor_token(x),
+                    (token::ACTOR_TOKEN_TYPE, Value::Text(x)) => request.actor_token_type(x),
                     (key, _) => return Err(TryFromCborMapError::unknown_field(key)),
                 };
             }
@@ -791,13 +1334,14 @@ mod conversion {
             cbor_map_vec! {
                 token::ACCESS_TOKEN => Some(Value::Bytes(self.access_token.clone())),
                 token::EXPIRES_IN => self.expires_in,
-                introspection::ISSUED_AT => self.issued_at.as_ref().map(|x| x.clone().to_cbor_value().expect("serialization of issued_at failed")),
+                token::ISSUED_AT => self.issued_at.as_ref().map(|x| x.clone().to_cbor_value().expect("serialization of issued_at failed")),
                 token::CNF => self.cnf.as_ref().map(ToCborMap::to_ciborium_value),
                 token::SCOPE => self.scope.as_ref(),
                 token::TOKEN_TYPE => token_type,
                 token::REFRESH_TOKEN => self.refresh_token.as_ref().map(|v| Value::Bytes(v.clone())),
                 token::ACE_PROFILE => ace_profile,
-                token::RS_CNF => self.rs_cnf.as_ref().map(ToCborMap::to_ciborium_value)
+                token::RS_CNF => self.rs_cnf.as_ref().map(ToCborMap::to_ciborium_value),
+                token::KEY_CONFIRMATION => self.key_confirmation.as_ref().map(|v| Value::Bytes(v.clone()))
             }
         }
 
@@ -807,22 +1351,16 @@ mod conversion {
         {
             let mut response = AccessTokenResponse::builder();
             for entry in map {
-                match (u8::try_from(entry.0)?, entry.1) {
+                match (CborKey::try_from(entry.0)?, entry.1) {
                     (token::ACCESS_TOKEN, Value::Bytes(x)) => response.access_token(x),
                     (token::EXPIRES_IN, Value::Integer(x)) => {
                         response.expires_in(decode_number::<u32>(x, "expires_in")?)
                     }
-                    (introspection::ISSUED_AT, v) => response.issued_at(
+                    (token::ISSUED_AT, v) => response.issued_at(
                         Timestamp::from_cbor_value(v)
                             .map_err(|x| TryFromCborMapError::from_message(x.to_string()))?,
                     ),
-                    (token::CNF, Value::Map(x)) => {
-                        response.cnf(ProofOfPossessionKey::try_from_cbor_map(decode_int_map::<
-                            Self,
-                        >(
-                            x, "cnf"
-                        )?)?)
-                    }
+                    (token::CNF, v) => response.cnf(decode_cnf(v, "cnf")?),
                     (token::SCOPE, v) => response.scope(decode_scope(v)?),
                     (token::TOKEN_TYPE, Value::Integer(x)) => {
                         response.token_type(TokenType::from(decode_number::<i32>(x, "token_type")?))
@@ -830,13 +1368,8 @@ mod conversion {
                     (token::REFRESH_TOKEN, Value::Bytes(x)) => response.refresh_token(x),
                     (token::ACE_PROFILE, Value::Integer(x)) => response
                         .ace_profile(AceProfile::from(decode_number::<i32>(x, "ace_profile")?)),
-                    (token::RS_CNF, Value::Map(x)) => {
-                        response.rs_cnf(ProofOfPossessionKey::try_from_cbor_map(decode_int_map::<
-                            Self,
-                        >(
-                            x, "rs_cnf"
-                        )?)?)
-                    }
+                    (token::RS_CNF, v) => response.rs_cnf(decode_cnf(v, "rs_cnf")?),
+                    (token::KEY_CONFIRMATION, Value::Bytes(x)) => response.key_confirmation(x),
                     (key, _) => return Err(TryFromCborMapError::unknown_field(key)),
                 };
             }
@@ -849,10 +1382,19 @@ mod conversion {
     impl ToCborMap for ErrorResponse {
         fn to_cbor_map(&self) -> Vec<(i128, Option<Box<dyn ErasedSerialize + '_>>)> {
             let error = CborMapValue(self.error);
+            let profiles: Option<Vec<CborMapValue<AceProfile>>> = Some(
+                self.ace_profiles_supported
+                    .iter()
+                    .copied()
+                    .map(CborMapValue)
+                    .collect(),
+            )
+            .filter(|x: &Vec<_>| !x.is_empty());
             cbor_map_vec! {
                 token::ERROR => Some(error),
                 token::ERROR_DESCRIPTION => self.description.as_ref(),
-                token::ERROR_URI => self.uri.as_ref()
+                token::ERROR_URI => self.uri.as_ref(),
+                token::ACE_PROFILES_SUPPORTED => profiles
             }
         }
 
@@ -862,12 +1404,25 @@ mod conversion {
         {
             let mut error = ErrorResponse::builder();
             for entry in map {
-                match (u8::try_from(entry.0)?, entry.1) {
+                match (CborKey::try_from(entry.0)?, entry.1) {
                     (token::ERROR, Value::Integer(x)) => {
                         error.error(ErrorCode::from(decode_number::<i32>(x, "error")?))
                     }
                     (token::ERROR_URI, Value::Text(x)) => error.uri(x),
                     (token::ERROR_DESCRIPTION, Value::Text(x)) => error.description(x),
+                    (token::ACE_PROFILES_SUPPORTED, Value::Array(x)) => error
+                        .ace_profiles_supported(
+                            x.into_iter()
+                                .map(|profile| match profile {
+                                    Value::Integer(i) => {
+                                        decode_number::<i32>(i, "ace_profile").map(AceProfile::from)
+                                    }
+                                    _ => Err(TryFromCborMapError::from_message(
+                                        "ace_profile must be an integer",
+                                    )),
+                                })
+                                .collect::<Result<Vec<_>, _>>()?,
+                        ),
                     (key, _) => return Err(TryFromCborMapError::unknown_field(key)),
                 };
             }
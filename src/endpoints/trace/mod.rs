@@ -0,0 +1,242 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+//! Contains helpers for capturing a trace of the messages exchanged during an ACE-OAuth flow,
+//! which can be attached to bug reports when debugging interoperability issues.
+//!
+//! Messages are encoded using [`trace_sent`] and decoded using [`trace_received`], which work
+//! just like [`AceMessage::encode`] and [`AceMessage::decode`], but additionally record a
+//! [`TraceEntry`] in a [`TraceSink`]. Each entry contains the exact bytes which have been sent or
+//! received, their decoded CBOR structure, and a timestamp supplied by the caller (since
+//! `no_std` environments may not have a clock). Messages which couldn't be decoded are recorded
+//! as well, along with the reason why decoding failed.
+//!
+//! A [`TraceBundle`] simply collects all entries and can be [saved](TraceBundle::save) as CBOR.
+//! Since it implements [`Serialize`], it can also be exported in other formats, such as JSON
+//! using `serde_json`.
+//!
+//! # Example
+//! ```
+//! # use dcaf::AccessTokenRequest;
+//! use dcaf::endpoints::trace::{trace_received, trace_sent, TraceBundle, TraceDirection};
+//!
+//! # #[cfg(feature = "std")] {
+//! let mut bundle = TraceBundle::default();
+//! let request = AccessTokenRequest::builder().client_id("myclient").build()?;
+//! let encoded = trace_sent(request.clone(), 1_700_000_000_000, &mut bundle)?;
+//! // ...the other party receives the request...
+//! let received: AccessTokenRequest = trace_received(&encoded, 1_700_000_000_042, &mut bundle)?;
+//! assert_eq!(received, request);
+//!
+//! assert_eq!(bundle.entries.len(), 2);
+//! assert_eq!(bundle.entries[0].direction, TraceDirection::Sent);
+//! assert_eq!(bundle.entries[1].message_type, "access_token_request");
+//! assert_eq!(bundle.entries[1].bytes, encoded);
+//!
+//! let mut attachment = Vec::new();
+//! bundle.save(&mut attachment)?;
+//! assert_eq!(TraceBundle::restore(attachment.as_slice())?, bundle);
+//! # }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use core::fmt::Debug;
+
+use ciborium::de::from_reader;
+use ciborium::ser::into_writer;
+use ciborium::value::Value;
+use ciborium_io::{Read, Write};
+use serde::{Deserialize, Serialize};
+
+use crate::common::cbor_values::ByteString;
+use crate::endpoints::message::{message_name, AceMessage};
+
+#[cfg(not(feature = "std"))]
+use {alloc::string::String, alloc::string::ToString, alloc::vec::Vec};
+
+#[cfg(test)]
+mod tests;
+
+/// Whether a traced message has been sent or received.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash, Serialize, Deserialize)]
+pub enum TraceDirection {
+    /// The message has been sent by the tracing party.
+    Sent,
+
+    /// The message has been received by the tracing party.
+    Received,
+}
+
+/// A single message of a traced flow, as recorded in a [`TraceSink`].
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct TraceEntry {
+    /// The time at which the message has been sent or received, in a unit chosen by the caller
+    /// (e.g., milliseconds since the Unix epoch).
+    pub timestamp: i64,
+
+    /// Whether the message has been sent or received.
+    pub direction: TraceDirection,
+
+    /// The type of the message in snake case, e.g., `access_token_request`.
+    pub message_type: String,
+
+    /// The CoAP Content-Format ID of the message.
+    pub content_format: u16,
+
+    /// The exact bytes of the message.
+    #[serde(with = "serde_bytes")]
+    pub bytes: ByteString,
+
+    /// The decoded CBOR structure of the message, or `None` if the bytes aren't valid CBOR.
+    pub decoded: Option<Value>,
+
+    /// Why the message couldn't be decoded as its message type, or `None` if decoding succeeded.
+    pub error: Option<String>,
+}
+
+impl TraceEntry {
+    /// Creates a new entry for the message of type `T` contained in `bytes`, which couldn't
+    /// be decoded for the given reason if `error` is present.
+    fn new<T>(
+        timestamp: i64,
+        direction: TraceDirection,
+        bytes: &[u8],
+        error: Option<String>,
+    ) -> TraceEntry
+    where
+        T: AceMessage,
+    {
+        TraceEntry {
+            timestamp,
+            direction,
+            message_type: message_name::<T>(),
+            content_format: T::CONTENT_FORMAT,
+            bytes: bytes.to_vec(),
+            decoded: from_reader(bytes).ok(),
+            error,
+        }
+    }
+}
+
+/// Receives every message traced by [`trace_sent`] and [`trace_received`].
+///
+/// This is implemented for all closures taking a [`TraceEntry`], as well as for [`TraceBundle`].
+pub trait TraceSink {
+    /// Records the given entry.
+    fn record(&mut self, entry: TraceEntry);
+}
+
+impl<F> TraceSink for F
+where
+    F: FnMut(TraceEntry),
+{
+    fn record(&mut self, entry: TraceEntry) {
+        self(entry);
+    }
+}
+
+/// Collects all traced messages of a flow, in the order they have been recorded.
+///
+/// For an example, see the [module-level documentation](self).
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub struct TraceBundle {
+    /// The recorded messages.
+    pub entries: Vec<TraceEntry>,
+}
+
+impl TraceSink for TraceBundle {
+    fn record(&mut self, entry: TraceEntry) {
+        self.entries.push(entry);
+    }
+}
+
+impl TraceBundle {
+    /// Serializes this bundle as CBOR into the given `writer`.
+    ///
+    /// # Errors
+    /// - When the output couldn't be put inside the given `writer`.
+    pub fn save<W>(&self, writer: W) -> Result<(), ciborium::ser::Error<W::Error>>
+    where
+        W: Write,
+        W::Error: Debug,
+    {
+        into_writer(self, writer)
+    }
+
+    /// Deserializes a bundle which has previously been [saved](TraceBundle::save)
+    /// from the given `reader`.
+    ///
+    /// # Errors
+    /// - When the given `reader` does not contain a valid serialized bundle.
+    /// - When the input couldn't be read from the given `reader`.
+    pub fn restore<R>(reader: R) -> Result<TraceBundle, ciborium::de::Error<R::Error>>
+    where
+        R: Read,
+        R::Error: Debug,
+    {
+        from_reader(reader)
+    }
+}
+
+/// Encodes the given `message` (just like [`AceMessage::encode`]) and records it as sent at
+/// `timestamp` in the given `sink`.
+///
+/// For an example, see the [module-level documentation](self).
+///
+/// # Errors
+/// When serialization of the message failed, in which case nothing is recorded.
+pub fn trace_sent<T, S>(
+    message: T,
+    timestamp: i64,
+    sink: &mut S,
+) -> Result<Vec<u8>, ciborium::ser::Error<<Vec<u8> as Write>::Error>>
+where
+    T: AceMessage,
+    S: TraceSink,
+{
+    let encoded = message.encode()?;
+    sink.record(TraceEntry::new::<T>(
+        timestamp,
+        TraceDirection::Sent,
+        &encoded,
+        None,
+    ));
+    Ok(encoded)
+}
+
+/// Decodes a message of type `T` from the given `encoded` bytes (just like
+/// [`AceMessage::decode`]) and records it as received at `timestamp` in the given `sink`.
+///
+/// The message is recorded even if it couldn't be decoded.
+///
+/// For an example, see the [module-level documentation](self).
+///
+/// # Errors
+/// When `encoded` does not contain a valid CBOR map representing this message type.
+pub fn trace_received<'a, T, S>(
+    encoded: &'a [u8],
+    timestamp: i64,
+    sink: &mut S,
+) -> Result<T, ciborium::de::Error<<&'a [u8] as Read>::Error>>
+where
+    T: AceMessage,
+    S: TraceSink,
+{
+    let result = T::decode(encoded);
+    let error = result.as_ref().err().map(ToString::to_string);
+    sink.record(TraceEntry::new::<T>(
+        timestamp,
+        TraceDirection::Received,
+        encoded,
+        error,
+    ));
+    result
+}
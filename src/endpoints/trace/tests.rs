@@ -0,0 +1,87 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+#[cfg(not(feature = "std"))]
+use alloc::vec;
+
+use crate::constants::content_format;
+use crate::{AccessTokenResponse, ErrorResponse};
+
+use super::*;
+
+#[test]
+fn test_trace_flow() -> Result<(), String> {
+    let mut bundle = TraceBundle::default();
+    let response = AccessTokenResponse::builder()
+        .access_token(vec![0xDC, 0xAF])
+        .build()
+        .map_err(|x| x.to_string())?;
+    let encoded = trace_sent(response.clone(), 10, &mut bundle).map_err(|x| x.to_string())?;
+    let decoded: AccessTokenResponse =
+        trace_received(&encoded, 20, &mut bundle).map_err(|x| x.to_string())?;
+    assert_eq!(decoded, response);
+    // Messages which can't be decoded are recorded as well.
+    assert!(trace_received::<ErrorResponse, _>(&encoded, 30, &mut bundle).is_err());
+    assert!(trace_received::<AccessTokenResponse, _>(&[0xFF], 40, &mut bundle).is_err());
+
+    let expected_value = Value::Map(vec![(
+        Value::Integer(1.into()),
+        Value::Bytes(vec![0xDC, 0xAF]),
+    )]);
+    let [sent, received, wrong_type, invalid] = <[TraceEntry; 4]>::try_from(bundle.entries.clone())
+        .map_err(|_| "expected four entries".to_string())?;
+    assert_eq!(
+        sent,
+        TraceEntry {
+            timestamp: 10,
+            direction: TraceDirection::Sent,
+            message_type: "access_token_response".to_string(),
+            content_format: content_format::ACE_CBOR,
+            bytes: encoded.clone(),
+            decoded: Some(expected_value.clone()),
+            error: None,
+        }
+    );
+    assert_eq!(
+        received,
+        TraceEntry {
+            timestamp: 20,
+            direction: TraceDirection::Received,
+            ..sent
+        }
+    );
+    assert_eq!(wrong_type.message_type, "error_response");
+    assert_eq!(wrong_type.decoded, Some(expected_value));
+    assert!(wrong_type.error.is_some());
+    assert_eq!(invalid.decoded, None);
+    assert!(invalid.error.is_some());
+
+    let mut saved = Vec::new();
+    bundle.save(&mut saved).map_err(|x| x.to_string())?;
+    assert_eq!(
+        TraceBundle::restore(saved.as_slice()).map_err(|x| x.to_string())?,
+        bundle
+    );
+    Ok(())
+}
+
+#[test]
+fn test_trace_closure_sink() -> Result<(), String> {
+    let mut timestamps = Vec::new();
+    let mut sink = |entry: TraceEntry| timestamps.push(entry.timestamp);
+    let response = AccessTokenResponse::builder()
+        .access_token(vec![0xDC, 0xAF])
+        .build()
+        .map_err(|x| x.to_string())?;
+    trace_sent(response, 42, &mut sink).map_err(|x| x.to_string())?;
+    assert_eq!(timestamps, vec![42]);
+    Ok(())
+}
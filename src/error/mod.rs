@@ -20,6 +20,9 @@ use {
     derive_builder::export::core::marker::PhantomData,
 };
 
+#[cfg(all(not(feature = "std"), feature = "as"))]
+use alloc::vec::Vec;
+
 use core::any::type_name;
 use core::fmt::{Display, Formatter};
 
@@ -27,6 +30,12 @@ use ciborium::value::Value;
 use coset::{CoseError, Label};
 use strum_macros::IntoStaticStr;
 
+use crate::common::cbor_values::ByteString;
+use crate::constants::cbor_abbreviations::CborKey;
+#[cfg(feature = "as")]
+use crate::endpoints::token_req::admission::BudgetScope;
+use crate::endpoints::token_req::{AceProfile, GrantType, TokenType};
+
 /// Error type used when the parameter of the type `T` couldn't be
 /// converted into [`expected_type`](WrongSourceTypeError::expected_type) because the received
 /// type was [`actual_type`](WrongSourceTypeError::actual_type) instead.
@@ -100,7 +109,7 @@ impl TryFromCborMapError {
     /// Creates a new error with a message describing that an unknown field in
     /// the CBOR map with the given `key` was encountered.
     #[must_use]
-    pub(crate) fn unknown_field(key: u8) -> TryFromCborMapError {
+    pub(crate) fn unknown_field<R>(key: CborKey<R>) -> TryFromCborMapError {
         TryFromCborMapError {
             message: format!("unknown field with key {key} encountered"),
         }
@@ -125,6 +134,61 @@ impl From<TryFromIntError> for TryFromCborMapError {
     }
 }
 
+impl From<ConfirmationError> for TryFromCborMapError {
+    fn from(e: ConfirmationError) -> Self {
+        TryFromCborMapError::from_message(e.to_string())
+    }
+}
+
+/// Error type used when a confirmation (`cnf`) structure can't be parsed, see the
+/// [`confirmation`](crate::common::confirmation) module.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[non_exhaustive]
+pub enum ConfirmationError {
+    /// The confirmation structure is not a CBOR map.
+    NotAMap,
+
+    /// The confirmation structure doesn't contain exactly one confirmation method.
+    ///
+    /// The number of contained methods is given in this field.
+    MethodCount(usize),
+
+    /// A confirmation method is not identified by an integer.
+    NonIntegerMethod,
+
+    /// The confirmation method with the given identifier is unknown.
+    UnknownMethod(i128),
+
+    /// The content of a confirmation method is invalid.
+    InvalidMethod {
+        /// The name of the confirmation method.
+        method: &'static str,
+        /// A message describing why its content is invalid.
+        message: String,
+    },
+}
+
+impl Display for ConfirmationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ConfirmationError::NotAMap => write!(f, "confirmation must be a CBOR map"),
+            ConfirmationError::MethodCount(count) => write!(
+                f,
+                "confirmation must contain exactly one method, but contains {count}"
+            ),
+            ConfirmationError::NonIntegerMethod => {
+                write!(f, "confirmation methods must be identified by integers")
+            }
+            ConfirmationError::UnknownMethod(method) => {
+                write!(f, "unknown confirmation method {method}")
+            }
+            ConfirmationError::InvalidMethod { method, message } => {
+                write!(f, "invalid confirmation method {method}: {message}")
+            }
+        }
+    }
+}
+
 /// Error type used when a CBOR map does not use integers as its key type, but was expected to.
 #[derive(Debug, PartialEq, Eq, Clone, Hash)]
 pub struct ValueIsNotIntegerError;
@@ -135,6 +199,25 @@ impl Display for ValueIsNotIntegerError {
     }
 }
 
+/// Error type used when a CBOR map contains the same key more than once, but
+/// [`DuplicateKeyHandling::Reject`](crate::common::cbor_map::DuplicateKeyHandling::Reject)
+/// was requested.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct DuplicateMapKeyError {
+    /// The key which appeared more than once.
+    pub key: i128,
+}
+
+impl Display for DuplicateMapKeyError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "CBOR map key {} must not appear more than once",
+            self.key
+        )
+    }
+}
+
 /// Error type used when a [`TextEncodedScope`](crate::common::scope::TextEncodedScope)
 /// does not conform to the specification given in RFC 6749.
 #[derive(Debug, PartialEq, Eq, Clone, Hash, IntoStaticStr)]
@@ -239,11 +322,14 @@ impl Display for InvalidAifEncodedScopeError {
 }
 
 /// Error type used when a [`CoseEncrypt0Cipher`](crate::CoseEncrypt0Cipher),
-/// [`CoseSign1Cipher`](crate::CoseSign1Cipher), or [`CoseMac0Cipher`](crate::CoseMac0Cipher).
+/// [`CoseSign1Cipher`](crate::CoseSign1Cipher), or [`CoseMac0Cipher`](crate::CoseMac0Cipher)
 /// fails to perform an operation.
 ///
+/// Ciphers should use the most specific variant available, so that callers (which receive this
+/// error wrapped in an [`AccessTokenError`]) can tell apart e.g. a missing key from a forged token.
+///
 /// `T` is the type of the nested error represented by the [`Other`](CoseCipherError::Other) variant.
-#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 #[non_exhaustive]
 pub enum CoseCipherError<T>
 where
@@ -258,7 +344,19 @@ where
     VerificationFailure,
     /// The given ciphertext could not be decrypted.
     DecryptionFailure,
-    /// A different error has occurred. Details are provided in the contained error.
+    /// The cipher doesn't support the algorithm required for the operation.
+    UnsupportedAlgorithm {
+        /// The unsupported algorithm, if known.
+        algorithm: Option<crate::common::cose::Algorithm>,
+    },
+    /// The cipher has no key for the operation.
+    KeyNotFound {
+        /// The ID of the key which could not be found, or an empty bytestring if no
+        /// key ID has been given.
+        key_id: ByteString,
+    },
+    /// A different error has occurred, e.g., inside the cryptographic backend.
+    /// Details are provided in the contained error.
     Other(T),
 }
 
@@ -273,7 +371,7 @@ where
     pub fn existing_header_label(label: &Label) -> CoseCipherError<T> {
         let existing_header_name = match label {
             Label::Int(i) => i.to_string(),
-            Label::Text(s) => s.to_string(),
+            Label::Text(s) => s.clone(),
         };
         CoseCipherError::HeaderAlreadySet {
             existing_header_name,
@@ -300,6 +398,39 @@ where
     pub fn other_error(other: T) -> CoseCipherError<T> {
         CoseCipherError::Other(other)
     }
+
+    /// Creates a new [`CoseCipherError`] of type
+    /// [`UnsupportedAlgorithm`](CoseCipherError::UnsupportedAlgorithm) for the given `algorithm`
+    /// (e.g., the one given in the token's protected header).
+    #[must_use]
+    pub fn unsupported_algorithm(
+        algorithm: Option<crate::common::cose::Algorithm>,
+    ) -> CoseCipherError<T> {
+        CoseCipherError::UnsupportedAlgorithm { algorithm }
+    }
+
+    /// Creates a new [`CoseCipherError`] of type
+    /// [`KeyNotFound`](CoseCipherError::KeyNotFound) for the given `key_id`.
+    #[must_use]
+    pub fn key_not_found<K>(key_id: K) -> CoseCipherError<T>
+    where
+        K: Into<ByteString>,
+    {
+        CoseCipherError::KeyNotFound {
+            key_id: key_id.into(),
+        }
+    }
+
+    /// Returns the error of the cryptographic backend if this is an
+    /// [`Other`](CoseCipherError::Other) error, and `None` otherwise.
+    #[must_use]
+    pub fn backend_error(&self) -> Option<&T> {
+        if let CoseCipherError::Other(e) = self {
+            Some(e)
+        } else {
+            None
+        }
+    }
 }
 
 impl<T> Display for CoseCipherError<T>
@@ -316,6 +447,15 @@ where
             ),
             CoseCipherError::VerificationFailure => write!(f, "data verification failed"),
             CoseCipherError::DecryptionFailure => write!(f, "decryption failed"),
+            CoseCipherError::UnsupportedAlgorithm {
+                algorithm: Some(algorithm),
+            } => write!(f, "unsupported algorithm {algorithm:?}"),
+            CoseCipherError::UnsupportedAlgorithm { algorithm: None } => {
+                write!(f, "unsupported algorithm")
+            }
+            CoseCipherError::KeyNotFound { key_id } => {
+                write!(f, "no key found for key ID {key_id:02x?}")
+            }
             CoseCipherError::Other(s) => write!(f, "{s}"),
         }
     }
@@ -348,6 +488,40 @@ pub enum ScopeFromValueError {
     InvalidType(WrongSourceTypeError<Value>),
 }
 
+/// Error type used when a [`Custom`](crate::common::scope::Scope::Custom) scope can't be
+/// handled by a [`CustomScopeRegistry`](crate::common::scope::CustomScopeRegistry).
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[non_exhaustive]
+pub enum CustomScopeError {
+    /// No codec has been registered for the encoding with the given identifier.
+    UnknownEncoding(u64),
+
+    /// A codec has already been registered for the encoding with the given identifier.
+    DuplicateEncoding(u64),
+
+    /// The scope is invalid according to the codec of the encoding with the given identifier.
+    InvalidScope(u64),
+}
+
+impl Display for CustomScopeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            CustomScopeError::UnknownEncoding(id) => {
+                write!(f, "no codec registered for scope encoding {id}")
+            }
+            CustomScopeError::DuplicateEncoding(id) => {
+                write!(
+                    f,
+                    "a codec has already been registered for scope encoding {id}"
+                )
+            }
+            CustomScopeError::InvalidScope(id) => {
+                write!(f, "scope is invalid in scope encoding {id}")
+            }
+        }
+    }
+}
+
 fn to_variant_name(value: &Value) -> &'static str {
     match value {
         Value::Integer(_) => "Integer",
@@ -418,6 +592,51 @@ impl Display for ScopeFromValueError {
     }
 }
 
+/// Error type used when the unprotected header of an access token can't be updated using
+/// [`update_unprotected_header`](crate::token::update_unprotected_header).
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum HeaderUpdateError {
+    /// A COSE specific error occurred while re-emitting the token.
+    ///
+    /// Details are contained in this field using coset's [`CoseError`].
+    CoseError(CoseError),
+    /// The token is neither a [`CoseEncrypt0`](coset::CoseEncrypt0),
+    /// [`CoseSign1`](coset::CoseSign1), nor [`CoseMac0`](coset::CoseMac0).
+    UnknownCoseStructure,
+    /// The header parameter with the label contained in this field would be present in both the
+    /// protected and the unprotected header.
+    ProtectedLabel(Label),
+    /// The header parameter with the label contained in this field (the IV or Partial IV)
+    /// would be modified, even though it's needed to process the token.
+    ImmutableParameter(Label),
+    /// The protected header, payload (or ciphertext), or signature (or tag) of the re-emitted
+    /// token would differ from the original one.
+    ProtectedContentChanged,
+}
+
+impl Display for HeaderUpdateError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            HeaderUpdateError::CoseError(e) => write!(f, "{e}"),
+            HeaderUpdateError::UnknownCoseStructure => write!(
+                f,
+                "input is either invalid or none of CoseEncrypt0, CoseSign1 nor CoseMac0"
+            ),
+            HeaderUpdateError::ProtectedLabel(label) => write!(
+                f,
+                "header parameter {label:?} is already present in the protected header"
+            ),
+            HeaderUpdateError::ImmutableParameter(label) => {
+                write!(f, "header parameter {label:?} must not be modified")
+            }
+            HeaderUpdateError::ProtectedContentChanged => {
+                write!(f, "protected content of the token would be modified")
+            }
+        }
+    }
+}
+
 /// Error type used when an operation creating or receiving an access token failed.
 ///
 /// `T` is the type of the nested error possibly contained by the
@@ -440,6 +659,17 @@ where
     /// [`CoseEncrypt0`](coset::CoseEncrypt0), [`CoseSign1`](coset::CoseSign1),
     /// nor [`CoseMac0`](coset::CoseMac0).
     UnknownCoseStructure,
+    /// The token was rejected by the
+    /// [`SecurityConfig`](crate::common::security::SecurityConfig) it was received under.
+    ///
+    /// The reason is contained in this field.
+    SecurityConfig(SecurityConfigError),
+    /// The claims of the token were rejected by the
+    /// [`ValidationPolicy`](crate::token::validation::ValidationPolicy) of the Resource Server.
+    ///
+    /// The reason is contained in this field.
+    #[cfg(feature = "rs")]
+    InvalidClaims(ClaimsValidationError),
 }
 
 impl<T> Display for AccessTokenError<T>
@@ -454,6 +684,11 @@ where
                 f,
                 "input is either invalid or none of CoseEncrypt0, CoseSign1 nor CoseMac0"
             ),
+            AccessTokenError::SecurityConfig(e) => {
+                write!(f, "rejected by security configuration: {e}")
+            }
+            #[cfg(feature = "rs")]
+            AccessTokenError::InvalidClaims(e) => write!(f, "invalid claims: {e}"),
         }
     }
 }
@@ -475,43 +710,2212 @@ where
     pub fn from_cose_cipher_error(error: CoseCipherError<T>) -> AccessTokenError<T> {
         AccessTokenError::CoseCipherError(error)
     }
+
+    /// Returns the error reported by the cipher if this is a
+    /// [`CoseCipherError`](AccessTokenError::CoseCipherError), and `None` otherwise.
+    #[must_use]
+    pub fn cipher_error(&self) -> Option<&CoseCipherError<T>> {
+        if let AccessTokenError::CoseCipherError(e) = self {
+            Some(e)
+        } else {
+            None
+        }
+    }
 }
 
-#[cfg(feature = "std")]
-mod std_error {
-    use core::fmt::Debug;
-    use std::error::Error;
+/// Error type used when a message protected by a COSE structure (e.g., an introspection request
+/// signed by the Resource Server) can't be created or verified.
+///
+/// `T` is the type of the nested error possibly contained by the cipher.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ProtectedMessageError<T>
+where
+    T: Display,
+{
+    /// The COSE structure could not be created or verified.
+    ///
+    /// Details are contained in this field, represented by an [`AccessTokenError`].
+    Protection(AccessTokenError<T>),
+    /// The payload of the verified COSE structure is not a valid message of the expected type.
+    InvalidPayload(TryFromCborMapError),
+}
 
-    use crate::endpoints::creation_hint::AuthServerRequestCreationHintBuilderError;
-    use crate::endpoints::token_req::AccessTokenRequestBuilderError;
-    use crate::endpoints::token_req::AccessTokenResponseBuilderError;
-    use crate::endpoints::token_req::ErrorResponseBuilderError;
+impl<T> Display for ProtectedMessageError<T>
+where
+    T: Display,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ProtectedMessageError::Protection(e) => write!(f, "{e}"),
+            ProtectedMessageError::InvalidPayload(e) => write!(f, "invalid payload: {e}"),
+        }
+    }
+}
 
-    use super::*;
+impl<T> From<AccessTokenError<T>> for ProtectedMessageError<T>
+where
+    T: Display,
+{
+    fn from(error: AccessTokenError<T>) -> Self {
+        ProtectedMessageError::Protection(error)
+    }
+}
 
-    impl<T> Error for WrongSourceTypeError<T> where T: Debug {}
+impl<T> From<TryFromCborMapError> for ProtectedMessageError<T>
+where
+    T: Display,
+{
+    fn from(error: TryFromCborMapError) -> Self {
+        ProtectedMessageError::InvalidPayload(error)
+    }
+}
 
-    impl Error for TryFromCborMapError {}
+/// Error type used when a key confirmation of the Authorization Server in an access token
+/// response can't be created or verified.
+///
+/// `T` is the type of the nested error possibly contained by the cipher.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum KeyConfirmationError<T>
+where
+    T: Display,
+{
+    /// The access token request contains no confirmation nonce to compute the key confirmation
+    /// over.
+    MissingConfirmationNonce,
+    /// The access token response contains no key confirmation.
+    MissingConfirmation,
+    /// The key confirmation has been computed over another confirmation nonce than the one
+    /// in the request.
+    NonceMismatch,
+    /// The COSE structure of the key confirmation could not be created or verified.
+    ///
+    /// Details are contained in this field, represented by an [`AccessTokenError`].
+    InvalidConfirmation(AccessTokenError<T>),
+}
 
-    impl Error for ValueIsNotIntegerError {}
+impl<T> Display for KeyConfirmationError<T>
+where
+    T: Display,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            KeyConfirmationError::MissingConfirmationNonce => {
+                write!(f, "access token request contains no confirmation nonce")
+            }
+            KeyConfirmationError::MissingConfirmation => {
+                write!(f, "access token response contains no key confirmation")
+            }
+            KeyConfirmationError::NonceMismatch => {
+                write!(f, "key confirmation doesn't match the confirmation nonce")
+            }
+            KeyConfirmationError::InvalidConfirmation(e) => {
+                write!(f, "invalid key confirmation: {e}")
+            }
+        }
+    }
+}
 
-    impl Error for InvalidTextEncodedScopeError {}
+impl<T> From<AccessTokenError<T>> for KeyConfirmationError<T>
+where
+    T: Display,
+{
+    fn from(error: AccessTokenError<T>) -> Self {
+        KeyConfirmationError::InvalidConfirmation(error)
+    }
+}
 
-    impl Error for InvalidBinaryEncodedScopeError {}
+#[cfg(feature = "rs")]
+/// Error type used when the claims of an access token are rejected by the Resource Server.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[non_exhaustive]
+pub enum ClaimsValidationError {
+    /// A claim required for the validation is missing from the token.
+    ///
+    /// The name of the missing claim is contained in this field.
+    MissingClaim(&'static str),
 
-    impl Error for InvalidAifEncodedScopeError {}
+    /// A claim is present, but its value does not have the expected type or format.
+    ///
+    /// The name of the invalid claim is contained in this field.
+    InvalidClaim(&'static str),
 
-    impl Error for ScopeFromValueError {}
+    /// The token was issued before the newest token the Resource Server has already seen,
+    /// and is hence considered stale.
+    StaleIssuedAt {
+        /// The `iat` value of the rejected token.
+        issued_at: i64,
+        /// The highest `iat` value the Resource Server has seen so far.
+        watermark: i64,
+    },
 
-    impl<T> Error for CoseCipherError<T> where T: Debug + Display {}
+    /// The token's `aud` claim is a wildcard audience, which the Resource Server doesn't accept.
+    ///
+    /// The rejected audience is contained in this field.
+    WildcardAudience(String),
 
-    impl<T> Error for AccessTokenError<T> where T: Debug + Display {}
+    /// The token's `aud` claim doesn't match any of the
+    /// [`audiences`](crate::token::validation::ValidationPolicy::audiences) of the Resource Server.
+    ///
+    /// The rejected audience is contained in this field.
+    AudienceMismatch(String),
 
-    impl Error for AccessTokenRequestBuilderError {}
+    /// The token has been superseded by a newer token of the same client for the same audience.
+    Superseded {
+        /// The `iat` value of the rejected token.
+        issued_at: i64,
+        /// The `iat` value of the token which superseded it.
+        current_issued_at: i64,
+    },
 
-    impl Error for AccessTokenResponseBuilderError {}
+    /// The token is protected with another algorithm than the one expected for its audience,
+    /// see [`AudienceAlgorithms`](crate::token::validation::AudienceAlgorithms).
+    UnexpectedAlgorithm {
+        /// The audience of the rejected token.
+        audience: String,
+        /// The algorithm expected for this audience.
+        expected: crate::common::cose::Algorithm,
+        /// The algorithm the token is actually protected with, if any is given
+        /// in its protected header.
+        actual: Option<crate::common::cose::Algorithm>,
+    },
 
-    impl Error for ErrorResponseBuilderError {}
+    /// The token's `cnonce` claim doesn't contain a client nonce the Resource Server is waiting
+    /// for, see [`ClientNonces`](crate::token::validation::ClientNonces).
+    ///
+    /// The rejected nonce is contained in this field.
+    UnknownClientNonce(ByteString),
 
-    impl Error for AuthServerRequestCreationHintBuilderError {}
+    /// The proof-of-possession key in the token's `cnf` claim doesn't meet the requirements of
+    /// the Resource Server, see [`KeyStrengthPolicy`](crate::common::security::KeyStrengthPolicy).
+    WeakKey(KeyStrengthError),
+
+    /// The token has expired, taking the
+    /// [`expiration_leeway`](crate::token::validation::ValidationPolicy::expiration_leeway)
+    /// into account.
+    Expired {
+        /// The `exp` value of the rejected token.
+        expiration: i64,
+        /// The time at which the token has been validated.
+        now: i64,
+    },
+
+    /// The token is not valid yet, taking the
+    /// [`not_before_leeway`](crate::token::validation::ValidationPolicy::not_before_leeway)
+    /// into account.
+    NotYetValid {
+        /// The `nbf` value of the rejected token.
+        not_before: i64,
+        /// The time at which the token has been validated.
+        now: i64,
+    },
+
+    /// The token has been issued longer ago than the
+    /// [`max_issued_at_age`](crate::token::validation::ValidationPolicy::max_issued_at_age)
+    /// allows.
+    IssuedAtTooOld {
+        /// The `iat` value of the rejected token.
+        issued_at: i64,
+        /// The time at which the token has been validated.
+        now: i64,
+    },
+}
+
+#[cfg(feature = "rs")]
+impl Display for ClaimsValidationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ClaimsValidationError::MissingClaim(name) => write!(f, "claim '{name}' is missing"),
+            ClaimsValidationError::InvalidClaim(name) => write!(f, "claim '{name}' is invalid"),
+            ClaimsValidationError::StaleIssuedAt {
+                issued_at,
+                watermark,
+            } => write!(
+                f,
+                "token issued at {issued_at} is older than newest seen token (issued at {watermark})"
+            ),
+            ClaimsValidationError::WildcardAudience(audience) => {
+                write!(f, "wildcard audience '{audience}' is not accepted")
+            }
+            ClaimsValidationError::AudienceMismatch(audience) => {
+                write!(f, "audience '{audience}' doesn't match the Resource Server")
+            }
+            ClaimsValidationError::Superseded {
+                issued_at,
+                current_issued_at,
+            } => write!(
+                f,
+                "token issued at {issued_at} has been superseded by token issued at {current_issued_at}"
+            ),
+            ClaimsValidationError::UnexpectedAlgorithm {
+                audience,
+                expected,
+                actual: Some(actual),
+            } => write!(
+                f,
+                "token for audience '{audience}' is protected with algorithm {actual:?} instead of {expected:?}"
+            ),
+            ClaimsValidationError::UnexpectedAlgorithm {
+                audience,
+                expected,
+                actual: None,
+            } => write!(
+                f,
+                "token for audience '{audience}' doesn't specify an algorithm (expected {expected:?})"
+            ),
+            ClaimsValidationError::UnknownClientNonce(nonce) => {
+                write!(f, "client nonce {nonce:?} is unknown or has already been used")
+            }
+            ClaimsValidationError::WeakKey(error) => {
+                write!(f, "proof-of-possession key is too weak: {error}")
+            }
+            ClaimsValidationError::Expired { expiration, now } => {
+                write!(f, "token expired at {expiration} (now: {now})")
+            }
+            ClaimsValidationError::NotYetValid { not_before, now } => {
+                write!(f, "token is not valid before {not_before} (now: {now})")
+            }
+            ClaimsValidationError::IssuedAtTooOld { issued_at, now } => {
+                write!(f, "token issued at {issued_at} is too old (now: {now})")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "rs")]
+impl ClaimsValidationError {
+    /// Returns the CoAP code with which the Resource Server should reject the token,
+    /// as specified in [section 5.10.1.1 of RFC 9200](https://www.rfc-editor.org/rfc/rfc9200#section-5.10.1.1):
+    /// [4.03 (Forbidden)](crate::constants::coap_code::FORBIDDEN) if the token's audience
+    /// doesn't match the Resource Server (i.e., the token doesn't authorize access to it), and
+    /// [4.01 (Unauthorized)](crate::constants::coap_code::UNAUTHORIZED) for all other
+    /// invalid tokens.
+    ///
+    /// Requests which aren't covered by the scope of an otherwise valid token are rejected with
+    /// 4.03 as well, see [`AccessDecision::coap_code`](crate::token::validation::AccessDecision::coap_code).
+    #[must_use]
+    pub fn coap_code(&self) -> u8 {
+        if self.is_forbidden() {
+            crate::constants::coap_code::FORBIDDEN
+        } else {
+            crate::constants::coap_code::UNAUTHORIZED
+        }
+    }
+
+    /// Returns the HTTP status with which the Resource Server should reject the token,
+    /// i.e., 403 (Forbidden) if the token's audience doesn't match the Resource Server,
+    /// and 401 (Unauthorized) for all other invalid tokens.
+    #[must_use]
+    pub fn http_status(&self) -> u16 {
+        if self.is_forbidden() {
+            403
+        } else {
+            401
+        }
+    }
+
+    /// Returns whether this error indicates that the token doesn't authorize access to the
+    /// Resource Server (rather than being invalid).
+    fn is_forbidden(&self) -> bool {
+        matches!(
+            self,
+            ClaimsValidationError::WildcardAudience(_) | ClaimsValidationError::AudienceMismatch(_)
+        )
+    }
+}
+
+#[cfg(feature = "rs")]
+/// Error type used when a token can't be added to a
+/// [`TokenStore`](crate::token::token_store::TokenStore) of the Resource Server.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[non_exhaustive]
+pub enum TokenStoreError {
+    /// The store is full and its eviction strategy didn't make room for the new token,
+    /// either because it rejects new tokens or because there was nothing to evict.
+    CapacityExceeded {
+        /// The maximum number of tokens the store holds.
+        capacity: usize,
+    },
+}
+
+#[cfg(feature = "rs")]
+impl Display for TokenStoreError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            TokenStoreError::CapacityExceeded { capacity } => {
+                write!(f, "token store is full (capacity: {capacity} tokens)")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "rs")]
+/// Error type used when a key can't be added to or found in a
+/// [`TrustStore`](crate::token::validation::TrustStore) of the Resource Server.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[non_exhaustive]
+pub enum TrustStoreError {
+    /// The key (or the token whose key was looked up) has no key ID.
+    MissingKeyId,
+
+    /// A different key with the same key ID has already been added.
+    KeyIdCollision {
+        /// The key ID which is already in use.
+        key_id: ByteString,
+        /// The issuer of the existing key with this key ID.
+        issuer: String,
+    },
+
+    /// No key with the key ID of the token is known.
+    ///
+    /// The key ID is contained in this field.
+    UnknownKeyId(ByteString),
+
+    /// The token's `iss` claim doesn't name the issuer of the key which verified it.
+    IssuerMismatch {
+        /// The issuer of the key which verified the token.
+        expected: String,
+        /// The issuer named in the token, if any.
+        actual: Option<String>,
+    },
+
+    /// The key with the key ID of the token has been declared for a different usage.
+    KeyUsage(KeyUsageError),
+}
+
+#[cfg(feature = "rs")]
+impl Display for TrustStoreError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            TrustStoreError::MissingKeyId => write!(f, "no key ID is given"),
+            TrustStoreError::KeyIdCollision { key_id, issuer } => write!(
+                f,
+                "key ID {key_id:?} is already in use by a different key of issuer '{issuer}'"
+            ),
+            TrustStoreError::UnknownKeyId(key_id) => write!(f, "unknown key ID {key_id:?}"),
+            TrustStoreError::IssuerMismatch {
+                expected,
+                actual: Some(actual),
+            } => write!(
+                f,
+                "token of issuer '{actual}' has been verified with a key of issuer '{expected}'"
+            ),
+            TrustStoreError::IssuerMismatch {
+                expected,
+                actual: None,
+            } => write!(
+                f,
+                "token without issuer has been verified with a key of issuer '{expected}'"
+            ),
+            TrustStoreError::KeyUsage(e) => e.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "rs")]
+impl From<KeyUsageError> for TrustStoreError {
+    fn from(e: KeyUsageError) -> Self {
+        TrustStoreError::KeyUsage(e)
+    }
+}
+
+/// Error type used when a key is looked up (or declared) for a different
+/// [`KeyUsage`](crate::token::KeyUsage) than the one it has been declared for.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[non_exhaustive]
+pub enum KeyUsageError {
+    /// No key with the contained key ID is known.
+    UnknownKeyId(ByteString),
+
+    /// The key has been declared for a different usage than the requested one.
+    UsageMismatch {
+        /// The key ID of the key.
+        key_id: ByteString,
+        /// The usage the key has been declared for.
+        declared: crate::token::KeyUsage,
+        /// The usage the key was requested for.
+        requested: crate::token::KeyUsage,
+    },
+}
+
+impl Display for KeyUsageError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            KeyUsageError::UnknownKeyId(key_id) => write!(f, "unknown key ID {key_id:?}"),
+            KeyUsageError::UsageMismatch {
+                key_id,
+                declared,
+                requested,
+            } => write!(
+                f,
+                "key with key ID {key_id:?} is declared for {declared}, not for {requested}"
+            ),
+        }
+    }
+}
+
+/// Error type used when a generic [`AccessTokenRequest`](crate::AccessTokenRequest) can't be
+/// converted into a grant-type-specific request, such as a
+/// [`ClientCredentialsRequest`](crate::endpoints::token_req::grant::ClientCredentialsRequest).
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[non_exhaustive]
+pub enum InvalidGrantRequestError {
+    /// The request uses a different grant type than the one expected.
+    WrongGrantType {
+        /// The grant type expected by the specific request type.
+        expected: GrantType,
+        /// The grant type actually used by the request.
+        actual: GrantType,
+    },
+
+    /// A field required by the grant type is missing from the request.
+    ///
+    /// The name of the missing field is contained in this field.
+    MissingField(&'static str),
+
+    /// A field which is not valid for the grant type is present in the request.
+    ///
+    /// The name of the unexpected field is contained in this field.
+    UnexpectedField(&'static str),
+}
+
+impl Display for InvalidGrantRequestError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            InvalidGrantRequestError::WrongGrantType { expected, actual } => write!(
+                f,
+                "expected grant type {expected:?}, but request uses {actual:?}"
+            ),
+            InvalidGrantRequestError::MissingField(name) => {
+                write!(f, "field '{name}' is required for this grant type")
+            }
+            InvalidGrantRequestError::UnexpectedField(name) => {
+                write!(f, "field '{name}' is not valid for this grant type")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "as")]
+/// Error type used when a key can't be added to an
+/// [`AudienceKeyRing`](crate::token::keys::AudienceKeyRing) because a different key with the
+/// same key ID is already present.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct KeyIdCollisionError {
+    /// The key ID which is already in use.
+    pub key_id: Vec<u8>,
+
+    /// The audience the existing key with this key ID is associated with.
+    pub audience: String,
+}
+
+#[cfg(feature = "as")]
+impl Display for KeyIdCollisionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "key ID {:?} is already in use by a different key for audience '{}'",
+            self.key_id, self.audience
+        )
+    }
+}
+
+#[cfg(feature = "as")]
+/// Error type used when a [`PartialIvCounters`](crate::token::keys::PartialIvCounters)
+/// can't generate a partial IV.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[non_exhaustive]
+pub enum PartialIvError {
+    /// All partial IVs of the configured maximum length have been used up for this key.
+    Exhausted,
+
+    /// The header field with the given name, which conflicts with the partial IV, is already set.
+    HeaderAlreadySet(&'static str),
+}
+
+#[cfg(feature = "as")]
+impl Display for PartialIvError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            PartialIvError::Exhausted => write!(f, "partial IVs for this key are exhausted"),
+            PartialIvError::HeaderAlreadySet(name) => {
+                write!(f, "header field '{name}' is already set")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "as")]
+/// Error type used when a [`RequestBudget`](crate::endpoints::token_req::admission::RequestBudget)
+/// rejects an incoming request.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+pub struct RequestThrottledError {
+    /// The budget which has been exhausted.
+    pub scope: BudgetScope,
+
+    /// Number of seconds after which the client may try again, if known.
+    pub retry_after: Option<u32>,
+}
+
+#[cfg(feature = "as")]
+impl Display for RequestThrottledError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let scope = match self.scope {
+            BudgetScope::Global => "global",
+            BudgetScope::Client => "client",
+        };
+        match self.retry_after {
+            Some(seconds) => write!(
+                f,
+                "{scope} request budget exhausted, retry after {seconds} seconds"
+            ),
+            None => write!(f, "{scope} request budget exhausted"),
+        }
+    }
+}
+
+/// Error type used when a client can't load the
+/// [`AuthServerMetadata`](crate::endpoints::metadata::AuthServerMetadata) of an Authorization Server.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[non_exhaustive]
+pub enum InvalidMetadataError {
+    /// The metadata document couldn't be decoded.
+    ///
+    /// The reason is contained in this field.
+    Malformed(String),
+
+    /// The metadata document has been issued by a different Authorization Server than expected.
+    IssuerMismatch {
+        /// The issuer the client expected.
+        expected: String,
+        /// The issuer contained in the metadata document.
+        actual: String,
+    },
+}
+
+impl Display for InvalidMetadataError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            InvalidMetadataError::Malformed(reason) => {
+                write!(f, "malformed metadata document: {reason}")
+            }
+            InvalidMetadataError::IssuerMismatch { expected, actual } => write!(
+                f,
+                "metadata issuer '{actual}' does not match expected issuer '{expected}'"
+            ),
+        }
+    }
+}
+
+/// Error type used when an access token can't be decompressed using
+/// [`decompress_token`](crate::token::compression::decompress_token).
+///
+/// `T` is the error type of the used
+/// [`TokenCompressor`](crate::token::compression::TokenCompressor).
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[non_exhaustive]
+pub enum TokenCompressionError<T> {
+    /// The token has been compressed with a content coding the compressor doesn't implement.
+    ///
+    /// The name of the content coding is contained in this field.
+    UnsupportedCoding(String),
+
+    /// The compressor failed to decompress the token.
+    Backend(T),
+
+    /// The decompressed token is longer than allowed.
+    TooLarge {
+        /// The length of the decompressed token in bytes.
+        length: usize,
+        /// The maximum length of the decompressed token in bytes.
+        max_len: usize,
+    },
+}
+
+impl<T> Display for TokenCompressionError<T>
+where
+    T: Display,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            TokenCompressionError::UnsupportedCoding(coding) => {
+                write!(f, "unsupported content coding '{coding}'")
+            }
+            TokenCompressionError::Backend(e) => write!(f, "decompression failed: {e}"),
+            TokenCompressionError::TooLarge { length, max_len } => write!(
+                f,
+                "decompressed token has {length} bytes, but at most {max_len} bytes are allowed"
+            ),
+        }
+    }
+}
+
+/// Error type used when a [`CorpusRecorder`](crate::endpoints::corpus::CorpusRecorder)
+/// couldn't export a decoded message.
+///
+/// `T` is the error type of the used [`CorpusSink`](crate::endpoints::corpus::CorpusSink).
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[non_exhaustive]
+pub enum CorpusExportError<T> {
+    /// The message couldn't be re-encoded after redacting it, so it hasn't been exported.
+    Redaction,
+
+    /// The sink failed to store the message.
+    Sink(T),
+}
+
+impl<T> Display for CorpusExportError<T>
+where
+    T: Display,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            CorpusExportError::Redaction => write!(f, "message couldn't be redacted"),
+            CorpusExportError::Sink(e) => write!(f, "message couldn't be stored: {e}"),
+        }
+    }
+}
+
+/// Error type used when a creation hint received from the Resource Server contradicts
+/// the provisioned one, see
+/// [`AuthServerDiscovery::resolve`](crate::endpoints::creation_hint::AuthServerDiscovery::resolve).
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct PinnedHintMismatchError {
+    /// The name of the field whose values differ.
+    pub field: &'static str,
+    /// The provisioned value of the field.
+    pub pinned: String,
+    /// The value of the field received from the Resource Server.
+    pub received: String,
+}
+
+impl Display for PinnedHintMismatchError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "received {} '{}' does not match pinned value '{}'",
+            self.field, self.received, self.pinned
+        )
+    }
+}
+
+/// Error type used when an [`AccessTokenResponse`](crate::AccessTokenResponse) doesn't match
+/// the [`AccessTokenRequest`](crate::AccessTokenRequest) it's a response to, see
+/// [`AccessTokenRequest::validate_response`](crate::AccessTokenRequest::validate_response).
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[non_exhaustive]
+pub enum InvalidTokenResponseError {
+    /// The granted scope contains permissions which haven't been requested.
+    ScopeExceedsRequest,
+
+    /// The client requested the profile to be indicated, but the response contains none.
+    MissingProfile,
+
+    /// The response indicates a profile which the client doesn't support.
+    ///
+    /// The unsupported profile is contained in this field.
+    UnsupportedProfile(AceProfile),
+
+    /// The response issues a proof-of-possession token, but neither the request nor the response
+    /// contains a key to prove possession with.
+    MissingConfirmation,
+
+    /// The response issues a token of a type the client didn't ask for (e.g., a bearer token
+    /// although the client provided a proof-of-possession key).
+    ///
+    /// The unexpected token type is contained in this field.
+    UnexpectedTokenType(TokenType),
+}
+
+impl Display for InvalidTokenResponseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            InvalidTokenResponseError::ScopeExceedsRequest => {
+                write!(f, "granted scope exceeds requested scope")
+            }
+            InvalidTokenResponseError::MissingProfile => {
+                write!(f, "response doesn't indicate the requested profile")
+            }
+            InvalidTokenResponseError::UnsupportedProfile(profile) => {
+                write!(f, "profile {profile:?} is not supported")
+            }
+            InvalidTokenResponseError::MissingConfirmation => {
+                write!(f, "no key to prove possession of the token with")
+            }
+            InvalidTokenResponseError::UnexpectedTokenType(token_type) => {
+                write!(f, "unexpected token type {token_type:?}")
+            }
+        }
+    }
+}
+
+/// Error type used when the client nonce echoed back in an access token doesn't match the
+/// `client_nonce` the client sent in its [`AccessTokenRequest`](crate::AccessTokenRequest).
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct NonceMismatchError {
+    /// The client nonce which was sent in the request.
+    pub expected: ByteString,
+
+    /// The `cnonce` claim contained in the access token,
+    /// or `None` if it was missing or not a bytestring.
+    pub actual: Option<ByteString>,
+}
+
+impl Display for NonceMismatchError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match &self.actual {
+            Some(actual) => write!(
+                f,
+                "token's client nonce {:?} does not match sent client nonce {:?}",
+                actual, self.expected
+            ),
+            None => write!(
+                f,
+                "token does not contain the sent client nonce {:?}",
+                self.expected
+            ),
+        }
+    }
+}
+
+/// Error type used when the COSE Key Thumbprint of a [`CoseKey`](coset::CoseKey) can't be
+/// computed.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[non_exhaustive]
+pub enum KeyThumbprintError {
+    /// The key type of the key is not supported for thumbprint computation.
+    UnsupportedKeyType,
+
+    /// A parameter required for the thumbprint of this key type is missing from the key.
+    ///
+    /// The label of the missing parameter is contained in this field.
+    MissingParameter(i64),
+}
+
+impl Display for KeyThumbprintError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            KeyThumbprintError::UnsupportedKeyType => {
+                write!(f, "key type is not supported for thumbprint computation")
+            }
+            KeyThumbprintError::MissingParameter(label) => {
+                write!(f, "key is missing required parameter with label {label}")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "as")]
+/// Error type used when an access token request is not allowed by an
+/// [`IssuancePolicy`](crate::token::policy::IssuancePolicy).
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[non_exhaustive]
+pub enum IssuancePolicyError {
+    /// The client is not part of the policy.
+    ///
+    /// The client ID is contained in this field.
+    UnknownClient(String),
+
+    /// The request doesn't specify an audience, and the client has no default audience.
+    MissingAudience,
+
+    /// The requested audience is not part of the policy.
+    ///
+    /// The audience is contained in this field.
+    UnknownAudience(String),
+
+    /// The client may not obtain tokens for the requested audience.
+    ///
+    /// The audience is contained in this field.
+    AudienceNotAllowed(String),
+
+    /// No part of the requested scope is allowed for the client.
+    ScopeNotAllowed,
+
+    /// None of the profiles supported by the audience is supported by the client.
+    NoCommonProfile,
+
+    /// The client requested the profile to be included in the response, but neither the
+    /// audience nor the client specify any profiles to select from.
+    UndeterminedProfile,
+    /// The proof-of-possession key requested by the client doesn't meet the requirements of the
+    /// policy's [`key_strength`](crate::token::policy::IssuancePolicy::key_strength).
+    WeakKey(KeyStrengthError),
+
+    /// The token can't be renewed anymore, as the
+    /// [`renewal_window`](crate::token::policy::LifetimePolicy::renewal_window) of the audience
+    /// has already passed.
+    RenewalWindowPassed,
+
+    /// The client may not exchange tokens.
+    ExchangeNotAllowed,
+
+    /// The subject token of a token exchange request is not an access token, has expired,
+    /// wasn't issued to the requesting client, or contains an invalid scope.
+    InvalidSubjectToken,
+
+    /// The actor token of a token exchange request is not an access token, has expired, or
+    /// doesn't name its subject, or its claims haven't been passed along with it.
+    InvalidActorToken,
+
+    /// The grant has been denied by an [`ApprovalHook`](crate::token::approval::ApprovalHook).
+    ApprovalDenied,
+}
+
+#[cfg(feature = "as")]
+impl Display for IssuancePolicyError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            IssuancePolicyError::UnknownClient(client) => write!(f, "unknown client '{client}'"),
+            IssuancePolicyError::MissingAudience => write!(f, "no audience has been requested"),
+            IssuancePolicyError::UnknownAudience(audience) => {
+                write!(f, "unknown audience '{audience}'")
+            }
+            IssuancePolicyError::AudienceNotAllowed(audience) => {
+                write!(f, "client may not obtain tokens for audience '{audience}'")
+            }
+            IssuancePolicyError::ScopeNotAllowed => {
+                write!(f, "requested scope is not allowed for client")
+            }
+            IssuancePolicyError::NoCommonProfile => {
+                write!(f, "client and audience have no profile in common")
+            }
+            IssuancePolicyError::UndeterminedProfile => {
+                write!(
+                    f,
+                    "requested profile can't be determined for client and audience"
+                )
+            }
+            IssuancePolicyError::WeakKey(error) => {
+                write!(f, "requested proof-of-possession key is too weak: {error}")
+            }
+            IssuancePolicyError::RenewalWindowPassed => {
+                write!(f, "renewal window of the token has passed")
+            }
+            IssuancePolicyError::ExchangeNotAllowed => {
+                write!(f, "client may not exchange tokens")
+            }
+            IssuancePolicyError::InvalidSubjectToken => {
+                write!(f, "subject token can't be exchanged")
+            }
+            IssuancePolicyError::InvalidActorToken => {
+                write!(f, "actor token is invalid")
+            }
+            IssuancePolicyError::ApprovalDenied => write!(f, "grant has been denied"),
+        }
+    }
+}
+
+#[cfg(feature = "as")]
+impl IssuancePolicyError {
+    /// Returns the error code with which the Authorization Server should answer the request
+    /// in an [`ErrorResponse`](crate::ErrorResponse), as specified in
+    /// [section 5.8.3 of RFC 9200](https://www.rfc-editor.org/rfc/rfc9200#section-5.8.3):
+    /// [`InvalidClient`](crate::endpoints::token_req::ErrorCode::InvalidClient) for unknown
+    /// clients, [`InvalidScope`](crate::endpoints::token_req::ErrorCode::InvalidScope) for
+    /// disallowed scopes,
+    /// [`IncompatibleAceProfiles`](crate::endpoints::token_req::ErrorCode::IncompatibleAceProfiles)
+    /// if no profile can be selected,
+    /// [`InvalidGrant`](crate::endpoints::token_req::ErrorCode::InvalidGrant) if the token can't
+    /// be renewed anymore or the subject or actor token of a token exchange is invalid,
+    /// [`UnauthorizedClient`](crate::endpoints::token_req::ErrorCode::UnauthorizedClient) if the
+    /// client may not exchange tokens or the grant has been denied by an approval hook, and
+    /// [`InvalidRequest`](crate::endpoints::token_req::ErrorCode::InvalidRequest) otherwise
+    /// (e.g., if no audience has been requested and the client has no default audience).
+    #[must_use]
+    pub fn error_code(&self) -> crate::endpoints::token_req::ErrorCode {
+        use crate::endpoints::token_req::ErrorCode;
+        match self {
+            IssuancePolicyError::UnknownClient(_) => ErrorCode::InvalidClient,
+            IssuancePolicyError::ScopeNotAllowed => ErrorCode::InvalidScope,
+            IssuancePolicyError::NoCommonProfile | IssuancePolicyError::UndeterminedProfile => {
+                ErrorCode::IncompatibleAceProfiles
+            }
+            IssuancePolicyError::MissingAudience
+            | IssuancePolicyError::UnknownAudience(_)
+            | IssuancePolicyError::AudienceNotAllowed(_) => ErrorCode::InvalidRequest,
+            IssuancePolicyError::WeakKey(_) => ErrorCode::UnsupportedPopKey,
+            IssuancePolicyError::RenewalWindowPassed
+            | IssuancePolicyError::InvalidSubjectToken
+            | IssuancePolicyError::InvalidActorToken => ErrorCode::InvalidGrant,
+            IssuancePolicyError::ExchangeNotAllowed | IssuancePolicyError::ApprovalDenied => {
+                ErrorCode::UnauthorizedClient
+            }
+        }
+    }
+}
+
+#[cfg(feature = "as")]
+/// Error type used when an [`AuthServerConfig`](crate::token::policy::AuthServerConfig)
+/// (or its [`IssuancePolicy`](crate::token::policy::IssuancePolicy)) is inconsistent.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[non_exhaustive]
+pub enum InvalidConfigError {
+    /// The client ID contained in this field is used by more than one client.
+    DuplicateClient(String),
+
+    /// The audience contained in this field is described more than once.
+    DuplicateAudience(String),
+
+    /// A client is allowed to obtain tokens for an audience which is not part of the policy.
+    UnknownAudience {
+        /// The ID of the client.
+        client_id: String,
+        /// The unknown audience.
+        audience: String,
+    },
+
+    /// There's no key for the audience contained in this field.
+    MissingKey(String),
+
+    /// The default audience of a client is not among the audiences it may obtain tokens for.
+    DefaultAudienceNotAllowed {
+        /// The ID of the client.
+        client_id: String,
+        /// The default audience.
+        audience: String,
+    },
+
+    /// An algorithm which may be used for an audience isn't supported by the cipher.
+    UnsupportedAlgorithm {
+        /// The audience.
+        audience: String,
+        /// The unsupported algorithm.
+        algorithm: crate::common::cose::Algorithm,
+    },
+
+    /// A key of the audience contained in this field has a type (or is restricted to an
+    /// algorithm) which isn't supported by the cipher.
+    UnsupportedKey(String),
+
+    /// The [`percentage`](crate::token::policy::LifetimePolicy::percentage) of an audience's
+    /// lifetime policy is outside of the range of 1 to 100.
+    InvalidPercentage {
+        /// The audience.
+        audience: String,
+        /// The invalid percentage.
+        percentage: u8,
+    },
+}
+
+#[cfg(feature = "as")]
+impl Display for InvalidConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            InvalidConfigError::DuplicateClient(client_id) => {
+                write!(f, "duplicate client '{client_id}'")
+            }
+            InvalidConfigError::DuplicateAudience(audience) => {
+                write!(f, "duplicate audience '{audience}'")
+            }
+            InvalidConfigError::UnknownAudience {
+                client_id,
+                audience,
+            } => write!(
+                f,
+                "client '{client_id}' refers to unknown audience '{audience}'"
+            ),
+            InvalidConfigError::MissingKey(audience) => {
+                write!(f, "no key for audience '{audience}'")
+            }
+            InvalidConfigError::DefaultAudienceNotAllowed {
+                client_id,
+                audience,
+            } => write!(
+                f,
+                "client '{client_id}' may not obtain tokens for its default audience '{audience}'"
+            ),
+            InvalidConfigError::UnsupportedAlgorithm {
+                audience,
+                algorithm,
+            } => write!(
+                f,
+                "algorithm {algorithm:?} of audience '{audience}' is not supported by the cipher"
+            ),
+            InvalidConfigError::UnsupportedKey(audience) => write!(
+                f,
+                "a key of audience '{audience}' is not supported by the cipher"
+            ),
+            InvalidConfigError::InvalidPercentage {
+                audience,
+                percentage,
+            } => write!(
+                f,
+                "lifetime percentage {percentage} of audience '{audience}' is not in 1..=100"
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "as")]
+/// Error type used when an opaque token reference can't be issued or resolved using a
+/// [`TokenReferenceFormat`](crate::token::format::TokenReferenceFormat).
+///
+/// `T` is the error type of the used
+/// [`TokenReferenceStore`](crate::token::storage::TokenReferenceStore).
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[non_exhaustive]
+pub enum TokenReferenceError<T> {
+    /// The token reference is unknown, e.g., because it has been revoked.
+    UnknownReference,
+
+    /// Token references are configured to be shorter than
+    /// [`MIN_REFERENCE_LENGTH`](crate::token::issuance::TokenReferenceIssuance::MIN_REFERENCE_LENGTH)
+    /// bytes.
+    ReferenceTooShort {
+        /// The configured length of token references in bytes.
+        length: usize,
+    },
+
+    /// The newly generated token reference is already in use.
+    DuplicateReference,
+
+    /// The underlying storage failed.
+    Storage(T),
+}
+
+#[cfg(feature = "as")]
+impl<T> Display for TokenReferenceError<T>
+where
+    T: Display,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            TokenReferenceError::UnknownReference => write!(f, "unknown token reference"),
+            TokenReferenceError::ReferenceTooShort { length } => write!(
+                f,
+                "token references of {length} bytes are too short to be unguessable"
+            ),
+            TokenReferenceError::DuplicateReference => {
+                write!(f, "generated token reference is already in use")
+            }
+            TokenReferenceError::Storage(e) => write!(f, "token reference storage failed: {e}"),
+        }
+    }
+}
+
+#[cfg(feature = "as")]
+/// Error type used when a client assertion can't be verified, see
+/// [`verify_client_assertion`](crate::endpoints::token_req::assertion::verify_client_assertion).
+///
+/// All of these errors should be answered with an
+/// [`InvalidClient`](crate::endpoints::token_req::ErrorCode::InvalidClient) error response.
+///
+/// `C` is the error type of the used cipher, while `S` is the error type of the used
+/// [`ClientRegistry`](crate::token::storage::ClientRegistry) and
+/// [`AssertionReplayCache`](crate::token::storage::AssertionReplayCache).
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ClientAssertionError<C, S>
+where
+    C: Display,
+{
+    /// The request doesn't contain a client assertion.
+    MissingAssertion,
+
+    /// The assertion isn't a valid signed CWT, or its signature couldn't be verified.
+    InvalidAssertion(AccessTokenError<C>),
+
+    /// The assertion doesn't contain the claim with the given name.
+    MissingClaim(&'static str),
+
+    /// The issuer and subject of the assertion don't identify the same client, or don't match
+    /// the `client_id` of the request.
+    ClientMismatch,
+
+    /// The audience of the assertion isn't the Authorization Server.
+    ///
+    /// The audience of the assertion is contained in this field.
+    WrongAudience(String),
+
+    /// The assertion has expired.
+    Expired,
+
+    /// The assertion isn't valid yet, i.e., its `nbf` or `iat` claim lies in the future.
+    NotYetValid,
+
+    /// The assertion is valid for longer (from its `iat` to its `exp`) than allowed.
+    LifetimeTooLong,
+
+    /// The assertion has already been used, i.e., its `cti` has been seen before.
+    Replayed,
+
+    /// The client identified by the assertion isn't registered.
+    ///
+    /// The identifier of the client is contained in this field.
+    UnknownClient(String),
+
+    /// The client identified by the assertion has no registered key.
+    MissingKey,
+
+    /// The underlying client registry or replay cache failed.
+    Storage(S),
+}
+
+#[cfg(feature = "as")]
+impl<C, S> Display for ClientAssertionError<C, S>
+where
+    C: Display,
+    S: Display,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ClientAssertionError::MissingAssertion => write!(f, "no client assertion present"),
+            ClientAssertionError::InvalidAssertion(e) => {
+                write!(f, "invalid client assertion: {e}")
+            }
+            ClientAssertionError::MissingClaim(claim) => {
+                write!(f, "client assertion lacks required claim '{claim}'")
+            }
+            ClientAssertionError::ClientMismatch => {
+                write!(f, "client assertion identifies a different client")
+            }
+            ClientAssertionError::WrongAudience(audience) => {
+                write!(f, "client assertion is intended for audience '{audience}'")
+            }
+            ClientAssertionError::Expired => write!(f, "client assertion has expired"),
+            ClientAssertionError::NotYetValid => write!(f, "client assertion is not valid yet"),
+            ClientAssertionError::LifetimeTooLong => {
+                write!(f, "client assertion is valid for too long")
+            }
+            ClientAssertionError::Replayed => write!(f, "client assertion has already been used"),
+            ClientAssertionError::UnknownClient(client_id) => {
+                write!(f, "client '{client_id}' is not registered")
+            }
+            ClientAssertionError::MissingKey => write!(f, "client has no registered key"),
+            ClientAssertionError::Storage(e) => write!(f, "client storage failed: {e}"),
+        }
+    }
+}
+
+/// Error type used when a textual field of a message violates the
+/// [`TextLimits`](crate::common::text::TextLimits).
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[non_exhaustive]
+pub enum InvalidTextError {
+    /// The field is longer than allowed.
+    TooLong {
+        /// The name of the field.
+        field: &'static str,
+        /// The length of the field in bytes.
+        length: usize,
+        /// The maximum allowed length of the field in bytes.
+        max_length: usize,
+    },
+
+    /// The field contains a character which isn't allowed for its kind.
+    InvalidCharacter {
+        /// The name of the field.
+        field: &'static str,
+        /// The first character which isn't allowed.
+        character: char,
+    },
+}
+
+impl Display for InvalidTextError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            InvalidTextError::TooLong {
+                field,
+                length,
+                max_length,
+            } => write!(
+                f,
+                "{field} is {length} bytes long (at most {max_length} bytes are allowed)"
+            ),
+            InvalidTextError::InvalidCharacter { field, character } => {
+                write!(f, "{field} contains the invalid character {character:?}")
+            }
+        }
+    }
+}
+
+/// Error type used when a block-wise transfer of an access token fails, see
+/// [`endpoints::blockwise`](crate::endpoints::blockwise).
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[non_exhaustive]
+pub enum BlockwiseError {
+    /// The value of a block option is malformed or uses the reserved size exponent 7.
+    InvalidOption,
+
+    /// The given block size is invalid, i.e., not a power of two between 16 and 1024 bytes,
+    /// or a block contains the wrong amount of data.
+    ///
+    /// The invalid size is contained in this field.
+    InvalidBlockSize(usize),
+
+    /// The given block number is too large to be represented in a block option.
+    ///
+    /// The invalid block number is contained in this field.
+    InvalidBlockNumber(u32),
+
+    /// A block has been received out of order, e.g., because a previous one has been lost.
+    Incomplete {
+        /// The offset at which the next block was expected to start.
+        expected_offset: usize,
+        /// The offset at which the received block starts.
+        offset: usize,
+    },
+
+    /// The transferred token exceeds the maximum size accepted by the receiver.
+    TooLarge {
+        /// The maximum accepted token size in bytes.
+        max: usize,
+    },
+}
+
+impl Display for BlockwiseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            BlockwiseError::InvalidOption => write!(f, "invalid block option value"),
+            BlockwiseError::InvalidBlockSize(size) => write!(f, "invalid block size {size}"),
+            BlockwiseError::InvalidBlockNumber(num) => write!(f, "block number {num} too large"),
+            BlockwiseError::Incomplete {
+                expected_offset,
+                offset,
+            } => write!(
+                f,
+                "expected block at offset {expected_offset}, but got one at offset {offset}"
+            ),
+            BlockwiseError::TooLarge { max } => {
+                write!(f, "token exceeds maximum size of {max} bytes")
+            }
+        }
+    }
+}
+
+/// Error type used when a message or COSE structure is rejected by a
+/// [`SecurityConfig`](crate::common::security::SecurityConfig).
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[non_exhaustive]
+pub enum SecurityConfigError {
+    /// The message is larger than the maximum allowed size.
+    TooLarge {
+        /// The size of the message in bytes.
+        size: usize,
+        /// The maximum allowed size in bytes.
+        max_size: usize,
+    },
+
+    /// The message isn't encoded deterministically.
+    NonDeterministicEncoding,
+
+    /// The message couldn't be (de)serialized, e.g., because it contains duplicate keys.
+    ///
+    /// A message describing the problem is contained in this field.
+    InvalidMessage(String),
+
+    /// The structure is protected with an algorithm which isn't allowed.
+    ///
+    /// The algorithm given in its protected header, if any, is contained in this field.
+    DisallowedAlgorithm(Option<crate::common::cose::Algorithm>),
+
+    /// None of the allowed algorithms is supported by the cipher, as determined by
+    /// [`SecurityConfig::check_capabilities`](crate::common::security::SecurityConfig::check_capabilities).
+    NoSupportedAlgorithm,
+
+    /// The proof-of-possession key of an access token is too weak.
+    ///
+    /// Details are contained in this field.
+    WeakKey(KeyStrengthError),
+}
+
+impl Display for SecurityConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SecurityConfigError::TooLarge { size, max_size } => write!(
+                f,
+                "message is {size} bytes long (at most {max_size} bytes are allowed)"
+            ),
+            SecurityConfigError::NonDeterministicEncoding => {
+                write!(f, "message is not encoded deterministically")
+            }
+            SecurityConfigError::InvalidMessage(message) => {
+                write!(f, "invalid message: {message}")
+            }
+            SecurityConfigError::DisallowedAlgorithm(Some(algorithm)) => {
+                write!(f, "algorithm {algorithm:?} is not allowed")
+            }
+            SecurityConfigError::DisallowedAlgorithm(None) => {
+                write!(f, "no algorithm is specified in the protected header")
+            }
+            SecurityConfigError::NoSupportedAlgorithm => {
+                write!(
+                    f,
+                    "none of the allowed algorithms is supported by the cipher"
+                )
+            }
+            SecurityConfigError::WeakKey(e) => write!(f, "weak key: {e}"),
+        }
+    }
+}
+
+/// Error type used when a key can't be converted from or to a [`Jwk`](crate::common::jwk::Jwk).
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[non_exhaustive]
+pub enum JwkError {
+    /// The key type isn't supported.
+    ///
+    /// The unsupported key type is contained in this field.
+    UnsupportedKeyType(String),
+
+    /// The algorithm of the key has no counterpart in the other format.
+    ///
+    /// The unsupported algorithm is contained in this field.
+    UnsupportedAlgorithm(String),
+
+    /// The curve of the key has no counterpart in the other format.
+    ///
+    /// The unsupported curve is contained in this field.
+    UnsupportedCurve(String),
+
+    /// A parameter required for the key type is missing.
+    ///
+    /// The (JWK) name of the missing parameter is contained in this field.
+    MissingParameter(&'static str),
+
+    /// A member of the JWK isn't validly base64url-encoded.
+    ///
+    /// The name of the invalid member is contained in this field.
+    InvalidBase64(&'static str),
+
+    /// The proof-of-possession key only references a key (e.g., by its key ID) or contains an
+    /// encrypted one, so there's no key material to convert.
+    NoKeyMaterial,
+}
+
+impl Display for JwkError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            JwkError::UnsupportedKeyType(kty) => write!(f, "unsupported key type {kty}"),
+            JwkError::UnsupportedAlgorithm(alg) => write!(f, "unsupported algorithm {alg}"),
+            JwkError::UnsupportedCurve(crv) => write!(f, "unsupported curve {crv}"),
+            JwkError::MissingParameter(name) => write!(f, "key parameter '{name}' is missing"),
+            JwkError::InvalidBase64(name) => {
+                write!(f, "key parameter '{name}' is not valid base64url")
+            }
+            JwkError::NoKeyMaterial => write!(f, "no key material to convert"),
+        }
+    }
+}
+
+/// Error type used when an access token can't be parsed from an HTTP `Authorization` header.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[non_exhaustive]
+pub enum HttpTokenError {
+    /// The header uses another authentication scheme than expected, which is contained here.
+    UnexpectedScheme(String),
+
+    /// The header contains no token after the authentication scheme.
+    MissingToken,
+
+    /// The token isn't validly encoded.
+    InvalidEncoding,
+}
+
+impl Display for HttpTokenError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            HttpTokenError::UnexpectedScheme(scheme) => {
+                write!(f, "unexpected authentication scheme '{scheme}'")
+            }
+            HttpTokenError::MissingToken => write!(f, "authorization header contains no token"),
+            HttpTokenError::InvalidEncoding => write!(f, "token is not validly encoded"),
+        }
+    }
+}
+
+/// Error type used when a proof-of-possession key doesn't meet the requirements of a
+/// [`KeyStrengthPolicy`](crate::common::security::KeyStrengthPolicy).
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[non_exhaustive]
+pub enum KeyStrengthError {
+    /// The symmetric key is shorter than allowed.
+    SymmetricKeyTooShort {
+        /// The length of the key in bytes.
+        length: usize,
+        /// The minimum length of symmetric keys in bytes.
+        min_length: usize,
+    },
+
+    /// The elliptic curve key uses a curve which isn't allowed (or doesn't specify a curve,
+    /// in which case this field is `None`).
+    DisallowedCurve(Option<crate::common::cose::EllipticCurve>),
+
+    /// The confirmation structure containing the key couldn't be parsed.
+    InvalidConfirmation(ConfirmationError),
+}
+
+impl Display for KeyStrengthError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            KeyStrengthError::SymmetricKeyTooShort { length, min_length } => write!(
+                f,
+                "symmetric key has {length} bytes, but at least {min_length} bytes are required"
+            ),
+            KeyStrengthError::DisallowedCurve(Some(curve)) => {
+                write!(f, "elliptic curve {curve:?} is not allowed")
+            }
+            KeyStrengthError::DisallowedCurve(None) => {
+                write!(f, "elliptic curve key doesn't specify a curve")
+            }
+            KeyStrengthError::InvalidConfirmation(error) => {
+                write!(f, "invalid confirmation structure: {error}")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "as")]
+/// Error type used when the isolation of the [`Tenants`](crate::token::tenancy::Tenants) of an
+/// Authorization Server would be violated.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[non_exhaustive]
+pub enum TenancyError {
+    /// A tenant with the identifier contained in this field already exists.
+    DuplicateTenant(String),
+
+    /// The issuer is already used by another tenant.
+    DuplicateIssuer {
+        /// The issuer which is already in use.
+        issuer: String,
+        /// The tenant using the issuer.
+        tenant: String,
+    },
+
+    /// A key with the same key ID is already used by another tenant.
+    SharedKeyId {
+        /// The key ID which is already in use.
+        key_id: Vec<u8>,
+        /// The tenant using the key ID.
+        tenant: String,
+    },
+
+    /// A key without a key ID uses the same key material as a key of another tenant.
+    SharedKey {
+        /// The tenant using the key material.
+        tenant: String,
+    },
+
+    /// The tenant already uses a different key with the same key ID.
+    KeyIdCollision(KeyIdCollisionError),
+
+    /// The usage of the key can't be declared, e.g., because the tenant has already declared
+    /// another usage for it.
+    KeyUsage(KeyUsageError),
+
+    /// There is no tenant with the identifier contained in this field.
+    UnknownTenant(String),
+
+    /// The token has not been issued for the tenant it has been presented to.
+    ForeignIssuer {
+        /// The tenant the token has been presented to.
+        tenant: String,
+        /// The issuer of the token, if it has one.
+        issuer: Option<String>,
+    },
+}
+
+#[cfg(feature = "as")]
+impl From<KeyIdCollisionError> for TenancyError {
+    fn from(e: KeyIdCollisionError) -> Self {
+        TenancyError::KeyIdCollision(e)
+    }
+}
+
+#[cfg(feature = "as")]
+impl From<KeyUsageError> for TenancyError {
+    fn from(e: KeyUsageError) -> Self {
+        TenancyError::KeyUsage(e)
+    }
+}
+
+#[cfg(feature = "as")]
+impl Display for TenancyError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            TenancyError::DuplicateTenant(tenant) => write!(f, "tenant '{tenant}' already exists"),
+            TenancyError::DuplicateIssuer { issuer, tenant } => {
+                write!(f, "issuer '{issuer}' is already used by tenant '{tenant}'")
+            }
+            TenancyError::SharedKeyId { key_id, tenant } => {
+                write!(f, "key ID {key_id:?} is already used by tenant '{tenant}'")
+            }
+            TenancyError::SharedKey { tenant } => {
+                write!(f, "key is already used by tenant '{tenant}'")
+            }
+            TenancyError::KeyIdCollision(error) => write!(f, "{error}"),
+            TenancyError::KeyUsage(error) => write!(f, "{error}"),
+            TenancyError::UnknownTenant(tenant) => write!(f, "unknown tenant '{tenant}'"),
+            TenancyError::ForeignIssuer {
+                tenant,
+                issuer: Some(issuer),
+            } => write!(f, "issuer '{issuer}' doesn't belong to tenant '{tenant}'"),
+            TenancyError::ForeignIssuer {
+                tenant,
+                issuer: None,
+            } => write!(f, "token presented to tenant '{tenant}' has no issuer"),
+        }
+    }
+}
+
+/// The category of a [`ReasonCode`].
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+pub enum ReasonCategory {
+    /// A message, token, or value couldn't be decoded or is malformed.
+    Parse,
+
+    /// A well-formed request or token has been rejected by a policy.
+    Policy,
+
+    /// A cryptographic operation failed or couldn't be performed.
+    Crypto,
+}
+
+/// Stable, machine-readable code of the reason why something has been rejected, which can be
+/// obtained from this crate's errors using [`Rejection::reason_code`].
+///
+/// Each code has a stable [numeric representation](ReasonCode::code) (obtainable using
+/// [`From`]), so that failure causes can be aggregated across a fleet of devices without
+/// matching on error messages. Codes in the range `100..200` belong to the
+/// [`Parse`](ReasonCategory::Parse) category, codes in `200..300` to the
+/// [`Policy`](ReasonCategory::Policy) category, and codes in `300..400` to the
+/// [`Crypto`](ReasonCategory::Crypto) category. Numeric values are never reassigned.
+///
+/// # Example
+/// ```
+/// use dcaf::error::{DuplicateMapKeyError, ReasonCategory, ReasonCode, Rejection};
+///
+/// let error = DuplicateMapKeyError { key: 5 };
+/// assert_eq!(error.reason_code(), ReasonCode::DuplicateMapKey);
+/// assert_eq!(u16::from(error.reason_code()), 101);
+/// assert_eq!(ReasonCode::from(101), ReasonCode::DuplicateMapKey);
+/// assert_eq!(ReasonCode::DuplicateMapKey.category(), Some(ReasonCategory::Parse));
+/// ```
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+#[non_exhaustive]
+pub enum ReasonCode {
+    /// A message couldn't be decoded from CBOR or lacks required fields.
+    MalformedMessage,
+
+    /// A CBOR map contains the same key more than once.
+    DuplicateMapKey,
+
+    /// A scope is malformed or uses an unknown encoding.
+    InvalidScope,
+
+    /// A textual field is too long or contains disallowed characters.
+    InvalidText,
+
+    /// A request contains fields which are invalid for its grant type (or lacks required ones).
+    InvalidGrantRequest,
+
+    /// A confirmation (`cnf`) structure is malformed.
+    InvalidConfirmation,
+
+    /// An access token response doesn't match its request.
+    InvalidTokenResponse,
+
+    /// A token isn't a valid COSE structure of a supported type.
+    MalformedToken,
+
+    /// The client is unknown.
+    UnknownClient,
+
+    /// No audience has been requested, and there's no default one.
+    MissingAudience,
+
+    /// The audience is unknown or not allowed for the client.
+    AudienceNotAllowed,
+
+    /// No part of the requested scope is allowed.
+    ScopeNotAllowed,
+
+    /// No common profile could be selected.
+    NoCommonProfile,
+
+    /// A proof-of-possession key doesn't meet the key strength requirements.
+    WeakKey,
+
+    /// The renewal window of a token has passed.
+    RenewalWindowPassed,
+
+    /// The client may not exchange tokens.
+    ExchangeNotAllowed,
+
+    /// The subject token of a token exchange is invalid.
+    InvalidSubjectToken,
+
+    /// The actor token of a token exchange is invalid.
+    InvalidActorToken,
+
+    /// The grant has been denied by an approval hook.
+    ApprovalDenied,
+
+    /// A claim of a token is missing or invalid.
+    InvalidClaims,
+
+    /// A token has expired, is too old, or has been superseded.
+    TokenExpired,
+
+    /// A token is not valid yet.
+    TokenNotYetValid,
+
+    /// A nonce is unknown, has been reused, or doesn't match.
+    NonceMismatch,
+
+    /// A token's issuer or key ID isn't trusted.
+    UntrustedIssuer,
+
+    /// A request has been throttled.
+    Throttled,
+
+    /// A store has reached its capacity.
+    CapacityExceeded,
+
+    /// A signature or MAC tag couldn't be verified.
+    VerificationFailed,
+
+    /// A ciphertext couldn't be decrypted.
+    DecryptionFailed,
+
+    /// The required algorithm isn't supported.
+    UnsupportedAlgorithm,
+
+    /// The required key couldn't be found.
+    KeyNotFound,
+
+    /// A header which the cipher is supposed to set has already been set.
+    HeaderConflict,
+
+    /// The cryptographic backend failed for another reason.
+    CipherFailure,
+
+    /// A code unknown to this version of the crate, e.g., one received from a newer one.
+    Other(u16),
+}
+
+impl ReasonCode {
+    /// Returns the stable numeric representation of this reason code.
+    #[must_use]
+    pub fn code(self) -> u16 {
+        u16::from(self)
+    }
+
+    /// Returns the category of this reason code, or `None` if it's an unknown
+    /// [`Other`](ReasonCode::Other) code outside the ranges of the categories.
+    #[must_use]
+    pub fn category(self) -> Option<ReasonCategory> {
+        match self.code() {
+            100..=199 => Some(ReasonCategory::Parse),
+            200..=299 => Some(ReasonCategory::Policy),
+            300..=399 => Some(ReasonCategory::Crypto),
+            _ => None,
+        }
+    }
+}
+
+impl From<ReasonCode> for u16 {
+    fn from(reason: ReasonCode) -> Self {
+        match reason {
+            ReasonCode::MalformedMessage => 100,
+            ReasonCode::DuplicateMapKey => 101,
+            ReasonCode::InvalidScope => 102,
+            ReasonCode::InvalidText => 103,
+            ReasonCode::InvalidGrantRequest => 104,
+            ReasonCode::InvalidConfirmation => 105,
+            ReasonCode::InvalidTokenResponse => 106,
+            ReasonCode::MalformedToken => 107,
+            ReasonCode::UnknownClient => 200,
+            ReasonCode::MissingAudience => 201,
+            ReasonCode::AudienceNotAllowed => 202,
+            ReasonCode::ScopeNotAllowed => 203,
+            ReasonCode::NoCommonProfile => 204,
+            ReasonCode::WeakKey => 205,
+            ReasonCode::RenewalWindowPassed => 206,
+            ReasonCode::ExchangeNotAllowed => 207,
+            ReasonCode::InvalidSubjectToken => 208,
+            ReasonCode::ApprovalDenied => 209,
+            ReasonCode::InvalidClaims => 210,
+            ReasonCode::TokenExpired => 211,
+            ReasonCode::TokenNotYetValid => 212,
+            ReasonCode::NonceMismatch => 213,
+            ReasonCode::UntrustedIssuer => 214,
+            ReasonCode::Throttled => 215,
+            ReasonCode::CapacityExceeded => 216,
+            ReasonCode::InvalidActorToken => 217,
+            ReasonCode::VerificationFailed => 300,
+            ReasonCode::DecryptionFailed => 301,
+            ReasonCode::UnsupportedAlgorithm => 302,
+            ReasonCode::KeyNotFound => 303,
+            ReasonCode::HeaderConflict => 304,
+            ReasonCode::CipherFailure => 305,
+            ReasonCode::Other(code) => code,
+        }
+    }
+}
+
+impl From<u16> for ReasonCode {
+    fn from(code: u16) -> Self {
+        match code {
+            100 => ReasonCode::MalformedMessage,
+            101 => ReasonCode::DuplicateMapKey,
+            102 => ReasonCode::InvalidScope,
+            103 => ReasonCode::InvalidText,
+            104 => ReasonCode::InvalidGrantRequest,
+            105 => ReasonCode::InvalidConfirmation,
+            106 => ReasonCode::InvalidTokenResponse,
+            107 => ReasonCode::MalformedToken,
+            200 => ReasonCode::UnknownClient,
+            201 => ReasonCode::MissingAudience,
+            202 => ReasonCode::AudienceNotAllowed,
+            203 => ReasonCode::ScopeNotAllowed,
+            204 => ReasonCode::NoCommonProfile,
+            205 => ReasonCode::WeakKey,
+            206 => ReasonCode::RenewalWindowPassed,
+            207 => ReasonCode::ExchangeNotAllowed,
+            208 => ReasonCode::InvalidSubjectToken,
+            209 => ReasonCode::ApprovalDenied,
+            210 => ReasonCode::InvalidClaims,
+            211 => ReasonCode::TokenExpired,
+            212 => ReasonCode::TokenNotYetValid,
+            213 => ReasonCode::NonceMismatch,
+            214 => ReasonCode::UntrustedIssuer,
+            215 => ReasonCode::Throttled,
+            216 => ReasonCode::CapacityExceeded,
+            217 => ReasonCode::InvalidActorToken,
+            300 => ReasonCode::VerificationFailed,
+            301 => ReasonCode::DecryptionFailed,
+            302 => ReasonCode::UnsupportedAlgorithm,
+            303 => ReasonCode::KeyNotFound,
+            304 => ReasonCode::HeaderConflict,
+            305 => ReasonCode::CipherFailure,
+            code => ReasonCode::Other(code),
+        }
+    }
+}
+
+impl Display for ReasonCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
+
+/// An error which represents the rejection of a message, token, or operation, and which can
+/// hence be classified by a [`ReasonCode`].
+///
+/// This is implemented by all errors of this crate which reject inputs (as opposed to, e.g.,
+/// errors describing invalid configurations).
+pub trait Rejection {
+    /// Returns the stable code of the reason for this rejection.
+    fn reason_code(&self) -> ReasonCode;
+}
+
+impl Rejection for TryFromCborMapError {
+    fn reason_code(&self) -> ReasonCode {
+        ReasonCode::MalformedMessage
+    }
+}
+
+impl Rejection for ValueIsNotIntegerError {
+    fn reason_code(&self) -> ReasonCode {
+        ReasonCode::MalformedMessage
+    }
+}
+
+impl Rejection for DuplicateMapKeyError {
+    fn reason_code(&self) -> ReasonCode {
+        ReasonCode::DuplicateMapKey
+    }
+}
+
+impl Rejection for InvalidTextEncodedScopeError {
+    fn reason_code(&self) -> ReasonCode {
+        ReasonCode::InvalidScope
+    }
+}
+
+impl Rejection for InvalidBinaryEncodedScopeError {
+    fn reason_code(&self) -> ReasonCode {
+        ReasonCode::InvalidScope
+    }
+}
+
+impl Rejection for InvalidAifEncodedScopeError {
+    fn reason_code(&self) -> ReasonCode {
+        ReasonCode::InvalidScope
+    }
+}
+
+impl Rejection for ScopeFromValueError {
+    fn reason_code(&self) -> ReasonCode {
+        ReasonCode::InvalidScope
+    }
+}
+
+impl Rejection for CustomScopeError {
+    fn reason_code(&self) -> ReasonCode {
+        ReasonCode::InvalidScope
+    }
+}
+
+impl Rejection for ConfirmationError {
+    fn reason_code(&self) -> ReasonCode {
+        ReasonCode::InvalidConfirmation
+    }
+}
+
+impl Rejection for InvalidTextError {
+    fn reason_code(&self) -> ReasonCode {
+        ReasonCode::InvalidText
+    }
+}
+
+impl Rejection for InvalidGrantRequestError {
+    fn reason_code(&self) -> ReasonCode {
+        ReasonCode::InvalidGrantRequest
+    }
+}
+
+impl Rejection for InvalidTokenResponseError {
+    fn reason_code(&self) -> ReasonCode {
+        ReasonCode::InvalidTokenResponse
+    }
+}
+
+impl Rejection for NonceMismatchError {
+    fn reason_code(&self) -> ReasonCode {
+        ReasonCode::NonceMismatch
+    }
+}
+
+impl Rejection for KeyStrengthError {
+    fn reason_code(&self) -> ReasonCode {
+        ReasonCode::WeakKey
+    }
+}
+
+impl Rejection for SecurityConfigError {
+    fn reason_code(&self) -> ReasonCode {
+        match self {
+            SecurityConfigError::TooLarge { .. }
+            | SecurityConfigError::NonDeterministicEncoding
+            | SecurityConfigError::InvalidMessage(_) => ReasonCode::MalformedMessage,
+            SecurityConfigError::DisallowedAlgorithm(_)
+            | SecurityConfigError::NoSupportedAlgorithm => ReasonCode::UnsupportedAlgorithm,
+            SecurityConfigError::WeakKey(e) => e.reason_code(),
+        }
+    }
+}
+
+impl<T> Rejection for CoseCipherError<T>
+where
+    T: Display,
+{
+    fn reason_code(&self) -> ReasonCode {
+        match self {
+            CoseCipherError::HeaderAlreadySet { .. } => ReasonCode::HeaderConflict,
+            CoseCipherError::VerificationFailure => ReasonCode::VerificationFailed,
+            CoseCipherError::DecryptionFailure => ReasonCode::DecryptionFailed,
+            CoseCipherError::UnsupportedAlgorithm { .. } => ReasonCode::UnsupportedAlgorithm,
+            CoseCipherError::KeyNotFound { .. } => ReasonCode::KeyNotFound,
+            CoseCipherError::Other(_) => ReasonCode::CipherFailure,
+        }
+    }
+}
+
+impl<T> Rejection for AccessTokenError<T>
+where
+    T: Display,
+{
+    fn reason_code(&self) -> ReasonCode {
+        match self {
+            AccessTokenError::CoseError(_) | AccessTokenError::UnknownCoseStructure => {
+                ReasonCode::MalformedToken
+            }
+            AccessTokenError::CoseCipherError(e) => e.reason_code(),
+            AccessTokenError::SecurityConfig(e) => e.reason_code(),
+            #[cfg(feature = "rs")]
+            AccessTokenError::InvalidClaims(e) => e.reason_code(),
+        }
+    }
+}
+
+impl<T> Rejection for ProtectedMessageError<T>
+where
+    T: Display,
+{
+    fn reason_code(&self) -> ReasonCode {
+        match self {
+            ProtectedMessageError::Protection(e) => e.reason_code(),
+            ProtectedMessageError::InvalidPayload(e) => e.reason_code(),
+        }
+    }
+}
+
+impl<T> Rejection for KeyConfirmationError<T>
+where
+    T: Display,
+{
+    fn reason_code(&self) -> ReasonCode {
+        match self {
+            KeyConfirmationError::MissingConfirmationNonce
+            | KeyConfirmationError::NonceMismatch => ReasonCode::NonceMismatch,
+            KeyConfirmationError::MissingConfirmation => ReasonCode::MalformedMessage,
+            KeyConfirmationError::InvalidConfirmation(e) => e.reason_code(),
+        }
+    }
+}
+
+#[cfg(feature = "rs")]
+impl Rejection for ClaimsValidationError {
+    fn reason_code(&self) -> ReasonCode {
+        match self {
+            ClaimsValidationError::MissingClaim(_)
+            | ClaimsValidationError::InvalidClaim(_)
+            | ClaimsValidationError::WildcardAudience(_)
+            | ClaimsValidationError::UnexpectedAlgorithm { .. } => ReasonCode::InvalidClaims,
+            ClaimsValidationError::StaleIssuedAt { .. }
+            | ClaimsValidationError::Superseded { .. }
+            | ClaimsValidationError::Expired { .. }
+            | ClaimsValidationError::IssuedAtTooOld { .. } => ReasonCode::TokenExpired,
+            ClaimsValidationError::NotYetValid { .. } => ReasonCode::TokenNotYetValid,
+            ClaimsValidationError::AudienceMismatch(_) => ReasonCode::AudienceNotAllowed,
+            ClaimsValidationError::UnknownClientNonce(_) => ReasonCode::NonceMismatch,
+            ClaimsValidationError::WeakKey(_) => ReasonCode::WeakKey,
+        }
+    }
+}
+
+#[cfg(feature = "rs")]
+impl Rejection for TrustStoreError {
+    fn reason_code(&self) -> ReasonCode {
+        ReasonCode::UntrustedIssuer
+    }
+}
+
+#[cfg(feature = "rs")]
+impl Rejection for TokenStoreError {
+    fn reason_code(&self) -> ReasonCode {
+        ReasonCode::CapacityExceeded
+    }
+}
+
+#[cfg(feature = "as")]
+impl Rejection for RequestThrottledError {
+    fn reason_code(&self) -> ReasonCode {
+        ReasonCode::Throttled
+    }
+}
+
+#[cfg(feature = "as")]
+impl Rejection for IssuancePolicyError {
+    fn reason_code(&self) -> ReasonCode {
+        match self {
+            IssuancePolicyError::UnknownClient(_) => ReasonCode::UnknownClient,
+            IssuancePolicyError::MissingAudience => ReasonCode::MissingAudience,
+            IssuancePolicyError::UnknownAudience(_)
+            | IssuancePolicyError::AudienceNotAllowed(_) => ReasonCode::AudienceNotAllowed,
+            IssuancePolicyError::ScopeNotAllowed => ReasonCode::ScopeNotAllowed,
+            IssuancePolicyError::NoCommonProfile | IssuancePolicyError::UndeterminedProfile => {
+                ReasonCode::NoCommonProfile
+            }
+            IssuancePolicyError::WeakKey(_) => ReasonCode::WeakKey,
+            IssuancePolicyError::RenewalWindowPassed => ReasonCode::RenewalWindowPassed,
+            IssuancePolicyError::ExchangeNotAllowed => ReasonCode::ExchangeNotAllowed,
+            IssuancePolicyError::InvalidSubjectToken => ReasonCode::InvalidSubjectToken,
+            IssuancePolicyError::InvalidActorToken => ReasonCode::InvalidActorToken,
+            IssuancePolicyError::ApprovalDenied => ReasonCode::ApprovalDenied,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+mod std_error {
+    use core::fmt::Debug;
+    use std::error::Error;
+
+    #[cfg(feature = "oscore-profile")]
+    use crate::endpoints::authz_info::{
+        OscoreAuthzInfoRequestBuilderError, OscoreAuthzInfoResponseBuilderError,
+        OscoreInputMaterialBuilderError,
+    };
+    use crate::endpoints::creation_hint::AuthServerRequestCreationHintBuilderError;
+    #[cfg(feature = "introspection")]
+    use crate::endpoints::introspection::{
+        IntrospectionRequestBuilderError, IntrospectionResponseBuilderError,
+    };
+    use crate::endpoints::metadata::AuthServerMetadataBuilderError;
+    use crate::endpoints::token_req::grant::{
+        AuthorizationCodeRequestBuilderError, ClientCredentialsRequestBuilderError,
+        TokenExchangeRequestBuilderError,
+    };
+    use crate::endpoints::token_req::AccessTokenRequestBuilderError;
+    use crate::endpoints::token_req::AccessTokenResponseBuilderError;
+    use crate::endpoints::token_req::ErrorResponseBuilderError;
+
+    use super::*;
+
+    impl<T> Error for WrongSourceTypeError<T> where T: Debug {}
+
+    impl Error for TryFromCborMapError {}
+
+    impl Error for ConfirmationError {}
+
+    impl Error for ValueIsNotIntegerError {}
+
+    impl Error for DuplicateMapKeyError {}
+
+    impl Error for InvalidTextEncodedScopeError {}
+
+    impl Error for InvalidBinaryEncodedScopeError {}
+
+    impl Error for InvalidAifEncodedScopeError {}
+
+    impl Error for ScopeFromValueError {}
+
+    impl Error for CustomScopeError {}
+
+    impl<T> Error for CoseCipherError<T> where T: Debug + Display {}
+
+    impl<T> Error for AccessTokenError<T>
+    where
+        T: Debug + Display + 'static,
+    {
+        fn source(&self) -> Option<&(dyn Error + 'static)> {
+            match self {
+                AccessTokenError::CoseCipherError(e) => Some(e),
+                AccessTokenError::SecurityConfig(e) => Some(e),
+                #[cfg(feature = "rs")]
+                AccessTokenError::InvalidClaims(e) => Some(e),
+                _ => None,
+            }
+        }
+    }
+
+    impl Error for HeaderUpdateError {}
+
+    #[cfg(feature = "rs")]
+    impl Error for ClaimsValidationError {}
+
+    #[cfg(feature = "rs")]
+    impl Error for TokenStoreError {}
+
+    #[cfg(feature = "rs")]
+    impl Error for TrustStoreError {}
+
+    impl Error for KeyUsageError {}
+
+    impl Error for InvalidGrantRequestError {}
+
+    impl Error for InvalidTextError {}
+
+    #[cfg(feature = "as")]
+    impl Error for KeyIdCollisionError {}
+
+    #[cfg(feature = "as")]
+    impl Error for PartialIvError {}
+
+    #[cfg(feature = "as")]
+    impl Error for TenancyError {}
+
+    #[cfg(feature = "as")]
+    impl Error for RequestThrottledError {}
+
+    impl Error for NonceMismatchError {}
+
+    impl Error for KeyThumbprintError {}
+
+    impl Error for InvalidMetadataError {}
+
+    impl<T> Error for TokenCompressionError<T> where T: Debug + Display {}
+
+    impl<T> Error for CorpusExportError<T> where T: Debug + Display {}
+
+    impl Error for InvalidTokenResponseError {}
+
+    impl Error for BlockwiseError {}
+
+    impl Error for SecurityConfigError {}
+
+    impl Error for JwkError {}
+
+    impl Error for HttpTokenError {}
+
+    impl Error for KeyStrengthError {}
+
+    #[cfg(feature = "as")]
+    impl Error for IssuancePolicyError {}
+
+    #[cfg(feature = "as")]
+    impl Error for InvalidConfigError {}
+
+    #[cfg(feature = "as")]
+    impl<T> Error for TokenReferenceError<T> where T: Debug + Display {}
+
+    #[cfg(feature = "as")]
+    impl<C, S> Error for ClientAssertionError<C, S>
+    where
+        C: Debug + Display,
+        S: Debug + Display,
+    {
+    }
+
+    impl Error for PinnedHintMismatchError {}
+
+    impl<T> Error for ProtectedMessageError<T>
+    where
+        T: Debug + Display + 'static,
+    {
+        fn source(&self) -> Option<&(dyn Error + 'static)> {
+            match self {
+                ProtectedMessageError::Protection(e) => Some(e),
+                ProtectedMessageError::InvalidPayload(e) => Some(e),
+            }
+        }
+    }
+
+    impl<T> Error for KeyConfirmationError<T>
+    where
+        T: Debug + Display + 'static,
+    {
+        fn source(&self) -> Option<&(dyn Error + 'static)> {
+            match self {
+                KeyConfirmationError::InvalidConfirmation(e) => Some(e),
+                _ => None,
+            }
+        }
+    }
+
+    impl Error for AccessTokenRequestBuilderError {}
+
+    impl Error for AccessTokenResponseBuilderError {}
+
+    impl Error for ErrorResponseBuilderError {}
+
+    impl Error for AuthServerRequestCreationHintBuilderError {}
+
+    impl Error for ClientCredentialsRequestBuilderError {}
+
+    impl Error for AuthorizationCodeRequestBuilderError {}
+
+    impl Error for TokenExchangeRequestBuilderError {}
+
+    #[cfg(feature = "oscore-profile")]
+    impl Error for OscoreAuthzInfoRequestBuilderError {}
+
+    #[cfg(feature = "oscore-profile")]
+    impl Error for OscoreAuthzInfoResponseBuilderError {}
+
+    #[cfg(feature = "oscore-profile")]
+    impl Error for OscoreInputMaterialBuilderError {}
+
+    impl Error for AuthServerMetadataBuilderError {}
+
+    #[cfg(feature = "introspection")]
+    impl Error for IntrospectionRequestBuilderError {}
+
+    #[cfg(feature = "introspection")]
+    impl Error for IntrospectionResponseBuilderError {}
 }
@@ -197,6 +197,11 @@
 //! When implementing any of the specific COSE ciphers, you'll also need to implement the
 //! [`CoseCipherCommon`] trait, which can be used to set headers specific to your COSE cipher
 //! (e.g. the used algorithm).
+//!
+//! Ciphers which only need to support one direction (e.g., on a Resource Server which only
+//! verifies or decrypts tokens) can instead implement one of [`CoseSignCipher`],
+//! [`CoseVerifyCipher`], [`CoseEncryptCipher`], or [`CoseDecryptCipher`]. Of these, only
+//! [`CoseSignCipher`] and [`CoseEncryptCipher`] require [`CoseCipherCommon`].
 
 #![deny(rustdoc::broken_intra_doc_links, clippy::pedantic)]
 #![warn(missing_docs, rustdoc::missing_crate_level_docs)]
@@ -233,7 +238,8 @@ pub use endpoints::token_req::{
 #[doc(inline)]
 pub use token::{
     decrypt_access_token, encrypt_access_token, get_token_headers, sign_access_token,
-    verify_access_token, CoseCipherCommon, CoseEncrypt0Cipher, CoseMac0Cipher, CoseSign1Cipher,
+    verify_access_token, CoseCipherCommon, CoseDecryptCipher, CoseEncrypt0Cipher,
+    CoseEncryptCipher, CoseMac0Cipher, CoseSign1Cipher, CoseSignCipher, CoseVerifyCipher,
 };
 
 pub mod common;
@@ -0,0 +1,241 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+//! Contains a hook through which an operator UI or an external policy service can approve,
+//! deny, or modify grants before the Authorization Server issues a token.
+//!
+//! After the [`IssuancePolicy`] has decided on a request, [`IssuancePolicy::evaluate_with_approval`]
+//! passes the resulting [`IssuanceGrant`] to an [`ApprovalHook`], which returns an
+//! [`ApprovalDecision`]. Grants which can't be decided on right away (e.g., because a human has
+//! to confirm them first) are represented as a [`PendingApproval`], which the Authorization
+//! Server keeps until the decision is available and then [resolves](PendingApproval::resolve).
+//! In the meantime, it can tell the client to retry its request later.
+//!
+//! Modifications made by the hook can only narrow the grant down, so the policy can't be
+//! circumvented by a misbehaving hook.
+//!
+//! # Example
+//! ```
+//! # use dcaf::{AccessTokenRequest, Scope};
+//! use dcaf::token::approval::{Approval, ApprovalDecision};
+//! use dcaf::token::policy::{AudiencePolicy, ClientPolicy, IssuancePolicy, IssuanceGrant};
+//!
+//! # #[cfg(feature = "std")] {
+//! let mut policy = IssuancePolicy::default();
+//! policy.audiences.push(AudiencePolicy::new("door", 3600));
+//! policy.clients.push(ClientPolicy::new("visitor"));
+//! let request = AccessTokenRequest::builder()
+//!     .audience("door")
+//!     .scope(Scope::try_from(vec!["open"])?)
+//!     .build()?;
+//!
+//! // Opening the door needs to be confirmed by the operator first.
+//! let mut hook = |_client: &str, _request: &AccessTokenRequest, _grant: &IssuanceGrant| {
+//!     ApprovalDecision::Pending { ticket: vec![0x01], retry_after: Some(30) }
+//! };
+//! let pending = match policy.evaluate_with_approval("visitor", &request, &mut hook)? {
+//!     Approval::Pending(pending) => pending,
+//!     Approval::Granted(_) => unreachable!(),
+//! };
+//! assert_eq!(pending.ticket, vec![0x01]);
+//!
+//! // Later on, the operator confirms, but only for ten minutes.
+//! let mut shorter = pending.grant.clone();
+//! shorter.lifetime = 600;
+//! let grant = match pending.resolve(ApprovalDecision::Modify(shorter))? {
+//!     Approval::Granted(grant) => grant,
+//!     Approval::Pending(_) => unreachable!(),
+//! };
+//! assert_eq!(grant.lifetime, 600);
+//! # }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::common::cbor_values::ByteString;
+use crate::common::scope::CustomScopeRegistry;
+use crate::error::IssuancePolicyError;
+use crate::token::policy::{narrow_scope, IssuanceGrant, IssuancePolicy};
+use crate::AccessTokenRequest;
+
+#[cfg(not(feature = "std"))]
+use alloc::string::String;
+
+#[cfg(test)]
+mod tests;
+
+/// The decision of an [`ApprovalHook`] on a grant.
+#[derive(Debug, PartialEq, Clone)]
+pub enum ApprovalDecision {
+    /// The grant is approved as it is.
+    Approve,
+
+    /// The grant is approved with the modifications contained in this field.
+    ///
+    /// Only the [`scope`](IssuanceGrant::scope) and the [`lifetime`](IssuanceGrant::lifetime)
+    /// of the modified grant are taken into account, and only as far as they narrow the
+    /// original grant down. All other fields are kept from the original grant.
+    Modify(IssuanceGrant),
+
+    /// The grant is denied.
+    Deny,
+
+    /// The decision can't be made synchronously, e.g., because an operator has to confirm it.
+    Pending {
+        /// An opaque identifier of the pending decision, with which the Authorization Server
+        /// can look it up once the decision is available.
+        ticket: ByteString,
+
+        /// The number of seconds after which the client should retry its request, if known.
+        retry_after: Option<u32>,
+    },
+}
+
+/// A hook which approves, denies, or modifies the grants decided on by an [`IssuancePolicy`],
+/// see [`IssuancePolicy::evaluate_with_approval`].
+///
+/// This is implemented for all closures taking the same arguments as
+/// [`review`](ApprovalHook::review).
+///
+/// For an example, see the [module-level documentation](self).
+pub trait ApprovalHook {
+    /// Returns the decision on the given `grant`, which the [`IssuancePolicy`] has decided on
+    /// for the given `request` of the client identified by `client_id`.
+    fn review(
+        &mut self,
+        client_id: &str,
+        request: &AccessTokenRequest,
+        grant: &IssuanceGrant,
+    ) -> ApprovalDecision;
+}
+
+impl<F> ApprovalHook for F
+where
+    F: FnMut(&str, &AccessTokenRequest, &IssuanceGrant) -> ApprovalDecision,
+{
+    fn review(
+        &mut self,
+        client_id: &str,
+        request: &AccessTokenRequest,
+        grant: &IssuanceGrant,
+    ) -> ApprovalDecision {
+        self(client_id, request, grant)
+    }
+}
+
+/// The outcome of [`IssuancePolicy::evaluate_with_approval`] or [`PendingApproval::resolve`].
+#[derive(Debug, PartialEq, Clone)]
+pub enum Approval {
+    /// The token described by the contained grant may be issued.
+    Granted(IssuanceGrant),
+
+    /// The decision is still pending.
+    Pending(PendingApproval),
+}
+
+/// A grant whose approval is still pending, as returned by
+/// [`IssuancePolicy::evaluate_with_approval`].
+///
+/// For an example, see the [module-level documentation](self).
+#[derive(Debug, PartialEq, Clone)]
+pub struct PendingApproval {
+    /// The identifier of the client which sent the request.
+    pub client_id: String,
+
+    /// The grant awaiting approval, as decided on by the [`IssuancePolicy`].
+    pub grant: IssuanceGrant,
+
+    /// The opaque identifier of the pending decision, as returned by the [`ApprovalHook`].
+    pub ticket: ByteString,
+
+    /// The number of seconds after which the client should retry its request, if known.
+    pub retry_after: Option<u32>,
+}
+
+impl PendingApproval {
+    /// Applies the given `decision` (which has become available in the meantime) to the
+    /// pending grant.
+    ///
+    /// # Errors
+    /// - If the `decision` denies the grant ([`IssuancePolicyError::ApprovalDenied`]).
+    /// - If the `decision` modifies the grant such that no part of its scope is left
+    ///   ([`IssuancePolicyError::ScopeNotAllowed`]).
+    pub fn resolve(self, decision: ApprovalDecision) -> Result<Approval, IssuancePolicyError> {
+        apply_decision(self.client_id, self.grant, decision)
+    }
+}
+
+impl IssuancePolicy {
+    /// Decides on the given access token `request` like [`evaluate`](IssuancePolicy::evaluate),
+    /// but additionally lets the given `hook` approve, deny, or modify the resulting grant.
+    ///
+    /// The `hook` is only called if the policy allows the request. If it can't decide right
+    /// away, an [`Approval::Pending`] is returned.
+    ///
+    /// For an example, see the [module-level documentation](self).
+    ///
+    /// # Errors
+    /// - If the request is not allowed by this policy, as for
+    ///   [`evaluate`](IssuancePolicy::evaluate).
+    /// - If the `hook` denies the grant ([`IssuancePolicyError::ApprovalDenied`]).
+    /// - If the `hook` modifies the grant such that no part of its scope is left
+    ///   ([`IssuancePolicyError::ScopeNotAllowed`]).
+    pub fn evaluate_with_approval<H>(
+        &self,
+        client_id: &str,
+        request: &AccessTokenRequest,
+        hook: &mut H,
+    ) -> Result<Approval, IssuancePolicyError>
+    where
+        H: ApprovalHook,
+    {
+        let grant = self.evaluate(client_id, request)?;
+        let decision = hook.review(client_id, request, &grant);
+        apply_decision(client_id.into(), grant, decision)
+    }
+}
+
+/// Applies the given `decision` to the `grant` for the client identified by `client_id`.
+fn apply_decision(
+    client_id: String,
+    grant: IssuanceGrant,
+    decision: ApprovalDecision,
+) -> Result<Approval, IssuancePolicyError> {
+    match decision {
+        ApprovalDecision::Approve => Ok(Approval::Granted(grant)),
+        ApprovalDecision::Modify(modified) => {
+            // Removing the scope altogether would widen the grant, so the original one is kept.
+            let scope = match (modified.scope, grant.scope.clone()) {
+                (Some(narrowed), Some(permitted)) => Some(narrow_scope(
+                    &narrowed,
+                    &permitted,
+                    &CustomScopeRegistry::new(),
+                )?),
+                (Some(narrowed), None) => Some(narrowed),
+                (None, permitted) => permitted,
+            };
+            Ok(Approval::Granted(IssuanceGrant {
+                scope,
+                lifetime: modified.lifetime.min(grant.lifetime),
+                ..grant
+            }))
+        }
+        ApprovalDecision::Deny => Err(IssuancePolicyError::ApprovalDenied),
+        ApprovalDecision::Pending {
+            ticket,
+            retry_after,
+        } => Ok(Approval::Pending(PendingApproval {
+            client_id,
+            grant,
+            ticket,
+            retry_after,
+        })),
+    }
+}
@@ -0,0 +1,132 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+#[cfg(not(feature = "std"))]
+use {alloc::string::ToString, alloc::vec};
+
+use crate::endpoints::token_req::ErrorCode;
+use crate::token::policy::{AudiencePolicy, ClientPolicy};
+use crate::Scope;
+
+use super::*;
+
+fn example_policy() -> IssuancePolicy {
+    let mut client = ClientPolicy::new("visitor");
+    client.scope = Scope::try_from(vec!["open", "lock"]).ok();
+    IssuancePolicy {
+        clients: vec![client],
+        audiences: vec![AudiencePolicy::new("door", 3600)],
+        ..IssuancePolicy::default()
+    }
+}
+
+fn request() -> AccessTokenRequest {
+    AccessTokenRequest {
+        audience: Some("door".to_string()),
+        scope: Scope::try_from(vec!["open", "lock"]).ok(),
+        ..AccessTokenRequest::default()
+    }
+}
+
+fn evaluate(decision: &ApprovalDecision) -> Result<Approval, IssuancePolicyError> {
+    let mut hook = |_: &str, _: &AccessTokenRequest, _: &IssuanceGrant| decision.clone();
+    example_policy().evaluate_with_approval("visitor", &request(), &mut hook)
+}
+
+#[test]
+fn test_approve_and_deny() -> Result<(), IssuancePolicyError> {
+    let expected = example_policy().evaluate("visitor", &request())?;
+    assert_eq!(
+        evaluate(&ApprovalDecision::Approve)?,
+        Approval::Granted(expected)
+    );
+    let denied = evaluate(&ApprovalDecision::Deny);
+    assert_eq!(denied, Err(IssuancePolicyError::ApprovalDenied));
+    assert_eq!(
+        IssuancePolicyError::ApprovalDenied.error_code(),
+        ErrorCode::UnauthorizedClient
+    );
+    Ok(())
+}
+
+#[test]
+fn test_modify_only_narrows() -> Result<(), IssuancePolicyError> {
+    let original = example_policy().evaluate("visitor", &request())?;
+    let modified = IssuanceGrant {
+        audience: "other".to_string(),
+        scope: Scope::try_from(vec!["open", "admin"]).ok(),
+        lifetime: 7200,
+        ..original.clone()
+    };
+    let expected = IssuanceGrant {
+        scope: Scope::try_from(vec!["open"]).ok(),
+        ..original.clone()
+    };
+    assert_eq!(
+        evaluate(&ApprovalDecision::Modify(modified))?,
+        Approval::Granted(expected)
+    );
+
+    let without_scope = IssuanceGrant {
+        scope: None,
+        lifetime: 60,
+        ..original.clone()
+    };
+    let expected = IssuanceGrant {
+        lifetime: 60,
+        ..original.clone()
+    };
+    assert_eq!(
+        evaluate(&ApprovalDecision::Modify(without_scope))?,
+        Approval::Granted(expected)
+    );
+
+    let disjoint = IssuanceGrant {
+        scope: Scope::try_from(vec!["admin"]).ok(),
+        ..original
+    };
+    assert_eq!(
+        evaluate(&ApprovalDecision::Modify(disjoint)),
+        Err(IssuancePolicyError::ScopeNotAllowed)
+    );
+    Ok(())
+}
+
+#[test]
+fn test_pending() -> Result<(), IssuancePolicyError> {
+    let original = example_policy().evaluate("visitor", &request())?;
+    let pending = evaluate(&ApprovalDecision::Pending {
+        ticket: vec![0xDC, 0xAF],
+        retry_after: Some(30),
+    })?;
+    let expected = PendingApproval {
+        client_id: "visitor".to_string(),
+        grant: original.clone(),
+        ticket: vec![0xDC, 0xAF],
+        retry_after: Some(30),
+    };
+    assert_eq!(pending, Approval::Pending(expected.clone()));
+
+    let still_pending = expected.clone().resolve(ApprovalDecision::Pending {
+        ticket: vec![0xDC, 0xAF],
+        retry_after: None,
+    })?;
+    assert!(matches!(still_pending, Approval::Pending(x) if x.retry_after.is_none()));
+    assert_eq!(
+        expected.clone().resolve(ApprovalDecision::Approve)?,
+        Approval::Granted(original)
+    );
+    assert_eq!(
+        expected.resolve(ApprovalDecision::Deny),
+        Err(IssuancePolicyError::ApprovalDenied)
+    );
+    Ok(())
+}
@@ -0,0 +1,217 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+//! Contains functions for compressing access tokens, e.g., so that they fit into a single
+//! frame on constrained links such as IEEE 802.15.4 (whose frames carry at most 127 bytes).
+//!
+//! Similar to the ciphers used for the [token functions](crate::token), the actual compression
+//! algorithm (e.g., deflate) is provided by implementing the [`TokenCompressor`] trait.
+//! Each compressor is identified by its content coding (e.g., `deflate`), which both sides agree
+//! on using [`select_coding`] and which has to be transmitted along with the compressed token.
+//! [`compress_token`] only uses the compressor if this actually makes the token smaller, and
+//! reports the sizes involved in a [`TokenSizeReport`]. Since a small compressed token may
+//! expand to a huge one, [`decompress_token`] takes the maximum length of the decompressed token.
+//!
+//! # Example
+//! ```
+//! # use dcaf::error::TokenCompressionError;
+//! use dcaf::token::compression::{compress_token, decompress_token, select_coding, TokenCompressor};
+//!
+//! /// Replaces runs of zeroes by their length (which is **not** a real compression algorithm).
+//! struct ZeroRunCompressor;
+//!
+//! impl TokenCompressor for ZeroRunCompressor {
+//!     type Error = String;
+//!
+//!     fn coding(&self) -> &str {
+//!         "x-zero-run"
+//!     }
+//!
+//!     fn compress(&mut self, token: &[u8]) -> Vec<u8> {
+//!         // Only handles tokens consisting entirely of zeroes, for brevity.
+//!         vec![token.len() as u8]
+//!     }
+//!
+//!     fn decompress(&mut self, compressed: &[u8], max_len: usize) -> Result<Vec<u8>, String> {
+//!         let len = usize::from(compressed[0]);
+//!         if len > max_len {
+//!             return Err(format!("token would have {len} bytes"));
+//!         }
+//!         Ok(vec![0; len])
+//!     }
+//! }
+//!
+//! // The recipient announced which codings it accepts.
+//! let coding = select_coding(&["x-zero-run", "deflate"], &[ZeroRunCompressor.coding()]);
+//! assert_eq!(coding, "x-zero-run");
+//! let compressed = compress_token(&[0; 200], &mut ZeroRunCompressor);
+//! assert_eq!(compressed.coding, "x-zero-run");
+//! assert!(compressed.report.fits(127));
+//! assert!(!compressed.report.fits_uncompressed(127));
+//! let token = decompress_token(&compressed.data, &compressed.coding, &mut ZeroRunCompressor, 1024)?;
+//! assert_eq!(token, vec![0; 200]);
+//! // Tokens decompressing to more than the given maximum length are rejected.
+//! assert!(decompress_token(&compressed.data, &compressed.coding, &mut ZeroRunCompressor, 100).is_err());
+//! # Ok::<(), TokenCompressionError<String>>(())
+//! ```
+
+use crate::common::cbor_values::ByteString;
+use crate::error::TokenCompressionError;
+
+#[cfg(not(feature = "std"))]
+use {
+    alloc::string::{String, ToString},
+    alloc::vec::Vec,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The content coding of tokens which haven't been compressed.
+pub const IDENTITY: &str = "identity";
+
+/// Provides a compression algorithm for access tokens, identified by its content coding.
+///
+/// For an example, see the [module-level documentation](self).
+pub trait TokenCompressor {
+    /// Error type returned when decompression fails.
+    type Error;
+
+    /// Returns the name of the content coding implemented by this compressor, e.g., `deflate`.
+    fn coding(&self) -> &str;
+
+    /// Compresses the given `token`.
+    fn compress(&mut self, token: &[u8]) -> Vec<u8>;
+
+    /// Decompresses the given `compressed` token, which may be at most `max_len` bytes long
+    /// after decompression.
+    ///
+    /// Implementations should stop decompressing as soon as the output exceeds `max_len`, so that
+    /// a small malicious input can't exhaust the recipient's memory. [`decompress_token`] rejects
+    /// longer results in any case.
+    ///
+    /// # Errors
+    /// If `compressed` is not a valid compressed token or would exceed `max_len` bytes.
+    fn decompress(&mut self, compressed: &[u8], max_len: usize) -> Result<Vec<u8>, Self::Error>;
+}
+
+/// Sizes of an access token before and after compression.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+pub struct TokenSizeReport {
+    /// Size of the token in bytes before compression.
+    pub uncompressed: usize,
+
+    /// Size of the token in bytes as it will be transmitted.
+    pub compressed: usize,
+}
+
+impl TokenSizeReport {
+    /// Returns whether the token as transmitted fits into the given `budget` (in bytes).
+    #[must_use]
+    pub fn fits(&self, budget: usize) -> bool {
+        self.compressed <= budget
+    }
+
+    /// Returns whether the token would have fit into the given `budget` (in bytes)
+    /// even without compression.
+    #[must_use]
+    pub fn fits_uncompressed(&self, budget: usize) -> bool {
+        self.uncompressed <= budget
+    }
+
+    /// Returns how many bytes have been saved by compression.
+    #[must_use]
+    pub fn saved(&self) -> usize {
+        self.uncompressed.saturating_sub(self.compressed)
+    }
+}
+
+/// An access token as transmitted, along with the content coding it has been compressed with.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct CompressedToken {
+    /// The content coding of [`data`](CompressedToken::data), which is [`IDENTITY`] if the
+    /// token hasn't been compressed.
+    pub coding: String,
+
+    /// The (possibly compressed) token.
+    pub data: ByteString,
+
+    /// The sizes of the token before and after compression.
+    pub report: TokenSizeReport,
+}
+
+/// Returns the first of the `accepted` content codings (in order of the recipient's preference)
+/// for which a compressor is `available`, or [`IDENTITY`] if there is none.
+#[must_use]
+pub fn select_coding<'a>(accepted: &[&str], available: &[&'a str]) -> &'a str {
+    accepted
+        .iter()
+        .find_map(|coding| available.iter().find(|x| *x == coding).copied())
+        .unwrap_or(IDENTITY)
+}
+
+/// Compresses the given `token` using the given `compressor`.
+///
+/// If compression doesn't make the token smaller, the uncompressed token is used instead,
+/// with its [`coding`](CompressedToken::coding) set to [`IDENTITY`].
+pub fn compress_token<C>(token: &[u8], compressor: &mut C) -> CompressedToken
+where
+    C: TokenCompressor,
+{
+    let compressed = compressor.compress(token);
+    let (coding, data) = if compressed.len() < token.len() {
+        (compressor.coding().to_string(), compressed)
+    } else {
+        (IDENTITY.to_string(), token.to_vec())
+    };
+    CompressedToken {
+        coding,
+        report: TokenSizeReport {
+            uncompressed: token.len(),
+            compressed: data.len(),
+        },
+        data,
+    }
+}
+
+/// Decompresses the given `data`, which has been compressed with the given content `coding`,
+/// using the given `compressor`, into a token of at most `max_len` bytes.
+///
+/// # Errors
+/// - If `coding` is neither [`IDENTITY`] nor the coding of the `compressor`.
+/// - If the `compressor` fails to decompress the `data`.
+/// - If the decompressed token is longer than `max_len` bytes.
+pub fn decompress_token<C>(
+    data: &[u8],
+    coding: &str,
+    compressor: &mut C,
+    max_len: usize,
+) -> Result<ByteString, TokenCompressionError<C::Error>>
+where
+    C: TokenCompressor,
+{
+    let token = if coding == IDENTITY {
+        data.to_vec()
+    } else if coding == compressor.coding() {
+        compressor
+            .decompress(data, max_len)
+            .map_err(TokenCompressionError::Backend)?
+    } else {
+        return Err(TokenCompressionError::UnsupportedCoding(coding.to_string()));
+    };
+    if token.len() > max_len {
+        return Err(TokenCompressionError::TooLarge {
+            length: token.len(),
+            max_len,
+        });
+    }
+    Ok(token)
+}
@@ -0,0 +1,137 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+#[cfg(not(feature = "std"))]
+use alloc::{string::String, vec};
+
+use super::*;
+
+/// Run-length encodes tokens as pairs of (count, byte), which is only useful for testing.
+struct RunLengthCompressor;
+
+impl TokenCompressor for RunLengthCompressor {
+    type Error = String;
+
+    fn coding(&self) -> &'static str {
+        "x-rle"
+    }
+
+    fn compress(&mut self, token: &[u8]) -> Vec<u8> {
+        let mut result: Vec<u8> = Vec::new();
+        for byte in token {
+            match result.len() {
+                len if len >= 2 && result[len - 1] == *byte && result[len - 2] < u8::MAX => {
+                    result[len - 2] += 1;
+                }
+                _ => result.extend_from_slice(&[1, *byte]),
+            }
+        }
+        result
+    }
+
+    fn decompress(&mut self, compressed: &[u8], max_len: usize) -> Result<Vec<u8>, String> {
+        if compressed.len() % 2 != 0 {
+            return Err(String::from("odd length"));
+        }
+        let mut result = Vec::new();
+        for pair in compressed.chunks(2) {
+            if result.len() + usize::from(pair[0]) > max_len {
+                return Err(String::from("too long"));
+            }
+            result.extend(vec![pair[1]; pair[0].into()]);
+        }
+        Ok(result)
+    }
+}
+
+#[test]
+fn test_select_coding() {
+    assert_eq!(select_coding(&["deflate", "x-rle"], &["x-rle"]), "x-rle");
+    assert_eq!(
+        select_coding(&["deflate", "x-rle"], &["x-rle", "deflate"]),
+        "deflate"
+    );
+    assert_eq!(select_coding(&["deflate"], &["x-rle"]), IDENTITY);
+    assert_eq!(select_coding(&[], &["x-rle"]), IDENTITY);
+}
+
+#[test]
+fn test_compress_roundtrip() -> Result<(), TokenCompressionError<String>> {
+    let token = [0xDC; 150];
+    let compressed = compress_token(&token, &mut RunLengthCompressor);
+    assert_eq!(compressed.coding, "x-rle");
+    assert_eq!(
+        compressed.report,
+        TokenSizeReport {
+            uncompressed: 150,
+            compressed: 2
+        }
+    );
+    assert_eq!(compressed.report.saved(), 148);
+    assert!(compressed.report.fits(127));
+    assert!(!compressed.report.fits_uncompressed(127));
+    assert_eq!(
+        decompress_token(
+            &compressed.data,
+            &compressed.coding,
+            &mut RunLengthCompressor,
+            150
+        )?,
+        token.to_vec()
+    );
+    Ok(())
+}
+
+#[test]
+fn test_compress_incompressible() -> Result<(), TokenCompressionError<String>> {
+    let token = [0xDC, 0xAF];
+    let compressed = compress_token(&token, &mut RunLengthCompressor);
+    assert_eq!(compressed.coding, IDENTITY);
+    assert_eq!(compressed.data, token.to_vec());
+    assert_eq!(compressed.report.saved(), 0);
+    assert_eq!(
+        decompress_token(&compressed.data, IDENTITY, &mut RunLengthCompressor, 2)?,
+        token.to_vec()
+    );
+    Ok(())
+}
+
+#[test]
+fn test_decompress_errors() {
+    assert_eq!(
+        decompress_token(&[1], "deflate", &mut RunLengthCompressor, 100),
+        Err(TokenCompressionError::UnsupportedCoding(String::from(
+            "deflate"
+        )))
+    );
+    assert_eq!(
+        decompress_token(&[1], "x-rle", &mut RunLengthCompressor, 100),
+        Err(TokenCompressionError::Backend(String::from("odd length")))
+    );
+    // A few bytes may expand to a large token, which the compressor refuses to produce.
+    assert_eq!(
+        decompress_token(
+            &[0xFF, 0].repeat(4),
+            "x-rle",
+            &mut RunLengthCompressor,
+            1000
+        ),
+        Err(TokenCompressionError::Backend(String::from("too long")))
+    );
+    // Results exceeding the maximum length are rejected even if the compressor ignores it.
+    assert_eq!(
+        decompress_token(&[0xDC; 4], IDENTITY, &mut RunLengthCompressor, 3),
+        Err(TokenCompressionError::TooLarge {
+            length: 4,
+            max_len: 3
+        })
+    );
+}
@@ -0,0 +1,226 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+//! Contains functions for signing and encrypting access tokens with detached content.
+//!
+//! [Section 2 of RFC 8152](https://www.rfc-editor.org/rfc/rfc8152#section-2) allows the
+//! payload of a [`CoseSign1`] (or the ciphertext of a [`CoseEncrypt0`]) structure to be
+//! transported separately from the structure itself, in which case it's replaced by `nil`.
+//! This is useful when the token body is transported e.g. in a different CoAP option or
+//! block than its protection structure.
+//!
+//! The functions in this module behave like their counterparts in the [parent module](super),
+//! except that the detached content is returned (or passed in) separately as part of a
+//! [`DetachedToken`].
+//!
+//! # Example
+//! ```
+//! # use coset::cwt::ClaimsSetBuilder;
+//! # use coset::Header;
+//! # use dcaf::{CoseCipherCommon, CoseSign1Cipher};
+//! # use dcaf::error::{AccessTokenError, CoseCipherError};
+//! use dcaf::token::detached::{sign_access_token_detached, verify_access_token_detached};
+//! # struct FakeSigner {}
+//! # impl CoseCipherCommon for FakeSigner {
+//! #     type Error = String;
+//! #     fn header(&self, unprotected_header: &mut Header, protected_header: &mut Header) -> Result<(), CoseCipherError<Self::Error>> {
+//! #         Ok(())
+//! #     }
+//! # }
+//! # impl CoseSign1Cipher for FakeSigner {
+//! #     fn generate_signature(&mut self, target: &[u8]) -> Vec<u8> {
+//! #         target.to_vec()
+//! #     }
+//! #     fn verify_signature(&mut self, signature: &[u8], signed_data: &[u8]) -> Result<(), CoseCipherError<Self::Error>> {
+//! #         if signature == signed_data { Ok(()) } else { Err(CoseCipherError::VerificationFailure) }
+//! #     }
+//! # }
+//! # let mut cipher = FakeSigner {};
+//! let claims = ClaimsSetBuilder::new().audience("valve242".to_string()).build();
+//! let detached = sign_access_token_detached(claims, &mut cipher, None, None, None)?;
+//! // `detached.token` and `detached.content` can now be transported separately.
+//! assert!(verify_access_token_detached(&detached.token, &detached.content, &mut cipher, None).is_ok());
+//! # Ok::<(), AccessTokenError<String>>(())
+//! ```
+
+use coset::cwt::ClaimsSet;
+use coset::{
+    CborSerializable, CoseEncrypt0, CoseEncrypt0Builder, CoseError, CoseSign1, CoseSign1Builder,
+    Header,
+};
+
+use crate::common::cbor_values::ByteString;
+use crate::error::AccessTokenError;
+use crate::token::{
+    prepare_headers, CoseDecryptCipher, CoseEncryptCipher, CoseSignCipher, CoseVerifyCipher,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// An access token whose protected content has been detached from its COSE structure.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct DetachedToken {
+    /// The serialized COSE structure, containing `nil` in place of the content.
+    pub token: ByteString,
+
+    /// The detached content, i.e., the payload of a [`CoseSign1`] structure or the
+    /// ciphertext of a [`CoseEncrypt0`] structure.
+    pub content: ByteString,
+}
+
+/// Signs the given `claims` with the given headers and `aad` using `cipher` for cryptography,
+/// returning the [`CoseSign1`] structure with its payload detached.
+///
+/// Behaves like [`sign_access_token`](crate::sign_access_token), except that the serialized
+/// `claims` are returned in the [`content`](DetachedToken::content) of the result instead of
+/// being contained in the token.
+///
+/// # Errors
+/// - When there's a [`CoseError`] while serializing the given `claims` to CBOR.
+/// - When there's a [`CoseError`] while serializing the [`CoseSign1`] structure.
+pub fn sign_access_token_detached<T>(
+    claims: ClaimsSet,
+    cipher: &mut T,
+    aad: Option<&[u8]>,
+    unprotected_header: Option<Header>,
+    protected_header: Option<Header>,
+) -> Result<DetachedToken, AccessTokenError<T::Error>>
+where
+    T: CoseSignCipher,
+{
+    let (unprotected, protected) = prepare_headers(unprotected_header, protected_header, cipher)?;
+    let mut sign = CoseSign1Builder::new()
+        .unprotected(unprotected)
+        .protected(protected)
+        .payload(claims.to_vec().map_err(AccessTokenError::from_cose_error)?)
+        .create_signature(aad.unwrap_or(&[0; 0]), |x| cipher.generate_signature(x))
+        .build();
+    let content = sign.payload.take().unwrap_or_default();
+    Ok(DetachedToken {
+        token: sign.to_vec().map_err(AccessTokenError::from_cose_error)?,
+        content,
+    })
+}
+
+/// Verifies the given `token` with its detached `payload` and `aad` using `cipher` for
+/// cryptography, returning an error in case it could not be verified.
+///
+/// Behaves like [`verify_access_token`](crate::verify_access_token), except that the payload
+/// is passed in separately.
+///
+/// # Errors
+/// - When there's a [`CoseError`] while deserializing the given `token`
+///   to a [`CoseSign1`] structure
+///   (e.g., if it's not in fact a [`CoseSign1`] structure but rather something else).
+/// - When the `token` doesn't have a detached payload, i.e., its payload is not `nil`.
+/// - When there's a verification error coming from the `cipher`
+///   (e.g., if the `payload` does not match the `token`'s signature).
+pub fn verify_access_token_detached<T>(
+    token: &ByteString,
+    payload: &[u8],
+    cipher: &mut T,
+    aad: Option<&[u8]>,
+) -> Result<(), AccessTokenError<T::Error>>
+where
+    T: CoseVerifyCipher,
+{
+    let mut sign =
+        CoseSign1::from_slice(token.as_slice()).map_err(AccessTokenError::from_cose_error)?;
+    if sign.payload.is_some() {
+        return Err(AccessTokenError::from_cose_error(expected_nil()));
+    }
+    sign.payload = Some(payload.to_vec());
+    sign.verify_signature(aad.unwrap_or(&[0; 0]), |signature, signed_data| {
+        cipher.verify_signature(signature, signed_data)
+    })
+    .map_err(AccessTokenError::from_cose_cipher_error)
+}
+
+/// Encrypts the given `claims` with the given headers and `aad` using `cipher` for cryptography,
+/// returning the [`CoseEncrypt0`] structure with its ciphertext detached.
+///
+/// Behaves like [`encrypt_access_token`](crate::encrypt_access_token), except that the
+/// ciphertext is returned in the [`content`](DetachedToken::content) of the result instead of
+/// being contained in the token.
+///
+/// # Errors
+/// - When there's a [`CoseError`] while serializing the given `claims` to CBOR.
+/// - When there's a [`CoseError`] while serializing the [`CoseEncrypt0`] structure.
+pub fn encrypt_access_token_detached<T>(
+    claims: ClaimsSet,
+    cipher: &mut T,
+    aad: Option<&[u8]>,
+    unprotected_header: Option<Header>,
+    protected_header: Option<Header>,
+) -> Result<DetachedToken, AccessTokenError<T::Error>>
+where
+    T: CoseEncryptCipher,
+{
+    let (unprotected, protected) = prepare_headers(unprotected_header, protected_header, cipher)?;
+    let mut encrypt = CoseEncrypt0Builder::new()
+        .unprotected(unprotected)
+        .protected(protected)
+        .create_ciphertext(
+            &claims.to_vec().map_err(AccessTokenError::from_cose_error)?[..],
+            aad.unwrap_or(&[0; 0]),
+            |payload, aad| cipher.encrypt(payload, aad),
+        )
+        .build();
+    let content = encrypt.ciphertext.take().unwrap_or_default();
+    Ok(DetachedToken {
+        token: encrypt
+            .to_vec()
+            .map_err(AccessTokenError::from_cose_error)?,
+        content,
+    })
+}
+
+/// Decrypts the given `token` with its detached `ciphertext` and `aad` using `cipher` for
+/// cryptography, returning the decrypted `ClaimsSet`.
+///
+/// Behaves like [`decrypt_access_token`](crate::decrypt_access_token), except that the
+/// ciphertext is passed in separately.
+///
+/// # Errors
+/// - When there's a [`CoseError`] while deserializing
+///   the given `token` to a [`CoseEncrypt0`] structure
+///   (e.g., if it's not in fact a [`CoseEncrypt0`] structure but rather something else).
+/// - When the `token` doesn't have a detached ciphertext, i.e., its ciphertext is not `nil`.
+/// - When there's a decryption error coming from the `cipher`.
+/// - When the decrypted `ciphertext` does not contain a valid [`ClaimsSet`].
+pub fn decrypt_access_token_detached<T>(
+    token: &ByteString,
+    ciphertext: &[u8],
+    cipher: &mut T,
+    aad: Option<&[u8]>,
+) -> Result<ClaimsSet, AccessTokenError<T::Error>>
+where
+    T: CoseDecryptCipher,
+{
+    let mut encrypt =
+        CoseEncrypt0::from_slice(token.as_slice()).map_err(AccessTokenError::from_cose_error)?;
+    if encrypt.ciphertext.is_some() {
+        return Err(AccessTokenError::from_cose_error(expected_nil()));
+    }
+    encrypt.ciphertext = Some(ciphertext.to_vec());
+    let result = encrypt
+        .decrypt(aad.unwrap_or(&[0; 0]), |ciphertext, aad| {
+            cipher.decrypt(ciphertext, aad)
+        })
+        .map_err(AccessTokenError::from_cose_cipher_error)?;
+    ClaimsSet::from_slice(result.as_slice()).map_err(AccessTokenError::from_cose_error)
+}
+
+/// Returns the error used when a token which should have detached content contains it instead.
+fn expected_nil() -> CoseError {
+    CoseError::UnexpectedItem("bstr", "nil")
+}
@@ -0,0 +1,102 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+use coset::cwt::ClaimsSetBuilder;
+
+#[cfg(not(feature = "std"))]
+use {alloc::string::String, alloc::string::ToString, alloc::vec};
+
+use crate::common::test_helper::FakeCrypto;
+use crate::{encrypt_access_token, sign_access_token};
+
+use super::*;
+
+fn example_claims() -> ClaimsSet {
+    ClaimsSetBuilder::new()
+        .audience("valve242".to_string())
+        .issuer("coaps://as.example.com".to_string())
+        .build()
+}
+
+#[test]
+fn test_sign_verify_detached() -> Result<(), AccessTokenError<String>> {
+    let aad = vec![0x01, 0x02];
+    let detached =
+        sign_access_token_detached(example_claims(), &mut FakeCrypto {}, Some(&aad), None, None)?;
+    let sign = CoseSign1::from_slice(&detached.token).map_err(AccessTokenError::from_cose_error)?;
+    assert_eq!(sign.payload, None);
+    assert_eq!(
+        ClaimsSet::from_slice(&detached.content).map_err(AccessTokenError::from_cose_error)?,
+        example_claims()
+    );
+    verify_access_token_detached(
+        &detached.token,
+        &detached.content,
+        &mut FakeCrypto {},
+        Some(&aad),
+    )?;
+
+    let mut tampered = detached.content.clone();
+    tampered.push(0x00);
+    assert!(verify_access_token_detached(
+        &detached.token,
+        &tampered,
+        &mut FakeCrypto {},
+        Some(&aad)
+    )
+    .is_err());
+
+    // Tokens with an attached payload must not be accepted as detached ones.
+    let attached = sign_access_token(example_claims(), &mut FakeCrypto {}, None, None, None)?;
+    assert!(matches!(
+        verify_access_token_detached(&attached, &detached.content, &mut FakeCrypto {}, None),
+        Err(AccessTokenError::CoseError(_))
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_encrypt_decrypt_detached() -> Result<(), AccessTokenError<String>> {
+    let aad = vec![0x01, 0x02];
+    let detached = encrypt_access_token_detached(
+        example_claims(),
+        &mut FakeCrypto {},
+        Some(&aad),
+        None,
+        None,
+    )?;
+    let encrypt =
+        CoseEncrypt0::from_slice(&detached.token).map_err(AccessTokenError::from_cose_error)?;
+    assert_eq!(encrypt.ciphertext, None);
+    assert_eq!(
+        decrypt_access_token_detached(
+            &detached.token,
+            &detached.content,
+            &mut FakeCrypto {},
+            Some(&aad)
+        )?,
+        example_claims()
+    );
+    assert!(decrypt_access_token_detached(
+        &detached.token,
+        &detached.content,
+        &mut FakeCrypto {},
+        None
+    )
+    .is_err());
+
+    let attached = encrypt_access_token(example_claims(), &mut FakeCrypto {}, None, None, None)?;
+    assert!(matches!(
+        decrypt_access_token_detached(&attached, &detached.content, &mut FakeCrypto {}, None),
+        Err(AccessTokenError::CoseError(_))
+    ));
+    Ok(())
+}
@@ -0,0 +1,267 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+//! Contains the [`TokenFormat`] trait, which abstracts over the wire format of access tokens.
+//!
+//! Code which issues or validates tokens can be written against [`TokenFormat`] instead of calling
+//! [`sign_access_token`], [`encrypt_access_token`] and so on directly, so that the format used
+//! for an audience becomes a matter of configuration. This module provides implementations for
+//! CWTs protected by a `COSE_Sign1` ([`CoseSign1Format`]), `COSE_Encrypt0` ([`CoseEncrypt0Format`]),
+//! or `COSE_Mac0` ([`CoseMac0Format`]) structure, as well as for opaque token references
+//! (`TokenReferenceFormat`, which requires the `as` feature).
+//! Other formats (e.g., JWTs) can be supported by implementing [`TokenFormat`] for them.
+//!
+//! # Example
+//! ```
+//! # use coset::cwt::ClaimsSetBuilder;
+//! # use coset::Header;
+//! # use dcaf::{CoseCipherCommon, CoseSign1Cipher};
+//! # use dcaf::error::{AccessTokenError, CoseCipherError};
+//! use dcaf::token::format::{CoseSign1Format, TokenFormat};
+//! # struct FakeSigner {}
+//! # impl CoseCipherCommon for FakeSigner {
+//! #     type Error = String;
+//! #     fn header(&self, unprotected_header: &mut Header, protected_header: &mut Header) -> Result<(), CoseCipherError<Self::Error>> {
+//! #         Ok(())
+//! #     }
+//! # }
+//! # impl CoseSign1Cipher for FakeSigner {
+//! #     fn generate_signature(&mut self, target: &[u8]) -> Vec<u8> {
+//! #         target.to_vec()
+//! #     }
+//! #     fn verify_signature(&mut self, signature: &[u8], signed_data: &[u8]) -> Result<(), CoseCipherError<Self::Error>> {
+//! #         if signature == signed_data { Ok(()) } else { Err(CoseCipherError::VerificationFailure) }
+//! #     }
+//! # }
+//!
+//! /// Issues a token for the given audience in whatever format has been configured.
+//! fn issue<F: TokenFormat>(format: &mut F, audience: &str) -> Result<Vec<u8>, F::Error> {
+//!     format.encode(ClaimsSetBuilder::new().audience(audience.to_string()).build())
+//! }
+//!
+//! let mut format = CoseSign1Format::new(FakeSigner {});
+//! let token = issue(&mut format, "valve242")?;
+//! assert_eq!(format.decode(&token)?.audience, Some("valve242".to_string()));
+//! # Ok::<(), AccessTokenError<String>>(())
+//! ```
+
+use coset::cwt::ClaimsSet;
+use coset::{CborSerializable, CoseError, CoseMac0, CoseMac0Builder, CoseSign1};
+
+use crate::common::cbor_values::ByteString;
+use crate::error::AccessTokenError;
+#[cfg(feature = "as")]
+use crate::error::TokenReferenceError;
+#[cfg(feature = "as")]
+use crate::token::issuance::TokenReferenceIssuance;
+#[cfg(feature = "as")]
+use crate::token::storage::TokenReferenceStore;
+use crate::token::{
+    prepare_headers, CoseDecryptCipher, CoseEncryptCipher, CoseMac0Cipher, CoseSignCipher,
+    CoseVerifyCipher,
+};
+use crate::{decrypt_access_token, encrypt_access_token, sign_access_token};
+
+#[cfg(test)]
+mod tests;
+
+/// A wire format of access tokens, which turns claims into tokens and back.
+///
+/// For an example, see the [module-level documentation](self).
+pub trait TokenFormat {
+    /// The error type returned when encoding or decoding fails.
+    type Error;
+
+    /// Encodes the given `claims` into an access token of this format,
+    /// e.g., by signing or encrypting them.
+    ///
+    /// # Errors
+    /// If the `claims` can't be encoded, e.g., because a cryptographic operation failed.
+    fn encode(&mut self, claims: ClaimsSet) -> Result<ByteString, Self::Error>;
+
+    /// Decodes the given access `token` of this format into its claims, verifying or decrypting
+    /// it if the format requires it.
+    ///
+    /// # Errors
+    /// If the `token` isn't a valid token of this format, e.g., because its signature is invalid.
+    fn decode(&mut self, token: &[u8]) -> Result<ClaimsSet, Self::Error>;
+}
+
+/// CWTs protected by a `COSE_Sign1` structure, using [`sign_access_token`] for encoding.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct CoseSign1Format<T> {
+    /// The cipher used to sign and verify tokens.
+    pub cipher: T,
+
+    /// The external additional authenticated data to use, if any.
+    pub aad: Option<ByteString>,
+}
+
+/// CWTs protected by a `COSE_Encrypt0` structure, using [`encrypt_access_token`] for encoding
+/// and [`decrypt_access_token`] for decoding.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct CoseEncrypt0Format<T> {
+    /// The cipher used to encrypt and decrypt tokens.
+    pub cipher: T,
+
+    /// The external additional authenticated data to use, if any.
+    pub aad: Option<ByteString>,
+}
+
+/// CWTs protected by a `COSE_Mac0` structure.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct CoseMac0Format<T> {
+    /// The cipher used to create and verify MAC tags.
+    pub cipher: T,
+
+    /// The external additional authenticated data to use, if any.
+    pub aad: Option<ByteString>,
+}
+
+#[cfg(feature = "as")]
+/// Opaque token references, whose claims are kept in a [`TokenReferenceStore`] by the
+/// Authorization Server (see [`TokenReferenceIssuance`]).
+///
+/// Decoding a reference looks up its claims in the store, which is only possible on the
+/// Authorization Server itself, e.g., when processing an introspection request.
+pub struct TokenReferenceFormat<S, F> {
+    /// Determines the length of issued references.
+    pub issuance: TokenReferenceIssuance,
+
+    /// The store in which the claims of issued references are kept.
+    pub store: S,
+
+    /// Fills in the bytes of new references, which should use a cryptographically secure
+    /// random number generator.
+    pub generate: F,
+}
+
+macro_rules! cose_format_constructors {
+    ($format:ident) => {
+        impl<T> $format<T> {
+            /// Creates a new instance of this format using the given `cipher` without any
+            /// external additional authenticated data.
+            #[must_use]
+            pub fn new(cipher: T) -> $format<T> {
+                $format { cipher, aad: None }
+            }
+
+            /// Sets the external additional authenticated data to the given `aad`.
+            #[must_use]
+            pub fn with_aad(mut self, aad: ByteString) -> $format<T> {
+                self.aad = Some(aad);
+                self
+            }
+        }
+    };
+}
+
+cose_format_constructors!(CoseSign1Format);
+cose_format_constructors!(CoseEncrypt0Format);
+cose_format_constructors!(CoseMac0Format);
+
+/// Returns the claims contained in the given (verified) `payload` of a COSE structure.
+fn decode_claims<E>(payload: Option<ByteString>) -> Result<ClaimsSet, AccessTokenError<E>>
+where
+    E: core::fmt::Display,
+{
+    let payload = payload.ok_or(AccessTokenError::CoseError(CoseError::UnexpectedItem(
+        "nil", "bstr",
+    )))?;
+    ClaimsSet::from_slice(&payload).map_err(AccessTokenError::from_cose_error)
+}
+
+impl<T> TokenFormat for CoseSign1Format<T>
+where
+    T: CoseSignCipher + CoseVerifyCipher<Error = <T as crate::CoseCipherCommon>::Error>,
+{
+    type Error = AccessTokenError<<T as CoseVerifyCipher>::Error>;
+
+    fn encode(&mut self, claims: ClaimsSet) -> Result<ByteString, Self::Error> {
+        sign_access_token(claims, &mut self.cipher, self.aad.as_deref(), None, None)
+    }
+
+    fn decode(&mut self, token: &[u8]) -> Result<ClaimsSet, Self::Error> {
+        let sign = CoseSign1::from_slice(token).map_err(AccessTokenError::from_cose_error)?;
+        sign.verify_signature(self.aad.as_deref().unwrap_or(&[0; 0]), |signature, data| {
+            self.cipher.verify_signature(signature, data)
+        })
+        .map_err(AccessTokenError::from_cose_cipher_error)?;
+        decode_claims(sign.payload)
+    }
+}
+
+impl<T> TokenFormat for CoseEncrypt0Format<T>
+where
+    T: CoseEncryptCipher + CoseDecryptCipher<Error = <T as crate::CoseCipherCommon>::Error>,
+{
+    type Error = AccessTokenError<<T as CoseDecryptCipher>::Error>;
+
+    fn encode(&mut self, claims: ClaimsSet) -> Result<ByteString, Self::Error> {
+        encrypt_access_token(claims, &mut self.cipher, self.aad.as_deref(), None, None)
+    }
+
+    fn decode(&mut self, token: &[u8]) -> Result<ClaimsSet, Self::Error> {
+        decrypt_access_token(&token.to_vec(), &mut self.cipher, self.aad.as_deref())
+    }
+}
+
+impl<T> TokenFormat for CoseMac0Format<T>
+where
+    T: CoseMac0Cipher,
+{
+    type Error = AccessTokenError<T::Error>;
+
+    fn encode(&mut self, claims: ClaimsSet) -> Result<ByteString, Self::Error> {
+        let (unprotected, protected) = prepare_headers(None, None, &self.cipher)?;
+        let cipher = &mut self.cipher;
+        CoseMac0Builder::new()
+            .unprotected(unprotected)
+            .protected(protected)
+            .payload(claims.to_vec().map_err(AccessTokenError::from_cose_error)?)
+            .create_tag(self.aad.as_deref().unwrap_or(&[0; 0]), |x| {
+                cipher.generate_tag(x)
+            })
+            .build()
+            .to_vec()
+            .map_err(AccessTokenError::from_cose_error)
+    }
+
+    fn decode(&mut self, token: &[u8]) -> Result<ClaimsSet, Self::Error> {
+        let mac = CoseMac0::from_slice(token).map_err(AccessTokenError::from_cose_error)?;
+        mac.verify_tag(self.aad.as_deref().unwrap_or(&[0; 0]), |tag, data| {
+            self.cipher.verify_tag(tag, data)
+        })
+        .map_err(AccessTokenError::from_cose_cipher_error)?;
+        decode_claims(mac.payload)
+    }
+}
+
+#[cfg(feature = "as")]
+impl<S, F> TokenFormat for TokenReferenceFormat<S, F>
+where
+    S: TokenReferenceStore,
+    F: FnMut(&mut [u8]),
+{
+    type Error = TokenReferenceError<S::Error>;
+
+    fn encode(&mut self, claims: ClaimsSet) -> Result<ByteString, Self::Error> {
+        self.issuance
+            .issue(claims, &mut self.store, &mut self.generate)
+    }
+
+    fn decode(&mut self, token: &[u8]) -> Result<ClaimsSet, Self::Error> {
+        self.store
+            .resolve_token_reference(token)
+            .map_err(TokenReferenceError::Storage)?
+            .ok_or(TokenReferenceError::UnknownReference)
+    }
+}
@@ -0,0 +1,90 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+use core::fmt::Debug;
+
+use coset::cwt::ClaimsSetBuilder;
+
+#[cfg(not(feature = "std"))]
+use {alloc::string::String, alloc::string::ToString, alloc::vec};
+
+use crate::common::test_helper::FakeCrypto;
+
+use super::*;
+
+fn example_claims() -> ClaimsSet {
+    ClaimsSetBuilder::new()
+        .audience("valve242".to_string())
+        .issuer("coaps://as.example.com".to_string())
+        .build()
+}
+
+/// Checks that the given `format` can decode the tokens it encodes, and that tampered tokens
+/// are rejected.
+fn check_roundtrip<F>(mut format: F) -> Result<(), F::Error>
+where
+    F: TokenFormat,
+    F::Error: Debug,
+{
+    let token = format.encode(example_claims())?;
+    assert_eq!(format.decode(&token)?, example_claims());
+    let mut tampered = token;
+    if let Some(last) = tampered.last_mut() {
+        *last ^= 0xFF;
+    }
+    assert!(format.decode(&tampered).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_cose_formats() -> Result<(), AccessTokenError<String>> {
+    check_roundtrip(CoseSign1Format::new(FakeCrypto {}))?;
+    check_roundtrip(CoseEncrypt0Format::new(FakeCrypto {}).with_aad(vec![0xDC, 0xAF]))?;
+    check_roundtrip(CoseMac0Format::new(FakeCrypto {}).with_aad(vec![0xDC, 0xAF]))?;
+
+    // Each format only accepts its own tokens.
+    let token = CoseSign1Format::new(FakeCrypto {}).encode(example_claims())?;
+    assert!(CoseMac0Format::new(FakeCrypto {}).decode(&token).is_err());
+    assert!(CoseEncrypt0Format::new(FakeCrypto {})
+        .decode(&token)
+        .is_err());
+    // The AAD must match.
+    let token = CoseMac0Format::new(FakeCrypto {})
+        .with_aad(vec![0xDC])
+        .encode(example_claims())?;
+    assert!(CoseMac0Format::new(FakeCrypto {}).decode(&token).is_err());
+    Ok(())
+}
+
+#[cfg(feature = "as")]
+#[test]
+fn test_token_reference_format() -> Result<(), TokenReferenceError<core::convert::Infallible>> {
+    use crate::token::storage::InMemoryStorage;
+
+    let mut counter = 0;
+    let mut format = TokenReferenceFormat {
+        issuance: TokenReferenceIssuance::new(vec!["valve242"]),
+        store: InMemoryStorage::default(),
+        generate: |x: &mut [u8]| {
+            counter += 1;
+            x.fill(counter);
+        },
+    };
+    let first = format.encode(example_claims())?;
+    let second = format.encode(example_claims())?;
+    assert_eq!((first.clone(), second), (vec![1; 16], vec![2; 16]));
+    assert_eq!(format.decode(&first)?, example_claims());
+    assert_eq!(
+        format.decode(&[3; 16]),
+        Err(TokenReferenceError::UnknownReference)
+    );
+    Ok(())
+}
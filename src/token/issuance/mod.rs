@@ -0,0 +1,360 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+//! Contains helpers for the Authorization Server to prepare the claims of access tokens
+//! before they are [signed](crate::sign_access_token) or [encrypted](crate::encrypt_access_token).
+//!
+//! # Clock-less Resource Servers
+//! Some Resource Servers don't have a synchronized clock and hence can't make use of the
+//! absolute `exp` claim. For these, [section 5.10.3 of RFC 9200](https://www.rfc-editor.org/rfc/rfc9200#section-5.10.3)
+//! specifies the `exi` claim, which gives the lifetime of the token in seconds, relative to
+//! the time the Resource Server first received it.
+//! [`ClockLessIssuance`] takes care of this for all audiences flagged as clock-less.
+//!
+//! # Audience Aliases
+//! When Resource Servers are renamed, clients may still request tokens using their old name.
+//! [`AudienceAliases`] maps such aliases to the canonical audience before tokens are issued.
+//!
+//! ## Example
+//! ```
+//! # use coset::cwt::{ClaimsSetBuilder, Timestamp};
+//! # use coset::iana::CwtClaimName;
+//! # use coset::RegisteredLabelWithPrivate;
+//! use dcaf::token::issuance::ClockLessIssuance;
+//!
+//! let mut issuance = ClockLessIssuance::new(vec!["valve242"]);
+//! let claims = ClaimsSetBuilder::new()
+//!     .audience("valve242".to_string())
+//!     .expiration_time(Timestamp::WholeSeconds(1_700_003_600))
+//!     .build();
+//! let claims = issuance.apply(claims, 3600, 1_700_000_000);
+//! assert_eq!(claims.expiration_time, None);
+//! assert_eq!(claims.issued_at, Some(Timestamp::WholeSeconds(1_700_000_000)));
+//! assert!(claims.rest.iter().any(|(name, _)| name == &RegisteredLabelWithPrivate::Assigned(CwtClaimName::Exi)));
+//! ```
+//!
+//! # Token References
+//! For audiences where the size of the token on the wire is critical and the Resource Server can
+//! reach the Authorization Server, [`TokenReferenceIssuance`] issues short opaque token
+//! references instead of self-contained tokens. The claims are kept in a
+//! [`TokenReferenceStore`] and handed out to the Resource Server through introspection
+//! (see [section 5.9 of RFC 9200](https://www.rfc-editor.org/rfc/rfc9200#section-5.9)).
+
+use ciborium::value::Value;
+use coset::cwt::{ClaimName, ClaimsSet, Timestamp};
+use coset::iana::CwtClaimName;
+use serde::{Deserialize, Serialize};
+
+use crate::common::cbor_values::ByteString;
+use crate::error::TokenReferenceError;
+use crate::token::storage::TokenReferenceStore;
+use crate::AccessTokenRequest;
+
+#[cfg(not(feature = "std"))]
+use {
+    alloc::string::{String, ToString},
+    alloc::vec,
+    alloc::vec::Vec,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Adapts the claims of access tokens issued for Resource Servers without a synchronized clock,
+/// as described in [section 5.10.3 of RFC 9200](https://www.rfc-editor.org/rfc/rfc9200#section-5.10.3).
+///
+/// For audiences flagged as clock-less, the `exp` claim is replaced by an `exi` claim, and the
+/// `iat` claim is set to a value which is strictly higher than that of all previously issued
+/// tokens, so that the Resource Server can recognize stale tokens even after a reboot
+/// (see `IssuedAtWatermark` in the `validation` module).
+///
+/// Note that the Authorization Server should persist [`last_issued_at`](ClockLessIssuance::last_issued_at)
+/// and pass it to [`resume_from`](ClockLessIssuance::resume_from) after a restart, as otherwise
+/// the `iat` values could go backwards in case its clock does. As this type implements
+/// [`Serialize`] and [`Deserialize`], it can also be persisted as a whole.
+///
+/// For an example, see the [module-level documentation](self).
+#[derive(Debug, PartialEq, Eq, Clone, Hash, Default, Serialize, Deserialize)]
+pub struct ClockLessIssuance {
+    /// Audiences which are flagged as clock-less.
+    audiences: Vec<String>,
+
+    /// The `iat` value of the most recently issued token for a clock-less audience.
+    last_issued_at: Option<i64>,
+}
+
+impl ClockLessIssuance {
+    /// Creates a new [`ClockLessIssuance`] in which the given `audiences` are flagged as clock-less.
+    #[must_use]
+    pub fn new<T, S>(audiences: T) -> ClockLessIssuance
+    where
+        T: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        ClockLessIssuance {
+            audiences: audiences.into_iter().map(Into::into).collect(),
+            last_issued_at: None,
+        }
+    }
+
+    /// Continues the sequence of `iat` values from the given `last_issued_at`, e.g., as
+    /// persisted from [`last_issued_at`](ClockLessIssuance::last_issued_at) before a restart.
+    #[must_use]
+    pub fn resume_from(mut self, last_issued_at: i64) -> ClockLessIssuance {
+        self.last_issued_at = Some(last_issued_at);
+        self
+    }
+
+    /// Returns the `iat` value of the most recently issued token for a clock-less audience,
+    /// or `None` if no such token has been issued yet.
+    #[must_use]
+    pub fn last_issued_at(&self) -> Option<i64> {
+        self.last_issued_at
+    }
+
+    /// Returns whether the given `audience` is flagged as clock-less.
+    #[must_use]
+    pub fn is_clock_less(&self, audience: &str) -> bool {
+        self.audiences.iter().any(|x| x == audience)
+    }
+
+    /// Adapts the given `claims` for a clock-less audience, using `exi` as the lifetime of the
+    /// token in seconds and `now` as the current time in seconds since the UNIX epoch.
+    ///
+    /// If the audience of the `claims` is not flagged as clock-less (or there is none),
+    /// the `claims` are returned unchanged. Otherwise:
+    /// - The `exp` claim is removed.
+    /// - The `exi` claim is set to the given `exi` value, replacing any existing one.
+    /// - The `iat` claim is set to `now`, or to one second after the previously issued token
+    ///   if `now` would not be strictly higher than that.
+    #[must_use]
+    pub fn apply(&mut self, mut claims: ClaimsSet, exi: u32, now: i64) -> ClaimsSet {
+        if !matches!(claims.audience.as_deref(), Some(aud) if self.is_clock_less(aud)) {
+            return claims;
+        }
+        let exi_name = ClaimName::Assigned(CwtClaimName::Exi);
+        claims.expiration_time = None;
+        claims.rest.retain(|(name, _)| name != &exi_name);
+        claims.rest.push((exi_name, Value::from(exi)));
+        let issued_at = self
+            .last_issued_at
+            .map_or(now, |last| now.max(last.saturating_add(1)));
+        self.last_issued_at = Some(issued_at);
+        claims.issued_at = Some(Timestamp::WholeSeconds(issued_at));
+        claims
+    }
+}
+
+/// Maps audience aliases (e.g., a device's serial number) to their canonical audience
+/// (e.g., the device's logical name), so that tokens keep being issued for the same audience
+/// after a Resource Server has been renamed.
+///
+/// Aliases should be applied to the incoming [`AccessTokenRequest`]
+/// using [`normalize_request`](AudienceAliases::normalize_request) before it is validated, and to
+/// the claims of the token using [`normalize_claims`](AudienceAliases::normalize_claims) before
+/// any other processing (such as [`ClockLessIssuance::apply`]).
+///
+/// # Example
+/// ```
+/// # use dcaf::AccessTokenRequest;
+/// # use dcaf::endpoints::token_req::AccessTokenRequestBuilderError;
+/// use dcaf::token::issuance::AudienceAliases;
+///
+/// let mut aliases = AudienceAliases::default();
+/// aliases.insert("SN-4711", "valve242");
+/// let mut request = AccessTokenRequest::builder().client_id("client").audience("SN-4711").build()?;
+/// aliases.normalize_request(&mut request);
+/// assert_eq!(request.audience.as_deref(), Some("valve242"));
+/// assert_eq!(aliases.normalize("valve243"), "valve243");
+/// # Ok::<(), AccessTokenRequestBuilderError>(())
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Hash, Default, Serialize, Deserialize)]
+pub struct AudienceAliases {
+    /// Pairs of aliases and the canonical audience they refer to.
+    aliases: Vec<(String, String)>,
+}
+
+impl AudienceAliases {
+    /// Registers `alias` as an alias of the `canonical` audience.
+    ///
+    /// If `canonical` is itself an alias, the new alias refers to its canonical audience instead,
+    /// and existing aliases of `alias` are redirected to the canonical audience as well, so that
+    /// aliases never need to be resolved more than once.
+    /// An alias which refers to itself is ignored.
+    pub fn insert<A, C>(&mut self, alias: A, canonical: C)
+    where
+        A: Into<String>,
+        C: Into<String>,
+    {
+        let alias = alias.into();
+        let canonical = self.normalize(&canonical.into()).to_string();
+        if alias == canonical {
+            return;
+        }
+        self.aliases.retain(|(existing, _)| existing != &alias);
+        for (_, target) in &mut self.aliases {
+            if target == &alias {
+                target.clone_from(&canonical);
+            }
+        }
+        self.aliases.push((alias, canonical));
+    }
+
+    /// Returns the canonical audience for the given `audience`, which is the `audience` itself
+    /// if it's not an alias.
+    #[must_use]
+    pub fn normalize<'a>(&'a self, audience: &'a str) -> &'a str {
+        self.aliases
+            .iter()
+            .find(|(alias, _)| alias == audience)
+            .map_or(audience, |(_, canonical)| canonical.as_str())
+    }
+
+    /// Replaces the audience of the given `request` by its canonical audience, if it's an alias.
+    pub fn normalize_request(&self, request: &mut AccessTokenRequest) {
+        if let Some(audience) = request.audience.as_mut() {
+            *audience = self.normalize(audience).to_string();
+        }
+    }
+
+    /// Replaces the `aud` claim of the given `claims` by its canonical audience,
+    /// if it's an alias.
+    #[must_use]
+    pub fn normalize_claims(&self, mut claims: ClaimsSet) -> ClaimsSet {
+        if let Some(audience) = claims.audience.as_mut() {
+            *audience = self.normalize(audience).to_string();
+        }
+        claims
+    }
+}
+
+/// Issues opaque token references (random handles whose claims are kept by the Authorization
+/// Server) instead of self-contained tokens for the audiences flagged as using them.
+///
+/// References are only useful if the Resource Server can introspect them at the Authorization
+/// Server, which then looks up the claims using
+/// [`resolve_token_reference`](TokenReferenceStore::resolve_token_reference).
+/// As references need to be unguessable, they're generated by a caller-supplied function, which
+/// should use a cryptographically secure random number generator, and consist of at least
+/// [`MIN_REFERENCE_LENGTH`](TokenReferenceIssuance::MIN_REFERENCE_LENGTH) bytes.
+///
+/// # Example
+/// ```
+/// # use coset::cwt::ClaimsSetBuilder;
+/// # use dcaf::error::TokenReferenceError;
+/// use dcaf::token::issuance::TokenReferenceIssuance;
+/// use dcaf::token::storage::{InMemoryStorage, TokenReferenceStore};
+///
+/// let issuance = TokenReferenceIssuance::new(vec!["valve242"]);
+/// let mut storage = InMemoryStorage::default();
+/// let claims = ClaimsSetBuilder::new().audience("valve242".to_string()).build();
+/// assert!(issuance.uses_references("valve242"));
+/// // A real AS would use a proper random number generator here.
+/// let reference = issuance.issue(claims.clone(), &mut storage, |x| x.fill(0xDC))?;
+/// assert_eq!(reference, vec![0xDC; 16]);
+/// assert_eq!(storage.resolve_token_reference(&reference).map_err(TokenReferenceError::Storage)?, Some(claims));
+/// # Ok::<(), TokenReferenceError<core::convert::Infallible>>(())
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Hash, Serialize, Deserialize)]
+pub struct TokenReferenceIssuance {
+    /// Audiences for which token references are issued.
+    audiences: Vec<String>,
+
+    /// The length of issued token references in bytes.
+    reference_length: usize,
+}
+
+impl TokenReferenceIssuance {
+    /// The default length of token references in bytes, which is long enough to make them
+    /// unguessable while still being considerably shorter than any self-contained token.
+    pub const DEFAULT_REFERENCE_LENGTH: usize = 16;
+
+    /// The minimum length of token references in bytes (i.e., 128 bits), below which
+    /// [`issue`](TokenReferenceIssuance::issue) refuses to issue references, as they could be
+    /// guessed by brute force.
+    pub const MIN_REFERENCE_LENGTH: usize = 16;
+
+    /// Creates a new [`TokenReferenceIssuance`] which issues token references of
+    /// [`DEFAULT_REFERENCE_LENGTH`](TokenReferenceIssuance::DEFAULT_REFERENCE_LENGTH) bytes
+    /// for the given `audiences`.
+    #[must_use]
+    pub fn new<T, S>(audiences: T) -> TokenReferenceIssuance
+    where
+        T: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        TokenReferenceIssuance {
+            audiences: audiences.into_iter().map(Into::into).collect(),
+            reference_length: Self::DEFAULT_REFERENCE_LENGTH,
+        }
+    }
+
+    /// Sets the length of issued token references to the given number of bytes, which must be
+    /// at least [`MIN_REFERENCE_LENGTH`](TokenReferenceIssuance::MIN_REFERENCE_LENGTH),
+    /// as [`issue`](TokenReferenceIssuance::issue) fails otherwise.
+    #[must_use]
+    pub fn with_reference_length(mut self, reference_length: usize) -> TokenReferenceIssuance {
+        self.reference_length = reference_length;
+        self
+    }
+
+    /// Returns whether token references are issued for the given `audience`.
+    /// Tokens for all other audiences should be issued as self-contained tokens, as usual.
+    #[must_use]
+    pub fn uses_references(&self, audience: &str) -> bool {
+        self.audiences.iter().any(|x| x == audience)
+    }
+
+    /// Issues a new token reference for the given `claims`, storing them in the given `store`
+    /// and returning the reference, which is then used as the `access_token` of the response.
+    ///
+    /// The bytes of the reference are filled in by the given `generate` function.
+    /// A reference which is already in use is never replaced, as that would hand the claims
+    /// of one token out to the holder of another.
+    ///
+    /// # Errors
+    /// - If the configured reference length is shorter than
+    ///   [`MIN_REFERENCE_LENGTH`](TokenReferenceIssuance::MIN_REFERENCE_LENGTH)
+    ///   ([`TokenReferenceError::ReferenceTooShort`]).
+    /// - If the generated reference is already stored in the `store`, which indicates a broken
+    ///   random number generator ([`TokenReferenceError::DuplicateReference`]).
+    /// - If the `store` fails to look up or store the reference
+    ///   ([`TokenReferenceError::Storage`]).
+    pub fn issue<S, F>(
+        &self,
+        claims: ClaimsSet,
+        store: &mut S,
+        generate: F,
+    ) -> Result<ByteString, TokenReferenceError<S::Error>>
+    where
+        S: TokenReferenceStore,
+        F: FnOnce(&mut [u8]),
+    {
+        if self.reference_length < Self::MIN_REFERENCE_LENGTH {
+            return Err(TokenReferenceError::ReferenceTooShort {
+                length: self.reference_length,
+            });
+        }
+        let mut reference = vec![0; self.reference_length];
+        generate(&mut reference);
+        if store
+            .resolve_token_reference(&reference)
+            .map_err(TokenReferenceError::Storage)?
+            .is_some()
+        {
+            return Err(TokenReferenceError::DuplicateReference);
+        }
+        store
+            .store_token_reference(reference.clone(), claims)
+            .map_err(TokenReferenceError::Storage)?;
+        Ok(reference)
+    }
+}
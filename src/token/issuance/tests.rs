@@ -0,0 +1,144 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+use core::convert::Infallible;
+
+use coset::cwt::ClaimsSetBuilder;
+
+#[cfg(not(feature = "std"))]
+use {alloc::string::ToString, alloc::vec};
+
+use crate::error::TokenReferenceError;
+use crate::token::storage::InMemoryStorage;
+
+use super::*;
+
+fn example_claims(audience: &str) -> ClaimsSet {
+    ClaimsSetBuilder::new()
+        .audience(audience.to_string())
+        .expiration_time(Timestamp::WholeSeconds(2000))
+        .issued_at(Timestamp::WholeSeconds(1000))
+        .build()
+}
+
+fn exi_of(claims: &ClaimsSet) -> Vec<&Value> {
+    claims
+        .rest
+        .iter()
+        .filter(|(name, _)| name == &ClaimName::Assigned(CwtClaimName::Exi))
+        .map(|(_, value)| value)
+        .collect()
+}
+
+#[test]
+fn test_clock_less_audience() {
+    let mut issuance = ClockLessIssuance::new(vec!["clockless"]);
+    let claims = issuance.apply(example_claims("clockless"), 3600, 1500);
+    assert_eq!(claims.expiration_time, None);
+    assert_eq!(claims.issued_at, Some(Timestamp::WholeSeconds(1500)));
+    assert_eq!(exi_of(&claims), vec![&Value::from(3600)]);
+    assert_eq!(issuance.last_issued_at(), Some(1500));
+}
+
+#[test]
+fn test_other_audience_unchanged() {
+    let mut issuance = ClockLessIssuance::new(vec!["clockless"]);
+    let claims = issuance.apply(example_claims("regular"), 3600, 1500);
+    assert_eq!(claims, example_claims("regular"));
+    assert_eq!(issuance.last_issued_at(), None);
+}
+
+#[test]
+fn test_issued_at_monotonic() {
+    let mut issuance = ClockLessIssuance::new(vec!["clockless"]).resume_from(1500);
+    let first = issuance.apply(example_claims("clockless"), 60, 1500);
+    let second = issuance.apply(example_claims("clockless"), 60, 1200);
+    let third = issuance.apply(first.clone(), 30, 1700);
+    assert_eq!(first.issued_at, Some(Timestamp::WholeSeconds(1501)));
+    assert_eq!(second.issued_at, Some(Timestamp::WholeSeconds(1502)));
+    assert_eq!(third.issued_at, Some(Timestamp::WholeSeconds(1700)));
+    // Existing `exi` claims are replaced rather than duplicated.
+    assert_eq!(exi_of(&third), vec![&Value::from(30)]);
+}
+
+#[test]
+fn test_persistence() {
+    let mut issuance = ClockLessIssuance::new(vec!["clockless"]);
+    let _ = issuance.apply(example_claims("clockless"), 60, 1500);
+    let mut persisted = Vec::new();
+    assert!(ciborium::ser::into_writer(&issuance, &mut persisted).is_ok());
+    assert_eq!(
+        ciborium::de::from_reader::<ClockLessIssuance, &[u8]>(persisted.as_slice()).ok(),
+        Some(issuance)
+    );
+}
+
+#[test]
+fn test_audience_aliases() {
+    let mut aliases = AudienceAliases::default();
+    aliases.insert("SN-4711", "valve242");
+    aliases.insert("old-valve", "SN-4711");
+    assert_eq!(aliases.normalize("SN-4711"), "valve242");
+    assert_eq!(aliases.normalize("old-valve"), "valve242");
+    assert_eq!(aliases.normalize("valve242"), "valve242");
+
+    // Renaming the canonical audience redirects all existing aliases.
+    aliases.insert("valve242", "valve-kitchen");
+    assert_eq!(aliases.normalize("SN-4711"), "valve-kitchen");
+    assert_eq!(aliases.normalize("old-valve"), "valve-kitchen");
+    assert_eq!(aliases.normalize("valve242"), "valve-kitchen");
+
+    // Aliases can't refer to themselves.
+    aliases.insert("valve-kitchen", "valve242");
+    assert_eq!(aliases.normalize("valve-kitchen"), "valve-kitchen");
+
+    let claims = aliases.normalize_claims(example_claims("SN-4711"));
+    assert_eq!(claims.audience.as_deref(), Some("valve-kitchen"));
+    let mut request = AccessTokenRequest::default();
+    aliases.normalize_request(&mut request);
+    assert_eq!(request.audience, None);
+    request.audience = Some("old-valve".to_string());
+    aliases.normalize_request(&mut request);
+    assert_eq!(request.audience.as_deref(), Some("valve-kitchen"));
+}
+
+#[test]
+fn test_token_references() -> Result<(), TokenReferenceError<Infallible>> {
+    let issuance = TokenReferenceIssuance::new(vec!["valve242"]).with_reference_length(20);
+    assert!(issuance.uses_references("valve242"));
+    assert!(!issuance.uses_references("tempSensor4711"));
+    let mut storage = InMemoryStorage::default();
+    let first = issuance.issue(example_claims("valve242"), &mut storage, |x| x.fill(1))?;
+    let second = issuance.issue(example_claims("other"), &mut storage, |x| x.fill(2))?;
+    assert_eq!(first, vec![1; 20]);
+    // A reference which is in use must not be overwritten.
+    assert_eq!(
+        issuance.issue(example_claims("attacker"), &mut storage, |x| x.fill(2)),
+        Err(TokenReferenceError::DuplicateReference)
+    );
+    let short = issuance.clone().with_reference_length(15);
+    assert_eq!(
+        short.issue(example_claims("valve242"), &mut storage, |x| x.fill(3)),
+        Err(TokenReferenceError::ReferenceTooShort { length: 15 })
+    );
+    assert_eq!(
+        storage.resolve_token_reference(&first),
+        Ok(Some(example_claims("valve242")))
+    );
+    assert_eq!(storage.revoke_token_reference(&first), Ok(true));
+    assert_eq!(storage.revoke_token_reference(&first), Ok(false));
+    assert_eq!(storage.resolve_token_reference(&first), Ok(None));
+    assert_eq!(
+        storage.resolve_token_reference(&second),
+        Ok(Some(example_claims("other")))
+    );
+    Ok(())
+}
@@ -0,0 +1,462 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+//! Contains helpers for the Authorization Server to manage the keys it uses for its audiences.
+//!
+//! An Authorization Server serving multiple tenants will usually use different keys for
+//! different audiences. Using an [`AudienceKeyRing`], it can keep track of which key belongs
+//! to which audience and export only the public keys a given Resource Server needs.
+//!
+//! When encrypting many tokens under the same key, [`PartialIvCounters`] can be used to
+//! generate a fresh partial IV for each token, so that nonces are never reused.
+//!
+//! For large fleets of Resource Servers using symmetric keys, [`AudienceKeyDerivation`] derives
+//! each Resource Server's key from a single master secret instead of storing one key per audience.
+//!
+//! To make sure that a key is only ever used for one purpose (e.g., that a key encrypting tokens
+//! isn't also handed out as a DTLS PSK), its [`KeyUsage`] can be
+//! [declared](AudienceKeyRing::declare_usage) in the key ring, after which lookups
+//! [for another usage](AudienceKeyRing::key_for) fail.
+//!
+//! # Example
+//! ```
+//! # use coset::CoseKeyBuilder;
+//! # use coset::iana::EllipticCurve;
+//! # use dcaf::error::KeyIdCollisionError;
+//! use dcaf::token::keys::AudienceKeyRing;
+//!
+//! let mut ring = AudienceKeyRing::default();
+//! let key = CoseKeyBuilder::new_ec2_priv_key(EllipticCurve::P_256, vec![1], vec![2], vec![3])
+//!     .key_id(vec![0xDC, 0xAF])
+//!     .build();
+//! ring.insert("valve242", key)?;
+//! let exported = ring.export("valve242");
+//! assert_eq!(exported.0.len(), 1);
+//! // The private key is never exported.
+//! assert_eq!(exported.0[0].params.len(), 3);
+//! assert!(ring.export("valve243").0.is_empty());
+//! # Ok::<(), KeyIdCollisionError>(())
+//! ```
+
+use core::num::NonZeroUsize;
+
+use coset::iana::{Ec2KeyParameter, KeyType, OkpKeyParameter, RsaKeyParameter};
+use coset::{CoseKey, CoseKeyBuilder, CoseKeySet, Header, Label, RegisteredLabel};
+use serde::{Deserialize, Serialize};
+
+use crate::common::cbor_values::ByteString;
+use crate::error::{KeyIdCollisionError, KeyUsageError, PartialIvError};
+use crate::token::{KeyUsage, KeyUsages};
+
+#[cfg(not(feature = "std"))]
+use {alloc::string::String, alloc::vec, alloc::vec::Vec};
+
+#[cfg(test)]
+mod tests;
+
+/// A collection of keys, each of which is associated with an audience.
+///
+/// The same key may be used for multiple audiences, but key IDs must be unambiguous:
+/// [`insert`](AudienceKeyRing::insert) fails if a *different* key with the same key ID has
+/// already been added, regardless of the audience it has been added for. Rotating the key of a
+/// key ID requires an explicit call to [`replace`](AudienceKeyRing::replace).
+///
+/// # Key Usage
+/// Keys with a key ID can be restricted to a single [`KeyUsage`] using
+/// [`declare_usage`](AudienceKeyRing::declare_usage). Lookups using
+/// [`key_for`](AudienceKeyRing::key_for) or [`keys_for`](AudienceKeyRing::keys_for) then refuse
+/// (or skip) the key when it's requested for any other usage, and only keys which may be used
+/// for [signing](KeyUsage::Sign) are [exported](AudienceKeyRing::export). Keys whose usage hasn't
+/// been declared can be used for any purpose. The declared usage is kept when a key is
+/// [replaced](AudienceKeyRing::replace).
+///
+/// ```
+/// # use coset::CoseKeyBuilder;
+/// # use dcaf::error::KeyUsageError;
+/// use dcaf::token::KeyUsage;
+/// use dcaf::token::keys::AudienceKeyRing;
+///
+/// # #[cfg(feature = "std")] {
+/// let mut ring = AudienceKeyRing::default();
+/// let kek = CoseKeyBuilder::new_symmetric_key(vec![0xDC; 16]).key_id(vec![0x01]).build();
+/// ring.insert("valve242", kek.clone())?;
+/// ring.declare_usage(&[0x01], KeyUsage::Encrypt)?;
+/// assert_eq!(ring.key_for(&[0x01], KeyUsage::Encrypt)?, &kek);
+/// assert!(ring.key_for(&[0x01], KeyUsage::PreSharedKey).is_err());
+/// assert_eq!(ring.keys_for("valve242", KeyUsage::PreSharedKey).count(), 0);
+/// # }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+///
+/// For another example, see the [module-level documentation](self).
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct AudienceKeyRing {
+    /// The keys in this key ring, along with their audience.
+    entries: Vec<(String, CoseKey)>,
+
+    /// The declared usages of the keys in this key ring.
+    usages: KeyUsages,
+}
+
+impl AudienceKeyRing {
+    /// Adds the given `key` to this key ring, associating it with the given `audience`.
+    ///
+    /// # Errors
+    /// If a different key with the same (non-empty) key ID has already been added.
+    pub fn insert<T>(&mut self, audience: T, key: CoseKey) -> Result<(), KeyIdCollisionError>
+    where
+        T: Into<String>,
+    {
+        let audience = audience.into();
+        if !key.key_id.is_empty() {
+            if let Some((existing, _)) = self
+                .entries
+                .iter()
+                .find(|(_, other)| other.key_id == key.key_id && other != &key)
+            {
+                return Err(KeyIdCollisionError {
+                    key_id: key.key_id,
+                    audience: existing.clone(),
+                });
+            }
+        }
+        if !self.entries.iter().any(|x| x.0 == audience && x.1 == key) {
+            self.entries.push((audience, key));
+        }
+        Ok(())
+    }
+
+    /// Adds the given `key` to this key ring like [`insert`](AudienceKeyRing::insert), but
+    /// replaces all keys which previously used the same (non-empty) key ID instead of failing,
+    /// returning them along with their audience.
+    ///
+    /// This is intended for key rotation, where reusing a key ID is deliberate, so the usage
+    /// declared for the key ID is kept.
+    pub fn replace<T>(&mut self, audience: T, key: CoseKey) -> Vec<(String, CoseKey)>
+    where
+        T: Into<String>,
+    {
+        let mut replaced = Vec::new();
+        if !key.key_id.is_empty() {
+            let (same_id, others) = core::mem::take(&mut self.entries)
+                .into_iter()
+                .partition(|(_, other)| other.key_id == key.key_id);
+            self.entries = others;
+            replaced = same_id;
+        }
+        self.entries.push((audience.into(), key));
+        replaced
+    }
+
+    /// Returns an iterator over all keys in this key ring along with their audience,
+    /// including their private parts.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &CoseKey)> {
+        self.entries
+            .iter()
+            .map(|(audience, key)| (audience.as_str(), key))
+    }
+
+    /// Restricts the key with the given `key_id` to the given `usage`.
+    ///
+    /// Declaring the same usage again has no effect.
+    ///
+    /// # Errors
+    /// - If no key with the given `key_id` is contained in this key ring
+    ///   ([`KeyUsageError::UnknownKeyId`]).
+    /// - If a different usage has already been declared for the key
+    ///   ([`KeyUsageError::UsageMismatch`]).
+    pub fn declare_usage(&mut self, key_id: &[u8], usage: KeyUsage) -> Result<(), KeyUsageError> {
+        if key_id.is_empty() || !self.entries.iter().any(|(_, x)| x.key_id == key_id) {
+            return Err(KeyUsageError::UnknownKeyId(key_id.to_vec()));
+        }
+        self.usages.declare(key_id, usage)
+    }
+
+    /// Returns the usage declared for the key with the given `key_id`, if any.
+    #[must_use]
+    pub fn usage(&self, key_id: &[u8]) -> Option<KeyUsage> {
+        self.usages.get(key_id)
+    }
+
+    /// Returns the key with the given `key_id`, including its private parts, if it may be used
+    /// for the given `usage`.
+    ///
+    /// # Errors
+    /// - If no key with the given `key_id` is contained in this key ring
+    ///   ([`KeyUsageError::UnknownKeyId`]).
+    /// - If a different usage has been declared for the key ([`KeyUsageError::UsageMismatch`]).
+    pub fn key_for(&self, key_id: &[u8], usage: KeyUsage) -> Result<&CoseKey, KeyUsageError> {
+        let key = self
+            .entries
+            .iter()
+            .map(|(_, key)| key)
+            .find(|x| !key_id.is_empty() && x.key_id == key_id)
+            .ok_or_else(|| KeyUsageError::UnknownKeyId(key_id.to_vec()))?;
+        self.usages.check(key_id, usage)?;
+        Ok(key)
+    }
+
+    /// Returns an iterator over all keys associated with the given `audience` (including their
+    /// private parts) which may be used for the given `usage`, skipping keys declared for another
+    /// usage.
+    pub fn keys_for<'a>(
+        &'a self,
+        audience: &'a str,
+        usage: KeyUsage,
+    ) -> impl Iterator<Item = &'a CoseKey> + 'a {
+        self.entries
+            .iter()
+            .filter(move |(aud, _)| aud == audience)
+            .map(|(_, key)| key)
+            .filter(move |key| self.usages.check(&key.key_id, usage).is_ok())
+    }
+
+    /// Exports the public parts of all keys associated with the given `audience` which may be
+    /// used for [signing](KeyUsage::Sign) as a [`CoseKeySet`], e.g., for publication to the
+    /// Resource Server, which verifies tokens with them.
+    ///
+    /// Private key parameters are removed from asymmetric keys, and symmetric keys
+    /// are left out entirely.
+    #[must_use]
+    pub fn export(&self, audience: &str) -> CoseKeySet {
+        CoseKeySet(
+            self.keys_for(audience, KeyUsage::Sign)
+                .filter_map(public_key)
+                .collect(),
+        )
+    }
+}
+
+/// Returns the public part of the given `key`, or `None` if it's a symmetric key
+/// (or any other key type whose public parameters aren't known).
+fn public_key(key: &CoseKey) -> Option<CoseKey> {
+    let public_labels: &[i64] = match key.kty {
+        RegisteredLabel::Assigned(KeyType::EC2) => &[
+            Ec2KeyParameter::Crv as i64,
+            Ec2KeyParameter::X as i64,
+            Ec2KeyParameter::Y as i64,
+        ],
+        RegisteredLabel::Assigned(KeyType::OKP) => {
+            &[OkpKeyParameter::Crv as i64, OkpKeyParameter::X as i64]
+        }
+        RegisteredLabel::Assigned(KeyType::RSA) => {
+            &[RsaKeyParameter::N as i64, RsaKeyParameter::E as i64]
+        }
+        _ => return None,
+    };
+    let mut public = key.clone();
+    public
+        .params
+        .retain(|(label, _)| matches!(label, Label::Int(x) if public_labels.contains(x)));
+    Some(public)
+}
+
+/// Keeps one counter per key ID, from which unique partial IVs for encrypted tokens are
+/// generated, as described in [section 3.1 of RFC 8152](https://www.rfc-editor.org/rfc/rfc8152#section-3.1).
+///
+/// The partial IV is placed in the unprotected header of the token (see
+/// [`apply`](PartialIvCounters::apply)), where it is left-padded and XORed with the base IV of
+/// the key by the cipher to form the nonce. Since each counter value is only handed out once,
+/// this prevents nonce reuse when many tokens are encrypted under the same key.
+///
+/// # Persistence
+/// Handing out the same partial IV twice (e.g., after a restart) would lead to nonce reuse,
+/// so the counters must be persisted across restarts. This type implements [`Serialize`] and
+/// [`Deserialize`] for this purpose. The counters should be persisted *before* a token
+/// encrypted with a newly generated partial IV leaves the Authorization Server.
+///
+/// # Example
+/// ```
+/// # use coset::HeaderBuilder;
+/// # use core::num::NonZeroUsize;
+/// # use dcaf::error::PartialIvError;
+/// use dcaf::token::keys::PartialIvCounters;
+///
+/// let mut counters = PartialIvCounters::new(NonZeroUsize::new(5).expect("5 is not zero"));
+/// let mut unprotected = HeaderBuilder::new().build();
+/// counters.apply(&[0xDC, 0xAF], &mut unprotected)?;
+/// assert_eq!(unprotected.partial_iv, vec![0x00]);
+/// assert_eq!(counters.next_partial_iv(&[0xDC, 0xAF])?, vec![0x01]);
+/// // Counters of different keys are independent.
+/// assert_eq!(counters.next_partial_iv(&[0x42])?, vec![0x00]);
+/// # Ok::<(), PartialIvError>(())
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Hash, Serialize, Deserialize)]
+pub struct PartialIvCounters {
+    /// The maximum length of a generated partial IV in bytes.
+    max_length: NonZeroUsize,
+
+    /// The next counter value for each key ID.
+    counters: Vec<(Vec<u8>, u64)>,
+}
+
+impl PartialIvCounters {
+    /// Creates new counters generating partial IVs of at most `max_length` bytes
+    /// (at most 8, as the counters are 64 bits wide, so larger values behave like 8).
+    ///
+    /// `max_length` should be chosen such that the partial IV is shorter than the nonce
+    /// of the used algorithm. It can't be zero, as not even the first partial IV could
+    /// be generated then.
+    #[must_use]
+    pub fn new(max_length: NonZeroUsize) -> PartialIvCounters {
+        PartialIvCounters {
+            max_length,
+            counters: Vec::new(),
+        }
+    }
+
+    /// Returns the counter value from which the next partial IV for the given `key_id`
+    /// will be generated.
+    #[must_use]
+    pub fn counter(&self, key_id: &[u8]) -> u64 {
+        self.counters
+            .iter()
+            .find(|(id, _)| id == key_id)
+            .map_or(0, |(_, counter)| *counter)
+    }
+
+    /// Generates a new, never before returned partial IV for the given `key_id`,
+    /// encoded as the shortest big-endian representation of the counter.
+    ///
+    /// # Errors
+    /// If the counter for the given `key_id` is exhausted, i.e., the next partial IV would be
+    /// longer than the configured maximum length. In this case, the key must be replaced.
+    pub fn next_partial_iv(&mut self, key_id: &[u8]) -> Result<Vec<u8>, PartialIvError> {
+        let counter = self.counter(key_id);
+        let bytes = counter.to_be_bytes();
+        let skip = bytes
+            .iter()
+            .take_while(|x| **x == 0)
+            .count()
+            .min(bytes.len() - 1);
+        let partial_iv = bytes[skip..].to_vec();
+        if partial_iv.len() > self.max_length.get() {
+            return Err(PartialIvError::Exhausted);
+        }
+        let next = counter.checked_add(1).ok_or(PartialIvError::Exhausted)?;
+        if let Some(entry) = self.counters.iter_mut().find(|(id, _)| id == key_id) {
+            entry.1 = next;
+        } else {
+            self.counters.push((key_id.to_vec(), next));
+        }
+        Ok(partial_iv)
+    }
+
+    /// Sets the partial IV of the given (unprotected) `header` to a newly generated partial IV
+    /// for the given `key_id`.
+    ///
+    /// # Errors
+    /// - If the `header` already contains an IV or partial IV.
+    /// - If the counter for the given `key_id` is exhausted.
+    pub fn apply(&mut self, key_id: &[u8], header: &mut Header) -> Result<(), PartialIvError> {
+        if !header.iv.is_empty() {
+            return Err(PartialIvError::HeaderAlreadySet("iv"));
+        }
+        if !header.partial_iv.is_empty() {
+            return Err(PartialIvError::HeaderAlreadySet("partial_iv"));
+        }
+        header.partial_iv = self.next_partial_iv(key_id)?;
+        Ok(())
+    }
+}
+
+/// Derives symmetric keys for individual audiences from a single master secret using HKDF
+/// ([RFC 5869](https://www.rfc-editor.org/rfc/rfc5869)), with the audience as the `info` input.
+///
+/// This way, an Authorization Server serving a large fleet of Resource Servers only needs to store
+/// the master secret, while each Resource Server is provisioned with its own derived key.
+/// Since this crate doesn't contain any cryptographic code, the HKDF itself is supplied by the
+/// caller as a function (which also has access to the master secret, so that it never needs to be
+/// passed to this crate).
+///
+/// # Key IDs
+/// The key ID of a derived key consists of the [`master_key_id`](AudienceKeyDerivation::master_key_id)
+/// followed by the UTF-8 encoded audience. This allows the Resource Server to check that a
+/// token is meant for its derived key, and the Authorization Server to find out (using
+/// [`audience_of`](AudienceKeyDerivation::audience_of)) for which audience it needs to re-derive
+/// the key, e.g., when processing introspection requests. Short audience identifiers should be
+/// used to keep the key IDs small. Using a new master key ID whenever the master secret is
+/// rotated ensures that keys of different generations can be told apart.
+///
+/// # Example
+/// ```
+/// use dcaf::token::keys::AudienceKeyDerivation;
+///
+/// let derivation = AudienceKeyDerivation::new(vec![0x01], 16);
+/// let key = derivation.derive_key("rs1", |info, okm| {
+///     // A real AS would compute HKDF-Expand(PRK, info, okm.len()) here,
+///     // where PRK = HKDF-Extract(salt, master_secret).
+///     okm.fill(info.len() as u8);
+/// });
+/// assert_eq!(key.key_id, vec![0x01, b'r', b's', b'1']);
+/// assert_eq!(derivation.audience_of(&key.key_id), Some("rs1"));
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Hash, Serialize, Deserialize)]
+pub struct AudienceKeyDerivation {
+    /// Identifies the master secret the keys are derived from.
+    ///
+    /// Used as the prefix of the key IDs of derived keys.
+    pub master_key_id: ByteString,
+
+    /// The length of the derived keys in bytes.
+    pub key_length: usize,
+}
+
+impl AudienceKeyDerivation {
+    /// Creates a new key derivation for the master secret identified by `master_key_id`,
+    /// deriving keys of `key_length` bytes.
+    #[must_use]
+    pub fn new<T>(master_key_id: T, key_length: usize) -> AudienceKeyDerivation
+    where
+        T: Into<ByteString>,
+    {
+        AudienceKeyDerivation {
+            master_key_id: master_key_id.into(),
+            key_length,
+        }
+    }
+
+    /// Returns the key ID of the key derived for the given `audience`.
+    #[must_use]
+    pub fn key_id(&self, audience: &str) -> ByteString {
+        let mut key_id = self.master_key_id.clone();
+        key_id.extend_from_slice(audience.as_bytes());
+        key_id
+    }
+
+    /// Returns the audience whose derived key has the given `key_id`, or `None` if the `key_id`
+    /// doesn't belong to a key derived by this derivation.
+    #[must_use]
+    pub fn audience_of<'a>(&self, key_id: &'a [u8]) -> Option<&'a str> {
+        key_id
+            .strip_prefix(self.master_key_id.as_slice())
+            .and_then(|audience| core::str::from_utf8(audience).ok())
+    }
+
+    /// Derives the symmetric key for the given `audience`.
+    ///
+    /// `hkdf_expand` is called with the `info` (the UTF-8 encoded `audience`) and a buffer of
+    /// [`key_length`](AudienceKeyDerivation::key_length) bytes, which it needs to fill with the
+    /// output of HKDF-Expand using the pseudorandom key extracted from the master secret.
+    ///
+    /// The returned key has its key ID set as described in the
+    /// [type-level documentation](AudienceKeyDerivation#key-ids).
+    pub fn derive_key<F>(&self, audience: &str, hkdf_expand: F) -> CoseKey
+    where
+        F: FnOnce(&[u8], &mut [u8]),
+    {
+        let mut okm = vec![0; self.key_length];
+        hkdf_expand(audience.as_bytes(), &mut okm);
+        CoseKeyBuilder::new_symmetric_key(okm)
+            .key_id(self.key_id(audience))
+            .build()
+    }
+}
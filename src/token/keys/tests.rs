@@ -0,0 +1,252 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+use ciborium::value::Value;
+use coset::iana::EllipticCurve;
+use coset::{CoseKeyBuilder, HeaderBuilder};
+
+#[cfg(not(feature = "std"))]
+use {alloc::string::ToString, alloc::vec, alloc::vec::Vec};
+
+use super::*;
+
+fn ec2_key(key_id: &[u8], d: u8) -> CoseKey {
+    CoseKeyBuilder::new_ec2_priv_key(EllipticCurve::P_256, vec![1], vec![2], vec![d])
+        .key_id(key_id.to_vec())
+        .build()
+}
+
+#[test]
+fn test_export_per_audience() -> Result<(), KeyIdCollisionError> {
+    let mut ring = AudienceKeyRing::default();
+    ring.insert("tenant-a", ec2_key(&[1], 10))?;
+    ring.insert("tenant-b", ec2_key(&[2], 20))?;
+    ring.insert(
+        "tenant-b",
+        CoseKeyBuilder::new_symmetric_key(vec![0xDC, 0xAF])
+            .key_id(vec![3])
+            .build(),
+    )?;
+    let exported = ring.export("tenant-b");
+    assert_eq!(exported.0.len(), 1);
+    assert_eq!(exported.0[0].key_id, vec![2]);
+    assert!(exported.0[0]
+        .params
+        .iter()
+        .all(|(label, _)| label != &Label::Int(Ec2KeyParameter::D as i64)));
+    assert_eq!(ring.keys_for("tenant-b", KeyUsage::Sign).count(), 2);
+    assert!(ring.export("tenant-c").0.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_shared_key() -> Result<(), KeyIdCollisionError> {
+    let mut ring = AudienceKeyRing::default();
+    ring.insert("tenant-a", ec2_key(&[1], 10))?;
+    ring.insert("tenant-b", ec2_key(&[1], 10))?;
+    ring.insert("tenant-b", ec2_key(&[1], 10))?;
+    assert_eq!(ring.export("tenant-a"), ring.export("tenant-b"));
+    assert_eq!(ring.keys_for("tenant-b", KeyUsage::Sign).count(), 1);
+    Ok(())
+}
+
+#[test]
+fn test_key_id_collision() -> Result<(), KeyIdCollisionError> {
+    let mut ring = AudienceKeyRing::default();
+    ring.insert("tenant-a", ec2_key(&[1], 10))?;
+    assert_eq!(
+        ring.insert("tenant-b", ec2_key(&[1], 20)),
+        Err(KeyIdCollisionError {
+            key_id: vec![1],
+            audience: "tenant-a".to_string()
+        })
+    );
+    // Keys without a key ID can't collide.
+    ring.insert("tenant-a", ec2_key(&[], 30))?;
+    ring.insert("tenant-b", ec2_key(&[], 40))?;
+    assert_eq!(ring.export("tenant-b").0.len(), 1);
+    Ok(())
+}
+
+#[test]
+fn test_key_id_replace() -> Result<(), KeyIdCollisionError> {
+    let mut ring = AudienceKeyRing::default();
+    ring.insert("tenant-a", ec2_key(&[1], 10))?;
+    ring.insert("tenant-b", ec2_key(&[1], 10))?;
+    let replaced = ring.replace("tenant-b", ec2_key(&[1], 20));
+    assert_eq!(
+        replaced,
+        vec![
+            ("tenant-a".to_string(), ec2_key(&[1], 10)),
+            ("tenant-b".to_string(), ec2_key(&[1], 10))
+        ]
+    );
+    assert_eq!(ring.keys_for("tenant-a", KeyUsage::Sign).count(), 0);
+    assert_eq!(
+        ring.keys_for("tenant-b", KeyUsage::Sign)
+            .collect::<Vec<_>>(),
+        vec![&ec2_key(&[1], 20)]
+    );
+    // The new key is now the one which collides with others.
+    assert!(ring.insert("tenant-a", ec2_key(&[1], 10)).is_err());
+    Ok(())
+}
+
+fn non_zero(length: usize) -> NonZeroUsize {
+    NonZeroUsize::new(length).expect("length must not be zero")
+}
+
+#[test]
+fn test_partial_iv_sequence() -> Result<(), PartialIvError> {
+    let mut counters = PartialIvCounters::new(non_zero(2));
+    for expected in 0..=255u8 {
+        assert_eq!(counters.next_partial_iv(&[1])?, vec![expected]);
+    }
+    assert_eq!(counters.next_partial_iv(&[1])?, vec![0x01, 0x00]);
+    assert_eq!(counters.counter(&[1]), 257);
+    assert_eq!(counters.counter(&[2]), 0);
+    Ok(())
+}
+
+#[test]
+fn test_partial_iv_exhausted() -> Result<(), PartialIvError> {
+    let mut counters = PartialIvCounters::new(non_zero(1));
+    for _ in 0..=255 {
+        counters.next_partial_iv(&[1])?;
+    }
+    assert_eq!(
+        counters.next_partial_iv(&[1]),
+        Err(PartialIvError::Exhausted)
+    );
+    // Exhaustion doesn't advance the counter, and other keys are unaffected.
+    assert_eq!(counters.counter(&[1]), 256);
+    assert_eq!(counters.next_partial_iv(&[2])?, vec![0]);
+    Ok(())
+}
+
+#[test]
+fn test_partial_iv_header() -> Result<(), PartialIvError> {
+    let mut counters = PartialIvCounters::new(non_zero(5));
+    let mut header = HeaderBuilder::new().build();
+    counters.apply(&[1], &mut header)?;
+    assert_eq!(header.partial_iv, vec![0]);
+    assert_eq!(
+        counters.apply(&[1], &mut header),
+        Err(PartialIvError::HeaderAlreadySet("partial_iv"))
+    );
+    let mut with_iv = HeaderBuilder::new().iv(vec![1, 2, 3]).build();
+    assert_eq!(
+        counters.apply(&[1], &mut with_iv),
+        Err(PartialIvError::HeaderAlreadySet("iv"))
+    );
+    assert_eq!(counters.counter(&[1]), 1);
+    Ok(())
+}
+
+#[test]
+fn test_partial_iv_persistence() {
+    let mut counters = PartialIvCounters::new(non_zero(5));
+    assert!(counters.next_partial_iv(&[1]).is_ok());
+    let mut persisted = Vec::new();
+    assert!(ciborium::ser::into_writer(&counters, &mut persisted).is_ok());
+    let mut restored: PartialIvCounters =
+        ciborium::de::from_reader(persisted.as_slice()).expect("invalid counters");
+    assert_eq!(restored.next_partial_iv(&[1]), Ok(vec![1]));
+}
+
+#[test]
+fn test_partial_iv_zero_length_rejected() {
+    // A maximum length of zero would make every partial IV generation fail.
+    let counters = Value::Map(vec![
+        (Value::from("max_length"), Value::from(0)),
+        (Value::from("counters"), Value::Array(vec![])),
+    ]);
+    let mut persisted = Vec::new();
+    assert!(ciborium::ser::into_writer(&counters, &mut persisted).is_ok());
+    assert!(ciborium::de::from_reader::<PartialIvCounters, _>(persisted.as_slice()).is_err());
+}
+
+/// Stands in for HKDF-Expand by repeating the info (which is obviously **not** secure).
+fn fake_hkdf_expand(info: &[u8], okm: &mut [u8]) {
+    for (target, source) in okm.iter_mut().zip(info.iter().cycle()) {
+        *target = *source;
+    }
+}
+
+#[test]
+fn test_audience_key_derivation() -> Result<(), KeyIdCollisionError> {
+    let derivation = AudienceKeyDerivation::new(vec![0x02], 4);
+    let key = derivation.derive_key("rs1", fake_hkdf_expand);
+    assert_eq!(
+        key,
+        CoseKeyBuilder::new_symmetric_key(vec![b'r', b's', b'1', b'r'])
+            .key_id(vec![0x02, b'r', b's', b'1'])
+            .build()
+    );
+    assert_eq!(derivation.audience_of(&key.key_id), Some("rs1"));
+    assert_eq!(derivation.audience_of(&[0x01, b'r', b's', b'1']), None);
+    assert_eq!(derivation.audience_of(&[0x02, 0xFF]), None);
+
+    // Keys derived for different audiences can be used in the same key ring.
+    let mut ring = AudienceKeyRing::default();
+    ring.insert("rs1", key)?;
+    ring.insert("rs2", derivation.derive_key("rs2", fake_hkdf_expand))?;
+    assert_eq!(ring.keys_for("rs2", KeyUsage::Sign).count(), 1);
+    Ok(())
+}
+
+#[test]
+fn test_key_usage() -> Result<(), KeyUsageError> {
+    let mut ring = AudienceKeyRing::default();
+    ring.insert("rs1", ec2_key(&[1], 1))
+        .expect("key ID must be unique");
+    ring.insert("rs1", ec2_key(&[2], 2))
+        .expect("key ID must be unique");
+    ring.insert("rs2", ec2_key(&[1], 1))
+        .expect("key ID must be unique");
+    ring.declare_usage(&[1], KeyUsage::Sign)?;
+    ring.declare_usage(&[1], KeyUsage::Sign)?;
+    assert_eq!(
+        ring.declare_usage(&[1], KeyUsage::PreSharedKey),
+        Err(KeyUsageError::UsageMismatch {
+            key_id: vec![1],
+            declared: KeyUsage::Sign,
+            requested: KeyUsage::PreSharedKey
+        })
+    );
+    assert_eq!(
+        ring.declare_usage(&[3], KeyUsage::Sign),
+        Err(KeyUsageError::UnknownKeyId(vec![3]))
+    );
+    assert_eq!(ring.usage(&[1]), Some(KeyUsage::Sign));
+    assert_eq!(ring.usage(&[2]), None);
+
+    assert_eq!(ring.key_for(&[1], KeyUsage::Sign)?, &ec2_key(&[1], 1));
+    assert!(ring.key_for(&[1], KeyUsage::Encrypt).is_err());
+    // Keys without a declared usage can be used for anything.
+    assert_eq!(ring.key_for(&[2], KeyUsage::Encrypt)?, &ec2_key(&[2], 2));
+    assert_eq!(
+        ring.keys_for("rs1", KeyUsage::Encrypt).collect::<Vec<_>>(),
+        vec![&ec2_key(&[2], 2)]
+    );
+    assert_eq!(ring.keys_for("rs2", KeyUsage::Sign).count(), 1);
+
+    // Only keys which may be used for signing are exported.
+    ring.declare_usage(&[2], KeyUsage::Encrypt)?;
+    let exported = ring.export("rs1");
+    assert_eq!(exported.0.len(), 1);
+    assert_eq!(exported.0[0].key_id, vec![1]);
+
+    // The usage is kept when the key is rotated.
+    ring.replace("rs1", ec2_key(&[1], 3));
+    assert!(ring.key_for(&[1], KeyUsage::Encrypt).is_err());
+    Ok(())
+}
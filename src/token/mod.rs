@@ -23,6 +23,32 @@
 //! necessary headers) and either [`CoseEncrypt0Cipher`], [`CoseMac0Cipher`] or [`CoseSign1Cipher`],
 //! depending on the intended operation. See the respective traits for details.
 //!
+//! If a cipher only needs to support one direction of an operation (e.g., a Resource Server
+//! which only verifies signatures), it can instead implement just the corresponding
+//! capability-specific trait: [`CoseSignCipher`], [`CoseVerifyCipher`], [`CoseEncryptCipher`],
+//! or [`CoseDecryptCipher`]. These are automatically implemented for every [`CoseSign1Cipher`]
+//! and [`CoseEncrypt0Cipher`], respectively.
+//!
+//! # Capabilities
+//! Ciphers can declare the algorithms and key types they support by returning
+//! [`CipherCapabilities`] from their `capabilities` method. On startup, these can be checked
+//! against the configuration of the Authorization Server (`AuthServerConfig::check_capabilities`,
+//! requires the `as` feature) or the algorithms a Resource Server accepts
+//! ([`SecurityConfig::check_capabilities`](crate::common::security::SecurityConfig::check_capabilities)),
+//! so that configuration mismatches are detected before the first token is processed.
+//!
+//! # Header Ordering
+//! When issuing tokens, the header parameters which aren't natively supported by [`coset`]
+//! (i.e., those in [`Header::rest`]) are put into [canonical order](canonicalize_header) after the
+//! cipher has set its headers, so that the same headers are always encoded the same way.
+//!
+//! Received tokens keep the original encoding of their protected header, including the order of
+//! its parameters, which is what their signature or tag has been computed over. Components
+//! which parse and re-emit tokens (e.g., proxies modifying the unprotected header) hence don't
+//! invalidate them, as long as they keep the [`ProtectedHeader`] as parsed (see also
+//! [`get_token_headers`]). [`update_unprotected_header`] does exactly this, checking that the
+//! protected content of the token stays untouched.
+//!
 //! # Example
 //! The following shows how to create and sign an access token (assuming a cipher implementing
 //! both [`CoseSign1Cipher`] and [`CoseCipherCommon`] exists in variable `cipher`):
@@ -82,23 +108,218 @@
 //! assert!(verify_access_token(&token, &mut cipher, None).is_ok());
 //! # Ok::<(), AccessTokenError<String>>(())
 //! ```
+//!
+//! # Layout
+//! Apart from the functions and traits mentioned above, this module contains
+//! (note that [`approval`], [`issuance`], [`keys`], [`policy`], and [`storage`] require the `as` feature,
+//! while [`token_store`] and [`validation`] require the `rs` feature):
+//! - [`approval`]: A hook through which operators can approve, deny, or modify grants.
+//! - [`compression`]: Functions for compressing access tokens for constrained links.
+//! - [`detached`]: Variants of the functions above which transport the payload (or ciphertext)
+//!   of the token separately from its COSE structure.
+//! - [`format`](mod@format): The [`TokenFormat`](format::TokenFormat) trait, which abstracts over the wire
+//!   format of access tokens.
+//! - [`issuance`]: Helpers for the Authorization Server to prepare the claims of access tokens.
+//! - [`keys`]: Helpers for the Authorization Server to manage the keys used for its audiences.
+//! - [`policy`]: A declarative issuance policy for simple Authorization Servers.
+//! - [`storage`]: Storage traits for the state the Authorization Server needs to keep across restarts.
+//! - [`streaming`]: Variants of [`sign_access_token`] and [`verify_access_token`] for ciphers
+//!   which process the to-be-signed data incrementally.
+//! - [`tenancy`]: Isolated tenants sharing a single Authorization Server.
+//! - [`token_store`]: A capacity-bounded store for the tokens the Resource Server has accepted.
+//! - [`validation`]: Helpers for the Resource Server to validate the claims of access tokens.
 
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
 
 use crate::common::cbor_values::ByteString;
-use core::fmt::{Debug, Display};
-use coset::cwt::ClaimsSet;
+use crate::common::cose::Algorithm;
+use core::fmt::{Debug, Display, Formatter};
+use coset::cwt::{ClaimName, ClaimsSet};
+use coset::iana::{CwtClaimName, EnumI64, HeaderParameter, KeyType};
 use coset::{
-    CborSerializable, CoseEncrypt0, CoseEncrypt0Builder, CoseMac0, CoseSign1, CoseSign1Builder,
-    Header, HeaderBuilder, ProtectedHeader,
+    CborSerializable, CoseEncrypt0, CoseEncrypt0Builder, CoseKey, CoseMac0, CoseSign1,
+    CoseSign1Builder, Header, HeaderBuilder, Label, ProtectedHeader, RegisteredLabel,
 };
 
-use crate::error::{AccessTokenError, CoseCipherError};
+#[cfg(feature = "rs")]
+use crate::common::security::SecurityConfig;
+#[cfg(any(feature = "as", feature = "rs"))]
+use crate::error::KeyUsageError;
+#[cfg(feature = "rs")]
+use crate::error::SecurityConfigError;
+use crate::error::{AccessTokenError, CoseCipherError, HeaderUpdateError};
+
+#[cfg(feature = "as")]
+pub mod approval;
+pub mod compression;
+pub mod detached;
+pub mod format;
+#[cfg(feature = "as")]
+pub mod issuance;
+#[cfg(feature = "as")]
+pub mod keys;
+#[cfg(feature = "as")]
+pub mod policy;
+#[cfg(feature = "as")]
+pub mod storage;
+pub mod streaming;
+#[cfg(feature = "as")]
+pub mod tenancy;
+#[cfg(feature = "rs")]
+pub mod token_store;
+#[cfg(feature = "rs")]
+pub mod validation;
 
 #[cfg(test)]
 mod tests;
 
+/// The algorithms and key types supported by a cipher, as returned by
+/// [`CoseCipherCommon::capabilities`] (or the `capabilities` methods of [`CoseVerifyCipher`] and
+/// [`CoseDecryptCipher`]).
+///
+/// # Example
+/// ```
+/// # use coset::CoseKeyBuilder;
+/// # use coset::iana::KeyType;
+/// use dcaf::common::cose::Algorithm;
+/// use dcaf::token::CipherCapabilities;
+///
+/// let capabilities = CipherCapabilities::new(vec![Algorithm::Es256], vec![KeyType::EC2]);
+/// assert!(capabilities.supports_algorithm(Algorithm::Es256));
+/// assert!(!capabilities.supports_algorithm(Algorithm::EdDsa));
+/// let key = CoseKeyBuilder::new_symmetric_key(vec![0; 16]).build();
+/// assert!(!capabilities.supports_key(&key));
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct CipherCapabilities {
+    /// The COSE algorithms the cipher supports.
+    pub algorithms: Vec<Algorithm>,
+
+    /// The COSE key types the cipher supports.
+    pub key_types: Vec<KeyType>,
+}
+
+impl CipherCapabilities {
+    /// Creates new [`CipherCapabilities`] supporting the given `algorithms` and `key_types`.
+    #[must_use]
+    pub fn new(algorithms: Vec<Algorithm>, key_types: Vec<KeyType>) -> CipherCapabilities {
+        CipherCapabilities {
+            algorithms,
+            key_types,
+        }
+    }
+
+    /// Returns whether the given `algorithm` is supported.
+    #[must_use]
+    pub fn supports_algorithm(&self, algorithm: Algorithm) -> bool {
+        self.algorithms.contains(&algorithm)
+    }
+
+    /// Returns whether the given `key_type` is supported.
+    #[must_use]
+    pub fn supports_key_type(&self, key_type: KeyType) -> bool {
+        self.key_types.contains(&key_type)
+    }
+
+    /// Returns whether the type of the given `key` is supported, and, if the key is restricted
+    /// to an algorithm, whether that one is supported as well.
+    #[must_use]
+    pub fn supports_key(&self, key: &CoseKey) -> bool {
+        let key_type = match key.kty {
+            RegisteredLabel::Assigned(x) => self.supports_key_type(x),
+            RegisteredLabel::Text(_) => false,
+        };
+        key_type
+            && key.alg.clone().map_or(true, |alg| {
+                Algorithm::try_from(alg).map_or(false, |x| self.supports_algorithm(x))
+            })
+    }
+}
+
+/// The purpose a key is intended for, as declared in an
+/// [`AudienceKeyRing`](crate::token::keys::AudienceKeyRing) (requires the `as` feature) or a
+/// [`TrustStore`](crate::token::validation::TrustStore) (requires the `rs` feature).
+///
+/// Using the same key for different purposes weakens all of them (e.g., a key encrypting
+/// access tokens must never be handed out as a DTLS PSK), so once a key's usage is declared,
+/// lookups for any other usage fail with a [`KeyUsageError`](crate::error::KeyUsageError).
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum KeyUsage {
+    /// Protecting access tokens with signatures or MACs.
+    Sign,
+
+    /// Encrypting access tokens (or other keys contained in them).
+    Encrypt,
+
+    /// Proving possession of an access token, i.e., as the key in its `cnf` claim.
+    ProofOfPossession,
+
+    /// Securing the channel between client and Resource Server, e.g., as a DTLS PSK.
+    PreSharedKey,
+}
+
+impl Display for KeyUsage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let name = match self {
+            KeyUsage::Sign => "signing",
+            KeyUsage::Encrypt => "encryption",
+            KeyUsage::ProofOfPossession => "proof-of-possession",
+            KeyUsage::PreSharedKey => "pre-shared key",
+        };
+        f.write_str(name)
+    }
+}
+
+/// The declared [`KeyUsage`]s of keys, identified by their key IDs.
+#[cfg(any(feature = "as", feature = "rs"))]
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub(crate) struct KeyUsages(Vec<(ByteString, KeyUsage)>);
+
+#[cfg(any(feature = "as", feature = "rs"))]
+impl KeyUsages {
+    /// Returns the usage declared for the key with the given `key_id`, if any.
+    pub(crate) fn get(&self, key_id: &[u8]) -> Option<KeyUsage> {
+        self.0
+            .iter()
+            .find(|(id, _)| id == key_id)
+            .map(|(_, usage)| *usage)
+    }
+
+    /// Declares the given `usage` for the key with the given `key_id`.
+    ///
+    /// # Errors
+    /// If a different usage has already been declared for the key.
+    pub(crate) fn declare(&mut self, key_id: &[u8], usage: KeyUsage) -> Result<(), KeyUsageError> {
+        if self.get(key_id).is_none() {
+            self.0.push((key_id.to_vec(), usage));
+        }
+        self.check(key_id, usage)
+    }
+
+    /// Checks that the key with the given `key_id` may be used for the `requested` usage,
+    /// which is the case if it has been declared for it or not declared at all.
+    ///
+    /// # Errors
+    /// If a different usage has been declared for the key.
+    pub(crate) fn check(&self, key_id: &[u8], requested: KeyUsage) -> Result<(), KeyUsageError> {
+        match self.get(key_id) {
+            Some(declared) if declared != requested => Err(KeyUsageError::UsageMismatch {
+                key_id: key_id.to_vec(),
+                declared,
+                requested,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Removes the declared usage of the key with the given `key_id`.
+    #[cfg(feature = "rs")]
+    pub(crate) fn remove(&mut self, key_id: &[u8]) {
+        self.0.retain(|(id, _)| id != key_id);
+    }
+}
+
 /// Provides common operations necessary for other COSE cipher types to function.
 ///
 /// This needs to be implemented if [`CoseEncrypt0Cipher`], [`CoseSign1Cipher`], or
@@ -158,6 +379,12 @@ pub trait CoseCipherCommon {
         unprotected_header: &mut Header,
         protected_header: &mut Header,
     ) -> Result<(), CoseCipherError<Self::Error>>;
+
+    /// Returns the algorithms and key types this cipher supports, or `None` (the default)
+    /// if it doesn't declare them.
+    fn capabilities(&self) -> Option<CipherCapabilities> {
+        None
+    }
 }
 
 /// Provides basic operations for encrypting and decrypting COSE structures.
@@ -356,9 +583,156 @@ pub trait CoseMac0Cipher: CoseCipherCommon {
     ) -> Result<(), CoseCipherError<Self::Error>>;
 }
 
+/// Provides the signing half of [`CoseSign1Cipher`], as used by [`sign_access_token`].
+///
+/// Implement this trait directly if your cipher is only ever used to sign tokens (e.g., on the
+/// Authorization Server). Every [`CoseSign1Cipher`] implements this trait as well.
+pub trait CoseSignCipher: CoseCipherCommon {
+    /// Cryptographically signs the given `target` value and returns the signature.
+    fn generate_signature(&mut self, target: &[u8]) -> Vec<u8>;
+}
+
+/// Provides the verification half of [`CoseSign1Cipher`], as used by [`verify_access_token`].
+///
+/// Implement this trait directly if your cipher is only ever used to verify tokens (e.g., on a
+/// Resource Server). As no headers need to be set for verification, this trait doesn't require
+/// [`CoseCipherCommon`] to be implemented. Every [`CoseSign1Cipher`] implements this trait as well.
+///
+/// # Example
+/// ```
+/// # use dcaf::CoseVerifyCipher;
+/// # use dcaf::error::CoseCipherError;
+/// struct FakeVerifier {}
+///
+/// impl CoseVerifyCipher for FakeVerifier {
+///     type Error = String;
+///
+///     fn verify_signature(&mut self, signature: &[u8], signed_data: &[u8]) -> Result<(), CoseCipherError<Self::Error>> {
+///         // This is obviously not how signatures should be verified.
+///         if signature != signed_data {
+///             Err(CoseCipherError::VerificationFailure)
+///         } else {
+///             Ok(())
+///         }
+///     }
+/// }
+/// ```
+pub trait CoseVerifyCipher {
+    /// Error type that this cipher uses in [`Result`]s returned by cryptographic operations.
+    type Error: Display + Debug;
+
+    /// Verifies the `signature` of the `signed_data`.
+    ///
+    /// # Errors
+    /// If the `signature` is invalid or does not belong to the `signed_data`.
+    fn verify_signature(
+        &mut self,
+        signature: &[u8],
+        signed_data: &[u8],
+    ) -> Result<(), CoseCipherError<Self::Error>>;
+
+    /// Returns the algorithms and key types this cipher supports, or `None` (the default)
+    /// if it doesn't declare them.
+    fn capabilities(&self) -> Option<CipherCapabilities> {
+        None
+    }
+}
+
+/// Provides the encrypting half of [`CoseEncrypt0Cipher`], as used by [`encrypt_access_token`].
+///
+/// Implement this trait directly if your cipher is only ever used to encrypt tokens (e.g., on the
+/// Authorization Server). Every [`CoseEncrypt0Cipher`] implements this trait as well.
+pub trait CoseEncryptCipher: CoseCipherCommon {
+    /// Encrypts the given `plaintext` and `aad`, returning the result.
+    fn encrypt(&mut self, plaintext: &[u8], aad: &[u8]) -> Vec<u8>;
+}
+
+/// Provides the decrypting half of [`CoseEncrypt0Cipher`], as used by [`decrypt_access_token`].
+///
+/// Implement this trait directly if your cipher is only ever used to decrypt tokens (e.g., on a
+/// Resource Server). As no headers need to be set for decryption, this trait doesn't require
+/// [`CoseCipherCommon`] to be implemented. Every [`CoseEncrypt0Cipher`] implements this trait
+/// as well.
+pub trait CoseDecryptCipher {
+    /// Error type that this cipher uses in [`Result`]s returned by cryptographic operations.
+    type Error: Display + Debug;
+
+    /// Decrypts the given `ciphertext` and `aad`, returning the result.
+    ///
+    /// # Errors
+    /// If the `ciphertext` and `aad` are invalid, i.e., can't be decrypted.
+    fn decrypt(
+        &mut self,
+        ciphertext: &[u8],
+        aad: &[u8],
+    ) -> Result<Vec<u8>, CoseCipherError<Self::Error>>;
+
+    /// Returns the algorithms and key types this cipher supports, or `None` (the default)
+    /// if it doesn't declare them.
+    fn capabilities(&self) -> Option<CipherCapabilities> {
+        None
+    }
+}
+
+impl<T> CoseSignCipher for T
+where
+    T: CoseSign1Cipher,
+{
+    fn generate_signature(&mut self, target: &[u8]) -> Vec<u8> {
+        CoseSign1Cipher::generate_signature(self, target)
+    }
+}
+
+impl<T> CoseVerifyCipher for T
+where
+    T: CoseSign1Cipher,
+{
+    type Error = <T as CoseCipherCommon>::Error;
+
+    fn verify_signature(
+        &mut self,
+        signature: &[u8],
+        signed_data: &[u8],
+    ) -> Result<(), CoseCipherError<Self::Error>> {
+        CoseSign1Cipher::verify_signature(self, signature, signed_data)
+    }
+
+    fn capabilities(&self) -> Option<CipherCapabilities> {
+        CoseCipherCommon::capabilities(self)
+    }
+}
+
+impl<T> CoseEncryptCipher for T
+where
+    T: CoseEncrypt0Cipher,
+{
+    fn encrypt(&mut self, plaintext: &[u8], aad: &[u8]) -> Vec<u8> {
+        CoseEncrypt0Cipher::encrypt(self, plaintext, aad)
+    }
+}
+
+impl<T> CoseDecryptCipher for T
+where
+    T: CoseEncrypt0Cipher,
+{
+    type Error = <T as CoseCipherCommon>::Error;
+
+    fn decrypt(
+        &mut self,
+        ciphertext: &[u8],
+        aad: &[u8],
+    ) -> Result<Vec<u8>, CoseCipherError<Self::Error>> {
+        CoseEncrypt0Cipher::decrypt(self, ciphertext, aad)
+    }
+
+    fn capabilities(&self) -> Option<CipherCapabilities> {
+        CoseCipherCommon::capabilities(self)
+    }
+}
+
 /// Creates new headers if `unprotected_header` or `protected_header` is `None`, respectively,
 /// and passes them to the `cipher`'s `header` function, returning the mutated result.
-fn prepare_headers<T>(
+pub(crate) fn prepare_headers<T>(
     unprotected_header: Option<Header>,
     protected_header: Option<Header>,
     cipher: &T,
@@ -371,9 +745,50 @@ where
     cipher
         .header(&mut unprotected, &mut protected)
         .map_err(AccessTokenError::from_cose_cipher_error)?;
+    canonicalize_header(&mut unprotected);
+    canonicalize_header(&mut protected);
     Ok((unprotected, protected))
 }
 
+/// Sorts the [`rest`](Header::rest) of the given `header` by the encoding of the labels, as
+/// required for [deterministic encoding](https://www.rfc-editor.org/rfc/rfc8949#section-4.2.1):
+/// Non-negative integers come first (in ascending order), followed by negative integers
+/// (in descending order) and then text labels (shorter ones first).
+///
+/// Since [`coset`] always encodes its natively supported parameters (with labels 1 to 7) first,
+/// the encoded header is then deterministic as well, unless it contains the unassigned label 0.
+/// This is done automatically for the headers of all tokens issued by this crate.
+///
+/// # Example
+/// ```
+/// # use ciborium::value::Value;
+/// # use coset::{HeaderBuilder, Label};
+/// use dcaf::token::canonicalize_header;
+///
+/// let mut header = HeaderBuilder::new()
+///     .text_value("kccs".to_string(), Value::Null)
+///     .value(-1, Value::Null)
+///     .value(47, Value::Null)
+///     .build();
+/// canonicalize_header(&mut header);
+/// let labels: Vec<Label> = header.rest.into_iter().map(|(label, _)| label).collect();
+/// assert_eq!(labels, vec![Label::Int(47), Label::Int(-1), Label::Text("kccs".to_string())]);
+/// ```
+pub fn canonicalize_header(header: &mut Header) {
+    header
+        .rest
+        .sort_by(|(a, _), (b, _)| label_sort_key(a).cmp(&label_sort_key(b)));
+}
+
+/// Returns a key for the given `label` by which labels are sorted like their encodings.
+fn label_sort_key(label: &Label) -> (u8, u64, &[u8]) {
+    match label {
+        Label::Int(x) if *x >= 0 => (0, x.unsigned_abs(), &[]),
+        Label::Int(x) => (1, x.unsigned_abs() - 1, &[]),
+        Label::Text(x) => (3, u64::try_from(x.len()).unwrap_or(u64::MAX), x.as_bytes()),
+    }
+}
+
 /// Encrypts the given `claims` with the given headers and `aad` using `cipher` for cryptography,
 /// returning the token as a serialized bytestring of the [`CoseEncrypt0`] structure.
 ///
@@ -439,7 +854,7 @@ pub fn encrypt_access_token<T>(
     protected_header: Option<Header>,
 ) -> Result<ByteString, AccessTokenError<T::Error>>
 where
-    T: CoseEncrypt0Cipher,
+    T: CoseEncryptCipher,
 {
     let (unprotected, protected) = prepare_headers(unprotected_header, protected_header, cipher)?;
     CoseEncrypt0Builder::new()
@@ -511,7 +926,7 @@ pub fn sign_access_token<T>(
     protected_header: Option<Header>,
 ) -> Result<ByteString, AccessTokenError<T::Error>>
 where
-    T: CoseSign1Cipher,
+    T: CoseSignCipher,
 {
     let (unprotected, protected) = prepare_headers(unprotected_header, protected_header, cipher)?;
     CoseSign1Builder::new()
@@ -530,6 +945,9 @@ where
 /// When the given `token` is neither a [`CoseEncrypt0`], [`CoseSign1`], nor a [`CoseMac0`]
 /// structure, `None` is returned.
 ///
+/// The returned [`ProtectedHeader`] keeps its [original encoding](ProtectedHeader::original_data),
+/// so that it's serialized exactly as received when the token is re-emitted.
+///
 /// # Example
 /// For example, say you have an access token saved in `token` and want to look at its headers:
 /// ```
@@ -562,6 +980,225 @@ pub fn get_token_headers(token: &ByteString) -> Option<(Header, ProtectedHeader)
         .ok()
 }
 
+/// Re-emits the given signed ([`CoseSign1`]), MAC tagged ([`CoseMac0`]), or encrypted
+/// ([`CoseEncrypt0`]) access token after applying `update` to its unprotected header, leaving
+/// everything else untouched.
+///
+/// This can be used by components forwarding tokens (e.g., to add a key ID hint for a different
+/// Resource Server front-end) without access to the key of the token, since the unprotected
+/// header is not covered by its signature, tag, or encryption. As described in the
+/// [module-level documentation](self#header-ordering), the protected header is re-emitted with
+/// its original encoding, so the protection of the returned token stays valid.
+///
+/// # Errors
+/// - When the given `token` is neither a [`CoseEncrypt0`], [`CoseSign1`], nor a [`CoseMac0`]
+///   structure ([`HeaderUpdateError::UnknownCoseStructure`]).
+/// - When `update` adds a header parameter which is already present in the protected header,
+///   which [RFC 9052](https://www.rfc-editor.org/rfc/rfc9052#section-3) forbids
+///   ([`HeaderUpdateError::ProtectedLabel`]).
+/// - When `update` modifies the IV or Partial IV, which are needed to decrypt the token
+///   ([`HeaderUpdateError::ImmutableParameter`]).
+/// - When the re-emitted token could not be serialized ([`HeaderUpdateError::CoseError`]) or
+///   its protected header, payload (or ciphertext), or signature (or tag) would not be
+///   byte-for-byte identical to those of `token` ([`HeaderUpdateError::ProtectedContentChanged`]).
+///
+/// # Example
+/// For example, say you have an access token saved in `token` and want to add a text hint
+/// to its unprotected header:
+/// ```
+/// # use ciborium::value::Value;
+/// # use coset::Label;
+/// # use dcaf::error::HeaderUpdateError;
+/// # use dcaf::token::{get_token_headers, update_unprotected_header};
+/// # let token = vec![
+/// # 0x84, 0x4b, 0xa2, 0x1, 0x25, 0x4, 0x46, 0x84, 0x9b, 0x57, 0x86, 0x45, 0x7c, 0xa2, 0x5, 0x4d,
+/// # 0x63, 0x68, 0x98, 0x99, 0x4f, 0xf0, 0xec, 0x7b, 0xfc, 0xf6, 0xd3, 0xf9, 0x5b, 0x18, 0x2f, 0xf6,
+/// # 0x58, 0x20, 0xa1, 0x8, 0xa3, 0x1, 0x4, 0x2, 0x46, 0x84, 0x9b, 0x57, 0x86, 0x45, 0x7c, 0x20,
+/// # 0x51, 0x84, 0x9b, 0x57, 0x86, 0x45, 0x7c, 0x14, 0x91, 0xbe, 0x3a, 0x76, 0xdc, 0xea, 0x6c, 0x42,
+/// # 0x71, 0x8, 0x58, 0x40, 0x84, 0x6a, 0x53, 0x69, 0x67, 0x6e, 0x61, 0x74, 0x75, 0x72, 0x65, 0x31,
+/// # 0x4b, 0xa2, 0x1, 0x25, 0x4, 0x46, 0x84, 0x9b, 0x57, 0x86, 0x45, 0x7c, 0x45, 0x1, 0x2, 0x3, 0x4,
+/// # 0x5, 0x58, 0x20, 0xa1, 0x8, 0xa3, 0x1, 0x4, 0x2, 0x46, 0x84, 0x9b, 0x57, 0x86, 0x45, 0x7c, 0x20,
+/// # 0x51, 0x84, 0x9b, 0x57, 0x86, 0x45, 0x7c, 0x14, 0x91, 0xbe, 0x3a, 0x76, 0xdc, 0xea, 0x6c, 0x42,
+/// # 0x71, 0x8];
+/// let hint = (Label::Text("rs".to_string()), Value::Text("front-end-2".to_string()));
+/// let updated = update_unprotected_header(&token, |header| header.rest.push(hint.clone()))?;
+/// let (unprotected, _) = get_token_headers(&updated).expect("token is valid");
+/// assert!(unprotected.rest.contains(&hint));
+///
+/// // The key ID is already part of the protected header.
+/// assert!(matches!(
+///     update_unprotected_header(&token, |header| header.key_id = vec![0xDC, 0xAF]),
+///     Err(HeaderUpdateError::ProtectedLabel(Label::Int(4)))
+/// ));
+/// # Ok::<(), HeaderUpdateError>(())
+/// ```
+pub fn update_unprotected_header<F>(
+    token: &ByteString,
+    update: F,
+) -> Result<ByteString, HeaderUpdateError>
+where
+    F: FnOnce(&mut Header),
+{
+    if let Ok(sign) = CoseSign1::from_slice(token.as_slice()) {
+        reemit_with_update(sign, update)
+    } else if let Ok(encrypt) = CoseEncrypt0::from_slice(token.as_slice()) {
+        reemit_with_update(encrypt, update)
+    } else if let Ok(mac) = CoseMac0::from_slice(token.as_slice()) {
+        reemit_with_update(mac, update)
+    } else {
+        Err(HeaderUpdateError::UnknownCoseStructure)
+    }
+}
+
+/// A COSE structure containing an access token, whose unprotected header can be updated by
+/// [`update_unprotected_header`].
+trait TokenStructure: CborSerializable + Clone {
+    /// Returns the protected and the unprotected header.
+    fn headers(&mut self) -> (&ProtectedHeader, &mut Header);
+
+    /// Returns the encoded protected header, the payload (or ciphertext), and the signature
+    /// (or tag), i.e., everything which must not be modified when re-emitting the structure.
+    fn protected_content(&self) -> (Option<&[u8]>, Option<&[u8]>, &[u8]);
+}
+
+impl TokenStructure for CoseSign1 {
+    fn headers(&mut self) -> (&ProtectedHeader, &mut Header) {
+        (&self.protected, &mut self.unprotected)
+    }
+
+    fn protected_content(&self) -> (Option<&[u8]>, Option<&[u8]>, &[u8]) {
+        (
+            self.protected.original_data.as_deref(),
+            self.payload.as_deref(),
+            &self.signature,
+        )
+    }
+}
+
+impl TokenStructure for CoseEncrypt0 {
+    fn headers(&mut self) -> (&ProtectedHeader, &mut Header) {
+        (&self.protected, &mut self.unprotected)
+    }
+
+    fn protected_content(&self) -> (Option<&[u8]>, Option<&[u8]>, &[u8]) {
+        (
+            self.protected.original_data.as_deref(),
+            self.ciphertext.as_deref(),
+            &[],
+        )
+    }
+}
+
+impl TokenStructure for CoseMac0 {
+    fn headers(&mut self) -> (&ProtectedHeader, &mut Header) {
+        (&self.protected, &mut self.unprotected)
+    }
+
+    fn protected_content(&self) -> (Option<&[u8]>, Option<&[u8]>, &[u8]) {
+        (
+            self.protected.original_data.as_deref(),
+            self.payload.as_deref(),
+            &self.tag,
+        )
+    }
+}
+
+/// Applies `update` to the unprotected header of the given parsed `structure` and re-emits it,
+/// checking that its protected content is unchanged.
+fn reemit_with_update<S, F>(mut structure: S, update: F) -> Result<ByteString, HeaderUpdateError>
+where
+    S: TokenStructure,
+    F: FnOnce(&mut Header),
+{
+    let original = structure.clone();
+    let (protected, unprotected) = structure.headers();
+    let previous = unprotected.clone();
+    update(unprotected);
+    for (label, changed) in [
+        (HeaderParameter::Iv, previous.iv != unprotected.iv),
+        (
+            HeaderParameter::PartialIv,
+            previous.partial_iv != unprotected.partial_iv,
+        ),
+    ] {
+        if changed {
+            return Err(HeaderUpdateError::ImmutableParameter(Label::Int(
+                label.to_i64(),
+            )));
+        }
+    }
+    let protected_labels = header_labels(&protected.header);
+    if let Some(label) = header_labels(unprotected)
+        .into_iter()
+        .find(|x| protected_labels.contains(x))
+    {
+        return Err(HeaderUpdateError::ProtectedLabel(label));
+    }
+
+    let token = structure.to_vec().map_err(HeaderUpdateError::CoseError)?;
+    let reemitted = S::from_slice(token.as_slice()).map_err(HeaderUpdateError::CoseError)?;
+    if reemitted.protected_content() == original.protected_content() {
+        Ok(token)
+    } else {
+        Err(HeaderUpdateError::ProtectedContentChanged)
+    }
+}
+
+/// Returns the labels of all parameters present in the given `header`.
+fn header_labels(header: &Header) -> Vec<Label> {
+    let mut labels = Vec::new();
+    for (parameter, present) in [
+        (HeaderParameter::Alg, header.alg.is_some()),
+        (HeaderParameter::Crit, !header.crit.is_empty()),
+        (HeaderParameter::ContentType, header.content_type.is_some()),
+        (HeaderParameter::Kid, !header.key_id.is_empty()),
+        (HeaderParameter::Iv, !header.iv.is_empty()),
+        (HeaderParameter::PartialIv, !header.partial_iv.is_empty()),
+        (
+            HeaderParameter::CounterSignature,
+            !header.counter_signatures.is_empty(),
+        ),
+    ] {
+        if present {
+            labels.push(Label::Int(parameter.to_i64()));
+        }
+    }
+    labels.extend(header.rest.iter().map(|(label, _)| label.clone()));
+    labels
+}
+
+/// Returns the client nonce contained in the `cnonce` claim of the given `claims`, or `None` if
+/// there is no such claim or it isn't a byte string.
+///
+/// The `cnonce` claim echoes the [`client_nonce`](crate::AccessTokenRequest::client_nonce) of the
+/// request the token was issued for, which in turn stems from the
+/// [creation hint](crate::AuthServerRequestCreationHint::client_nonce) of the Resource Server.
+/// This way, a Resource Server without a synchronized clock can tell that a token is fresh, as
+/// described in [section 5.3 of RFC 9200](https://www.rfc-editor.org/rfc/rfc9200#section-5.3).
+///
+/// # Example
+/// ```
+/// # use ciborium::value::Value;
+/// # use coset::cwt::ClaimsSetBuilder;
+/// # use coset::iana::CwtClaimName;
+/// use dcaf::token::get_client_nonce;
+///
+/// let claims = ClaimsSetBuilder::new()
+///     .claim(CwtClaimName::CNonce, Value::Bytes(vec![0xDC, 0xAF]))
+///     .build();
+/// assert_eq!(get_client_nonce(&claims), Some(&vec![0xDC, 0xAF]));
+/// assert_eq!(get_client_nonce(&ClaimsSetBuilder::new().build()), None);
+/// ```
+#[must_use]
+pub fn get_client_nonce(claims: &ClaimsSet) -> Option<&ByteString> {
+    let cnonce = ClaimName::Assigned(CwtClaimName::CNonce);
+    claims
+        .rest
+        .iter()
+        .find(|(name, _)| name == &cnonce)
+        .and_then(|(_, value)| value.as_bytes())
+}
+
 /// Verifies the given `token` and `aad` using `verifier` for cryptography,
 /// returning an error in case it could not be verified.
 ///
@@ -581,7 +1218,7 @@ pub fn verify_access_token<T>(
     aad: Option<&[u8]>,
 ) -> Result<(), AccessTokenError<T::Error>>
 where
-    T: CoseSign1Cipher,
+    T: CoseVerifyCipher,
 {
     let sign = CoseSign1::from_slice(token.as_slice()).map_err(AccessTokenError::CoseError)?;
     // TODO: Verify protected headers
@@ -609,7 +1246,7 @@ pub fn decrypt_access_token<T>(
     aad: Option<&[u8]>,
 ) -> Result<ClaimsSet, AccessTokenError<T::Error>>
 where
-    T: CoseEncrypt0Cipher,
+    T: CoseDecryptCipher,
 {
     let encrypt =
         CoseEncrypt0::from_slice(token.as_slice()).map_err(AccessTokenError::from_cose_error)?;
@@ -620,3 +1257,180 @@ where
         .map_err(AccessTokenError::from_cose_cipher_error)?;
     ClaimsSet::from_slice(result.as_slice()).map_err(AccessTokenError::from_cose_error)
 }
+
+/// Verifies the given `token` and `aad` using `cipher` for cryptography like
+/// [`verify_access_token`], but additionally enforces the given `config`, returning the
+/// verified `ClaimsSet`.
+///
+/// Before verifying the signature, the `token` (including its protected header) is checked
+/// against the [`max_message_size`](SecurityConfig::max_message_size),
+/// [`deterministic_encoding`](SecurityConfig::deterministic_encoding), and
+/// [`duplicates`](SecurityConfig::duplicates) settings, and the algorithm in its protected header
+/// against [`allowed_algorithms`](SecurityConfig::allowed_algorithms). Afterwards, the encoding
+/// of the signed claims is checked the same way, their proof-of-possession key (if any) against
+/// [`key_strength`](SecurityConfig::key_strength), and the claims themselves using the
+/// [`validation_policy`](SecurityConfig::validation_policy) of the `config`, which only accepts
+/// tokens for the given `audience` of this Resource Server. If the Resource Server has a
+/// synchronized clock, the current time should be given as `now` (in seconds since the epoch),
+/// so that the policy is applied using [`validate_at`](validation::ValidationPolicy::validate_at)
+/// instead of [`validate`](validation::ValidationPolicy::validate).
+///
+/// # Errors
+/// - When the `token` is rejected by the `config` ([`AccessTokenError::SecurityConfig`]).
+/// - When the claims are rejected by the validation policy ([`AccessTokenError::InvalidClaims`]).
+/// - As for [`verify_access_token`], or when the payload of the `token` isn't a valid
+///   [`ClaimsSet`].
+#[cfg(feature = "rs")]
+pub fn verify_access_token_with_config<T>(
+    token: &ByteString,
+    cipher: &mut T,
+    aad: Option<&[u8]>,
+    config: &SecurityConfig,
+    audience: &str,
+    now: Option<i64>,
+) -> Result<ClaimsSet, AccessTokenError<T::Error>>
+where
+    T: CoseVerifyCipher,
+{
+    config
+        .check_size(token)
+        .and_then(|()| config.check_encoding(token))
+        .map_err(AccessTokenError::SecurityConfig)?;
+    let sign = CoseSign1::from_slice(token.as_slice()).map_err(AccessTokenError::CoseError)?;
+    check_protected_header(&sign.protected, config)?;
+    sign.verify_signature(aad.unwrap_or(&[0; 0]), |signature, signed_data| {
+        cipher.verify_signature(signature, signed_data)
+    })
+    .map_err(AccessTokenError::from_cose_cipher_error)?;
+    check_received_claims(
+        sign.payload.as_deref().unwrap_or_default(),
+        config,
+        audience,
+        now,
+    )
+}
+
+/// Verifies the MAC tag of the given `COSE_Mac0` `token` and `aad` using `cipher` for
+/// cryptography, enforcing the given `config` like [`verify_access_token_with_config`] and
+/// returning the verified `ClaimsSet`.
+///
+/// # Errors
+/// - When the `token` is rejected by the `config` ([`AccessTokenError::SecurityConfig`]).
+/// - When the claims are rejected by the validation policy ([`AccessTokenError::InvalidClaims`]).
+/// - When the `token` isn't a valid [`CoseMac0`] structure, when its tag is invalid, or when its
+///   payload isn't a valid [`ClaimsSet`].
+#[cfg(feature = "rs")]
+pub fn verify_access_token_tag_with_config<T>(
+    token: &ByteString,
+    cipher: &mut T,
+    aad: Option<&[u8]>,
+    config: &SecurityConfig,
+    audience: &str,
+    now: Option<i64>,
+) -> Result<ClaimsSet, AccessTokenError<T::Error>>
+where
+    T: CoseMac0Cipher,
+{
+    config
+        .check_size(token)
+        .and_then(|()| config.check_encoding(token))
+        .map_err(AccessTokenError::SecurityConfig)?;
+    let mac = CoseMac0::from_slice(token.as_slice()).map_err(AccessTokenError::CoseError)?;
+    check_protected_header(&mac.protected, config)?;
+    mac.verify_tag(aad.unwrap_or(&[0; 0]), |tag, maced_data| {
+        cipher.verify_tag(tag, maced_data)
+    })
+    .map_err(AccessTokenError::from_cose_cipher_error)?;
+    check_received_claims(
+        mac.payload.as_deref().unwrap_or_default(),
+        config,
+        audience,
+        now,
+    )
+}
+
+/// Decrypts the given `token` and `aad` using `cipher` for cryptography like
+/// [`decrypt_access_token`], but additionally enforces the given `config` like
+/// [`verify_access_token_with_config`], returning the decrypted `ClaimsSet`.
+///
+/// # Errors
+/// - When the `token` is rejected by the `config` ([`AccessTokenError::SecurityConfig`]).
+/// - When the claims are rejected by the validation policy ([`AccessTokenError::InvalidClaims`]).
+/// - As for [`decrypt_access_token`].
+#[cfg(feature = "rs")]
+pub fn decrypt_access_token_with_config<T>(
+    token: &ByteString,
+    cipher: &mut T,
+    aad: Option<&[u8]>,
+    config: &SecurityConfig,
+    audience: &str,
+    now: Option<i64>,
+) -> Result<ClaimsSet, AccessTokenError<T::Error>>
+where
+    T: CoseDecryptCipher,
+{
+    config
+        .check_size(token)
+        .and_then(|()| config.check_encoding(token))
+        .map_err(AccessTokenError::SecurityConfig)?;
+    let encrypt =
+        CoseEncrypt0::from_slice(token.as_slice()).map_err(AccessTokenError::from_cose_error)?;
+    check_protected_header(&encrypt.protected, config)?;
+    let result = encrypt
+        .decrypt(aad.unwrap_or(&[0; 0]), |ciphertext, aad| {
+            cipher.decrypt(ciphertext, aad)
+        })
+        .map_err(AccessTokenError::from_cose_cipher_error)?;
+    check_received_claims(&result, config, audience, now)
+}
+
+/// Checks the encoding of the given received `protected` header of a token, as well as the
+/// algorithm given in it, against the given `config`.
+#[cfg(feature = "rs")]
+fn check_protected_header<E>(
+    protected: &ProtectedHeader,
+    config: &SecurityConfig,
+) -> Result<(), AccessTokenError<E>>
+where
+    E: Display,
+{
+    match protected.original_data.as_deref() {
+        // An empty protected header is encoded as an empty byte string.
+        Some(header) if !header.is_empty() => config.check_encoding(header),
+        _ => Ok(()),
+    }
+    .and_then(|()| config.check_algorithm(protected))
+    .map_err(AccessTokenError::SecurityConfig)
+}
+
+/// Decodes the given verified (or decrypted) `payload` of a token into its claims, which are
+/// checked against the given `config` for the given `audience` (at the given time `now`, if any).
+#[cfg(feature = "rs")]
+fn check_received_claims<E>(
+    payload: &[u8],
+    config: &SecurityConfig,
+    audience: &str,
+    now: Option<i64>,
+) -> Result<ClaimsSet, AccessTokenError<E>>
+where
+    E: Display,
+{
+    config
+        .check_encoding(payload)
+        .map_err(AccessTokenError::SecurityConfig)?;
+    let claims = ClaimsSet::from_slice(payload).map_err(AccessTokenError::from_cose_error)?;
+    config
+        .key_strength
+        .check_claims(&claims)
+        .map_err(|e| AccessTokenError::SecurityConfig(SecurityConfigError::WeakKey(e)))?;
+    let policy = validation::ValidationPolicy {
+        audiences: Vec::from([audience.into()]),
+        ..config.validation_policy()
+    };
+    match now {
+        Some(now) => policy.validate_at(&claims, now),
+        None => policy.validate(&claims),
+    }
+    .map_err(AccessTokenError::InvalidClaims)?;
+    Ok(claims)
+}
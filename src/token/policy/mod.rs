@@ -0,0 +1,1216 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+//! Contains a declarative issuance policy for simple Authorization Servers.
+//!
+//! An [`IssuancePolicy`] describes which clients may obtain tokens for which audiences,
+//! along with the allowed scopes, token lifetimes, profiles, and algorithms.
+//! As it implements [`Deserialize`], it can be loaded from any format supported by serde
+//! (e.g., TOML, JSON, or CBOR), so that simple Authorization Servers don't need any custom
+//! policy code. Scopes are given in their CBOR representation, i.e., as a string for text-encoded
+//! scopes or as an array of `[path, permissions]` pairs for AIF-encoded scopes, while profiles
+//! and algorithms are given as their integer abbreviations.
+//!
+//! [`IssuancePolicy::evaluate`] decides on an [`AccessTokenRequest`] of a client, returning
+//! an [`IssuanceGrant`] which describes the token to issue.
+//! Scopes using an encoding unknown to this crate ([`Custom`](Scope::Custom) scopes) can be
+//! granted using [`IssuancePolicy::evaluate_with_codecs`], which dispatches them to the
+//! application-provided codecs of a [`CustomScopeRegistry`].
+//!
+//! # Scope Shrinking
+//! Operators can narrow the scope permitted by the policy further, based on information the
+//! policy can't express (e.g., the time of day or the posture of the client's device), by passing
+//! a [`ScopeShrinker`] to [`IssuancePolicy::evaluate_with`]. The shrunken scope is part of the
+//! returned [`IssuanceGrant`], and is hence reflected in both the [claims](IssuanceGrant::claims)
+//! of the token and the [response](IssuanceGrant::response) to the client.
+//!
+//! # Token Exchange
+//! Clients flagged with [`token_exchange`](ClientPolicy::token_exchange) (e.g., gateways acting
+//! on behalf of sensors) may exchange an access token for one targeting a different audience
+//! using a [`TokenExchangeRequest`]. [`IssuancePolicy::evaluate_exchange`] decides on such
+//! requests, granting no more than the client may obtain by itself, and no more than the
+//! exchanged token grants. The new token names the subject of the exchanged token and the party
+//! acting on its behalf, as described in
+//! [section 4.1 of RFC 8693](https://www.rfc-editor.org/rfc/rfc8693#section-4.1).
+//!
+//! # Token Lifetimes
+//! Besides its fixed [`lifetime`](AudiencePolicy::lifetime), each audience can have a
+//! [`LifetimePolicy`] encoding organizational rules for token lifetimes: a hard cap, a percentage
+//! of the lifetime which would otherwise be granted, and a sliding renewal window limiting how long
+//! a client may wait between renewals (see [`IssuanceGrant::renew`]) before it has to go through
+//! the full authorization again.
+//!
+//! # Reloading
+//! Together with the [`AudienceKeyRing`], the policy forms the [`AuthServerConfig`].
+//! With the `std` feature enabled, a `ReloadableConfig` allows replacing this configuration
+//! at runtime (e.g., to rotate keys or register new clients) without restarting the token
+//! endpoint. New configurations are [validated](AuthServerConfig::validate) before they're
+//! activated, and requests which are already being processed keep using the configuration they
+//! started with.
+//!
+//! # Example
+//! ```
+//! # use dcaf::{AccessTokenRequest, AceProfile, Scope};
+//! # use dcaf::error::IssuancePolicyError;
+//! use dcaf::token::policy::{AudiencePolicy, ClientPolicy, IssuancePolicy};
+//!
+//! # #[cfg(feature = "std")] {
+//! // This would usually be deserialized from a configuration file.
+//! let mut policy = IssuancePolicy::default();
+//! let mut audience = AudiencePolicy::new("tempSensor4711", 3600);
+//! audience.profiles = vec![AceProfile::CoapOscore];
+//! policy.audiences.push(audience);
+//! let mut client = ClientPolicy::new("myclient");
+//! client.scope = Some(Scope::try_from(vec!["rTempC", "rHum"])?);
+//! client.lifetime = Some(600);
+//! policy.clients.push(client);
+//!
+//! let request = AccessTokenRequest::builder()
+//!     .audience("tempSensor4711")
+//!     .scope(Scope::try_from(vec!["rTempC", "wLed"])?)
+//!     .build()?;
+//! let grant = policy.evaluate("myclient", &request)?;
+//! assert_eq!(grant.scope, Some(Scope::try_from(vec!["rTempC"])?));
+//! assert_eq!(grant.lifetime, 600);
+//! assert_eq!(grant.profile, Some(AceProfile::CoapOscore));
+//! assert_eq!(
+//!     policy.evaluate("otherclient", &request),
+//!     Err(IssuancePolicyError::UnknownClient("otherclient".to_string()))
+//! );
+//! # }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use ciborium::value::Value;
+use coset::cwt::{ClaimName, ClaimsSet, ClaimsSetBuilder, Timestamp};
+use coset::iana::CwtClaimName;
+use serde::{Deserialize, Serialize};
+
+use crate::common::cbor_values::ByteString;
+use crate::common::cose::Algorithm;
+use crate::common::scope::CustomScopeRegistry;
+use crate::common::security::KeyStrengthPolicy;
+use crate::constants::token_type_identifiers;
+use crate::endpoints::token_req::grant::TokenExchangeRequest;
+use crate::endpoints::token_req::{AccessTokenResponse, AccessTokenResponseBuilder, ErrorResponse};
+use crate::error::IssuancePolicyError;
+use crate::error::{CustomScopeError, InvalidConfigError};
+use crate::token::keys::AudienceKeyRing;
+use crate::token::storage::{ClientRecord, ResourceServerRecord};
+use crate::token::CipherCapabilities;
+use crate::{AccessTokenRequest, AceProfile, Scope};
+
+#[cfg(feature = "std")]
+use std::sync::{Arc, PoisonError, RwLock};
+#[cfg(not(feature = "std"))]
+use {
+    alloc::string::{String, ToString},
+    alloc::vec::Vec,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Text name of the `act` claim of [RFC 8693](https://www.rfc-editor.org/rfc/rfc8693#section-4.1),
+/// for which no CWT claim key has been registered.
+const ACT_CLAIM: &str = "act";
+
+/// Declarative issuance policy of an Authorization Server.
+///
+/// For an example, see the [module-level documentation](self).
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub struct IssuancePolicy {
+    /// The clients which may obtain tokens.
+    #[serde(default)]
+    pub clients: Vec<ClientPolicy>,
+
+    /// The audiences tokens may be issued for.
+    #[serde(default)]
+    pub audiences: Vec<AudiencePolicy>,
+
+    /// The requirements for the proof-of-possession keys requested by clients.
+    #[serde(default)]
+    pub key_strength: KeyStrengthPolicy,
+}
+
+/// Part of an [`IssuancePolicy`] describing a single client.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ClientPolicy {
+    /// The identifier of the client, as used in the `client_id` parameter.
+    pub client_id: String,
+
+    /// The audiences the client may obtain tokens for. If this is empty, all audiences
+    /// of the policy are allowed.
+    #[serde(default)]
+    pub audiences: Vec<String>,
+
+    /// The audience tokens are issued for if the client's request doesn't specify one, as
+    /// allowed by [section 5.8.1 of RFC 9200](https://www.rfc-editor.org/rfc/rfc9200#section-5.8.1),
+    /// or `None` if such requests are rejected.
+    #[serde(default)]
+    pub default_audience: Option<String>,
+
+    /// The maximum scope the client may obtain, or `None` if it's not restricted.
+    #[serde(default)]
+    pub scope: Option<Scope>,
+
+    /// The maximum lifetime of the client's tokens in seconds, or `None` if only the
+    /// lifetime of the audience applies.
+    #[serde(default)]
+    pub lifetime: Option<u32>,
+
+    /// The profiles the client supports. If this is empty, all profiles are assumed to be
+    /// supported.
+    #[serde(default)]
+    pub profiles: Vec<AceProfile>,
+
+    /// The redirection URIs registered for the client
+    /// (see [`AccessTokenRequest::validate_redirect_uri`]).
+    #[serde(default)]
+    pub redirect_uris: Vec<String>,
+
+    /// Whether the client may exchange access tokens for new ones
+    /// (see [`IssuancePolicy::evaluate_exchange`]).
+    #[serde(default)]
+    pub token_exchange: bool,
+}
+
+/// Part of an [`IssuancePolicy`] describing a single audience (i.e., a Resource Server).
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct AudiencePolicy {
+    /// The name of the audience.
+    pub audience: String,
+
+    /// The scope to grant if the client doesn't request a specific one, if any.
+    #[serde(default)]
+    pub default_scope: Option<Scope>,
+
+    /// The lifetime of tokens for this audience in seconds.
+    pub lifetime: u32,
+
+    /// The profiles the Resource Server supports, in order of preference.
+    #[serde(default)]
+    pub profiles: Vec<AceProfile>,
+
+    /// The COSE algorithms which may be used to protect tokens for this audience,
+    /// in order of preference.
+    #[serde(default)]
+    pub algorithms: Vec<Algorithm>,
+
+    /// Further restrictions on the lifetime of tokens for this audience.
+    #[serde(default)]
+    pub lifetime_policy: LifetimePolicy,
+}
+
+/// Restrictions on the lifetime of the tokens of an audience, as part of its [`AudiencePolicy`].
+///
+/// By default, no restrictions apply.
+///
+/// # Example
+/// ```
+/// use dcaf::token::policy::LifetimePolicy;
+///
+/// let policy = LifetimePolicy {
+///     max_lifetime: Some(1800),
+///     percentage: Some(50),
+///     renewal_window: Some(7200),
+/// };
+/// assert_eq!(policy.lifetime(600), 300);
+/// assert_eq!(policy.lifetime(86400), 1800);
+/// // A token last renewed 7000 seconds ago can still be renewed, which restarts the window.
+/// assert_eq!(policy.renewal_lifetime(3600, 1_000, 8_000), Some(1800));
+/// // Once the window since the last renewal has passed, tokens can't be renewed anymore.
+/// assert_eq!(policy.renewal_lifetime(3600, 1_000, 8_200), None);
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Default, Serialize, Deserialize)]
+pub struct LifetimePolicy {
+    /// The maximum lifetime of tokens in seconds, which is never exceeded regardless of the
+    /// lifetimes configured for the audience and the client.
+    #[serde(default)]
+    pub max_lifetime: Option<u32>,
+
+    /// The percentage of the requested lifetime (i.e., the shorter one of the client's and the
+    /// audience's) to grant, e.g., to hand out shorter tokens to an audience while keeping the
+    /// relative differences between clients.
+    ///
+    /// Must be in the range of 1 to 100, which is checked by [`IssuancePolicy::validate`].
+    /// Values outside of it are clamped to it.
+    #[serde(default)]
+    pub percentage: Option<u8>,
+
+    /// The length of the sliding renewal window in seconds, starting anew whenever a token of a
+    /// series of renewed tokens is issued. Tokens can only be renewed within this window, and no
+    /// renewed token may be valid for longer than it. If this is `None`, tokens can be renewed
+    /// indefinitely.
+    #[serde(default)]
+    pub renewal_window: Option<u32>,
+}
+
+/// The configuration of an Authorization Server, consisting of its [`IssuancePolicy`] and the
+/// keys of its audiences.
+///
+/// For reloading the configuration at runtime, see `ReloadableConfig` (requires the `std` feature).
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct AuthServerConfig {
+    /// The issuance policy.
+    pub policy: IssuancePolicy,
+
+    /// The keys used to protect the tokens of each audience.
+    pub keys: AudienceKeyRing,
+}
+
+/// Holds the current [`AuthServerConfig`] of an Authorization Server and allows replacing it
+/// atomically at runtime.
+///
+/// Each request should call [`current`](ReloadableConfig::current) once and use the returned
+/// snapshot throughout, so that it's processed consistently even if the configuration is
+/// [reloaded](ReloadableConfig::reload) concurrently.
+///
+/// # Example
+/// ```
+/// # use dcaf::error::InvalidConfigError;
+/// use dcaf::token::policy::{AudiencePolicy, AuthServerConfig, ClientPolicy, ReloadableConfig};
+///
+/// let config = ReloadableConfig::new(AuthServerConfig::default())?;
+/// let snapshot = config.current();
+///
+/// let mut updated = AuthServerConfig::default();
+/// updated.policy.clients.push(ClientPolicy::new("myclient"));
+/// config.reload(updated.clone())?;
+/// assert_eq!(*config.current(), updated);
+/// assert_eq!(config.generation(), 1);
+/// // Snapshots taken before the reload are unaffected.
+/// assert_eq!(*snapshot, AuthServerConfig::default());
+///
+/// // Invalid configurations are rejected and the current one is kept.
+/// updated.policy.audiences.push(AudiencePolicy::new("tempSensor4711", 3600));
+/// assert_eq!(
+///     config.reload(updated),
+///     Err(InvalidConfigError::MissingKey("tempSensor4711".to_string()))
+/// );
+/// assert_eq!(config.generation(), 1);
+/// # Ok::<(), InvalidConfigError>(())
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct ReloadableConfig {
+    current: RwLock<(u64, Arc<AuthServerConfig>)>,
+}
+
+/// Description of the access token to issue, as decided by [`IssuancePolicy::evaluate`].
+#[derive(Debug, PartialEq, Clone)]
+pub struct IssuanceGrant {
+    /// The audience of the token.
+    pub audience: String,
+
+    /// The granted scope, which may be narrower than the requested one.
+    pub scope: Option<Scope>,
+
+    /// The lifetime of the token in seconds.
+    pub lifetime: u32,
+
+    /// The profile to use, if the audience specifies any or the client requested it to be
+    /// included in the response.
+    pub profile: Option<AceProfile>,
+
+    /// The algorithm to protect the token with, if the audience specifies any.
+    pub algorithm: Option<Algorithm>,
+
+    /// The [`client_nonce`](AccessTokenRequest::client_nonce) of the request, which has to be
+    /// echoed back in the `cnonce` claim of the token.
+    pub client_nonce: Option<ByteString>,
+
+    /// The [`LifetimePolicy`] of the audience, which has already been applied to the
+    /// [`lifetime`](IssuanceGrant::lifetime) and is used again when [renewing](IssuanceGrant::renew)
+    /// the token.
+    pub lifetime_policy: LifetimePolicy,
+
+    /// The subject of the token, which is only set for
+    /// [exchanged tokens](IssuancePolicy::evaluate_exchange) and included in their `sub` claim.
+    pub subject: Option<String>,
+
+    /// The value of the `act` claim describing the acting party (and any prior actors) of the
+    /// token, which is only set for [exchanged tokens](IssuancePolicy::evaluate_exchange).
+    ///
+    /// As no CWT claim key has been registered for the `act` claim of
+    /// [RFC 8693](https://www.rfc-editor.org/rfc/rfc8693#section-4.1), it's included under its
+    /// text name. Its value is a map of claims identifying the actor, i.e., containing its `sub`
+    /// claim and, for delegation chains, the `act` claim of the prior actor.
+    pub actor: Option<Value>,
+}
+
+/// A hook which narrows the scope of a token down further than the [`IssuancePolicy`] does,
+/// see [`IssuancePolicy::evaluate_with`].
+///
+/// This is implemented for all closures taking the same arguments as
+/// [`shrink`](ScopeShrinker::shrink).
+///
+/// # Example
+/// ```
+/// # use dcaf::{AccessTokenRequest, Scope};
+/// use dcaf::token::policy::{AudiencePolicy, ClientPolicy, IssuancePolicy};
+///
+/// # #[cfg(feature = "std")] {
+/// let mut policy = IssuancePolicy::default();
+/// policy.audiences.push(AudiencePolicy::new("tempSensor4711", 3600));
+/// policy.clients.push(ClientPolicy::new("myclient"));
+/// let request = AccessTokenRequest::builder()
+///     .audience("tempSensor4711")
+///     .scope(Scope::try_from(vec!["rTempC", "wLed"])?)
+///     .build()?;
+///
+/// // Outside of business hours, only reading is allowed.
+/// let business_hours = false;
+/// let mut read_only = |_client: &str, _audience: &str, scope: Scope| {
+///     if business_hours { Some(scope) } else { Scope::try_from(vec!["rTempC"]).ok() }
+/// };
+/// let grant = policy.evaluate_with("myclient", &request, &mut read_only)?;
+/// assert_eq!(grant.scope, Some(Scope::try_from(vec!["rTempC"])?));
+/// # }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub trait ScopeShrinker {
+    /// Returns the scope to grant to the client identified by `client_id` for the given
+    /// `audience`, where `scope` is the scope permitted by the [`IssuancePolicy`],
+    /// or `None` if no scope at all should be granted.
+    ///
+    /// Any parts of the returned scope which exceed `scope` are ignored, so the policy can't be
+    /// circumvented by returning a wider scope.
+    fn shrink(&mut self, client_id: &str, audience: &str, scope: Scope) -> Option<Scope>;
+}
+
+impl<F> ScopeShrinker for F
+where
+    F: FnMut(&str, &str, Scope) -> Option<Scope>,
+{
+    fn shrink(&mut self, client_id: &str, audience: &str, scope: Scope) -> Option<Scope> {
+        self(client_id, audience, scope)
+    }
+}
+
+impl ClientPolicy {
+    /// Creates a new [`ClientPolicy`] for the given `client_id` which may obtain tokens for all
+    /// audiences, without any further restrictions.
+    #[must_use]
+    pub fn new<T>(client_id: T) -> ClientPolicy
+    where
+        T: Into<String>,
+    {
+        ClientPolicy {
+            client_id: client_id.into(),
+            audiences: Vec::new(),
+            default_audience: None,
+            scope: None,
+            lifetime: None,
+            profiles: Vec::new(),
+            redirect_uris: Vec::new(),
+            token_exchange: false,
+        }
+    }
+}
+
+impl AudiencePolicy {
+    /// Creates a new [`AudiencePolicy`] for the given `audience` with the given token
+    /// `lifetime` in seconds, without a default scope, profiles, or algorithms.
+    #[must_use]
+    pub fn new<T>(audience: T, lifetime: u32) -> AudiencePolicy
+    where
+        T: Into<String>,
+    {
+        AudiencePolicy {
+            audience: audience.into(),
+            default_scope: None,
+            lifetime,
+            profiles: Vec::new(),
+            algorithms: Vec::new(),
+            lifetime_policy: LifetimePolicy::default(),
+        }
+    }
+}
+
+impl LifetimePolicy {
+    /// Returns the lifetime in seconds to grant for a token whose `requested` lifetime is given
+    /// in seconds, i.e., the [`percentage`](LifetimePolicy::percentage) of it (clamped to the
+    /// range of 1 to 100), limited to the [`max_lifetime`](LifetimePolicy::max_lifetime).
+    #[must_use]
+    pub fn lifetime(&self, requested: u32) -> u32 {
+        let lifetime = self.percentage.map_or(requested, |percentage| {
+            let percentage = u64::from(percentage.clamp(1, 100));
+            // The result can't be larger than `requested` for percentages of up to 100.
+            u32::try_from(u64::from(requested) * percentage / 100).unwrap_or(requested)
+        });
+        self.max_lifetime.map_or(lifetime, |max| lifetime.min(max))
+    }
+
+    /// Returns the lifetime in seconds to grant for a token renewed at `now` (in seconds since
+    /// the Unix epoch) whose requested lifetime is `requested`, where the previous token of the
+    /// series of renewed tokens has been issued at `last_issued_at`.
+    ///
+    /// As the [`renewal_window`](LifetimePolicy::renewal_window) is a sliding one, it starts anew
+    /// with each renewal: this is the [`lifetime`](LifetimePolicy::lifetime) for the `requested`
+    /// one, shortened to the length of the window. Returns `None` if the window since the previous
+    /// token has already passed.
+    #[must_use]
+    pub fn renewal_lifetime(&self, requested: u32, last_issued_at: i64, now: i64) -> Option<u32> {
+        let lifetime = self.lifetime(requested);
+        match self.renewal_window {
+            None => Some(lifetime),
+            Some(window) => {
+                let window_end = last_issued_at.saturating_add(i64::from(window));
+                if now < window_end {
+                    Some(lifetime.min(window))
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+impl IssuancePolicy {
+    /// Checks that this policy is consistent, i.e., that client IDs and audiences are unique
+    /// and that clients are only allowed to obtain tokens for audiences which are part of it.
+    /// The default audience of a client must be part of the policy and allowed for the client,
+    /// and the [`percentage`](LifetimePolicy::percentage) of each audience must be in the range
+    /// of 1 to 100.
+    ///
+    /// # Errors
+    /// If the policy is inconsistent, with the variant of [`InvalidConfigError`] describing how.
+    pub fn validate(&self) -> Result<(), InvalidConfigError> {
+        for (index, client) in self.clients.iter().enumerate() {
+            if self.clients[..index]
+                .iter()
+                .any(|x| x.client_id == client.client_id)
+            {
+                return Err(InvalidConfigError::DuplicateClient(
+                    client.client_id.clone(),
+                ));
+            }
+            if let Some(audience) = client
+                .audiences
+                .iter()
+                .find(|x| !self.audiences.iter().any(|y| &y.audience == *x))
+            {
+                return Err(InvalidConfigError::UnknownAudience {
+                    client_id: client.client_id.clone(),
+                    audience: audience.clone(),
+                });
+            }
+            if let Some(audience) = &client.default_audience {
+                if !self.audiences.iter().any(|x| &x.audience == audience) {
+                    return Err(InvalidConfigError::UnknownAudience {
+                        client_id: client.client_id.clone(),
+                        audience: audience.clone(),
+                    });
+                }
+                if !client.audiences.is_empty() && !client.audiences.contains(audience) {
+                    return Err(InvalidConfigError::DefaultAudienceNotAllowed {
+                        client_id: client.client_id.clone(),
+                        audience: audience.clone(),
+                    });
+                }
+            }
+        }
+        for (index, audience) in self.audiences.iter().enumerate() {
+            if self.audiences[..index]
+                .iter()
+                .any(|x| x.audience == audience.audience)
+            {
+                return Err(InvalidConfigError::DuplicateAudience(
+                    audience.audience.clone(),
+                ));
+            }
+            if let Some(percentage) = audience
+                .lifetime_policy
+                .percentage
+                .filter(|x| !(1..=100).contains(x))
+            {
+                return Err(InvalidConfigError::InvalidPercentage {
+                    audience: audience.audience.clone(),
+                    percentage,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Decides on the given access token `request` of the client identified by `client_id`,
+    /// returning the [`IssuanceGrant`] describing the token to issue.
+    ///
+    /// - The requested audience must be part of the policy and allowed for the client.
+    ///   If the request doesn't specify an audience, the client's
+    ///   [`default_audience`](ClientPolicy::default_audience) is used instead.
+    /// - The requested scope (or the audience's default scope, if none has been requested)
+    ///   is narrowed down to the client's maximum scope (see [`Scope::diff`]).
+    /// - The lifetime is the shorter one of the client's and the audience's, restricted further
+    ///   by the audience's [`lifetime_policy`](AudiencePolicy::lifetime_policy).
+    /// - The profile is the first one of the audience's profiles supported by the client.
+    ///   If the audience doesn't specify any profiles, no profile is selected unless the
+    ///   request has the [`ace_profile`](AccessTokenRequest::ace_profile) flag set, in which case
+    ///   the first one of the client's profiles is selected, so that it can be included in the
+    ///   response as required by
+    ///   [section 5.8.4.3 of RFC 9200](https://www.rfc-editor.org/rfc/rfc9200#section-5.8.4.3).
+    /// - The algorithm is the first one of the audience's algorithms.
+    /// - The requested proof-of-possession key (if any) must meet the requirements of
+    ///   [`key_strength`](IssuancePolicy::key_strength).
+    ///
+    /// Note that the client still needs to be authenticated separately.
+    /// [`Custom`](Scope::Custom) scopes are only granted if they're equal to the client's maximum
+    /// scope, use [`evaluate_with_codecs`](IssuancePolicy::evaluate_with_codecs) to narrow them
+    /// down as well.
+    ///
+    /// # Errors
+    /// If the request is not allowed by this policy, with the variant of
+    /// [`IssuancePolicyError`] describing why.
+    pub fn evaluate(
+        &self,
+        client_id: &str,
+        request: &AccessTokenRequest,
+    ) -> Result<IssuanceGrant, IssuancePolicyError> {
+        self.evaluate_with_codecs(client_id, request, &CustomScopeRegistry::new())
+    }
+
+    /// Decides on the given access token `request` like [`evaluate`](IssuancePolicy::evaluate),
+    /// but uses the [`CustomScopeCodec`](crate::common::scope::CustomScopeCodec)s in the given
+    /// `codecs` registry to validate [`Custom`](Scope::Custom) scopes and to narrow them down to
+    /// the client's maximum scope.
+    ///
+    /// # Example
+    /// ```
+    /// # use dcaf::{AccessTokenRequest, Scope};
+    /// # use dcaf::common::scope::{CustomScopeCodec, CustomScopeRegistry};
+    /// # use dcaf::token::policy::{AudiencePolicy, ClientPolicy, IssuancePolicy};
+    /// struct Bitmask;
+    ///
+    /// impl CustomScopeCodec for Bitmask {
+    ///     fn validate(&self, scope: &[u8]) -> bool {
+    ///         scope.len() == 1
+    ///     }
+    ///
+    ///     fn narrow(&self, requested: &[u8], allowed: &[u8]) -> Option<Vec<u8>> {
+    ///         Some(vec![requested[0] & allowed[0]]).filter(|x| x[0] != 0)
+    ///     }
+    /// }
+    ///
+    /// # #[cfg(feature = "std")] {
+    /// let mut codecs = CustomScopeRegistry::new();
+    /// codecs.register(65000, Bitmask)?;
+    /// let mut client = ClientPolicy::new("myclient");
+    /// client.scope = Some(Scope::Custom { encoding_id: 65000, bytes: vec![0b1100] });
+    /// let policy = IssuancePolicy {
+    ///     clients: vec![client],
+    ///     audiences: vec![AudiencePolicy::new("coaps://rs.example.com", 3600)],
+    ///     ..IssuancePolicy::default()
+    /// };
+    /// let request = AccessTokenRequest::builder()
+    ///     .client_id("myclient")
+    ///     .audience("coaps://rs.example.com")
+    ///     .scope(Scope::Custom { encoding_id: 65000, bytes: vec![0b0111] })
+    ///     .build()?;
+    /// let grant = policy.evaluate_with_codecs("myclient", &request, &codecs)?;
+    /// assert_eq!(grant.scope, Some(Scope::Custom { encoding_id: 65000, bytes: vec![0b0100] }));
+    /// # }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    ///
+    /// # Errors
+    /// - If the request is not allowed by this policy, as for
+    ///   [`evaluate`](IssuancePolicy::evaluate).
+    /// - If the requested custom scope is invalid according to its registered codec
+    ///   ([`IssuancePolicyError::ScopeNotAllowed`]).
+    pub fn evaluate_with_codecs(
+        &self,
+        client_id: &str,
+        request: &AccessTokenRequest,
+        codecs: &CustomScopeRegistry,
+    ) -> Result<IssuanceGrant, IssuancePolicyError> {
+        let client = self
+            .clients
+            .iter()
+            .find(|x| x.client_id == client_id)
+            .ok_or_else(|| IssuancePolicyError::UnknownClient(client_id.to_string()))?;
+        let requested_audience = request
+            .audience
+            .as_deref()
+            .or(client.default_audience.as_deref())
+            .ok_or(IssuancePolicyError::MissingAudience)?;
+        let audience = self
+            .audiences
+            .iter()
+            .find(|x| x.audience == requested_audience)
+            .ok_or_else(|| IssuancePolicyError::UnknownAudience(requested_audience.to_string()))?;
+        if !client.audiences.is_empty() && !client.audiences.iter().any(|x| x == requested_audience)
+        {
+            return Err(IssuancePolicyError::AudienceNotAllowed(
+                requested_audience.to_string(),
+            ));
+        }
+        if let Some(key) = &request.req_cnf {
+            self.key_strength
+                .check(key)
+                .map_err(IssuancePolicyError::WeakKey)?;
+        }
+        let requested_scope = request.scope.as_ref().or(audience.default_scope.as_ref());
+        if let Some(requested) = requested_scope {
+            if let Err(CustomScopeError::InvalidScope(_)) = codecs.validate(requested) {
+                return Err(IssuancePolicyError::ScopeNotAllowed);
+            }
+        }
+        let scope = match (requested_scope, &client.scope) {
+            (Some(requested), Some(allowed)) => Some(narrow_scope(requested, allowed, codecs)?),
+            (Some(requested), None) => Some(requested.clone()),
+            (None, _) => None,
+        };
+        let profile = if audience.profiles.is_empty() {
+            // The profile must be included in the response if the client asked for it.
+            match client.profiles.first() {
+                Some(profile) if request.ace_profile.is_some() => Some(*profile),
+                None if request.ace_profile.is_some() => {
+                    return Err(IssuancePolicyError::UndeterminedProfile);
+                }
+                _ => None,
+            }
+        } else {
+            Some(
+                *audience
+                    .profiles
+                    .iter()
+                    .find(|x| client.profiles.is_empty() || client.profiles.contains(x))
+                    .ok_or(IssuancePolicyError::NoCommonProfile)?,
+            )
+        };
+        Ok(IssuanceGrant {
+            audience: audience.audience.clone(),
+            scope,
+            lifetime: audience.lifetime_policy.lifetime(
+                client
+                    .lifetime
+                    .map_or(audience.lifetime, |x| x.min(audience.lifetime)),
+            ),
+            profile,
+            algorithm: audience.algorithms.first().copied(),
+            client_nonce: request.client_nonce.clone(),
+            lifetime_policy: audience.lifetime_policy,
+            subject: None,
+            actor: None,
+        })
+    }
+
+    /// Decides on the given access token `request` like [`evaluate`](IssuancePolicy::evaluate),
+    /// but additionally lets the given `shrinker` narrow down the granted scope.
+    ///
+    /// The `shrinker` is only called if a scope would be granted at all.
+    ///
+    /// For an example, see the documentation of [`ScopeShrinker`].
+    ///
+    /// # Errors
+    /// - If the request is not allowed by this policy, as for
+    ///   [`evaluate`](IssuancePolicy::evaluate).
+    /// - If the `shrinker` leaves no part of the scope permitted by this policy
+    ///   ([`IssuancePolicyError::ScopeNotAllowed`]).
+    pub fn evaluate_with<S>(
+        &self,
+        client_id: &str,
+        request: &AccessTokenRequest,
+        shrinker: &mut S,
+    ) -> Result<IssuanceGrant, IssuancePolicyError>
+    where
+        S: ScopeShrinker,
+    {
+        let mut grant = self.evaluate(client_id, request)?;
+        if let Some(permitted) = grant.scope.take() {
+            let shrunk = shrinker
+                .shrink(client_id, &grant.audience, permitted.clone())
+                .ok_or(IssuancePolicyError::ScopeNotAllowed)?;
+            grant.scope = Some(narrow_scope(
+                &shrunk,
+                &permitted,
+                &CustomScopeRegistry::new(),
+            )?);
+        }
+        Ok(grant)
+    }
+
+    /// Decides on the given token exchange `request` of the client identified by `client_id`,
+    /// where `subject` contains the claims of the request's
+    /// [`subject_token`](TokenExchangeRequest::subject_token) and `actor` those of its
+    /// [`actor_token`](TokenExchangeRequest::actor_token) (if any), returning the
+    /// [`IssuanceGrant`] describing the new token to issue at `now` (in seconds since the Unix
+    /// epoch).
+    ///
+    /// The Authorization Server has to verify the subject and actor tokens itself (e.g., using
+    /// [`verify_access_token`](crate::verify_access_token) with the key of their original
+    /// audience) before passing their claims in here.
+    ///
+    /// - The client must be allowed to [exchange tokens](ClientPolicy::token_exchange).
+    /// - The subject token must be an
+    ///   [access token](crate::constants::token_type_identifiers::ACCESS_TOKEN) which hasn't
+    ///   expired yet, and must have been issued to the client (i.e., its `aud` claim must be the
+    ///   `client_id`), so that clients can't exchange tokens they merely got hold of.
+    /// - The `actor` claims must be given if and only if the request contains an actor token,
+    ///   which must then also be an unexpired access token with a `sub` claim.
+    /// - The request is then evaluated like a regular one by [`evaluate`](IssuancePolicy::evaluate).
+    /// - The granted scope is narrowed down to the scope of the subject token, if it has one.
+    ///   If neither the request nor the audience specify a scope, the scope of the subject token
+    ///   is used instead, narrowed down to the [scope](ClientPolicy::scope) of the client.
+    /// - The new token expires no later than the subject token.
+    /// - The new token has the same [`subject`](IssuanceGrant::subject) as the subject token, and
+    ///   its [`actor`](IssuanceGrant::actor) is the subject of the actor token or, if there is
+    ///   none, the client. If the subject token has an `act` claim itself, it's nested into the
+    ///   new one as the prior actor.
+    ///
+    /// # Example
+    /// ```
+    /// # use ciborium::value::Value;
+    /// # use coset::cwt::{ClaimsSetBuilder, Timestamp};
+    /// # use dcaf::constants::token_type_identifiers::ACCESS_TOKEN;
+    /// # use dcaf::endpoints::token_req::grant::TokenExchangeRequest;
+    /// use dcaf::token::policy::{AudiencePolicy, ClientPolicy, IssuancePolicy};
+    ///
+    /// # #[cfg(feature = "std")] {
+    /// let mut gateway = ClientPolicy::new("gateway");
+    /// gateway.token_exchange = true;
+    /// let policy = IssuancePolicy {
+    ///     clients: vec![gateway],
+    ///     audiences: vec![AudiencePolicy::new("backend", 3600)],
+    ///     ..IssuancePolicy::default()
+    /// };
+    /// // The gateway received this token from a sensor, and verified it.
+    /// let subject = ClaimsSetBuilder::new()
+    ///     .subject("sensor".to_string())
+    ///     .audience("gateway".to_string())
+    ///     .expiration_time(Timestamp::WholeSeconds(1_000_600))
+    ///     .build();
+    /// let request = TokenExchangeRequest::builder()
+    ///     .subject_token(vec![0xDC, 0xAF])
+    ///     .subject_token_type(ACCESS_TOKEN)
+    ///     .audience("backend")
+    ///     .build()?;
+    /// let grant = policy.evaluate_exchange("gateway", &request, &subject, None, 1_000_000)?;
+    /// assert_eq!(grant.audience, "backend");
+    /// assert_eq!(grant.lifetime, 600);
+    /// assert_eq!(grant.subject.as_deref(), Some("sensor"));
+    /// assert_eq!(
+    ///     grant.actor,
+    ///     Some(Value::Map(vec![(Value::from(2), Value::from("gateway"))]))
+    /// );
+    /// # }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    ///
+    /// # Errors
+    /// - If the client may not exchange tokens ([`IssuancePolicyError::ExchangeNotAllowed`]).
+    /// - If the subject token is not an access token, has already expired, or wasn't issued to
+    ///   the client ([`IssuancePolicyError::InvalidSubjectToken`]).
+    /// - If the actor token is invalid as described above
+    ///   ([`IssuancePolicyError::InvalidActorToken`]).
+    /// - If the request is not allowed by this policy, as for
+    ///   [`evaluate`](IssuancePolicy::evaluate).
+    /// - If no part of the granted scope is contained in the scope of the subject token
+    ///   ([`IssuancePolicyError::ScopeNotAllowed`]).
+    pub fn evaluate_exchange(
+        &self,
+        client_id: &str,
+        request: &TokenExchangeRequest,
+        subject: &ClaimsSet,
+        actor: Option<&ClaimsSet>,
+        now: i64,
+    ) -> Result<IssuanceGrant, IssuancePolicyError> {
+        self.evaluate_exchange_with_codecs(
+            client_id,
+            request,
+            subject,
+            actor,
+            now,
+            &CustomScopeRegistry::new(),
+        )
+    }
+
+    /// Decides on the given token exchange `request` like
+    /// [`evaluate_exchange`](IssuancePolicy::evaluate_exchange), but uses the
+    /// [`CustomScopeCodec`](crate::common::scope::CustomScopeCodec)s in the given `codecs`
+    /// registry to narrow [`Custom`](Scope::Custom) scopes down, as
+    /// [`evaluate_with_codecs`](IssuancePolicy::evaluate_with_codecs) does.
+    ///
+    /// # Errors
+    /// As for [`evaluate_exchange`](IssuancePolicy::evaluate_exchange) and
+    /// [`evaluate_with_codecs`](IssuancePolicy::evaluate_with_codecs).
+    pub fn evaluate_exchange_with_codecs(
+        &self,
+        client_id: &str,
+        request: &TokenExchangeRequest,
+        subject: &ClaimsSet,
+        actor: Option<&ClaimsSet>,
+        now: i64,
+        codecs: &CustomScopeRegistry,
+    ) -> Result<IssuanceGrant, IssuancePolicyError> {
+        let client = self
+            .clients
+            .iter()
+            .find(|x| x.client_id == client_id)
+            .ok_or_else(|| IssuancePolicyError::UnknownClient(client_id.to_string()))?;
+        if !client.token_exchange {
+            return Err(IssuancePolicyError::ExchangeNotAllowed);
+        }
+        if request.subject_token_type != token_type_identifiers::ACCESS_TOKEN
+            || subject.audience.as_deref() != Some(client_id)
+        {
+            return Err(IssuancePolicyError::InvalidSubjectToken);
+        }
+        let remaining = match remaining_lifetime(subject, now).map(u32::try_from) {
+            Some(Ok(remaining)) if remaining > 0 => Some(remaining),
+            Some(Ok(_) | Err(_)) => return Err(IssuancePolicyError::InvalidSubjectToken),
+            None => None,
+        };
+        let actor_id = match (actor, request.actor_token_type.as_deref()) {
+            (None, None) if request.actor_token.is_none() => client_id,
+            (Some(actor), Some(token_type_identifiers::ACCESS_TOKEN))
+                if request.actor_token.is_some()
+                    && remaining_lifetime(actor, now).map_or(true, |x| x > 0) =>
+            {
+                actor
+                    .subject
+                    .as_deref()
+                    .ok_or(IssuancePolicyError::InvalidActorToken)?
+            }
+            _ => return Err(IssuancePolicyError::InvalidActorToken),
+        };
+        let mut grant = self.evaluate_with_codecs(
+            client_id,
+            &AccessTokenRequest::from(request.clone()),
+            codecs,
+        )?;
+        let scope_name = ClaimName::Assigned(CwtClaimName::Scope);
+        if let Some((_, value)) = subject.rest.iter().find(|(name, _)| name == &scope_name) {
+            let delegated = Scope::try_from(value.clone())
+                .map_err(|_| IssuancePolicyError::InvalidSubjectToken)?;
+            grant.scope = Some(match (grant.scope, &client.scope) {
+                (Some(granted), _) => narrow_scope(&granted, &delegated, codecs)?,
+                (None, Some(allowed)) => narrow_scope(&delegated, allowed, codecs)?,
+                (None, None) => delegated,
+            });
+        }
+        if let Some(remaining) = remaining {
+            grant.lifetime = grant.lifetime.min(remaining);
+        }
+        let mut act = vec![(Value::from(CwtClaimName::Sub as i64), Value::from(actor_id))];
+        let act_name = ClaimName::Text(ACT_CLAIM.to_string());
+        if let Some((_, prior)) = subject.rest.iter().find(|(name, _)| name == &act_name) {
+            act.push((Value::from(ACT_CLAIM), prior.clone()));
+        }
+        grant.subject.clone_from(&subject.subject);
+        grant.actor = Some(Value::Map(act));
+        Ok(grant)
+    }
+
+    /// Returns the [`ErrorResponse`] with which the Authorization Server should answer the given
+    /// `request` of the client identified by `client_id` after it has been rejected with the
+    /// given `error` by [`evaluate`](IssuancePolicy::evaluate).
+    ///
+    /// The response uses the [error code](IssuancePolicyError::error_code) of the `error`.
+    /// If the client and the requested audience have no profile in common, the response also
+    /// advertises the audience's profiles in its
+    /// [`ace_profiles_supported`](ErrorResponse::ace_profiles_supported) parameter, so that the
+    /// client can retry its request with one of them.
+    ///
+    /// # Example
+    /// ```
+    /// # use dcaf::{AccessTokenRequest, AceProfile, ErrorCode};
+    /// # use dcaf::error::IssuancePolicyError;
+    /// use dcaf::token::policy::{AudiencePolicy, ClientPolicy, IssuancePolicy};
+    ///
+    /// # #[cfg(feature = "std")] {
+    /// let mut audience = AudiencePolicy::new("tempSensor4711", 3600);
+    /// audience.profiles = vec![AceProfile::CoapOscore];
+    /// let mut client = ClientPolicy::new("myclient");
+    /// client.profiles = vec![AceProfile::CoapDtls];
+    /// let policy = IssuancePolicy {
+    ///     clients: vec![client],
+    ///     audiences: vec![audience],
+    ///     ..IssuancePolicy::default()
+    /// };
+    ///
+    /// let request = AccessTokenRequest::builder().audience("tempSensor4711").build()?;
+    /// let error = policy.evaluate("myclient", &request).unwrap_err();
+    /// assert_eq!(error, IssuancePolicyError::NoCommonProfile);
+    /// let response = policy.error_response("myclient", &request, &error);
+    /// assert_eq!(response.error, ErrorCode::IncompatibleAceProfiles);
+    /// assert_eq!(response.ace_profiles_supported, vec![AceProfile::CoapOscore]);
+    /// # }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    #[must_use]
+    pub fn error_response(
+        &self,
+        client_id: &str,
+        request: &AccessTokenRequest,
+        error: &IssuancePolicyError,
+    ) -> ErrorResponse {
+        let ace_profiles_supported = match error {
+            IssuancePolicyError::NoCommonProfile => {
+                let client = self.clients.iter().find(|x| x.client_id == client_id);
+                request
+                    .audience
+                    .as_deref()
+                    .or_else(|| client.and_then(|x| x.default_audience.as_deref()))
+                    .and_then(|requested| self.audiences.iter().find(|x| x.audience == requested))
+                    .map(|audience| audience.profiles.clone())
+                    .unwrap_or_default()
+            }
+            _ => Vec::new(),
+        };
+        ErrorResponse {
+            error: error.error_code(),
+            description: None,
+            uri: None,
+            ace_profiles_supported,
+        }
+    }
+
+    /// Returns [`ClientRecord`]s for all clients of this policy, e.g., to populate a
+    /// [`ClientRegistry`](crate::token::storage::ClientRegistry).
+    #[must_use]
+    pub fn client_records(&self) -> Vec<ClientRecord> {
+        self.clients
+            .iter()
+            .map(|client| ClientRecord {
+                allowed_scope: client.scope.clone(),
+                profiles: client.profiles.clone(),
+                redirect_uris: client.redirect_uris.clone(),
+                ..ClientRecord::new(client.client_id.clone())
+            })
+            .collect()
+    }
+
+    /// Returns [`ResourceServerRecord`]s for all audiences of this policy, e.g., to populate a
+    /// [`ResourceServerRegistry`](crate::token::storage::ResourceServerRegistry).
+    #[must_use]
+    pub fn resource_server_records(&self) -> Vec<ResourceServerRecord> {
+        self.audiences
+            .iter()
+            .map(|audience| ResourceServerRecord {
+                profiles: audience.profiles.clone(),
+                ..ResourceServerRecord::new(audience.audience.clone())
+            })
+            .collect()
+    }
+}
+
+impl AuthServerConfig {
+    /// Checks that this configuration is consistent, i.e., that its
+    /// [policy is valid](IssuancePolicy::validate) and that there's at least one key for each
+    /// audience of the policy.
+    ///
+    /// # Errors
+    /// If the configuration is inconsistent, with the variant of [`InvalidConfigError`]
+    /// describing how.
+    pub fn validate(&self) -> Result<(), InvalidConfigError> {
+        self.policy.validate()?;
+        if let Some(audience) = self
+            .policy
+            .audiences
+            .iter()
+            .find(|x| !self.keys.iter().any(|(audience, _)| audience == x.audience))
+        {
+            return Err(InvalidConfigError::MissingKey(audience.audience.clone()));
+        }
+        Ok(())
+    }
+
+    /// Checks that the given `capabilities` of the cipher protecting the tokens support all
+    /// [`algorithms`](AudiencePolicy::algorithms) of the audiences and all of their keys,
+    /// which should be done on startup, so that configuration mismatches don't only become
+    /// apparent once the first token is issued.
+    ///
+    /// # Errors
+    /// - If an algorithm of an audience isn't supported
+    ///   ([`InvalidConfigError::UnsupportedAlgorithm`]).
+    /// - If a key of an audience isn't supported ([`InvalidConfigError::UnsupportedKey`]).
+    pub fn check_capabilities(
+        &self,
+        capabilities: &CipherCapabilities,
+    ) -> Result<(), InvalidConfigError> {
+        for audience in &self.policy.audiences {
+            if let Some(algorithm) = audience
+                .algorithms
+                .iter()
+                .find(|x| !capabilities.supports_algorithm(**x))
+            {
+                return Err(InvalidConfigError::UnsupportedAlgorithm {
+                    audience: audience.audience.clone(),
+                    algorithm: *algorithm,
+                });
+            }
+        }
+        if let Some((audience, _)) = self
+            .keys
+            .iter()
+            .find(|(_, key)| !capabilities.supports_key(key))
+        {
+            return Err(InvalidConfigError::UnsupportedKey(audience.into()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl ReloadableConfig {
+    /// Creates a new [`ReloadableConfig`], initially holding the given `config`.
+    ///
+    /// # Errors
+    /// If the given `config` is [invalid](AuthServerConfig::validate).
+    pub fn new(config: AuthServerConfig) -> Result<ReloadableConfig, InvalidConfigError> {
+        config.validate()?;
+        Ok(ReloadableConfig {
+            current: RwLock::new((0, Arc::new(config))),
+        })
+    }
+
+    /// Returns a snapshot of the current configuration.
+    #[must_use]
+    pub fn current(&self) -> Arc<AuthServerConfig> {
+        // The lock only guards a pointer swap, so it can't be left in an inconsistent state.
+        let current = self.current.read().unwrap_or_else(PoisonError::into_inner);
+        Arc::clone(&current.1)
+    }
+
+    /// Returns the number of times the configuration has been replaced.
+    #[must_use]
+    pub fn generation(&self) -> u64 {
+        self.current
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .0
+    }
+
+    /// Validates the given `config` and, if it's valid, atomically replaces the current
+    /// configuration with it.
+    ///
+    /// # Errors
+    /// If the given `config` is [invalid](AuthServerConfig::validate), in which case the
+    /// current configuration stays active.
+    pub fn reload(&self, config: AuthServerConfig) -> Result<(), InvalidConfigError> {
+        config.validate()?;
+        let config = Arc::new(config);
+        let mut current = self.current.write().unwrap_or_else(PoisonError::into_inner);
+        *current = (current.0 + 1, config);
+        Ok(())
+    }
+}
+
+impl IssuanceGrant {
+    /// Adapts this grant to the renewal of a token at `now` (in seconds since the Unix epoch),
+    /// where the previous token of the series of renewed tokens has been issued at
+    /// `last_issued_at`, by shortening its [`lifetime`](IssuanceGrant::lifetime) according to
+    /// the [`renewal_window`](LifetimePolicy::renewal_window) of its
+    /// [`lifetime_policy`](IssuanceGrant::lifetime_policy).
+    ///
+    /// Both the [claims](IssuanceGrant::claims) and the [response](IssuanceGrant::response) of
+    /// the returned grant reflect the shortened lifetime.
+    ///
+    /// # Errors
+    /// If the renewal window since the previous token has already passed
+    /// ([`IssuancePolicyError::RenewalWindowPassed`]).
+    pub fn renew(
+        mut self,
+        last_issued_at: i64,
+        now: i64,
+    ) -> Result<IssuanceGrant, IssuancePolicyError> {
+        // The policy has already been applied to the lifetime, so only the window is left.
+        let window = LifetimePolicy {
+            renewal_window: self.lifetime_policy.renewal_window,
+            ..LifetimePolicy::default()
+        };
+        self.lifetime = window
+            .renewal_lifetime(self.lifetime, last_issued_at, now)
+            .ok_or(IssuancePolicyError::RenewalWindowPassed)?;
+        Ok(self)
+    }
+
+    /// Returns a [`ClaimsSetBuilder`] containing the audience, scope, issued-at and expiration
+    /// time claims for this grant, assuming the token is issued at `now` (in seconds since the
+    /// Unix epoch), as well as the `cnonce` claim if the request contained a
+    /// [`client_nonce`](IssuanceGrant::client_nonce).
+    ///
+    /// The scope is [canonicalized](Scope::canonicalize), so that tokens granting the same
+    /// permissions contain the same scope claim. Further claims (e.g., the `cnf` claim) can be
+    /// added to the returned builder.
+    #[must_use]
+    pub fn claims(&self, now: i64) -> ClaimsSetBuilder {
+        let mut builder = ClaimsSetBuilder::new()
+            .audience(self.audience.clone())
+            .issued_at(Timestamp::WholeSeconds(now))
+            .expiration_time(Timestamp::WholeSeconds(now + i64::from(self.lifetime)));
+        if let Some(scope) = &self.scope {
+            builder = builder.claim(CwtClaimName::Scope, scope.clone().canonicalize().into());
+        }
+        if let Some(nonce) = &self.client_nonce {
+            builder = builder.claim(CwtClaimName::CNonce, Value::Bytes(nonce.clone()));
+        }
+        if let Some(subject) = &self.subject {
+            builder = builder.subject(subject.clone());
+        }
+        if let Some(actor) = &self.actor {
+            builder = builder.text_claim(ACT_CLAIM.to_string(), actor.clone());
+        }
+        builder
+    }
+
+    /// Returns an [`AccessTokenResponseBuilder`] for the given `access_token` issued for this
+    /// grant, containing its lifetime, scope, and profile.
+    ///
+    /// The scope is [canonicalized](Scope::canonicalize) just like in the
+    /// [claims](IssuanceGrant::claims), so that the response reflects the scope of the token.
+    /// Further parameters (e.g., the `cnf` parameter) can be added to the returned builder.
+    #[must_use]
+    pub fn response(&self, access_token: ByteString) -> AccessTokenResponseBuilder {
+        let mut builder = AccessTokenResponse::builder();
+        builder.access_token(access_token).expires_in(self.lifetime);
+        if let Some(scope) = &self.scope {
+            builder.scope(scope.clone().canonicalize());
+        }
+        if let Some(profile) = self.profile {
+            builder.ace_profile(profile);
+        }
+        builder
+    }
+}
+
+/// Returns the number of seconds the token with the given `claims` has left at `now`
+/// (which is not positive if it has already expired), or `None` if it doesn't expire.
+fn remaining_lifetime(claims: &ClaimsSet, now: i64) -> Option<i64> {
+    match claims.expiration_time.as_ref()? {
+        Timestamp::WholeSeconds(exp) => Some(exp.saturating_sub(now)),
+        // Fractional expiration times are truncated, so the new token won't outlive the
+        // exchanged one.
+        #[allow(clippy::cast_possible_truncation)]
+        Timestamp::FractionalSeconds(exp) => Some((*exp as i64).saturating_sub(now)),
+    }
+}
+
+/// Narrows the `requested` scope down to the parts contained in the `allowed` scope, using the
+/// given `codecs` for custom scopes.
+pub(crate) fn narrow_scope(
+    requested: &Scope,
+    allowed: &Scope,
+    codecs: &CustomScopeRegistry,
+) -> Result<Scope, IssuancePolicyError> {
+    if let (Scope::Custom { encoding_id, .. }, Scope::Custom { .. }) = (requested, allowed) {
+        if codecs.codec(*encoding_id).is_some() {
+            return codecs
+                .narrow(requested, allowed)
+                .ok_or(IssuancePolicyError::ScopeNotAllowed);
+        }
+    }
+    requested
+        .narrow(allowed)
+        .ok_or(IssuancePolicyError::ScopeNotAllowed)
+}
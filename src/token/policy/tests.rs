@@ -0,0 +1,863 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+use ciborium::de::from_reader;
+use ciborium::ser::into_writer;
+use coset::cwt::ClaimName;
+use coset::CoseKeyBuilder;
+use enumflags2::make_bitflags;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec;
+
+use crate::common::cbor_values::ProofOfPossessionKey;
+use crate::common::scope::{AifRestMethod, CustomScopeCodec};
+use crate::endpoints::token_req::ErrorCode;
+use crate::error::{CustomScopeError, KeyStrengthError};
+use crate::token::get_client_nonce;
+use crate::AifEncodedScope;
+
+use super::*;
+
+fn example_policy() -> IssuancePolicy {
+    let mut sensor = AudiencePolicy::new("tempSensor4711", 3600);
+    sensor.profiles = vec![AceProfile::CoapOscore, AceProfile::CoapDtls];
+    sensor.algorithms = vec![Algorithm::Es256, Algorithm::EdDsa];
+    sensor.default_scope = Some(Scope::from(AifEncodedScope::from(vec![(
+        "/s/temp",
+        make_bitflags!(AifRestMethod::{Get}),
+    )])));
+    let mut restricted = ClientPolicy::new("restricted");
+    restricted.audiences = vec!["tempSensor4711".to_string()];
+    restricted.scope = Some(Scope::from(AifEncodedScope::from(vec![
+        ("/s/temp", make_bitflags!(AifRestMethod::{Get})),
+        ("/a/led", make_bitflags!(AifRestMethod::{Get | Put})),
+    ])));
+    restricted.lifetime = Some(600);
+    restricted.profiles = vec![AceProfile::CoapDtls];
+    IssuancePolicy {
+        clients: vec![ClientPolicy::new("admin"), restricted],
+        audiences: vec![sensor, AudiencePolicy::new("valve242", 60)],
+        ..IssuancePolicy::default()
+    }
+}
+
+fn request(audience: Option<&str>, scope: Option<Scope>) -> AccessTokenRequest {
+    AccessTokenRequest {
+        audience: audience.map(ToString::to_string),
+        scope,
+        ..AccessTokenRequest::default()
+    }
+}
+
+#[test]
+fn test_policy_evaluate() {
+    let policy = example_policy();
+    let requested = Scope::from(AifEncodedScope::from(vec![
+        ("/s/temp", make_bitflags!(AifRestMethod::{Get | Put})),
+        ("/a/led", make_bitflags!(AifRestMethod::{Put})),
+    ]));
+    let grant = policy
+        .evaluate(
+            "restricted",
+            &request(Some("tempSensor4711"), Some(requested.clone())),
+        )
+        .expect("request should be allowed");
+    assert_eq!(
+        grant,
+        IssuanceGrant {
+            audience: "tempSensor4711".to_string(),
+            scope: Some(Scope::from(AifEncodedScope::from(vec![
+                ("/s/temp", make_bitflags!(AifRestMethod::{Get})),
+                ("/a/led", make_bitflags!(AifRestMethod::{Put})),
+            ]))),
+            lifetime: 600,
+            profile: Some(AceProfile::CoapDtls),
+            algorithm: Some(Algorithm::Es256),
+            client_nonce: None,
+            lifetime_policy: LifetimePolicy::default(),
+            subject: None,
+            actor: None,
+        }
+    );
+    let claims = grant.claims(1_000).build();
+    assert_eq!(claims.audience, Some("tempSensor4711".to_string()));
+    assert_eq!(claims.expiration_time, Some(Timestamp::WholeSeconds(1_600)));
+    assert_eq!(get_client_nonce(&claims), None);
+
+    let mut with_nonce = request(Some("tempSensor4711"), None);
+    with_nonce.client_nonce = Some(vec![0xDC, 0xAF]);
+    let grant = policy
+        .evaluate("restricted", &with_nonce)
+        .expect("request should be allowed");
+    assert_eq!(grant.client_nonce, Some(vec![0xDC, 0xAF]));
+    let claims = grant.claims(1_000).build();
+    assert_eq!(get_client_nonce(&claims), Some(&vec![0xDC, 0xAF]));
+    assert!(with_nonce.verify_client_nonce(&claims).is_ok());
+
+    let grant = policy
+        .evaluate("admin", &request(Some("tempSensor4711"), None))
+        .expect("request should be allowed");
+    assert_eq!(grant.scope, policy.audiences[0].default_scope);
+    assert_eq!(grant.profile, Some(AceProfile::CoapOscore));
+    assert_eq!(grant.lifetime, 3600);
+    let grant = policy
+        .evaluate("admin", &request(Some("valve242"), Some(requested.clone())))
+        .expect("request should be allowed");
+    assert_eq!(grant.scope, Some(requested));
+    assert_eq!((grant.profile, grant.algorithm), (None, None));
+}
+
+#[test]
+fn test_lifetime_policy() -> Result<(), IssuancePolicyError> {
+    let mut policy = example_policy();
+    policy.audiences[0].lifetime_policy = LifetimePolicy {
+        max_lifetime: Some(1200),
+        percentage: Some(50),
+        renewal_window: Some(1000),
+    };
+    let restricted = policy.evaluate("restricted", &request(Some("tempSensor4711"), None))?;
+    assert_eq!(restricted.lifetime, 300);
+    let admin = policy.evaluate("admin", &request(Some("tempSensor4711"), None))?;
+    assert_eq!(admin.lifetime, 1200);
+    let unrestricted = policy.evaluate("admin", &request(Some("valve242"), None))?;
+    assert_eq!(unrestricted.lifetime, 60);
+    assert_eq!(unrestricted.clone().renew(0, 1_000_000)?, unrestricted);
+
+    // Renewed tokens may live for the length of the window at most.
+    let renewed = admin.clone().renew(1_000, 1_500)?;
+    assert_eq!(renewed.lifetime, 1000);
+    assert_eq!(
+        renewed.claims(1_500).build().expiration_time,
+        Some(Timestamp::WholeSeconds(2_500))
+    );
+    // The window slides along with each renewal.
+    assert_eq!(admin.clone().renew(1_500, 2_400)?.lifetime, 1000);
+    assert_eq!(admin.clone().renew(2_400, 3_300)?.lifetime, 1000);
+    assert_eq!(
+        admin.clone().renew(1_000, 2_000),
+        Err(IssuancePolicyError::RenewalWindowPassed)
+    );
+    assert_eq!(
+        admin.renew(2_400, 3_500),
+        Err(IssuancePolicyError::RenewalWindowPassed)
+    );
+    assert_eq!(
+        IssuancePolicyError::RenewalWindowPassed.error_code(),
+        ErrorCode::InvalidGrant
+    );
+    Ok(())
+}
+
+#[test]
+fn test_policy_evaluate_exchange() -> Result<(), IssuancePolicyError> {
+    let mut policy = example_policy();
+    policy.clients[1].token_exchange = true;
+    let exchange = |audience: &str| {
+        TokenExchangeRequest::builder()
+            .subject_token(vec![0xDC, 0xAF])
+            .subject_token_type(token_type_identifiers::ACCESS_TOKEN)
+            .audience(audience)
+            .build()
+            .expect("request should be valid")
+    };
+    let subject = ClaimsSetBuilder::new()
+        .audience("restricted".to_string())
+        .expiration_time(Timestamp::WholeSeconds(1_400))
+        .claim(
+            CwtClaimName::Scope,
+            Scope::from(AifEncodedScope::from(vec![(
+                "/a/led",
+                make_bitflags!(AifRestMethod::{Get | Put}),
+            )]))
+            .into(),
+        )
+        .build();
+    let request = TokenExchangeRequest {
+        scope: Some(Scope::from(AifEncodedScope::from(vec![
+            ("/s/temp", make_bitflags!(AifRestMethod::{Get})),
+            ("/a/led", make_bitflags!(AifRestMethod::{Get | Put})),
+        ]))),
+        ..exchange("tempSensor4711")
+    };
+    let grant = policy.evaluate_exchange("restricted", &request, &subject, None, 1_000)?;
+    assert_eq!(
+        grant.scope,
+        Some(Scope::from(AifEncodedScope::from(vec![(
+            "/a/led",
+            make_bitflags!(AifRestMethod::{Get | Put})
+        )])))
+    );
+    assert_eq!(grant.lifetime, 400);
+
+    assert_eq!(
+        policy.evaluate_exchange("admin", &request, &subject, None, 1_000),
+        Err(IssuancePolicyError::ExchangeNotAllowed)
+    );
+    assert_eq!(
+        policy.evaluate_exchange("restricted", &request, &subject, None, 1_400),
+        Err(IssuancePolicyError::InvalidSubjectToken)
+    );
+    let refresh_token = TokenExchangeRequest {
+        subject_token_type: token_type_identifiers::REFRESH_TOKEN.to_string(),
+        ..request.clone()
+    };
+    assert_eq!(
+        policy.evaluate_exchange("restricted", &refresh_token, &subject, None, 1_000),
+        Err(IssuancePolicyError::InvalidSubjectToken)
+    );
+    assert_eq!(
+        policy.evaluate_exchange("restricted", &exchange("valve242"), &subject, None, 1_000),
+        Err(IssuancePolicyError::AudienceNotAllowed(
+            "valve242".to_string()
+        ))
+    );
+    // Only tokens issued to the client itself may be exchanged.
+    for audience in [None, Some("admin")] {
+        let foreign = ClaimsSet {
+            audience: audience.map(ToString::to_string),
+            ..subject.clone()
+        };
+        assert_eq!(
+            policy.evaluate_exchange("restricted", &request, &foreign, None, 1_000),
+            Err(IssuancePolicyError::InvalidSubjectToken)
+        );
+    }
+    assert_eq!(
+        IssuancePolicyError::ExchangeNotAllowed.error_code(),
+        ErrorCode::UnauthorizedClient
+    );
+    Ok(())
+}
+
+#[test]
+fn test_policy_evaluate_exchange_without_scope() -> Result<(), IssuancePolicyError> {
+    let mut policy = example_policy();
+    policy.clients[1].token_exchange = true;
+    policy.clients[1].audiences.push("valve242".to_string());
+    // The subject token grants more than the client may obtain by itself.
+    let subject = ClaimsSetBuilder::new()
+        .audience("restricted".to_string())
+        .claim(
+            CwtClaimName::Scope,
+            Scope::from(AifEncodedScope::from(vec![
+                (
+                    "/a/led",
+                    make_bitflags!(AifRestMethod::{Get | Put | Delete}),
+                ),
+                ("/a/valve", make_bitflags!(AifRestMethod::{Get | Put})),
+            ]))
+            .into(),
+        )
+        .build();
+    // Neither the request nor the audience specify a scope.
+    let request = TokenExchangeRequest::builder()
+        .subject_token(vec![0xDC, 0xAF])
+        .subject_token_type(token_type_identifiers::ACCESS_TOKEN)
+        .audience("valve242")
+        .build()
+        .expect("request should be valid");
+    let grant = policy.evaluate_exchange("restricted", &request, &subject, None, 1_000)?;
+    assert_eq!(
+        grant.scope,
+        Some(Scope::from(AifEncodedScope::from(vec![(
+            "/a/led",
+            make_bitflags!(AifRestMethod::{Get | Put})
+        )])))
+    );
+
+    // Clients whose scope isn't restricted get the whole scope of the subject token.
+    policy.clients[0].token_exchange = true;
+    let admin_subject = ClaimsSet {
+        audience: Some("admin".to_string()),
+        ..subject.clone()
+    };
+    let grant = policy.evaluate_exchange("admin", &request, &admin_subject, None, 1_000)?;
+    assert_eq!(grant.scope, Scope::try_from(subject.rest[0].1.clone()).ok());
+    Ok(())
+}
+
+#[test]
+fn test_policy_evaluate_exchange_actor() -> Result<(), IssuancePolicyError> {
+    let mut policy = example_policy();
+    policy.clients[1].token_exchange = true;
+    let act = |actor: &str| Value::Map(vec![(Value::from(2), Value::from(actor))]);
+    let subject = ClaimsSetBuilder::new()
+        .subject("sensor".to_string())
+        .audience("restricted".to_string())
+        .expiration_time(Timestamp::WholeSeconds(1_400))
+        .text_claim("act".to_string(), act("relay"))
+        .build();
+    let actor = ClaimsSetBuilder::new()
+        .subject("gateway".to_string())
+        .expiration_time(Timestamp::WholeSeconds(1_200))
+        .build();
+    let request = TokenExchangeRequest::builder()
+        .subject_token(vec![0xDC, 0xAF])
+        .subject_token_type(token_type_identifiers::ACCESS_TOKEN)
+        .actor_token(vec![0x01])
+        .actor_token_type(token_type_identifiers::ACCESS_TOKEN)
+        .audience("tempSensor4711")
+        .build()
+        .expect("request should be valid");
+
+    let grant = policy.evaluate_exchange("restricted", &request, &subject, Some(&actor), 1_000)?;
+    // The prior actor of the subject token is nested into the new `act` claim.
+    let expected_act = Value::Map(vec![
+        (Value::from(2), Value::from("gateway")),
+        (Value::from("act"), act("relay")),
+    ]);
+    assert_eq!(grant.subject.as_deref(), Some("sensor"));
+    assert_eq!(grant.actor.as_ref(), Some(&expected_act));
+    let claims = grant.claims(1_000).build();
+    assert_eq!(claims.subject.as_deref(), Some("sensor"));
+    assert!(claims
+        .rest
+        .contains(&(ClaimName::Text("act".to_string()), expected_act)));
+
+    // Without an actor token, the client is the actor.
+    let without_actor = TokenExchangeRequest {
+        actor_token: None,
+        actor_token_type: None,
+        ..request.clone()
+    };
+    let grant = policy.evaluate_exchange("restricted", &without_actor, &subject, None, 1_000)?;
+    assert_eq!(
+        grant.actor,
+        Some(Value::Map(vec![
+            (Value::from(2), Value::from("restricted")),
+            (Value::from("act"), act("relay")),
+        ]))
+    );
+
+    // The actor token must be valid, and its claims must be given exactly if it is present.
+    for (request, actor, now) in [
+        (&request, None, 1_000),
+        (&without_actor, Some(&actor), 1_000),
+        (&request, Some(&actor), 1_200),
+        (&request, Some(&ClaimsSet::default()), 1_000),
+    ] {
+        assert_eq!(
+            policy.evaluate_exchange("restricted", request, &subject, actor, now),
+            Err(IssuancePolicyError::InvalidActorToken)
+        );
+    }
+    let refresh_token = TokenExchangeRequest {
+        actor_token_type: Some(token_type_identifiers::REFRESH_TOKEN.to_string()),
+        ..request
+    };
+    assert_eq!(
+        policy.evaluate_exchange("restricted", &refresh_token, &subject, Some(&actor), 1_000),
+        Err(IssuancePolicyError::InvalidActorToken)
+    );
+    Ok(())
+}
+
+#[test]
+fn test_policy_evaluate_with_shrinker() {
+    let policy = example_policy();
+    let requested = Scope::from(AifEncodedScope::from(vec![
+        ("/s/temp", make_bitflags!(AifRestMethod::{Get | Put})),
+        ("/a/led", make_bitflags!(AifRestMethod::{Put})),
+    ]));
+    let read_only = Scope::from(AifEncodedScope::from(vec![
+        ("/s/temp", make_bitflags!(AifRestMethod::{Get})),
+        // Exceeds the granted scope, hence must be ignored.
+        ("/a/door", make_bitflags!(AifRestMethod::{Get})),
+    ]));
+    let mut calls = Vec::new();
+    let mut shrinker = |client: &str, audience: &str, scope: Scope| {
+        calls.push((client.to_string(), audience.to_string(), scope));
+        Some(read_only.clone())
+    };
+    let grant = policy
+        .evaluate_with(
+            "admin",
+            &request(Some("tempSensor4711"), Some(requested.clone())),
+            &mut shrinker,
+        )
+        .expect("request should be allowed");
+    let expected = Scope::from(AifEncodedScope::from(vec![(
+        "/s/temp",
+        make_bitflags!(AifRestMethod::{Get}),
+    )]));
+    assert_eq!(grant.scope, Some(expected.clone()));
+    assert_eq!(
+        calls,
+        vec![(
+            "admin".to_string(),
+            "tempSensor4711".to_string(),
+            requested.clone()
+        )]
+    );
+
+    // The shrunken scope is reflected in both the token and the response.
+    let claims = grant.claims(1_000).build();
+    let response = grant
+        .response(vec![0xDC, 0xAF])
+        .build()
+        .expect("response should be valid");
+    assert_eq!(response.scope, Some(expected.clone()));
+    assert_eq!(response.expires_in, Some(3600));
+    assert_eq!(response.ace_profile, Some(AceProfile::CoapOscore));
+    assert!(claims.rest.iter().any(|(name, value)| *name
+        == ClaimName::Assigned(CwtClaimName::Scope)
+        && Scope::try_from(value.clone()).ok() == Some(expected.clone())));
+
+    let mut deny = |_: &str, _: &str, _: Scope| None;
+    assert_eq!(
+        policy.evaluate_with(
+            "admin",
+            &request(Some("tempSensor4711"), Some(requested)),
+            &mut deny
+        ),
+        Err(IssuancePolicyError::ScopeNotAllowed)
+    );
+}
+
+#[test]
+fn test_policy_errors() {
+    let mut policy = example_policy();
+    let forbidden = Scope::from(AifEncodedScope::from(vec![(
+        "/s/temp",
+        make_bitflags!(AifRestMethod::{Delete}),
+    )]));
+    assert_eq!(
+        policy.evaluate("unknown", &request(Some("valve242"), None)),
+        Err(IssuancePolicyError::UnknownClient("unknown".to_string()))
+    );
+    assert_eq!(
+        policy.evaluate("admin", &request(None, None)),
+        Err(IssuancePolicyError::MissingAudience)
+    );
+    assert_eq!(
+        policy.evaluate("admin", &request(Some("unknown"), None)),
+        Err(IssuancePolicyError::UnknownAudience("unknown".to_string()))
+    );
+    assert_eq!(
+        policy.evaluate("restricted", &request(Some("valve242"), None)),
+        Err(IssuancePolicyError::AudienceNotAllowed(
+            "valve242".to_string()
+        ))
+    );
+    assert_eq!(
+        policy.evaluate(
+            "restricted",
+            &request(Some("tempSensor4711"), Some(forbidden))
+        ),
+        Err(IssuancePolicyError::ScopeNotAllowed)
+    );
+    policy.clients[1].profiles = vec![AceProfile::Other(42)];
+    assert_eq!(
+        policy.evaluate("restricted", &request(Some("tempSensor4711"), None)),
+        Err(IssuancePolicyError::NoCommonProfile)
+    );
+}
+
+#[test]
+fn test_policy_default_audience() {
+    let mut policy = example_policy();
+    policy.clients[1].default_audience = Some("tempSensor4711".to_string());
+    assert_eq!(policy.validate(), Ok(()));
+    let grant = policy
+        .evaluate("restricted", &request(None, None))
+        .expect("request should be allowed");
+    assert_eq!(grant.audience, "tempSensor4711");
+    assert_eq!(grant.lifetime, 600);
+    // An explicitly requested audience takes precedence.
+    assert_eq!(
+        policy.evaluate("restricted", &request(Some("valve242"), None)),
+        Err(IssuancePolicyError::AudienceNotAllowed(
+            "valve242".to_string()
+        ))
+    );
+    let error = policy
+        .evaluate("admin", &request(None, None))
+        .expect_err("request without audience should be rejected");
+    assert_eq!(error, IssuancePolicyError::MissingAudience);
+    assert_eq!(error.error_code(), ErrorCode::InvalidRequest);
+}
+
+#[test]
+fn test_policy_error_codes() {
+    for (error, code) in [
+        (
+            IssuancePolicyError::UnknownClient("client".to_string()),
+            ErrorCode::InvalidClient,
+        ),
+        (
+            IssuancePolicyError::MissingAudience,
+            ErrorCode::InvalidRequest,
+        ),
+        (
+            IssuancePolicyError::AudienceNotAllowed("rs".to_string()),
+            ErrorCode::InvalidRequest,
+        ),
+        (
+            IssuancePolicyError::ScopeNotAllowed,
+            ErrorCode::InvalidScope,
+        ),
+        (
+            IssuancePolicyError::NoCommonProfile,
+            ErrorCode::IncompatibleAceProfiles,
+        ),
+    ] {
+        assert_eq!(error.error_code(), code);
+    }
+}
+
+#[test]
+fn test_policy_requested_profile() {
+    let mut policy = example_policy();
+    let mut flagged = request(Some("valve242"), None);
+    flagged.ace_profile = Some(());
+    // valve242 specifies no profiles, so none is selected unless the client asks for one.
+    let grant = policy
+        .evaluate("admin", &request(Some("valve242"), None))
+        .expect("request must be allowed");
+    assert_eq!(grant.profile, None);
+    assert_eq!(
+        policy.evaluate("admin", &flagged),
+        Err(IssuancePolicyError::UndeterminedProfile)
+    );
+
+    policy.clients[0].profiles = vec![AceProfile::CoapOscore];
+    let grant = policy
+        .evaluate("admin", &flagged)
+        .expect("request must be allowed");
+    assert_eq!(grant.profile, Some(AceProfile::CoapOscore));
+    let response = grant
+        .response(vec![0xDC, 0xAF])
+        .cnf(ProofOfPossessionKey::KeyId(vec![0x42]))
+        .build()
+        .expect("response must be valid");
+    assert_eq!(response.ace_profile, Some(AceProfile::CoapOscore));
+    #[cfg(feature = "client")]
+    assert_eq!(
+        flagged.validate_response(&response, &[AceProfile::CoapOscore]),
+        Ok(())
+    );
+}
+
+#[test]
+fn test_policy_error_response() {
+    let policy = example_policy();
+    let mut oscore_only = request(Some("tempSensor4711"), None);
+    let mut restricted_policy = policy.clone();
+    restricted_policy.audiences[0].profiles = vec![AceProfile::CoapOscore];
+    let error = restricted_policy
+        .evaluate("restricted", &oscore_only)
+        .expect_err("profiles must be incompatible");
+    let response = restricted_policy.error_response("restricted", &oscore_only, &error);
+    assert_eq!(response.error, ErrorCode::IncompatibleAceProfiles);
+    assert_eq!(
+        response.ace_profiles_supported,
+        vec![AceProfile::CoapOscore]
+    );
+
+    // Other errors don't advertise any profiles.
+    oscore_only.audience = Some("unknown".to_string());
+    let error = policy
+        .evaluate("restricted", &oscore_only)
+        .expect_err("audience must be unknown");
+    let response = policy.error_response("restricted", &oscore_only, &error);
+    assert_eq!(response.error, ErrorCode::InvalidRequest);
+    assert!(response.ace_profiles_supported.is_empty());
+}
+
+#[test]
+fn test_policy_custom_scope() -> Result<(), CustomScopeError> {
+    struct Bitmask;
+
+    impl CustomScopeCodec for Bitmask {
+        fn validate(&self, scope: &[u8]) -> bool {
+            scope.len() == 1
+        }
+
+        fn narrow(&self, requested: &[u8], allowed: &[u8]) -> Option<ByteString> {
+            Some(vec![requested[0] & allowed[0]]).filter(|x| x[0] != 0)
+        }
+    }
+
+    let custom = |bytes: &[u8]| Scope::Custom {
+        encoding_id: 65000,
+        bytes: bytes.to_vec(),
+    };
+    let mut policy = example_policy();
+    policy.clients[1].scope = Some(custom(&[0b1100]));
+    let mut codecs = CustomScopeRegistry::new();
+    codecs.register(65000, Bitmask)?;
+
+    let requested = request(Some("tempSensor4711"), Some(custom(&[0b0111])));
+    assert_eq!(
+        policy
+            .evaluate_with_codecs("restricted", &requested, &codecs)
+            .map(|x| x.scope),
+        Ok(Some(custom(&[0b0100])))
+    );
+    // Without the codec, the scope can't be narrowed down.
+    assert_eq!(
+        policy.evaluate("restricted", &requested),
+        Err(IssuancePolicyError::ScopeNotAllowed)
+    );
+    let disjoint = request(Some("tempSensor4711"), Some(custom(&[0b0011])));
+    assert_eq!(
+        policy.evaluate_with_codecs("restricted", &disjoint, &codecs),
+        Err(IssuancePolicyError::ScopeNotAllowed)
+    );
+    // Invalid scopes aren't granted even if the client's scope isn't restricted.
+    let invalid = request(Some("tempSensor4711"), Some(custom(&[1, 2])));
+    assert_eq!(
+        policy.evaluate_with_codecs("admin", &invalid, &codecs),
+        Err(IssuancePolicyError::ScopeNotAllowed)
+    );
+    assert_eq!(
+        policy.evaluate("admin", &invalid).map(|x| x.scope),
+        Ok(Some(custom(&[1, 2])))
+    );
+
+    // Exchanged tokens are narrowed down to the scope of the subject token using the codecs.
+    policy.clients[1].token_exchange = true;
+    let subject = ClaimsSetBuilder::new()
+        .audience("restricted".to_string())
+        .claim(CwtClaimName::Scope, custom(&[0b0110]).into())
+        .build();
+    let exchange = TokenExchangeRequest::builder()
+        .subject_token(vec![0xDC, 0xAF])
+        .subject_token_type(token_type_identifiers::ACCESS_TOKEN)
+        .audience("tempSensor4711")
+        .scope(custom(&[0b1111]))
+        .build()
+        .expect("request should be valid");
+    assert_eq!(
+        policy
+            .evaluate_exchange_with_codecs("restricted", &exchange, &subject, None, 0, &codecs)
+            .map(|x| x.scope),
+        Ok(Some(custom(&[0b0100])))
+    );
+    Ok(())
+}
+
+#[test]
+fn test_policy_serde() {
+    let policy = example_policy();
+    let mut serialized = Vec::new();
+    into_writer(&policy, &mut serialized).expect("policy should be serializable");
+    let deserialized: IssuancePolicy =
+        from_reader(serialized.as_slice()).expect("policy should be deserializable");
+    assert_eq!(deserialized, policy);
+    assert_eq!(policy.client_records().len(), 2);
+    assert_eq!(
+        policy.resource_server_records()[0].profiles,
+        vec![AceProfile::CoapOscore, AceProfile::CoapDtls]
+    );
+}
+
+#[test]
+fn test_config_validate() -> Result<(), String> {
+    let mut config = AuthServerConfig {
+        policy: example_policy(),
+        keys: AudienceKeyRing::default(),
+    };
+    assert_eq!(
+        config.validate(),
+        Err(InvalidConfigError::MissingKey("tempSensor4711".to_string()))
+    );
+    for audience in ["tempSensor4711", "valve242"] {
+        config
+            .keys
+            .insert(
+                audience,
+                CoseKeyBuilder::new_symmetric_key(vec![0xDC; 16])
+                    .key_id(audience.as_bytes().to_vec())
+                    .build(),
+            )
+            .map_err(|x| x.to_string())?;
+    }
+    assert_eq!(config.validate(), Ok(()));
+
+    let mut policy = example_policy();
+    policy.clients.push(ClientPolicy::new("admin"));
+    assert_eq!(
+        policy.validate(),
+        Err(InvalidConfigError::DuplicateClient("admin".to_string()))
+    );
+    let mut policy = example_policy();
+    policy.audiences.push(AudiencePolicy::new("valve242", 10));
+    assert_eq!(
+        policy.validate(),
+        Err(InvalidConfigError::DuplicateAudience(
+            "valve242".to_string()
+        ))
+    );
+    let mut policy = example_policy();
+    policy.clients[1].audiences.push("unknown".to_string());
+    assert_eq!(
+        policy.validate(),
+        Err(InvalidConfigError::UnknownAudience {
+            client_id: "restricted".to_string(),
+            audience: "unknown".to_string()
+        })
+    );
+    let mut policy = example_policy();
+    policy.clients[0].default_audience = Some("unknown".to_string());
+    assert_eq!(
+        policy.validate(),
+        Err(InvalidConfigError::UnknownAudience {
+            client_id: "admin".to_string(),
+            audience: "unknown".to_string()
+        })
+    );
+    let mut policy = example_policy();
+    policy.clients[1].default_audience = Some("valve242".to_string());
+    assert_eq!(
+        policy.validate(),
+        Err(InvalidConfigError::DefaultAudienceNotAllowed {
+            client_id: "restricted".to_string(),
+            audience: "valve242".to_string()
+        })
+    );
+    for (percentage, valid) in [
+        (0, false),
+        (1, true),
+        (100, true),
+        (101, false),
+        (250, false),
+    ] {
+        let mut policy = example_policy();
+        policy.audiences[1].lifetime_policy.percentage = Some(percentage);
+        let expected = if valid {
+            Ok(())
+        } else {
+            Err(InvalidConfigError::InvalidPercentage {
+                audience: "valve242".to_string(),
+                percentage,
+            })
+        };
+        assert_eq!(policy.validate(), expected);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_lifetime_policy_percentage_bounds() {
+    let policy = |percentage| LifetimePolicy {
+        percentage: Some(percentage),
+        ..LifetimePolicy::default()
+    };
+    // Percentages can never lengthen the lifetime or result in already expired tokens.
+    assert_eq!(policy(250).lifetime(3600), 3600);
+    assert_eq!(policy(101).lifetime(3600), 3600);
+    assert_eq!(policy(100).lifetime(3600), 3600);
+    assert_eq!(policy(1).lifetime(3600), 36);
+    assert_eq!(policy(0).lifetime(3600), 36);
+}
+
+#[test]
+fn test_config_capabilities() -> Result<(), String> {
+    let mut config = AuthServerConfig {
+        policy: example_policy(),
+        keys: AudienceKeyRing::default(),
+    };
+    config
+        .keys
+        .insert(
+            "valve242",
+            CoseKeyBuilder::new_symmetric_key(vec![0xDC; 16])
+                .key_id(vec![0x01])
+                .build(),
+        )
+        .map_err(|x| x.to_string())?;
+    let mut capabilities = CipherCapabilities::new(
+        vec![Algorithm::Es256],
+        vec![coset::iana::KeyType::Symmetric],
+    );
+    assert_eq!(
+        config.check_capabilities(&capabilities),
+        Err(InvalidConfigError::UnsupportedAlgorithm {
+            audience: "tempSensor4711".to_string(),
+            algorithm: Algorithm::EdDsa
+        })
+    );
+    capabilities.algorithms.push(Algorithm::EdDsa);
+    assert_eq!(config.check_capabilities(&capabilities), Ok(()));
+    config
+        .keys
+        .insert(
+            "valve242",
+            CoseKeyBuilder::new_symmetric_key(vec![0xDC; 16])
+                .key_id(vec![0x02])
+                .algorithm(coset::iana::Algorithm::A128GCM)
+                .build(),
+        )
+        .map_err(|x| x.to_string())?;
+    assert_eq!(
+        config.check_capabilities(&capabilities),
+        Err(InvalidConfigError::UnsupportedKey("valve242".to_string()))
+    );
+    Ok(())
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_reloadable_config() -> Result<(), InvalidConfigError> {
+    let config = Arc::new(ReloadableConfig::new(AuthServerConfig::default())?);
+    let before = config.current();
+    let mut updated = AuthServerConfig::default();
+    updated.policy.clients.push(ClientPolicy::new("admin"));
+    let reloader = Arc::clone(&config);
+    let new_config = updated.clone();
+    std::thread::spawn(move || reloader.reload(new_config))
+        .join()
+        .expect("reloading thread should not panic")?;
+    assert_eq!(*config.current(), updated);
+    assert_eq!(*before, AuthServerConfig::default());
+    assert_eq!(config.generation(), 1);
+
+    updated.policy.clients.push(ClientPolicy::new("admin"));
+    assert!(config.reload(updated).is_err());
+    assert_eq!(config.generation(), 1);
+    assert!(ReloadableConfig::new(AuthServerConfig {
+        policy: example_policy(),
+        keys: AudienceKeyRing::default()
+    })
+    .is_err());
+    Ok(())
+}
+
+#[test]
+fn test_policy_key_strength() {
+    let mut policy = example_policy();
+    policy.key_strength = KeyStrengthPolicy::strict();
+    let mut weak = request(Some("valve242"), None);
+    weak.req_cnf = Some(ProofOfPossessionKey::PlainCoseKey(
+        CoseKeyBuilder::new_symmetric_key(vec![0xDC; 8]).build(),
+    ));
+    let error = policy.evaluate("admin", &weak).unwrap_err();
+    assert_eq!(
+        error,
+        IssuancePolicyError::WeakKey(KeyStrengthError::SymmetricKeyTooShort {
+            length: 8,
+            min_length: 16
+        })
+    );
+    assert_eq!(
+        policy.error_response("admin", &weak, &error).error,
+        ErrorCode::UnsupportedPopKey
+    );
+    let mut strong = weak.clone();
+    strong.req_cnf = Some(ProofOfPossessionKey::PlainCoseKey(
+        CoseKeyBuilder::new_symmetric_key(vec![0xDC; 16]).build(),
+    ));
+    assert!(policy.evaluate("admin", &strong).is_ok());
+    // The default policy doesn't impose any requirements.
+    assert!(example_policy().evaluate("admin", &weak).is_ok());
+}
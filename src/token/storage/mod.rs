@@ -0,0 +1,628 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+//! Contains storage traits for the state an Authorization Server needs to keep across restarts.
+//!
+//! The state is split into six traits, so that each part can be stored wherever it fits best:
+//! - [`ClientRegistry`]: The clients known to the Authorization Server.
+//! - [`ResourceServerRegistry`]: The Resource Servers (audiences) tokens can be issued for.
+//! - [`RefreshTokenStore`]: The refresh tokens which have been issued and not yet used.
+//! - [`IssuanceJournal`]: A record of all access tokens which have been issued.
+//! - [`TokenReferenceStore`]: The claims of access tokens issued as opaque token references.
+//! - [`AssertionReplayCache`]: The client assertions which have already been used.
+//!
+//! Each trait has an associated `Error` type, so that implementations backed by a database or
+//! a file system can report their failures. [`InMemoryStorage`] implements all of them without
+//! persisting anything, which is useful for tests or as a cache in front of a persistent store.
+//!
+//! # Deprovisioning
+//! Removing a client from the [`ClientRegistry`] alone doesn't cut its access, since the access
+//! tokens it has already obtained remain valid until they expire. [`deprovision_client`] hence
+//! also revokes its refresh tokens and the token references issued to it, and reports each of its
+//! outstanding access tokens (as recorded in the [`IssuanceJournal`]) to a [`RevocationHandler`],
+//! which can, e.g., add them to a token revocation list or notify the affected Resource Servers.
+//!
+//! # Example
+//! ```
+//! use dcaf::token::storage::{ClientRecord, ClientRegistry, InMemoryStorage};
+//!
+//! let mut storage = InMemoryStorage::default();
+//! storage.register_client(ClientRecord::new("valve242"))?;
+//! assert_eq!(storage.client("valve242")?, Some(ClientRecord::new("valve242")));
+//! assert!(storage.remove_client("valve242")?);
+//! assert_eq!(storage.client("valve242")?, None);
+//! # Ok::<(), core::convert::Infallible>(())
+//! ```
+
+use core::convert::Infallible;
+
+use coset::cwt::ClaimsSet;
+use coset::CoseKey;
+
+use crate::common::cbor_values::{ByteString, ProofOfPossessionKey};
+use crate::{AceProfile, Scope};
+
+#[cfg(not(feature = "std"))]
+use {alloc::string::String, alloc::vec::Vec};
+
+#[cfg(test)]
+mod tests;
+
+/// A client registered at the Authorization Server.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ClientRecord {
+    /// The identifier of the client, as used in the `client_id` parameter.
+    pub client_id: String,
+
+    /// The key the client authenticates itself with, if any.
+    pub key: Option<ProofOfPossessionKey>,
+
+    /// The maximum scope the client may request, or `None` if it's not restricted.
+    pub allowed_scope: Option<Scope>,
+
+    /// The profiles the client supports.
+    pub profiles: Vec<AceProfile>,
+
+    /// The redirection URIs registered for the client, which the `redirect_uri` of its requests
+    /// is validated against (see [`AccessTokenRequest::validate_redirect_uri`](crate::AccessTokenRequest::validate_redirect_uri)).
+    pub redirect_uris: Vec<String>,
+}
+
+impl ClientRecord {
+    /// Creates a new [`ClientRecord`] for the given `client_id` without a key, scope restriction,
+    /// supported profiles, or redirection URIs.
+    #[must_use]
+    pub fn new<T>(client_id: T) -> ClientRecord
+    where
+        T: Into<String>,
+    {
+        ClientRecord {
+            client_id: client_id.into(),
+            key: None,
+            allowed_scope: None,
+            profiles: Vec::new(),
+            redirect_uris: Vec::new(),
+        }
+    }
+}
+
+/// A Resource Server registered at the Authorization Server.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ResourceServerRecord {
+    /// The audience identifying the Resource Server.
+    pub audience: String,
+
+    /// The key shared with the Resource Server which is used to protect its access tokens, if any.
+    pub key: Option<CoseKey>,
+
+    /// The profiles the Resource Server supports.
+    pub profiles: Vec<AceProfile>,
+}
+
+impl ResourceServerRecord {
+    /// Creates a new [`ResourceServerRecord`] for the given `audience` without a key or
+    /// supported profiles.
+    #[must_use]
+    pub fn new<T>(audience: T) -> ResourceServerRecord
+    where
+        T: Into<String>,
+    {
+        ResourceServerRecord {
+            audience: audience.into(),
+            key: None,
+            profiles: Vec::new(),
+        }
+    }
+}
+
+/// The information the Authorization Server needs to issue a new access token
+/// in exchange for a refresh token.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct RefreshTokenRecord {
+    /// The client the refresh token has been issued to.
+    pub client_id: String,
+
+    /// The audience of the access tokens which may be obtained using the refresh token.
+    pub audience: Option<String>,
+
+    /// The scope of the access tokens which may be obtained using the refresh token.
+    pub scope: Option<Scope>,
+
+    /// The time (in seconds since the Unix epoch) after which the refresh token expires, if any.
+    pub expires_at: Option<i64>,
+}
+
+/// An entry in the [`IssuanceJournal`], describing a single issued access token.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct IssuanceRecord {
+    /// The client the access token has been issued to.
+    pub client_id: String,
+
+    /// The audience of the access token, after aliases have been resolved
+    /// (see `AudienceAliases` in the `issuance` module).
+    pub audience: Option<String>,
+
+    /// The audience as requested by the client, if it differs from the (normalized)
+    /// [`audience`](IssuanceRecord::audience), e.g., because the client used an alias.
+    pub requested_audience: Option<String>,
+
+    /// The scope of the access token.
+    pub scope: Option<Scope>,
+
+    /// The `iat` value of the access token.
+    pub issued_at: i64,
+
+    /// The `cti` (CWT ID) of the access token, if it has one.
+    pub token_id: Option<ByteString>,
+
+    /// The token reference the access token has been issued as, if any
+    /// (see [`TokenReferenceStore`]).
+    pub token_reference: Option<ByteString>,
+}
+
+/// Storage for the clients registered at the Authorization Server.
+pub trait ClientRegistry {
+    /// The error type returned when the underlying storage fails.
+    type Error;
+
+    /// Returns the client with the given `client_id`, or `None` if it isn't registered.
+    ///
+    /// # Errors
+    /// If the underlying storage fails.
+    fn client(&self, client_id: &str) -> Result<Option<ClientRecord>, Self::Error>;
+
+    /// Registers the given `client`, replacing any client with the same `client_id`.
+    ///
+    /// # Errors
+    /// If the underlying storage fails.
+    fn register_client(&mut self, client: ClientRecord) -> Result<(), Self::Error>;
+
+    /// Removes the client with the given `client_id`, returning whether it was registered.
+    ///
+    /// # Errors
+    /// If the underlying storage fails.
+    fn remove_client(&mut self, client_id: &str) -> Result<bool, Self::Error>;
+}
+
+/// Storage for the Resource Servers registered at the Authorization Server.
+pub trait ResourceServerRegistry {
+    /// The error type returned when the underlying storage fails.
+    type Error;
+
+    /// Returns the Resource Server with the given `audience`, or `None` if it isn't registered.
+    ///
+    /// # Errors
+    /// If the underlying storage fails.
+    fn resource_server(&self, audience: &str) -> Result<Option<ResourceServerRecord>, Self::Error>;
+
+    /// Registers the given `resource_server`, replacing any Resource Server with the same audience.
+    ///
+    /// # Errors
+    /// If the underlying storage fails.
+    fn register_resource_server(
+        &mut self,
+        resource_server: ResourceServerRecord,
+    ) -> Result<(), Self::Error>;
+
+    /// Removes the Resource Server with the given `audience`, returning whether it was registered.
+    ///
+    /// # Errors
+    /// If the underlying storage fails.
+    fn remove_resource_server(&mut self, audience: &str) -> Result<bool, Self::Error>;
+}
+
+/// Storage for the refresh tokens issued by the Authorization Server.
+pub trait RefreshTokenStore {
+    /// The error type returned when the underlying storage fails.
+    type Error;
+
+    /// Stores the given refresh `token` along with its `record`.
+    ///
+    /// # Errors
+    /// If the underlying storage fails.
+    fn store_refresh_token(
+        &mut self,
+        token: ByteString,
+        record: RefreshTokenRecord,
+    ) -> Result<(), Self::Error>;
+
+    /// Removes the given refresh `token` from the store and returns its record,
+    /// or `None` if the token is unknown (e.g., because it has already been used).
+    ///
+    /// Refresh tokens are taken out of the store so that each of them can only be used once.
+    ///
+    /// # Errors
+    /// If the underlying storage fails.
+    fn take_refresh_token(
+        &mut self,
+        token: &[u8],
+    ) -> Result<Option<RefreshTokenRecord>, Self::Error>;
+
+    /// Revokes all refresh tokens issued to the client with the given `client_id`,
+    /// returning how many have been revoked.
+    ///
+    /// # Errors
+    /// If the underlying storage fails.
+    fn revoke_refresh_tokens(&mut self, client_id: &str) -> Result<usize, Self::Error>;
+}
+
+/// Append-only storage for records of the access tokens issued by the Authorization Server.
+pub trait IssuanceJournal {
+    /// The error type returned when the underlying storage fails.
+    type Error;
+
+    /// Appends the given `record` to the journal.
+    ///
+    /// # Errors
+    /// If the underlying storage fails.
+    fn record_issuance(&mut self, record: IssuanceRecord) -> Result<(), Self::Error>;
+
+    /// Returns all records in the journal, in the order in which they have been added.
+    ///
+    /// # Errors
+    /// If the underlying storage fails.
+    fn issuances(&self) -> Result<Vec<IssuanceRecord>, Self::Error>;
+}
+
+/// Storage for the claims of access tokens which have been issued as opaque token references
+/// (see `TokenReferenceIssuance` in the `issuance` module), so that the Authorization Server can
+/// look them up when a Resource Server introspects a reference.
+pub trait TokenReferenceStore {
+    /// The error type returned when the underlying storage fails.
+    type Error;
+
+    /// Stores the given `claims` under the given token `reference`, replacing any claims
+    /// previously stored under it.
+    ///
+    /// # Errors
+    /// If the underlying storage fails.
+    fn store_token_reference(
+        &mut self,
+        reference: ByteString,
+        claims: ClaimsSet,
+    ) -> Result<(), Self::Error>;
+
+    /// Returns the claims stored under the given token `reference`, or `None` if the reference
+    /// is unknown (e.g., because it has been revoked).
+    ///
+    /// # Errors
+    /// If the underlying storage fails.
+    fn resolve_token_reference(&self, reference: &[u8]) -> Result<Option<ClaimsSet>, Self::Error>;
+
+    /// Removes the given token `reference`, returning whether it was present.
+    ///
+    /// # Errors
+    /// If the underlying storage fails.
+    fn revoke_token_reference(&mut self, reference: &[u8]) -> Result<bool, Self::Error>;
+}
+
+/// Storage for the `cti`s of the client assertions the Authorization Server has accepted
+/// (see `verify_client_assertion` in the `assertion` module of the token endpoint), so that
+/// each assertion can only be used once.
+pub trait AssertionReplayCache {
+    /// The error type returned when the underlying storage fails.
+    type Error;
+
+    /// Records the assertion with the given `token_id` (i.e., its `cti`) of the client identified
+    /// by `client_id`, which expires at `expires_at` (in seconds since the Unix epoch), returning
+    /// `false` if it has already been recorded, i.e., if the assertion is being replayed.
+    ///
+    /// Records of assertions which have expired at `now` may be dropped, as expired assertions
+    /// are rejected anyway.
+    ///
+    /// # Errors
+    /// If the underlying storage fails.
+    fn record_assertion(
+        &mut self,
+        client_id: &str,
+        token_id: &[u8],
+        expires_at: i64,
+        now: i64,
+    ) -> Result<bool, Self::Error>;
+}
+
+/// Receives every outstanding access token of a client which is
+/// [deprovisioned](deprovision_client), e.g., to add it to a token revocation list or to notify
+/// the Resource Server it has been issued for.
+///
+/// This is implemented for all closures taking an [`IssuanceRecord`].
+pub trait RevocationHandler {
+    /// Handles the revocation of the access token described by the given `record`.
+    fn revoke(&mut self, record: &IssuanceRecord);
+}
+
+impl<F> RevocationHandler for F
+where
+    F: FnMut(&IssuanceRecord),
+{
+    fn revoke(&mut self, record: &IssuanceRecord) {
+        self(record);
+    }
+}
+
+/// What has been revoked by [`deprovision_client`].
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash, Default)]
+pub struct Deprovisioning {
+    /// Whether the client was registered before it has been removed.
+    pub client_removed: bool,
+
+    /// The number of refresh tokens of the client which have been revoked.
+    pub refresh_tokens: usize,
+
+    /// The number of token references issued to the client which have been revoked.
+    pub token_references: usize,
+
+    /// The number of access tokens of the client which have been reported to the
+    /// [`RevocationHandler`].
+    pub access_tokens: usize,
+}
+
+/// Removes the client identified by `client_id` from the `storage`, revokes its refresh tokens
+/// and the [token references](IssuanceRecord::token_reference) issued to it, and reports each of
+/// its access tokens issued at or after `issued_since` to the given `handler`.
+///
+/// Revoked token references can't be resolved anymore, so that introspecting them reports the
+/// token as inactive right away, regardless of `issued_since`.
+/// As the [`IssuanceJournal`] doesn't record when tokens expire, `issued_since` should be set to
+/// the current time minus the longest lifetime of any token (e.g., the longest
+/// `AudiencePolicy::lifetime`), so that tokens which have already expired aren't reported.
+/// Use [`i64::MIN`] to report all tokens of the client.
+///
+/// # Errors
+/// If the underlying storage fails, in which case the client may have been deprovisioned
+/// partially. As each step is idempotent, it's safe to call this function again.
+///
+/// # Example
+/// ```
+/// use dcaf::token::storage::{
+///     deprovision_client, ClientRecord, ClientRegistry, InMemoryStorage, IssuanceJournal,
+///     IssuanceRecord,
+/// };
+///
+/// let mut storage = InMemoryStorage::default();
+/// storage.register_client(ClientRecord::new("valve242"))?;
+/// for issued_at in [1000, 5000] {
+///     storage.record_issuance(IssuanceRecord {
+///         client_id: "valve242".to_string(),
+///         audience: Some("coaps://rs.example.com".to_string()),
+///         requested_audience: None,
+///         scope: None,
+///         issued_at,
+///         token_id: Some(vec![0xDC, 0xAF]),
+///         token_reference: None,
+///     })?;
+/// }
+/// // Tokens live for an hour at most, so the first one has already expired.
+/// let now = 6000;
+/// let mut revocation_list = Vec::new();
+/// let mut revoke = |record: &IssuanceRecord| revocation_list.push(record.token_id.clone());
+/// let result = deprovision_client(&mut storage, "valve242", now - 3600, &mut revoke)?;
+/// assert!(result.client_removed);
+/// assert_eq!(result.access_tokens, 1);
+/// assert_eq!(revocation_list, vec![Some(vec![0xDC, 0xAF])]);
+/// assert_eq!(storage.client("valve242")?, None);
+/// # Ok::<(), core::convert::Infallible>(())
+/// ```
+pub fn deprovision_client<S, E, H>(
+    storage: &mut S,
+    client_id: &str,
+    issued_since: i64,
+    handler: &mut H,
+) -> Result<Deprovisioning, E>
+where
+    S: ClientRegistry<Error = E>
+        + RefreshTokenStore<Error = E>
+        + IssuanceJournal<Error = E>
+        + TokenReferenceStore<Error = E>,
+    H: RevocationHandler,
+{
+    // The client is removed first, so that it can't obtain new tokens in the meantime.
+    let client_removed = storage.remove_client(client_id)?;
+    let refresh_tokens = storage.revoke_refresh_tokens(client_id)?;
+    let mut token_references = 0;
+    let mut access_tokens = 0;
+    for record in storage
+        .issuances()?
+        .iter()
+        .filter(|x| x.client_id == client_id)
+    {
+        if let Some(reference) = &record.token_reference {
+            if storage.revoke_token_reference(reference)? {
+                token_references += 1;
+            }
+        }
+        if record.issued_at >= issued_since {
+            handler.revoke(record);
+            access_tokens += 1;
+        }
+    }
+    Ok(Deprovisioning {
+        client_removed,
+        refresh_tokens,
+        token_references,
+        access_tokens,
+    })
+}
+
+/// Implements all storage traits of this module by simply keeping the state in memory,
+/// which means that it's lost on restart.
+///
+/// For an example, see the [module-level documentation](self).
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct InMemoryStorage {
+    /// The registered clients.
+    clients: Vec<ClientRecord>,
+
+    /// The registered Resource Servers.
+    resource_servers: Vec<ResourceServerRecord>,
+
+    /// The unused refresh tokens, along with their records.
+    refresh_tokens: Vec<(ByteString, RefreshTokenRecord)>,
+
+    /// The issuance journal.
+    journal: Vec<IssuanceRecord>,
+
+    /// The token references, along with the claims of their access tokens.
+    token_references: Vec<(ByteString, ClaimsSet)>,
+
+    /// The client IDs and `cti`s of the accepted client assertions, along with their expiration.
+    assertions: Vec<(String, ByteString, i64)>,
+}
+
+impl ClientRegistry for InMemoryStorage {
+    type Error = Infallible;
+
+    fn client(&self, client_id: &str) -> Result<Option<ClientRecord>, Infallible> {
+        Ok(self
+            .clients
+            .iter()
+            .find(|c| c.client_id == client_id)
+            .cloned())
+    }
+
+    fn register_client(&mut self, client: ClientRecord) -> Result<(), Infallible> {
+        self.clients.retain(|c| c.client_id != client.client_id);
+        self.clients.push(client);
+        Ok(())
+    }
+
+    fn remove_client(&mut self, client_id: &str) -> Result<bool, Infallible> {
+        let before = self.clients.len();
+        self.clients.retain(|c| c.client_id != client_id);
+        Ok(self.clients.len() != before)
+    }
+}
+
+impl ResourceServerRegistry for InMemoryStorage {
+    type Error = Infallible;
+
+    fn resource_server(&self, audience: &str) -> Result<Option<ResourceServerRecord>, Infallible> {
+        Ok(self
+            .resource_servers
+            .iter()
+            .find(|rs| rs.audience == audience)
+            .cloned())
+    }
+
+    fn register_resource_server(
+        &mut self,
+        resource_server: ResourceServerRecord,
+    ) -> Result<(), Infallible> {
+        self.resource_servers
+            .retain(|rs| rs.audience != resource_server.audience);
+        self.resource_servers.push(resource_server);
+        Ok(())
+    }
+
+    fn remove_resource_server(&mut self, audience: &str) -> Result<bool, Infallible> {
+        let before = self.resource_servers.len();
+        self.resource_servers.retain(|rs| rs.audience != audience);
+        Ok(self.resource_servers.len() != before)
+    }
+}
+
+impl RefreshTokenStore for InMemoryStorage {
+    type Error = Infallible;
+
+    fn store_refresh_token(
+        &mut self,
+        token: ByteString,
+        record: RefreshTokenRecord,
+    ) -> Result<(), Infallible> {
+        self.refresh_tokens.retain(|(t, _)| t != &token);
+        self.refresh_tokens.push((token, record));
+        Ok(())
+    }
+
+    fn take_refresh_token(
+        &mut self,
+        token: &[u8],
+    ) -> Result<Option<RefreshTokenRecord>, Infallible> {
+        Ok(self
+            .refresh_tokens
+            .iter()
+            .position(|(t, _)| t == token)
+            .map(|index| self.refresh_tokens.remove(index).1))
+    }
+
+    fn revoke_refresh_tokens(&mut self, client_id: &str) -> Result<usize, Infallible> {
+        let before = self.refresh_tokens.len();
+        self.refresh_tokens
+            .retain(|(_, record)| record.client_id != client_id);
+        Ok(before - self.refresh_tokens.len())
+    }
+}
+
+impl IssuanceJournal for InMemoryStorage {
+    type Error = Infallible;
+
+    fn record_issuance(&mut self, record: IssuanceRecord) -> Result<(), Infallible> {
+        self.journal.push(record);
+        Ok(())
+    }
+
+    fn issuances(&self) -> Result<Vec<IssuanceRecord>, Infallible> {
+        Ok(self.journal.clone())
+    }
+}
+
+impl TokenReferenceStore for InMemoryStorage {
+    type Error = Infallible;
+
+    fn store_token_reference(
+        &mut self,
+        reference: ByteString,
+        claims: ClaimsSet,
+    ) -> Result<(), Infallible> {
+        self.token_references.retain(|(r, _)| r != &reference);
+        self.token_references.push((reference, claims));
+        Ok(())
+    }
+
+    fn resolve_token_reference(&self, reference: &[u8]) -> Result<Option<ClaimsSet>, Infallible> {
+        Ok(self
+            .token_references
+            .iter()
+            .find(|(r, _)| r == reference)
+            .map(|(_, claims)| claims.clone()))
+    }
+
+    fn revoke_token_reference(&mut self, reference: &[u8]) -> Result<bool, Infallible> {
+        let before = self.token_references.len();
+        self.token_references.retain(|(r, _)| r != reference);
+        Ok(self.token_references.len() != before)
+    }
+}
+
+impl AssertionReplayCache for InMemoryStorage {
+    type Error = Infallible;
+
+    fn record_assertion(
+        &mut self,
+        client_id: &str,
+        token_id: &[u8],
+        expires_at: i64,
+        now: i64,
+    ) -> Result<bool, Infallible> {
+        self.assertions
+            .retain(|(_, _, expiration)| *expiration > now);
+        if self
+            .assertions
+            .iter()
+            .any(|(client, id, _)| client == client_id && id == token_id)
+        {
+            return Ok(false);
+        }
+        self.assertions
+            .push((client_id.into(), token_id.to_vec(), expires_at));
+        Ok(true)
+    }
+}
@@ -0,0 +1,197 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+use coset::cwt::ClaimsSetBuilder;
+
+#[cfg(not(feature = "std"))]
+use alloc::{string::ToString, vec, vec::Vec};
+
+use crate::error::TokenReferenceError;
+use crate::token::format::{TokenFormat, TokenReferenceFormat};
+use crate::token::issuance::TokenReferenceIssuance;
+
+use super::*;
+
+fn refresh_record(client_id: &str) -> RefreshTokenRecord {
+    RefreshTokenRecord {
+        client_id: client_id.to_string(),
+        audience: Some("valve242".to_string()),
+        scope: None,
+        expires_at: Some(1_700_000_000),
+    }
+}
+
+#[test]
+fn test_clients() -> Result<(), Infallible> {
+    let mut storage = InMemoryStorage::default();
+    storage.register_client(ClientRecord::new("a"))?;
+    let mut replacement = ClientRecord::new("a");
+    replacement.profiles = vec![AceProfile::CoapDtls];
+    storage.register_client(replacement.clone())?;
+    assert_eq!(storage.client("a")?, Some(replacement));
+    assert_eq!(storage.client("b")?, None);
+    assert!(storage.remove_client("a")?);
+    assert!(!storage.remove_client("a")?);
+    Ok(())
+}
+
+#[test]
+fn test_resource_servers() -> Result<(), Infallible> {
+    let mut storage = InMemoryStorage::default();
+    storage.register_resource_server(ResourceServerRecord::new("valve242"))?;
+    assert_eq!(
+        storage.resource_server("valve242")?,
+        Some(ResourceServerRecord::new("valve242"))
+    );
+    assert_eq!(storage.resource_server("valve243")?, None);
+    assert!(storage.remove_resource_server("valve242")?);
+    assert_eq!(storage.resource_server("valve242")?, None);
+    Ok(())
+}
+
+#[test]
+fn test_refresh_tokens() -> Result<(), Infallible> {
+    let mut storage = InMemoryStorage::default();
+    storage.store_refresh_token(vec![1], refresh_record("a"))?;
+    storage.store_refresh_token(vec![2], refresh_record("a"))?;
+    storage.store_refresh_token(vec![3], refresh_record("b"))?;
+    // Refresh tokens can only be used once.
+    assert_eq!(storage.take_refresh_token(&[1])?, Some(refresh_record("a")));
+    assert_eq!(storage.take_refresh_token(&[1])?, None);
+    assert_eq!(storage.revoke_refresh_tokens("a")?, 1);
+    assert_eq!(storage.take_refresh_token(&[2])?, None);
+    assert_eq!(storage.take_refresh_token(&[3])?, Some(refresh_record("b")));
+    Ok(())
+}
+
+#[test]
+fn test_issuance_journal() -> Result<(), Infallible> {
+    let mut storage = InMemoryStorage::default();
+    let records: Vec<IssuanceRecord> = (0..3)
+        .map(|i| IssuanceRecord {
+            client_id: "a".to_string(),
+            audience: Some("valve242".to_string()),
+            requested_audience: None,
+            scope: None,
+            issued_at: i,
+            token_id: None,
+            token_reference: None,
+        })
+        .collect();
+    for record in &records {
+        storage.record_issuance(record.clone())?;
+    }
+    assert_eq!(storage.issuances()?, records);
+    Ok(())
+}
+
+#[test]
+fn test_deprovision_client() -> Result<(), Infallible> {
+    let mut storage = InMemoryStorage::default();
+    storage.register_client(ClientRecord::new("valve242"))?;
+    storage.register_client(ClientRecord::new("other"))?;
+    storage.store_refresh_token(vec![1], refresh_record("valve242"))?;
+    storage.store_refresh_token(vec![2], refresh_record("other"))?;
+    for (client_id, issued_at, token_id) in [
+        ("valve242", 100, 1),
+        ("other", 200, 2),
+        ("valve242", 300, 3),
+    ] {
+        storage.record_issuance(IssuanceRecord {
+            client_id: client_id.to_string(),
+            audience: None,
+            requested_audience: None,
+            scope: None,
+            issued_at,
+            token_id: Some(vec![token_id]),
+            token_reference: None,
+        })?;
+    }
+    let mut revoked = Vec::new();
+    let mut handler = |record: &IssuanceRecord| revoked.push(record.token_id.clone());
+    assert_eq!(
+        deprovision_client(&mut storage, "valve242", 100, &mut handler)?,
+        Deprovisioning {
+            client_removed: true,
+            refresh_tokens: 1,
+            token_references: 0,
+            access_tokens: 2,
+        }
+    );
+    assert_eq!(revoked, vec![Some(vec![1]), Some(vec![3])]);
+    assert!(storage.client("other")?.is_some());
+    assert!(storage.take_refresh_token(&[2])?.is_some());
+
+    // Deprovisioning again is harmless, and tokens issued before the cutoff aren't reported.
+    let mut revoked = Vec::new();
+    let mut handler = |record: &IssuanceRecord| revoked.push(record.token_id.clone());
+    assert_eq!(
+        deprovision_client(&mut storage, "valve242", 200, &mut handler)?,
+        Deprovisioning {
+            client_removed: false,
+            refresh_tokens: 0,
+            token_references: 0,
+            access_tokens: 1,
+        }
+    );
+    assert_eq!(revoked, vec![Some(vec![3])]);
+    Ok(())
+}
+
+#[test]
+fn test_deprovision_client_token_references() -> Result<(), Infallible> {
+    let mut counter = 0;
+    let mut format = TokenReferenceFormat {
+        issuance: TokenReferenceIssuance::new(vec!["valve242"]),
+        store: InMemoryStorage::default(),
+        generate: |x: &mut [u8]| {
+            counter += 1;
+            x.fill(counter);
+        },
+    };
+    let mut references = Vec::new();
+    for client_id in ["valve242", "other", "valve242"] {
+        let claims = ClaimsSetBuilder::new()
+            .audience("valve242".to_string())
+            .build();
+        let reference = format
+            .encode(claims)
+            .expect("in-memory storage is infallible");
+        format.store.record_issuance(IssuanceRecord {
+            client_id: client_id.to_string(),
+            audience: Some("valve242".to_string()),
+            requested_audience: None,
+            scope: None,
+            issued_at: 100,
+            token_id: None,
+            token_reference: Some(reference.clone()),
+        })?;
+        references.push(reference);
+    }
+
+    let result = deprovision_client(
+        &mut format.store,
+        "valve242",
+        1000,
+        &mut |_: &IssuanceRecord| {},
+    )?;
+    assert_eq!(result.token_references, 2);
+    assert_eq!(result.access_tokens, 0);
+    // Introspecting the revoked references finds no claims, so they're reported as inactive.
+    for reference in [&references[0], &references[2]] {
+        assert!(matches!(
+            format.decode(reference),
+            Err(TokenReferenceError::UnknownReference)
+        ));
+    }
+    assert!(format.decode(&references[1]).is_ok());
+    Ok(())
+}
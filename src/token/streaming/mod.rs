@@ -0,0 +1,231 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+//! Contains functions for signing and verifying access tokens with a cipher which processes
+//! the to-be-signed data incrementally.
+//!
+//! [`sign_access_token`](crate::sign_access_token) and
+//! [`verify_access_token`](crate::verify_access_token) construct the whole `Sig_structure`
+//! (described in [section 4.4 of RFC 8152](https://www.rfc-editor.org/rfc/rfc8152#section-4.4))
+//! in memory before passing it to the cipher, which means the (potentially large) claims are
+//! held in memory twice. The functions in this module instead pass the `Sig_structure` to a
+//! [`CoseSign1StreamingCipher`] piece by piece, so that the cipher can e.g. feed it into
+//! an incremental hash function.
+//!
+//! # Example
+//! ```
+//! # use coset::cwt::ClaimsSetBuilder;
+//! # use coset::Header;
+//! # use dcaf::CoseCipherCommon;
+//! # use dcaf::error::{AccessTokenError, CoseCipherError};
+//! use dcaf::token::streaming::{
+//!     sign_access_token_streaming, verify_access_token_streaming, CoseSign1StreamingCipher,
+//! };
+//!
+//! /// Collects the data to sign in a buffer (a real cipher would feed it into a hash instead),
+//! /// then "signs" it by using it as the signature itself (which you **clearly should not do**).
+//! struct FakeStreamingSigner {
+//!     buffer: Vec<u8>,
+//! }
+//! # impl CoseCipherCommon for FakeStreamingSigner {
+//! #     type Error = String;
+//! #     fn header(&self, unprotected_header: &mut Header, protected_header: &mut Header) -> Result<(), CoseCipherError<Self::Error>> {
+//! #         Ok(())
+//! #     }
+//! # }
+//!
+//! impl CoseSign1StreamingCipher for FakeStreamingSigner {
+//!     fn update(&mut self, data: &[u8]) {
+//!         self.buffer.extend_from_slice(data);
+//!     }
+//!
+//!     fn finalize_signature(&mut self) -> Vec<u8> {
+//!         core::mem::take(&mut self.buffer)
+//!     }
+//!
+//!     fn finalize_verification(&mut self, signature: &[u8]) -> Result<(), CoseCipherError<Self::Error>> {
+//!         if signature == self.finalize_signature() {
+//!             Ok(())
+//!         } else {
+//!             Err(CoseCipherError::VerificationFailure)
+//!         }
+//!     }
+//! }
+//!
+//! let mut cipher = FakeStreamingSigner { buffer: Vec::new() };
+//! let claims = ClaimsSetBuilder::new().audience("valve242".to_string()).build();
+//! let token = sign_access_token_streaming(claims, &mut cipher, None, None, None)?;
+//! assert!(verify_access_token_streaming(&token, &mut cipher, None).is_ok());
+//! # Ok::<(), AccessTokenError<String>>(())
+//! ```
+
+use ciborium::value::Value;
+use coset::cwt::ClaimsSet;
+use coset::{CborSerializable, CoseError, CoseSign1, Header, ProtectedHeader};
+
+use crate::common::cbor_values::ByteString;
+use crate::error::{AccessTokenError, CoseCipherError};
+use crate::token::{prepare_headers, CoseCipherCommon};
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+#[cfg(test)]
+mod tests;
+
+/// Provides operations for signing and verifying COSE structures incrementally, i.e., without
+/// requiring the whole to-be-signed data to be in a single buffer.
+///
+/// To sign (or verify), the to-be-signed data is passed to [`update`](CoseSign1StreamingCipher::update)
+/// in one or more chunks, followed by a single call to
+/// [`finalize_signature`](CoseSign1StreamingCipher::finalize_signature) (or
+/// [`finalize_verification`](CoseSign1StreamingCipher::finalize_verification)), after which
+/// the cipher must be ready to process new data.
+///
+/// For an example, see the [module-level documentation](self).
+pub trait CoseSign1StreamingCipher: CoseCipherCommon {
+    /// Feeds the next chunk of the to-be-signed data into the signature computation.
+    fn update(&mut self, data: &[u8]);
+
+    /// Finishes the signature computation over all data passed to
+    /// [`update`](CoseSign1StreamingCipher::update) and returns the signature.
+    fn finalize_signature(&mut self) -> Vec<u8>;
+
+    /// Verifies that `signature` is valid for all data passed to
+    /// [`update`](CoseSign1StreamingCipher::update).
+    ///
+    /// # Errors
+    /// If the `signature` is invalid or does not belong to the data passed in.
+    fn finalize_verification(
+        &mut self,
+        signature: &[u8],
+    ) -> Result<(), CoseCipherError<Self::Error>>;
+}
+
+/// Signs the given `claims` with the given headers and `aad` using the streaming `cipher` for
+/// cryptography, returning the token as a serialized bytestring of the [`CoseSign1`] structure.
+///
+/// Behaves like [`sign_access_token`](crate::sign_access_token), except that the cipher
+/// receives the to-be-signed data in chunks rather than as a single buffer.
+///
+/// # Errors
+/// - When there's a [`CoseError`] while serializing the given `claims` to CBOR.
+/// - When there's a [`CoseError`] while serializing the [`CoseSign1`] structure.
+pub fn sign_access_token_streaming<T>(
+    claims: ClaimsSet,
+    cipher: &mut T,
+    aad: Option<&[u8]>,
+    unprotected_header: Option<Header>,
+    protected_header: Option<Header>,
+) -> Result<ByteString, AccessTokenError<T::Error>>
+where
+    T: CoseSign1StreamingCipher,
+{
+    let (unprotected, protected) = prepare_headers(unprotected_header, protected_header, cipher)?;
+    let payload = claims.to_vec().map_err(AccessTokenError::from_cose_error)?;
+    let protected = ProtectedHeader {
+        original_data: None,
+        header: protected,
+    };
+    feed_sig_structure(protected.clone(), aad.unwrap_or(&[0; 0]), &payload, cipher)
+        .map_err(AccessTokenError::from_cose_error)?;
+    let signature = cipher.finalize_signature();
+    CoseSign1 {
+        protected,
+        unprotected,
+        payload: Some(payload),
+        signature,
+    }
+    .to_vec()
+    .map_err(AccessTokenError::from_cose_error)
+}
+
+/// Verifies the given `token` and `aad` using the streaming `cipher` for cryptography,
+/// returning an error in case it could not be verified.
+///
+/// Behaves like [`verify_access_token`](crate::verify_access_token), except that the cipher
+/// receives the to-be-signed data in chunks rather than as a single buffer.
+///
+/// # Errors
+/// - When there's a [`CoseError`] while deserializing the given `token`
+///   to a [`CoseSign1`] structure
+///   (e.g., if it's not in fact a [`CoseSign1`] structure but rather something else).
+/// - When there's a verification error coming from the `cipher`
+///   (e.g., if the `token`'s data does not match its signature).
+pub fn verify_access_token_streaming<T>(
+    token: &ByteString,
+    cipher: &mut T,
+    aad: Option<&[u8]>,
+) -> Result<(), AccessTokenError<T::Error>>
+where
+    T: CoseSign1StreamingCipher,
+{
+    let sign = CoseSign1::from_slice(token.as_slice()).map_err(AccessTokenError::CoseError)?;
+    feed_sig_structure(
+        sign.protected,
+        aad.unwrap_or(&[0; 0]),
+        sign.payload.as_deref().unwrap_or(&[0; 0]),
+        cipher,
+    )
+    .map_err(AccessTokenError::from_cose_error)?;
+    cipher
+        .finalize_verification(&sign.signature)
+        .map_err(AccessTokenError::from_cose_cipher_error)
+}
+
+/// Passes the `Sig_structure` for a `COSE_Sign1` object with the given `protected` header,
+/// `aad` and `payload` to the given `cipher` in chunks, without copying the `payload`.
+fn feed_sig_structure<T>(
+    protected: ProtectedHeader,
+    aad: &[u8],
+    payload: &[u8],
+    cipher: &mut T,
+) -> Result<(), CoseError>
+where
+    T: CoseSign1StreamingCipher,
+{
+    // Array of four elements, followed by the text string "Signature1".
+    cipher.update(&[0x84, 0x6A]);
+    cipher.update(b"Signature1");
+    let protected = match protected.cbor_bstr()? {
+        Value::Bytes(x) => x,
+        _ => unreachable!("cbor_bstr always returns a bytestring"),
+    };
+    for part in [protected.as_slice(), aad, payload] {
+        cipher.update(&bstr_header(part.len()));
+        cipher.update(part);
+    }
+    Ok(())
+}
+
+/// Returns the CBOR header of a bytestring with the given length.
+fn bstr_header(length: usize) -> Vec<u8> {
+    const BYTES: u8 = 0x40;
+    let mut header = Vec::with_capacity(9);
+    if let Ok(short) = u8::try_from(length) {
+        if short < 0x18 {
+            header.push(BYTES | short);
+        } else {
+            header.push(BYTES | 0x18);
+            header.push(short);
+        }
+    } else if let Ok(medium) = u16::try_from(length) {
+        header.push(BYTES | 0x19);
+        header.extend_from_slice(&medium.to_be_bytes());
+    } else if let Ok(long) = u32::try_from(length) {
+        header.push(BYTES | 0x1A);
+        header.extend_from_slice(&long.to_be_bytes());
+    } else {
+        header.push(BYTES | 0x1B);
+        header.extend_from_slice(&(length as u64).to_be_bytes());
+    }
+    header
+}
@@ -0,0 +1,107 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+use coset::cwt::ClaimsSetBuilder;
+use coset::iana::CwtClaimName;
+use coset::HeaderBuilder;
+
+#[cfg(not(feature = "std"))]
+use {alloc::string::String, alloc::string::ToString, alloc::vec};
+
+use crate::common::test_helper::FakeCrypto;
+use crate::{verify_access_token, CoseSign1Cipher};
+
+use super::*;
+
+/// Streaming variant of [`FakeCrypto`], which buffers all data and then "signs" it the same way.
+struct FakeStreamingCrypto {
+    buffer: Vec<u8>,
+    chunks: usize,
+}
+
+impl CoseCipherCommon for FakeStreamingCrypto {
+    type Error = String;
+
+    fn header(
+        &self,
+        unprotected_header: &mut Header,
+        protected_header: &mut Header,
+    ) -> Result<(), CoseCipherError<Self::Error>> {
+        FakeCrypto {}.header(unprotected_header, protected_header)
+    }
+}
+
+impl CoseSign1StreamingCipher for FakeStreamingCrypto {
+    fn update(&mut self, data: &[u8]) {
+        self.chunks += 1;
+        self.buffer.extend_from_slice(data);
+    }
+
+    fn finalize_signature(&mut self) -> Vec<u8> {
+        FakeCrypto {}.generate_signature(&core::mem::take(&mut self.buffer))
+    }
+
+    fn finalize_verification(
+        &mut self,
+        signature: &[u8],
+    ) -> Result<(), CoseCipherError<Self::Error>> {
+        FakeCrypto {}.verify_signature(signature, &core::mem::take(&mut self.buffer))
+    }
+}
+
+fn large_claims(size: usize) -> ClaimsSet {
+    ClaimsSetBuilder::new()
+        .audience("coaps://rs.example.com".to_string())
+        .claim(CwtClaimName::Scope, Value::Bytes(vec![0xDC; size]))
+        .build()
+}
+
+#[test]
+fn test_bstr_header() {
+    assert_eq!(bstr_header(0), vec![0x40]);
+    assert_eq!(bstr_header(23), vec![0x57]);
+    assert_eq!(bstr_header(24), vec![0x58, 24]);
+    assert_eq!(bstr_header(256), vec![0x59, 0x01, 0x00]);
+    assert_eq!(bstr_header(65536), vec![0x5A, 0x00, 0x01, 0x00, 0x00]);
+}
+
+#[test]
+fn test_streaming_matches_regular() -> Result<(), AccessTokenError<String>> {
+    let aad = vec![0x01, 0x02, 0x03];
+    for size in [0, 30, 300, 70000] {
+        let mut cipher = FakeStreamingCrypto {
+            buffer: Vec::new(),
+            chunks: 0,
+        };
+        let protected = HeaderBuilder::new().key_id(vec![0xDC, 0xAF]).build();
+        let token = sign_access_token_streaming(
+            large_claims(size),
+            &mut cipher,
+            Some(&aad),
+            None,
+            Some(protected),
+        )?;
+        assert!(cipher.chunks > 1);
+        // Tokens signed using the streaming cipher can be verified using the regular one...
+        verify_access_token(&token, &mut FakeCrypto {}, Some(&aad))?;
+        // ...and vice versa.
+        let regular = crate::sign_access_token(
+            large_claims(size),
+            &mut FakeCrypto {},
+            Some(&aad),
+            None,
+            None,
+        )?;
+        verify_access_token_streaming(&regular, &mut cipher, Some(&aad))?;
+        assert!(verify_access_token_streaming(&regular, &mut cipher, None).is_err());
+    }
+    Ok(())
+}
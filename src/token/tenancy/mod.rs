@@ -0,0 +1,324 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+//! Contains [`Tenants`], which lets a single Authorization Server process serve multiple
+//! independent deployments (tenants).
+//!
+//! Each [`Tenant`] has its own issuer, its own storage (e.g., an
+//! [`InMemoryStorage`](crate::token::storage::InMemoryStorage) or any other type implementing the
+//! traits of the [`storage`](crate::token::storage) module, so that clients, Resource Servers,
+//! and the issuance journal are kept apart), and its own [`AudienceKeyRing`].
+//! In order to make sure that a token of one tenant is never accepted for another one,
+//! [`Tenants`] enforces that
+//! - no two tenants use the same issuer, so that tokens can be attributed to their tenant
+//!   using [`by_issuer`](Tenants::by_issuer),
+//! - no two tenants use keys with the same key ID (or, for keys without a key ID, the same key
+//!   material), so that a token can't be verified with the key of another tenant, and
+//! - tokens presented to a tenant (e.g., for introspection) have been issued by it
+//!   ([`check_claims`](Tenants::check_claims)).
+//!
+//! # Example
+//! ```
+//! # use coset::CoseKeyBuilder;
+//! # use coset::cwt::ClaimsSetBuilder;
+//! # use dcaf::error::TenancyError;
+//! use dcaf::token::KeyUsage;
+//! use dcaf::token::storage::InMemoryStorage;
+//! use dcaf::token::tenancy::{Tenant, Tenants};
+//!
+//! let mut tenants = Tenants::new();
+//! tenants.insert(Tenant::new("acme", "coaps://as.example.com/acme", InMemoryStorage::default()))?;
+//! tenants.insert(Tenant::new("umbrella", "coaps://as.example.com/umbrella", InMemoryStorage::default()))?;
+//!
+//! let key = |key_id: u8| CoseKeyBuilder::new_symmetric_key(vec![key_id; 16]).key_id(vec![key_id]).build();
+//! tenants.insert_key("acme", "valve242", key(1), Some(KeyUsage::Sign))?;
+//! // Key IDs must be unique across tenants.
+//! assert!(tenants.insert_key("umbrella", "valve242", key(1), None).is_err());
+//!
+//! let claims = tenants.get("acme").expect("tenant exists").claims().audience("valve242".to_string()).build();
+//! assert!(tenants.check_claims("acme", &claims).is_ok());
+//! assert_eq!(
+//!     tenants.check_claims("umbrella", &claims),
+//!     Err(TenancyError::ForeignIssuer {
+//!         tenant: "umbrella".to_string(),
+//!         issuer: Some("coaps://as.example.com/acme".to_string()),
+//!     })
+//! );
+//! # Ok::<(), TenancyError>(())
+//! ```
+
+use coset::cwt::{ClaimsSet, ClaimsSetBuilder};
+use coset::CoseKey;
+
+use crate::error::{KeyUsageError, TenancyError};
+use crate::token::keys::AudienceKeyRing;
+use crate::token::KeyUsage;
+
+#[cfg(not(feature = "std"))]
+use {alloc::string::String, alloc::vec::Vec};
+
+#[cfg(test)]
+mod tests;
+
+/// A single tenant of an Authorization Server, as kept in [`Tenants`].
+///
+/// The tenant's storage `S` is usually a type implementing the traits of the
+/// [`storage`](crate::token::storage) module. Its identifier and issuer can't be changed once it
+/// has been created, so that they stay unique among all [`Tenants`].
+///
+/// For an example, see the [module-level documentation](self).
+#[derive(Debug, PartialEq, Clone)]
+pub struct Tenant<S> {
+    /// The identifier of the tenant.
+    id: String,
+
+    /// The issuer (`iss` claim) of the tokens issued for this tenant.
+    issuer: String,
+
+    /// The tenant's clients, Resource Servers, issuance journal, and so on.
+    pub storage: S,
+
+    /// The keys used to protect the tokens of the tenant's audiences.
+    keys: AudienceKeyRing,
+}
+
+impl<S> Tenant<S> {
+    /// Creates a new [`Tenant`] with the given `id`, `issuer`, and `storage`, which has no keys.
+    #[must_use]
+    pub fn new<I, T>(id: I, issuer: T, storage: S) -> Tenant<S>
+    where
+        I: Into<String>,
+        T: Into<String>,
+    {
+        Tenant {
+            id: id.into(),
+            issuer: issuer.into(),
+            storage,
+            keys: AudienceKeyRing::default(),
+        }
+    }
+
+    /// Returns the identifier of this tenant, which is unique among all [`Tenants`].
+    #[must_use]
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Returns the issuer (`iss` claim) of the tokens issued for this tenant, which is unique
+    /// among all [`Tenants`].
+    #[must_use]
+    pub fn issuer(&self) -> &str {
+        &self.issuer
+    }
+
+    /// Returns the keys of this tenant.
+    ///
+    /// Keys can only be added using [`Tenants::insert_key`], which makes sure that they aren't
+    /// used by any other tenant.
+    #[must_use]
+    pub fn keys(&self) -> &AudienceKeyRing {
+        &self.keys
+    }
+
+    /// Returns a [`ClaimsSetBuilder`] containing the `iss` claim of this tenant, to which the
+    /// remaining claims of a token can be added (e.g., from an
+    /// [`IssuanceGrant`](crate::token::policy::IssuanceGrant)).
+    #[must_use]
+    pub fn claims(&self) -> ClaimsSetBuilder {
+        ClaimsSetBuilder::new().issuer(self.issuer.clone())
+    }
+}
+
+/// The tenants of an Authorization Server, which are kept isolated from each other.
+///
+/// For an example, see the [module-level documentation](self).
+#[derive(Debug, PartialEq, Clone)]
+pub struct Tenants<S> {
+    /// The registered tenants.
+    tenants: Vec<Tenant<S>>,
+}
+
+impl<S> Default for Tenants<S> {
+    fn default() -> Self {
+        Tenants {
+            tenants: Vec::new(),
+        }
+    }
+}
+
+impl<S> Tenants<S> {
+    /// Creates a new, empty set of tenants.
+    #[must_use]
+    pub fn new() -> Tenants<S> {
+        Tenants::default()
+    }
+
+    /// Returns the number of tenants.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.tenants.len()
+    }
+
+    /// Returns `true` if there are no tenants.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.tenants.is_empty()
+    }
+
+    /// Adds the given `tenant`.
+    ///
+    /// # Errors
+    /// - If a tenant with the same [`id`](Tenant::id) already exists
+    ///   ([`TenancyError::DuplicateTenant`]).
+    /// - If another tenant uses the same [`issuer`](Tenant::issuer)
+    ///   ([`TenancyError::DuplicateIssuer`]).
+    /// - If another tenant uses a key with the same key ID as one of the tenant's keys
+    ///   ([`TenancyError::SharedKeyId`]), or the same key material as one of its keys without a
+    ///   key ID ([`TenancyError::SharedKey`]).
+    pub fn insert(&mut self, tenant: Tenant<S>) -> Result<(), TenancyError> {
+        if self.get(&tenant.id).is_some() {
+            return Err(TenancyError::DuplicateTenant(tenant.id));
+        }
+        if let Some(other) = self.by_issuer(&tenant.issuer) {
+            return Err(TenancyError::DuplicateIssuer {
+                issuer: tenant.issuer,
+                tenant: other.id.clone(),
+            });
+        }
+        for (_, key) in tenant.keys.iter() {
+            self.check_key(&tenant.id, key)?;
+        }
+        self.tenants.push(tenant);
+        Ok(())
+    }
+
+    /// Removes and returns the tenant with the given `id`, if any.
+    pub fn remove(&mut self, id: &str) -> Option<Tenant<S>> {
+        let index = self.tenants.iter().position(|x| x.id == id)?;
+        Some(self.tenants.remove(index))
+    }
+
+    /// Returns the tenant with the given `id`, if any.
+    #[must_use]
+    pub fn get(&self, id: &str) -> Option<&Tenant<S>> {
+        self.tenants.iter().find(|x| x.id == id)
+    }
+
+    /// Returns the tenant with the given `id` mutably (e.g., to modify its storage), if any.
+    ///
+    /// Its identifier and issuer can't be modified this way.
+    pub fn get_mut(&mut self, id: &str) -> Option<&mut Tenant<S>> {
+        self.tenants.iter_mut().find(|x| x.id == id)
+    }
+
+    /// Returns the tenant using the given `issuer`, if any.
+    #[must_use]
+    pub fn by_issuer(&self, issuer: &str) -> Option<&Tenant<S>> {
+        self.tenants.iter().find(|x| x.issuer == issuer)
+    }
+
+    /// Adds the given `key` to the key ring of the tenant with the given `id`, associating it
+    /// with the given `audience` and, if given, restricting it to the given `usage`
+    /// (see [`AudienceKeyRing::declare_usage`]).
+    ///
+    /// # Errors
+    /// - If there is no tenant with the given `id` ([`TenancyError::UnknownTenant`]).
+    /// - If another tenant uses a key with the same key ID ([`TenancyError::SharedKeyId`]), or,
+    ///   if the `key` has no key ID, a key with the same key material ([`TenancyError::SharedKey`]).
+    /// - If the tenant itself already uses a different key with the same key ID
+    ///   ([`TenancyError::KeyIdCollision`]).
+    /// - If a `usage` is given for a key without a key ID, or the tenant has already declared
+    ///   another usage for the key ([`TenancyError::KeyUsage`]).
+    ///
+    /// The key is only added if none of these errors occur.
+    pub fn insert_key<T>(
+        &mut self,
+        id: &str,
+        audience: T,
+        key: CoseKey,
+        usage: Option<KeyUsage>,
+    ) -> Result<(), TenancyError>
+    where
+        T: Into<String>,
+    {
+        self.check_key(id, &key)?;
+        let keys = &mut self
+            .get_mut(id)
+            .ok_or_else(|| TenancyError::UnknownTenant(id.into()))?
+            .keys;
+        if let Some(usage) = usage {
+            if key.key_id.is_empty() {
+                return Err(KeyUsageError::UnknownKeyId(key.key_id).into());
+            }
+            if let Some(declared) = keys.usage(&key.key_id).filter(|x| *x != usage) {
+                return Err(KeyUsageError::UsageMismatch {
+                    key_id: key.key_id,
+                    declared,
+                    requested: usage,
+                }
+                .into());
+            }
+        }
+        let key_id = key.key_id.clone();
+        keys.insert(audience, key)?;
+        if let Some(usage) = usage {
+            keys.declare_usage(&key_id, usage)?;
+        }
+        Ok(())
+    }
+
+    /// Checks that the given access token `claims`, which have been presented to the tenant with
+    /// the given `id` (e.g., for introspection), have been issued for this tenant, returning it.
+    ///
+    /// # Errors
+    /// - If there is no tenant with the given `id` ([`TenancyError::UnknownTenant`]).
+    /// - If the `iss` claim is missing or belongs to another issuer
+    ///   ([`TenancyError::ForeignIssuer`]).
+    pub fn check_claims(&self, id: &str, claims: &ClaimsSet) -> Result<&Tenant<S>, TenancyError> {
+        let tenant = self
+            .get(id)
+            .ok_or_else(|| TenancyError::UnknownTenant(id.into()))?;
+        if claims.issuer.as_deref() == Some(tenant.issuer.as_str()) {
+            Ok(tenant)
+        } else {
+            Err(TenancyError::ForeignIssuer {
+                tenant: tenant.id.clone(),
+                issuer: claims.issuer.clone(),
+            })
+        }
+    }
+
+    /// Checks that the key ID of the given `key` (or, if it has none, its key material) isn't
+    /// used by any tenant other than the one with the given `id`.
+    fn check_key(&self, id: &str, key: &CoseKey) -> Result<(), TenancyError> {
+        let shares_key = |x: &CoseKey| {
+            if key.key_id.is_empty() {
+                x.kty == key.kty && x.params == key.params
+            } else {
+                x.key_id == key.key_id
+            }
+        };
+        match self
+            .tenants
+            .iter()
+            .find(|tenant| tenant.id != id && tenant.keys.iter().any(|(_, x)| shares_key(x)))
+        {
+            Some(other) if key.key_id.is_empty() => Err(TenancyError::SharedKey {
+                tenant: other.id.clone(),
+            }),
+            Some(other) => Err(TenancyError::SharedKeyId {
+                key_id: key.key_id.clone(),
+                tenant: other.id.clone(),
+            }),
+            None => Ok(()),
+        }
+    }
+}
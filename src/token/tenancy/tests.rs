@@ -0,0 +1,216 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+use coset::CoseKeyBuilder;
+
+use crate::error::KeyUsageError;
+use crate::token::storage::{ClientRecord, ClientRegistry, InMemoryStorage};
+
+#[cfg(not(feature = "std"))]
+use {alloc::string::ToString, alloc::vec, alloc::vec::Vec};
+
+use super::*;
+
+fn key(key_id: u8, material: u8) -> CoseKey {
+    CoseKeyBuilder::new_symmetric_key(vec![material; 16])
+        .key_id(vec![key_id])
+        .build()
+}
+
+fn tenants() -> Result<Tenants<InMemoryStorage>, TenancyError> {
+    let mut tenants = Tenants::new();
+    tenants.insert(Tenant::new("a", "as/a", InMemoryStorage::default()))?;
+    tenants.insert(Tenant::new("b", "as/b", InMemoryStorage::default()))?;
+    Ok(tenants)
+}
+
+#[test]
+fn test_duplicate_tenants() -> Result<(), TenancyError> {
+    let mut tenants = tenants()?;
+    assert_eq!(
+        tenants.insert(Tenant::new("a", "as/c", InMemoryStorage::default())),
+        Err(TenancyError::DuplicateTenant("a".to_string()))
+    );
+    assert_eq!(
+        tenants.insert(Tenant::new("c", "as/b", InMemoryStorage::default())),
+        Err(TenancyError::DuplicateIssuer {
+            issuer: "as/b".to_string(),
+            tenant: "b".to_string()
+        })
+    );
+    assert_eq!(tenants.len(), 2);
+    assert_eq!(tenants.by_issuer("as/b").map(Tenant::id), Some("b"));
+    assert!(tenants.remove("b").is_some());
+    tenants.insert(Tenant::new("c", "as/b", InMemoryStorage::default()))?;
+    assert_eq!(tenants.by_issuer("as/b").map(Tenant::id), Some("c"));
+    Ok(())
+}
+
+#[test]
+fn test_storage_isolation() -> Result<(), TenancyError> {
+    let mut tenants = tenants()?;
+    tenants
+        .get_mut("a")
+        .expect("tenant exists")
+        .storage
+        .register_client(ClientRecord::new("client"))
+        .expect("in-memory storage is infallible");
+    let client = |tenants: &Tenants<InMemoryStorage>, id: &str| {
+        tenants
+            .get(id)
+            .expect("tenant exists")
+            .storage
+            .client("client")
+            .expect("in-memory storage is infallible")
+    };
+    assert!(client(&tenants, "a").is_some());
+    assert!(client(&tenants, "b").is_none());
+    Ok(())
+}
+
+#[test]
+fn test_key_isolation() -> Result<(), TenancyError> {
+    let mut tenants = tenants()?;
+    tenants.insert_key("a", "rs1", key(1, 1), None)?;
+    // The same key for another audience of the same tenant is fine.
+    tenants.insert_key("a", "rs2", key(1, 1), None)?;
+    assert_eq!(
+        tenants.insert_key("b", "rs1", key(1, 1), None),
+        Err(TenancyError::SharedKeyId {
+            key_id: vec![1],
+            tenant: "a".to_string()
+        })
+    );
+    assert!(matches!(
+        tenants.insert_key("a", "rs1", key(1, 2), None),
+        Err(TenancyError::KeyIdCollision(_))
+    ));
+    assert_eq!(
+        tenants.insert_key("c", "rs1", key(2, 2), None),
+        Err(TenancyError::UnknownTenant("c".to_string()))
+    );
+    tenants.insert_key("b", "rs1", key(2, 2), None)?;
+    assert_eq!(
+        tenants
+            .get("a")
+            .expect("tenant exists")
+            .keys()
+            .iter()
+            .count(),
+        2
+    );
+    assert_eq!(
+        tenants
+            .get("b")
+            .expect("tenant exists")
+            .keys()
+            .keys_for("rs1", KeyUsage::Sign)
+            .count(),
+        1
+    );
+
+    let mut shared = Tenant::new("c", "as/c", InMemoryStorage::default());
+    shared.keys.insert("rs1", key(2, 2))?;
+    assert_eq!(
+        tenants.insert(shared),
+        Err(TenancyError::SharedKeyId {
+            key_id: vec![2],
+            tenant: "b".to_string()
+        })
+    );
+    Ok(())
+}
+
+#[test]
+fn test_key_isolation_without_key_id() -> Result<(), TenancyError> {
+    let mut tenants = tenants()?;
+    let unnamed = |material: u8| CoseKeyBuilder::new_symmetric_key(vec![material; 16]).build();
+    tenants.insert_key("a", "rs1", unnamed(1), None)?;
+    // Keys without a key ID are compared by their key material, whether the other one has a
+    // key ID or not.
+    tenants.insert_key("a", "rs2", key(3, 3), None)?;
+    for material in [1, 3] {
+        assert_eq!(
+            tenants.insert_key("b", "rs1", unnamed(material), None),
+            Err(TenancyError::SharedKey {
+                tenant: "a".to_string()
+            })
+        );
+    }
+    tenants.insert_key("b", "rs1", unnamed(2), None)?;
+
+    let mut shared = Tenant::new("c", "as/c", InMemoryStorage::default());
+    shared.keys.insert("rs1", unnamed(2))?;
+    assert_eq!(
+        tenants.insert(shared),
+        Err(TenancyError::SharedKey {
+            tenant: "b".to_string()
+        })
+    );
+    Ok(())
+}
+
+#[test]
+fn test_key_usage() -> Result<(), TenancyError> {
+    let mut tenants = tenants()?;
+    tenants.insert_key("a", "rs1", key(1, 1), Some(KeyUsage::Sign))?;
+    let keys = tenants.get("a").expect("tenant exists").keys();
+    assert_eq!(keys.keys_for("rs1", KeyUsage::Sign).count(), 1);
+    // A signing-only key is refused for encryption.
+    assert_eq!(keys.keys_for("rs1", KeyUsage::Encrypt).count(), 0);
+    assert!(keys.key_for(&[1], KeyUsage::Encrypt).is_err());
+
+    let mismatch = TenancyError::KeyUsage(KeyUsageError::UsageMismatch {
+        key_id: vec![1],
+        declared: KeyUsage::Sign,
+        requested: KeyUsage::Encrypt,
+    });
+    assert_eq!(
+        tenants.insert_key("a", "rs2", key(1, 1), Some(KeyUsage::Encrypt)),
+        Err(mismatch)
+    );
+    let keys = tenants.get("a").expect("tenant exists").keys();
+    assert_eq!(keys.keys_for("rs2", KeyUsage::Sign).count(), 0);
+    let unnamed = CoseKeyBuilder::new_symmetric_key(vec![2; 16]).build();
+    assert_eq!(
+        tenants.insert_key("a", "rs2", unnamed, Some(KeyUsage::Sign)),
+        Err(TenancyError::KeyUsage(KeyUsageError::UnknownKeyId(
+            Vec::new()
+        )))
+    );
+    Ok(())
+}
+
+#[test]
+fn test_check_claims() -> Result<(), TenancyError> {
+    let tenants = tenants()?;
+    let claims = tenants.get("a").expect("tenant exists").claims().build();
+    assert_eq!(tenants.check_claims("a", &claims)?.id(), "a");
+    assert_eq!(
+        tenants.check_claims("b", &claims),
+        Err(TenancyError::ForeignIssuer {
+            tenant: "b".to_string(),
+            issuer: Some("as/a".to_string())
+        })
+    );
+    assert_eq!(
+        tenants.check_claims("b", &ClaimsSetBuilder::new().build()),
+        Err(TenancyError::ForeignIssuer {
+            tenant: "b".to_string(),
+            issuer: None
+        })
+    );
+    assert_eq!(
+        tenants.check_claims("c", &claims),
+        Err(TenancyError::UnknownTenant("c".to_string()))
+    );
+    Ok(())
+}
@@ -9,8 +9,14 @@
  * SPDX-License-Identifier: MIT OR Apache-2.0
  */
 
+#[cfg(all(feature = "rs", not(feature = "std")))]
+use alloc::format;
 #[cfg(not(feature = "std"))]
-use alloc::vec;
+use alloc::{
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
 
 use ciborium::value::Value;
 use coset::cwt::ClaimsSetBuilder;
@@ -20,6 +26,16 @@ use coset::{AsCborValue, CoseKey, CoseKeyBuilder, CoseMac0Builder, HeaderBuilder
 use crate::common::test_helper::FakeCrypto;
 use crate::error::CoseCipherError;
 
+#[cfg(feature = "rs")]
+use {
+    crate::common::cbor_map::{DuplicateKeyHandling, ToCborMap},
+    crate::common::cbor_values::ProofOfPossessionKey,
+    crate::common::security::KeyStrengthPolicy,
+    crate::error::ClaimsValidationError,
+    crate::token::format::{CoseMac0Format, TokenFormat},
+    coset::cwt::Timestamp,
+};
+
 use super::*;
 
 fn example_key() -> CoseKey {
@@ -267,3 +283,459 @@ fn test_sign_verify() -> Result<(), AccessTokenError<<FakeCrypto as CoseCipherCo
     verify_access_token(&signed, &mut crypto, Some(&aad))?;
     Ok(())
 }
+
+#[test]
+fn test_canonical_headers_on_issuance(
+) -> Result<(), AccessTokenError<<FakeCrypto as CoseCipherCommon>::Error>> {
+    let mut crypto = FakeCrypto {};
+    let protected_header = HeaderBuilder::new()
+        .text_value("b".to_string(), Value::Null)
+        .text_value("aa".to_string(), Value::Null)
+        .value(-2, Value::Null)
+        .value(-1, Value::Null)
+        .value(300, Value::Null)
+        .value(8, Value::Null)
+        .build();
+    let signed = sign_access_token(
+        example_claims(example_key())?,
+        &mut crypto,
+        None,
+        None,
+        Some(protected_header),
+    )?;
+    let (_, protected) =
+        get_token_headers(&signed).ok_or(AccessTokenError::UnknownCoseStructure)?;
+    let labels: Vec<Label> = protected.header.rest.into_iter().map(|x| x.0).collect();
+    assert_eq!(
+        labels,
+        vec![
+            Label::Int(8),
+            Label::Int(300),
+            Label::Int(-1),
+            Label::Int(-2),
+            Label::Text("b".to_string()),
+            Label::Text("aa".to_string()),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_reemitted_token_verifies(
+) -> Result<(), AccessTokenError<<FakeCrypto as CoseCipherCommon>::Error>> {
+    let mut crypto = FakeCrypto {};
+    // A protected header whose parameters aren't in canonical order: {-1: null, 47: null}.
+    let protected =
+        ProtectedHeader::from_cbor_bstr(Value::Bytes(vec![0xA2, 0x20, 0xF6, 0x18, 0x2F, 0xF6]))
+            .map_err(AccessTokenError::from_cose_error)?;
+    let mut sign = CoseSign1 {
+        protected,
+        unprotected: Header::default(),
+        payload: Some(
+            example_claims(example_key())?
+                .to_vec()
+                .map_err(AccessTokenError::from_cose_error)?,
+        ),
+        signature: Vec::new(),
+    };
+    sign.signature = CoseSign1Cipher::generate_signature(&mut crypto, &sign.tbs_data(&[]));
+    let token = sign.to_vec().map_err(AccessTokenError::from_cose_error)?;
+    verify_access_token(&token, &mut crypto, None)?;
+
+    // A proxy parses the token, modifies its unprotected header, and re-emits it.
+    let mut parsed =
+        CoseSign1::from_slice(token.as_slice()).map_err(AccessTokenError::from_cose_error)?;
+    parsed.unprotected = HeaderBuilder::new().key_id(vec![0xDC, 0xAF]).build();
+    let reemitted = parsed.to_vec().map_err(AccessTokenError::from_cose_error)?;
+    assert_ne!(reemitted, token);
+    verify_access_token(&reemitted, &mut crypto, None)?;
+    let (unprotected, protected) =
+        get_token_headers(&reemitted).ok_or(AccessTokenError::UnknownCoseStructure)?;
+    assert_eq!(unprotected.key_id, vec![0xDC, 0xAF]);
+    assert_eq!(
+        protected.original_data,
+        Some(vec![0xA2, 0x20, 0xF6, 0x18, 0x2F, 0xF6])
+    );
+    Ok(())
+}
+
+#[test]
+fn test_update_unprotected_header_sign(
+) -> Result<(), AccessTokenError<<FakeCrypto as CoseCipherCommon>::Error>> {
+    let mut crypto = FakeCrypto {};
+    let (unprotected_header, protected_header) = example_headers();
+    let token = sign_access_token(
+        example_claims(example_key())?,
+        &mut crypto,
+        None,
+        Some(unprotected_header),
+        Some(protected_header),
+    )?;
+    let updated = update_unprotected_header(&token, |header| {
+        header
+            .rest
+            .push((Label::Text("rs".to_string()), Value::Integer(2.into())));
+    })
+    .map_err(|_| AccessTokenError::UnknownCoseStructure)?;
+    assert_ne!(updated, token);
+    verify_access_token(&updated, &mut crypto, None)?;
+    let (unprotected, protected) =
+        get_token_headers(&updated).ok_or(AccessTokenError::UnknownCoseStructure)?;
+    let (_, original_protected) =
+        get_token_headers(&token).ok_or(AccessTokenError::UnknownCoseStructure)?;
+    assert!(unprotected
+        .rest
+        .contains(&(Label::Text("rs".to_string()), Value::Integer(2.into()))));
+    assert_eq!(protected.original_data, original_protected.original_data);
+    Ok(())
+}
+
+#[test]
+fn test_update_unprotected_header_encrypt(
+) -> Result<(), AccessTokenError<<FakeCrypto as CoseCipherCommon>::Error>> {
+    let mut crypto = FakeCrypto {};
+    let claims = example_claims(example_key())?;
+    let (unprotected_header, protected_header) = example_headers();
+    let token = encrypt_access_token(
+        claims.clone(),
+        &mut crypto,
+        None,
+        Some(unprotected_header),
+        Some(protected_header),
+    )?;
+    let (unprotected, _) =
+        get_token_headers(&token).ok_or(AccessTokenError::UnknownCoseStructure)?;
+
+    let updated = update_unprotected_header(&token, |header| {
+        header.content_type = Some(coset::ContentType::Text("application/cwt".to_string()));
+    })
+    .map_err(|_| AccessTokenError::UnknownCoseStructure)?;
+    assert_eq!(decrypt_access_token(&updated, &mut crypto, None)?, claims);
+
+    // The IV must stay the same, and labels of the protected header must not be duplicated.
+    assert!(matches!(
+        update_unprotected_header(&token, |header| header.iv = vec![0; 13]),
+        Err(HeaderUpdateError::ImmutableParameter(Label::Int(5)))
+    ));
+    assert!(matches!(
+        update_unprotected_header(&token, |header| header.key_id = vec![0xDC, 0xAF]),
+        Err(HeaderUpdateError::ProtectedLabel(Label::Int(4)))
+    ));
+    // Leaving the header as is yields the same token.
+    assert_eq!(
+        update_unprotected_header(&token, |header| assert_eq!(header, &unprotected)).ok(),
+        Some(token)
+    );
+    assert!(matches!(
+        update_unprotected_header(&vec![0xA0], |_| {}),
+        Err(HeaderUpdateError::UnknownCoseStructure)
+    ));
+    Ok(())
+}
+
+/// A cipher which can only verify signatures, as used by a Resource Server.
+struct FakeVerifier;
+
+impl CoseVerifyCipher for FakeVerifier {
+    type Error = String;
+
+    fn verify_signature(
+        &mut self,
+        signature: &[u8],
+        signed_data: &[u8],
+    ) -> Result<(), CoseCipherError<Self::Error>> {
+        if signature == signed_data {
+            Ok(())
+        } else {
+            Err(CoseCipherError::VerificationFailure)
+        }
+    }
+}
+
+/// A cipher which can only decrypt tokens, as used by a Resource Server.
+struct FakeDecrypter;
+
+impl CoseDecryptCipher for FakeDecrypter {
+    type Error = String;
+
+    fn capabilities(&self) -> Option<CipherCapabilities> {
+        Some(CipherCapabilities::new(
+            vec![crate::common::cose::Algorithm::A128Gcm],
+            vec![coset::iana::KeyType::Symmetric],
+        ))
+    }
+
+    fn decrypt(
+        &mut self,
+        ciphertext: &[u8],
+        aad: &[u8],
+    ) -> Result<Vec<u8>, CoseCipherError<Self::Error>> {
+        ciphertext
+            .strip_suffix(aad)
+            .map(<[u8]>::to_vec)
+            .ok_or(CoseCipherError::DecryptionFailure)
+    }
+}
+
+#[test]
+fn test_single_capability_ciphers(
+) -> Result<(), AccessTokenError<<FakeCrypto as CoseCipherCommon>::Error>> {
+    let claims = example_claims(example_key())?;
+    let aad = example_aad();
+    let signed = sign_access_token(claims.clone(), &mut FakeCrypto {}, Some(&aad), None, None)?;
+    verify_access_token(&signed, &mut FakeVerifier, Some(&aad))?;
+    assert!(verify_access_token(&signed, &mut FakeVerifier, None).is_err());
+
+    let encrypted =
+        encrypt_access_token(claims.clone(), &mut FakeCrypto {}, Some(&aad), None, None)?;
+    assert_eq!(
+        decrypt_access_token(&encrypted, &mut FakeDecrypter, Some(&aad))?,
+        claims
+    );
+    Ok(())
+}
+
+/// A verifier which doesn't know any keys.
+struct KeylessVerifier;
+
+impl CoseVerifyCipher for KeylessVerifier {
+    type Error = String;
+
+    fn verify_signature(
+        &mut self,
+        _signature: &[u8],
+        _signed_data: &[u8],
+    ) -> Result<(), CoseCipherError<Self::Error>> {
+        Err(CoseCipherError::key_not_found(vec![0xDC, 0xAF]))
+    }
+}
+
+#[test]
+fn test_cipher_error_propagation(
+) -> Result<(), AccessTokenError<<FakeCrypto as CoseCipherCommon>::Error>> {
+    let claims = example_claims(example_key())?;
+    let signed = sign_access_token(claims, &mut FakeCrypto {}, None, None, None)?;
+    let error = verify_access_token(&signed, &mut KeylessVerifier, None)
+        .expect_err("verifier without keys must fail");
+    assert_eq!(
+        error.cipher_error(),
+        Some(&CoseCipherError::KeyNotFound {
+            key_id: vec![0xDC, 0xAF]
+        })
+    );
+    assert_eq!(
+        error.to_string(),
+        "cipher error: no key found for key ID [dc, af]"
+    );
+    #[cfg(feature = "std")]
+    assert_eq!(
+        std::error::Error::source(&error).map(ToString::to_string),
+        Some(String::from("no key found for key ID [dc, af]"))
+    );
+
+    let unsupported: CoseCipherError<String> =
+        CoseCipherError::unsupported_algorithm(Some(crate::common::cose::Algorithm::Es256));
+    assert_eq!(unsupported.to_string(), "unsupported algorithm Es256");
+    assert_eq!(unsupported.backend_error(), None);
+    assert_eq!(
+        CoseCipherError::other_error(String::from("HSM unavailable")).backend_error(),
+        Some(&String::from("HSM unavailable"))
+    );
+    Ok(())
+}
+
+#[test]
+fn test_capabilities() {
+    assert_eq!(CoseCipherCommon::capabilities(&FakeCrypto {}), None);
+    // Forwarded by the blanket implementation for `CoseSign1Cipher`s.
+    assert_eq!(CoseVerifyCipher::capabilities(&FakeCrypto {}), None);
+    assert_eq!(FakeVerifier.capabilities(), None);
+    let capabilities = FakeDecrypter
+        .capabilities()
+        .expect("capabilities must be declared");
+    assert!(capabilities.supports_algorithm(crate::common::cose::Algorithm::A128Gcm));
+    // Algorithms are compared by their value, however they have been constructed.
+    assert!(capabilities.supports_algorithm(crate::common::cose::Algorithm::Other(1)));
+    assert!(capabilities.supports_key(&example_key()));
+}
+
+/// Returns the given `payload` wrapped in a `COSE_Mac0` structure tagged by [`FakeCrypto`].
+#[cfg(feature = "rs")]
+fn fake_mac0(payload: Vec<u8>) -> ByteString {
+    CoseMac0Builder::new()
+        .payload(payload)
+        .create_tag(&[], |x| FakeCrypto {}.generate_tag(x))
+        .build()
+        .to_vec()
+        .expect("invalid COSE_Mac0 structure")
+}
+
+#[test]
+#[cfg(feature = "rs")]
+fn test_security_config_enforced(
+) -> Result<(), AccessTokenError<<FakeCrypto as CoseCipherCommon>::Error>> {
+    let mut crypto = FakeCrypto {};
+    let weak_key = ProofOfPossessionKey::PlainCoseKey(
+        CoseKeyBuilder::new_symmetric_key(vec![0xDC, 0xAF]).build(),
+    );
+    let claims = ClaimsSetBuilder::new()
+        .audience("rs1".to_string())
+        .claim(CwtClaimName::Cnf, weak_key.to_ciborium_value())
+        .build();
+    let signed = sign_access_token(claims.clone(), &mut crypto, None, None, None)?;
+    let encrypted = encrypt_access_token(claims.clone(), &mut crypto, None, None, None)?;
+    let maced = CoseMac0Format::new(FakeCrypto {}).encode(claims.clone())?;
+    let lenient = SecurityConfig::default();
+    assert_eq!(
+        verify_access_token_with_config(&signed, &mut crypto, None, &lenient, "rs1", None)?,
+        claims
+    );
+    assert_eq!(
+        decrypt_access_token_with_config(&encrypted, &mut crypto, None, &lenient, "rs1", None)?,
+        claims
+    );
+    assert_eq!(
+        verify_access_token_tag_with_config(&maced, &mut crypto, None, &lenient, "rs1", None)?,
+        claims
+    );
+
+    let small = SecurityConfig {
+        max_message_size: Some(8),
+        ..SecurityConfig::default()
+    };
+    let restricted = SecurityConfig {
+        allowed_algorithms: Some(vec![crate::common::cose::Algorithm::A128Gcm]),
+        ..SecurityConfig::default()
+    };
+    let strong = SecurityConfig {
+        key_strength: KeyStrengthPolicy::strict(),
+        ..SecurityConfig::default()
+    };
+    for (config, expected) in [
+        (small, "message is"),
+        (restricted, "algorithm"),
+        (strong, "weak key"),
+    ] {
+        for error in [
+            verify_access_token_with_config(&signed, &mut crypto, None, &config, "rs1", None)
+                .unwrap_err(),
+            decrypt_access_token_with_config(&encrypted, &mut crypto, None, &config, "rs1", None)
+                .unwrap_err(),
+            verify_access_token_tag_with_config(&maced, &mut crypto, None, &config, "rs1", None)
+                .unwrap_err(),
+        ] {
+            assert!(matches!(error, AccessTokenError::SecurityConfig(_)));
+            assert!(error
+                .to_string()
+                .starts_with(&format!("rejected by security configuration: {expected}")));
+        }
+    }
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "rs")]
+fn test_security_config_claims(
+) -> Result<(), AccessTokenError<<FakeCrypto as CoseCipherCommon>::Error>> {
+    let mut crypto = FakeCrypto {};
+    let config = SecurityConfig::default();
+    let claims = ClaimsSetBuilder::new()
+        .audience("rs1".to_string())
+        .expiration_time(Timestamp::WholeSeconds(100))
+        .build();
+    let signed = sign_access_token(claims.clone(), &mut crypto, None, None, None)?;
+    // The claims are checked for the given audience...
+    assert!(matches!(
+        verify_access_token_with_config(&signed, &mut crypto, None, &config, "rs2", None),
+        Err(AccessTokenError::InvalidClaims(
+            ClaimsValidationError::AudienceMismatch(_)
+        ))
+    ));
+    // ...and at the given time, if any.
+    assert_eq!(
+        verify_access_token_with_config(&signed, &mut crypto, None, &config, "rs1", Some(99))?,
+        claims
+    );
+    assert!(matches!(
+        verify_access_token_with_config(&signed, &mut crypto, None, &config, "rs1", Some(100)),
+        Err(AccessTokenError::InvalidClaims(
+            ClaimsValidationError::Expired { .. }
+        ))
+    ));
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "rs")]
+fn test_security_config_token_encoding(
+) -> Result<(), AccessTokenError<<FakeCrypto as CoseCipherCommon>::Error>> {
+    let mut crypto = FakeCrypto {};
+    let deterministic = SecurityConfig {
+        deterministic_encoding: true,
+        ..SecurityConfig::default()
+    };
+    let no_duplicates = SecurityConfig {
+        duplicates: DuplicateKeyHandling::Reject,
+        ..SecurityConfig::default()
+    };
+    // {3: "rs1", 1: "as"}, whose keys aren't sorted.
+    let unsorted = fake_mac0(vec![
+        0xA2, 0x03, 0x63, 0x72, 0x73, 0x31, 0x01, 0x62, 0x61, 0x73,
+    ]);
+    // {1: "as", 3: "rs1"}
+    let sorted = fake_mac0(vec![
+        0xA2, 0x01, 0x62, 0x61, 0x73, 0x03, 0x63, 0x72, 0x73, 0x31,
+    ]);
+    for token in [&unsorted, &sorted] {
+        assert_eq!(
+            verify_access_token_tag_with_config(
+                token,
+                &mut crypto,
+                None,
+                &no_duplicates,
+                "rs1",
+                None
+            )?
+            .issuer,
+            Some("as".to_string())
+        );
+    }
+    verify_access_token_tag_with_config(&sorted, &mut crypto, None, &deterministic, "rs1", None)?;
+    assert!(matches!(
+        verify_access_token_tag_with_config(
+            &unsorted,
+            &mut crypto,
+            None,
+            &deterministic,
+            "rs1",
+            None
+        ),
+        Err(AccessTokenError::SecurityConfig(
+            SecurityConfigError::NonDeterministicEncoding
+        ))
+    ));
+    // {3: "rs1", 3: "rs1"}, in which the audience is given twice.
+    let duplicate_claim = fake_mac0(vec![
+        0xA2, 0x03, 0x63, 0x72, 0x73, 0x31, 0x03, 0x63, 0x72, 0x73, 0x31,
+    ]);
+    // [h'', {4: h'01', 4: h'01'}, h'A0', h'A0'], in which the key ID header is given twice.
+    let duplicate_header = vec![
+        0x84, 0x40, 0xA2, 0x04, 0x41, 0x01, 0x04, 0x41, 0x01, 0x41, 0xA0, 0x41, 0xA0,
+    ];
+    for token in [&duplicate_claim, &duplicate_header] {
+        assert!(matches!(
+            verify_access_token_tag_with_config(
+                token,
+                &mut crypto,
+                None,
+                &no_duplicates,
+                "rs1",
+                None
+            ),
+            Err(AccessTokenError::SecurityConfig(
+                SecurityConfigError::InvalidMessage(_)
+            ))
+        ));
+    }
+    Ok(())
+}
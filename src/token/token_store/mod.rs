@@ -0,0 +1,644 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+//! Contains the [`TokenStore`], in which a Resource Server keeps the access tokens it has
+//! accepted, bound to the identity of the client which posted them.
+//!
+//! Constrained Resource Servers can only keep a limited number of tokens in memory, but still
+//! have to behave predictably when more clients present tokens than fit. The store is hence
+//! created with a fixed capacity and an [`EvictionStrategy`], which determines what happens
+//! once it is full:
+//! - [`LeastRecentlyUsed`](EvictionStrategy::LeastRecentlyUsed) evicts the token which hasn't
+//!   been [accessed](TokenStore::get) for the longest time.
+//! - [`NearestExpiry`](EvictionStrategy::NearestExpiry) evicts the token which expires first.
+//! - [`RejectNew`](EvictionStrategy::RejectNew) keeps all stored tokens and rejects the new one.
+//!
+//! Every evicted or rejected token is reported to an [`OverflowHandler`], e.g., to log the event
+//! or to tear down the security context associated with an evicted token.
+//!
+//! The scope of each token is compiled into a [`ScopeMatcher`] when it's inserted, so that
+//! [`is_authorized`](TokenStore::is_authorized) can check incoming requests without allocating.
+//!
+//! # Forwarding Tokens
+//! Besides its claims, a [`StoredToken`] can keep the [access token](StoredToken::token) exactly as
+//! it has been received, using [`StoredToken::with_token`]. This way, header parameters unknown to
+//! this crate (in both the protected and the unprotected header) and their encoding are preserved,
+//! so that the token can be forwarded byte-identically, e.g., to a backend validator.
+//!
+//! # Persistence
+//! So that clients don't have to post their tokens again after the Resource Server rebooted, the
+//! whole store (including its configuration and the recency of its tokens) can be
+//! [saved](TokenStore::save) as CBOR, e.g., to a region of flash memory, and later be
+//! [restored](TokenStore::restore). The compiled scopes aren't saved, but compiled anew when the
+//! store is restored. Note that tokens which have expired in the meantime should then be removed
+//! using [`remove_expired`](TokenStore::remove_expired).
+//!
+//! # Example
+//! ```
+//! # use coset::cwt::ClaimsSetBuilder;
+//! # use dcaf::error::TokenStoreError;
+//! use dcaf::token::token_store::{EvictionStrategy, Overflow, StoredToken, TokenStore};
+//!
+//! let mut store = TokenStore::new(2, EvictionStrategy::NearestExpiry);
+//! let token = |identity: &str, expires_at| {
+//!     StoredToken::new(identity, ClaimsSetBuilder::new().build(), Some(expires_at))
+//! };
+//! let mut evicted = Vec::new();
+//! let mut on_overflow = |overflow: Overflow<'_>| {
+//!     if let Overflow::Evicted(token) = overflow {
+//!         evicted.push(token.identity.clone());
+//!     }
+//! };
+//! store.insert(token("client1", 2000), &mut on_overflow)?;
+//! store.insert(token("client2", 1000), &mut on_overflow)?;
+//! // The store is full, so the token of client2 (which expires first) is evicted.
+//! store.insert(token("client3", 3000), &mut on_overflow)?;
+//! assert_eq!(evicted, vec!["client2"]);
+//! assert!(store.get("client2").is_none());
+//! assert_eq!(store.len(), 2);
+//! # Ok::<(), TokenStoreError>(())
+//! ```
+
+use core::fmt::Debug;
+
+use ciborium::de::from_reader;
+use ciborium::ser::into_writer;
+use ciborium::value::Value;
+use ciborium_io::{Read, Write};
+use coset::cwt::ClaimsSet;
+use coset::{AsCborValue, Header, ProtectedHeader};
+
+use crate::common::cbor_values::ByteString;
+use crate::common::scope::{AifRestMethod, ToidMatching};
+use crate::error::TokenStoreError;
+use crate::token::get_token_headers;
+use crate::token::validation::ScopeMatcher;
+
+#[cfg(not(feature = "std"))]
+use {
+    alloc::string::{String, ToString},
+    alloc::vec,
+    alloc::vec::Vec,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Determines which token a full [`TokenStore`] gives up when a new token is inserted.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+pub enum EvictionStrategy {
+    /// Evicts the token which hasn't been inserted or [accessed](TokenStore::get) for the
+    /// longest time.
+    LeastRecentlyUsed,
+
+    /// Evicts the token with the earliest expiration time, where tokens without one are
+    /// evicted last. Ties are broken by evicting the least recently used token.
+    NearestExpiry,
+
+    /// Doesn't evict any token, rejecting the new token instead.
+    RejectNew,
+}
+
+/// An access token accepted by the Resource Server, as kept in a [`TokenStore`].
+#[derive(Debug, PartialEq, Clone)]
+pub struct StoredToken {
+    /// The identity of the client the token is bound to (e.g., as determined by the secure
+    /// channel), which is unique within the store.
+    pub identity: String,
+
+    /// The claims of the token.
+    pub claims: ClaimsSet,
+
+    /// The time (in seconds since the Unix epoch, or any other monotonic clock the Resource
+    /// Server uses) at which the token expires, if it does.
+    pub expires_at: Option<i64>,
+
+    /// The access token exactly as it has been received, if it's kept.
+    ///
+    /// This is never re-encoded, so that forwarding it preserves header parameters unknown to
+    /// this crate along with their encoding.
+    pub token: Option<ByteString>,
+}
+
+impl StoredToken {
+    /// Creates a new [`StoredToken`] with the given `claims`, bound to the given `identity`
+    /// and expiring at `expires_at`.
+    #[must_use]
+    pub fn new<T>(identity: T, claims: ClaimsSet, expires_at: Option<i64>) -> StoredToken
+    where
+        T: Into<String>,
+    {
+        StoredToken {
+            identity: identity.into(),
+            claims,
+            expires_at,
+            token: None,
+        }
+    }
+
+    /// Keeps the given `token` (the access token exactly as it has been received, from which
+    /// the claims of this [`StoredToken`] have been obtained) along with the claims.
+    ///
+    /// # Example
+    /// ```
+    /// # use coset::cwt::ClaimsSetBuilder;
+    /// use dcaf::token::token_store::StoredToken;
+    ///
+    /// // A COSE_Sign1 structure whose unprotected header contains the unknown parameter -65537.
+    /// let received = vec![
+    ///     0x84, 0x43, 0xA1, 0x01, 0x26, 0xA1, 0x3A, 0x00, 0x01, 0x00, 0x00, 0xF6, 0x41, 0xA0,
+    ///     0x41, 0x00,
+    /// ];
+    /// let stored = StoredToken::new("client", ClaimsSetBuilder::new().build(), None)
+    ///     .with_token(received.clone());
+    /// assert_eq!(stored.token, Some(received));
+    /// let (unprotected, _) = stored.headers().expect("token must be a valid COSE structure");
+    /// assert_eq!(unprotected.rest.len(), 1);
+    /// ```
+    #[must_use]
+    pub fn with_token(mut self, token: ByteString) -> StoredToken {
+        self.token = Some(token);
+        self
+    }
+
+    /// Returns the unprotected and protected header of the kept [`token`](StoredToken::token),
+    /// including parameters unknown to this crate (which are contained in [`Header::rest`]).
+    ///
+    /// Returns `None` if no token is kept or if it isn't a valid COSE structure.
+    #[must_use]
+    pub fn headers(&self) -> Option<(Header, ProtectedHeader)> {
+        self.token.as_ref().and_then(get_token_headers)
+    }
+}
+
+/// A token affected by the capacity limit of a [`TokenStore`], as reported to an
+/// [`OverflowHandler`].
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Overflow<'a> {
+    /// The contained token has been evicted from the store to make room for a new one.
+    Evicted(&'a StoredToken),
+
+    /// The contained new token has been rejected, since the store is full.
+    Rejected(&'a StoredToken),
+}
+
+/// Receives every token which is evicted from or rejected by a [`TokenStore`] due to its
+/// capacity limit.
+///
+/// This is implemented for all closures taking an [`Overflow`], as well as for `()`, which
+/// ignores all such events.
+pub trait OverflowHandler {
+    /// Handles the given overflow event.
+    fn overflow(&mut self, overflow: Overflow<'_>);
+}
+
+impl<F> OverflowHandler for F
+where
+    F: FnMut(Overflow<'_>),
+{
+    fn overflow(&mut self, overflow: Overflow<'_>) {
+        self(overflow);
+    }
+}
+
+impl OverflowHandler for () {
+    fn overflow(&mut self, _overflow: Overflow<'_>) {}
+}
+
+/// A token in a [`TokenStore`] along with its compiled scope and the time it was last used.
+#[derive(Debug, PartialEq, Clone)]
+struct Entry {
+    token: StoredToken,
+    matcher: ScopeMatcher,
+    last_used: u64,
+}
+
+/// Stores at most a fixed number of access tokens, one per client identity, and makes room for
+/// new tokens according to an [`EvictionStrategy`].
+///
+/// For an example, see the [module-level documentation](self).
+#[derive(Debug, PartialEq, Clone)]
+pub struct TokenStore {
+    /// The maximum number of stored tokens.
+    capacity: usize,
+
+    /// The strategy used when the store is full.
+    strategy: EvictionStrategy,
+
+    /// The stored tokens.
+    entries: Vec<Entry>,
+
+    /// A logical clock, which is advanced on every use of a token to determine recency.
+    clock: u64,
+
+    /// How the Toids in the scopes of stored tokens are interpreted.
+    matching: ToidMatching,
+}
+
+impl TokenStore {
+    /// Creates a new, empty store holding at most `capacity` tokens, which uses the given
+    /// `strategy` once it is full.
+    #[must_use]
+    pub fn new(capacity: usize, strategy: EvictionStrategy) -> TokenStore {
+        TokenStore {
+            capacity,
+            strategy,
+            entries: Vec::new(),
+            clock: 0,
+            matching: ToidMatching::default(),
+        }
+    }
+
+    /// Sets how the Toids in the scopes of stored tokens are interpreted by
+    /// [`is_authorized`](TokenStore::is_authorized), recompiling the scopes of all tokens
+    /// which are already stored.
+    #[must_use]
+    pub fn with_toid_matching(mut self, matching: ToidMatching) -> TokenStore {
+        self.matching = matching;
+        for entry in &mut self.entries {
+            entry.matcher = ScopeMatcher::from_claims(&entry.token.claims, matching);
+        }
+        self
+    }
+
+    /// Returns how the Toids in the scopes of stored tokens are interpreted.
+    #[must_use]
+    pub fn toid_matching(&self) -> ToidMatching {
+        self.matching
+    }
+
+    /// Returns the maximum number of tokens this store holds.
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the strategy this store uses once it is full.
+    #[must_use]
+    pub fn strategy(&self) -> EvictionStrategy {
+        self.strategy
+    }
+
+    /// Returns the number of stored tokens.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if no tokens are stored.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Advances the logical clock and returns its new value.
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    /// Inserts the given `token`, replacing any token already stored for its identity.
+    ///
+    /// The scope of the token is compiled into a [`ScopeMatcher`] at this point.
+    ///
+    /// If the store is full and no token is stored for the identity yet, room is made according
+    /// to the [`EvictionStrategy`] of this store, and the evicted (or rejected) token is reported
+    /// to the given `handler`.
+    ///
+    /// # Errors
+    /// If the store is full and the new token has been rejected
+    /// ([`TokenStoreError::CapacityExceeded`]), either because the strategy is
+    /// [`RejectNew`](EvictionStrategy::RejectNew) or because the capacity is zero.
+    pub fn insert<H>(&mut self, token: StoredToken, handler: &mut H) -> Result<(), TokenStoreError>
+    where
+        H: OverflowHandler,
+    {
+        let last_used = self.tick();
+        let matcher = ScopeMatcher::from_claims(&token.claims, self.matching);
+        if let Some(entry) = self
+            .entries
+            .iter_mut()
+            .find(|x| x.token.identity == token.identity)
+        {
+            *entry = Entry {
+                token,
+                matcher,
+                last_used,
+            };
+            return Ok(());
+        }
+        if self.entries.len() >= self.capacity {
+            let victim = match self.strategy {
+                EvictionStrategy::LeastRecentlyUsed => self
+                    .entries
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|(_, x)| x.last_used),
+                EvictionStrategy::NearestExpiry => {
+                    self.entries.iter().enumerate().min_by_key(|(_, x)| {
+                        // Tokens without an expiration time are sorted after all others.
+                        (
+                            x.token.expires_at.is_none(),
+                            x.token.expires_at,
+                            x.last_used,
+                        )
+                    })
+                }
+                EvictionStrategy::RejectNew => None,
+            }
+            .map(|(index, _)| index);
+            if let Some(index) = victim {
+                let evicted = self.entries.swap_remove(index);
+                handler.overflow(Overflow::Evicted(&evicted.token));
+            } else {
+                handler.overflow(Overflow::Rejected(&token));
+                return Err(TokenStoreError::CapacityExceeded {
+                    capacity: self.capacity,
+                });
+            }
+        }
+        self.entries.push(Entry {
+            token,
+            matcher,
+            last_used,
+        });
+        Ok(())
+    }
+
+    /// Returns the token stored for the given `identity`, if any, marking it as recently used.
+    pub fn get(&mut self, identity: &str) -> Option<&StoredToken> {
+        let now = self.tick();
+        self.entries
+            .iter_mut()
+            .find(|x| x.token.identity == identity)
+            .map(|entry| {
+                entry.last_used = now;
+                &entry.token
+            })
+    }
+
+    /// Returns the token stored for the given `identity`, if any, without marking it as
+    /// recently used.
+    #[must_use]
+    pub fn peek(&self, identity: &str) -> Option<&StoredToken> {
+        self.entries
+            .iter()
+            .map(|x| &x.token)
+            .find(|x| x.identity == identity)
+    }
+
+    /// Returns whether the token stored for the given `identity` permits the given `method` on
+    /// the given `resource`, marking it as recently used.
+    ///
+    /// Returns `false` if no token is stored for the identity. Note that this doesn't check
+    /// whether the token has expired, for which [`remove_expired`](TokenStore::remove_expired)
+    /// should be called regularly.
+    ///
+    /// This doesn't allocate, as the scope of the token has already been compiled when it was
+    /// [inserted](TokenStore::insert).
+    ///
+    /// # Example
+    /// ```
+    /// # use coset::cwt::ClaimsSetBuilder;
+    /// # use coset::iana::CwtClaimName;
+    /// # use dcaf::error::TokenStoreError;
+    /// # use dcaf::{AifEncodedScope, Scope};
+    /// # use dcaf::common::scope::AifRestMethod;
+    /// use dcaf::token::token_store::{EvictionStrategy, StoredToken, TokenStore};
+    ///
+    /// let scope = Scope::from(AifEncodedScope::from(vec![("/s/temp", AifRestMethod::Get.into())]));
+    /// let claims = ClaimsSetBuilder::new().claim(CwtClaimName::Scope, scope.into()).build();
+    /// let mut store = TokenStore::new(4, EvictionStrategy::LeastRecentlyUsed);
+    /// store.insert(StoredToken::new("client1", claims, None), &mut ())?;
+    /// assert!(store.is_authorized("client1", "/s/temp", AifRestMethod::Get));
+    /// assert!(!store.is_authorized("client1", "/s/temp", AifRestMethod::Put));
+    /// assert!(!store.is_authorized("client2", "/s/temp", AifRestMethod::Get));
+    /// # Ok::<(), TokenStoreError>(())
+    /// ```
+    pub fn is_authorized(&mut self, identity: &str, resource: &str, method: AifRestMethod) -> bool {
+        let now = self.tick();
+        self.entries
+            .iter_mut()
+            .find(|x| x.token.identity == identity)
+            .map_or(false, |entry| {
+                entry.last_used = now;
+                entry.matcher.permits(resource, method)
+            })
+    }
+
+    /// Returns the compiled scope of the token stored for the given `identity`, if any,
+    /// without marking it as recently used.
+    #[must_use]
+    pub fn matcher(&self, identity: &str) -> Option<&ScopeMatcher> {
+        self.entries
+            .iter()
+            .find(|x| x.token.identity == identity)
+            .map(|x| &x.matcher)
+    }
+
+    /// Removes and returns the token stored for the given `identity`, if any.
+    pub fn remove(&mut self, identity: &str) -> Option<StoredToken> {
+        let index = self
+            .entries
+            .iter()
+            .position(|x| x.token.identity == identity)?;
+        Some(self.entries.swap_remove(index).token)
+    }
+
+    /// Removes all tokens which have expired at `now`, returning how many have been removed.
+    pub fn remove_expired(&mut self, now: i64) -> usize {
+        let before = self.entries.len();
+        self.entries
+            .retain(|x| x.token.expires_at.map_or(true, |exp| exp > now));
+        before - self.entries.len()
+    }
+
+    /// Serializes this store (its configuration along with all stored tokens and their recency)
+    /// as CBOR into the given `writer`, e.g., a region of flash memory, so that it can later be
+    /// [restored](TokenStore::restore).
+    ///
+    /// # Example
+    /// ```
+    /// # use coset::cwt::ClaimsSetBuilder;
+    /// use dcaf::token::token_store::{EvictionStrategy, StoredToken, TokenStore};
+    ///
+    /// let mut store = TokenStore::new(4, EvictionStrategy::LeastRecentlyUsed);
+    /// let claims = ClaimsSetBuilder::new().audience("tempSensor4711".to_string()).build();
+    /// store.insert(StoredToken::new("client1", claims, Some(2000)), &mut ()).expect("store has room");
+    /// let mut persisted: Vec<u8> = Vec::new();
+    /// store.save(&mut persisted).expect("token store must be serializable");
+    /// let restored = TokenStore::restore(persisted.as_slice()).expect("invalid token store");
+    /// assert_eq!(restored, store);
+    /// ```
+    ///
+    /// # Errors
+    /// - When the claims of a stored token can't be serialized.
+    /// - When the output couldn't be put inside the given `writer`.
+    pub fn save<W>(&self, writer: W) -> Result<(), ciborium::ser::Error<W::Error>>
+    where
+        W: Write,
+        W::Error: Debug,
+    {
+        let mut entries = Vec::with_capacity(self.entries.len());
+        for entry in &self.entries {
+            let token = &entry.token;
+            let claims = token
+                .claims
+                .clone()
+                .to_cbor_value()
+                .map_err(|e| ciborium::ser::Error::Value(e.to_string()))?;
+            entries.push(Value::Array(vec![
+                Value::from(token.identity.as_str()),
+                claims,
+                token.expires_at.map_or(Value::Null, Value::from),
+                token.token.clone().map_or(Value::Null, Value::Bytes),
+                Value::from(entry.last_used),
+            ]));
+        }
+        let store = Value::Array(vec![
+            Value::from(self.capacity as u64),
+            Value::from(strategy_code(self.strategy)),
+            Value::from(matching_code(self.matching)),
+            Value::from(self.clock),
+            Value::Array(entries),
+        ]);
+        into_writer(&store, writer)
+    }
+
+    /// Deserializes a store which has previously been [saved](TokenStore::save) from the given
+    /// `reader`, compiling the scopes of all of its tokens anew.
+    ///
+    /// # Errors
+    /// - When the given `reader` does not contain a valid serialized store, which includes stores
+    ///   holding more tokens than their capacity or more than one token for the same identity.
+    /// - When the input couldn't be read from the given `reader`.
+    pub fn restore<R>(reader: R) -> Result<TokenStore, ciborium::de::Error<R::Error>>
+    where
+        R: Read,
+        R::Error: Debug,
+    {
+        let invalid = |reason: &str| ciborium::de::Error::Semantic(None, reason.to_string());
+        let [capacity, strategy, matching, clock, entries]: [Value; 5] = match from_reader(reader)?
+        {
+            Value::Array(fields) => fields
+                .try_into()
+                .map_err(|_| invalid("token store must consist of five fields"))?,
+            _ => return Err(invalid("token store must be an array")),
+        };
+        let entries = match entries {
+            Value::Array(entries) => entries,
+            _ => return Err(invalid("stored tokens must be an array")),
+        };
+        let capacity = integer(&capacity).ok_or_else(|| invalid("invalid capacity"))?;
+        if entries.len() > capacity {
+            return Err(invalid("more tokens are stored than the capacity allows"));
+        }
+        let mut store = TokenStore {
+            capacity,
+            strategy: integer(&strategy)
+                .and_then(strategy_from_code)
+                .ok_or_else(|| invalid("unknown eviction strategy"))?,
+            matching: integer(&matching)
+                .and_then(matching_from_code)
+                .ok_or_else(|| invalid("unknown Toid matching"))?,
+            clock: integer(&clock).ok_or_else(|| invalid("invalid clock"))?,
+            entries: Vec::with_capacity(entries.len()),
+        };
+        for entry in entries {
+            let [identity, claims, expires_at, token, last_used]: [Value; 5] = match entry {
+                Value::Array(fields) => fields
+                    .try_into()
+                    .map_err(|_| invalid("stored token must consist of five fields"))?,
+                _ => return Err(invalid("stored token must be an array")),
+            };
+            let token = StoredToken {
+                identity: match identity {
+                    Value::Text(identity) => identity,
+                    _ => return Err(invalid("invalid identity")),
+                },
+                claims: ClaimsSet::from_cbor_value(claims)
+                    .map_err(|_| invalid("invalid claims"))?,
+                expires_at: match expires_at {
+                    Value::Null => None,
+                    x => Some(integer(&x).ok_or_else(|| invalid("invalid expiration time"))?),
+                },
+                token: match token {
+                    Value::Null => None,
+                    Value::Bytes(x) => Some(x),
+                    _ => return Err(invalid("invalid access token")),
+                },
+            };
+            if store
+                .entries
+                .iter()
+                .any(|x| x.token.identity == token.identity)
+            {
+                return Err(invalid("more than one token is stored for an identity"));
+            }
+            store.entries.push(Entry {
+                matcher: ScopeMatcher::from_claims(&token.claims, store.matching),
+                token,
+                last_used: integer(&last_used).ok_or_else(|| invalid("invalid recency"))?,
+            });
+        }
+        Ok(store)
+    }
+}
+
+/// Returns the given CBOR `value` as an integer of type `T`, or `None` if it isn't one or is out
+/// of range.
+fn integer<T>(value: &Value) -> Option<T>
+where
+    T: TryFrom<ciborium::value::Integer>,
+{
+    match value {
+        Value::Integer(x) => T::try_from(*x).ok(),
+        _ => None,
+    }
+}
+
+/// Returns the code with which the given `strategy` is [saved](TokenStore::save).
+fn strategy_code(strategy: EvictionStrategy) -> u8 {
+    match strategy {
+        EvictionStrategy::LeastRecentlyUsed => 0,
+        EvictionStrategy::NearestExpiry => 1,
+        EvictionStrategy::RejectNew => 2,
+    }
+}
+
+/// Returns the eviction strategy with the given `code`, as [saved](TokenStore::save).
+fn strategy_from_code(code: u8) -> Option<EvictionStrategy> {
+    match code {
+        0 => Some(EvictionStrategy::LeastRecentlyUsed),
+        1 => Some(EvictionStrategy::NearestExpiry),
+        2 => Some(EvictionStrategy::RejectNew),
+        _ => None,
+    }
+}
+
+/// Returns the code with which the given `matching` is [saved](TokenStore::save).
+fn matching_code(matching: ToidMatching) -> u8 {
+    match matching {
+        ToidMatching::Exact => 0,
+        ToidMatching::Wildcard => 1,
+        ToidMatching::Prefix => 2,
+    }
+}
+
+/// Returns the Toid matching with the given `code`, as [saved](TokenStore::save).
+fn matching_from_code(code: u8) -> Option<ToidMatching> {
+    match code {
+        0 => Some(ToidMatching::Exact),
+        1 => Some(ToidMatching::Wildcard),
+        2 => Some(ToidMatching::Prefix),
+        _ => None,
+    }
+}
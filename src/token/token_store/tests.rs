@@ -0,0 +1,267 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+use coset::cwt::ClaimsSetBuilder;
+use coset::iana::CwtClaimName;
+use coset::{CborSerializable, CoseSign1};
+
+use crate::{AifEncodedScope, Scope};
+
+#[cfg(not(feature = "std"))]
+use {alloc::string::ToString, alloc::vec, alloc::vec::Vec};
+
+use super::*;
+
+fn token(identity: &str, expires_at: Option<i64>) -> StoredToken {
+    StoredToken::new(identity, ClaimsSetBuilder::new().build(), expires_at)
+}
+
+/// Inserts the given tokens into a new store, returning it along with all overflow events.
+fn fill(
+    strategy: EvictionStrategy,
+    tokens: &[(&str, Option<i64>)],
+) -> (TokenStore, Vec<Result<String, String>>) {
+    let mut store = TokenStore::new(2, strategy);
+    let mut events = Vec::new();
+    let mut handler = |overflow: Overflow<'_>| {
+        events.push(match overflow {
+            Overflow::Evicted(x) => Ok(x.identity.clone()),
+            Overflow::Rejected(x) => Err(x.identity.clone()),
+        });
+    };
+    for (identity, expires_at) in tokens {
+        let _ = store.insert(token(identity, *expires_at), &mut handler);
+    }
+    (store, events)
+}
+
+#[test]
+fn test_least_recently_used() {
+    let mut store = TokenStore::new(2, EvictionStrategy::LeastRecentlyUsed);
+    let mut evicted = Vec::new();
+    let mut handler = |overflow: Overflow<'_>| {
+        if let Overflow::Evicted(x) = overflow {
+            evicted.push(x.identity.clone());
+        }
+    };
+    assert!(store.insert(token("a", None), &mut handler).is_ok());
+    assert!(store.insert(token("b", None), &mut handler).is_ok());
+    // Using "a" makes "b" the least recently used token.
+    assert!(store.get("a").is_some());
+    assert!(store.insert(token("c", None), &mut handler).is_ok());
+    // Peeking doesn't count as a use, so "a" is evicted next.
+    assert!(store.peek("a").is_some());
+    assert!(store.insert(token("d", None), &mut handler).is_ok());
+    assert_eq!(evicted, vec!["b".to_string(), "a".to_string()]);
+    assert!(store.peek("c").is_some() && store.peek("d").is_some());
+}
+
+#[test]
+fn test_nearest_expiry() {
+    let (store, events) = fill(
+        EvictionStrategy::NearestExpiry,
+        &[
+            ("a", None),
+            ("b", Some(1000)),
+            ("c", Some(2000)),
+            ("d", Some(500)),
+        ],
+    );
+    // "b" expires first, then "c" expires before "a", which never expires.
+    assert_eq!(events, vec![Ok("b".to_string()), Ok("c".to_string())]);
+    assert_eq!(store.len(), 2);
+    assert!(store.peek("a").is_some() && store.peek("d").is_some());
+}
+
+#[test]
+fn test_reject_new() {
+    let (mut store, events) = fill(
+        EvictionStrategy::RejectNew,
+        &[("a", Some(1000)), ("b", Some(2000)), ("c", None)],
+    );
+    assert_eq!(events, vec![Err("c".to_string())]);
+    assert_eq!(
+        store.insert(token("c", None), &mut ()),
+        Err(TokenStoreError::CapacityExceeded { capacity: 2 })
+    );
+    // Replacing the token of a known identity doesn't need any room.
+    assert!(store.insert(token("a", Some(3000)), &mut ()).is_ok());
+    assert_eq!(store.peek("a").and_then(|x| x.expires_at), Some(3000));
+    // Once expired tokens are removed, there is room again.
+    assert_eq!(store.remove_expired(2500), 1);
+    assert!(store.insert(token("c", None), &mut ()).is_ok());
+    assert_eq!(store.remove("c").map(|x| x.identity), Some("c".to_string()));
+    assert_eq!(store.len(), 1);
+}
+
+#[test]
+fn test_zero_capacity() {
+    let mut store = TokenStore::new(0, EvictionStrategy::LeastRecentlyUsed);
+    assert_eq!(
+        store.insert(token("a", None), &mut ()),
+        Err(TokenStoreError::CapacityExceeded { capacity: 0 })
+    );
+    assert!(store.is_empty());
+}
+
+#[test]
+fn test_is_authorized() {
+    let scope = Scope::from(AifEncodedScope::from(vec![(
+        "/s/*",
+        AifRestMethod::Get.into(),
+    )]));
+    let claims = ClaimsSetBuilder::new()
+        .claim(CwtClaimName::Scope, scope.into())
+        .build();
+    let mut store = TokenStore::new(2, EvictionStrategy::LeastRecentlyUsed);
+    assert!(store
+        .insert(StoredToken::new("a", claims, None), &mut ())
+        .is_ok());
+    assert!(store.insert(token("b", None), &mut ()).is_ok());
+    // Toids are compared exactly by default.
+    assert!(store.is_authorized("a", "/s/*", AifRestMethod::Get));
+    assert!(!store.is_authorized("a", "/s/temp", AifRestMethod::Get));
+    // Changing the strategy recompiles the scopes of stored tokens.
+    let mut store = store.with_toid_matching(ToidMatching::Wildcard);
+    assert!(store.is_authorized("a", "/s/temp", AifRestMethod::Get));
+    assert!(!store.is_authorized("a", "/s/temp", AifRestMethod::Put));
+    assert!(!store.is_authorized("b", "/s/temp", AifRestMethod::Get));
+    assert!(!store.is_authorized("c", "/s/temp", AifRestMethod::Get));
+    assert!(store.matcher("b").map_or(false, ScopeMatcher::is_empty));
+    // Checking authorization counts as a use, so "b" is evicted next.
+    assert!(store.is_authorized("a", "/s/hum", AifRestMethod::Get));
+    assert!(store.insert(token("c", None), &mut ()).is_ok());
+    assert!(store.peek("a").is_some() && store.peek("b").is_none());
+}
+
+#[test]
+fn test_keeps_token_unchanged() -> Result<(), TokenStoreError> {
+    // The unprotected header contains the unknown parameter -65537 before the key ID,
+    // which wouldn't be the case if the token were re-encoded.
+    let received = vec![
+        0x84, 0x43, 0xA1, 0x01, 0x26, 0xA2, 0x3A, 0x00, 0x01, 0x00, 0x00, 0xF6, 0x04, 0x41, 0x01,
+        0x41, 0xA0, 0x41, 0x00,
+    ];
+    let (unprotected, protected) = get_token_headers(&received).expect("token must be valid");
+    let reencoded = CoseSign1 {
+        protected,
+        unprotected,
+        payload: Some(vec![0xA0]),
+        signature: vec![0x00],
+    };
+    assert_ne!(
+        reencoded.to_vec().expect("token must be serializable"),
+        received
+    );
+
+    let mut store = TokenStore::new(1, EvictionStrategy::RejectNew);
+    store.insert(token("a", None).with_token(received.clone()), &mut ())?;
+    let stored = store.get("a").expect("token must be stored");
+    assert_eq!(stored.token, Some(received));
+    let (unprotected, _) = stored.headers().expect("token must be valid");
+    assert_eq!(unprotected.key_id, vec![0x01]);
+    assert_eq!(unprotected.rest.len(), 1);
+    assert_eq!(token("b", None).headers(), None);
+    Ok(())
+}
+
+#[test]
+fn test_save_restore() -> Result<(), TokenStoreError> {
+    let scope = Scope::from(AifEncodedScope::from(vec![(
+        "/s/*",
+        AifRestMethod::Get.into(),
+    )]));
+    let claims = ClaimsSetBuilder::new()
+        .audience("rs1".to_string())
+        .claim(CwtClaimName::Scope, scope.into())
+        .build();
+    let mut store = TokenStore::new(2, EvictionStrategy::LeastRecentlyUsed)
+        .with_toid_matching(ToidMatching::Wildcard);
+    store.insert(StoredToken::new("a", claims, Some(2000)), &mut ())?;
+    store.insert(token("b", None).with_token(vec![0xDC, 0xAF]), &mut ())?;
+    assert!(store.get("a").is_some());
+
+    let mut persisted: Vec<u8> = Vec::new();
+    store
+        .save(&mut persisted)
+        .expect("store must be serializable");
+    let mut restored = TokenStore::restore(persisted.as_slice()).expect("store must be valid");
+    assert_eq!(restored, store);
+    assert_eq!(restored.toid_matching(), ToidMatching::Wildcard);
+    assert!(restored.is_authorized("a", "/s/temp", AifRestMethod::Get));
+    assert_eq!(
+        restored.peek("b").and_then(|x| x.token.clone()),
+        Some(vec![0xDC, 0xAF])
+    );
+    // The recency of the tokens is restored as well, so "b" is evicted next.
+    restored.insert(token("c", None), &mut ())?;
+    assert!(restored.peek("a").is_some() && restored.peek("b").is_none());
+
+    assert!(TokenStore::restore([0xA0].as_slice()).is_err());
+    // An unknown eviction strategy is rejected.
+    persisted[2] = 0x07;
+    assert!(TokenStore::restore(persisted.as_slice()).is_err());
+    Ok(())
+}
+
+/// Saves a store containing tokens for "a" and "b", applies `modify` to the fields of the
+/// serialized store, and tries to restore it, returning the reason of semantic errors.
+fn restore_modified<F>(modify: F) -> Result<TokenStore, Option<String>>
+where
+    F: FnOnce(&mut Vec<Value>),
+{
+    let mut store = TokenStore::new(2, EvictionStrategy::LeastRecentlyUsed);
+    store
+        .insert(token("a", None), &mut ())
+        .and_then(|()| store.insert(token("b", None), &mut ()))
+        .expect("tokens must fit into the store");
+    let mut persisted: Vec<u8> = Vec::new();
+    store
+        .save(&mut persisted)
+        .expect("store must be serializable");
+    let mut fields = match from_reader(persisted.as_slice()) {
+        Ok(Value::Array(fields)) => fields,
+        _ => unreachable!("store must be saved as an array"),
+    };
+    modify(&mut fields);
+    let mut modified: Vec<u8> = Vec::new();
+    into_writer(&Value::Array(fields), &mut modified).expect("store must be serializable");
+    TokenStore::restore(modified.as_slice()).map_err(|e| match e {
+        ciborium::de::Error::Semantic(_, reason) => Some(reason),
+        _ => None,
+    })
+}
+
+#[test]
+fn test_restore_inconsistent() {
+    assert!(restore_modified(|_| ()).is_ok());
+    // The store holds more tokens than its capacity.
+    assert_eq!(
+        restore_modified(|fields| fields[0] = Value::from(1)).err(),
+        Some(Some(
+            "more tokens are stored than the capacity allows".to_string()
+        ))
+    );
+    // The store holds more than one token for "a".
+    let duplicate = restore_modified(|fields| {
+        if let Value::Array(entries) = &mut fields[4] {
+            if let Value::Array(entry) = &mut entries[1] {
+                entry[0] = Value::from("a");
+            }
+        }
+    });
+    assert_eq!(
+        duplicate.err(),
+        Some(Some(
+            "more than one token is stored for an identity".to_string()
+        ))
+    );
+}
@@ -0,0 +1,1528 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+//! Contains helpers for the Resource Server to validate the claims of access tokens
+//! after they have been [verified](crate::verify_access_token) or
+//! [decrypted](crate::decrypt_access_token), as described in
+//! [section 5.10.1.1 of RFC 9200](https://www.rfc-editor.org/rfc/rfc9200#section-5.10.1.1).
+//!
+//! # Validation Policy
+//! General checks on the claims, such as requiring an `aud` claim, are configured using a
+//! [`ValidationPolicy`].
+//!
+//! # Access Decisions
+//! Once a token has been accepted, [`authorize`] checks whether its AIF scope permits a given
+//! request and reports every decision to an [`AccessDecisionSink`], which can be used to keep an
+//! audit trail of who accessed (or was denied access to) which resource with which token.
+//!
+//! On the hot path of constrained Resource Servers, a [`ScopeMatcher`] compiled once per token
+//! checks requests without allocating.
+//!
+//! # Client Nonces
+//! Resource Servers without a synchronized clock can make sure a token is fresh by sending a
+//! client nonce in their creation hint, which the Authorization Server echoes back in the
+//! `cnonce` claim of the token. [`ClientNonces`] keeps track of the nonces which have been sent
+//! and accepts each of them only once.
+//!
+//! With the `std` feature enabled, `ShardedClientNonces` and `AtomicIssuedAtWatermark` can be
+//! shared between the threads of a multi-core Resource Server without contending for a single
+//! lock. A `ShardedClientNonces` can also be set as the `client_nonces` of the
+//! [`ValidationPolicy`], so that [`validate_at`](ValidationPolicy::validate_at) checks the
+//! `cnonce` claim of every token along with its other claims.
+//!
+//! # Token Supersession
+//! When a client posts a new token (e.g., to update its permissions), older tokens of the same
+//! client for the same audience should no longer be used. [`TokenSupersession`] keeps track of
+//! the current token per client and audience and reports which token has been superseded.
+//!
+//! # Algorithm Pinning
+//! A Resource Server serving multiple audiences may accept several algorithms in general, while
+//! the tokens for each of its audiences are always protected with one specific algorithm.
+//! Recording that algorithm in [`AudienceAlgorithms`] makes sure that a token for one audience
+//! can't be protected with a weaker algorithm only meant for another one.
+//!
+//! # Trust Store
+//! The keys of the Authorization Servers whose tokens are accepted are kept in a [`TrustStore`],
+//! which rejects ambiguous key IDs, so that a token is never verified with the wrong key.
+//! Since each trusted key belongs to one issuer, the store also makes sure that the `iss` claim
+//! of a verified token names the issuer of the key which verified it. Keys can additionally be
+//! restricted to a single [`KeyUsage`], so that, e.g., a key meant for verifying tokens is never
+//! used for anything else.
+//!
+//! # Clock-less Resource Servers
+//! Resource Servers without a synchronized clock receive tokens with an `exi` claim instead of
+//! an `exp` claim (see `ClockLessIssuance` in the `issuance` module).
+//! Use [`exi_expiration`] to determine when such a token expires, and an [`IssuedAtWatermark`]
+//! to reject tokens which are older than the newest token seen so far.
+//!
+//! In order not to accept replayed old tokens after a power cycle, constrained devices should
+//! [`save`](IssuedAtWatermark::save) the watermark to persistent storage whenever it changes
+//! and [`restore`](IssuedAtWatermark::restore) it on startup.
+//!
+//! ## Example
+//! ```
+//! # use coset::cwt::{ClaimsSetBuilder, Timestamp};
+//! # use dcaf::error::ClaimsValidationError;
+//! use dcaf::token::validation::IssuedAtWatermark;
+//!
+//! let mut watermark = IssuedAtWatermark::default();
+//! let newer = ClaimsSetBuilder::new().issued_at(Timestamp::WholeSeconds(1001)).build();
+//! let older = ClaimsSetBuilder::new().issued_at(Timestamp::WholeSeconds(1000)).build();
+//! assert!(watermark.check(&newer).is_ok());
+//! assert!(watermark.check(&older).is_err());
+//! assert_eq!(watermark.highest(), Some(1001));
+//! ```
+
+use core::cmp::Ordering;
+use core::fmt::Debug;
+
+use ciborium::de::from_reader;
+use ciborium::ser::into_writer;
+use ciborium::value::Value;
+use ciborium_io::{Read, Write};
+use coset::cwt::{ClaimName, ClaimsSet, Timestamp};
+use coset::iana::CwtClaimName;
+use coset::{CoseKey, Header, ProtectedHeader};
+use serde::{Deserialize, Serialize};
+
+use crate::common::cbor_values::ByteString;
+use crate::common::cose::Algorithm;
+use crate::common::scope::{
+    remove_dot_segments, AifEncodedScopeElement, AifRestMethod, AifRestMethodSet, ToidMatching,
+};
+use crate::error::{ClaimsValidationError, TrustStoreError};
+use crate::token::{get_client_nonce, KeyUsage, KeyUsages};
+use crate::{AifEncodedScope, Scope};
+
+#[cfg(feature = "std")]
+use {
+    std::collections::hash_map::DefaultHasher,
+    std::hash::{Hash, Hasher},
+    std::sync::atomic::{AtomicI64, Ordering as AtomicOrdering},
+    std::sync::{Arc, Mutex, PoisonError},
+};
+
+#[cfg(not(feature = "std"))]
+use {
+    alloc::string::{String, ToString},
+    alloc::vec::Vec,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Policy according to which the Resource Server validates the claims of access tokens.
+///
+/// Use [`validate`](ValidationPolicy::validate) to check the claims of a token against this policy.
+/// The [`Default`] policy requires an `aud` claim, but no `exp` claim.
+///
+/// Resource Servers with a clock can additionally check the `exp`, `nbf`, and `iat` claims
+/// using [`validate_at`](ValidationPolicy::validate_at). Since the clocks of constrained
+/// (e.g., battery-powered) devices tend to drift, each of these checks has its own tolerance,
+/// which is zero by default.
+///
+/// # Example
+/// ```
+/// # use coset::cwt::{ClaimsSetBuilder, Timestamp};
+/// # use dcaf::error::ClaimsValidationError;
+/// use dcaf::token::validation::ValidationPolicy;
+///
+/// let policy = ValidationPolicy::default();
+/// let claims = ClaimsSetBuilder::new().audience("coaps://rs.example.com".to_string()).build();
+/// assert!(policy.validate(&claims).is_ok());
+/// let audience_less = ClaimsSetBuilder::new().build();
+/// assert_eq!(policy.validate(&audience_less), Err(ClaimsValidationError::MissingClaim("aud")));
+///
+/// // Allow the clock of the Resource Server to be up to a minute ahead.
+/// let drifting = ValidationPolicy { expiration_leeway: 60, ..ValidationPolicy::default() };
+/// let claims = ClaimsSetBuilder::new()
+///     .audience("coaps://rs.example.com".to_string())
+///     .expiration_time(Timestamp::WholeSeconds(1000))
+///     .build();
+/// assert!(drifting.validate_at(&claims, 1030).is_ok());
+/// assert!(policy.validate_at(&claims, 1030).is_err());
+/// ```
+#[derive(Debug, Clone)]
+pub struct ValidationPolicy {
+    /// Whether tokens without an `aud` claim, or with a wildcard audience (i.e., an empty
+    /// audience or one containing `*`), are rejected.
+    ///
+    /// Accepting such tokens means that a token issued for any other Resource Server of the same
+    /// AS may be accepted too, so this should only be disabled if the AS is known to never issue
+    /// tokens for more than this Resource Server.
+    pub require_audience: bool,
+
+    /// The audiences of this Resource Server, or an empty list if the `aud` claim of tokens
+    /// shouldn't be compared to them.
+    ///
+    /// If this isn't empty, tokens without an `aud` claim or with an audience not contained in
+    /// this list are rejected.
+    pub audiences: Vec<String>,
+
+    /// Whether tokens without an `exp` claim are rejected.
+    ///
+    /// Note that this only checks for the presence of the claim, since the Resource Server may
+    /// not have a synchronized clock to compare it with.
+    pub require_expiration: bool,
+
+    /// The number of seconds after the time given in the `exp` claim during which
+    /// [`validate_at`](ValidationPolicy::validate_at) still accepts a token.
+    pub expiration_leeway: u32,
+
+    /// The number of seconds before the time given in the `nbf` claim during which
+    /// [`validate_at`](ValidationPolicy::validate_at) already accepts a token.
+    pub not_before_leeway: u32,
+
+    /// The maximum number of seconds since the time given in the `iat` claim for which
+    /// [`validate_at`](ValidationPolicy::validate_at) accepts a token, or `None` if the age of
+    /// tokens isn't limited.
+    ///
+    /// If this is set, tokens without an `iat` claim are rejected.
+    pub max_issued_at_age: Option<u32>,
+
+    /// The client nonces the Resource Server has sent in its creation hints, against which
+    /// [`validate_at`](ValidationPolicy::validate_at) checks (and consumes) the `cnonce` claim
+    /// of each token, or `None` if client nonces aren't used.
+    ///
+    /// The same [`ShardedClientNonces`] should be used to [`issue`](ShardedClientNonces::issue)
+    /// the nonces when creating the hints.
+    #[cfg(feature = "std")]
+    pub client_nonces: Option<Arc<ShardedClientNonces>>,
+}
+
+impl PartialEq for ValidationPolicy {
+    fn eq(&self, other: &Self) -> bool {
+        #[cfg(feature = "std")]
+        let same_nonces = match (&self.client_nonces, &other.client_nonces) {
+            (Some(x), Some(y)) => Arc::ptr_eq(x, y),
+            (x, y) => x.is_none() && y.is_none(),
+        };
+        #[cfg(not(feature = "std"))]
+        let same_nonces = true;
+        same_nonces
+            && self.require_audience == other.require_audience
+            && self.audiences == other.audiences
+            && self.require_expiration == other.require_expiration
+            && self.expiration_leeway == other.expiration_leeway
+            && self.not_before_leeway == other.not_before_leeway
+            && self.max_issued_at_age == other.max_issued_at_age
+    }
+}
+
+impl Eq for ValidationPolicy {}
+
+impl ValidationPolicy {
+    /// Checks the given `claims` against this policy.
+    ///
+    /// # Errors
+    /// - If [`require_audience`](ValidationPolicy::require_audience) is set and the `claims`
+    ///   don't contain an `aud` claim ([`ClaimsValidationError::MissingClaim`]) or contain
+    ///   a wildcard audience ([`ClaimsValidationError::WildcardAudience`]).
+    /// - If [`audiences`](ValidationPolicy::audiences) isn't empty and the `claims` don't contain
+    ///   an `aud` claim ([`ClaimsValidationError::MissingClaim`]) or one of another audience
+    ///   ([`ClaimsValidationError::AudienceMismatch`]).
+    /// - If [`require_expiration`](ValidationPolicy::require_expiration) is set and the `claims`
+    ///   don't contain an `exp` claim ([`ClaimsValidationError::MissingClaim`]).
+    pub fn validate(&self, claims: &ClaimsSet) -> Result<(), ClaimsValidationError> {
+        if self.require_audience {
+            match claims.audience.as_deref() {
+                None => return Err(ClaimsValidationError::MissingClaim("aud")),
+                Some(audience) if audience.is_empty() || audience.contains('*') => {
+                    return Err(ClaimsValidationError::WildcardAudience(
+                        audience.to_string(),
+                    ));
+                }
+                Some(_) => {}
+            }
+        }
+        if !self.audiences.is_empty() {
+            let audience = claims
+                .audience
+                .as_deref()
+                .ok_or(ClaimsValidationError::MissingClaim("aud"))?;
+            if !self.audiences.iter().any(|x| x == audience) {
+                return Err(ClaimsValidationError::AudienceMismatch(
+                    audience.to_string(),
+                ));
+            }
+        }
+        if self.require_expiration && claims.expiration_time.is_none() {
+            return Err(ClaimsValidationError::MissingClaim("exp"));
+        }
+        Ok(())
+    }
+
+    /// Checks the given `claims` against this policy like [`validate`](ValidationPolicy::validate),
+    /// and additionally checks the `exp`, `nbf`, and `iat` claims against the current time `now`
+    /// (in seconds since the UNIX epoch), taking the configured leeways into account.
+    ///
+    /// # Errors
+    /// - If [`validate`](ValidationPolicy::validate) fails.
+    /// - If the token expired more than
+    ///   [`expiration_leeway`](ValidationPolicy::expiration_leeway) seconds ago
+    ///   ([`ClaimsValidationError::Expired`]).
+    /// - If the token only becomes valid more than
+    ///   [`not_before_leeway`](ValidationPolicy::not_before_leeway) seconds from now
+    ///   ([`ClaimsValidationError::NotYetValid`]).
+    /// - If [`max_issued_at_age`](ValidationPolicy::max_issued_at_age) is set and the token
+    ///   doesn't contain an `iat` claim ([`ClaimsValidationError::MissingClaim`]) or has been
+    ///   issued longer ago ([`ClaimsValidationError::IssuedAtTooOld`]).
+    /// - If `client_nonces` are set and the token doesn't contain a `cnonce` claim
+    ///   ([`ClaimsValidationError::MissingClaim`]) or its nonce isn't pending
+    ///   ([`ClaimsValidationError::UnknownClientNonce`]). The nonce is only consumed if all
+    ///   other checks have passed.
+    pub fn validate_at(&self, claims: &ClaimsSet, now: i64) -> Result<(), ClaimsValidationError> {
+        self.validate(claims)?;
+        if let Some(expiration) = claims.expiration_time.as_ref().map(timestamp_seconds) {
+            if now >= expiration.saturating_add(i64::from(self.expiration_leeway)) {
+                return Err(ClaimsValidationError::Expired { expiration, now });
+            }
+        }
+        if let Some(not_before) = claims.not_before.as_ref().map(timestamp_seconds) {
+            if now.saturating_add(i64::from(self.not_before_leeway)) < not_before {
+                return Err(ClaimsValidationError::NotYetValid { not_before, now });
+            }
+        }
+        if let Some(max_age) = self.max_issued_at_age {
+            let issued_at = claims
+                .issued_at
+                .as_ref()
+                .map(timestamp_seconds)
+                .ok_or(ClaimsValidationError::MissingClaim("iat"))?;
+            if now.saturating_sub(issued_at) > i64::from(max_age) {
+                return Err(ClaimsValidationError::IssuedAtTooOld { issued_at, now });
+            }
+        }
+        #[cfg(feature = "std")]
+        if let Some(nonces) = &self.client_nonces {
+            nonces.check(claims)?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for ValidationPolicy {
+    fn default() -> Self {
+        ValidationPolicy {
+            require_audience: true,
+            audiences: Vec::new(),
+            require_expiration: false,
+            expiration_leeway: 0,
+            not_before_leeway: 0,
+            max_issued_at_age: None,
+            #[cfg(feature = "std")]
+            client_nonces: None,
+        }
+    }
+}
+
+/// Whether a request has been granted or denied by [`authorize`].
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+pub enum AccessDecision {
+    /// The request is permitted by the scope of the token.
+    Granted,
+
+    /// The request is not permitted by the scope of the token.
+    Denied,
+}
+
+impl AccessDecision {
+    /// Returns the CoAP code with which the Resource Server should reject the request, or `None`
+    /// if it has been granted.
+    ///
+    /// As specified in
+    /// [section 5.10.1.1 of RFC 9200](https://www.rfc-editor.org/rfc/rfc9200#section-5.10.1.1),
+    /// requests not covered by the scope of a valid token are rejected with
+    /// [4.03 (Forbidden)](crate::constants::coap_code::FORBIDDEN).
+    #[must_use]
+    pub fn coap_code(self) -> Option<u8> {
+        match self {
+            AccessDecision::Granted => None,
+            AccessDecision::Denied => Some(crate::constants::coap_code::FORBIDDEN),
+        }
+    }
+
+    /// Returns the HTTP status with which the Resource Server should reject the request, i.e.,
+    /// 403 (Forbidden) if it has been denied, or `None` if it has been granted.
+    #[must_use]
+    pub fn http_status(self) -> Option<u16> {
+        match self {
+            AccessDecision::Granted => None,
+            AccessDecision::Denied => Some(403),
+        }
+    }
+}
+
+/// A request for a resource on the Resource Server, which is checked by [`authorize`].
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+pub struct AccessRequest<'a> {
+    /// The identity of the requesting client (e.g., as determined by the secure channel),
+    /// if known.
+    pub identity: Option<&'a str>,
+
+    /// The URI path of the requested resource.
+    pub resource: &'a str,
+
+    /// The REST method of the request.
+    pub method: AifRestMethod,
+
+    /// The `cti` (CWT ID) of the token used for the request, if it has one.
+    pub token_id: Option<&'a [u8]>,
+}
+
+/// A single decision made by [`authorize`], as reported to an [`AccessDecisionSink`].
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+pub struct AccessDecisionRecord<'a> {
+    /// The request which has been checked.
+    pub request: AccessRequest<'a>,
+
+    /// Whether the request has been granted.
+    pub decision: AccessDecision,
+
+    /// The element of the token's scope which granted the request,
+    /// or `None` if the request has been denied.
+    pub matched_element: Option<&'a AifEncodedScopeElement>,
+}
+
+/// Receives every decision made by [`authorize`], e.g., to keep an audit trail.
+///
+/// # Example
+/// ```
+/// # use dcaf::AifEncodedScope;
+/// # use dcaf::common::scope::{AifRestMethod, ToidMatching};
+/// use dcaf::token::validation::{authorize, AccessDecision, AccessDecisionRecord, AccessDecisionSink, AccessRequest};
+///
+/// /// Collects the paths of all denied requests.
+/// #[derive(Default)]
+/// struct DeniedLog(Vec<String>);
+///
+/// impl AccessDecisionSink for DeniedLog {
+///     fn record(&mut self, record: &AccessDecisionRecord<'_>) {
+///         if record.decision == AccessDecision::Denied {
+///             self.0.push(record.request.resource.to_string());
+///         }
+///     }
+/// }
+///
+/// let scope = AifEncodedScope::from(vec![("/s/temp", AifRestMethod::Get.into())]);
+/// let mut log = DeniedLog::default();
+/// let request = AccessRequest {
+///     identity: Some("client1"),
+///     resource: "/s/temp",
+///     method: AifRestMethod::Put,
+///     token_id: Some(&[0xDC, 0xAF]),
+/// };
+/// assert_eq!(authorize(&scope, ToidMatching::Exact, request, &mut log), AccessDecision::Denied);
+/// assert_eq!(log.0, vec!["/s/temp"]);
+/// ```
+pub trait AccessDecisionSink {
+    /// Records the given decision.
+    fn record(&mut self, record: &AccessDecisionRecord<'_>);
+}
+
+/// Checks whether the given AIF `scope` (with Toids being matched according to `matching`)
+/// permits the given `request`, reporting the decision to the given `sink`.
+///
+/// For an example, see the documentation of [`AccessDecisionSink`].
+pub fn authorize<'a, S>(
+    scope: &'a AifEncodedScope,
+    matching: ToidMatching,
+    request: AccessRequest<'a>,
+    sink: &mut S,
+) -> AccessDecision
+where
+    S: AccessDecisionSink,
+{
+    let matched_element = scope.elements().iter().find(|element| {
+        element.permissions.contains(request.method) && element.covers(request.resource, matching)
+    });
+    let decision = if matched_element.is_some() {
+        AccessDecision::Granted
+    } else {
+        AccessDecision::Denied
+    };
+    sink.record(&AccessDecisionRecord {
+        request,
+        decision,
+        matched_element,
+    });
+    decision
+}
+
+/// An element of a [`ScopeMatcher`], along with the [`ToidMatching`] strategy its Toid is
+/// interpreted with.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+struct CompiledElement {
+    toid: String,
+    matching: ToidMatching,
+    permissions: AifRestMethodSet,
+}
+
+impl CompiledElement {
+    /// Compiles the given `element` for the given `matching` strategy, removing the dot segments
+    /// from its Toid.
+    fn new(element: &AifEncodedScopeElement, matching: ToidMatching) -> CompiledElement {
+        CompiledElement {
+            toid: remove_dot_segments(&element.path).into_owned(),
+            matching,
+            permissions: element.permissions,
+        }
+    }
+
+    /// Returns whether this element only covers the path equal to its Toid.
+    fn is_exact(&self) -> bool {
+        self.matching.is_exact(&self.toid)
+    }
+
+    /// Returns whether this element covers the given `path`, which must not contain
+    /// dot segments.
+    fn covers(&self, path: &str) -> bool {
+        self.matching.covers_normalized(&self.toid, path)
+    }
+}
+
+/// The scope of an access token, compiled ahead of time so that checking whether it permits a
+/// request doesn't allocate.
+///
+/// Decoding the `scope` claim of a token and interpreting its Toids requires heap allocations,
+/// which makes the latency of every request hard to predict on constrained devices.
+/// A [`ScopeMatcher`] does this work once (e.g., when the token is
+/// [inserted](crate::token::token_store::TokenStore::insert) into a token store), after which
+/// [`permits`](ScopeMatcher::permits) only compares strings and bit flags (as long as the
+/// requested path doesn't contain dot segments, which are removed first).
+///
+/// AIF-encoded and libdcaf-encoded scopes are supported. All other scopes don't specify which
+/// methods they permit on which resources, so a matcher compiled from them (or from a token
+/// without a valid `scope` claim) doesn't permit any request.
+///
+/// Elements whose Toids are compared exactly are kept sorted, so that they're found using a
+/// binary search, while all other elements are checked in order.
+///
+/// # Example
+/// ```
+/// # use dcaf::{AifEncodedScope, Scope};
+/// # use dcaf::common::scope::{AifRestMethod, ToidMatching};
+/// use dcaf::token::validation::ScopeMatcher;
+///
+/// let scope = Scope::from(AifEncodedScope::from(vec![
+///     ("/s/*", AifRestMethod::Get.into()),
+///     ("/a/led", AifRestMethod::Put.into()),
+/// ]));
+/// let matcher = ScopeMatcher::new(&scope, ToidMatching::Wildcard);
+/// assert!(matcher.permits("/s/temp", AifRestMethod::Get));
+/// assert!(matcher.permits("/a/led", AifRestMethod::Put));
+/// assert!(!matcher.permits("/a/led", AifRestMethod::Get));
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Hash, Default)]
+pub struct ScopeMatcher {
+    /// Elements whose Toids are compared exactly, sorted by their Toid.
+    exact: Vec<CompiledElement>,
+
+    /// All other elements.
+    patterns: Vec<CompiledElement>,
+}
+
+impl ScopeMatcher {
+    /// Compiles the given `scope`, with Toids being interpreted according to `matching`.
+    #[must_use]
+    pub fn new(scope: &Scope, matching: ToidMatching) -> ScopeMatcher {
+        let elements: &[AifEncodedScopeElement] = match scope {
+            Scope::AifEncoded(scope) => scope.elements(),
+            Scope::LibdcafEncoded(scope) => core::slice::from_ref(scope.element()),
+            _ => &[],
+        };
+        let (mut exact, patterns): (Vec<CompiledElement>, Vec<CompiledElement>) = elements
+            .iter()
+            .map(|element| CompiledElement::new(element, matching))
+            .partition(CompiledElement::is_exact);
+        exact.sort_by(|a, b| a.toid.cmp(&b.toid));
+        // Permissions of elements with the same Toid are merged, so a binary search suffices.
+        exact.dedup_by(|next, previous| {
+            if next.toid == previous.toid {
+                previous.permissions |= next.permissions;
+                true
+            } else {
+                false
+            }
+        });
+        ScopeMatcher { exact, patterns }
+    }
+
+    /// Compiles the `scope` claim of the given `claims`, with Toids being interpreted according
+    /// to `matching`.
+    ///
+    /// If the claims contain no `scope` claim or an invalid one, the returned matcher doesn't
+    /// permit any request.
+    #[must_use]
+    pub fn from_claims(claims: &ClaimsSet, matching: ToidMatching) -> ScopeMatcher {
+        let scope_name = ClaimName::Assigned(CwtClaimName::Scope);
+        claims
+            .rest
+            .iter()
+            .find(|(name, _)| name == &scope_name)
+            .and_then(|(_, value)| Scope::try_from(value.clone()).ok())
+            .map_or_else(ScopeMatcher::default, |scope| {
+                ScopeMatcher::new(&scope, matching)
+            })
+    }
+
+    /// Returns all permissions the compiled scope grants for the given `path`, after removing
+    /// dot segments from it (see [`ToidMatching`]).
+    ///
+    /// This doesn't allocate, unless the `path` contains dot segments.
+    #[must_use]
+    pub fn permissions_for(&self, path: &str) -> AifRestMethodSet {
+        let path = &*remove_dot_segments(path);
+        let exact = self
+            .exact
+            .binary_search_by(|element| element.toid.as_str().cmp(path))
+            .map_or(AifRestMethodSet::empty(), |index| {
+                self.exact[index].permissions
+            });
+        self.patterns
+            .iter()
+            .filter(|element| element.covers(path))
+            .fold(exact, |acc, element| acc | element.permissions)
+    }
+
+    /// Returns whether the compiled scope permits the given `method` on the given `path`.
+    ///
+    /// This doesn't allocate, unless the `path` contains dot segments.
+    #[must_use]
+    pub fn permits(&self, path: &str, method: AifRestMethod) -> bool {
+        self.permissions_for(path).contains(method)
+    }
+
+    /// Returns `true` if the compiled scope doesn't permit any request.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.exact.is_empty() && self.patterns.is_empty()
+    }
+}
+
+/// Keeps track of the highest `iat` (issued at) value seen in any access token, so that tokens
+/// issued before it can be rejected as stale.
+///
+/// This is intended for Resource Servers without a synchronized clock, which would otherwise
+/// not be able to tell whether an old token (e.g., one replayed after a reboot) is still valid.
+/// In order to survive reboots, the watermark should be persisted using
+/// [`save`](IssuedAtWatermark::save) and [`restore`](IssuedAtWatermark::restore) (or, alternatively,
+/// its [`highest`](IssuedAtWatermark::highest) value and [`from_highest`](IssuedAtWatermark::from_highest)).
+///
+/// Note that tokens whose `iat` is *equal* to the watermark are accepted, since the same token
+/// may legitimately be posted to the Resource Server more than once.
+///
+/// For an example, see the [module-level documentation](self).
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct IssuedAtWatermark {
+    /// The highest `iat` value seen so far.
+    highest: Option<i64>,
+}
+
+impl IssuedAtWatermark {
+    /// Creates a new watermark which starts at the given `highest` value, e.g., as persisted
+    /// from [`highest`](IssuedAtWatermark::highest) before a reboot.
+    #[must_use]
+    pub fn from_highest(highest: i64) -> IssuedAtWatermark {
+        IssuedAtWatermark {
+            highest: Some(highest),
+        }
+    }
+
+    /// Returns the highest `iat` value seen so far, or `None` if no token has been checked yet.
+    #[must_use]
+    pub fn highest(&self) -> Option<i64> {
+        self.highest
+    }
+
+    /// Serializes this watermark as CBOR into the given `writer`, e.g., a region of flash memory,
+    /// so that it can later be [restored](IssuedAtWatermark::restore).
+    ///
+    /// # Example
+    /// ```
+    /// # use ciborium_io::Write;
+    /// use dcaf::token::validation::IssuedAtWatermark;
+    ///
+    /// let mut persisted: Vec<u8> = Vec::new();
+    /// IssuedAtWatermark::from_highest(1000).save(&mut persisted)?;
+    /// let restored = IssuedAtWatermark::restore(persisted.as_slice()).expect("invalid watermark");
+    /// assert_eq!(restored.highest(), Some(1000));
+    /// # Ok::<(), ciborium::ser::Error<<Vec<u8> as Write>::Error>>(())
+    /// ```
+    ///
+    /// # Errors
+    /// - When the output couldn't be put inside the given `writer`.
+    pub fn save<W>(&self, writer: W) -> Result<(), ciborium::ser::Error<W::Error>>
+    where
+        W: Write,
+        W::Error: Debug,
+    {
+        into_writer(self, writer)
+    }
+
+    /// Deserializes a watermark which has previously been [saved](IssuedAtWatermark::save)
+    /// from the given `reader`.
+    ///
+    /// # Errors
+    /// - When the given `reader` does not contain a valid serialized watermark.
+    /// - When the input couldn't be read from the given `reader`.
+    pub fn restore<R>(reader: R) -> Result<IssuedAtWatermark, ciborium::de::Error<R::Error>>
+    where
+        R: Read,
+        R::Error: Debug,
+    {
+        from_reader(reader)
+    }
+
+    /// Checks the `iat` claim of the given `claims` against this watermark, raising the watermark
+    /// if the token is newer than all tokens seen before.
+    ///
+    /// # Errors
+    /// - If the `claims` don't contain an `iat` claim.
+    /// - If the `iat` claim is lower than the watermark.
+    pub fn check(&mut self, claims: &ClaimsSet) -> Result<(), ClaimsValidationError> {
+        let issued_at = claims
+            .issued_at
+            .as_ref()
+            .map(timestamp_seconds)
+            .ok_or(ClaimsValidationError::MissingClaim("iat"))?;
+        match self.highest {
+            Some(watermark) if issued_at < watermark => Err(ClaimsValidationError::StaleIssuedAt {
+                issued_at,
+                watermark,
+            }),
+            _ => {
+                self.highest = Some(issued_at);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The client nonces a Resource Server has sent in its
+/// [creation hints](crate::AuthServerRequestCreationHint::client_nonce) and not yet seen echoed
+/// back in the `cnonce` claim of a token.
+///
+/// Each nonce is only accepted once, so that a token can't be replayed, and only the newest
+/// `capacity` nonces are kept, so that unanswered creation hints can't exhaust the memory of the
+/// Resource Server.
+///
+/// # Example
+/// ```
+/// # use ciborium::value::Value;
+/// # use coset::cwt::ClaimsSetBuilder;
+/// # use coset::iana::CwtClaimName;
+/// # use dcaf::error::ClaimsValidationError;
+/// use dcaf::token::validation::ClientNonces;
+///
+/// let mut nonces = ClientNonces::new(4);
+/// nonces.issue(vec![0xDC, 0xAF]);
+/// let claims = ClaimsSetBuilder::new()
+///     .claim(CwtClaimName::CNonce, Value::Bytes(vec![0xDC, 0xAF]))
+///     .build();
+/// assert!(nonces.check(&claims).is_ok());
+/// // The nonce has been consumed.
+/// assert_eq!(
+///     nonces.check(&claims),
+///     Err(ClaimsValidationError::UnknownClientNonce(vec![0xDC, 0xAF]))
+/// );
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct ClientNonces {
+    /// The maximum number of pending nonces.
+    capacity: usize,
+
+    /// The pending nonces, oldest first.
+    pending: Vec<ByteString>,
+}
+
+impl ClientNonces {
+    /// Creates a new, empty set of client nonces which keeps at most `capacity` pending nonces.
+    #[must_use]
+    pub fn new(capacity: usize) -> ClientNonces {
+        ClientNonces {
+            capacity,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Returns the maximum number of pending nonces.
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of pending nonces.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Returns whether there are no pending nonces.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Records the given `nonce` as sent to a client, dropping the oldest pending nonce if the
+    /// capacity has been reached.
+    pub fn issue(&mut self, nonce: ByteString) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.pending.len() >= self.capacity {
+            self.pending.remove(0);
+        }
+        self.pending.push(nonce);
+    }
+
+    /// Checks that the `cnonce` claim of the given `claims` contains a pending nonce, which is
+    /// consumed in the process.
+    ///
+    /// # Errors
+    /// - If the `claims` don't contain a `cnonce` claim holding a byte string
+    ///   ([`ClaimsValidationError::MissingClaim`]).
+    /// - If the nonce isn't pending, i.e., it has never been issued, has already been used,
+    ///   or has been dropped ([`ClaimsValidationError::UnknownClientNonce`]).
+    pub fn check(&mut self, claims: &ClaimsSet) -> Result<(), ClaimsValidationError> {
+        let nonce =
+            get_client_nonce(claims).ok_or(ClaimsValidationError::MissingClaim("cnonce"))?;
+        let index = self
+            .pending
+            .iter()
+            .position(|x| x == nonce)
+            .ok_or_else(|| ClaimsValidationError::UnknownClientNonce(nonce.clone()))?;
+        self.pending.remove(index);
+        Ok(())
+    }
+}
+
+/// A variant of [`IssuedAtWatermark`] which can be shared between threads without locking,
+/// e.g., by the worker threads of a multi-core Resource Server gateway.
+///
+/// The watermark is kept in an atomic integer, which [`check`](AtomicIssuedAtWatermark::check)
+/// raises using a compare-and-swap loop, so that concurrent checks never block each other.
+/// In order to persist it, take a [`snapshot`](AtomicIssuedAtWatermark::snapshot) and
+/// [`save`](IssuedAtWatermark::save) that.
+///
+/// # Example
+/// ```
+/// # use std::sync::Arc;
+/// # use coset::cwt::{ClaimsSetBuilder, Timestamp};
+/// use dcaf::token::validation::AtomicIssuedAtWatermark;
+///
+/// let watermark = Arc::new(AtomicIssuedAtWatermark::default());
+/// let workers: Vec<_> = (1000..1004)
+///     .map(|issued_at| {
+///         let watermark = Arc::clone(&watermark);
+///         std::thread::spawn(move || {
+///             let claims = ClaimsSetBuilder::new().issued_at(Timestamp::WholeSeconds(issued_at)).build();
+///             let _ = watermark.check(&claims);
+///         })
+///     })
+///     .collect();
+/// for worker in workers {
+///     worker.join().expect("worker panicked");
+/// }
+/// assert_eq!(watermark.snapshot().highest(), Some(1003));
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct AtomicIssuedAtWatermark {
+    /// The highest `iat` value seen so far, with [`i64::MIN`] standing for none.
+    highest: AtomicI64,
+}
+
+#[cfg(feature = "std")]
+impl AtomicIssuedAtWatermark {
+    /// Returns the current state of this watermark as an [`IssuedAtWatermark`], e.g., in order
+    /// to [`save`](IssuedAtWatermark::save) it.
+    #[must_use]
+    pub fn snapshot(&self) -> IssuedAtWatermark {
+        match self.highest.load(AtomicOrdering::Acquire) {
+            i64::MIN => IssuedAtWatermark::default(),
+            highest => IssuedAtWatermark::from_highest(highest),
+        }
+    }
+
+    /// Checks the `iat` claim of the given `claims` against this watermark, raising the watermark
+    /// if the token is newer than all tokens seen before, just like [`IssuedAtWatermark::check`].
+    ///
+    /// # Errors
+    /// - If the `claims` don't contain an `iat` claim.
+    /// - If the `iat` claim is lower than the watermark.
+    pub fn check(&self, claims: &ClaimsSet) -> Result<(), ClaimsValidationError> {
+        let issued_at = claims
+            .issued_at
+            .as_ref()
+            .map(timestamp_seconds)
+            .ok_or(ClaimsValidationError::MissingClaim("iat"))?;
+        self.highest
+            .fetch_update(
+                AtomicOrdering::AcqRel,
+                AtomicOrdering::Acquire,
+                |watermark| (issued_at >= watermark).then(|| issued_at),
+            )
+            .map(|_| ())
+            .map_err(|watermark| ClaimsValidationError::StaleIssuedAt {
+                issued_at,
+                watermark,
+            })
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for AtomicIssuedAtWatermark {
+    fn default() -> Self {
+        AtomicIssuedAtWatermark::from(IssuedAtWatermark::default())
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<IssuedAtWatermark> for AtomicIssuedAtWatermark {
+    fn from(watermark: IssuedAtWatermark) -> Self {
+        AtomicIssuedAtWatermark {
+            highest: AtomicI64::new(watermark.highest.unwrap_or(i64::MIN)),
+        }
+    }
+}
+
+/// A variant of [`ClientNonces`] which can be shared between threads, e.g., by the worker
+/// threads of a multi-core Resource Server gateway.
+///
+/// The nonces are distributed over several shards by their hash, each of which is locked
+/// separately, so that concurrent checks of different nonces rarely contend for the same lock.
+/// The capacity is split evenly between the shards.
+///
+/// # Example
+/// ```
+/// # use ciborium::value::Value;
+/// # use coset::cwt::ClaimsSetBuilder;
+/// # use coset::iana::CwtClaimName;
+/// use dcaf::token::validation::ShardedClientNonces;
+///
+/// let nonces = ShardedClientNonces::new(64, 8);
+/// nonces.issue(vec![0xDC, 0xAF]);
+/// let claims = ClaimsSetBuilder::new()
+///     .claim(CwtClaimName::CNonce, Value::Bytes(vec![0xDC, 0xAF]))
+///     .build();
+/// assert!(nonces.check(&claims).is_ok());
+/// assert!(nonces.check(&claims).is_err());
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct ShardedClientNonces {
+    /// The shards, each of which holds the nonces hashing to it.
+    shards: Vec<Mutex<ClientNonces>>,
+}
+
+#[cfg(feature = "std")]
+impl ShardedClientNonces {
+    /// Creates a new, empty set of client nonces which keeps at most `capacity` pending nonces
+    /// (rounded up to a multiple of the number of shards), distributed over the given number
+    /// of `shards` (at least one).
+    #[must_use]
+    pub fn new(capacity: usize, shards: usize) -> ShardedClientNonces {
+        let shards = shards.max(1);
+        let per_shard = (capacity + shards - 1) / shards;
+        ShardedClientNonces {
+            shards: (0..shards)
+                .map(|_| Mutex::new(ClientNonces::new(per_shard)))
+                .collect(),
+        }
+    }
+
+    /// Returns the number of pending nonces.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| shard.lock().unwrap_or_else(PoisonError::into_inner).len())
+            .sum()
+    }
+
+    /// Returns whether there are no pending nonces.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Records the given `nonce` as sent to a client, dropping the oldest pending nonce of its
+    /// shard if the shard is full.
+    pub fn issue(&self, nonce: ByteString) {
+        self.shard(&nonce)
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .issue(nonce);
+    }
+
+    /// Checks that the `cnonce` claim of the given `claims` contains a pending nonce, which is
+    /// consumed in the process, just like [`ClientNonces::check`].
+    ///
+    /// # Errors
+    /// - If the `claims` don't contain a `cnonce` claim holding a byte string
+    ///   ([`ClaimsValidationError::MissingClaim`]).
+    /// - If the nonce isn't pending, i.e., it has never been issued, has already been used,
+    ///   or has been dropped ([`ClaimsValidationError::UnknownClientNonce`]).
+    pub fn check(&self, claims: &ClaimsSet) -> Result<(), ClaimsValidationError> {
+        let nonce =
+            get_client_nonce(claims).ok_or(ClaimsValidationError::MissingClaim("cnonce"))?;
+        self.shard(nonce)
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .check(claims)
+    }
+
+    /// Returns the shard the given `nonce` belongs to.
+    #[allow(clippy::cast_possible_truncation)]
+    fn shard(&self, nonce: &[u8]) -> &Mutex<ClientNonces> {
+        let mut hasher = DefaultHasher::new();
+        nonce.hash(&mut hasher);
+        &self.shards[(hasher.finish() % self.shards.len() as u64) as usize]
+    }
+}
+
+/// Determines whether a newer token of a client supersedes its older tokens,
+/// see [`TokenSupersession`].
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash, Serialize, Deserialize)]
+pub enum SupersessionRule {
+    /// A token with a newer `iat` supersedes older tokens of the same client for the same
+    /// audience, which are rejected from then on.
+    ///
+    /// If two tokens have the same `iat`, the one with the (lexicographically) greater `cti`
+    /// supersedes the other.
+    ///
+    /// This is the default, as recommended for updating access rights in
+    /// [section 5.10.1 of RFC 9200](https://www.rfc-editor.org/rfc/rfc9200#section-5.10.1).
+    NewerIssuedAt,
+
+    /// Tokens never supersede each other, so multiple tokens may be valid at the same time.
+    KeepAll,
+}
+
+impl Default for SupersessionRule {
+    fn default() -> Self {
+        SupersessionRule::NewerIssuedAt
+    }
+}
+
+/// The result of offering a token to a [`TokenSupersession`].
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub enum Supersession {
+    /// The token is the current one for its client and audience, and no other token has been
+    /// superseded (e.g., because it's the first token or has already been offered before).
+    Current,
+
+    /// The token is now the current one for its client and audience, and the token with the
+    /// contained `cti` (empty if it had none) has been superseded by it.
+    ///
+    /// The Resource Server should discard the superseded token and any state associated with it.
+    Supersedes(ByteString),
+}
+
+/// Keeps track of the current (i.e., newest) token of each client for each audience, so that
+/// older tokens are superseded by newer ones according to a [`SupersessionRule`].
+///
+/// Tokens are ordered by their `iat` claim, with ties broken by their `cti` claim.
+/// This type implements [`Serialize`] and [`Deserialize`], so that it can be persisted.
+///
+/// # Example
+/// ```
+/// # use coset::cwt::{ClaimsSetBuilder, Timestamp};
+/// # use dcaf::error::ClaimsValidationError;
+/// use dcaf::token::validation::{Supersession, SupersessionRule, TokenSupersession};
+///
+/// let mut supersession = TokenSupersession::new(SupersessionRule::NewerIssuedAt);
+/// let token = |iat, cti: u8| ClaimsSetBuilder::new()
+///     .audience("rs1".to_string())
+///     .issued_at(Timestamp::WholeSeconds(iat))
+///     .cwt_id(vec![cti])
+///     .build();
+/// assert_eq!(supersession.offer("client1", &token(1000, 1))?, Supersession::Current);
+/// assert_eq!(supersession.offer("client1", &token(2000, 2))?, Supersession::Supersedes(vec![1]));
+/// assert!(supersession.offer("client1", &token(1000, 1)).is_err());
+/// // Other clients are not affected.
+/// assert_eq!(supersession.offer("client2", &token(1000, 3))?, Supersession::Current);
+/// # Ok::<(), ClaimsValidationError>(())
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Hash, Default, Serialize, Deserialize)]
+pub struct TokenSupersession {
+    /// The rule according to which tokens supersede each other.
+    rule: SupersessionRule,
+
+    /// The current token per client and audience, identified by its `iat` and `cti`.
+    current: Vec<CurrentToken>,
+}
+
+/// The current token of a client for an audience, as tracked by [`TokenSupersession`].
+#[derive(Debug, PartialEq, Eq, Clone, Hash, Serialize, Deserialize)]
+struct CurrentToken {
+    client: String,
+    audience: Option<String>,
+    issued_at: i64,
+    token_id: ByteString,
+}
+
+impl TokenSupersession {
+    /// Creates a new, empty instance which applies the given `rule`.
+    #[must_use]
+    pub fn new(rule: SupersessionRule) -> TokenSupersession {
+        TokenSupersession {
+            rule,
+            current: Vec::new(),
+        }
+    }
+
+    /// Offers the `claims` of a token posted by the given `client` (e.g., as identified by the
+    /// secure channel or the token's `cnf` claim), making it the current token of this client
+    /// for its audience unless it is superseded by a token seen before.
+    ///
+    /// If the [`SupersessionRule::KeepAll`] rule is used, this always returns
+    /// [`Supersession::Current`].
+    ///
+    /// # Errors
+    /// - If the `claims` contain no `iat` claim ([`ClaimsValidationError::MissingClaim`]).
+    /// - If the token has been superseded by a newer token of the same client for the same
+    ///   audience ([`ClaimsValidationError::Superseded`]).
+    pub fn offer(
+        &mut self,
+        client: &str,
+        claims: &ClaimsSet,
+    ) -> Result<Supersession, ClaimsValidationError> {
+        if self.rule == SupersessionRule::KeepAll {
+            return Ok(Supersession::Current);
+        }
+        let issued_at = claims
+            .issued_at
+            .as_ref()
+            .map(timestamp_seconds)
+            .ok_or(ClaimsValidationError::MissingClaim("iat"))?;
+        let token_id = claims.cwt_id.clone().unwrap_or_default();
+        let existing = self
+            .current
+            .iter_mut()
+            .find(|x| x.client == client && x.audience == claims.audience);
+        match existing {
+            None => {
+                self.current.push(CurrentToken {
+                    client: client.to_string(),
+                    audience: claims.audience.clone(),
+                    issued_at,
+                    token_id,
+                });
+                Ok(Supersession::Current)
+            }
+            Some(current) => {
+                match (issued_at, &token_id).cmp(&(current.issued_at, &current.token_id)) {
+                    Ordering::Equal => Ok(Supersession::Current),
+                    Ordering::Less => Err(ClaimsValidationError::Superseded {
+                        issued_at,
+                        current_issued_at: current.issued_at,
+                    }),
+                    Ordering::Greater => {
+                        current.issued_at = issued_at;
+                        let superseded = core::mem::replace(&mut current.token_id, token_id);
+                        Ok(Supersession::Supersedes(superseded))
+                    }
+                }
+            }
+        }
+    }
+
+    /// Forgets the current tokens of the given `client` (e.g., after its tokens have expired).
+    pub fn remove_client(&mut self, client: &str) {
+        self.current.retain(|x| x.client != client);
+    }
+}
+
+/// The algorithms with which the tokens for each audience of the Resource Server are expected
+/// to be protected.
+///
+/// Tokens for an audience without a recorded algorithm are not restricted by this.
+///
+/// # Example
+/// ```
+/// # use coset::cwt::ClaimsSetBuilder;
+/// # use coset::{Header, ProtectedHeader};
+/// # use dcaf::error::ClaimsValidationError;
+/// use dcaf::common::cose::Algorithm;
+/// use dcaf::token::validation::AudienceAlgorithms;
+///
+/// let mut algorithms = AudienceAlgorithms::default();
+/// algorithms.pin("valve242", Algorithm::Es256);
+/// let claims = ClaimsSetBuilder::new().audience("valve242".to_string()).build();
+/// let protected = |alg: Algorithm| ProtectedHeader {
+///     original_data: None,
+///     header: Header { alg: Some(alg.into()), ..Header::default() },
+/// };
+/// assert!(algorithms.check(&claims, &protected(Algorithm::Es256)).is_ok());
+/// assert_eq!(
+///     algorithms.check(&claims, &protected(Algorithm::Hmac256_64)),
+///     Err(ClaimsValidationError::UnexpectedAlgorithm {
+///         audience: "valve242".to_string(),
+///         expected: Algorithm::Es256,
+///         actual: Some(Algorithm::Hmac256_64),
+///     })
+/// );
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Hash, Default, Serialize, Deserialize)]
+pub struct AudienceAlgorithms {
+    /// The expected algorithm per audience.
+    expected: Vec<(String, Algorithm)>,
+}
+
+impl AudienceAlgorithms {
+    /// Records that tokens for the given `audience` must be protected with `algorithm`,
+    /// replacing any algorithm previously recorded for it.
+    pub fn pin<T>(&mut self, audience: T, algorithm: Algorithm)
+    where
+        T: Into<String>,
+    {
+        let audience = audience.into();
+        self.expected.retain(|(x, _)| *x != audience);
+        self.expected.push((audience, algorithm));
+    }
+
+    /// Returns the algorithm tokens for the given `audience` must be protected with, if any.
+    #[must_use]
+    pub fn expected(&self, audience: &str) -> Option<Algorithm> {
+        self.expected
+            .iter()
+            .find(|(x, _)| x == audience)
+            .map(|(_, algorithm)| *algorithm)
+    }
+
+    /// Checks that the token with the given `claims` is protected with the algorithm expected
+    /// for its audience, as given in the `protected` header of the token
+    /// (see [`get_token_headers`](crate::token::get_token_headers)).
+    ///
+    /// Note that the algorithm in the unprotected header is ignored, as it could have been
+    /// changed by an attacker.
+    ///
+    /// # Errors
+    /// If an algorithm has been recorded for the token's audience, but the token is protected
+    /// with another one (or doesn't specify its algorithm).
+    pub fn check(
+        &self,
+        claims: &ClaimsSet,
+        protected: &ProtectedHeader,
+    ) -> Result<(), ClaimsValidationError> {
+        let audience = match claims.audience.as_deref() {
+            Some(audience) => audience,
+            None => return Ok(()),
+        };
+        match self.expected(audience) {
+            None => Ok(()),
+            Some(expected) => {
+                let actual = protected
+                    .header
+                    .alg
+                    .clone()
+                    .and_then(|x| Algorithm::try_from(x).ok());
+                if actual == Some(expected) {
+                    Ok(())
+                } else {
+                    Err(ClaimsValidationError::UnexpectedAlgorithm {
+                        audience: audience.to_string(),
+                        expected,
+                        actual,
+                    })
+                }
+            }
+        }
+    }
+}
+
+/// The keys of the Authorization Servers whose tokens the Resource Server accepts, identified by
+/// their key IDs.
+///
+/// Since the key used to verify (or decrypt) a token is selected by the key ID in its header,
+/// key IDs must be unambiguous: [`insert`](TrustStore::insert) rejects keys without a key ID
+/// as well as keys whose key ID is already used by a *different* key (even of a different
+/// issuer), instead of silently picking one of them later. Rotating the key of a key ID
+/// requires an explicit call to [`replace`](TrustStore::replace).
+///
+/// Each key is bound to the issuer it has been added for. Once a token has been verified (or
+/// decrypted) with a key from this store, [`check_issuer`](TrustStore::check_issuer) makes sure
+/// that the token's `iss` claim names that issuer, so that an Authorization Server can't issue
+/// tokens in the name of another one trusted by the same Resource Server.
+///
+/// Keys can be restricted to a single [`KeyUsage`] using
+/// [`declare_usage`](TrustStore::declare_usage), after which [`key_for`](TrustStore::key_for)
+/// refuses to return them for any other usage. Keys whose usage hasn't been declared can be used
+/// for any purpose.
+/// The declared usage is kept when a key is [replaced](TrustStore::replace).
+///
+/// # Example
+/// ```
+/// # use coset::{CoseKeyBuilder, HeaderBuilder};
+/// # use coset::cwt::ClaimsSetBuilder;
+/// # use dcaf::error::TrustStoreError;
+/// use dcaf::token::KeyUsage;
+/// use dcaf::token::validation::TrustStore;
+///
+/// let mut store = TrustStore::default();
+/// let key = |secret: u8| CoseKeyBuilder::new_symmetric_key(vec![secret; 16])
+///     .key_id(vec![0xDC, 0xAF])
+///     .build();
+/// store.insert("as1.example.com", key(1))?;
+/// assert_eq!(
+///     store.insert("as2.example.com", key(2)),
+///     Err(TrustStoreError::KeyIdCollision {
+///         key_id: vec![0xDC, 0xAF],
+///         issuer: "as1.example.com".to_string(),
+///     })
+/// );
+///
+/// let header = HeaderBuilder::new().key_id(vec![0xDC, 0xAF]).build();
+/// assert_eq!(
+///     store.key_for(&header, &Default::default(), KeyUsage::Sign)?,
+///     ("as1.example.com", &key(1))
+/// );
+/// let unknown = HeaderBuilder::new().key_id(vec![0x42]).build();
+/// assert_eq!(
+///     store.key_for(&unknown, &Default::default(), KeyUsage::Sign),
+///     Err(TrustStoreError::UnknownKeyId(vec![0x42]))
+/// );
+///
+/// // After verifying a token with this key, its issuer has to match the key's.
+/// let claims = ClaimsSetBuilder::new().issuer("as2.example.com".to_string()).build();
+/// assert_eq!(
+///     store.check_issuer(&header, &Default::default(), &claims),
+///     Err(TrustStoreError::IssuerMismatch {
+///         expected: "as1.example.com".to_string(),
+///         actual: Some("as2.example.com".to_string()),
+///     })
+/// );
+///
+/// // This key may only be used to verify tokens, not to decrypt them.
+/// store.declare_usage(&[0xDC, 0xAF], KeyUsage::Sign)?;
+/// assert!(store.key_for(&header, &Default::default(), KeyUsage::Sign).is_ok());
+/// assert!(store.key_for(&header, &Default::default(), KeyUsage::Encrypt).is_err());
+/// # Ok::<(), TrustStoreError>(())
+/// ```
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct TrustStore {
+    /// The trusted keys, along with the issuer they belong to.
+    keys: Vec<(String, CoseKey)>,
+
+    /// The declared usages of the trusted keys.
+    usages: KeyUsages,
+}
+
+impl TrustStore {
+    /// Adds the given `key` of the given `issuer` to this store.
+    ///
+    /// Adding the same key for the same issuer again has no effect.
+    ///
+    /// # Errors
+    /// - If the key has no key ID ([`TrustStoreError::MissingKeyId`]).
+    /// - If a different key (or the same key of a different issuer) with the same key ID has
+    ///   already been added ([`TrustStoreError::KeyIdCollision`]).
+    pub fn insert<T>(&mut self, issuer: T, key: CoseKey) -> Result<(), TrustStoreError>
+    where
+        T: Into<String>,
+    {
+        let issuer = issuer.into();
+        if key.key_id.is_empty() {
+            return Err(TrustStoreError::MissingKeyId);
+        }
+        match self.keys.iter().find(|(_, x)| x.key_id == key.key_id) {
+            Some((existing, existing_key)) if *existing == issuer && *existing_key == key => Ok(()),
+            Some((existing, _)) => Err(TrustStoreError::KeyIdCollision {
+                key_id: key.key_id,
+                issuer: existing.clone(),
+            }),
+            None => {
+                self.keys.push((issuer, key));
+                Ok(())
+            }
+        }
+    }
+
+    /// Adds the given `key` of the given `issuer` to this store, replacing the key which
+    /// previously used the same key ID (if any) and returning it along with its issuer.
+    ///
+    /// This is intended for key rotation, where reusing a key ID is deliberate, so the usage
+    /// declared for the previous key is kept.
+    ///
+    /// # Errors
+    /// If the key has no key ID ([`TrustStoreError::MissingKeyId`]).
+    pub fn replace<T>(
+        &mut self,
+        issuer: T,
+        key: CoseKey,
+    ) -> Result<Option<(String, CoseKey)>, TrustStoreError>
+    where
+        T: Into<String>,
+    {
+        if key.key_id.is_empty() {
+            return Err(TrustStoreError::MissingKeyId);
+        }
+        let usage = self.usage(&key.key_id);
+        let replaced = self.remove(&key.key_id);
+        if let Some(usage) = usage {
+            self.usages.declare(&key.key_id, usage)?;
+        }
+        self.keys.push((issuer.into(), key));
+        Ok(replaced)
+    }
+
+    /// Removes the key with the given `key_id` along with its declared usage, returning the key
+    /// along with its issuer.
+    pub fn remove(&mut self, key_id: &[u8]) -> Option<(String, CoseKey)> {
+        let index = self.keys.iter().position(|(_, x)| x.key_id == key_id)?;
+        self.usages.remove(key_id);
+        Some(self.keys.remove(index))
+    }
+
+    /// Restricts the key with the given `key_id` to the given `usage`.
+    ///
+    /// Declaring the same usage again has no effect.
+    ///
+    /// # Errors
+    /// - If no key with the given `key_id` is known ([`TrustStoreError::UnknownKeyId`]).
+    /// - If a different usage has already been declared for the key
+    ///   ([`TrustStoreError::KeyUsage`]).
+    pub fn declare_usage(&mut self, key_id: &[u8], usage: KeyUsage) -> Result<(), TrustStoreError> {
+        if self.key(key_id).is_none() {
+            return Err(TrustStoreError::UnknownKeyId(key_id.to_vec()));
+        }
+        Ok(self.usages.declare(key_id, usage)?)
+    }
+
+    /// Returns the usage declared for the key with the given `key_id`, if any.
+    #[must_use]
+    pub fn usage(&self, key_id: &[u8]) -> Option<KeyUsage> {
+        self.usages.get(key_id)
+    }
+
+    /// Returns the issuer and the key with the given `key_id`, if any.
+    #[must_use]
+    pub fn key(&self, key_id: &[u8]) -> Option<(&str, &CoseKey)> {
+        self.keys
+            .iter()
+            .find(|(_, x)| x.key_id == key_id)
+            .map(|(issuer, key)| (issuer.as_str(), key))
+    }
+
+    /// Returns the issuer and the key identified by the key ID of a token with the given
+    /// `protected` and `unprotected` headers (see [`get_token_headers`](crate::token::get_token_headers)),
+    /// where the key ID in the protected header takes precedence, if the key may be used for the
+    /// given `usage`.
+    ///
+    /// # Errors
+    /// - If neither header contains a key ID ([`TrustStoreError::MissingKeyId`]).
+    /// - If no key with the token's key ID is known ([`TrustStoreError::UnknownKeyId`]).
+    /// - If a different usage has been declared for the key ([`TrustStoreError::KeyUsage`]).
+    pub fn key_for(
+        &self,
+        protected: &Header,
+        unprotected: &Header,
+        usage: KeyUsage,
+    ) -> Result<(&str, &CoseKey), TrustStoreError> {
+        let (issuer, key) = self.key_by_header(protected, unprotected)?;
+        self.usages.check(&key.key_id, usage)?;
+        Ok((issuer, key))
+    }
+
+    /// Returns the issuer and the key identified by the key ID of a token with the given
+    /// `protected` and `unprotected` headers, regardless of its declared usage.
+    fn key_by_header(
+        &self,
+        protected: &Header,
+        unprotected: &Header,
+    ) -> Result<(&str, &CoseKey), TrustStoreError> {
+        let key_id = [protected, unprotected]
+            .into_iter()
+            .map(|x| &x.key_id)
+            .find(|x| !x.is_empty())
+            .ok_or(TrustStoreError::MissingKeyId)?;
+        self.key(key_id)
+            .ok_or_else(|| TrustStoreError::UnknownKeyId(key_id.clone()))
+    }
+
+    /// Checks that the `iss` claim of the given `claims` names the issuer of the key which is
+    /// identified by the given `protected` and `unprotected` headers of the token (as in
+    /// [`key_for`](TrustStore::key_for)), returning that issuer.
+    ///
+    /// This should be called after the token has been verified (or decrypted) using the key
+    /// returned by [`key_for`](TrustStore::key_for).
+    ///
+    /// # Errors
+    /// - If the key can't be found, as described for [`key_for`](TrustStore::key_for).
+    /// - If the `claims` contain no `iss` claim or one naming a different issuer
+    ///   ([`TrustStoreError::IssuerMismatch`]).
+    pub fn check_issuer(
+        &self,
+        protected: &Header,
+        unprotected: &Header,
+        claims: &ClaimsSet,
+    ) -> Result<&str, TrustStoreError> {
+        let (issuer, _) = self.key_by_header(protected, unprotected)?;
+        if claims.issuer.as_deref() == Some(issuer) {
+            Ok(issuer)
+        } else {
+            Err(TrustStoreError::IssuerMismatch {
+                expected: issuer.to_string(),
+                actual: claims.issuer.clone(),
+            })
+        }
+    }
+}
+
+/// Returns the point in time (in seconds) at which a token with the given `claims` expires,
+/// assuming the Resource Server first received it at `received_at`, as described in
+/// [section 5.10.3 of RFC 9200](https://www.rfc-editor.org/rfc/rfc9200#section-5.10.3).
+///
+/// Note that `received_at` doesn't need to be an absolute timestamp---any monotonic clock
+/// (e.g., seconds since boot) works, as long as it's used consistently.
+///
+/// Returns `None` if the `claims` don't contain an `exi` claim.
+///
+/// # Errors
+/// If the `exi` claim is not a non-negative integer.
+///
+/// # Example
+/// ```
+/// # use ciborium::value::Value;
+/// # use coset::cwt::ClaimsSetBuilder;
+/// # use coset::iana::CwtClaimName;
+/// # use dcaf::error::ClaimsValidationError;
+/// use dcaf::token::validation::exi_expiration;
+///
+/// let claims = ClaimsSetBuilder::new().claim(CwtClaimName::Exi, Value::from(3600)).build();
+/// assert_eq!(exi_expiration(&claims, 100)?, Some(3700));
+/// # Ok::<(), ClaimsValidationError>(())
+/// ```
+pub fn exi_expiration(
+    claims: &ClaimsSet,
+    received_at: i64,
+) -> Result<Option<i64>, ClaimsValidationError> {
+    let exi_name = ClaimName::Assigned(CwtClaimName::Exi);
+    match claims.rest.iter().find(|(name, _)| name == &exi_name) {
+        None => Ok(None),
+        Some((_, Value::Integer(exi))) => u32::try_from(*exi)
+            .map(|exi| Some(received_at.saturating_add(i64::from(exi))))
+            .map_err(|_| ClaimsValidationError::InvalidClaim("exi")),
+        Some(_) => Err(ClaimsValidationError::InvalidClaim("exi")),
+    }
+}
+
+/// Returns the given `timestamp` in whole seconds, truncating fractional parts.
+#[allow(clippy::cast_possible_truncation)]
+pub(crate) fn timestamp_seconds(timestamp: &Timestamp) -> i64 {
+    match timestamp {
+        Timestamp::WholeSeconds(seconds) => *seconds,
+        Timestamp::FractionalSeconds(seconds) => *seconds as i64,
+    }
+}
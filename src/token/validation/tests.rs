@@ -0,0 +1,794 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+use coset::cwt::ClaimsSetBuilder;
+use coset::{CoseKeyBuilder, HeaderBuilder};
+
+#[cfg(not(feature = "std"))]
+use alloc::{string::ToString, vec::Vec};
+
+use crate::constants::coap_code;
+use crate::error::KeyUsageError;
+
+use super::*;
+
+fn issued_at(seconds: i64) -> ClaimsSet {
+    ClaimsSetBuilder::new()
+        .issued_at(Timestamp::WholeSeconds(seconds))
+        .build()
+}
+
+#[test]
+fn test_watermark_rejects_stale() {
+    let mut watermark = IssuedAtWatermark::from_highest(1000);
+    assert_eq!(
+        watermark.check(&issued_at(999)),
+        Err(ClaimsValidationError::StaleIssuedAt {
+            issued_at: 999,
+            watermark: 1000
+        })
+    );
+    assert_eq!(watermark.highest(), Some(1000));
+}
+
+#[test]
+fn test_watermark_accepts_same_and_newer() {
+    let mut watermark = IssuedAtWatermark::from_highest(1000);
+    assert!(watermark.check(&issued_at(1000)).is_ok());
+    assert!(watermark.check(&issued_at(1500)).is_ok());
+    assert_eq!(watermark.highest(), Some(1500));
+}
+
+#[test]
+fn test_watermark_missing_iat() {
+    let mut watermark = IssuedAtWatermark::default();
+    assert_eq!(
+        watermark.check(&ClaimsSetBuilder::new().build()),
+        Err(ClaimsValidationError::MissingClaim("iat"))
+    );
+    assert_eq!(watermark.highest(), None);
+}
+
+#[test]
+fn test_exi_expiration() {
+    let claims = ClaimsSetBuilder::new()
+        .claim(CwtClaimName::Exi, Value::from(60))
+        .build();
+    assert_eq!(exi_expiration(&claims, 40), Ok(Some(100)));
+    assert_eq!(exi_expiration(&issued_at(5), 40), Ok(None));
+    let invalid = ClaimsSetBuilder::new()
+        .claim(CwtClaimName::Exi, Value::from(-1))
+        .build();
+    assert_eq!(
+        exi_expiration(&invalid, 40),
+        Err(ClaimsValidationError::InvalidClaim("exi"))
+    );
+}
+
+#[test]
+fn test_watermark_save_restore() {
+    for watermark in [
+        IssuedAtWatermark::default(),
+        IssuedAtWatermark::from_highest(-5),
+    ] {
+        let mut persisted = Vec::new();
+        assert!(watermark.save(&mut persisted).is_ok());
+        assert_eq!(
+            IssuedAtWatermark::restore(persisted.as_slice()).ok(),
+            Some(watermark)
+        );
+    }
+    assert!(IssuedAtWatermark::restore([0x61, 0x78].as_slice()).is_err());
+}
+
+#[test]
+fn test_client_nonces() {
+    let cnonce = |nonce: &[u8]| {
+        ClaimsSetBuilder::new()
+            .claim(CwtClaimName::CNonce, Value::Bytes(nonce.to_vec()))
+            .build()
+    };
+    let mut nonces = ClientNonces::new(2);
+    assert!(nonces.is_empty());
+    nonces.issue(vec![1]);
+    nonces.issue(vec![2]);
+    nonces.issue(vec![3]);
+    // The oldest nonce has been dropped.
+    assert_eq!((nonces.len(), nonces.capacity()), (2, 2));
+    assert_eq!(
+        nonces.check(&cnonce(&[1])),
+        Err(ClaimsValidationError::UnknownClientNonce(vec![1]))
+    );
+    assert_eq!(nonces.check(&cnonce(&[3])), Ok(()));
+    assert_eq!(
+        nonces.check(&cnonce(&[3])),
+        Err(ClaimsValidationError::UnknownClientNonce(vec![3]))
+    );
+    assert_eq!(
+        nonces.check(&ClaimsSetBuilder::new().build()),
+        Err(ClaimsValidationError::MissingClaim("cnonce"))
+    );
+    assert_eq!(nonces.check(&cnonce(&[2])), Ok(()));
+    assert!(nonces.is_empty());
+
+    let mut disabled = ClientNonces::new(0);
+    disabled.issue(vec![1]);
+    assert!(disabled.check(&cnonce(&[1])).is_err());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_atomic_watermark() {
+    let watermark = std::sync::Arc::new(AtomicIssuedAtWatermark::default());
+    assert_eq!(watermark.snapshot(), IssuedAtWatermark::default());
+    let workers: Vec<_> = (0..8)
+        .map(|worker| {
+            let watermark = std::sync::Arc::clone(&watermark);
+            std::thread::spawn(move || {
+                for seconds in 0..100 {
+                    let _ = watermark.check(&issued_at(seconds * 8 + worker));
+                }
+            })
+        })
+        .collect();
+    for worker in workers {
+        worker.join().expect("worker panicked");
+    }
+    assert_eq!(watermark.snapshot().highest(), Some(799));
+    assert_eq!(
+        watermark.check(&issued_at(10)),
+        Err(ClaimsValidationError::StaleIssuedAt {
+            issued_at: 10,
+            watermark: 799
+        })
+    );
+    assert_eq!(
+        watermark.check(&ClaimsSetBuilder::new().build()),
+        Err(ClaimsValidationError::MissingClaim("iat"))
+    );
+    let restored = AtomicIssuedAtWatermark::from(IssuedAtWatermark::from_highest(1000));
+    assert!(restored.check(&issued_at(999)).is_err());
+    assert!(restored.check(&issued_at(1000)).is_ok());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_sharded_client_nonces() {
+    let cnonce = |nonce: &[u8]| {
+        ClaimsSetBuilder::new()
+            .claim(CwtClaimName::CNonce, Value::Bytes(nonce.to_vec()))
+            .build()
+    };
+    let nonces = std::sync::Arc::new(ShardedClientNonces::new(1024, 4));
+    for nonce in 0..=255 {
+        nonces.issue(vec![nonce, 0xDC]);
+    }
+    let workers: Vec<_> = (0..4)
+        .map(|_| {
+            let nonces = std::sync::Arc::clone(&nonces);
+            std::thread::spawn(move || {
+                (0..=255)
+                    .filter(|nonce| nonces.check(&cnonce(&[*nonce, 0xDC])).is_ok())
+                    .count()
+            })
+        })
+        .collect();
+    let accepted: usize = workers
+        .into_iter()
+        .map(|worker| worker.join().expect("worker panicked"))
+        .sum();
+    // Each nonce is accepted exactly once, no matter which worker checks it first.
+    assert_eq!(accepted, 256);
+    assert!(nonces.is_empty());
+    assert_eq!(
+        nonces.check(&ClaimsSetBuilder::new().build()),
+        Err(ClaimsValidationError::MissingClaim("cnonce"))
+    );
+
+    let single = ShardedClientNonces::new(2, 0);
+    single.issue(vec![1]);
+    single.issue(vec![2]);
+    single.issue(vec![3]);
+    assert_eq!(single.len(), 2);
+    assert!(single.check(&cnonce(&[1])).is_err());
+}
+
+#[test]
+fn test_policy_require_audience() {
+    let policy = ValidationPolicy::default();
+    let with_audience = |audience: &str| {
+        ClaimsSetBuilder::new()
+            .audience(audience.to_string())
+            .build()
+    };
+    assert!(policy
+        .validate(&with_audience("coaps://rs.example.com"))
+        .is_ok());
+    assert_eq!(
+        policy.validate(&issued_at(5)),
+        Err(ClaimsValidationError::MissingClaim("aud"))
+    );
+    for wildcard in ["", "*", "coaps://*.example.com"] {
+        assert_eq!(
+            policy.validate(&with_audience(wildcard)),
+            Err(ClaimsValidationError::WildcardAudience(
+                wildcard.to_string()
+            ))
+        );
+    }
+
+    let lenient = ValidationPolicy {
+        require_audience: false,
+        require_expiration: false,
+        ..ValidationPolicy::default()
+    };
+    assert!(lenient.validate(&issued_at(5)).is_ok());
+    assert!(lenient.validate(&with_audience("*")).is_ok());
+
+    let expiring = ValidationPolicy {
+        require_expiration: true,
+        ..ValidationPolicy::default()
+    };
+    assert_eq!(
+        expiring.validate(&with_audience("rs1")),
+        Err(ClaimsValidationError::MissingClaim("exp"))
+    );
+    let claims = ClaimsSetBuilder::new()
+        .audience("rs1".to_string())
+        .expiration_time(Timestamp::WholeSeconds(3600))
+        .build();
+    assert!(expiring.validate(&claims).is_ok());
+
+    let rs1 = ValidationPolicy {
+        audiences: vec!["rs0".to_string(), "rs1".to_string()],
+        ..ValidationPolicy::default()
+    };
+    assert!(rs1.validate(&with_audience("rs1")).is_ok());
+    assert_eq!(
+        rs1.validate(&with_audience("rs2")),
+        Err(ClaimsValidationError::AudienceMismatch("rs2".to_string()))
+    );
+    let lenient_rs1 = ValidationPolicy {
+        require_audience: false,
+        ..rs1
+    };
+    assert_eq!(
+        lenient_rs1.validate(&issued_at(5)),
+        Err(ClaimsValidationError::MissingClaim("aud"))
+    );
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_policy_client_nonces() {
+    let nonces = std::sync::Arc::new(ShardedClientNonces::new(16, 2));
+    nonces.issue(vec![0xDC, 0xAF]);
+    let policy = ValidationPolicy {
+        client_nonces: Some(std::sync::Arc::clone(&nonces)),
+        ..ValidationPolicy::default()
+    };
+    assert_ne!(policy, ValidationPolicy::default());
+    assert_eq!(policy, policy.clone());
+    let claims = |audience: &str| {
+        ClaimsSetBuilder::new()
+            .audience(audience.to_string())
+            .claim(CwtClaimName::CNonce, Value::Bytes(vec![0xDC, 0xAF]))
+            .build()
+    };
+    assert_eq!(
+        policy.validate_at(
+            &ClaimsSetBuilder::new().audience("rs1".to_string()).build(),
+            1000
+        ),
+        Err(ClaimsValidationError::MissingClaim("cnonce"))
+    );
+    // A token failing another check doesn't use up its nonce.
+    assert!(policy.validate_at(&claims("*"), 1000).is_err());
+    assert_eq!(nonces.len(), 1);
+    assert!(policy.validate_at(&claims("rs1"), 1000).is_ok());
+    assert!(nonces.is_empty());
+    assert_eq!(
+        policy.validate_at(&claims("rs1"), 1000),
+        Err(ClaimsValidationError::UnknownClientNonce(vec![0xDC, 0xAF]))
+    );
+}
+
+#[test]
+fn test_policy_leeway() {
+    let claims = ClaimsSetBuilder::new()
+        .audience("rs1".to_string())
+        .not_before(Timestamp::WholeSeconds(1000))
+        .issued_at(Timestamp::WholeSeconds(1000))
+        .expiration_time(Timestamp::FractionalSeconds(2000.5))
+        .build();
+    let strict = ValidationPolicy::default();
+    assert!(strict.validate_at(&claims, 1000).is_ok());
+    assert!(strict.validate_at(&claims, 1999).is_ok());
+    assert_eq!(
+        strict.validate_at(&claims, 2000),
+        Err(ClaimsValidationError::Expired {
+            expiration: 2000,
+            now: 2000
+        })
+    );
+    assert_eq!(
+        strict.validate_at(&claims, 999),
+        Err(ClaimsValidationError::NotYetValid {
+            not_before: 1000,
+            now: 999
+        })
+    );
+    // Audience checks still apply.
+    assert_eq!(
+        strict.validate_at(&issued_at(1000), 1000),
+        Err(ClaimsValidationError::MissingClaim("aud"))
+    );
+
+    let drifting = ValidationPolicy {
+        expiration_leeway: 30,
+        not_before_leeway: 10,
+        max_issued_at_age: Some(600),
+        ..ValidationPolicy::default()
+    };
+    assert!(drifting.validate_at(&claims, 990).is_ok());
+    assert!(drifting.validate_at(&claims, 989).is_err());
+    assert!(drifting.validate_at(&claims, 1600).is_ok());
+    assert_eq!(
+        drifting.validate_at(&claims, 1601),
+        Err(ClaimsValidationError::IssuedAtTooOld {
+            issued_at: 1000,
+            now: 1601
+        })
+    );
+    let long_lived = ValidationPolicy {
+        max_issued_at_age: Some(2000),
+        ..drifting.clone()
+    };
+    assert!(long_lived.validate_at(&claims, 2029).is_ok());
+    assert!(long_lived.validate_at(&claims, 2030).is_err());
+    assert_eq!(
+        drifting.validate_at(
+            &ClaimsSetBuilder::new().audience("rs1".to_string()).build(),
+            1000
+        ),
+        Err(ClaimsValidationError::MissingClaim("iat"))
+    );
+}
+
+#[test]
+fn test_validation_error_codes() {
+    let wildcard = ClaimsValidationError::WildcardAudience("*".to_string());
+    assert_eq!(wildcard.coap_code(), coap_code::FORBIDDEN);
+    assert_eq!(wildcard.http_status(), 403);
+    let mismatch = ClaimsValidationError::AudienceMismatch("rs2".to_string());
+    assert_eq!(mismatch.coap_code(), coap_code::FORBIDDEN);
+    assert_eq!(mismatch.http_status(), 403);
+    let missing = ClaimsValidationError::MissingClaim("iat");
+    assert_eq!(missing.coap_code(), coap_code::UNAUTHORIZED);
+    assert_eq!(missing.http_status(), 401);
+    assert_eq!(
+        AccessDecision::Denied.coap_code(),
+        Some(coap_code::FORBIDDEN)
+    );
+    assert_eq!(AccessDecision::Denied.http_status(), Some(403));
+    assert_eq!(AccessDecision::Granted.coap_code(), None);
+}
+
+#[derive(Default)]
+struct RecordingSink(
+    Vec<(
+        AccessDecision,
+        Option<AifEncodedScopeElement>,
+        Option<Vec<u8>>,
+    )>,
+);
+
+impl AccessDecisionSink for RecordingSink {
+    fn record(&mut self, record: &AccessDecisionRecord<'_>) {
+        self.0.push((
+            record.decision,
+            record.matched_element.cloned(),
+            record.request.token_id.map(<[u8]>::to_vec),
+        ));
+    }
+}
+
+#[test]
+fn test_scope_matcher_agrees_with_scope() {
+    let scope = AifEncodedScope::new(vec![
+        AifEncodedScopeElement::new("/s/temp", AifRestMethod::Get),
+        AifEncodedScopeElement::new("/s/temp", AifRestMethod::Put),
+        AifEncodedScopeElement::new("/a/*", AifRestMethod::Get | AifRestMethod::Post),
+        AifEncodedScopeElement::new("/b/", AifRestMethod::Delete),
+        AifEncodedScopeElement::new("/c", AifRestMethod::Fetch),
+        AifEncodedScopeElement::new(".", AifRestMethod::Patch),
+        AifEncodedScopeElement::new("d", AifRestMethod::IPatch),
+    ]);
+    let paths = [
+        "",
+        "d",
+        "/s/temp",
+        "/s/temp/x",
+        "/a/",
+        "/a/led",
+        "/a",
+        "/b/",
+        "/b/x",
+        "/c",
+        "/c/d",
+        "/cd",
+        "/x",
+        "/s/./temp",
+        "/a/../s/temp",
+        "/c/../cd",
+        "/b/x/..",
+        "/a/../../a/led",
+    ];
+    let methods = [
+        AifRestMethod::Get,
+        AifRestMethod::Put,
+        AifRestMethod::Post,
+        AifRestMethod::Delete,
+        AifRestMethod::Fetch,
+        AifRestMethod::Patch,
+        AifRestMethod::IPatch,
+    ];
+    for matching in [
+        ToidMatching::Exact,
+        ToidMatching::Wildcard,
+        ToidMatching::Prefix,
+    ] {
+        let matcher = ScopeMatcher::new(&Scope::from(scope.clone()), matching);
+        for path in paths {
+            assert_eq!(
+                matcher.permissions_for(path),
+                scope.permissions_for(path, matching),
+                "{matching:?} {path}"
+            );
+            for method in methods {
+                assert_eq!(
+                    matcher.permits(path, method),
+                    scope.allows(path, method, matching)
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_scope_matcher_from_claims() {
+    let scope = Scope::from(AifEncodedScope::from(vec![(
+        "/s/temp",
+        AifRestMethod::Get.into(),
+    )]));
+    let claims = ClaimsSetBuilder::new()
+        .claim(CwtClaimName::Scope, scope.into())
+        .build();
+    let matcher = ScopeMatcher::from_claims(&claims, ToidMatching::Exact);
+    assert!(matcher.permits("/s/temp", AifRestMethod::Get));
+    assert!(!matcher.permits("/s/temp", AifRestMethod::Put));
+    // Tokens without a scope, or with one which doesn't specify methods, don't permit anything.
+    assert!(
+        ScopeMatcher::from_claims(&ClaimsSetBuilder::new().build(), ToidMatching::Exact).is_empty()
+    );
+    let text = ClaimsSetBuilder::new()
+        .claim(CwtClaimName::Scope, Value::Text("r_temp".to_string()))
+        .build();
+    assert!(ScopeMatcher::from_claims(&text, ToidMatching::Exact).is_empty());
+}
+
+#[test]
+fn test_authorize_records_decisions() {
+    let scope = AifEncodedScope::new(vec![
+        AifEncodedScopeElement::new("/s/temp", AifRestMethod::Get),
+        AifEncodedScopeElement::new("/a/*", AifRestMethod::Get | AifRestMethod::Put),
+    ]);
+    let mut sink = RecordingSink::default();
+    let request = |resource, method| AccessRequest {
+        identity: Some("client1"),
+        resource,
+        method,
+        token_id: Some(&[0xDC, 0xAF]),
+    };
+    assert_eq!(
+        authorize(
+            &scope,
+            ToidMatching::Wildcard,
+            request("/a/led", AifRestMethod::Put),
+            &mut sink
+        ),
+        AccessDecision::Granted
+    );
+    assert_eq!(
+        authorize(
+            &scope,
+            ToidMatching::Wildcard,
+            request("/s/temp", AifRestMethod::Put),
+            &mut sink
+        ),
+        AccessDecision::Denied
+    );
+    assert_eq!(
+        authorize(
+            &scope,
+            ToidMatching::Exact,
+            request("/a/led", AifRestMethod::Get),
+            &mut sink
+        ),
+        AccessDecision::Denied
+    );
+    assert_eq!(
+        sink.0,
+        vec![
+            (
+                AccessDecision::Granted,
+                Some(scope.elements()[1].clone()),
+                Some(vec![0xDC, 0xAF])
+            ),
+            (AccessDecision::Denied, None, Some(vec![0xDC, 0xAF])),
+            (AccessDecision::Denied, None, Some(vec![0xDC, 0xAF])),
+        ]
+    );
+}
+
+fn client_token(audience: &str, iat: i64, cti: &[u8]) -> ClaimsSet {
+    ClaimsSetBuilder::new()
+        .audience(audience.to_string())
+        .issued_at(Timestamp::WholeSeconds(iat))
+        .cwt_id(cti.to_vec())
+        .build()
+}
+
+#[test]
+fn test_supersession_newer_issued_at() -> Result<(), ClaimsValidationError> {
+    let mut supersession = TokenSupersession::default();
+    assert_eq!(
+        supersession.offer("c1", &client_token("rs1", 1000, &[5]))?,
+        Supersession::Current
+    );
+    // Posting the same token again is fine.
+    assert_eq!(
+        supersession.offer("c1", &client_token("rs1", 1000, &[5]))?,
+        Supersession::Current
+    );
+    // Same iat, so the greater cti wins.
+    assert_eq!(
+        supersession.offer("c1", &client_token("rs1", 1000, &[6]))?,
+        Supersession::Supersedes(vec![5])
+    );
+    assert_eq!(
+        supersession.offer("c1", &client_token("rs1", 1000, &[5])),
+        Err(ClaimsValidationError::Superseded {
+            issued_at: 1000,
+            current_issued_at: 1000
+        })
+    );
+    // Audiences are tracked separately.
+    assert_eq!(
+        supersession.offer("c1", &client_token("rs2", 900, &[1]))?,
+        Supersession::Current
+    );
+    assert_eq!(
+        supersession.offer("c1", &client_token("rs1", 1200, &[1]))?,
+        Supersession::Supersedes(vec![6])
+    );
+    supersession.remove_client("c1");
+    assert_eq!(
+        supersession.offer("c1", &client_token("rs1", 1000, &[5]))?,
+        Supersession::Current
+    );
+    assert_eq!(
+        supersession.offer("c1", &ClaimsSetBuilder::new().build()),
+        Err(ClaimsValidationError::MissingClaim("iat"))
+    );
+    Ok(())
+}
+
+#[test]
+fn test_supersession_keep_all() -> Result<(), ClaimsValidationError> {
+    let mut supersession = TokenSupersession::new(SupersessionRule::KeepAll);
+    assert_eq!(
+        supersession.offer("c1", &client_token("rs1", 2000, &[1]))?,
+        Supersession::Current
+    );
+    assert_eq!(
+        supersession.offer("c1", &client_token("rs1", 1000, &[2]))?,
+        Supersession::Current
+    );
+    Ok(())
+}
+
+#[test]
+fn test_audience_algorithms() {
+    use coset::{Header, ProtectedHeader};
+
+    let mut algorithms = AudienceAlgorithms::default();
+    algorithms.pin("rs1", Algorithm::EdDsa);
+    algorithms.pin("rs2", Algorithm::Hmac256_64);
+    algorithms.pin("rs1", Algorithm::Es256);
+    assert_eq!(algorithms.expected("rs1"), Some(Algorithm::Es256));
+    assert_eq!(algorithms.expected("rs3"), None);
+
+    let claims = |audience: &str| {
+        ClaimsSetBuilder::new()
+            .audience(audience.to_string())
+            .build()
+    };
+    let protected = |alg: Option<Algorithm>| ProtectedHeader {
+        original_data: None,
+        header: Header {
+            alg: alg.map(coset::Algorithm::from),
+            ..Header::default()
+        },
+    };
+    assert_eq!(
+        algorithms.check(&claims("rs1"), &protected(Some(Algorithm::Es256))),
+        Ok(())
+    );
+    // The weaker algorithm of rs2 must not be accepted for rs1.
+    assert_eq!(
+        algorithms.check(&claims("rs1"), &protected(Some(Algorithm::Hmac256_64))),
+        Err(ClaimsValidationError::UnexpectedAlgorithm {
+            audience: "rs1".to_string(),
+            expected: Algorithm::Es256,
+            actual: Some(Algorithm::Hmac256_64),
+        })
+    );
+    assert_eq!(
+        algorithms.check(&claims("rs2"), &protected(None)),
+        Err(ClaimsValidationError::UnexpectedAlgorithm {
+            audience: "rs2".to_string(),
+            expected: Algorithm::Hmac256_64,
+            actual: None,
+        })
+    );
+    // Audiences without a recorded algorithm aren't restricted.
+    assert_eq!(
+        algorithms.check(&claims("rs3"), &protected(Some(Algorithm::Hmac256_64))),
+        Ok(())
+    );
+    assert_eq!(
+        algorithms.check(&ClaimsSetBuilder::new().build(), &protected(None)),
+        Ok(())
+    );
+}
+
+#[test]
+fn test_trust_store() -> Result<(), TrustStoreError> {
+    let key = |key_id: &[u8], secret: u8| {
+        CoseKeyBuilder::new_symmetric_key(vec![secret; 16])
+            .key_id(key_id.to_vec())
+            .build()
+    };
+    let header = |key_id: &[u8]| HeaderBuilder::new().key_id(key_id.to_vec()).build();
+    let mut store = TrustStore::default();
+    store.insert("as1", key(&[1], 1))?;
+    store.insert("as1", key(&[1], 1))?;
+    store.insert("as2", key(&[2], 2))?;
+    assert_eq!(
+        store.insert("as1", key(&[], 3)),
+        Err(TrustStoreError::MissingKeyId)
+    );
+    // The same key may not be trusted for two issuers either.
+    assert_eq!(
+        store.insert("as2", key(&[1], 1)),
+        Err(TrustStoreError::KeyIdCollision {
+            key_id: vec![1],
+            issuer: "as1".to_string()
+        })
+    );
+
+    // The key ID in the protected header takes precedence.
+    assert_eq!(
+        store.key_for(&header(&[2]), &header(&[1]), KeyUsage::Sign)?,
+        ("as2", &key(&[2], 2))
+    );
+    assert_eq!(
+        store.key_for(&Header::default(), &header(&[1]), KeyUsage::Sign)?,
+        ("as1", &key(&[1], 1))
+    );
+    assert_eq!(
+        store.key_for(&Header::default(), &Header::default(), KeyUsage::Sign),
+        Err(TrustStoreError::MissingKeyId)
+    );
+    assert_eq!(
+        store.key_for(&header(&[3]), &Header::default(), KeyUsage::Sign),
+        Err(TrustStoreError::UnknownKeyId(vec![3]))
+    );
+
+    assert_eq!(
+        store.replace("as3", key(&[1], 4))?,
+        Some(("as1".to_string(), key(&[1], 1)))
+    );
+    assert_eq!(store.key(&[1]), Some(("as3", &key(&[1], 4))));
+    assert_eq!(
+        store.remove(&[2]).map(|(issuer, _)| issuer),
+        Some("as2".to_string())
+    );
+    assert_eq!(store.key(&[2]), None);
+
+    let claims = |issuer: Option<&str>| {
+        let mut claims = ClaimsSetBuilder::new();
+        if let Some(issuer) = issuer {
+            claims = claims.issuer(issuer.to_string());
+        }
+        claims.build()
+    };
+    assert_eq!(
+        store.check_issuer(&header(&[1]), &Header::default(), &claims(Some("as3")))?,
+        "as3"
+    );
+    assert_eq!(
+        store.check_issuer(&header(&[1]), &Header::default(), &claims(Some("as1"))),
+        Err(TrustStoreError::IssuerMismatch {
+            expected: "as3".to_string(),
+            actual: Some("as1".to_string())
+        })
+    );
+    assert_eq!(
+        store.check_issuer(&Header::default(), &header(&[1]), &claims(None)),
+        Err(TrustStoreError::IssuerMismatch {
+            expected: "as3".to_string(),
+            actual: None
+        })
+    );
+    assert_eq!(
+        store.check_issuer(&header(&[2]), &Header::default(), &claims(Some("as2"))),
+        Err(TrustStoreError::UnknownKeyId(vec![2]))
+    );
+    Ok(())
+}
+
+#[test]
+fn test_trust_store_key_usage() -> Result<(), TrustStoreError> {
+    let key = |secret: u8| {
+        CoseKeyBuilder::new_symmetric_key(vec![secret; 16])
+            .key_id(vec![1])
+            .build()
+    };
+    let header = HeaderBuilder::new().key_id(vec![1]).build();
+    let mut store = TrustStore::default();
+    store.insert("as1", key(1))?;
+    assert_eq!(
+        store.key_for(&header, &Header::default(), KeyUsage::Encrypt)?,
+        ("as1", &key(1))
+    );
+    store.declare_usage(&[1], KeyUsage::Sign)?;
+    assert_eq!(store.usage(&[1]), Some(KeyUsage::Sign));
+    assert_eq!(
+        store.declare_usage(&[2], KeyUsage::Sign),
+        Err(TrustStoreError::UnknownKeyId(vec![2]))
+    );
+    let mismatch = TrustStoreError::KeyUsage(KeyUsageError::UsageMismatch {
+        key_id: vec![1],
+        declared: KeyUsage::Sign,
+        requested: KeyUsage::Encrypt,
+    });
+    assert_eq!(
+        store.declare_usage(&[1], KeyUsage::Encrypt),
+        Err(mismatch.clone())
+    );
+    assert_eq!(
+        store.key_for(&header, &Header::default(), KeyUsage::Encrypt),
+        Err(mismatch.clone())
+    );
+
+    // Rotating the key keeps its usage, while removing it forgets it.
+    store.replace("as1", key(2))?;
+    assert_eq!(
+        store.key_for(&header, &Header::default(), KeyUsage::Encrypt),
+        Err(mismatch)
+    );
+    store.remove(&[1]);
+    store.insert("as1", key(3))?;
+    assert_eq!(store.usage(&[1]), None);
+    Ok(())
+}
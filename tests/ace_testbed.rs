@@ -0,0 +1,21 @@
+/*
+ * Copyright (c) 2022 The NAMIB Project Developers.
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ *
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+//! Runs the complete ACE-OAuth flow of the `ace_testbed` example, whose assertions check each
+//! response of the Authorization Server and the Resource Server.
+
+#[path = "../examples/ace_testbed.rs"]
+mod ace_testbed;
+
+#[test]
+fn test_ace_testbed() {
+    ace_testbed::main();
+}
@@ -60,7 +60,7 @@ impl CoseCipherCommon for FakeCrypto {
         {
             return Err(CoseCipherError::existing_header_label(&label.0));
         }
-        if protected_header.alg != None {
+        if protected_header.alg.is_some() {
             return Err(CoseCipherError::existing_header("alg"));
         }
         unprotected_header.rest.push((Label::Int(47), Value::Null));